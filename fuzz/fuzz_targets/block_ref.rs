@@ -0,0 +1,14 @@
+#![no_main]
+
+use gtd::{markdown::BlockRef, parser::Parser};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    // Block refs are only looked for in list items, so the text is read as one.
+    let item = format!("- {}", text.replace('\n', " "));
+    if let Ok(items) = Parser::new(&item).parse_list() {
+        for fragment in items {
+            let _ = BlockRef::from_fragment(&fragment);
+        }
+    }
+});
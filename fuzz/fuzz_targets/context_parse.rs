@@ -0,0 +1,8 @@
+#![no_main]
+
+use gtd::context::Context;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let _ = Context::parse("@home", text);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use gtd::project::Project;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let _ = Project::parse("202401011200 Plan trip", text);
+});
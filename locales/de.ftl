@@ -0,0 +1,39 @@
+# Messages shown by gtd in German.
+
+date-format = %d.%m.%Y
+
+created-project = Projekt „{ $name }“ erstellt.
+wrote-file = { $path } geschrieben.
+file-is-up-to-date = { $path } ist aktuell.
+published-site = { $count } Dateien nach { $path } geschrieben.
+merged-context = { $moved } Aktionen von { $source } nach { $target } verschoben, { $duplicates } bereits vorhandene ausgelassen.
+moved-action = Aktion von { $from } nach { $to } verschoben und { $links } Links darauf aktualisiert.
+deduped-projects = { $count } Gruppen doppelter Projekte bereinigt.
+split-context = { $name } in { $count } Gruppen aufgeteilt.
+nothing-to-change = Nichts zu ändern.
+action-list-matches = Die Aktionsliste stimmt mit den Kontextdateien überein.
+undid = `{ $description }` rückgängig gemacht.
+nothing-to-undo = Es gibt nichts rückgängig zu machen.
+trash-is-empty = Der Papierkorb ist leer.
+restored-files = { $count } Dateien wiederhergestellt.
+captured-emails = { $count } E-Mails erfasst.
+roundtrip-unchanged = { $path } würde unverändert zurückgeschrieben.
+roundtrip-changed = { $path } würde sich beim Zurückschreiben ändern:
+no-commits = gtd hat noch keine Commits erstellt.
+synced = { $added } Aktionen hinzugefügt, { $pushed } Aufgaben und { $pulled } Aktionen aktualisiert und { $conflicts } Konflikte übersprungen.
+dry-run = Dies war ein Probelauf, es wurde nichts geändert.
+checked-issues = { $checked } verknüpfte Aktionen geprüft: { $closed } haben geschlossene Issues, { $completed } wurden erledigt.
+imported-issues = { $added } von { $found } offenen Issues zu { $project } hinzugefügt.
+snapshot-unchanged = Seit dem Export hat sich nichts geändert.
+changes-introduced-problems = Die Änderung hat { $count } neue Probleme hinterlassen:
+ask-next-action = Nächste Aktion für { $project } (leer lassen zum Überspringen):
+
+## gtd throughput
+
+throughput-week = Woche
+throughput-completed = Erledigt
+throughput-outcomes = Ergebnisse:
+throughput-project = Projekt
+throughput-remaining = Offen
+throughput-estimated-finish = Voraussichtliches Ende
+throughput-unknown = unbekannt
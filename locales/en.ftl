@@ -0,0 +1,40 @@
+# Messages shown by gtd in English, which every other language falls back to.
+
+# The format that dates are shown in when `date-format` is `local`, with chrono's `strftime` syntax.
+date-format = %b %-d, %Y
+
+created-project = Created project "{ $name }".
+wrote-file = Wrote { $path }.
+file-is-up-to-date = { $path } is up to date.
+published-site = Wrote { $count } files to { $path }.
+merged-context = Moved { $moved } actions from { $source } to { $target }, leaving out { $duplicates } already there.
+moved-action = Moved the action from { $from } to { $to }, and updated { $links } links to it.
+deduped-projects = Cleaned up { $count } groups of duplicate projects.
+split-context = Split { $name } into { $count } groups.
+nothing-to-change = Nothing to change.
+action-list-matches = The action list matches the context files.
+undid = Undid `{ $description }`.
+nothing-to-undo = There's nothing to undo.
+trash-is-empty = The trash is empty.
+restored-files = Restored { $count } files.
+captured-emails = Captured { $count } emails.
+roundtrip-unchanged = { $path } would be written back unchanged.
+roundtrip-changed = { $path } would change if it were written back:
+no-commits = gtd hasn't made any commits.
+synced = Added { $added } actions, updated { $pushed } tasks and { $pulled } actions, and skipped { $conflicts } conflicts.
+dry-run = This was a dry run, so nothing was changed.
+checked-issues = Checked { $checked } linked actions: { $closed } have closed issues, and { $completed } were completed.
+imported-issues = Added { $added } of { $found } open issues to { $project }.
+snapshot-unchanged = Nothing has changed since the export.
+changes-introduced-problems = That change left { $count } new problems:
+ask-next-action = Next action for { $project } (leave empty to skip):
+
+## gtd throughput
+
+throughput-week = Week
+throughput-completed = Completed
+throughput-outcomes = Outcomes:
+throughput-project = Project
+throughput-remaining = Remaining
+throughput-estimated-finish = Estimated finish
+throughput-unknown = unknown
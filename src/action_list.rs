@@ -0,0 +1,371 @@
+//! A single "Next Actions" file with a `##` section for each context, as an alternative to a file
+//! per context.
+//!
+//! Either layout can be regenerated from the other, so they can be switched between or kept side by
+//! side. Groups in a context file are `###` headings in the context's section of the list. Both
+//! can be at other depths with the `[headings]` config.
+
+use crate::{
+    config::ContextLayout,
+    conflict::ConflictedFile,
+    context::{Context, Name as ContextName, ParseError as ContextParseError},
+    diff::{self, WriteMode},
+    edit,
+    frontmatter::Frontmatter,
+    gtd::{LoadContextError, Loader},
+    parser::{self, Doc, HeadingDepths, Parser},
+    project::NameScheme,
+    undo::Undo,
+};
+use pulldown_cmark::{Event, Tag};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error as IoError, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// The name of the action list file, in the root of the GTD folder.
+pub const ACTION_LIST_FILENAME: &str = "Next Actions.md";
+
+const TITLE: &str = "# Next Actions";
+
+/// The contexts in an action list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionList {
+    pub contexts: Vec<Context>,
+}
+
+impl ActionList {
+    pub fn parse<'a>(text: &'a str, scheme: &NameScheme) -> Result<Self, ContextParseError<'a>> {
+        Self::parse_with_depths(text, scheme, HeadingDepths::default())
+    }
+
+    /// Parses an action list whose contexts are at `depths`. If they're at depth 1, the list has
+    /// no title.
+    pub fn parse_with_depths<'a>(
+        text: &'a str,
+        scheme: &NameScheme,
+        depths: HeadingDepths,
+    ) -> Result<Self, ContextParseError<'a>> {
+        let level = depths.action_list_contexts;
+        let mut parser = match level {
+            1 => Parser::new_at(text, Frontmatter::parse(text).1),
+            _ => Doc::parse(text)?.parser,
+        };
+        let mut contexts = Vec::new();
+        while parser.peek() == Some(&Event::Start(Tag::Heading(level))) {
+            let heading = parser.parse_heading(level)?;
+            let name = ContextName::new(heading.to_string());
+            contexts.push(Context::parse_section(
+                name,
+                heading,
+                &mut parser,
+                scheme,
+                level + 1,
+            )?);
+        }
+        Ok(Self { contexts })
+    }
+
+    /// Loads the action list in the GTD folder of `loader`, which is empty if there isn't one.
+    pub fn load(loader: &Loader) -> Result<Self, LoadContextError> {
+        let path = path(loader.root_dir());
+        let text = read_or_empty(&path)?;
+        if let Some(file) = ConflictedFile::check(&path, &text) {
+            return Err(file.into());
+        }
+        if text.is_empty() {
+            return Ok(Self {
+                contexts: Vec::new(),
+            });
+        }
+        Ok(Self::parse_with_depths(
+            &text,
+            loader.scheme(),
+            loader.depths(),
+        )?)
+    }
+}
+
+/// The path of the action list in `root_dir`.
+pub fn path(root_dir: &Path) -> PathBuf {
+    root_dir.join(ACTION_LIST_FILENAME)
+}
+
+/// The ways that the contexts in the context files and in the action list differ, comparing just
+/// their actions.
+pub fn differences(files: &[Context], list: &[Context]) -> Vec<String> {
+    let files = files
+        .iter()
+        .map(|c| (&c.name, c))
+        .collect::<BTreeMap<_, _>>();
+    let list = list
+        .iter()
+        .map(|c| (&c.name, c))
+        .collect::<BTreeMap<_, _>>();
+    let mut names = files.keys().chain(list.keys()).collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| match (files.get(name), list.get(name)) {
+            (Some(_), None) => Some(format!("{} is only in the context files", name)),
+            (None, Some(_)) => Some(format!("{} is only in the action list", name)),
+            (Some(file), Some(section)) if file.actions() != section.actions() => Some(format!(
+                "{} has different actions in the action list than in its file",
+                name
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Renders an action list from the texts of context files, sorted by name, with the contexts at
+/// heading depth `level`.
+pub fn render_list(files: &BTreeMap<ContextName, String>, level: u32) -> String {
+    let (context, group) = prefixes(level);
+    let mut out = match level {
+        1 => String::new(),
+        _ => format!("{}\n", TITLE),
+    };
+    for (name, text) in files {
+        let text = parser::normalize_headings(text);
+        let body = text[edit::context_body_start(&text)..]
+            .lines()
+            .map(|l| match l.strip_prefix("## ") {
+                Some(heading) => format!("{}{}", group, heading),
+                None => l.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        push_section(&mut out, &format!("{}{}", context, name), &body);
+    }
+    match level {
+        1 => out.trim_start().to_string(),
+        _ => out,
+    }
+}
+
+/// Renders the text of a context file for each section in the action list `text`, whose contexts
+/// are at heading depth `level`.
+pub fn render_files(text: &str, level: u32) -> BTreeMap<ContextName, String> {
+    let (context, group) = prefixes(level);
+    let text = parser::normalize_headings(text);
+    let mut sections = Vec::<(ContextName, Vec<&str>)>::new();
+    for line in text.lines() {
+        match (line.strip_prefix(&context), sections.last_mut()) {
+            (Some(name), _) => sections.push((ContextName::new(name.trim().to_string()), vec![])),
+            (None, Some((_, lines))) => lines.push(line),
+            (None, None) => {}
+        }
+    }
+    sections
+        .into_iter()
+        .map(|(name, lines)| {
+            let body = lines
+                .into_iter()
+                .map(|l| match l.strip_prefix(&group) {
+                    Some(heading) => format!("## {}", heading),
+                    None => l.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let mut out = String::new();
+            push_section(&mut out, &format!("# {}", name), &body);
+            (name, out.trim_start().to_string())
+        })
+        .collect()
+}
+
+/// The start of a context heading at depth `level` in the action list, and of a group heading
+/// under it.
+fn prefixes(level: u32) -> (String, String) {
+    let hashes = "#".repeat(level as usize);
+    (format!("{} ", hashes), format!("{}# ", hashes))
+}
+
+/// Appends `heading` to `out` with `body` under it, separated by blank lines.
+fn push_section(out: &mut String, heading: &str, body: &str) {
+    out.push_str(&format!("\n{}\n", heading));
+    let body = body.trim();
+    if !body.is_empty() {
+        out.push_str(&format!("\n{}\n", body));
+    }
+}
+
+/// Checks that the context files and the action list have the same actions, returning the ways they
+/// differ.
+pub fn check(loader: &Loader) -> Result<Vec<String>, LoadContextError> {
+    let files = load_files(loader)?
+        .iter()
+        .map(|(name, text)| Context::parse_with_scheme(name.as_str(), text, loader.scheme()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let list = ActionList::load(loader)?;
+    Ok(differences(&files, &list.contexts))
+}
+
+/// Regenerates the other layout from `from`: the action list from the context files, or a context
+/// file for each section of the action list. Context files without a section are left alone.
+///
+/// Each change is shown as a word diff and written according to `mode`. Before anything is
+/// written, the files are recorded so `gtd undo` can put them back. Returns whether anything was
+/// written.
+pub fn regenerate(loader: &Loader, from: ContextLayout, mode: WriteMode) -> Result<bool, IoError> {
+    let files = load_files(loader)?;
+    let list_path = path(loader.root_dir());
+    let list_text = read_or_empty(&list_path)?;
+
+    let changes = match from {
+        ContextLayout::Files => vec![(
+            list_path,
+            String::from(ACTION_LIST_FILENAME),
+            list_text,
+            render_list(&files, loader.depths().action_list_contexts),
+        )],
+        ContextLayout::List => render_files(&list_text, loader.depths().action_list_contexts)
+            .into_iter()
+            .map(|(name, text)| {
+                let old = files.get(&name).cloned().unwrap_or_default();
+                (loader.context_path(&name), name.to_string(), old, text)
+            })
+            .collect(),
+    };
+    let changes = changes
+        .into_iter()
+        .filter(|(_, _, old, new)| old != new)
+        .collect::<Vec<_>>();
+    if changes.is_empty() {
+        return Ok(false);
+    }
+
+    let mut is_written = true;
+    for (_, name, old, new) in changes.iter() {
+        is_written &= diff::review(name, old, new, mode)?;
+    }
+    if !is_written {
+        return Ok(false);
+    }
+
+    let mut undo = Undo::new(format!("reconcile --from {}", from));
+    for (path, _, _, _) in changes.iter() {
+        undo.record(loader, path)?;
+    }
+    undo.save(loader)?;
+    for (path, _, _, new) in changes {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, new)?;
+    }
+    Ok(true)
+}
+
+/// The texts of the context files, by name.
+fn load_files(loader: &Loader) -> Result<BTreeMap<ContextName, String>, IoError> {
+    let names = match loader.all_context_names() {
+        Ok(names) => names.collect::<Vec<_>>(),
+        Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    names
+        .into_iter()
+        .map(|name| {
+            let text = fs::read_to_string(loader.context_path(&name))?;
+            Ok((name, text))
+        })
+        .collect()
+}
+
+fn read_or_empty(path: &Path) -> Result<String, IoError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    const LIST: &str = "# Next Actions\n\n## @computer\n\n- Back up laptop\n\n### Plan trip\n\n- ![[202401010000 Plan trip#^abcdef]]\n\n## @home\n\n- Water plants\n";
+
+    #[test]
+    fn sections_are_parsed_as_contexts() {
+        let list = ActionList::parse(LIST, NameScheme::default_ref()).unwrap();
+        let names = list
+            .contexts
+            .iter()
+            .map(|c| (c.name.as_str(), c.actions().len(), c.groups().len()))
+            .collect::<Vec<_>>();
+        assert_eq!(names, [("@computer", 2, 1), ("@home", 1, 0)]);
+    }
+
+    #[test]
+    fn layouts_round_trip() {
+        let files = render_files(LIST, 2);
+        assert_eq!(
+            files[&ContextName::new(String::from("@computer"))],
+            "# @computer\n\n- Back up laptop\n\n## Plan trip\n\n- ![[202401010000 Plan trip#^abcdef]]\n"
+        );
+        assert_eq!(render_list(&files, 2), LIST);
+
+        let scheme = NameScheme::default_ref();
+        let contexts = files
+            .iter()
+            .map(|(name, text)| Context::parse_with_scheme(name.as_str(), text, scheme).unwrap())
+            .collect::<Vec<_>>();
+        let list = ActionList::parse(LIST, scheme).unwrap();
+        assert!(differences(&contexts, &list.contexts).is_empty());
+    }
+
+    #[test]
+    fn contexts_can_be_top_level() {
+        let text = "# @computer\n\n- Back up laptop\n\n## Plan trip\n\n- ![[202401010000 Plan trip#^abcdef]]\n\n# @home\n\n- Water plants\n";
+        let depths = HeadingDepths::new(2, 3, 1).unwrap();
+        let scheme = NameScheme::default_ref();
+        assert_eq!(
+            ActionList::parse_with_depths(text, scheme, depths).unwrap(),
+            ActionList::parse(LIST, scheme).unwrap()
+        );
+
+        let files = render_files(text, 1);
+        assert_eq!(files, render_files(LIST, 2));
+        assert_eq!(render_list(&files, 1), text);
+    }
+
+    #[test]
+    fn differences_are_found() {
+        let scheme = NameScheme::default_ref();
+        let files = [
+            Context::parse_with_scheme("@computer", "# @computer\n\n- Back up laptop\n", scheme)
+                .unwrap(),
+            Context::parse_with_scheme("@phone", "# @phone\n\n- Call Alice\n", scheme).unwrap(),
+        ];
+        let list = ActionList::parse(LIST, scheme).unwrap();
+        assert_eq!(
+            differences(&files, &list.contexts),
+            [
+                "@computer has different actions in the action list than in its file",
+                "@home is only in the action list",
+                "@phone is only in the context files",
+            ]
+        );
+    }
+
+    #[test]
+    fn files_are_regenerated_from_list() {
+        let dir = TempDir::new("action-list");
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(path(&dir), LIST).unwrap();
+        fs::write(dir.join("Contexts/@home.md"), "# @home\n\n- Mow lawn\n").unwrap();
+        let loader = Loader::new(dir.to_path_buf(), NameScheme::default());
+
+        assert_eq!(check(&loader).unwrap().len(), 2);
+        assert!(regenerate(&loader, ContextLayout::List, WriteMode::Write).unwrap());
+        assert!(check(&loader).unwrap().is_empty());
+        assert!(!regenerate(&loader, ContextLayout::Files, WriteMode::Write).unwrap());
+    }
+}
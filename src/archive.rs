@@ -0,0 +1,104 @@
+//! Moving finished projects out of the projects folder.
+
+use crate::{
+    attachments::Index,
+    gtd::Documents,
+    selector::{self, NoSelectorError, Selector},
+};
+use chrono::NaiveDate;
+use std::{error::Error, fmt, io::Error as IoError, path::Path};
+
+/// Moves the projects picked by `selector` into the archive folder.
+///
+/// If `attachments` is the attachments folder, the attachments that only an archived project links
+/// to are moved into the archive along with it.
+///
+/// The projects are listed first, and nothing is moved unless the user confirms or `yes` is set.
+/// Returns the number of projects archived.
+pub fn archive(
+    docs: &Documents,
+    selector: &Selector,
+    today: NaiveDate,
+    yes: bool,
+    attachments: Option<&Path>,
+) -> Result<usize, ArchiveError> {
+    if selector.is_empty() {
+        return Err(NoSelectorError.into());
+    }
+
+    let projects = selector.select(docs, today);
+    if projects.is_empty() {
+        println!("No projects were selected.");
+        return Ok(0);
+    }
+
+    let loader = docs.loader();
+    // Links are read before anything is moved, so they're resolved from where projects are now.
+    let index = match attachments {
+        Some(folder) => Some(Index::build(loader.root_dir(), folder)?),
+        None => None,
+    };
+    let owned = projects
+        .iter()
+        .map(|p| match &index {
+            Some(index) => index.only_linked_from(&loader.project_path(&p.name)),
+            None => Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let items = projects
+        .iter()
+        .zip(owned.iter())
+        .map(|(p, owned)| match owned.len() {
+            0 => format!("{} ({})", p.title(), p.status),
+            n => format!("{} ({}, with {} attachments)", p.title(), p.status, n),
+        })
+        .collect::<Vec<_>>();
+    let question = format!("Archive {} projects?", projects.len());
+    if !selector::confirm_batch(&question, &items, yes)? {
+        return Ok(0);
+    }
+
+    let mut moved = 0;
+    for (project, owned) in projects.iter().zip(owned.iter()) {
+        loader.archive_project(&project.name)?;
+        if let Some(index) = &index {
+            moved += index.archive(owned)?.len();
+        }
+    }
+
+    println!("Archived {} projects.", projects.len());
+    if moved > 0 {
+        println!("Moved {} attachments into the archive.", moved);
+    }
+    Ok(projects.len())
+}
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    IoError(IoError),
+    NoSelectorError(NoSelectorError),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::NoSelectorError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ArchiveError {}
+
+impl From<IoError> for ArchiveError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<NoSelectorError> for ArchiveError {
+    fn from(error: NoSelectorError) -> Self {
+        Self::NoSelectorError(error)
+    }
+}
@@ -0,0 +1,355 @@
+//! Checking the images and other files that documents link to.
+//!
+//! Attachments are linked with Markdown links and images, like `![](Attachments/map.png)`, or with
+//! Obsidian links and embeds, like `![[map.png]]`. Links to notes, headings, and URLs aren't
+//! attachments.
+
+use crate::{
+    gtd::ARCHIVE_DIRNAME,
+    links::Linker,
+    references::{self, files},
+};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{Error as IoError, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// A link from a document to an attachment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// Where the link points, like `map.png` or `../Attachments/map.png`.
+    pub target: String,
+    /// The 1-based line the link is on.
+    pub line: usize,
+}
+
+/// The attachments that the Markdown in `text` links to, in the order they appear.
+pub fn references(text: &str) -> Vec<Reference> {
+    references::links(text)
+        .into_iter()
+        .filter(|link| is_attachment(link.path()))
+        .map(|link| Reference {
+            target: link.path().to_string(),
+            line: link.line,
+        })
+        .collect()
+}
+
+/// Whether `target` is a file other than a note, rather than a note or a URL.
+fn is_attachment(target: &str) -> bool {
+    if target.contains("://") || target.starts_with("mailto:") {
+        return false;
+    }
+    match Path::new(target).extension() {
+        Some(ext) => ext != "md",
+        None => false,
+    }
+}
+
+/// The results of checking the attachments in a GTD folder. Paths are relative to the folder.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Audit {
+    /// Links to attachments that don't exist, along with the documents they're in.
+    pub missing: Vec<(PathBuf, Reference)>,
+    /// Files in the attachments folder that nothing links to.
+    pub unused: Vec<PathBuf>,
+}
+
+/// The links from every document in a GTD folder to its attachments, resolved to the files they
+/// point to.
+///
+/// Attachments are the files in the attachments folder, and in the same folder under the archive
+/// folder, where the attachments of archived projects go.
+#[derive(Debug, Clone)]
+pub struct Index {
+    root_dir: PathBuf,
+    attachments: Vec<PathBuf>,
+    /// Each link, with the document it's in, relative to the GTD folder, and the attachment it
+    /// points to, if it exists.
+    links: Vec<(PathBuf, Reference, Option<PathBuf>)>,
+}
+
+impl Index {
+    /// Reads the links in every document in `root_dir`, where attachments are kept in `folder`,
+    /// which is relative to `root_dir`.
+    ///
+    /// Links are resolved relative to the document, the GTD folder, and the attachments folder,
+    /// and then by matching the end of the path of an attachment, the way Obsidian finds
+    /// `![[map.png]]`.
+    pub fn build(root_dir: &Path, folder: &Path) -> Result<Self, IoError> {
+        let root_dir = fs::canonicalize(root_dir)?;
+        let folder = root_dir.join(folder);
+        let mut attachments = Vec::new();
+        for dir in [folder.clone(), archive_folder(&root_dir, &folder)].iter() {
+            if dir.is_dir() {
+                attachments.extend(files(dir)?);
+            }
+        }
+
+        let mut links = Vec::new();
+        let docs = files(&root_dir)?
+            .into_iter()
+            .filter(|p| p.extension().is_some_and(|e| e == "md"));
+        for doc in docs {
+            let text = fs::read_to_string(&doc)?;
+            let doc_dir = doc.parent().unwrap_or(&root_dir);
+            for reference in references(&text) {
+                let dirs = [doc_dir, &root_dir, &folder];
+                let found = dirs
+                    .iter()
+                    .map(|d| d.join(&reference.target))
+                    .find(|p| p.is_file())
+                    .and_then(|p| fs::canonicalize(p).ok())
+                    .or_else(|| {
+                        attachments
+                            .iter()
+                            .find(|a| a.ends_with(&reference.target))
+                            .cloned()
+                    });
+                links.push((relative(&doc, &root_dir), reference, found));
+            }
+        }
+        Ok(Self {
+            root_dir,
+            attachments,
+            links,
+        })
+    }
+
+    /// Checks that every link to an attachment points to a file, and that every attachment is
+    /// linked to.
+    pub fn audit(&self) -> Audit {
+        let used = self
+            .links
+            .iter()
+            .filter_map(|(_, _, found)| found.as_ref())
+            .collect::<HashSet<_>>();
+        Audit {
+            missing: self
+                .links
+                .iter()
+                .filter(|(_, _, found)| found.is_none())
+                .map(|(doc, reference, _)| (doc.clone(), reference.clone()))
+                .collect(),
+            unused: self
+                .attachments
+                .iter()
+                .filter(|a| !used.contains(a))
+                .map(|a| relative(a, &self.root_dir))
+                .collect(),
+        }
+    }
+
+    /// The attachments that the document at `doc` links to and nothing else does, relative to the
+    /// GTD folder and sorted.
+    ///
+    /// Only attachments it links to by their filename alone are included, like `![[map.png]]`,
+    /// since those links still work wherever the attachment is moved to.
+    pub fn only_linked_from(&self, doc: &Path) -> Vec<PathBuf> {
+        let doc = fs::canonicalize(doc).unwrap_or_else(|_| doc.to_owned());
+        let doc = relative(&doc, &self.root_dir);
+        let mut only = self
+            .attachments
+            .iter()
+            .filter(|attachment| {
+                let mut links = self
+                    .links
+                    .iter()
+                    .filter(|(_, _, found)| found.as_ref() == Some(*attachment))
+                    .peekable();
+                links.peek().is_some()
+                    && links.all(|(from, reference, _)| {
+                        *from == doc && !reference.target.contains(&['/', '\\'][..])
+                    })
+            })
+            .map(|a| relative(a, &self.root_dir))
+            .collect::<Vec<_>>();
+        only.sort();
+        only
+    }
+
+    /// Moves `attachments`, which are relative to the GTD folder, to the same place under the
+    /// archive folder, returning their new paths relative to the GTD folder.
+    ///
+    /// Fails without moving anything if one of them is already in the archive.
+    pub fn archive(&self, attachments: &[PathBuf]) -> Result<Vec<PathBuf>, IoError> {
+        let archive_dir = Path::new(ARCHIVE_DIRNAME);
+        let moves = attachments
+            .iter()
+            .map(|a| (a, archive_dir.join(a)))
+            .collect::<Vec<_>>();
+        if let Some((_, to)) = moves.iter().find(|(_, to)| self.root_dir.join(to).exists()) {
+            return Err(IoError::new(
+                ErrorKind::AlreadyExists,
+                format!("{} is already archived", to.display()),
+            ));
+        }
+        for (from, to) in moves.iter() {
+            let to = self.root_dir.join(to);
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(self.root_dir.join(from), to)?;
+        }
+        Ok(moves.into_iter().map(|(_, to)| to).collect())
+    }
+}
+
+/// Checks the attachments linked from every document in `root_dir` against the files in the
+/// attachments folder `folder`, which is relative to `root_dir`.
+pub fn audit(root_dir: &Path, folder: &Path) -> Result<Audit, IoError> {
+    Ok(Index::build(root_dir, folder)?.audit())
+}
+
+/// The folder that the attachments in `folder` are archived to.
+fn archive_folder(root_dir: &Path, folder: &Path) -> PathBuf {
+    let folder = folder.strip_prefix(root_dir).unwrap_or(folder);
+    root_dir.join(ARCHIVE_DIRNAME).join(folder)
+}
+
+/// Prints the problems in `audit`, linking to the files in `root_dir` that it mentions.
+pub fn print_audit(audit: &Audit, root_dir: &Path, links: &Linker) {
+    let mut last_doc = None;
+    for (doc, reference) in audit.missing.iter() {
+        if last_doc != Some(doc) {
+            println!("{}:", links.link(&root_dir.join(doc), doc.display()));
+            last_doc = Some(doc);
+        }
+        println!(
+            "- line {}: attachment \"{}\" doesn't exist",
+            reference.line, reference.target
+        );
+    }
+
+    if !audit.unused.is_empty() {
+        println!("Unused attachments:");
+        for path in audit.unused.iter() {
+            println!("- {}", links.link(&root_dir.join(path), path.display()));
+        }
+    }
+}
+
+fn relative(path: &Path, root_dir: &Path) -> PathBuf {
+    path.strip_prefix(root_dir).unwrap_or(path).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    fn targets(text: &str) -> Vec<String> {
+        references(text).into_iter().map(|r| r.target).collect()
+    }
+
+    mod references {
+        use super::*;
+
+        #[test]
+        fn markdown_links_and_images_are_found() {
+            let text = "# Plan trip\n\n![Map](Attachments/map%20v2.png)\n\nSee [the plan](plan.pdf#page=2).\n";
+            assert_eq!(targets(text), vec!["Attachments/map v2.png", "plan.pdf"]);
+        }
+
+        #[test]
+        fn wikilinks_and_embeds_are_found() {
+            let text = "# Plan trip\n\n![[map.png|300]] and [[tickets.pdf]]\n";
+            assert_eq!(targets(text), vec!["map.png", "tickets.pdf"]);
+        }
+
+        #[test]
+        fn notes_and_urls_are_skipped() {
+            let text = "# Plan trip\n\n[[Other note]] [note](Other.md#Heading) [site](https://example.com/a.png)\n";
+            assert!(targets(text).is_empty());
+        }
+
+        #[test]
+        fn code_is_skipped() {
+            let text = "# Plan trip\n\n`![[map.png]]`\n\n```\n![](map.png)\n```\n";
+            assert!(targets(text).is_empty());
+        }
+
+        #[test]
+        fn lines_are_counted_from_start_of_file() {
+            let text = "---\nprogress: 1/2\n---\n# Plan trip\n\n![[map.png]]\n";
+            assert_eq!(references(text)[0].line, 6);
+        }
+    }
+
+    #[test]
+    fn missing_and_unused_attachments_are_found() {
+        let dir = TempDir::new("attachments");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Attachments/Trips")).unwrap();
+        fs::write(dir.join("Attachments/Trips/map.png"), "").unwrap();
+        fs::write(dir.join("Attachments/old.png"), "").unwrap();
+        fs::write(
+            dir.join("Projects/Plan trip.md"),
+            "# Plan trip\n\n![[map.png]]\n![](../Attachments/tickets.pdf)\n",
+        )
+        .unwrap();
+
+        let audit = audit(&dir, Path::new("Attachments")).unwrap();
+
+        assert_eq!(
+            audit,
+            Audit {
+                missing: vec![(
+                    PathBuf::from("Projects/Plan trip.md"),
+                    Reference {
+                        target: String::from("../Attachments/tickets.pdf"),
+                        line: 4,
+                    }
+                )],
+                unused: vec![PathBuf::from("Attachments/old.png")],
+            }
+        );
+    }
+
+    #[test]
+    fn attachments_only_one_project_links_to_are_archived() {
+        let dir = TempDir::new("attachments-archive");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Attachments/Trips")).unwrap();
+        for name in ["Trips/map.png", "shared.png", "tickets.pdf", "old.png"].iter() {
+            fs::write(dir.join("Attachments").join(name), "").unwrap();
+        }
+        fs::write(
+            dir.join("Projects/Plan trip.md"),
+            "# Plan trip\n\n![[map.png]]\n![[shared.png]]\n[Tickets](../Attachments/tickets.pdf)\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Projects/Budget.md"),
+            "# Budget\n\n![[shared.png]]\n",
+        )
+        .unwrap();
+
+        let index = Index::build(&dir, Path::new("Attachments")).unwrap();
+        let owned = index.only_linked_from(&dir.join("Projects/Plan trip.md"));
+        // The tickets are linked by their path, which would break if they moved.
+        assert_eq!(owned, [PathBuf::from("Attachments/Trips/map.png")]);
+        assert!(index
+            .only_linked_from(&dir.join("Projects/Budget.md"))
+            .is_empty());
+
+        fs::create_dir_all(dir.join("Archive")).unwrap();
+        fs::rename(
+            dir.join("Projects/Plan trip.md"),
+            dir.join("Archive/Plan trip.md"),
+        )
+        .unwrap();
+        assert_eq!(
+            index.archive(&owned).unwrap(),
+            [PathBuf::from("Archive/Attachments/Trips/map.png")]
+        );
+        assert!(dir.join("Archive/Attachments/Trips/map.png").is_file());
+        assert!(index.archive(&owned).is_err());
+
+        let audit = audit(&dir, Path::new("Attachments")).unwrap();
+        assert!(audit.missing.is_empty());
+        assert_eq!(audit.unused, [PathBuf::from("Attachments/old.png")]);
+    }
+}
@@ -0,0 +1,908 @@
+//! Builders for projects and contexts, for making them in code instead of writing Markdown.
+//!
+//! A builder renders its document to the same Markdown a person would write, and `build` parses
+//! that back, so a built document is exactly what would be loaded from the file. Anything that
+//! would be written or read back differently is an error.
+
+use crate::{
+    context::{self, Context},
+    estimate::Estimate,
+    frontmatter::Frontmatter,
+    markdown::Fragment,
+    project::{
+        self, Action, ActionStatus, Name, NameScheme, Priority, Project, Status, ACTIVE_ANNOTATION,
+        CREATED_MARKER, DEFER_MARKER, DONE_MARKER, DUE_MARKER, ISSUE_ANNOTATION, MOVED_ANNOTATION,
+        UID_ANNOTATION,
+    },
+    tag::Tag,
+};
+use chrono::NaiveDate;
+use std::{error::Error, fmt, ops::Range};
+
+/// Builds a [`Project`].
+#[derive(Debug, Clone)]
+pub struct ProjectBuilder {
+    name: String,
+    scheme: Option<NameScheme>,
+    frontmatter: Frontmatter,
+    status: Status,
+    title: Option<String>,
+    tags: Vec<String>,
+    goal: Option<String>,
+    info: Option<String>,
+    outcome: Option<(String, Option<NaiveDate>)>,
+    actions: Vec<(ActionStatus, ActionBuilder)>,
+    footnotes: Vec<(String, String)>,
+}
+
+impl ProjectBuilder {
+    /// Starts an in-progress project named `name`, like `202401010000 Plan trip`.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            scheme: None,
+            frontmatter: Frontmatter::default(),
+            status: Status::InProgress,
+            title: None,
+            tags: Vec::new(),
+            goal: None,
+            info: None,
+            outcome: None,
+            actions: Vec::new(),
+            footnotes: Vec::new(),
+        }
+    }
+
+    /// Starts a builder that renders `project` the way it would be written back to its file.
+    pub fn from_project(project: &Project) -> Self {
+        let mut builder = Self::new(project.name.as_str())
+            .status(project.status)
+            .title(project.title.to_string());
+        builder.frontmatter = project.frontmatter.clone();
+        builder.tags = project
+            .tags
+            .iter()
+            .map(|t| t.as_str().to_string())
+            .collect();
+        builder.goal = project.goal.as_ref().map(Fragment::to_markdown);
+        builder.info = project.info.as_ref().map(Fragment::to_markdown);
+        builder.outcome = project
+            .outcome
+            .as_ref()
+            .map(|o| (o.note.to_markdown(), o.completed));
+        builder.actions = project
+            .actions
+            .actions()
+            .map(|(action, status)| (status, ActionBuilder::from_action(action)))
+            .collect();
+        builder.footnotes = project
+            .footnotes
+            .iter()
+            .map(|f| (f.label.clone(), f.body.to_markdown()))
+            .collect();
+        builder
+    }
+
+    /// Uses `scheme` instead of the default to read the name.
+    pub fn scheme(mut self, scheme: &NameScheme) -> Self {
+        self.scheme = Some(scheme.clone());
+        self
+    }
+
+    /// Sets the frontmatter entry `key` to `value`.
+    pub fn frontmatter(mut self, key: &str, value: &str) -> Self {
+        self.frontmatter.set(key, value);
+        self
+    }
+
+    pub fn status(mut self, status: Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Sets the Markdown text of the heading, instead of using the title from the name.
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Adds a tag, with or without the leading `#`.
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Sets the Markdown text of the goal.
+    pub fn goal<S: Into<String>>(mut self, goal: S) -> Self {
+        self.goal = Some(goal.into());
+        self
+    }
+
+    /// Sets the Markdown text of the info section.
+    pub fn info<S: Into<String>>(mut self, info: S) -> Self {
+        self.info = Some(info.into());
+        self
+    }
+
+    /// Sets the Markdown text of the outcome section, along with when the project was completed.
+    pub fn outcome<S: Into<String>>(mut self, note: S, completed: Option<NaiveDate>) -> Self {
+        self.outcome = Some((note.into(), completed));
+        self
+    }
+
+    /// Adds an action with `status`, after the other actions with that status.
+    pub fn action(mut self, status: ActionStatus, action: ActionBuilder) -> Self {
+        self.actions.push((status, action));
+        self
+    }
+
+    /// Adds a footnote definition labelled `label`, like `1` for `[^1]`, with the Markdown `text`.
+    /// Footnotes are written at the end of the project.
+    pub fn footnote<S: Into<String>, T: Into<String>>(mut self, label: S, text: T) -> Self {
+        self.footnotes.push((label.into(), text.into()));
+        self
+    }
+
+    /// Renders the project as the Markdown text of its file.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        if !self.frontmatter.is_empty() {
+            out.push_str(&self.frontmatter.to_string());
+        }
+
+        let scheme = name_scheme(&self.scheme);
+        let title = match &self.title {
+            Some(title) => title.clone(),
+            None => Name::with_scheme(self.name.clone(), scheme)
+                .map(|n| n.title().to_string())
+                .unwrap_or_else(|| self.name.clone()),
+        };
+        out.push_str(&format!("# {}\n#{}", title, self.status.tag()));
+        for tag in self.tags.iter() {
+            out.push_str(&format!(" #{}", tag.trim_start_matches('#')));
+        }
+        out.push('\n');
+
+        let outcome = self
+            .outcome
+            .as_ref()
+            .map(|(note, completed)| match completed {
+                Some(date) => format!("{} {} {}", note, DONE_MARKER, date.format("%Y-%m-%d")),
+                None => note.clone(),
+            });
+        let sections = [
+            ("Goal", self.goal.as_deref()),
+            ("Info", self.info.as_deref()),
+            ("Outcome", outcome.as_deref()),
+        ];
+        for (heading, text) in sections.iter() {
+            match text.map(str::trim) {
+                Some("") => out.push_str(&format!("\n## {}\n", heading)),
+                Some(text) => out.push_str(&format!("\n## {}\n\n{}\n", heading, text)),
+                None => {}
+            }
+        }
+
+        out.push_str("\n## Actions\n");
+        let statuses = [
+            (ActionStatus::Active, "Active"),
+            (ActionStatus::Waiting, "Waiting"),
+            (ActionStatus::Upcoming, "Upcoming"),
+            (ActionStatus::Complete, "Complete"),
+        ];
+        for (status, heading) in statuses.iter() {
+            let actions = self
+                .actions
+                .iter()
+                .filter(|(s, _)| s == status)
+                .collect::<Vec<_>>();
+            if actions.is_empty() && *status != ActionStatus::Active {
+                continue;
+            }
+            out.push_str(&format!("\n### {}\n\n", heading));
+            for (_, action) in actions {
+                out.push_str(&format!("- {}\n", action.to_markdown()));
+            }
+        }
+
+        for (label, text) in self.footnotes.iter() {
+            // The lines after the first are indented to show that they're part of the definition.
+            let text = text.trim().replace('\n', "\n    ");
+            out.push_str(&format!("\n[^{}]: {}\n", label, text));
+        }
+        out
+    }
+
+    /// Checks the project and builds it.
+    pub fn build(&self) -> Result<Project, BuildError> {
+        let scheme = name_scheme(&self.scheme);
+        if Name::with_scheme(self.name.clone(), scheme).is_none() {
+            return Err(BuildError::InvalidProjectName(self.name.clone()));
+        }
+        for tag in self.tags.iter() {
+            check_tag(tag)?;
+        }
+        let mut ids = Vec::new();
+        for (_, action) in self.actions.iter() {
+            action.check()?;
+            if let Some(id) = &action.id {
+                if ids.contains(&id) {
+                    return Err(BuildError::DuplicateActionId(id.clone()));
+                }
+                ids.push(id);
+            }
+        }
+
+        let text = self.to_markdown();
+        let project = Project::parse_with_scheme(self.name.clone(), &text, scheme)
+            .map_err(|e| BuildError::InvalidProject(e.into_static()))?;
+
+        let labels = project.footnotes.iter().map(|f| &f.label);
+        if !labels.eq(self.footnotes.iter().map(|(label, _)| label)) {
+            return Err(BuildError::ProjectIsReadDifferently(self.name.clone()));
+        }
+        let parsed = project.actions.actions().collect::<Vec<_>>();
+        if parsed.len() != self.actions.len() {
+            return Err(BuildError::ProjectIsReadDifferently(self.name.clone()));
+        }
+        let mut expected = self.actions.iter().collect::<Vec<_>>();
+        expected.sort_by_key(|(s, _)| status_order(*s));
+        for ((status, builder), (action, parsed_status)) in expected.into_iter().zip(parsed) {
+            if *status != parsed_status || !builder.matches(action) {
+                return Err(BuildError::ActionIsReadDifferently(builder.text.clone()));
+            }
+        }
+        Ok(project)
+    }
+}
+
+fn status_order(status: ActionStatus) -> usize {
+    match status {
+        ActionStatus::Active => 0,
+        ActionStatus::Waiting => 1,
+        ActionStatus::Upcoming => 2,
+        ActionStatus::Complete => 3,
+    }
+}
+
+/// Builds an [`Action`], as part of a [`ProjectBuilder`].
+#[derive(Debug, Clone)]
+pub struct ActionBuilder {
+    text: String,
+    id: Option<String>,
+    tags: Vec<String>,
+    completed: Option<NaiveDate>,
+    due: Option<NaiveDate>,
+    deferred: Option<NaiveDate>,
+    created: Option<NaiveDate>,
+    who: Option<String>,
+    uid: Option<String>,
+    issue: Option<String>,
+    moved: Option<String>,
+    priority: Priority,
+    estimate: Option<Estimate>,
+    activated: Option<NaiveDate>,
+}
+
+impl ActionBuilder {
+    /// Starts an action whose text is the Markdown `text`.
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        Self {
+            text: text.into(),
+            id: None,
+            tags: Vec::new(),
+            completed: None,
+            due: None,
+            deferred: None,
+            created: None,
+            who: None,
+            uid: None,
+            issue: None,
+            moved: None,
+            priority: Priority::Normal,
+            estimate: None,
+            activated: None,
+        }
+    }
+
+    /// Starts a builder that renders `action` the way it would be written back to its list item.
+    pub fn from_action(action: &Action) -> Self {
+        Self {
+            text: action.text.to_markdown(),
+            id: action.id.as_ref().map(|id| id.as_str().to_string()),
+            tags: action.tags.iter().map(|t| t.as_str().to_string()).collect(),
+            completed: action.completed,
+            due: action.due,
+            deferred: action.deferred,
+            created: action.created,
+            who: action.who.clone(),
+            uid: action.uid.clone(),
+            issue: action.issue.clone(),
+            moved: action.moved.clone(),
+            priority: action.priority,
+            estimate: action.estimate,
+            activated: action.activated,
+        }
+    }
+
+    /// Sets the block ID that contexts link to the action with, like `abc123`.
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Adds a tag, with or without the leading `#`.
+    pub fn tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn completed(mut self, date: NaiveDate) -> Self {
+        self.completed = Some(date);
+        self
+    }
+
+    pub fn due(mut self, date: NaiveDate) -> Self {
+        self.due = Some(date);
+        self
+    }
+
+    pub fn deferred(mut self, date: NaiveDate) -> Self {
+        self.deferred = Some(date);
+        self
+    }
+
+    pub fn created(mut self, date: NaiveDate) -> Self {
+        self.created = Some(date);
+        self
+    }
+
+    /// Sets who a waiting action is waiting on.
+    pub fn who<S: Into<String>>(mut self, who: S) -> Self {
+        self.who = Some(who.into());
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn estimate(mut self, estimate: Estimate) -> Self {
+        self.estimate = Some(estimate);
+        self
+    }
+
+    /// Sets the ID of the action's copy in a sync backend.
+    pub fn uid<S: Into<String>>(mut self, uid: S) -> Self {
+        self.uid = Some(uid.into());
+        self
+    }
+
+    /// Sets the issue the action is tracked in, like `owner/repo#123`.
+    pub fn issue<S: Into<String>>(mut self, issue: S) -> Self {
+        self.issue = Some(issue.into());
+        self
+    }
+
+    /// Sets where the action was moved from and when, like `2024-01-05 from Plan trip`.
+    pub fn moved<S: Into<String>>(mut self, moved: S) -> Self {
+        self.moved = Some(moved.into());
+        self
+    }
+
+    /// Sets when the action was promoted to active.
+    pub fn activated(mut self, date: NaiveDate) -> Self {
+        self.activated = Some(date);
+        self
+    }
+
+    /// Renders the action as the text of its list item.
+    pub fn to_markdown(&self) -> String {
+        let mut parts = vec![self.text.trim().to_string()];
+        parts.extend(
+            self.tags
+                .iter()
+                .map(|t| format!("#{}", t.trim_start_matches('#'))),
+        );
+        parts.extend(self.priority.marker().map(String::from));
+        parts.extend(self.who.iter().map(|w| format!("@who({})", w)));
+        parts.extend(
+            self.uid
+                .iter()
+                .map(|u| format!("@{}({})", UID_ANNOTATION, u)),
+        );
+        parts.extend(
+            self.issue
+                .iter()
+                .map(|i| format!("@{}({})", ISSUE_ANNOTATION, i)),
+        );
+        parts.extend(
+            self.moved
+                .iter()
+                .map(|m| format!("@{}({})", MOVED_ANNOTATION, m)),
+        );
+        parts.extend(self.estimate.iter().map(|e| format!("@estimate({})", e)));
+        parts.extend(
+            self.activated
+                .iter()
+                .map(|d| format!("@{}({})", ACTIVE_ANNOTATION, d.format("%Y-%m-%d"))),
+        );
+        let dates = [
+            (CREATED_MARKER, self.created),
+            (DEFER_MARKER, self.deferred),
+            (DUE_MARKER, self.due),
+            (DONE_MARKER, self.completed),
+        ];
+        for (marker, date) in dates.iter() {
+            if let Some(date) = date {
+                parts.push(format!("{} {}", marker, date.format("%Y-%m-%d")));
+            }
+        }
+        parts.extend(self.id.iter().map(|id| format!("^{}", id)));
+        parts.join(" ")
+    }
+
+    fn check(&self) -> Result<(), BuildError> {
+        let text = self.text.trim();
+        if text.is_empty() || text.contains('\n') {
+            return Err(BuildError::InvalidActionText(self.text.clone()));
+        }
+        if let Some(id) = &self.id {
+            if id.len() != 6 || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(BuildError::InvalidActionId(id.clone()));
+            }
+        }
+        for tag in self.tags.iter() {
+            check_tag(tag)?;
+        }
+        if let Some(who) = &self.who {
+            if !is_annotation_value(who) {
+                return Err(BuildError::InvalidWho(who.clone()));
+            }
+        }
+        if let Some(uid) = &self.uid {
+            if !is_annotation_value(uid) {
+                return Err(BuildError::InvalidUid(uid.clone()));
+            }
+        }
+        if let Some(issue) = &self.issue {
+            if !is_annotation_value(issue) {
+                return Err(BuildError::InvalidIssue(issue.clone()));
+            }
+        }
+        if let Some(moved) = &self.moved {
+            if !is_annotation_value(moved) {
+                return Err(BuildError::InvalidMoved(moved.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks if `action` has everything this builder set.
+    fn matches(&self, action: &Action) -> bool {
+        let tags = self
+            .tags
+            .iter()
+            .filter_map(|t| Tag::new(t.as_str()))
+            .collect::<Vec<_>>();
+        action.id.as_ref().map(|id| id.as_str()) == self.id.as_deref()
+            && action.tags == tags
+            && action.completed == self.completed
+            && action.due == self.due
+            && action.deferred == self.deferred
+            && action.created == self.created
+            && action.who.as_deref() == self.who.as_deref().map(str::trim)
+            && action.priority == self.priority
+            && action.estimate == self.estimate
+            && action.uid.as_deref() == self.uid.as_deref().map(str::trim)
+            && action.issue.as_deref() == self.issue.as_deref().map(str::trim)
+            && action.moved.as_deref() == self.moved.as_deref().map(str::trim)
+            && action.activated == self.activated
+    }
+}
+
+/// Builds a [`Context`].
+#[derive(Debug, Clone)]
+pub struct ContextBuilder {
+    name: String,
+    scheme: Option<NameScheme>,
+    title: Option<String>,
+    actions: Vec<ContextActionBuilder>,
+    /// The heading of each group, and the index of the first action in it.
+    groups: Vec<(String, usize)>,
+}
+
+#[derive(Debug, Clone)]
+enum ContextActionBuilder {
+    Literal(String),
+    Reference { project: String, action_id: String },
+}
+
+impl ContextBuilder {
+    /// Starts a context named `name`, like `@computer`.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            scheme: None,
+            title: None,
+            actions: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Starts a builder that renders `context` the way it would be written back to its file.
+    pub fn from_context(context: &Context) -> Self {
+        let mut builder = Self::new(context.name.as_str()).title(context.title.to_string());
+        builder.push_actions(context.ungrouped_actions());
+        for group in context.groups() {
+            builder = builder.group(group.heading.to_string());
+            builder.push_actions(context.group_actions(group));
+        }
+        builder
+    }
+
+    /// Uses `scheme` instead of the default to read the names of linked projects.
+    pub fn scheme(mut self, scheme: &NameScheme) -> Self {
+        self.scheme = Some(scheme.clone());
+        self
+    }
+
+    /// Sets the Markdown text of the heading, instead of using the name.
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Starts a group under the heading with the Markdown text `heading`, which the actions added
+    /// after it are in.
+    pub fn group<S: Into<String>>(mut self, heading: S) -> Self {
+        self.groups.push((heading.into(), self.actions.len()));
+        self
+    }
+
+    /// Adds an action that's only in the context, whose text is the Markdown `text`.
+    pub fn literal<S: Into<String>>(mut self, text: S) -> Self {
+        self.actions
+            .push(ContextActionBuilder::Literal(text.into()));
+        self
+    }
+
+    /// Adds an embed of the action with `action_id` in the project named `project`.
+    pub fn reference<S: Into<String>, T: Into<String>>(mut self, project: S, action_id: T) -> Self {
+        self.actions.push(ContextActionBuilder::Reference {
+            project: project.into(),
+            action_id: action_id.into(),
+        });
+        self
+    }
+
+    fn push_actions(&mut self, actions: &[context::Action]) {
+        self.actions
+            .extend(actions.iter().map(|action| match action {
+                context::Action::Literal(text) => ContextActionBuilder::Literal(text.to_markdown()),
+                context::Action::UnresolvedReference(block_ref) => {
+                    ContextActionBuilder::Literal(block_ref.to_string())
+                }
+                context::Action::Reference(action_ref) => ContextActionBuilder::Reference {
+                    project: action_ref.project_name.as_str().to_string(),
+                    action_id: action_ref.action_id.as_str().to_string(),
+                },
+            }));
+    }
+
+    /// Renders the context as the Markdown text of its file.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n", self.title.as_ref().unwrap_or(&self.name));
+        let ungrouped_end = self.groups.first().map_or(self.actions.len(), |(_, i)| *i);
+        write_context_actions(&mut out, &self.actions[..ungrouped_end]);
+        for (heading, range) in self.group_ranges() {
+            out.push_str(&format!("\n## {}\n", heading));
+            write_context_actions(&mut out, &self.actions[range]);
+        }
+        out
+    }
+
+    /// The heading of each group, and the range of its actions.
+    fn group_ranges(&self) -> impl Iterator<Item = (&str, Range<usize>)> {
+        self.groups
+            .iter()
+            .enumerate()
+            .map(move |(i, (heading, start))| {
+                let end = self
+                    .groups
+                    .get(i + 1)
+                    .map_or(self.actions.len(), |(_, i)| *i);
+                (heading.as_str(), *start..end)
+            })
+    }
+
+    /// Checks the context and builds it.
+    pub fn build(&self) -> Result<Context, BuildError> {
+        let scheme = name_scheme(&self.scheme);
+        if self.name.trim().is_empty() || self.name.contains('\n') {
+            return Err(BuildError::InvalidContextName(self.name.clone()));
+        }
+        for (heading, _) in self.groups.iter() {
+            if heading.trim().is_empty() || heading.contains('\n') {
+                return Err(BuildError::InvalidHeading(heading.clone()));
+            }
+        }
+        for action in self.actions.iter() {
+            match action {
+                ContextActionBuilder::Literal(text) => {
+                    let trimmed = text.trim();
+                    if trimmed.is_empty() || trimmed.contains('\n') || trimmed.starts_with("![[") {
+                        return Err(BuildError::InvalidActionText(text.clone()));
+                    }
+                }
+                ContextActionBuilder::Reference { project, action_id } => {
+                    if Name::with_scheme(project.clone(), scheme).is_none() {
+                        return Err(BuildError::InvalidProjectName(project.clone()));
+                    }
+                    if action_id.len() != 6 || !action_id.chars().all(|c| c.is_ascii_alphanumeric())
+                    {
+                        return Err(BuildError::InvalidActionId(action_id.clone()));
+                    }
+                }
+            }
+        }
+
+        let text = self.to_markdown();
+        let context = Context::parse_with_scheme(self.name.clone(), &text, scheme)
+            .map_err(|e| BuildError::InvalidContext(e.into_static()))?;
+        let is_read_back = context.actions().len() == self.actions.len()
+            && context
+                .actions()
+                .iter()
+                .zip(self.actions.iter())
+                .all(|(parsed, built)| match (parsed, built) {
+                    (context::Action::Literal(_), ContextActionBuilder::Literal(_)) => true,
+                    (
+                        context::Action::Reference(action_ref),
+                        ContextActionBuilder::Reference { project, action_id },
+                    ) => {
+                        action_ref.project_name.as_str() == project
+                            && action_ref.action_id.as_str() == action_id
+                    }
+                    _ => false,
+                })
+            && context
+                .groups()
+                .iter()
+                .map(|g| context.group_actions(g).len())
+                .eq(self.group_ranges().map(|(_, range)| range.len()));
+        if !is_read_back {
+            return Err(BuildError::ContextIsReadDifferently(self.name.clone()));
+        }
+        Ok(context)
+    }
+}
+
+fn write_context_actions(out: &mut String, actions: &[ContextActionBuilder]) {
+    if !actions.is_empty() {
+        out.push('\n');
+    }
+    for action in actions {
+        match action {
+            ContextActionBuilder::Literal(text) => out.push_str(&format!("- {}\n", text.trim())),
+            ContextActionBuilder::Reference { project, action_id } => {
+                out.push_str(&format!("- ![[{}#^{}]]\n", project, action_id))
+            }
+        }
+    }
+}
+
+fn name_scheme(scheme: &Option<NameScheme>) -> &NameScheme {
+    match scheme {
+        Some(scheme) => scheme,
+        None => NameScheme::default_ref(),
+    }
+}
+
+/// Whether `value` can be written in an annotation like `@who(…)`.
+fn is_annotation_value(value: &str) -> bool {
+    !value.trim().is_empty() && !value.contains(')') && !value.contains('\n')
+}
+
+fn check_tag(tag: &str) -> Result<(), BuildError> {
+    match Tag::new(tag) {
+        Some(_) if !tag.contains(char::is_whitespace) => Ok(()),
+        _ => Err(BuildError::InvalidTag(tag.to_string())),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    InvalidProjectName(String),
+    InvalidContextName(String),
+    InvalidHeading(String),
+    InvalidTag(String),
+    InvalidActionText(String),
+    InvalidActionId(String),
+    DuplicateActionId(String),
+    InvalidWho(String),
+    InvalidUid(String),
+    InvalidIssue(String),
+    InvalidMoved(String),
+    InvalidProject(project::ParseError<'static>),
+    InvalidContext(context::ParseError<'static>),
+    /// The project's Markdown would be read back with different actions or footnotes.
+    ProjectIsReadDifferently(String),
+    /// The action's text has something in it, like a date marker, that would be read back as part
+    /// of the action instead of its text.
+    ActionIsReadDifferently(String),
+    ContextIsReadDifferently(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidProjectName(name) => {
+                write!(f, "\"{}\" doesn't follow the project name scheme", name)
+            }
+            Self::InvalidContextName(name) => write!(f, "\"{}\" is not a context name", name),
+            Self::InvalidHeading(heading) => {
+                write!(f, "\"{}\" is not a single line of heading text", heading)
+            }
+            Self::InvalidTag(tag) => write!(f, "\"{}\" is not a valid tag", tag),
+            Self::InvalidActionText(text) => {
+                write!(f, "\"{}\" is not a single line of action text", text)
+            }
+            Self::InvalidActionId(id) => {
+                write!(f, "\"{}\" is not an action ID, like \"abc123\"", id)
+            }
+            Self::DuplicateActionId(id) => write!(f, "more than one action has the ID \"{}\"", id),
+            Self::InvalidWho(who) => write!(f, "\"{}\" can't be written in @who(…)", who),
+            Self::InvalidUid(uid) => write!(f, "\"{}\" can't be written in @uid(…)", uid),
+            Self::InvalidIssue(issue) => {
+                write!(f, "\"{}\" can't be written in @issue(…)", issue)
+            }
+            Self::InvalidMoved(moved) => {
+                write!(f, "\"{}\" can't be written in @moved(…)", moved)
+            }
+            Self::InvalidProject(e) => write!(f, "the built project doesn't parse: {}", e),
+            Self::InvalidContext(e) => write!(f, "the built context doesn't parse: {}", e),
+            Self::ProjectIsReadDifferently(name) => write!(
+                f,
+                "project \"{}\" would be read back with different actions or footnotes",
+                name
+            ),
+            Self::ActionIsReadDifferently(text) => write!(
+                f,
+                "action \"{}\" would be read back differently, probably because of a marker in its text",
+                text
+            ),
+            Self::ContextIsReadDifferently(name) => write!(
+                f,
+                "context \"{}\" would be read back with different actions",
+                name
+            ),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::ActionId;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    #[test]
+    fn project_is_built() {
+        let builder = ProjectBuilder::new("202401010000 Plan trip")
+            .tag("area/home")
+            .goal("Have a relaxing week away.")
+            .action(
+                ActionStatus::Active,
+                ActionBuilder::new("Book **flights**")
+                    .id("abc123")
+                    .tag("phone")
+                    .priority(Priority::High)
+                    .estimate(Estimate::from_minutes(30))
+                    .due(date(5)),
+            )
+            .action(
+                ActionStatus::Complete,
+                ActionBuilder::new("Pick dates").completed(date(2)),
+            )
+            .action(
+                ActionStatus::Waiting,
+                ActionBuilder::new("Hear back about time off").who("Alice"),
+            );
+
+        assert_eq!(
+            builder.to_markdown(),
+            "# Plan trip\n#in-progress #area/home\n\n## Goal\n\nHave a relaxing week away.\n\n## Actions\n\n### Active\n\n- Book **flights** #phone ⏫ @estimate(30m) 📅 2024-01-05 ^abc123\n\n### Waiting\n\n- Hear back about time off @who(Alice)\n\n### Complete\n\n- Pick dates ✅ 2024-01-02\n"
+        );
+
+        let project = builder.build().unwrap();
+        assert_eq!(
+            project,
+            Project::parse("202401010000 Plan trip", &builder.to_markdown()).unwrap()
+        );
+        let (action, status) = project
+            .actions
+            .get_action(&ActionId::new(String::from("abc123")))
+            .unwrap();
+        assert_eq!(status, ActionStatus::Active);
+        assert_eq!(action.due, Some(date(5)));
+        assert_eq!(action.priority, Priority::High);
+    }
+
+    #[test]
+    fn invalid_projects_are_rejected() {
+        assert_eq!(
+            ProjectBuilder::new("Plan trip").build(),
+            Err(BuildError::InvalidProjectName(String::from("Plan trip")))
+        );
+        let project = ProjectBuilder::new("202401010000 Plan trip");
+        assert_eq!(
+            project.clone().tag("two words").build(),
+            Err(BuildError::InvalidTag(String::from("two words")))
+        );
+        assert_eq!(
+            project
+                .clone()
+                .action(ActionStatus::Active, ActionBuilder::new("A").id("abc123"))
+                .action(ActionStatus::Active, ActionBuilder::new("B").id("abc123"))
+                .build(),
+            Err(BuildError::DuplicateActionId(String::from("abc123")))
+        );
+        assert_eq!(
+            project
+                .action(
+                    ActionStatus::Active,
+                    ActionBuilder::new("Call 📅 2024-01-05")
+                )
+                .build(),
+            Err(BuildError::ActionIsReadDifferently(String::from(
+                "Call 📅 2024-01-05"
+            )))
+        );
+    }
+
+    #[test]
+    fn footnotes_are_written_at_the_end() {
+        let builder = ProjectBuilder::new("202401010000 Plan trip")
+            .goal("Relax.[^1]")
+            .action(ActionStatus::Active, ActionBuilder::new("Book flights[^b]"))
+            .footnote("1", "Really.")
+            .footnote("b", "Cheap ones,\nor a train.");
+        assert_eq!(
+            builder.to_markdown(),
+            "# Plan trip\n#in-progress\n\n## Goal\n\nRelax.[^1]\n\n## Actions\n\n### Active\n\n- Book flights[^b]\n\n[^1]: Really.\n\n[^b]: Cheap ones,\n    or a train.\n"
+        );
+        let project = builder.build().unwrap();
+        assert_eq!(
+            ProjectBuilder::from_project(&project).to_markdown(),
+            builder.to_markdown()
+        );
+    }
+
+    #[test]
+    fn context_is_built() {
+        let builder = ContextBuilder::new("@home")
+            .reference("202401010000 Plan trip", "abc123")
+            .literal("Water plants");
+        assert_eq!(
+            builder.to_markdown(),
+            "# @home\n\n- ![[202401010000 Plan trip#^abc123]]\n- Water plants\n"
+        );
+        assert_eq!(
+            builder.build().unwrap(),
+            Context::parse("@home", &builder.to_markdown()).unwrap()
+        );
+        assert_eq!(
+            ContextBuilder::new("@home")
+                .reference("Plan trip", "abc123")
+                .build(),
+            Err(BuildError::InvalidProjectName(String::from("Plan trip")))
+        );
+    }
+}
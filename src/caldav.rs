@@ -0,0 +1,193 @@
+//! A sync backend for the tasks in a CalDAV calendar, like Nextcloud Tasks.
+//!
+//! Requests are made with `curl`, and credentials are passed to it on stdin so they don't show up
+//! in the process list.
+
+use crate::{
+    config::CalDavConfig,
+    ics::{self, Todo},
+    obsidian::percent_encode,
+    sync::SyncBackend,
+};
+use chrono::{Local, NaiveDate};
+use regex::Regex;
+use std::{
+    env, fs,
+    io::{Error as IoError, ErrorKind, Write},
+    process::{Command, Stdio},
+};
+
+const QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><c:calendar-data/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+</c:calendar-query>"#;
+
+/// A CalDAV calendar collection holding tasks.
+#[derive(Debug, Clone)]
+pub struct CalDav {
+    url: String,
+    credentials: Option<String>,
+}
+
+impl CalDav {
+    /// Connects to the calendar in `config`, running its password command if it has one.
+    pub fn from_config(config: &CalDavConfig) -> Result<Self, IoError> {
+        let url = config
+            .url
+            .clone()
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "no CalDAV URL is configured"))?;
+
+        let credentials = match (&config.username, &config.password_command) {
+            (Some(username), Some(command)) => {
+                let output = Command::new("sh").args(["-c", command]).output()?;
+                if !output.status.success() {
+                    return Err(IoError::other("CalDAV password command failed"));
+                }
+                let password = String::from_utf8_lossy(&output.stdout);
+                Some(format!("{}:{}", username, password.trim_end_matches('\n')))
+            }
+            (Some(username), None) => Some(username.clone()),
+            (None, _) => None,
+        };
+
+        Ok(Self {
+            url: format!("{}/", url.trim_end_matches('/')),
+            credentials,
+        })
+    }
+
+    /// Makes a request with `curl`, returning the response body.
+    fn request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[&str],
+        body: &str,
+    ) -> Result<String, IoError> {
+        // The body goes in a file so that stdin is free for the curl config holding credentials.
+        let body_path = env::temp_dir().join(format!("gtd-caldav-{}.xml", std::process::id()));
+        fs::write(&body_path, body)?;
+
+        let mut command = Command::new("curl");
+        command.args(["-fsS", "-X", method, "-K", "-"]);
+        for header in headers {
+            command.args(["-H", header]);
+        }
+        command.arg("--data-binary");
+        command.arg(format!("@{}", body_path.display()));
+        command.arg(url);
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = command.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            if let Some(credentials) = &self.credentials {
+                writeln!(stdin, "user = \"{}\"", curl_escape(credentials))?;
+            }
+        }
+        let output = child.wait_with_output();
+        let _ = fs::remove_file(&body_path);
+        let output = output?;
+
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr);
+            return Err(IoError::other(format!(
+                "CalDAV request failed: {}",
+                message.trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl SyncBackend for CalDav {
+    fn name(&self) -> &str {
+        "caldav"
+    }
+
+    fn pull(&mut self) -> Result<Vec<Todo>, IoError> {
+        let response = self.request(
+            "REPORT",
+            &self.url,
+            &["Depth: 1", "Content-Type: application/xml; charset=utf-8"],
+            QUERY,
+        )?;
+        let today = Local::now().date_naive();
+        Ok(todos_in_response(&response, today))
+    }
+
+    fn push(&mut self, task: &Todo) -> Result<(), IoError> {
+        let url = format!("{}{}.ics", self.url, percent_encode(&task.uid));
+        self.request(
+            "PUT",
+            &url,
+            &["Content-Type: text/calendar; charset=utf-8"],
+            &task.to_ics(),
+        )?;
+        Ok(())
+    }
+}
+
+/// The tasks in the calendar data of a multistatus response.
+fn todos_in_response(response: &str, today: NaiveDate) -> Vec<Todo> {
+    let data = Regex::new(r"(?s)<(?:[\w-]+:)?calendar-data[^>]*>(.*?)</(?:[\w-]+:)?calendar-data>")
+        .unwrap();
+    data.captures_iter(response)
+        .flat_map(|c| {
+            let text = c[1].trim();
+            let text = match text
+                .strip_prefix("<![CDATA[")
+                .and_then(|t| t.strip_suffix("]]>"))
+            {
+                Some(cdata) => cdata.to_string(),
+                None => xml_unescape(text),
+            };
+            ics::parse_todos(&text, today)
+        })
+        .collect()
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&amp;", "&")
+}
+
+fn curl_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn todos_are_read_from_response() {
+        let response = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:propstat><d:prop>
+      <cal:calendar-data>BEGIN:VCALENDAR&#13;
+BEGIN:VTODO&#13;
+UID:abc&#13;
+SUMMARY:Pack &amp; go&#13;
+END:VTODO&#13;
+END:VCALENDAR&#13;
+</cal:calendar-data>
+    </d:prop></d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        let today = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let todos = todos_in_response(response, today);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].uid, "abc");
+        assert_eq!(todos[0].summary, "Pack & go");
+    }
+}
@@ -0,0 +1,175 @@
+//! Appointments from a calendar feed, for planning actions around fixed commitments.
+
+use crate::{
+    config::CalendarConfig,
+    ics::{self, Event},
+    timelog::format_duration,
+};
+use chrono::{Duration, NaiveDate};
+use std::{fs, io::Error as IoError, path::Path, process::Command};
+
+/// The events in a calendar.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Calendar {
+    events: Vec<Event>,
+}
+
+impl Calendar {
+    pub fn new(events: Vec<Event>) -> Self {
+        Self { events }
+    }
+
+    pub fn parse(text: &str) -> Self {
+        Self::new(ics::parse(text))
+    }
+
+    /// Loads the configured calendar, or returns `None` if there isn't one.
+    ///
+    /// Files are relative to `root_dir`, and `http://` and `https://` URLs are downloaded with
+    /// `curl`.
+    pub fn load(config: &CalendarConfig, root_dir: &Path) -> Result<Option<Self>, IoError> {
+        let source = match &config.source {
+            Some(source) => source,
+            None => return Ok(None),
+        };
+
+        let text = if source.starts_with("http://") || source.starts_with("https://") {
+            download(source)?
+        } else {
+            fs::read_to_string(root_dir.join(source))?
+        };
+        Ok(Some(Self::parse(&text)))
+    }
+
+    /// The events on `date`, in the order they start.
+    pub fn agenda(&self, date: NaiveDate) -> Vec<&Event> {
+        let mut events = self
+            .events
+            .iter()
+            .filter(|e| e.on(date).is_some())
+            .collect::<Vec<_>>();
+        events.sort_by_key(|e| (e.start, e.summary.clone()));
+        events
+    }
+
+    /// How much of `date` is taken up by events that block time. An all-day event takes up the
+    /// whole day.
+    pub fn busy_time(&self, date: NaiveDate) -> Duration {
+        let mut ranges = self
+            .events
+            .iter()
+            .filter(|e| !e.is_transparent)
+            .filter_map(|e| e.on(date))
+            .collect::<Vec<_>>();
+        ranges.sort();
+
+        // Merge overlapping events so that time isn't counted twice.
+        let mut busy = Duration::zero();
+        let mut current = None;
+        for (start, end) in ranges {
+            current = match current {
+                Some((s, e)) if start <= e => Some((s, end.max(e))),
+                Some((s, e)) => {
+                    busy += e - s;
+                    Some((start, end))
+                }
+                None => Some((start, end)),
+            };
+        }
+        if let Some((s, e)) = current {
+            busy += e - s;
+        }
+        busy
+    }
+
+    /// Whether events block at least `busy_hours` of `date`.
+    pub fn is_fully_booked(&self, date: NaiveDate, busy_hours: u32) -> bool {
+        self.busy_time(date) >= Duration::hours(busy_hours.into())
+    }
+}
+
+/// Renders an event on `date` like `09:00-09:30 Team sync`.
+pub fn format_event(event: &Event, date: NaiveDate) -> String {
+    match event.on(date) {
+        Some((start, end)) if !event.is_all_day() => format!(
+            "{}-{} {}",
+            start.format("%H:%M"),
+            end.format("%H:%M"),
+            event.summary
+        ),
+        _ => format!("All day: {}", event.summary),
+    }
+}
+
+/// Prints the events on `date`, if there are any.
+pub fn print_agenda(calendar: &Calendar, date: NaiveDate) {
+    let events = calendar.agenda(date);
+    if events.is_empty() {
+        return;
+    }
+
+    println!(
+        "Calendar ({} booked):",
+        format_duration(calendar.busy_time(date))
+    );
+    for event in events {
+        println!("- {}", format_event(event, date));
+    }
+    println!();
+}
+
+fn download(url: &str) -> Result<String, IoError> {
+    let output = Command::new("curl").args(["-fsSL", url]).output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(IoError::other(format!(
+            "couldn't download calendar: {}",
+            message.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, d).unwrap()
+    }
+
+    fn calendar() -> Calendar {
+        Calendar::parse(
+            "BEGIN:VEVENT\nSUMMARY:Workshop\nDTSTART:20240105T090000\nDTEND:20240105T130000\nEND:VEVENT\nBEGIN:VEVENT\nSUMMARY:Lunch\nDTSTART:20240105T120000\nDTEND:20240105T140000\nEND:VEVENT\nBEGIN:VEVENT\nSUMMARY:Focus time\nDTSTART:20240105T150000\nDTEND:20240105T170000\nTRANSP:TRANSPARENT\nEND:VEVENT\nBEGIN:VEVENT\nSUMMARY:Offsite\nDTSTART;VALUE=DATE:20240108\nEND:VEVENT\n",
+        )
+    }
+
+    #[test]
+    fn overlapping_events_are_counted_once() {
+        assert_eq!(calendar().busy_time(date(5)), Duration::hours(5));
+    }
+
+    #[test]
+    fn all_day_event_books_whole_day() {
+        assert!(calendar().is_fully_booked(date(8), 8));
+        assert!(!calendar().is_fully_booked(date(5), 6));
+    }
+
+    #[test]
+    fn agenda_is_in_start_order() {
+        let calendar = calendar();
+        let agenda = calendar
+            .agenda(date(5))
+            .into_iter()
+            .map(|e| format_event(e, date(5)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            agenda,
+            vec![
+                "09:00-13:00 Workshop",
+                "12:00-14:00 Lunch",
+                "15:00-17:00 Focus time"
+            ]
+        );
+    }
+}
@@ -0,0 +1,457 @@
+//! Completing actions, one at a time or in batches, and projects.
+
+use crate::{
+    action_list,
+    config::ContextLayout,
+    context::{Action as ContextAction, Context},
+    edit, filename,
+    gtd::{Documents, LoadProjectError},
+    project::{Action, ActionStatus, Name as ProjectName, Project, Status},
+    promote::{self, PromoteError},
+    prompt,
+    resolve::ResolveError,
+    selector::{self, NoSelectorError, Selector},
+    transaction::{Transaction, TransactionError},
+};
+use chrono::NaiveDate;
+use std::{
+    error::Error,
+    fmt, fs,
+    io::{Error as IoError, ErrorKind},
+    path::PathBuf,
+};
+
+/// The name of the logbook that completed projects are recorded in, in the root of the GTD folder.
+pub const LOGBOOK_FILE: &str = "Logbook.md";
+
+/// Completes the incomplete action best matching `query` on `today`. With `chain`, the project's
+/// first upcoming action is promoted if that was its last active action.
+pub fn complete_action(
+    docs: &Documents,
+    query: &str,
+    today: NaiveDate,
+    chain: bool,
+) -> Result<(), CompleteError> {
+    let (project, action) = docs.find_action(query)?;
+    if !complete(docs, project, action, today)? {
+        return Err(CompleteError::Changed);
+    }
+
+    println!("Completed \"{}\" ({}).", action.text, project.title());
+    if chain {
+        promote_next(docs, project, today)?;
+    }
+    Ok(())
+}
+
+/// Completes every active action in the projects picked by `selector` on `today`.
+///
+/// The actions are listed first, and nothing is changed unless the user confirms or `yes` is set.
+/// With `chain`, each project's first upcoming action is promoted afterwards. Returns the number of
+/// actions completed.
+pub fn complete_active(
+    docs: &Documents,
+    selector: &Selector,
+    today: NaiveDate,
+    yes: bool,
+    chain: bool,
+) -> Result<usize, CompleteError> {
+    if selector.is_empty() {
+        return Err(NoSelectorError.into());
+    }
+
+    let actions = selector
+        .select(docs, today)
+        .into_iter()
+        .flat_map(|p| {
+            p.actions
+                .actions()
+                .filter(|(_, s)| *s == ActionStatus::Active)
+                .map(move |(a, _)| (p, a))
+        })
+        .collect::<Vec<_>>();
+    if actions.is_empty() {
+        println!("No active actions were selected.");
+        return Ok(0);
+    }
+
+    let items = actions
+        .iter()
+        .map(|(p, a)| format!("{} ({})", a.text, p.title()))
+        .collect::<Vec<_>>();
+    let question = format!("Complete {} actions?", actions.len());
+    if !selector::confirm_batch(&question, &items, yes)? {
+        return Ok(0);
+    }
+
+    let mut completed = 0;
+    let mut projects = Vec::<&Project>::new();
+    for (project, action) in actions {
+        if complete(docs, project, action, today)? {
+            completed += 1;
+            if !projects.iter().any(|p| p.name == project.name) {
+                projects.push(project);
+            }
+        }
+    }
+
+    println!("Completed {} actions.", completed);
+    if chain {
+        for project in projects {
+            promote_next(docs, project, today)?;
+        }
+    }
+    Ok(completed)
+}
+
+/// Promotes the first upcoming action of `project` if it has no active actions left, saying which
+/// one was promoted.
+fn promote_next(
+    docs: &Documents,
+    project: &Project,
+    today: NaiveDate,
+) -> Result<(), CompleteError> {
+    if let Some(promoted) = promote::promote_next(docs, project, today)? {
+        println!(
+            "Promoted \"{}\", the next action in {}.",
+            promoted.text,
+            project.title()
+        );
+        if let Some(context) = promoted.linked_from {
+            println!("Linked it from {}.", context);
+        }
+    }
+    Ok(())
+}
+
+/// A project that was completed by `complete_project`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedProject {
+    pub name: ProjectName,
+    pub title: String,
+    pub outcome: String,
+}
+
+/// Marks the project best matching `query` as complete on `today`, writing what came of it to its
+/// `## Outcome` section.
+///
+/// Any actions that aren't complete yet are listed, and completed too if the user confirms or
+/// `yes` is set. The outcome is `outcome` if it's given, or else asked for after showing the
+/// project's goal, and can be left empty. Links to the project's actions are removed from the
+/// contexts, the completion is added to the logbook, and the project is archived if `archive` is
+/// set. The files are all written together or not at all, and can be put back with `gtd undo`.
+pub fn complete_project(
+    docs: &Documents,
+    query: &str,
+    outcome: Option<&str>,
+    yes: bool,
+    archive: bool,
+    today: NaiveDate,
+) -> Result<CompletedProject, CompleteError> {
+    let loader = docs.loader();
+    loader.ensure_files()?;
+    let project = docs.find_project(query)?;
+    if project.status == Status::Complete {
+        return Err(CompleteError::AlreadyComplete);
+    }
+
+    let remaining = project
+        .actions
+        .actions()
+        .filter(|(_, s)| *s != ActionStatus::Complete)
+        .map(|(a, _)| a)
+        .collect::<Vec<_>>();
+    if !remaining.is_empty() {
+        let items = remaining
+            .iter()
+            .map(|a| a.text.to_string())
+            .collect::<Vec<_>>();
+        let question = format!("Complete the {} remaining actions?", remaining.len());
+        if !selector::confirm_batch(&question, &items, yes)? {
+            return Err(CompleteError::HasIncompleteActions);
+        }
+    }
+
+    let outcome = match outcome {
+        Some(outcome) => outcome.to_string(),
+        None => {
+            if let Some(goal) = &project.goal {
+                println!("Goal: {}", goal);
+            }
+            prompt::ask("Outcome:", Some(""))?.unwrap_or_default()
+        }
+    };
+
+    let project_path = loader.project_path(&project.name);
+    let mut transaction = Transaction::new(loader, format!("complete-project {}", query));
+    let mut text = fs::read_to_string(&project_path)?;
+    for action in remaining {
+        let reparsed = Project::parse_with_depths(
+            project.name.as_str(),
+            &text,
+            loader.scheme(),
+            loader.depths(),
+        )
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        // Find the action again, since completing earlier actions moves it around.
+        let found = reparsed
+            .actions
+            .actions()
+            .find(|(a, s)| *s != ActionStatus::Complete && a == &action);
+        let (action, _) = found.ok_or(CompleteError::Changed)?;
+        text =
+            edit::complete_action(&text, &reparsed, action, today).ok_or(CompleteError::Changed)?;
+    }
+    let text = edit::set_status(&text, Status::Complete).ok_or(CompleteError::Changed)?;
+    transaction.write(&project_path, edit::set_outcome(&text, &outcome, today))?;
+    let mut contexts = 0;
+    for path in linking_contexts(docs, &project.name) {
+        let old_text = fs::read_to_string(&path)?;
+        let text = edit::remove_list_items(&old_text, |item| {
+            ContextAction::parse_item(item, loader.scheme()).is_some_and(|a| {
+                a.to_action_ref()
+                    .is_some_and(|r| r.project_name == project.name)
+            })
+        });
+        if text != old_text {
+            transaction.write(&path, text)?;
+            contexts += 1;
+        }
+    }
+
+    let log_path = loader.root_dir().join(LOGBOOK_FILE);
+    let log = transaction.read(&log_path)?.unwrap_or_default();
+    transaction.write(&log_path, log_completion(&log, &project.name, today))?;
+    if archive {
+        let archived_path =
+            filename::long_path(&loader.archive_dir().join(format!("{}.md", project.name)));
+        transaction.rename(&project_path, &archived_path)?;
+    }
+    transaction.commit()?;
+
+    println!("Completed \"{}\".", project.title());
+    if contexts > 0 {
+        println!("Removed its actions from {} context files.", contexts);
+    }
+    if archive {
+        println!("Archived {}.", project.name);
+    }
+    Ok(CompletedProject {
+        name: project.name.clone(),
+        title: project.title().to_string(),
+        outcome,
+    })
+}
+
+/// The paths of the context files that link to actions in the project `name`.
+fn linking_contexts(docs: &Documents, name: &ProjectName) -> Vec<PathBuf> {
+    let links = |c: &Context| {
+        c.actions()
+            .iter()
+            .filter_map(|a| a.to_action_ref())
+            .any(|r| &r.project_name == name)
+    };
+    let linking = docs.contexts().filter(|c| links(c)).collect::<Vec<_>>();
+    if linking.is_empty() {
+        return Vec::new();
+    }
+    match docs.context_layout() {
+        ContextLayout::Files => linking
+            .iter()
+            .map(|c| docs.loader().context_path(&c.name))
+            .collect(),
+        ContextLayout::List => vec![action_list::path(docs.loader().root_dir())],
+    }
+}
+
+/// The logbook `text` with a line added saying the project `name` was completed on `today`.
+fn log_completion(text: &str, name: &ProjectName, today: NaiveDate) -> String {
+    let mut text = text.to_string();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(&format!(
+        "- {} completed [[{}]]\n",
+        today.format("%Y-%m-%d"),
+        name
+    ));
+    text
+}
+
+/// Completes `action` in the file of `project`, returning whether the file was written.
+pub fn complete(
+    docs: &Documents,
+    project: &Project,
+    action: &Action,
+    today: NaiveDate,
+) -> Result<bool, LoadProjectError> {
+    docs.loader()
+        .update_project(&project.name, |text, reparsed| {
+            // Find the action again, since completing earlier actions moves it around.
+            let (action, _) = reparsed
+                .actions
+                .actions()
+                .find(|(a, s)| *s != ActionStatus::Complete && a == &action)?;
+            edit::complete_action(text, reparsed, action, today)
+        })
+}
+
+#[derive(Debug)]
+pub enum CompleteError {
+    IoError(IoError),
+    ResolveError(ResolveError),
+    LoadProjectError(LoadProjectError),
+    NoSelectorError(NoSelectorError),
+    TransactionError(TransactionError),
+    PromoteError(PromoteError),
+    AlreadyComplete,
+    HasIncompleteActions,
+    Changed,
+}
+
+impl fmt::Display for CompleteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::ResolveError(e) => write!(f, "{}", e),
+            Self::LoadProjectError(e) => write!(f, "{}", e),
+            Self::NoSelectorError(e) => write!(f, "{}", e),
+            Self::TransactionError(e) => write!(f, "{}", e),
+            Self::PromoteError(e) => write!(f, "{}", e),
+            Self::AlreadyComplete => write!(f, "project is already complete"),
+            Self::HasIncompleteActions => write!(f, "project still has incomplete actions"),
+            Self::Changed => write!(f, "project changed while it was being edited"),
+        }
+    }
+}
+
+impl Error for CompleteError {}
+
+impl From<IoError> for CompleteError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<ResolveError> for CompleteError {
+    fn from(error: ResolveError) -> Self {
+        Self::ResolveError(error)
+    }
+}
+
+impl From<LoadProjectError> for CompleteError {
+    fn from(error: LoadProjectError) -> Self {
+        Self::LoadProjectError(error)
+    }
+}
+
+impl From<NoSelectorError> for CompleteError {
+    fn from(error: NoSelectorError) -> Self {
+        Self::NoSelectorError(error)
+    }
+}
+
+impl From<TransactionError> for CompleteError {
+    fn from(error: TransactionError) -> Self {
+        Self::TransactionError(error)
+    }
+}
+
+impl From<PromoteError> for CompleteError {
+    fn from(error: PromoteError) -> Self {
+        Self::PromoteError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+
+    #[test]
+    fn completing_a_project_cleans_up_after_it() {
+        let dir = TempDir::new("complete");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights ^abcdef\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Contexts/@phone.md"),
+            "# @phone\n\n- ![[202401010000 Plan trip#^abcdef]]\n- Call mom\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+
+        complete_project(&docs, "Plan trip", Some("Went."), true, true, today).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("Archive/202401010000 Plan trip.md")).unwrap(),
+            "# Plan trip\n#complete\n\n## Outcome\n\nWent. ✅ 2024-01-10\n\n## Actions\n\n### Active\n\n### Complete\n\n- Book flights ✅ 2024-01-10 ^abcdef\n"
+        );
+        assert!(!dir.join("Projects/202401010000 Plan trip.md").exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("Contexts/@phone.md")).unwrap(),
+            "# @phone\n\n- Call mom\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(LOGBOOK_FILE)).unwrap(),
+            "- 2024-01-10 completed [[202401010000 Plan trip]]\n"
+        );
+    }
+
+    #[test]
+    fn projects_with_custom_heading_depths_are_left_alone() {
+        let dir = TempDir::new("complete-depths");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let path = dir.join("Projects/202401010000 Plan trip.md");
+        let text = "# Plan trip\n#in-progress\n\n## Active\n\n- Book flights ^abcdef\n";
+        fs::write(&path, text).unwrap();
+        let config = Config::parse("[headings]\naction-statuses = 2\n").unwrap();
+        let docs = Documents::load(&dir, &config).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+
+        let err = complete_project(&docs, "Plan trip", Some("Went."), true, false, today);
+        assert!(matches!(err, Err(CompleteError::IoError(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+    }
+
+    #[test]
+    fn completing_the_last_active_action_can_promote_the_next() {
+        let dir = TempDir::new("complete-chain");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let path = dir.join("Projects/202401010000 Paint shed.md");
+        fs::write(
+            &path,
+            "---\ndefault-context: \"@shed\"\n---\n# Paint shed\n#in-progress\n\n## Actions\n\n### Active\n\n- Sand walls ^aaaaaa\n- Buy paint ^bbbbbb\n\n### Upcoming\n\n- Prime walls ^cccccc\n- Paint walls ^dddddd\n",
+        )
+        .unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+        let load = || Documents::load(&dir, &Config::default()).unwrap();
+
+        complete_action(&load(), "aaaaaa", today, true).unwrap();
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("### Upcoming\n\n- Prime walls ^cccccc\n"));
+
+        complete_action(&load(), "bbbbbb", today, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "---\ndefault-context: \"@shed\"\n---\n# Paint shed\n#in-progress\n\n## Actions\n\n### Active\n\n- Prime walls @active(2024-01-10) ^cccccc\n\n### Upcoming\n\n- Paint walls ^dddddd\n\n### Complete\n\n- Sand walls ✅ 2024-01-10 ^aaaaaa\n- Buy paint ✅ 2024-01-10 ^bbbbbb\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("Contexts/@shed.md")).unwrap(),
+            "# @shed\n\n- ![[202401010000 Paint shed#^cccccc]]\n"
+        );
+
+        complete_action(&load(), "cccccc", today, false).unwrap();
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("### Upcoming\n\n- Paint walls ^dddddd\n"));
+    }
+}
@@ -0,0 +1,1262 @@
+//! User configuration, loaded from `gtd.toml` in the root of the GTD folder.
+
+use crate::{
+    context::Name as ContextName,
+    layers,
+    parser::{HeadingDepths, HeadingDepthsError},
+    period::Period,
+    project::{
+        NameScheme, NameSchemeError, Project, Section, DEFAULT_ID_FORMAT, DEFAULT_NAME_PATTERN,
+        DEFAULT_NAME_TEMPLATE,
+    },
+    tag::Tag,
+};
+use chrono::Weekday;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    error::Error,
+    fmt, fs,
+    io::{Error as IoError, ErrorKind},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use toml::Value;
+
+const CONFIG_FILENAME: &str = "gtd.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub project_names: NameScheme,
+    pub tags: TagConfig,
+    pub open: OpenConfig,
+    pub obsidian: ObsidianConfig,
+    pub links: LinkConfig,
+    pub pomodoro: PomodoroConfig,
+    pub calendar: CalendarConfig,
+    pub caldav: CalDavConfig,
+    pub issues: IssuesConfig,
+    pub attachments: AttachmentsConfig,
+    pub validate: ValidateConfig,
+    pub today: TodayConfig,
+    pub status: StatusConfig,
+    pub next: NextConfig,
+    pub waiting: WaitingConfig,
+    pub complete: CompleteConfig,
+    pub hooks: HooksConfig,
+    pub git: GitConfig,
+    pub contexts: ContextConfig,
+    pub single_file: SingleFileConfig,
+    pub headings: HeadingDepths,
+    pub locale: LocaleConfig,
+    pub publish: PublishConfig,
+    pub progress: ProgressConfig,
+}
+
+impl Config {
+    /// Loads the config in `root_dir`, falling back to the default config if there isn't one.
+    pub fn load<P: AsRef<Path>>(root_dir: P) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path(root_dir)) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        toml::from_str(text)
+            .map(Self::move_deprecated)
+            .map_err(ConfigError::from)
+    }
+
+    /// Moves settings that are still read from where they used to be to where they are now,
+    /// unless they're set there too.
+    pub(crate) fn move_deprecated(mut self) -> Self {
+        if self.obsidian.vault.is_none() {
+            self.obsidian.vault = self.open.vault.take();
+        }
+        self
+    }
+}
+
+/// The path of the config file in `root_dir`.
+pub fn path<P: AsRef<Path>>(root_dir: P) -> PathBuf {
+    root_dir.as_ref().join(CONFIG_FILENAME)
+}
+
+/// Rewrites the config `text` so that `alias` is an alias of `context`, along with any aliases that
+/// stood for `alias`. Only the `[contexts.aliases]` table is changed, and it's added if there isn't
+/// one.
+pub fn set_context_alias(text: &str, alias: &str, context: &str) -> String {
+    let entry = |key: &str| format!("\"{}\" = \"{}\"", key, context);
+    let mut lines = text.lines().map(String::from).collect::<Vec<_>>();
+    let start = match lines.iter().position(|l| l.trim() == "[contexts.aliases]") {
+        Some(i) => i + 1,
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(String::from("[contexts.aliases]"));
+            lines.push(entry(alias));
+            return lines.join("\n") + "\n";
+        }
+    };
+    let end = lines[start..]
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .map_or(lines.len(), |i| start + i);
+
+    let mut is_set = false;
+    for line in lines[start..end].iter_mut() {
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (unquote(key), unquote(value)),
+            None => continue,
+        };
+        if key == alias {
+            *line = entry(alias);
+            is_set = true;
+        } else if value == alias {
+            *line = entry(key);
+        }
+    }
+    if !is_set {
+        let last = lines[start..end]
+            .iter()
+            .rposition(|l| !l.trim().is_empty())
+            .map_or(start, |i| start + i + 1);
+        lines.insert(last, entry(alias));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// The kind of value a setting takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingKind {
+    Text,
+    Number,
+    Switch,
+    /// A list of strings, like `["GTD012"]`.
+    List,
+    /// A list of tables, which can only be written in the config file itself.
+    Tables,
+}
+
+/// A setting in the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Setting {
+    /// The dotted key of the setting, like `next.old-after`, with `*` for the keys of tables like
+    /// `contexts.aliases`.
+    pub key: &'static str,
+    pub kind: SettingKind,
+}
+
+const fn setting(key: &'static str, kind: SettingKind) -> Setting {
+    Setting { key, kind }
+}
+
+/// Every setting there is, in the order they're documented.
+pub const SETTINGS: &[Setting] = &[
+    setting("project-names.pattern", SettingKind::Text),
+    setting("project-names.id-format", SettingKind::Text),
+    setting("project-names.template", SettingKind::Text),
+    setting("tags.allowed", SettingKind::List),
+    setting("tags.required", SettingKind::List),
+    setting("open.method", SettingKind::Text),
+    setting("obsidian.vault", SettingKind::Text),
+    setting("links.style", SettingKind::Text),
+    setting("pomodoro.minutes", SettingKind::Number),
+    setting("pomodoro.sessions", SettingKind::Number),
+    setting("calendar.source", SettingKind::Text),
+    setting("calendar.busy-hours", SettingKind::Number),
+    setting("caldav.url", SettingKind::Text),
+    setting("caldav.username", SettingKind::Text),
+    setting("caldav.password-command", SettingKind::Text),
+    setting("issues.github-url", SettingKind::Text),
+    setting("issues.github-token-command", SettingKind::Text),
+    setting("issues.jira-url", SettingKind::Text),
+    setting("issues.jira-username", SettingKind::Text),
+    setting("issues.jira-token-command", SettingKind::Text),
+    setting("attachments.folder", SettingKind::Text),
+    setting("validate.disabled", SettingKind::List),
+    setting("validate.enabled", SettingKind::List),
+    setting("validate.stalled-after", SettingKind::Text),
+    setting("validate.archive-after", SettingKind::Text),
+    setting("validate.required-sections", SettingKind::Tables),
+    setting("validate.check-changes", SettingKind::Switch),
+    setting("validate.ask-for-next-action", SettingKind::Switch),
+    setting("today.per-context", SettingKind::Number),
+    setting("status.segments", SettingKind::List),
+    setting("next.old-after", SettingKind::Text),
+    setting("next.stuck-after", SettingKind::Text),
+    setting("waiting.follow-up-after", SettingKind::Text),
+    setting("waiting.follow-up-context", SettingKind::Text),
+    setting("complete.chain", SettingKind::Switch),
+    setting("hooks.on-capture", SettingKind::Text),
+    setting("hooks.on-complete-project", SettingKind::Text),
+    setting("hooks.post-fix", SettingKind::Text),
+    setting("git.auto-commit", SettingKind::Switch),
+    setting("contexts.layout", SettingKind::Text),
+    setting("contexts.aliases.*", SettingKind::Text),
+    setting("contexts.capacity.*", SettingKind::Number),
+    setting("contexts.order", SettingKind::Text),
+    setting("single-file.path", SettingKind::Text),
+    setting("headings.project-sections", SettingKind::Number),
+    setting("headings.action-statuses", SettingKind::Number),
+    setting("headings.action-list-contexts", SettingKind::Number),
+    setting("locale.language", SettingKind::Text),
+    setting("locale.date-format", SettingKind::Text),
+    setting("locale.first-day-of-week", SettingKind::Text),
+    setting("publish.note-url", SettingKind::Text),
+    setting("progress.show", SettingKind::Switch),
+];
+
+impl Setting {
+    /// The setting at `key`, like `["next", "old-after"]`, if there is one.
+    pub fn find(key: &[String]) -> Option<&'static Self> {
+        SETTINGS
+            .iter()
+            .find(|setting| setting.depth() == key.len() && setting.is_under(key))
+    }
+
+    /// Whether `key` is a table of settings, like `next` or `contexts.aliases`.
+    pub fn is_table(key: &[String]) -> bool {
+        SETTINGS
+            .iter()
+            .any(|setting| setting.depth() > key.len() && setting.is_under(key))
+    }
+
+    /// How many parts the setting's key has.
+    fn depth(&self) -> usize {
+        self.key.split('.').count()
+    }
+
+    /// Whether the setting's key starts with `key`, or is `key`.
+    fn is_under(&self, key: &[String]) -> bool {
+        self.key
+            .split('.')
+            .zip(key)
+            .all(|(part, k)| part == "*" || part == k)
+    }
+
+    /// `value` as this kind of setting takes it: text stays text even if it looks like a number,
+    /// and a single value for a list is a list of one.
+    pub fn coerce(&self, text: &str) -> Value {
+        let value = layers::parse_value(text);
+        match (self.kind, value) {
+            (SettingKind::Text, value @ Value::String(_)) => value,
+            (SettingKind::Text, _) => Value::String(text.to_string()),
+            (SettingKind::List, value @ Value::Array(_)) => value,
+            (SettingKind::List, value) => Value::Array(vec![value]),
+            (_, value) => value,
+        }
+    }
+}
+
+/// Sets the setting at `key` to the value written as `text` in the config file at `path`, which is
+/// created if it doesn't exist, returning the value it was set to.
+pub fn set_in_file(path: &Path, key: &[String], text: &str) -> Result<Value, ConfigError> {
+    let setting =
+        Setting::find(key).ok_or_else(|| ConfigError::UnknownSetting(layers::dotted(key)))?;
+    let value = setting.coerce(text);
+    let old = match fs::read_to_string(path) {
+        Ok(old) => old,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let new = set_setting(&old, key, &value)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, new)?;
+    Ok(value)
+}
+
+/// Rewrites the config `text` so that the setting at `key` is `value`, replacing the line that sets
+/// it or adding one to its table, which is added if there isn't one.
+///
+/// The key has to be a known setting, and the config has to be valid afterwards.
+pub fn set_setting(text: &str, key: &[String], value: &Value) -> Result<String, ConfigError> {
+    let old = toml::from_str::<toml::value::Table>(text)?;
+    let dotted = layers::dotted(key);
+    let setting = Setting::find(key).ok_or_else(|| ConfigError::UnknownSetting(dotted.clone()))?;
+    if setting.kind == SettingKind::Tables {
+        return Err(ConfigError::NotSettable(dotted));
+    }
+    let (name, table) = key
+        .split_last()
+        .ok_or(ConfigError::UnknownSetting(dotted.clone()))?;
+    let header = format!("[{}]", layers::dotted(table));
+    let name = std::slice::from_ref(name);
+    let entry = format!("{} = {}", layers::dotted(name), value);
+
+    let mut lines = text.lines().map(String::from).collect::<Vec<_>>();
+    match lines.iter().position(|l| l.trim() == header) {
+        Some(i) => {
+            let start = i + 1;
+            let end = lines[start..]
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .map_or(lines.len(), |i| start + i);
+            let existing = lines[start..end].iter().position(|l| {
+                l.split_once('=')
+                    .and_then(|(k, _)| layers::parse_key(k.trim()))
+                    .is_some_and(|k| k == name)
+            });
+            match existing {
+                Some(i) => lines[start + i] = entry,
+                None => {
+                    let last = lines[start..end]
+                        .iter()
+                        .rposition(|l| !l.trim().is_empty())
+                        .map_or(start, |i| start + i + 1);
+                    lines.insert(last, entry);
+                }
+            }
+        }
+        // The table is there, just not under a header of its own.
+        None if layers::lookup(&old, table).is_some() => {
+            return Err(ConfigError::NotSettable(dotted));
+        }
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.push(entry);
+        }
+    }
+    let text = lines.join("\n") + "\n";
+
+    // The setting could also be written as an inline table or a dotted key under its table's
+    // parent, which aren't rewritten, so check that it was really set.
+    let table = toml::from_str::<toml::value::Table>(&text).ok();
+    if table.as_ref().and_then(|t| layers::lookup(t, key)) != Some(value) {
+        return Err(ConfigError::NotSettable(dotted));
+    }
+    Config::parse(&text)?;
+    Ok(text)
+}
+
+fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s)
+}
+
+/// The tags that projects are allowed or expected to have.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TagConfig {
+    /// Tags that projects may have, along with any tags nested under them. If empty, all tags are
+    /// allowed.
+    pub allowed: Vec<Tag>,
+
+    /// Tags that projects must have exactly one tag nested under.
+    pub required: Vec<Tag>,
+}
+
+impl TagConfig {
+    pub fn is_allowed(&self, tag: &Tag) -> bool {
+        self.allowed.is_empty()
+            || self.allowed.iter().any(|a| tag.is_under(a))
+            || self.required.iter().any(|r| tag.is_under(r))
+    }
+}
+
+/// How documents are opened by `gtd open`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct OpenConfig {
+    pub method: OpenMethod,
+    /// Where `vault` in `[obsidian]` used to be, which is still read if that isn't set.
+    pub vault: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OpenMethod {
+    /// Open documents in `$EDITOR`.
+    #[default]
+    Editor,
+    /// Open documents in Obsidian using its URI scheme.
+    Obsidian,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ObsidianConfig {
+    /// The name of the Obsidian vault, if it shouldn't be detected automatically.
+    pub vault: Option<String>,
+}
+
+/// How links to documents are shown in command output.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LinkConfig {
+    pub style: LinkStyle,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkStyle {
+    /// Don't link to documents.
+    #[default]
+    Plain,
+    /// Show an `obsidian://` URI after the document's name.
+    Uri,
+    /// Turn the document's name into a terminal hyperlink.
+    Hyperlink,
+}
+
+/// Settings for `gtd pomodoro`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PomodoroConfig {
+    /// The length of a pomodoro in minutes.
+    pub minutes: u64,
+
+    /// The number of pomodoros on an action after which `gtd pomodoro` offers to complete it.
+    pub sessions: usize,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            minutes: 25,
+            sessions: 4,
+        }
+    }
+}
+
+/// The calendar of fixed appointments that actions are planned around.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CalendarConfig {
+    /// An `.ics` file, relative to the GTD folder, or an `http://` or `https://` URL of one.
+    pub source: Option<String>,
+
+    /// The number of hours of events after which a day counts as fully booked.
+    pub busy_hours: u32,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            source: None,
+            busy_hours: 6,
+        }
+    }
+}
+
+/// The CalDAV calendar that `gtd sync` syncs actions with.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CalDavConfig {
+    /// The URL of the calendar collection that tasks are stored in.
+    pub url: Option<String>,
+
+    pub username: Option<String>,
+
+    /// A shell command that prints the password, so it doesn't have to be stored in the config.
+    pub password_command: Option<String>,
+}
+
+/// The issue trackers that `gtd issues sync` looks up issues in.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct IssuesConfig {
+    /// The URL of the GitHub API, which is only different for GitHub Enterprise.
+    pub github_url: String,
+
+    /// A shell command that prints a GitHub token, for private repositories.
+    pub github_token_command: Option<String>,
+
+    /// The URL of the Jira site, like `https://example.atlassian.net`.
+    pub jira_url: Option<String>,
+
+    pub jira_username: Option<String>,
+
+    /// A shell command that prints the Jira API token, so it doesn't have to be stored in the
+    /// config.
+    pub jira_token_command: Option<String>,
+}
+
+impl Default for IssuesConfig {
+    fn default() -> Self {
+        Self {
+            github_url: String::from("https://api.github.com"),
+            github_token_command: None,
+            jira_url: None,
+            jira_username: None,
+            jira_token_command: None,
+        }
+    }
+}
+
+/// Where images and other files that documents link to are kept.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct AttachmentsConfig {
+    /// The folder attachments are kept in, relative to the GTD folder.
+    pub folder: PathBuf,
+}
+
+impl Default for AttachmentsConfig {
+    fn default() -> Self {
+        Self {
+            folder: PathBuf::from("Attachments"),
+        }
+    }
+}
+
+/// Settings for `gtd validate`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ValidateConfig {
+    /// The IDs of rules to skip, like `GTD012`.
+    pub disabled: Vec<String>,
+
+    /// The IDs of opt-in rules to check, like `GTD017`.
+    pub enabled: Vec<String>,
+
+    /// How long an in-progress project with upcoming actions can go without an active action.
+    pub stalled_after: Period,
+
+    /// How long a complete project can stay in the projects folder before it should be archived.
+    pub archive_after: Period,
+
+    /// Sections that projects must have.
+    pub required_sections: Vec<RequiredSections>,
+
+    /// Whether commands that change documents check them again afterwards, warning about problems
+    /// they introduced.
+    pub check_changes: bool,
+
+    /// Whether to ask for a next action when a command leaves an in-progress project without one.
+    pub ask_for_next_action: bool,
+}
+
+impl Default for ValidateConfig {
+    fn default() -> Self {
+        Self {
+            disabled: Vec::new(),
+            enabled: Vec::new(),
+            stalled_after: Period::Weeks(1),
+            archive_after: Period::Weeks(2),
+            required_sections: Vec::new(),
+            check_changes: true,
+            ask_for_next_action: false,
+        }
+    }
+}
+
+impl ValidateConfig {
+    /// The sections that `project` is required to have but doesn't, in the order they go in.
+    pub fn missing_sections(&self, project: &Project) -> Vec<Section> {
+        [Section::Goal, Section::Info]
+            .iter()
+            .copied()
+            .filter(|s| !project.has_section(*s))
+            .filter(|s| {
+                self.required_sections
+                    .iter()
+                    .any(|r| r.sections.contains(s) && r.applies_to(project))
+            })
+            .collect()
+    }
+}
+
+/// Sections that projects with a tag, or all projects, must have.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RequiredSections {
+    /// The tag of the projects that need the sections, which also covers tags nested under it. If
+    /// it's not given, every project needs them.
+    #[serde(default)]
+    pub tag: Option<Tag>,
+    pub sections: Vec<Section>,
+}
+
+impl RequiredSections {
+    pub fn applies_to(&self, project: &Project) -> bool {
+        self.tag.as_ref().is_none_or(|t| project.has_tag(t))
+    }
+}
+
+/// How dates and messages are shown.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LocaleConfig {
+    /// The language that messages are shown in, like `de`. If it isn't set, it's taken from the
+    /// `LC_ALL`, `LC_MESSAGES`, or `LANG` environment variables.
+    pub language: Option<String>,
+    /// How dates are shown in reports. Dates written to documents are always in ISO format, since
+    /// that's what's read back.
+    pub date_format: DateFormat,
+    /// The day that weeks start on in weekly reports.
+    pub first_day_of_week: Weekday,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            language: None,
+            date_format: DateFormat::default(),
+            first_day_of_week: Weekday::Mon,
+        }
+    }
+}
+
+/// How dates are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DateFormat {
+    /// Like `2024-01-05`.
+    #[default]
+    Iso,
+    /// The usual format for the language, like `Jan 5, 2024` in English or `05.01.2024` in
+    /// German.
+    Local,
+}
+
+/// Settings for `gtd publish`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PublishConfig {
+    /// Where links to notes that aren't projects or contexts point, with `{note}` replaced by the
+    /// note's name. They're left as text if this isn't set.
+    pub note_url: Option<String>,
+}
+
+/// Showing progress while loading and checking large GTD folders.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ProgressConfig {
+    /// Whether progress is shown in a terminal. It's never shown when stderr isn't one.
+    pub show: bool,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self { show: true }
+    }
+}
+
+/// Settings for `gtd today`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TodayConfig {
+    /// The most next actions to list from each context.
+    pub per_context: usize,
+}
+
+impl Default for TodayConfig {
+    fn default() -> Self {
+        Self { per_context: 3 }
+    }
+}
+
+/// Settings for `gtd next`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NextConfig {
+    /// How long an action can be active before it's flagged as getting old.
+    pub old_after: Period,
+
+    /// How long an action can be active before it's flagged as stuck, and should probably be
+    /// renegotiated.
+    pub stuck_after: Period,
+}
+
+impl Default for NextConfig {
+    fn default() -> Self {
+        Self {
+            old_after: Period::Weeks(2),
+            stuck_after: Period::Months(1),
+        }
+    }
+}
+
+/// Settings for following up on waiting actions in `gtd review --waiting`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct WaitingConfig {
+    /// How long an action can be waiting before it's time to follow up on it.
+    pub follow_up_after: Period,
+
+    /// The context that follow-up actions go in unless another one is picked, like `@computer`.
+    pub follow_up_context: Option<String>,
+}
+
+impl Default for WaitingConfig {
+    fn default() -> Self {
+        Self {
+            follow_up_after: Period::Weeks(1),
+            follow_up_context: None,
+        }
+    }
+}
+
+/// Settings for `gtd complete-action`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CompleteConfig {
+    /// Whether completing the last active action of an in-progress project promotes its first
+    /// upcoming action, as if `--chain` were given.
+    pub chain: bool,
+}
+
+/// Shell commands to run when things happen, which get the details as JSON on stdin.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HooksConfig {
+    /// Run after something is captured to the inbox.
+    pub on_capture: Option<String>,
+
+    /// Run after `gtd complete-project` completes a project.
+    pub on_complete_project: Option<String>,
+
+    /// Run after `gtd validate --fix` fixes anything.
+    pub post_fix: Option<String>,
+}
+
+/// Committing the changes gtd makes, when the GTD folder is in a git repository.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct GitConfig {
+    /// Whether commands that change files commit them.
+    pub auto_commit: bool,
+}
+
+/// Keeping everything in one document instead of a folder of them.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SingleFileConfig {
+    /// The document, relative to the GTD folder. If set, projects and contexts are read from its
+    /// sections instead of from `Projects` and `Contexts`.
+    pub path: Option<PathBuf>,
+}
+
+/// How contexts are kept, and other names for them.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ContextConfig {
+    /// Aliases like `@pc`, mapped to the context they stand for, like `@computer`.
+    pub aliases: BTreeMap<String, String>,
+    /// Where contexts are read from.
+    pub layout: ContextLayout,
+    /// The most actions that contexts like `@calls` should have at once, keyed by context or alias.
+    pub capacity: BTreeMap<String, usize>,
+    /// The order that the actions in each context are kept in.
+    pub order: ContextOrder,
+}
+
+/// Where contexts are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContextLayout {
+    /// A file for each context in `Contexts`.
+    #[default]
+    Files,
+    /// A section for each context in a single `Next Actions.md`.
+    List,
+}
+
+impl fmt::Display for ContextLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Files => write!(f, "files"),
+            Self::List => write!(f, "list"),
+        }
+    }
+}
+
+impl FromStr for ContextLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "files" => Ok(Self::Files),
+            "list" => Ok(Self::List),
+            _ => Err(format!(
+                "\"{}\" is not a context layout, like \"files\" or \"list\"",
+                s
+            )),
+        }
+    }
+}
+
+/// The order that the actions in a context are kept in. Actions that are tied keep the order they
+/// were written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContextOrder {
+    /// Whatever order they're written in.
+    #[default]
+    Manual,
+    /// Highest priority first.
+    Priority,
+    /// By the title of their project, with text actions last.
+    Project,
+    /// The ones that have been active longest first, with ones it isn't known for last.
+    Age,
+}
+
+impl fmt::Display for ContextOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Manual => write!(f, "manual"),
+            Self::Priority => write!(f, "priority"),
+            Self::Project => write!(f, "project"),
+            Self::Age => write!(f, "age"),
+        }
+    }
+}
+
+impl ContextConfig {
+    /// The name that `name` is an alias of, or `name` itself if it isn't an alias.
+    pub fn canonical(&self, name: &ContextName) -> ContextName {
+        match self.aliases.get(name.as_str()) {
+            Some(canonical) => ContextName::new(canonical.clone()),
+            None => name.clone(),
+        }
+    }
+
+    /// The most actions that the context `name` should have at once, if it has a limit. A limit
+    /// set on an alias applies to the context it stands for, and the other way around.
+    pub fn capacity(&self, name: &ContextName) -> Option<usize> {
+        let canonical = self.canonical(name);
+        self.capacity
+            .iter()
+            .find(|(key, _)| self.canonical(&ContextName::new((*key).clone())) == canonical)
+            .map(|(_, max)| *max)
+    }
+}
+
+/// Settings for `gtd status`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct StatusConfig {
+    /// The counts shown by `gtd status --oneline`, in order.
+    pub segments: Vec<Segment>,
+}
+
+impl Default for StatusConfig {
+    fn default() -> Self {
+        Self {
+            segments: vec![
+                Segment::Next,
+                Segment::Overdue,
+                Segment::Waiting,
+                Segment::Inbox,
+            ],
+        }
+    }
+}
+
+/// A count shown by `gtd status --oneline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Segment {
+    /// Next actions in all contexts.
+    Next,
+    /// Unfinished actions that are past due.
+    Overdue,
+    /// Actions waiting on someone else.
+    Waiting,
+    /// Items in the inbox.
+    Inbox,
+    /// In-progress projects.
+    InProgress,
+    /// Someday projects.
+    Someday,
+}
+
+/// The representation of a `NameScheme` in the config file.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct RawNameScheme {
+    pattern: String,
+    id_format: String,
+    template: String,
+}
+
+impl Default for RawNameScheme {
+    fn default() -> Self {
+        Self {
+            pattern: String::from(DEFAULT_NAME_PATTERN),
+            id_format: String::from(DEFAULT_ID_FORMAT),
+            template: String::from(DEFAULT_NAME_TEMPLATE),
+        }
+    }
+}
+
+impl TryFrom<RawNameScheme> for NameScheme {
+    type Error = NameSchemeError;
+
+    fn try_from(raw: RawNameScheme) -> Result<Self, Self::Error> {
+        Self::new(&raw.pattern, &raw.id_format, &raw.template)
+    }
+}
+
+impl<'de> Deserialize<'de> for NameScheme {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawNameScheme::deserialize(deserializer)?;
+        Self::try_from(raw).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The representation of `HeadingDepths` in the config file.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct RawHeadingDepths {
+    project_sections: u32,
+    action_statuses: u32,
+    action_list_contexts: u32,
+}
+
+impl Default for RawHeadingDepths {
+    fn default() -> Self {
+        let depths = HeadingDepths::default();
+        Self {
+            project_sections: depths.project_sections,
+            action_statuses: depths.action_statuses,
+            action_list_contexts: depths.action_list_contexts,
+        }
+    }
+}
+
+impl TryFrom<RawHeadingDepths> for HeadingDepths {
+    type Error = HeadingDepthsError;
+
+    fn try_from(raw: RawHeadingDepths) -> Result<Self, Self::Error> {
+        Self::new(
+            raw.project_sections,
+            raw.action_statuses,
+            raw.action_list_contexts,
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for HeadingDepths {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawHeadingDepths::deserialize(deserializer)?;
+        Self::try_from(raw).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    IoError(IoError),
+    TomlError(toml::de::Error),
+    /// A setting given as a flag that isn't like `key=value`.
+    InvalidSetting(String),
+    /// A key that isn't a setting.
+    UnknownSetting(String),
+    /// A setting that has to be changed in the config file by hand.
+    NotSettable(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::TomlError(e) => write!(f, "invalid config: {}", e),
+            Self::InvalidSetting(setting) => {
+                write!(f, "\"{}\" isn't a setting like next.old-after=1w", setting)
+            }
+            Self::UnknownSetting(key) => write!(f, "there's no setting \"{}\"", key),
+            Self::NotSettable(key) => {
+                write!(
+                    f,
+                    "\"{}\" has to be changed in the config file by hand",
+                    key
+                )
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<IoError> for ConfigError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::TomlError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(s: &str) -> Tag {
+        Tag::new(s).unwrap()
+    }
+
+    #[test]
+    fn invalid_tag_is_err() {
+        let text = "[tags]\nallowed = [\"area//work\"]\n";
+        assert!(Config::parse(text).is_err());
+    }
+
+    #[test]
+    fn empty_config_uses_default_scheme() {
+        let config = Config::parse("").unwrap();
+        assert!(config.project_names.format("197001010000", "Foo").is_some());
+    }
+
+    #[test]
+    fn custom_scheme_is_parsed() {
+        let text = r#"
+            [project-names]
+            pattern = '^(?P<id>\d{14}) (?P<title>.+)$'
+            id-format = "%Y%m%d%H%M%S"
+        "#;
+        let config = Config::parse(text).unwrap();
+        assert!(config
+            .project_names
+            .format("19700101000000", "Foo")
+            .is_some());
+        assert!(config.project_names.format("197001010000", "Foo").is_none());
+    }
+
+    #[test]
+    fn heading_depths_are_parsed() {
+        let config = Config::parse("[headings]\naction-statuses = 2\n").unwrap();
+        assert_eq!(config.headings, HeadingDepths::new(2, 2, 2).unwrap());
+        assert!(Config::parse("[headings]\nproject-sections = 4\n").is_err());
+    }
+
+    #[test]
+    fn tags_are_parsed() {
+        let text = "[tags]\nallowed = [\"someday\"]\nrequired = [\"area\"]\n";
+        let config = Config::parse(text).unwrap();
+        assert_eq!(config.tags.allowed, vec![tag("someday")]);
+        assert_eq!(config.tags.required, vec![tag("area")]);
+    }
+
+    #[test]
+    fn nested_tag_is_allowed() {
+        let tags = TagConfig {
+            allowed: vec![tag("area")],
+            required: vec![],
+        };
+        assert!(tags.is_allowed(&tag("area/work")));
+    }
+
+    #[test]
+    fn tag_with_same_prefix_is_not_allowed() {
+        let tags = TagConfig {
+            allowed: vec![tag("area")],
+            required: vec![],
+        };
+        assert!(!tags.is_allowed(&tag("areas")));
+    }
+
+    #[test]
+    fn required_tag_is_allowed() {
+        let tags = TagConfig {
+            allowed: vec![tag("other")],
+            required: vec![tag("area")],
+        };
+        assert!(tags.is_allowed(&tag("area/work")));
+    }
+
+    #[test]
+    fn open_method_is_parsed() {
+        let text = "[open]\nmethod = \"obsidian\"\n[obsidian]\nvault = \"Notes\"\n";
+        let config = Config::parse(text).unwrap();
+        assert_eq!(config.open.method, OpenMethod::Obsidian);
+        assert_eq!(config.obsidian.vault, Some(String::from("Notes")));
+    }
+
+    #[test]
+    fn vault_is_read_from_open_too() {
+        let config = Config::parse("[open]\nvault = \"Notes\"\n").unwrap();
+        assert_eq!(config.obsidian.vault, Some(String::from("Notes")));
+
+        let text = "[open]\nvault = \"Old\"\n[obsidian]\nvault = \"Notes\"\n";
+        let config = Config::parse(text).unwrap();
+        assert_eq!(config.obsidian.vault, Some(String::from("Notes")));
+    }
+
+    #[test]
+    fn required_sections_depend_on_tags() {
+        let config = Config::parse(
+            "[[validate.required-sections]]\ntag = \"work\"\nsections = [\"Goal\"]\n\n[[validate.required-sections]]\nsections = [\"Info\"]\n",
+        )
+        .unwrap();
+        let project = |tags: &str| {
+            Project::parse(
+                "197001010000 Plan trip",
+                &format!("# Plan trip\n#in-progress {}\n", tags),
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            config.validate.missing_sections(&project("#work/clients")),
+            vec![Section::Goal, Section::Info]
+        );
+        assert_eq!(
+            config.validate.missing_sections(&project("#home")),
+            vec![Section::Info]
+        );
+        assert!(Config::default()
+            .validate
+            .missing_sections(&project(""))
+            .is_empty());
+    }
+
+    #[test]
+    fn context_aliases_are_resolved() {
+        let config = Config::parse("[contexts.aliases]\n\"@pc\" = \"@computer\"\n").unwrap();
+        let canonical = |name: &str| {
+            config
+                .contexts
+                .canonical(&ContextName::new(name.to_string()))
+                .to_string()
+        };
+        assert_eq!(canonical("@pc"), "@computer");
+        assert_eq!(canonical("@computer"), "@computer");
+        assert_eq!(canonical("@home"), "@home");
+    }
+
+    #[test]
+    fn context_capacity_applies_through_aliases() {
+        let text = "[contexts.aliases]\n\"@phone\" = \"@calls\"\n\n[contexts.capacity]\n\"@phone\" = 10\n\"@home\" = 3\n";
+        let config = Config::parse(text).unwrap();
+        let capacity = |name: &str| {
+            config
+                .contexts
+                .capacity(&ContextName::new(name.to_string()))
+        };
+        assert_eq!(capacity("@calls"), Some(10));
+        assert_eq!(capacity("@phone"), Some(10));
+        assert_eq!(capacity("@home"), Some(3));
+        assert_eq!(capacity("@errands"), None);
+    }
+
+    #[test]
+    fn settings_are_set_in_their_tables() {
+        let key = |k: &str| layers::parse_key(k).unwrap();
+        let set = |text: &str, k: &str, value: &str| {
+            let setting = Setting::find(&key(k)).unwrap();
+            set_setting(text, &key(k), &setting.coerce(value))
+        };
+        let text = "# Shared settings.\n[next]\nold-after = \"2w\" # for now\n\n[git]\nauto-commit = true\n";
+        assert_eq!(
+            set(text, "next.old-after", "1w").unwrap(),
+            "# Shared settings.\n[next]\nold-after = \"1w\"\n\n[git]\nauto-commit = true\n"
+        );
+        assert_eq!(
+            set(text, "next.stuck-after", "6w").unwrap(),
+            "# Shared settings.\n[next]\nold-after = \"2w\" # for now\nstuck-after = \"6w\"\n\n[git]\nauto-commit = true\n"
+        );
+        assert_eq!(
+            set("", "contexts.capacity.\"@calls\"", "10").unwrap(),
+            "[contexts.capacity]\n\"@calls\" = 10\n"
+        );
+        assert_eq!(
+            set("", "validate.disabled", "GTD012").unwrap(),
+            "[validate]\ndisabled = [\"GTD012\"]\n"
+        );
+        assert_eq!(
+            set("", "obsidian.vault", "2024").unwrap(),
+            "[obsidian]\nvault = \"2024\"\n"
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_not_set() {
+        let key = |k: &str| layers::parse_key(k).unwrap();
+        let value = layers::parse_value;
+        assert!(matches!(
+            set_setting("", &key("next.too-old"), &value("1w")),
+            Err(ConfigError::UnknownSetting(_))
+        ));
+        assert!(matches!(
+            set_setting("", &key("next.old-after"), &value("soon")),
+            Err(ConfigError::TomlError(_))
+        ));
+        assert!(matches!(
+            set_setting("", &key("pomodoro.minutes"), &value("long")),
+            Err(ConfigError::TomlError(_))
+        ));
+        assert!(matches!(
+            set_setting("", &key("validate.required-sections"), &value("[]")),
+            Err(ConfigError::NotSettable(_))
+        ));
+        assert!(matches!(
+            set_setting(
+                "next = { old-after = \"2w\" }\n",
+                &key("next.old-after"),
+                &value("1w")
+            ),
+            Err(ConfigError::NotSettable(_))
+        ));
+        assert!(Setting::is_table(&key("contexts.aliases")));
+        assert!(!Setting::is_table(&key("next.old-after")));
+    }
+
+    #[test]
+    fn context_alias_is_set() {
+        let text = "[tags]\nrequired = [\"area\"]\n\n[contexts.aliases]\n\"@laptop\" = \"@online\"\n\"@pc\" = \"@computer\"\n\n[next]\nold-after = \"1w\"\n";
+        assert_eq!(
+            set_context_alias(text, "@online", "@computer"),
+            "[tags]\nrequired = [\"area\"]\n\n[contexts.aliases]\n\"@laptop\" = \"@computer\"\n\"@pc\" = \"@computer\"\n\"@online\" = \"@computer\"\n\n[next]\nold-after = \"1w\"\n"
+        );
+        assert_eq!(
+            set_context_alias("[tags]\nrequired = [\"area\"]\n", "@online", "@computer"),
+            "[tags]\nrequired = [\"area\"]\n\n[contexts.aliases]\n\"@online\" = \"@computer\"\n"
+        );
+        assert_eq!(
+            set_context_alias("", "@online", "@computer"),
+            "[contexts.aliases]\n\"@online\" = \"@computer\"\n"
+        );
+    }
+
+    #[test]
+    fn status_segments_are_parsed() {
+        let config = Config::parse("[status]\nsegments = [\"inbox\", \"in-progress\"]\n").unwrap();
+        assert_eq!(
+            config.status.segments,
+            vec![Segment::Inbox, Segment::InProgress]
+        );
+        assert_eq!(Config::default().status.segments.len(), 4);
+    }
+
+    #[test]
+    fn pomodoro_defaults_are_kept() {
+        let config = Config::parse("[pomodoro]\nminutes = 50\n").unwrap();
+        assert_eq!(config.pomodoro.minutes, 50);
+        assert_eq!(config.pomodoro.sessions, 4);
+    }
+
+    #[test]
+    fn calendar_is_parsed() {
+        let config = Config::parse("[calendar]\nsource = \"calendar.ics\"\n").unwrap();
+        assert_eq!(config.calendar.source.as_deref(), Some("calendar.ics"));
+        assert_eq!(config.calendar.busy_hours, 6);
+    }
+
+    #[test]
+    fn link_style_is_parsed() {
+        let config = Config::parse("[links]\nstyle = \"hyperlink\"\n").unwrap();
+        assert_eq!(config.links.style, LinkStyle::Hyperlink);
+    }
+
+    #[test]
+    fn scheme_without_title_is_err() {
+        let text = "[project-names]\npattern = '^(?P<id>\\d+)$'\n";
+        assert!(Config::parse(text).is_err());
+    }
+}
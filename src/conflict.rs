@@ -0,0 +1,396 @@
+//! Finding and resolving the conflict markers that git and sync tools leave in a file when two
+//! copies of it were changed at once.
+//!
+//! A conflict looks like this, with an optional `|||||||` section for the common ancestor that's
+//! ignored:
+//!
+//! ```text
+//! <<<<<<< HEAD
+//! - Book flights ^abcdef
+//! =======
+//! - Book flights ✅ 2024-01-05 ^abcdef
+//! >>>>>>> laptop
+//! ```
+
+use crate::{
+    edit,
+    gtd::Loader,
+    prompt,
+    undo::Undo,
+    walk::{self, Ignore},
+};
+use std::{
+    fmt, fs,
+    io::Error as IoError,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+const START_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR: &str = "=======";
+const END_MARKER: &str = ">>>>>>>";
+
+/// A conflict between two versions of part of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The bytes of the conflict in the file, from the start of its first marker to the end of its
+    /// last.
+    pub range: Range<usize>,
+    /// The lines the conflict is on, starting from 1.
+    pub lines: Range<usize>,
+    /// What the first version is called, like `HEAD`.
+    pub ours_label: String,
+    pub ours: String,
+    pub theirs_label: String,
+    pub theirs: String,
+}
+
+/// Finds the conflicts in `text`. Markers that don't make up a whole conflict are ignored.
+pub fn find(text: &str) -> Vec<Conflict> {
+    enum State {
+        Outside,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let mut conflicts = Vec::new();
+    let mut state = State::Outside;
+    let mut current = None::<Conflict>;
+    let mut offset = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let content = line.trim_end();
+        if let Some(label) = content.strip_prefix(START_MARKER) {
+            current = Some(Conflict {
+                range: offset..offset,
+                lines: i + 1..i + 1,
+                ours_label: label.trim().to_string(),
+                ours: String::new(),
+                theirs_label: String::new(),
+                theirs: String::new(),
+            });
+            state = State::Ours;
+        } else if let Some(conflict) = current.as_mut() {
+            match state {
+                State::Ours if content.starts_with(BASE_MARKER) => state = State::Base,
+                State::Ours | State::Base if content == SEPARATOR => state = State::Theirs,
+                State::Ours => conflict.ours.push_str(line),
+                State::Theirs if content.starts_with(END_MARKER) => {
+                    conflict.range.end = offset + line.len();
+                    conflict.lines.end = i + 2;
+                    conflict.theirs_label = content[END_MARKER.len()..].trim().to_string();
+                    conflicts.extend(current.take());
+                    state = State::Outside;
+                }
+                State::Theirs => conflict.theirs.push_str(line),
+                State::Base | State::Outside => {}
+            }
+        }
+        offset += line.len();
+    }
+    conflicts
+}
+
+/// A file with unresolved conflicts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictedFile {
+    pub path: PathBuf,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ConflictedFile {
+    /// The conflicts in `text`, the contents of the file at `path`, if it has any.
+    pub fn check(path: &Path, text: &str) -> Option<Self> {
+        let conflicts = find(text);
+        match conflicts.is_empty() {
+            true => None,
+            false => Some(Self {
+                path: path.to_owned(),
+                conflicts,
+            }),
+        }
+    }
+
+    /// The lines that the conflicts are on, like `lines 3-7 and 12-15`.
+    pub fn lines(&self) -> String {
+        let ranges = self
+            .conflicts
+            .iter()
+            .map(|c| format!("{}-{}", c.lines.start, c.lines.end - 1))
+            .collect::<Vec<_>>();
+        let ranges = match ranges.split_last() {
+            Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+            _ => ranges.join(""),
+        };
+        format!("lines {}", ranges)
+    }
+}
+
+impl fmt::Display for ConflictedFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} has unresolved conflicts on {}; run `gtd resolve` to fix them",
+            self.path.display(),
+            self.lines()
+        )
+    }
+}
+
+/// Something the user has to decide to resolve a conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Question<'a> {
+    /// Which version of a list item to keep, or of the whole conflict if it isn't a list.
+    Pick { ours: &'a str, theirs: &'a str },
+    /// Whether to keep a list item that's only in one version.
+    Keep { item: &'a str, is_ours: bool },
+}
+
+impl<'a> Question<'a> {
+    /// The answers to the question, which are picked by index.
+    pub fn choices(&self) -> &'static [&'static str] {
+        match self {
+            Self::Pick { .. } => &["ours", "theirs", "both"],
+            Self::Keep { .. } => &["yes", "no"],
+        }
+    }
+}
+
+/// Resolves `conflict` by asking `ask` each question, returning the text to replace it with, or
+/// `None` if `ask` stops answering.
+///
+/// If both versions are lists, they're resolved an item at a time. Items that are the same in both
+/// are kept, items with the same block ID are picked between, and items that are only in one
+/// version are kept or dropped. Otherwise, one version of the whole conflict is picked.
+pub fn resolve<F>(conflict: &Conflict, mut ask: F) -> Result<Option<String>, IoError>
+where
+    F: FnMut(&Question) -> Result<Option<usize>, IoError>,
+{
+    let (ours, theirs) = match (list_items(&conflict.ours), list_items(&conflict.theirs)) {
+        (Some(ours), Some(theirs)) => (ours, theirs),
+        _ => {
+            let question = Question::Pick {
+                ours: &conflict.ours,
+                theirs: &conflict.theirs,
+            };
+            return Ok(ask(&question)?.map(|answer| match answer {
+                0 => conflict.ours.clone(),
+                1 => conflict.theirs.clone(),
+                _ => format!("{}{}", conflict.ours, conflict.theirs),
+            }));
+        }
+    };
+
+    let mut kept = Vec::new();
+    let mut is_used = vec![false; theirs.len()];
+    for item in ours.iter() {
+        let same = (0..theirs.len()).find(|i| !is_used[*i] && theirs[*i] == *item);
+        let paired = same.or_else(|| {
+            let id = block_id(item)?;
+            (0..theirs.len()).find(|i| !is_used[*i] && block_id(theirs[*i]) == Some(id))
+        });
+        match paired {
+            Some(i) if theirs[i] == *item => {
+                is_used[i] = true;
+                kept.push(*item);
+            }
+            Some(i) => {
+                is_used[i] = true;
+                let question = Question::Pick {
+                    ours: item,
+                    theirs: theirs[i],
+                };
+                match ask(&question)? {
+                    Some(0) => kept.push(*item),
+                    Some(1) => kept.push(theirs[i]),
+                    Some(_) => kept.extend([*item, theirs[i]]),
+                    None => return Ok(None),
+                }
+            }
+            None => match ask(&Question::Keep {
+                item,
+                is_ours: true,
+            })? {
+                Some(0) => kept.push(*item),
+                Some(_) => {}
+                None => return Ok(None),
+            },
+        }
+    }
+    for (item, _) in theirs.iter().zip(is_used).filter(|(_, used)| !used) {
+        match ask(&Question::Keep {
+            item,
+            is_ours: false,
+        })? {
+            Some(0) => kept.push(*item),
+            Some(_) => {}
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(
+        kept.iter().map(|item| format!("{}\n", item)).collect(),
+    ))
+}
+
+/// Resolves every conflict in `text` with `resolve`, returning the resolved text, or `None` if
+/// `ask` stops answering.
+pub fn resolve_text<F>(text: &str, mut ask: F) -> Result<Option<String>, IoError>
+where
+    F: FnMut(&Conflict, &Question) -> Result<Option<usize>, IoError>,
+{
+    let mut resolved = String::with_capacity(text.len());
+    let mut last = 0;
+    for conflict in find(text) {
+        let replacement = match resolve(&conflict, |q| ask(&conflict, q))? {
+            Some(replacement) => replacement,
+            None => return Ok(None),
+        };
+        resolved.push_str(&text[last..conflict.range.start]);
+        resolved.push_str(&replacement);
+        last = conflict.range.end;
+    }
+    resolved.push_str(&text[last..]);
+    Ok(Some(resolved))
+}
+
+/// Walks the GTD folder of `loader` for files with conflicts and resolves them interactively.
+///
+/// Files are written once all of their conflicts are resolved, after being recorded so `gtd undo`
+/// can put them back. Quitting part way through a file leaves it as it was. Returns the number of
+/// files resolved.
+pub fn resolve_all(loader: &Loader) -> Result<usize, IoError> {
+    let root_dir = loader.root_dir();
+    let mut conflicted = Vec::new();
+    for path in walk::walk(root_dir, &Ignore::load(root_dir)?)?.included {
+        if path.extension().is_some_and(|e| e == "md") {
+            let text = fs::read_to_string(&path)?;
+            if let Some(file) = ConflictedFile::check(&path, &text) {
+                conflicted.push((file, text));
+            }
+        }
+    }
+    if conflicted.is_empty() {
+        println!("No files have conflicts.");
+        return Ok(0);
+    }
+
+    let mut changes = Vec::new();
+    for (file, text) in conflicted {
+        let relative = file.path.strip_prefix(root_dir).unwrap_or(&file.path);
+        println!("{} has conflicts on {}.", relative.display(), file.lines());
+        match resolve_text(&text, ask)? {
+            Some(resolved) => changes.push((file.path, resolved)),
+            None => break,
+        }
+        println!();
+    }
+    if changes.is_empty() {
+        return Ok(0);
+    }
+
+    let mut undo = Undo::new("resolve");
+    for (path, _) in changes.iter() {
+        undo.record(loader, path)?;
+    }
+    undo.save(loader)?;
+    for (path, text) in changes.iter() {
+        fs::write(path, text)?;
+    }
+    println!("Resolved {} files.", changes.len());
+    Ok(changes.len())
+}
+
+/// Shows `question` about `conflict` and asks it, returning `None` if the input ends.
+fn ask(conflict: &Conflict, question: &Question) -> Result<Option<usize>, IoError> {
+    let label = |label: &str, fallback: &'static str| match label.is_empty() {
+        true => fallback.to_string(),
+        false => format!("{} ({})", fallback, label),
+    };
+    let ours = label(&conflict.ours_label, "ours");
+    let theirs = label(&conflict.theirs_label, "theirs");
+    let prompt = match question {
+        Question::Pick { ours: a, theirs: b } => {
+            print!("{}:\n{}", ours, indent(a));
+            print!("{}:\n{}", theirs, indent(b));
+            "Keep"
+        }
+        Question::Keep { item, is_ours } => {
+            let side = if *is_ours { &ours } else { &theirs };
+            print!("Only in {}:\n{}", side, indent(item));
+            "Keep it?"
+        }
+    };
+    prompt::choose(prompt, question.choices())
+}
+
+fn indent(text: &str) -> String {
+    text.lines().map(|l| format!("    {}\n", l)).collect()
+}
+
+/// The list items in `text`, or `None` if it has anything other than list items and blank lines.
+fn list_items(text: &str) -> Option<Vec<&str>> {
+    let is_list = text.lines().all(|l| {
+        l.trim().is_empty() || edit::is_item_start(l) || l.starts_with(char::is_whitespace)
+    });
+    let items = edit::list_items(text);
+    match is_list && !items.is_empty() {
+        true => Some(items),
+        false => None,
+    }
+}
+
+/// The block ID at the end of the first line of `item`, like `^abcdef`.
+fn block_id(item: &str) -> Option<&str> {
+    let first = item.lines().next()?;
+    first
+        .split_whitespace()
+        .last()
+        .filter(|w| w.starts_with('^') && w.len() > 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "# @home\n\n- Water plants\n<<<<<<< HEAD\n- [ ] Call mom ^abcdef\n- Fix sink\n||||||| base\n- [ ] Call mom ^abcdef\n=======\n- [x] Call mom ✅ 2024-01-05 ^abcdef\n- Buy milk\n>>>>>>> laptop\n- Vacuum\n";
+
+    #[test]
+    fn conflicts_are_found() {
+        let conflicts = find(TEXT);
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!(conflict.lines, 4..13);
+        assert_eq!(conflict.ours_label, "HEAD");
+        assert_eq!(conflict.ours, "- [ ] Call mom ^abcdef\n- Fix sink\n");
+        assert_eq!(conflict.theirs_label, "laptop");
+        assert_eq!(
+            conflict.theirs,
+            "- [x] Call mom ✅ 2024-01-05 ^abcdef\n- Buy milk\n"
+        );
+        assert!(TEXT[conflict.range.clone()].starts_with("<<<<<<<"));
+        assert!(TEXT[conflict.range.clone()].ends_with("laptop\n"));
+        let file = ConflictedFile::check(Path::new("@home.md"), TEXT).unwrap();
+        assert_eq!(file.lines(), "lines 4-12");
+        assert!(find("- Unfinished\n<<<<<<< HEAD\n- Call mom\n").is_empty());
+    }
+
+    #[test]
+    fn list_items_are_resolved_one_at_a_time() {
+        let mut questions = Vec::new();
+        let resolved = resolve_text(TEXT, |_, question| {
+            questions.push(format!("{:?}", question));
+            Ok(Some(match question {
+                // Take the completed action, keep "Fix sink", and drop "Buy milk".
+                Question::Pick { .. } => 1,
+                Question::Keep { is_ours, .. } => usize::from(!is_ours),
+            }))
+        })
+        .unwrap();
+        assert_eq!(
+            resolved.unwrap(),
+            "# @home\n\n- Water plants\n- [x] Call mom ✅ 2024-01-05 ^abcdef\n- Fix sink\n- Vacuum\n"
+        );
+        assert_eq!(questions.len(), 3);
+    }
+}
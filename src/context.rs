@@ -1,45 +1,124 @@
 use crate::{
     markdown::{BlockRef, Fragment, Heading},
-    parser::{self, Doc},
-    project::ActionRef,
+    parser::{self, Doc, Parser},
+    project::{ActionRef, NameScheme},
 };
-use std::{error::Error, fmt};
+use pulldown_cmark::{Event, Tag};
+use std::{convert::Infallible, error::Error, fmt, ops::Range, str::FromStr};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Context {
     pub name: Name,
     pub title: Heading,
     actions: Vec<Action>,
+    groups: Vec<Group>,
+}
+
+/// A run of a context's actions under a `##` heading, like the project or tag they're grouped by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub heading: Heading,
+    actions: Range<usize>,
+}
+
+impl Group {
+    /// The text of the group's heading.
+    pub fn title(&self) -> String {
+        self.heading.to_string()
+    }
 }
 
 impl Context {
-    pub fn parse<S: Into<String>>(filename: S, text: &str) -> Result<Self, ParseError> {
+    pub fn parse<S: Into<String>>(filename: S, text: &str) -> Result<Self, ParseError<'_>> {
+        Self::parse_with_scheme(filename, text, NameScheme::default_ref())
+    }
+
+    pub fn parse_with_scheme<'a, S: Into<String>>(
+        filename: S,
+        text: &'a str,
+        scheme: &NameScheme,
+    ) -> Result<Self, ParseError<'a>> {
         let name = Name(filename.into());
 
         let Doc {
+            frontmatter: _frontmatter,
             title,
             tags: _tags,
             mut parser,
         } = Doc::parse(text)?;
+        Self::parse_section(name, title, &mut parser, scheme, 2)
+    }
 
-        let actions = parser
-            .parse_list()
-            .ok()
-            .unwrap_or_else(Vec::new)
-            .into_iter()
-            .map(Action::from_fragment)
-            .collect();
+    /// Parses a context titled `title` from the list after its title in `parser`, with groups under
+    /// headings of `group_level`. Stops at the first heading above that level, so that a context can
+    /// be one section of a bigger document.
+    pub fn parse_section<'a>(
+        name: Name,
+        title: Heading,
+        parser: &mut Parser<'a>,
+        scheme: &NameScheme,
+        group_level: u32,
+    ) -> Result<Self, ParseError<'a>> {
+        let parse_actions = |parser: &mut Parser<'a>| {
+            parser
+                .parse_list_opt()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|f| Action::from_fragment(f, scheme))
+                .collect::<Vec<_>>()
+        };
+
+        let mut actions = parse_actions(parser);
+        let mut groups = Vec::new();
+        while parser.peek() == Some(&Event::Start(Tag::Heading(group_level))) {
+            let heading = parser.parse_heading(group_level)?;
+            let start = actions.len();
+            actions.extend(parse_actions(parser));
+            groups.push(Group {
+                heading,
+                actions: start..actions.len(),
+            });
+        }
 
         Ok(Self {
             name,
             title,
             actions,
+            groups,
         })
     }
 
+    /// All of the context's actions, including the ones in groups.
     pub fn actions(&self) -> &[Action] {
         &self.actions[..]
     }
+
+    /// The actions that come before any `##` heading.
+    pub fn ungrouped_actions(&self) -> &[Action] {
+        let end = self
+            .groups
+            .first()
+            .map_or(self.actions.len(), |g| g.actions.start);
+        &self.actions[..end]
+    }
+
+    /// The groups of actions under `##` headings, in the order they're written.
+    pub fn groups(&self) -> &[Group] {
+        &self.groups[..]
+    }
+
+    /// Each action, along with the group it's in if it's in one.
+    pub fn grouped_actions(&self) -> impl Iterator<Item = (Option<&Group>, &Action)> {
+        self.actions.iter().enumerate().map(move |(i, action)| {
+            let group = self.groups.iter().find(|g| g.actions.contains(&i));
+            (group, action)
+        })
+    }
+
+    /// The actions under the heading of `group`, which has to be one of this context's groups.
+    pub fn group_actions(&self, group: &Group) -> &[Action] {
+        &self.actions[group.actions.clone()]
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -61,23 +140,42 @@ impl fmt::Display for Name {
     }
 }
 
+impl FromStr for Name {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_string()))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Action {
     Literal(Fragment),
     Reference(ActionRef),
+    /// A link to a block in a note that isn't a project, which can't be an action.
+    UnresolvedReference(BlockRef),
 }
 
 impl Action {
-    pub fn from_fragment(fragment: Fragment) -> Self {
+    pub fn from_fragment(fragment: Fragment, scheme: &NameScheme) -> Self {
         match BlockRef::from_fragment(&fragment) {
-            Some(block_ref) => Self::Reference(ActionRef::from_block_ref(block_ref).unwrap()),
+            Some(block_ref) => match ActionRef::from_block_ref(block_ref.clone(), scheme) {
+                Some(action_ref) => Self::Reference(action_ref),
+                None => Self::UnresolvedReference(block_ref),
+            },
             None => Self::Literal(fragment),
         }
     }
 
+    /// Parses the Markdown of a single list item, like `- Water plants`.
+    pub fn parse_item(text: &str, scheme: &NameScheme) -> Option<Self> {
+        let fragment = Parser::new(text).parse_list().ok()?.into_iter().next()?;
+        Some(Self::from_fragment(fragment, scheme))
+    }
+
     pub fn to_action_ref(&self) -> Option<&ActionRef> {
         match self {
-            Action::Literal(_) => None,
+            Action::Literal(_) | Action::UnresolvedReference(_) => None,
             Action::Reference(action_ref) => Some(action_ref),
         }
     }
@@ -117,7 +215,7 @@ mod tests {
     use super::*;
     use crate::markdown::BlockRef;
     use pulldown_cmark::Event;
-    use std::convert::TryInto;
+    use std::convert::TryFrom;
 
     #[test]
     fn title_parses() {
@@ -125,8 +223,7 @@ mod tests {
         let context = Context::parse("@computer", text).unwrap();
         assert_eq!(
             context.title,
-            Fragment::from_events(vec![Event::Text("@computer".into())])
-                .try_into()
+            Heading::try_from(Fragment::from_events(vec![Event::Text("@computer".into())]))
                 .unwrap()
         );
     }
@@ -140,17 +237,82 @@ mod tests {
             vec![
                 Action::Literal(Fragment::from_events(vec![Event::Text("foo".into())])),
                 Action::Reference(
-                    ActionRef::from_block_ref(BlockRef {
-                        link: String::from("197001010000 bar"),
-                        id: String::from("abcdef"),
-                        is_embedded: true,
-                    })
+                    ActionRef::from_block_ref(
+                        BlockRef {
+                            link: String::from("197001010000 bar"),
+                            id: String::from("abcdef"),
+                            is_embedded: true,
+                        },
+                        &NameScheme::default()
+                    )
                     .unwrap()
                 ),
             ]
         );
     }
 
+    #[test]
+    fn grouped_actions_parse() {
+        let text = "# @computer\n\n- foo\n\n## Plan trip\n\n- bar\n- baz\n\n## Empty\n\n## Do taxes\n\n- qux\n";
+        let context = Context::parse("@computer", text).unwrap();
+        let texts = |actions: &[Action]| {
+            actions
+                .iter()
+                .map(|a| match a {
+                    Action::Literal(f) => f.text(),
+                    Action::Reference(_) | Action::UnresolvedReference(_) => String::new(),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(context.actions()), ["foo", "bar", "baz", "qux"]);
+        assert_eq!(texts(context.ungrouped_actions()), ["foo"]);
+        let groups = context
+            .groups()
+            .iter()
+            .map(|g| {
+                (
+                    g.heading.try_to_text().unwrap(),
+                    texts(context.group_actions(g)),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            [
+                ("Plan trip", vec![String::from("bar"), String::from("baz")]),
+                ("Empty", vec![]),
+                ("Do taxes", vec![String::from("qux")]),
+            ]
+        );
+        let titles = context
+            .grouped_actions()
+            .map(|(g, _)| g.map(Group::title))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            [
+                None,
+                Some(String::from("Plan trip")),
+                Some(String::from("Plan trip")),
+                Some(String::from("Do taxes")),
+            ]
+        );
+    }
+
+    #[test]
+    fn link_to_a_block_in_a_note_is_unresolved() {
+        let text = "# @computer\n\n- ![[Packing list#^abcdef]]\n";
+        let context = Context::parse("@computer", text).unwrap();
+        assert_eq!(
+            context.actions(),
+            [Action::UnresolvedReference(BlockRef {
+                link: String::from("Packing list"),
+                id: String::from("abcdef"),
+                is_embedded: true,
+            })]
+        );
+    }
+
     #[test]
     fn context_without_actions_parses() {
         let text = "# @computer\n";
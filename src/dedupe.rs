@@ -0,0 +1,344 @@
+//! Finding projects that are copies of each other, like the duplicate notes that sync tools
+//! sometimes make, and cleaning them up.
+
+use crate::{
+    diff::{self, WriteMode},
+    edit,
+    gtd::Documents,
+    project::{ActionStatus, Project},
+    prompt,
+    trash::Trash,
+    undo::Undo,
+};
+use chrono::Local;
+use std::{
+    collections::HashSet,
+    fs,
+    io::{Error as IoError, ErrorKind},
+};
+
+/// How much of the actions of the project with fewer of them have to be in the other one for two
+/// projects to be near-duplicates.
+const MIN_SHARED_ACTIONS: f64 = 0.8;
+
+/// Projects that are copies of each other.
+#[derive(Debug, Clone)]
+pub struct Duplicates<'a> {
+    /// The projects, sorted by name, so the first is usually the original.
+    pub projects: Vec<&'a Project>,
+    /// Whether the projects all say exactly the same thing, rather than nearly.
+    pub is_identical: bool,
+}
+
+/// Finds the groups of projects in `docs` that have the same content hash, or near-duplicate titles
+/// and mostly the same actions.
+pub fn find(docs: &Documents) -> Vec<Duplicates<'_>> {
+    let projects = docs.projects().collect::<Vec<_>>();
+    let hashes = projects
+        .iter()
+        .map(|p| p.content_hash())
+        .collect::<Vec<_>>();
+
+    let mut is_grouped = vec![false; projects.len()];
+    let mut groups = Vec::new();
+    for i in 0..projects.len() {
+        if is_grouped[i] {
+            continue;
+        }
+        let mut group = Duplicates {
+            projects: vec![projects[i]],
+            is_identical: true,
+        };
+        for j in i + 1..projects.len() {
+            if is_grouped[j] {
+                continue;
+            }
+            let is_identical = hashes[i] == hashes[j];
+            if is_identical || is_near_copy(projects[i], projects[j]) {
+                is_grouped[j] = true;
+                group.projects.push(projects[j]);
+                group.is_identical &= is_identical;
+            }
+        }
+        if group.projects.len() > 1 {
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+/// Whether `a` and `b` have near-duplicate titles, and one has most of the other's actions.
+fn is_near_copy(a: &Project, b: &Project) -> bool {
+    if !is_near_duplicate(&a.title().to_lowercase(), &b.title().to_lowercase()) {
+        return false;
+    }
+    let hashes = |p: &Project| {
+        p.actions
+            .actions()
+            .map(|(a, _)| a.content_hash())
+            .collect::<HashSet<_>>()
+    };
+    let (a, b) = (hashes(a), hashes(b));
+    match a.len().min(b.len()) {
+        0 => a.is_empty() && b.is_empty(),
+        fewer => a.intersection(&b).count() as f64 / fewer as f64 >= MIN_SHARED_ACTIONS,
+    }
+}
+
+/// Whether `a` and `b` are the same text, or close enough that one is likely a typo of the other.
+pub fn is_near_duplicate(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let (a, b) = (a.chars().collect::<Vec<_>>(), b.chars().collect::<Vec<_>>());
+    let allowed = a.len().max(b.len()) / 10;
+    if allowed == 0 || a.len().abs_diff(b.len()) > allowed {
+        return false;
+    }
+    edit_distance(&a, &b) <= allowed
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// What to do with the copies of a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cleanup {
+    /// Move the actions the first project doesn't have into it, then delete the others.
+    Merge,
+    /// Delete all but the first project.
+    Delete,
+}
+
+/// Asks what to do with each group of copies in `docs`, then cleans them up with `clean_up`.
+/// Returns the number of groups that were cleaned up.
+pub fn assist(docs: &Documents, mode: WriteMode) -> Result<usize, IoError> {
+    let groups = find(docs);
+    if groups.is_empty() {
+        println!("No projects are duplicated.");
+        return Ok(0);
+    }
+
+    let mut choices = Vec::new();
+    for duplicates in groups.iter() {
+        let how = match duplicates.is_identical {
+            true => "the same",
+            false => "nearly the same",
+        };
+        println!("These projects have {} contents:", how);
+        for project in duplicates.projects.iter() {
+            println!(
+                "- {} ({}, {} actions)",
+                project.name,
+                project.status,
+                project.actions.actions().count()
+            );
+        }
+        let question = format!("Keep {} and", duplicates.projects[0].name);
+        match prompt::choose(&question, &["merge", "delete", "skip"])? {
+            Some(0) => choices.push((duplicates, Cleanup::Merge)),
+            Some(1) => choices.push((duplicates, Cleanup::Delete)),
+            Some(_) => {}
+            None => break,
+        }
+        println!();
+    }
+    clean_up(docs, &choices, mode)
+}
+
+/// Keeps the first project in each group of duplicates and moves the rest to the trash, first
+/// merging their actions into it for `Cleanup::Merge`. Merged actions keep their status, and ones
+/// the first project already has are left out.
+///
+/// Each change is shown as a word diff and written according to `mode`. Before anything is
+/// written, the files are recorded so `gtd undo` can put them back. Returns the number of groups
+/// that were cleaned up.
+pub fn clean_up(
+    docs: &Documents,
+    choices: &[(&Duplicates, Cleanup)],
+    mode: WriteMode,
+) -> Result<usize, IoError> {
+    let loader = docs.loader();
+    loader.ensure_files()?;
+
+    let mut changes = Vec::new();
+    for (duplicates, cleanup) in choices {
+        let (kept, copies) = match duplicates.projects.split_first() {
+            Some((kept, copies)) if !copies.is_empty() => (*kept, copies),
+            _ => continue,
+        };
+        let kept_path = loader.project_path(&kept.name);
+        let old_text = fs::read_to_string(&kept_path)?;
+        let text = match cleanup {
+            Cleanup::Merge => merge(docs, kept, copies, &old_text)?,
+            Cleanup::Delete => old_text.clone(),
+        };
+
+        let is_written = match text != old_text {
+            true => diff::review(kept.name.as_str(), &old_text, &text, mode)?,
+            false => mode != WriteMode::DryRun,
+        };
+        for copy in copies {
+            println!("{}:\n(deleted)", copy.name);
+        }
+        if is_written {
+            let deleted = copies
+                .iter()
+                .map(|c| loader.project_path(&c.name))
+                .collect::<Vec<_>>();
+            changes.push((kept_path, text, deleted));
+        }
+    }
+    if changes.is_empty() {
+        return Ok(0);
+    }
+
+    let trash = Trash::new(loader, Local::now().naive_local());
+    let mut undo = Undo::new("dedupe");
+    for (kept_path, _, deleted) in changes.iter() {
+        undo.record(loader, kept_path)?;
+        for path in deleted {
+            undo.record(loader, path)?;
+        }
+    }
+    undo.record_trash(&trash);
+    undo.save(loader)?;
+    for (kept_path, text, deleted) in changes.iter() {
+        fs::write(kept_path, text)?;
+        for path in deleted {
+            trash.delete(loader, path)?;
+        }
+    }
+    Ok(changes.len())
+}
+
+/// The text `text` of the project `kept` with the actions from `copies` that it doesn't have
+/// added.
+fn merge(
+    docs: &Documents,
+    kept: &Project,
+    copies: &[&Project],
+    text: &str,
+) -> Result<String, IoError> {
+    let loader = docs.loader();
+    let mut seen = kept
+        .actions
+        .actions()
+        .map(|(a, _)| a.content_hash())
+        .collect::<HashSet<_>>();
+    let mut added = Vec::<(ActionStatus, String)>::new();
+    for copy in copies {
+        let copy_text = fs::read_to_string(loader.project_path(&copy.name))?;
+        for (action, status) in copy.actions.actions() {
+            if !seen.insert(action.content_hash()) {
+                continue;
+            }
+            let mut item = copy_text[action.span.line_range(&copy_text)].to_string();
+            if !item.ends_with('\n') {
+                item.push('\n');
+            }
+            match added.iter_mut().find(|(s, _)| *s == status) {
+                Some((_, items)) => items.push_str(&item),
+                None => added.push((status, item)),
+            }
+        }
+    }
+
+    let mut text = text.to_string();
+    for (status, items) in added {
+        let project =
+            Project::parse_with_depths(kept.name.as_str(), &text, loader.scheme(), loader.depths())
+                .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        text = edit::add_actions(&text, &project, status, &items);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+
+    fn with_docs<F: FnOnce(&Documents)>(projects: &[(&str, &str)], f: F) {
+        let dir = TempDir::new("dedupe");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        for (name, text) in projects {
+            fs::write(dir.join(format!("Projects/{}.md", name)), text).unwrap();
+        }
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        f(&docs);
+    }
+
+    const TRIP: &str = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights\n- Pack bags\n- Buy guidebook\n- Renew passport\n- Exchange money\n";
+
+    #[test]
+    fn copies_are_found() {
+        let near = TRIP.replace(
+            "- Exchange money\n",
+            "- Exchange money\n\n### Upcoming\n\n- Send postcards\n",
+        );
+        let projects = [
+            ("202401010000 Plan trip", TRIP),
+            ("202401010001 Plan trip", TRIP),
+            ("202401010002 Plan trips", near.as_str()),
+            ("202401020000 Do taxes", "# Do taxes\n#in-progress\n"),
+        ];
+        with_docs(&projects, |docs| {
+            let groups = find(docs);
+            assert_eq!(groups.len(), 1);
+            let names = groups[0]
+                .projects
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                names,
+                [
+                    "202401010000 Plan trip",
+                    "202401010001 Plan trip",
+                    "202401010002 Plan trips"
+                ]
+            );
+            assert!(!groups[0].is_identical);
+        });
+    }
+
+    #[test]
+    fn missing_actions_are_merged_into_the_first_copy() {
+        let copy = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights\n- Pack bags\n- Buy guidebook\n- Renew passport\n- Exchange money\n\n### Waiting\n\n- Visa from embassy\n";
+        let projects = [
+            ("202401010000 Plan trip", TRIP),
+            ("202401010001 Plan trip", copy),
+        ];
+        with_docs(&projects, |docs| {
+            let groups = find(docs);
+            let choices = [(&groups[0], Cleanup::Merge)];
+            assert_eq!(clean_up(docs, &choices, WriteMode::Write).unwrap(), 1);
+
+            let root_dir = docs.loader().root_dir();
+            assert_eq!(
+                fs::read_to_string(root_dir.join("Projects/202401010000 Plan trip.md")).unwrap(),
+                format!("{}\n### Waiting\n\n- Visa from embassy\n", TRIP)
+            );
+            assert!(!root_dir.join("Projects/202401010001 Plan trip.md").exists());
+        });
+    }
+}
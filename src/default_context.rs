@@ -0,0 +1,125 @@
+//! Linking the new active actions of a project from the context its actions usually happen in.
+//!
+//! A project with `default-context: @studio` in its frontmatter has actions that become active,
+//! like ones promoted with `gtd promote`, linked from `@studio` without being asked.
+
+use crate::{
+    config::ContextLayout,
+    context::Name as ContextName,
+    gtd::Documents,
+    merge, order,
+    project::{ActionId, Name as ProjectName, Project},
+};
+use std::{fs, io::Error as IoError};
+
+/// The frontmatter key holding the context that a project's new active actions are linked from.
+pub const DEFAULT_CONTEXT_KEY: &str = "default-context";
+
+/// The context that new active actions in `project` are linked from, or the one it's an alias of.
+pub fn default_context(docs: &Documents, project: &Project) -> Option<ContextName> {
+    let name = project.frontmatter.get(DEFAULT_CONTEXT_KEY)?.trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some(docs.canonical_context_name(&ContextName::new(name.to_string())))
+}
+
+/// Links the action with the ID `id` in the project `project` from the file of `context`, creating
+/// it if there isn't one, and keeps the context in the order set in `[contexts]`.
+///
+/// Returns whether the context was written, which it isn't if it already links to the action, or
+/// if contexts aren't kept in a file each.
+pub fn link_action(
+    docs: &Documents,
+    context: &ContextName,
+    project: &ProjectName,
+    id: &ActionId,
+) -> Result<bool, IoError> {
+    let loader = docs.loader();
+    if loader.single_file().is_some() || docs.context_layout() == ContextLayout::List {
+        return Ok(false);
+    }
+
+    let path = loader.context_path(context);
+    let text = match path.exists() {
+        true => fs::read_to_string(&path)?,
+        false => format!("# {}\n", context),
+    };
+    let link = format!("- ![[{}#{}]]\n", project, id);
+    let (new_text, merged) = merge::merge_items(&text, &link, loader.scheme());
+    if merged.moved == 0 {
+        return Ok(false);
+    }
+    let new_text = order::reorder(docs, &new_text, docs.context_order());
+    fs::create_dir_all(loader.context_dir())?;
+    fs::write(path, new_text)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+
+    fn folder(frontmatter: &str) -> TempDir {
+        let dir = TempDir::new("default-context");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Paint shed.md"),
+            format!(
+                "{}# Paint shed\n#in-progress\n\n## Actions\n\n### Active\n\n- Sand walls ^abcdef\n",
+                frontmatter
+            ),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn default_context_is_read_from_frontmatter() {
+        let dir = folder("---\ndefault-context: \"@shed\"\n---\n");
+        let config = Config::parse("[contexts.aliases]\n\"@shed\" = \"@garden\"\n").unwrap();
+        let docs = Documents::load(&dir, &config).unwrap();
+        let project = docs.projects().next().unwrap();
+        assert_eq!(
+            default_context(&docs, project),
+            Some(ContextName::new(String::from("@garden")))
+        );
+
+        let dir = folder("");
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        assert_eq!(
+            default_context(&docs, docs.projects().next().unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn actions_are_linked_once() {
+        let dir = folder("");
+        fs::write(
+            dir.join("Contexts/@garden.md"),
+            "# @garden\n\n- Weed beds\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let project = ProjectName::new("202401010000 Paint shed".into()).unwrap();
+        let id = ActionId::new(String::from("abcdef"));
+        let garden = ContextName::new(String::from("@garden"));
+        let shed = ContextName::new(String::from("@shed"));
+
+        assert!(link_action(&docs, &garden, &project, &id).unwrap());
+        assert!(!link_action(&docs, &garden, &project, &id).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join("Contexts/@garden.md")).unwrap(),
+            "# @garden\n\n- Weed beds\n- ![[202401010000 Paint shed#^abcdef]]\n"
+        );
+        assert!(link_action(&docs, &shed, &project, &id).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join("Contexts/@shed.md")).unwrap(),
+            "# @shed\n\n- ![[202401010000 Paint shed#^abcdef]]\n"
+        );
+    }
+}
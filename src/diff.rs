@@ -0,0 +1,291 @@
+//! Word diffs of the changes that commands make to files, so rewrites can be previewed and
+//! confirmed instead of happening silently.
+
+use crate::prompt;
+use std::io::{self, Error as IoError, IsTerminal};
+
+/// The most pairs of words that are compared before a change is shown as a whole replacement.
+const MAX_COMPARISONS: usize = 4_000_000;
+
+/// How a command that rewrites files makes its changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Show each change and write it.
+    Write,
+    /// Show each change without writing it.
+    DryRun,
+    /// Show each change and ask whether to write it.
+    Interactive,
+}
+
+impl WriteMode {
+    pub fn from_flags(dry_run: bool, interactive: bool) -> Self {
+        match (dry_run, interactive) {
+            (true, _) => Self::DryRun,
+            (false, true) => Self::Interactive,
+            (false, false) => Self::Write,
+        }
+    }
+}
+
+/// A run of words in a diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// A group of consecutive changed lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// The 1-based line in the new text that the hunk starts on.
+    pub line: usize,
+    /// The 1-based line in the old text that the hunk starts on.
+    pub old_line: usize,
+    pub lines: Vec<Vec<Op>>,
+}
+
+/// Shows the changes from `old` to `new` in the file `name`, and returns whether to write them,
+/// according to `mode`.
+pub fn review(name: &str, old: &str, new: &str, mode: WriteMode) -> Result<bool, IoError> {
+    println!("{}:", name);
+    print!("{}", render(&hunks(old, new), io::stdout().is_terminal()));
+    match mode {
+        WriteMode::Write => Ok(true),
+        WriteMode::DryRun => Ok(false),
+        WriteMode::Interactive => prompt::confirm("Apply this change?"),
+    }
+}
+
+/// The lines that differ between `old` and `new`, compared word by word.
+pub fn hunks(old: &str, new: &str) -> Vec<Hunk> {
+    let mut hunks = Vec::new();
+    let mut current = None::<Hunk>;
+    for ((line, old_line), ops) in lines(&diff(&words(old), &words(new))) {
+        let is_changed = ops.iter().any(|op| !matches!(op, Op::Same(_)));
+        match (&mut current, is_changed) {
+            (Some(hunk), true) => hunk.lines.push(ops),
+            (None, true) => {
+                current = Some(Hunk {
+                    line,
+                    old_line,
+                    lines: vec![ops],
+                })
+            }
+            (_, false) => hunks.extend(current.take()),
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+/// Renders `hunks`, with removed words in red and added words in green if `color` is set, or
+/// marked like `[-old-]{+new+}` otherwise.
+pub fn render(hunks: &[Hunk], color: bool) -> String {
+    let mut out = String::new();
+    for hunk in hunks {
+        out.push_str(&format!("@@ line {} @@\n", hunk.line));
+        out.push_str(&render_lines(hunk, color));
+    }
+    out
+}
+
+/// Renders the lines of `hunk` without its header, marked like [`render`] does.
+pub fn render_lines(hunk: &Hunk, color: bool) -> String {
+    let mut out = String::new();
+    for line in hunk.lines.iter() {
+        for op in line {
+            let text = match (op, color) {
+                (Op::Same(t), _) => t.clone(),
+                (Op::Removed(t), true) => format!("\x1b[31m{}\x1b[0m", t),
+                (Op::Added(t), true) => format!("\x1b[32m{}\x1b[0m", t),
+                (Op::Removed(t), false) => format!("[-{}-]", t),
+                (Op::Added(t), false) => format!("{{+{}+}}", t),
+            };
+            out.push_str(&text);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Splits `text` into words, runs of whitespace, and newlines.
+fn words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let ends_word = match chars.peek() {
+            Some((_, next)) => {
+                c == '\n' || *next == '\n' || c.is_whitespace() != next.is_whitespace()
+            }
+            None => true,
+        };
+        if ends_word {
+            let end = i + c.len_utf8();
+            words.push(&text[start..end]);
+            start = end;
+        }
+    }
+    words
+}
+
+/// The operations that turn `old` into `new`, found from their longest common subsequence.
+fn diff(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut ops = Vec::new();
+    push(&mut ops, Op::Same(old[..prefix].concat()));
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_COMPARISONS {
+        push(&mut ops, Op::Removed(old_mid.concat()));
+        push(&mut ops, Op::Added(new_mid.concat()));
+    } else {
+        // lengths[i][j] is the length of the common subsequence of old_mid[i..] and new_mid[j..].
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i][j] = if old_mid[i] == new_mid[j] {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                push(&mut ops, Op::Same(old_mid[i].to_string()));
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lengths[i + 1][j] >= lengths[i][j + 1]) {
+                push(&mut ops, Op::Removed(old_mid[i].to_string()));
+                i += 1;
+            } else {
+                push(&mut ops, Op::Added(new_mid[j].to_string()));
+                j += 1;
+            }
+        }
+    }
+    push(&mut ops, Op::Same(old[old.len() - suffix..].concat()));
+    ops
+}
+
+/// Adds `op` to `ops`, joining it onto the last op if they're the same kind.
+fn push(ops: &mut Vec<Op>, op: Op) {
+    match (ops.last_mut(), op) {
+        (_, Op::Same(t)) | (_, Op::Removed(t)) | (_, Op::Added(t)) if t.is_empty() => {}
+        (Some(Op::Same(last)), Op::Same(t))
+        | (Some(Op::Removed(last)), Op::Removed(t))
+        | (Some(Op::Added(last)), Op::Added(t)) => last.push_str(&t),
+        (_, op) => ops.push(op),
+    }
+}
+
+/// Splits `ops` into lines, along with the lines in the new and old text that each one starts on.
+fn lines(ops: &[Op]) -> Vec<((usize, usize), Vec<Op>)> {
+    let mut lines = Vec::new();
+    let mut current = Vec::new();
+    let mut is_changed = false;
+    let (mut line, mut old_line) = (1, 1);
+    let mut start = (1, 1);
+    for op in ops {
+        let (text, make): (&str, fn(String) -> Op) = match op {
+            Op::Same(t) => (t, Op::Same),
+            Op::Removed(t) => (t, Op::Removed),
+            Op::Added(t) => (t, Op::Added),
+        };
+        for piece in text.split_inclusive('\n') {
+            let content = piece.trim_end_matches('\n');
+            if !content.is_empty() {
+                current.push(make(content.to_string()));
+            }
+            is_changed |= !matches!(op, Op::Same(_));
+            if piece.ends_with('\n') {
+                if !matches!(op, Op::Removed(_)) {
+                    line += 1;
+                }
+                if !matches!(op, Op::Added(_)) {
+                    old_line += 1;
+                }
+                // A line whose only change is its newline still shows up as changed.
+                if is_changed && current.iter().all(|op| matches!(op, Op::Same(_))) {
+                    current.push(make(String::new()));
+                }
+                lines.push((start, std::mem::take(&mut current)));
+                is_changed = false;
+                start = (line, old_line);
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push((start, current));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(old: &str, new: &str) -> String {
+        render(&hunks(old, new), false)
+    }
+
+    #[test]
+    fn changed_words_are_marked() {
+        assert_eq!(
+            plain(
+                "---\nprogress: 50\n---\n# Plan trip\n",
+                "---\nprogress: 75\n---\n# Plan trip\n"
+            ),
+            "@@ line 2 @@\nprogress: [-50-]{+75+}\n"
+        );
+    }
+
+    #[test]
+    fn added_lines_are_marked() {
+        assert_eq!(
+            plain("# Plan trip\n", "---\nprogress: 75\n---\n# Plan trip\n"),
+            "@@ line 1 @@\n{+---+}\n{+progress: 75+}\n{+---+}\n"
+        );
+    }
+
+    #[test]
+    fn separate_changes_are_separate_hunks() {
+        let hunks = hunks("a\nb\nc\n", "x\nb\ny\n");
+        assert_eq!(hunks.iter().map(|h| h.line).collect::<Vec<_>>(), vec![1, 3]);
+    }
+
+    #[test]
+    fn hunks_know_their_line_in_the_old_text() {
+        let hunks = hunks("a\nb\nc\nd\n", "b\nc\nx\n");
+        let lines = hunks
+            .iter()
+            .map(|h| (h.old_line, h.line))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![(1, 1), (4, 3)]);
+    }
+
+    #[test]
+    fn unchanged_text_has_no_hunks() {
+        assert_eq!(hunks("# Plan trip\n", "# Plan trip\n"), vec![]);
+    }
+
+    #[test]
+    fn dry_run_wins_over_interactive() {
+        assert_eq!(WriteMode::from_flags(true, true), WriteMode::DryRun);
+    }
+}
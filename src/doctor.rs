@@ -0,0 +1,69 @@
+//! A report on how a GTD folder is set up, for working out why a document isn't showing up.
+
+use crate::{
+    action_list::ACTION_LIST_FILENAME,
+    config::{Config, ContextLayout},
+    gtd::Loader,
+    walk::{self, Ignore, IGNORE_FILENAMES},
+};
+use std::{fmt, io::Error as IoError, path::PathBuf};
+
+/// How a GTD folder is set up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub root_dir: PathBuf,
+    /// The document everything is read from, in single-file mode.
+    pub single_file: Option<PathBuf>,
+    pub context_layout: ContextLayout,
+    /// The ignore files that exist in the root of the folder.
+    pub ignore_files: Vec<&'static str>,
+    /// The number of files in the folder that are read.
+    pub included: usize,
+    /// The number of files and folders that are skipped because they're ignored.
+    pub excluded: usize,
+}
+
+/// Checks the setup of the GTD folder of `loader`.
+pub fn check(loader: &Loader, config: &Config) -> Result<Report, IoError> {
+    let root_dir = loader.root_dir();
+    let walk = walk::walk(root_dir, &Ignore::load(root_dir)?)?;
+    let ignore_files = IGNORE_FILENAMES
+        .iter()
+        .copied()
+        .filter(|f| root_dir.join(f).is_file())
+        .collect();
+    Ok(Report {
+        root_dir: root_dir.to_owned(),
+        single_file: loader.single_file().map(|p| p.to_owned()),
+        context_layout: config.contexts.layout,
+        ignore_files,
+        included: walk.included.len(),
+        excluded: walk.excluded,
+    })
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "GTD folder: {}", self.root_dir.display())?;
+        match &self.single_file {
+            Some(path) => writeln!(f, "Layout: single file {}", path.display())?,
+            None => match self.context_layout {
+                ContextLayout::Files => writeln!(f, "Layout: a file per project and context")?,
+                ContextLayout::List => writeln!(
+                    f,
+                    "Layout: a file per project, with contexts in {}",
+                    ACTION_LIST_FILENAME
+                )?,
+            },
+        }
+        match self.ignore_files.is_empty() {
+            true => writeln!(f, "Ignore files: none")?,
+            false => writeln!(f, "Ignore files: {}", self.ignore_files.join(", "))?,
+        }
+        writeln!(
+            f,
+            "Files: {} included, {} excluded",
+            self.included, self.excluded
+        )
+    }
+}
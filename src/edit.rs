@@ -0,0 +1,920 @@
+//! Edits to the source text of documents.
+//!
+//! Edits work on the lines that parsed items came from, so the rest of the document is left
+//! exactly as it was written. That includes the order of list items: only the items an edit is
+//! about are added, moved, or removed, and the rest keep the order they were put in by hand, which
+//! [`keeps_order`] checks.
+
+use crate::{
+    parser,
+    project::{
+        Action, ActionId, ActionStatus, Project, Section, Status, ACTIVE_ANNOTATION, DONE_MARKER,
+    },
+    tag::Tag,
+};
+use chrono::NaiveDate;
+use std::ops::Range;
+
+const COMPLETE_HEADING: &str = "### Complete";
+const ACTIVE_HEADING: &str = "### Active";
+
+/// The headings of the sections that come after the active actions.
+const INACTIVE_HEADINGS: [&str; 3] = ["### Waiting", "### Upcoming", COMPLETE_HEADING];
+
+/// Moves `action` to the project's complete actions, marking it as completed on `date`.
+///
+/// `project` and `action` must have been parsed from `text`. Returns `None` if the project doesn't
+/// have the action or it's already complete.
+pub fn complete_action(
+    text: &str,
+    project: &Project,
+    action: &Action,
+    date: NaiveDate,
+) -> Option<String> {
+    let (_, status) = project
+        .actions
+        .actions()
+        .find(|(a, _)| a.span == action.span)?;
+    if status == ActionStatus::Complete {
+        return None;
+    }
+
+    let removed = action.span.line_range(text);
+    let item = mark_complete(&text[removed.clone()], action.id.as_ref(), date);
+
+    let last_complete = project
+        .actions
+        .actions()
+        .filter(|(_, s)| *s == ActionStatus::Complete)
+        .last();
+    let (at, inserted) = match last_complete {
+        Some((a, _)) => (a.span.line_range(text).end, item),
+        None => match heading_end(text, COMPLETE_HEADING) {
+            Some(end) => (end, format!("\n{}", item)),
+            None => {
+                let last = project
+                    .actions
+                    .actions()
+                    .map(|(a, _)| a.span)
+                    .max_by_key(|s| s.end)?;
+                let end = last.line_range(text).end;
+                let before = if end == removed.end {
+                    &text[..removed.start]
+                } else {
+                    &text[..end]
+                };
+                let gap = if before.ends_with("\n\n") { "" } else { "\n" };
+                (end, format!("{}{}\n\n{}", gap, COMPLETE_HEADING, item))
+            }
+        },
+    };
+
+    // Don't leave two blank lines behind when the action was alone between them.
+    let removed = if at != removed.end
+        && text[..removed.start].ends_with("\n\n")
+        && text[removed.end..].starts_with('\n')
+    {
+        removed.start..removed.end + 1
+    } else {
+        removed
+    };
+
+    Some(move_lines(text, removed, at, &inserted))
+}
+
+/// Moves a waiting or upcoming `action` to the project's active actions, annotating it with
+/// `@active(…)` so it's known how long it's been active since `date`.
+///
+/// `project` and `action` must have been parsed from `text`. Returns `None` if the project doesn't
+/// have the action or it's already active or complete.
+pub fn promote_action(
+    text: &str,
+    project: &Project,
+    action: &Action,
+    date: NaiveDate,
+) -> Option<String> {
+    let (_, status) = project
+        .actions
+        .actions()
+        .find(|(a, _)| a.span == action.span)?;
+    if matches!(status, ActionStatus::Active | ActionStatus::Complete) {
+        return None;
+    }
+
+    let removed = action.span.line_range(text);
+    let item = &text[removed.clone()];
+    let (first, rest) = match item.find('\n') {
+        Some(i) => (&item[..i], &item[i..]),
+        None => (item, "\n"),
+    };
+    let annotation = format!("@{}({})", ACTIVE_ANNOTATION, date.format("%Y-%m-%d"));
+    let item = format!(
+        "{}{}",
+        insert_marker(first, action.id.as_ref(), &annotation),
+        rest
+    );
+
+    let last_active = project
+        .actions
+        .actions()
+        .filter(|(_, s)| *s == ActionStatus::Active)
+        .last();
+    let (at, inserted) = match last_active {
+        Some((a, _)) => (a.span.line_range(text).end, item),
+        None => match heading_end(text, ACTIVE_HEADING) {
+            Some(end) => (end, format!("\n{}", item)),
+            None => {
+                // The action is under one of these, so at least one of them is there.
+                let start = INACTIVE_HEADINGS
+                    .iter()
+                    .filter_map(|h| heading_start(text, h))
+                    .min()?;
+                (start, format!("{}\n\n{}\n", ACTIVE_HEADING, item))
+            }
+        },
+    };
+
+    // Don't leave two blank lines behind, or a blank line at the end of the file.
+    let removed = if !text[..removed.start].ends_with("\n\n") {
+        removed
+    } else if text[removed.end..].starts_with('\n') {
+        removed.start..removed.end + 1
+    } else if removed.end == text.len() {
+        removed.start - 1..removed.end
+    } else {
+        removed
+    };
+
+    Some(move_lines(text, removed, at, &inserted))
+}
+
+/// Adds the list items in `items` after the project's actions with `status`, adding the section
+/// for them if it's missing.
+///
+/// `project` must have been parsed from `text`, and `items` must end with a newline.
+pub fn add_actions(text: &str, project: &Project, status: ActionStatus, items: &str) -> String {
+    let headings = [
+        ACTIVE_HEADING,
+        INACTIVE_HEADINGS[0],
+        INACTIVE_HEADINGS[1],
+        COMPLETE_HEADING,
+    ];
+    let index = match status {
+        ActionStatus::Active => 0,
+        ActionStatus::Waiting => 1,
+        ActionStatus::Upcoming => 2,
+        ActionStatus::Complete => 3,
+    };
+    let heading = headings[index];
+
+    let last = project
+        .actions
+        .actions()
+        .filter(|(_, s)| *s == status)
+        .last();
+    let (at, inserted) = match last {
+        Some((a, _)) => (a.span.line_range(text).end, items.to_string()),
+        None => match heading_end(text, heading) {
+            Some(end) => (end, format!("\n{}", items)),
+            None => match headings[index + 1..]
+                .iter()
+                .filter_map(|h| heading_start(text, h))
+                .min()
+            {
+                Some(start) => (start, format!("{}\n\n{}\n", heading, items)),
+                None => {
+                    let gap = match text {
+                        t if t.ends_with("\n\n") => "",
+                        t if t.ends_with('\n') => "\n",
+                        _ => "\n\n",
+                    };
+                    let actions = match heading_start(text, "## Actions") {
+                        Some(_) => String::new(),
+                        None => String::from("## Actions\n\n"),
+                    };
+                    (
+                        text.len(),
+                        format!("{}{}{}\n\n{}", gap, actions, heading, items),
+                    )
+                }
+            },
+        },
+    };
+    format!("{}{}{}", &text[..at], inserted, &text[at..])
+}
+
+/// Sets the date after `marker` (like `🛫`) on `action`, replacing the existing date if there is
+/// one.
+///
+/// `action` must have been parsed from `text`.
+pub fn set_action_date(text: &str, action: &Action, marker: &str, date: NaiveDate) -> String {
+    let new_marker = format!("{} {}", marker, date.format("%Y-%m-%d"));
+    edit_first_line(text, action, |first| match dated_marker(first, marker) {
+        Some(range) => format!(
+            "{}{}{}",
+            &first[..range.start],
+            new_marker,
+            &first[range.end..]
+        ),
+        None => insert_marker(first, action.id.as_ref(), &new_marker),
+    })
+}
+
+/// Removes `marker` and the date after it from `action`, if it has them.
+///
+/// `action` must have been parsed from `text`.
+pub fn clear_action_date(text: &str, action: &Action, marker: &str) -> String {
+    edit_first_line(text, action, |first| match dated_marker(first, marker) {
+        Some(range) => {
+            let start = first[..range.start].trim_end().len();
+            format!("{}{}", &first[..start], &first[range.end..])
+        }
+        None => first.to_string(),
+    })
+}
+
+/// Adds an annotation like `@uid(abc123)` to `action`, where `name` is the part before the
+/// parentheses, replacing the action's annotation with that name if it already has one.
+///
+/// `action` must have been parsed from `text`.
+pub fn annotate_action(text: &str, action: &Action, name: &str, value: &str) -> String {
+    let annotation = format!("@{}({})", name, value);
+    let annotation_start = format!("@{}(", name);
+    edit_first_line(text, action, |first| {
+        let existing = first
+            .find(&annotation_start)
+            .and_then(|start| Some(start..start + first[start..].find(')')? + 1));
+        match existing {
+            Some(range) => format!(
+                "{}{}{}",
+                &first[..range.start],
+                annotation,
+                &first[range.end..]
+            ),
+            None => insert_marker(first, action.id.as_ref(), &annotation),
+        }
+    })
+}
+
+/// Replaces the block ID of `action` with `id`, or adds it if the action doesn't have one.
+///
+/// `action` must have been parsed from `text`.
+pub fn set_action_id(text: &str, action: &Action, id: &ActionId) -> String {
+    edit_first_line(text, action, |first| {
+        let old_suffix = action.id.as_ref().map(|id| format!(" {}", id));
+        let start = old_suffix
+            .as_deref()
+            .and_then(|s| first.strip_suffix(s))
+            .unwrap_or(first);
+        format!("{} {}", start, id)
+    })
+}
+
+/// Removes `action` from the project, returning the new text and the removed list item, which ends
+/// with a newline.
+///
+/// `action` must have been parsed from `text`.
+pub fn remove_action(text: &str, action: &Action) -> (String, String) {
+    let lines = action.span.line_range(text);
+    let mut item = text[lines.clone()].to_string();
+    if !item.ends_with('\n') {
+        item.push('\n');
+    }
+    // Don't leave two blank lines behind when the action was alone between them, or a blank line
+    // at the end when it was the last thing in the file.
+    let after_blank = text[..lines.start].ends_with("\n\n");
+    let removed = if after_blank && text[lines.end..].starts_with('\n') {
+        lines.start..lines.end + 1
+    } else if after_blank && lines.end == text.len() {
+        lines.start - 1..lines.end
+    } else {
+        lines
+    };
+    let text = format!("{}{}", &text[..removed.start], &text[removed.end..]);
+    (text, item)
+}
+
+/// Replaces the status tag of the project in `text` with the one for `status`.
+///
+/// The status tag is the first one in the paragraphs between the title and the first section.
+/// Returns `None` if there isn't one.
+pub fn set_status(text: &str, status: Status) -> Option<String> {
+    let headings = parser::source_headings(text);
+    let title = headings.iter().position(|h| h.level == 1)?;
+    let start = headings[title].lines.end;
+    let end = headings
+        .get(title + 1)
+        .map_or(text.len(), |h| h.lines.start);
+
+    let mut offset = start;
+    let tag = text[start..end]
+        .split_inclusive(char::is_whitespace)
+        .find_map(|word| {
+            let word_start = offset;
+            offset += word.len();
+            let tag = Tag::from_word(word.trim_end())?;
+            tag.as_str()
+                .parse::<Status>()
+                .ok()
+                .map(|_| word_start..word_start + 1 + tag.as_str().len())
+        })?;
+    Some(format!(
+        "{}#{}{}",
+        &text[..tag.start],
+        status.tag(),
+        &text[tag.end..]
+    ))
+}
+
+/// Adds an empty `section` to the project in `text`, before the sections that go after it, or at the
+/// end if there aren't any.
+pub fn insert_section(text: &str, section: Section) -> String {
+    let later = match section {
+        Section::Goal => &["## Info", "## Actions"][..],
+        Section::Info => &["## Actions"][..],
+    };
+    let scaffold = format!("## {}\n\n", section.heading());
+    match later.iter().filter_map(|h| heading_start(text, h)).min() {
+        Some(at) => format!("{}{}{}", &text[..at], scaffold, &text[at..]),
+        None => {
+            let gap = match text {
+                "" => "",
+                t if t.ends_with("\n\n") => "",
+                t if t.ends_with('\n') => "\n",
+                _ => "\n\n",
+            };
+            format!("{}{}{}", text, gap, scaffold)
+        }
+    }
+}
+
+/// Sets the `## Outcome` section of the project in `text` to `note`, marked as completed on `date`,
+/// replacing the section if it's already there. A new section goes before the actions.
+pub fn set_outcome(text: &str, note: &str, date: NaiveDate) -> String {
+    let marker = format!("{} {}", DONE_MARKER, date.format("%Y-%m-%d"));
+    let section = match note.trim() {
+        "" => format!("## Outcome\n\n{}\n\n", marker),
+        note => format!("## Outcome\n\n{} {}\n\n", note, marker),
+    };
+
+    let text = match find_heading(text, "## Outcome") {
+        Some(lines) => {
+            let end = parser::source_headings(text)
+                .into_iter()
+                .find(|h| h.lines.start >= lines.end && h.level <= 2)
+                .map_or(text.len(), |h| h.lines.start);
+            format!("{}{}", &text[..lines.start], &text[end..])
+        }
+        None => text.to_string(),
+    };
+    match heading_start(&text, "## Actions") {
+        Some(at) => format!("{}{}{}", &text[..at], section, &text[at..]),
+        None => {
+            let gap = match text.as_str() {
+                t if t.ends_with("\n\n") => "",
+                t if t.ends_with('\n') => "\n",
+                _ => "\n\n",
+            };
+            format!("{}{}{}", text, gap, section.trim_end_matches('\n')) + "\n"
+        }
+    }
+}
+
+/// Replaces the first line of `action`'s list item with the result of `edit`.
+fn edit_first_line<F>(text: &str, action: &Action, edit: F) -> String
+where
+    F: FnOnce(&str) -> String,
+{
+    let lines = action.span.line_range(text);
+    let item = &text[lines.clone()];
+    let (first, rest) = match item.find('\n') {
+        Some(i) => (&item[..i], &item[i..]),
+        None => (item, ""),
+    };
+
+    format!(
+        "{}{}{}{}",
+        &text[..lines.start],
+        edit(first),
+        rest,
+        &text[lines.end..]
+    )
+}
+
+/// Where `marker` and the date after it, which is always 10 characters long, are in `first`.
+fn dated_marker(first: &str, marker: &str) -> Option<Range<usize>> {
+    let idx = first.find(marker)?;
+    let after = first[idx + marker.len()..].trim_start();
+    let date_start = first.len() - after.len();
+    let date_len = after.char_indices().nth(10).map_or(after.len(), |(i, _)| i);
+    Some(idx..date_start + date_len)
+}
+
+/// Inserts `marker` at the end of the first line of a list item, before its ID if it has one.
+fn insert_marker(first: &str, id: Option<&ActionId>, marker: &str) -> String {
+    let id_suffix = id.map(|id| format!(" {}", id));
+    match id_suffix.as_deref().and_then(|s| first.strip_suffix(s)) {
+        Some(start) => format!("{} {}{}", start, marker, id_suffix.unwrap()),
+        None => format!("{} {}", first, marker),
+    }
+}
+
+/// Adds the completion marker to the first line of a list item, and checks its checkbox if it has
+/// one.
+fn mark_complete(item: &str, id: Option<&ActionId>, date: NaiveDate) -> String {
+    let (first, rest) = match item.find('\n') {
+        Some(i) => (&item[..i], &item[i..]),
+        None => (item, "\n"),
+    };
+
+    let marker = format!("{} {}", DONE_MARKER, date.format("%Y-%m-%d"));
+    let first = insert_marker(first, id, &marker);
+    let first = first.replacen("[ ] ", "[x] ", 1);
+
+    format!("{}{}", first, rest)
+}
+
+/// The byte offset where the actions in the text of a context start, after its frontmatter and
+/// title.
+pub fn context_body_start(text: &str) -> usize {
+    let group = first_group(text).unwrap_or(text.len());
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if offset >= group || is_item_start(line) {
+            break;
+        }
+        offset += line.len();
+    }
+    offset.min(group)
+}
+
+/// The byte offset of the first group heading in the text of a context, if it has one.
+pub fn first_group(text: &str) -> Option<usize> {
+    parser::source_headings(text)
+        .into_iter()
+        .find(|h| h.level == 2)
+        .map(|h| h.lines.start)
+}
+
+/// The top-level list items in `text`, each with any indented lines under it.
+pub fn list_items(text: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut start = None;
+    let mut end = 0;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end();
+        if is_item_start(content) {
+            if let Some(s) = start {
+                items.push(text[s..end].trim_end());
+            }
+            start = Some(offset);
+            end = offset + content.len();
+        } else if start.is_some() && content.starts_with(char::is_whitespace) {
+            end = offset + content.len();
+        } else if let Some(s) = start.take() {
+            items.push(text[s..end].trim_end());
+        }
+        offset += line.len();
+    }
+    if let Some(s) = start {
+        items.push(text[s..end].trim_end());
+    }
+    items
+}
+
+/// Whether the top-level list items of `old` that are still in `new`, unchanged, are in the same
+/// order there. Items that were changed, added, or removed don't count.
+pub fn keeps_order(old: &str, new: &str) -> bool {
+    let old_items = list_items(old);
+    let new_items = list_items(new);
+    let mut rest = old_items.iter();
+    new_items
+        .iter()
+        .filter(|item| old_items.contains(item))
+        .all(|item| rest.any(|old| old == item))
+}
+
+/// Removes the list items in `text`, at any depth, whose first line `is_removed` picks, along with
+/// any lines indented under them.
+pub fn remove_list_items<F: Fn(&str) -> bool>(text: &str, is_removed: F) -> String {
+    let mut out = String::with_capacity(text.len());
+    // The indentation of the item being removed.
+    let mut removing = None;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim();
+        let indent = line.len() - line.trim_start().len();
+        if let Some(level) = removing {
+            if indent > level && !content.is_empty() {
+                continue;
+            }
+            removing = None;
+        }
+        if is_item_start(content) && is_removed(content) {
+            removing = Some(indent);
+            continue;
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+/// Whether `line` starts a top-level list item.
+pub fn is_item_start(line: &str) -> bool {
+    line.starts_with("- ") || line.starts_with("* ")
+}
+
+/// The byte offset just after the line `heading`, if `text` has it, however it's written there.
+fn heading_end(text: &str, heading: &str) -> Option<usize> {
+    find_heading(text, heading).map(|lines| lines.end)
+}
+
+/// The byte offset of the start of the line `heading`, if `text` has it, however it's written
+/// there.
+fn heading_start(text: &str, heading: &str) -> Option<usize> {
+    find_heading(text, heading).map(|lines| lines.start)
+}
+
+/// The lines of the heading in `text` with the same level and text as `heading`, which is written
+/// like `### Active`, so `### Active ###` or `Active` underlined are found too.
+fn find_heading(text: &str, heading: &str) -> Option<Range<usize>> {
+    let wanted = parser::source_headings(heading).pop()?;
+    parser::source_headings(text)
+        .into_iter()
+        .find(|h| h.level == wanted.level && h.text == wanted.text)
+        .map(|h| h.lines)
+}
+
+/// Removes the text in `removed` and inserts `inserted` at `at`, where both are offsets into the
+/// original text. `at` can't be inside `removed`.
+fn move_lines(text: &str, removed: Range<usize>, at: usize, inserted: &str) -> String {
+    let mut out = String::with_capacity(text.len() + inserted.len());
+    if at >= removed.end {
+        out.push_str(&text[..removed.start]);
+        out.push_str(&text[removed.end..at]);
+        out.push_str(inserted);
+        out.push_str(&text[at..]);
+    } else {
+        out.push_str(&text[..at]);
+        out.push_str(inserted);
+        out.push_str(&text[at..removed.start]);
+        out.push_str(&text[removed.end..]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::DUE_MARKER;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 5).unwrap()
+    }
+
+    fn complete(text: &str, id: &str) -> Option<String> {
+        let project = Project::parse("197001010000 Plan trip", text).unwrap();
+        let (action, _) = project.actions.get_action(&ActionId::new(id.to_string()))?;
+        complete_action(text, &project, action, date())
+    }
+
+    fn promote(text: &str, id: &str) -> Option<String> {
+        let project = Project::parse("197001010000 Plan trip", text).unwrap();
+        let (action, _) = project.actions.get_action(&ActionId::new(id.to_string()))?;
+        promote_action(text, &project, action, date())
+    }
+
+    #[test]
+    fn promoted_action_is_moved_after_active_actions() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n\n### Upcoming\n\n- Go ^bbbbbb\n- Unpack ^cccccc\n";
+        assert_eq!(
+            promote(text, "bbbbbb").unwrap(),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n- Go @active(2024-01-05) ^bbbbbb\n\n### Upcoming\n\n- Unpack ^cccccc\n"
+        );
+        assert_eq!(promote(text, "aaaaaa"), None);
+    }
+
+    #[test]
+    fn active_heading_is_added_when_missing() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Upcoming\n\n- Go ^bbbbbb\n";
+        assert_eq!(
+            promote(text, "bbbbbb").unwrap(),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Go @active(2024-01-05) ^bbbbbb\n\n### Upcoming\n"
+        );
+    }
+
+    #[test]
+    fn section_is_inserted_in_order() {
+        let text = "# Plan trip\n#in-progress\n\n## Info\n\nNotes.\n\n## Actions\n";
+        assert_eq!(
+            insert_section(text, Section::Goal),
+            "# Plan trip\n#in-progress\n\n## Goal\n\n## Info\n\nNotes.\n\n## Actions\n"
+        );
+        assert_eq!(
+            insert_section("# Plan trip\n#in-progress\n", Section::Info),
+            "# Plan trip\n#in-progress\n\n## Info\n\n"
+        );
+    }
+
+    #[test]
+    fn action_is_moved_after_complete_actions() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n- Go ^bbbbbb\n\n### Complete\n\n- Pick dates ^cccccc\n";
+        assert_eq!(
+            complete(text, "aaaaaa").unwrap(),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Go ^bbbbbb\n\n### Complete\n\n- Pick dates ^cccccc\n- Pack ✅ 2024-01-05 ^aaaaaa\n"
+        );
+    }
+
+    #[test]
+    fn last_active_action_leaves_one_blank_line() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n\n### Complete\n\n- Go ^bbbbbb\n";
+        assert_eq!(
+            complete(text, "aaaaaa").unwrap(),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n### Complete\n\n- Go ^bbbbbb\n- Pack ✅ 2024-01-05 ^aaaaaa\n"
+        );
+    }
+
+    #[test]
+    fn action_is_moved_under_empty_complete_heading() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n- Go ^bbbbbb\n\n### Complete\n";
+        assert_eq!(
+            complete(text, "bbbbbb").unwrap(),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n\n### Complete\n\n- Go ✅ 2024-01-05 ^bbbbbb\n"
+        );
+    }
+
+    #[test]
+    fn complete_section_is_added() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n";
+        assert_eq!(
+            complete(text, "aaaaaa").unwrap(),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n### Complete\n\n- Pack ✅ 2024-01-05 ^aaaaaa\n"
+        );
+    }
+
+    #[test]
+    fn action_without_id_is_completed() {
+        let text =
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack\n- Go ^bbbbbb\n";
+        let project = Project::parse("197001010000 Plan trip", text).unwrap();
+        let (action, _) = project.actions.actions().next().unwrap();
+        assert_eq!(
+            complete_action(text, &project, action, date()).unwrap(),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Go ^bbbbbb\n\n### Complete\n\n- Pack ✅ 2024-01-05\n"
+        );
+    }
+
+    #[test]
+    fn complete_action_isnt_completed_again() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Complete\n\n- Pack ^aaaaaa\n";
+        assert_eq!(complete(text, "aaaaaa"), None);
+    }
+
+    mod add_actions {
+        use super::*;
+
+        fn add(text: &str, status: ActionStatus) -> String {
+            let project = Project::parse("197001010000 Plan trip", text).unwrap();
+            add_actions(text, &project, status, "- Renew passport\n")
+        }
+
+        #[test]
+        fn actions_go_after_others_with_the_status() {
+            let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack\n\n### Upcoming\n\n- Go\n";
+            assert_eq!(
+                add(text, ActionStatus::Active),
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack\n- Renew passport\n\n### Upcoming\n\n- Go\n"
+            );
+        }
+
+        #[test]
+        fn section_is_added_before_later_sections() {
+            let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack\n\n### Complete\n\n- Book\n";
+            assert_eq!(
+                add(text, ActionStatus::Waiting),
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack\n\n### Waiting\n\n- Renew passport\n\n### Complete\n\n- Book\n"
+            );
+        }
+
+        #[test]
+        fn headings_are_found_however_they_are_written() {
+            let text = "Plan trip\n=========\n#in-progress\n\nActions\n-------\n\n### Active ###\n\n### Complete\n\n- Book\n";
+            assert_eq!(
+                add(text, ActionStatus::Active),
+                "Plan trip\n=========\n#in-progress\n\nActions\n-------\n\n### Active ###\n\n- Renew passport\n\n### Complete\n\n- Book\n"
+            );
+            assert_eq!(
+                add(text, ActionStatus::Upcoming),
+                "Plan trip\n=========\n#in-progress\n\nActions\n-------\n\n### Active ###\n\n### Upcoming\n\n- Renew passport\n\n### Complete\n\n- Book\n"
+            );
+        }
+
+        #[test]
+        fn actions_section_is_added_when_missing() {
+            assert_eq!(
+                add("# Plan trip\n#in-progress\n", ActionStatus::Upcoming),
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Upcoming\n\n- Renew passport\n"
+            );
+        }
+    }
+
+    mod set_action_date {
+        use super::*;
+        use crate::project::DEFER_MARKER;
+
+        fn set(text: &str) -> String {
+            let project = Project::parse("197001010000 Plan trip", text).unwrap();
+            let (action, _) = project.actions.actions().next().unwrap();
+            set_action_date(text, action, DEFER_MARKER, date())
+        }
+
+        #[test]
+        fn date_is_added_before_id() {
+            let text =
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n- Go\n";
+            assert_eq!(
+                set(text),
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack 🛫 2024-01-05 ^aaaaaa\n- Go\n"
+            );
+        }
+
+        #[test]
+        fn existing_date_is_replaced() {
+            let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack 🛫 2023-12-01 #home\n";
+            assert_eq!(
+                set(text),
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack 🛫 2024-01-05 #home\n"
+            );
+        }
+    }
+
+    #[test]
+    fn date_is_cleared() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack 📅 2024-01-05 ^aaaaaa\n";
+        let project = Project::parse("197001010000 Plan trip", text).unwrap();
+        let (action, _) = project.actions.actions().next().unwrap();
+        assert_eq!(
+            clear_action_date(text, action, DUE_MARKER),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n"
+        );
+    }
+
+    #[test]
+    fn annotation_is_added_before_id() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n";
+        let project = Project::parse("197001010000 Plan trip", text).unwrap();
+        let (action, _) = project.actions.actions().next().unwrap();
+        assert_eq!(
+            annotate_action(text, action, "uid", "abc"),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack @uid(abc) ^aaaaaa\n"
+        );
+    }
+
+    #[test]
+    fn annotation_is_replaced() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack @moved(2023-12-01 from Taxes) #home ^aaaaaa\n";
+        let project = Project::parse("197001010000 Plan trip", text).unwrap();
+        let (action, _) = project.actions.actions().next().unwrap();
+        assert_eq!(
+            annotate_action(text, action, "moved", "2024-01-05 from Chores"),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack @moved(2024-01-05 from Chores) #home ^aaaaaa\n"
+        );
+    }
+
+    #[test]
+    fn action_is_removed_with_its_id() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n\n### Upcoming\n\n- Go\n";
+        let project = Project::parse("197001010000 Plan trip", text).unwrap();
+        let (action, _) = project.actions.actions().next().unwrap();
+        let (removed, item) = remove_action(text, action);
+        assert_eq!(
+            removed,
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n### Upcoming\n\n- Go\n"
+        );
+        assert_eq!(item, "- Pack ^aaaaaa\n");
+
+        let (action, _) = project.actions.actions().nth(1).unwrap();
+        let id = ActionId::new(String::from("bbbbbb"));
+        assert!(set_action_id(text, action, &id).ends_with("- Go ^bbbbbb\n"));
+        let (action, _) = project.actions.actions().next().unwrap();
+        assert!(set_action_id(text, action, &id).contains("- Pack ^bbbbbb\n"));
+    }
+
+    #[test]
+    fn status_tag_is_replaced() {
+        let text = "---\nsomeday: 2024-01-05\n---\n# Plan trip\n#area/home #someday\n\nThe #someday tag.\n";
+        assert_eq!(
+            set_status(text, Status::InProgress).unwrap(),
+            "---\nsomeday: 2024-01-05\n---\n# Plan trip\n#area/home #in-progress\n\nThe #someday tag.\n"
+        );
+    }
+
+    #[test]
+    fn status_tag_is_found_after_intro() {
+        let text = "Plan trip\n=========\n\nA week in Rome.\n#area/home\nStill #someday, sadly.\n\n## Goal\n\n#complete\n";
+        assert_eq!(
+            set_status(text, Status::InProgress).unwrap(),
+            "Plan trip\n=========\n\nA week in Rome.\n#area/home\nStill #in-progress, sadly.\n\n## Goal\n\n#complete\n"
+        );
+        assert_eq!(
+            set_status("# Plan trip\n\n## Goal\n\n#complete\n", Status::Someday),
+            None
+        );
+    }
+
+    #[test]
+    fn outcome_goes_before_actions() {
+        let text = "# Plan trip\n#complete\n\n## Goal\n\nSee Rome.\n\n## Actions\n\n### Complete\n\n- Go\n";
+        let expected = "# Plan trip\n#complete\n\n## Goal\n\nSee Rome.\n\n## Outcome\n\nSaw Rome. ✅ 2024-01-05\n\n## Actions\n\n### Complete\n\n- Go\n";
+        assert_eq!(set_outcome(text, "Saw Rome.", date()), expected);
+        assert_eq!(set_outcome(expected, "Saw Rome.", date()), expected);
+        assert_eq!(
+            set_outcome("# Plan trip\n#complete\n", "", date()),
+            "# Plan trip\n#complete\n\n## Outcome\n\n✅ 2024-01-05\n"
+        );
+    }
+
+    #[test]
+    fn nested_items_are_removed_with_their_children() {
+        let text = "# @home\n\n- Keep\n- Drop\n  more\n- Group\n  - Drop\n    - Child\n  - Keep\n";
+        assert_eq!(
+            remove_list_items(text, |item| item == "- Drop"),
+            "# @home\n\n- Keep\n- Group\n  - Keep\n"
+        );
+    }
+
+    mod keeps_order {
+        use super::*;
+        use crate::project::DEFER_MARKER;
+
+        /// A project with its actions in an order that only makes sense to whoever wrote them.
+        const TEXT: &str = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n- Book flights ^bbbbbb\n- Ask Sam ^cccccc\n\n### Upcoming\n\n- Water plants\n- Call airline ^dddddd\n- Buy adapter\n\n### Complete\n\n- Renew passport ^eeeeee\n- Pick dates\n";
+
+        #[test]
+        fn reordering_is_caught() {
+            assert!(keeps_order(TEXT, TEXT));
+            assert!(keeps_order(TEXT, &TEXT.replace("- Ask Sam ^cccccc\n", "")));
+            assert!(!keeps_order(
+                TEXT,
+                &TEXT.replace(
+                    "- Water plants\n- Call airline ^dddddd\n",
+                    "- Call airline ^dddddd\n- Water plants\n"
+                )
+            ));
+        }
+
+        #[test]
+        fn edits_leave_other_actions_in_order() {
+            let project = Project::parse("197001010000 Plan trip", TEXT).unwrap();
+            for (action, _) in project.actions.actions() {
+                let edited = [
+                    complete_action(TEXT, &project, action, date()),
+                    promote_action(TEXT, &project, action, date()),
+                    Some(remove_action(TEXT, action).0),
+                    Some(set_action_date(TEXT, action, DEFER_MARKER, date())),
+                    Some(clear_action_date(TEXT, action, DEFER_MARKER)),
+                    Some(annotate_action(TEXT, action, "uid", "abc")),
+                    Some(set_action_id(
+                        TEXT,
+                        action,
+                        &ActionId::new(String::from("ffffff")),
+                    )),
+                ];
+                for text in edited.iter().flatten() {
+                    assert!(keeps_order(TEXT, text), "{}", text);
+                }
+            }
+            for status in [
+                ActionStatus::Active,
+                ActionStatus::Waiting,
+                ActionStatus::Upcoming,
+                ActionStatus::Complete,
+            ] {
+                let text = add_actions(TEXT, &project, status, "- Print tickets\n");
+                assert!(keeps_order(TEXT, &text), "{}", text);
+            }
+        }
+
+        #[test]
+        fn removing_from_a_context_leaves_the_rest_in_order() {
+            let text = "# @errands\n\n- Post letter\n- Buy milk\n- Return books\n\n## Town\n\n- Get keys cut\n- Drop off parcel\n";
+            for dropped in ["- Post letter", "- Return books", "- Get keys cut"] {
+                let removed = remove_list_items(text, |item| item == dropped);
+                assert!(!removed.contains(dropped));
+                assert!(keeps_order(text, &removed), "{}", removed);
+            }
+        }
+    }
+
+    #[test]
+    fn checkbox_is_checked() {
+        assert_eq!(
+            mark_complete(
+                "- [ ] Pack ^aaaaaa\n",
+                Some(&ActionId::new(String::from("aaaaaa"))),
+                date()
+            ),
+            "- [x] Pack ✅ 2024-01-05 ^aaaaaa\n"
+        );
+    }
+}
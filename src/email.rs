@@ -0,0 +1,237 @@
+//! Reading the headers of email messages.
+
+use chrono::{DateTime, FixedOffset};
+use std::{fs, io::Error as IoError, path::Path};
+
+/// The headers of an email that matter for capturing it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Email {
+    pub subject: Option<String>,
+    pub from: Option<String>,
+    pub date: Option<DateTime<FixedOffset>>,
+}
+
+impl Email {
+    /// Parses the headers at the start of a message in RFC 5322 format, decoding any MIME encoded
+    /// words in them.
+    pub fn parse(text: &str) -> Self {
+        let mut email = Self::default();
+        for (name, value) in headers(text) {
+            match name.to_ascii_lowercase().as_str() {
+                "subject" => email.subject = Some(decode_words(&value)),
+                "from" => email.from = Some(decode_words(&value)),
+                "date" => email.date = parse_date(&value),
+                _ => {}
+            }
+        }
+        email
+    }
+
+    /// Reads the message at `path`.
+    pub fn load(path: &Path) -> Result<Self, IoError> {
+        let bytes = fs::read(path)?;
+        Ok(Self::parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    /// Whether none of the headers were found, as happens when the file isn't an email.
+    pub fn is_empty(&self) -> bool {
+        self.subject.is_none() && self.from.is_none() && self.date.is_none()
+    }
+
+    /// The sender's name, or their address if the message doesn't give a name.
+    pub fn sender(&self) -> Option<&str> {
+        let from = self.from.as_deref()?.trim();
+        let name = match from.find('<') {
+            Some(0) => from.trim_start_matches('<').trim_end_matches('>'),
+            Some(idx) => from[..idx].trim().trim_matches('"'),
+            None => from,
+        };
+        Some(name.trim()).filter(|n| !n.is_empty())
+    }
+}
+
+/// The headers before the first blank line, with folded lines joined back together.
+fn headers(text: &str) -> Vec<(String, String)> {
+    let mut headers = Vec::<(String, String)>::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            break;
+        }
+
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn parse_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    // Dates often end with a comment naming the time zone, like `(UTC)`, which chrono rejects.
+    let value = match value.find('(') {
+        Some(idx) => value[..idx].trim(),
+        None => value,
+    };
+    DateTime::parse_from_rfc2822(value).ok()
+}
+
+/// Decodes the MIME encoded words, like `=?UTF-8?B?...?=`, in a header value.
+///
+/// Words that can't be decoded are left as they are.
+fn decode_words(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut after_word = false;
+
+    while let Some(start) = rest.find("=?") {
+        let decoded = split_word(&rest[start..]).and_then(|(charset, encoding, text, len)| {
+            Some((decode_word(charset, encoding, text)?, start + len))
+        });
+
+        match decoded {
+            Some((decoded, end)) => {
+                // Whitespace between two encoded words isn't part of the text.
+                let between = &rest[..start];
+                if !(after_word && between.trim().is_empty()) {
+                    out.push_str(between);
+                }
+                out.push_str(&decoded);
+                rest = &rest[end..];
+                after_word = true;
+            }
+            None => {
+                out.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+                after_word = false;
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Splits the encoded word at the start of `s` into its charset, encoding, and encoded text, along
+/// with the length of the whole word.
+fn split_word(s: &str) -> Option<(&str, &str, &str, usize)> {
+    let mut parts = s.strip_prefix("=?")?.splitn(3, '?');
+    let charset = parts.next()?;
+    let encoding = parts.next()?;
+    let rest = parts.next()?;
+    let text_len = rest.find("?=")?;
+    let len = charset.len() + encoding.len() + text_len + 6;
+    Some((charset, encoding, &rest[..text_len], len))
+}
+
+fn decode_word(charset: &str, encoding: &str, text: &str) -> Option<String> {
+    let bytes = match encoding {
+        "B" | "b" => decode_base64(text)?,
+        "Q" | "q" => decode_q(text)?,
+        _ => return None,
+    };
+
+    match charset.to_ascii_lowercase().as_str() {
+        "utf-8" | "us-ascii" => String::from_utf8(bytes).ok(),
+        "iso-8859-1" | "latin1" => Some(bytes.into_iter().map(char::from).collect()),
+        _ => None,
+    }
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in text.bytes().filter(|&c| c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+fn decode_q(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(c) = iter.next() {
+        match c {
+            b'_' => bytes.push(b' '),
+            b'=' => {
+                let hex = [iter.next()?, iter.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            c => bytes.push(c),
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn headers_are_parsed() {
+        let text = "From: \"Alice Smith\" <alice@example.com>\r\nSubject: Contract\r\n for review\r\nDate: Fri, 5 Jan 2024 14:30:00 +0100 (CET)\r\n\r\nSubject: not a header\r\n";
+        let email = Email::parse(text);
+        assert_eq!(email.subject.as_deref(), Some("Contract for review"));
+        assert_eq!(email.sender(), Some("Alice Smith"));
+        assert_eq!(
+            email.date.map(|d| d.date_naive()),
+            NaiveDate::from_ymd_opt(2024, 1, 5)
+        );
+    }
+
+    #[test]
+    fn sender_without_name_is_address() {
+        let email = Email {
+            from: Some(String::from("<alice@example.com>")),
+            ..Email::default()
+        };
+        assert_eq!(email.sender(), Some("alice@example.com"));
+    }
+
+    #[test]
+    fn base64_words_are_decoded() {
+        assert_eq!(decode_words("=?UTF-8?B?Q2Fmw6k=?= menu"), "Café menu");
+    }
+
+    #[test]
+    fn adjacent_q_words_are_joined() {
+        assert_eq!(
+            decode_words("Re: =?iso-8859-1?Q?Caf=E9?= =?utf-8?q?_tonight?="),
+            "Re: Café tonight"
+        );
+    }
+
+    #[test]
+    fn unknown_words_are_kept() {
+        assert_eq!(decode_words("=?koi8-r?B?abc?= hi"), "=?koi8-r?B?abc?= hi");
+    }
+
+    #[test]
+    fn non_email_is_empty() {
+        assert!(Email::parse("just some notes\n").is_empty());
+    }
+}
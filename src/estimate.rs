@@ -0,0 +1,103 @@
+//! Amounts of time written like `45m`, `2h`, or `1h30m`, for how long actions will take.
+
+use std::{convert::TryFrom, error::Error, fmt, str::FromStr};
+
+/// A whole number of minutes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Estimate {
+    minutes: u32,
+}
+
+impl Estimate {
+    pub fn from_minutes(minutes: u32) -> Self {
+        Self { minutes }
+    }
+
+    pub fn minutes(&self) -> u32 {
+        self.minutes
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.minutes / 60, self.minutes % 60) {
+            (0, m) => write!(f, "{}m", m),
+            (h, 0) => write!(f, "{}h", h),
+            (h, m) => write!(f, "{}h{}m", h, m),
+        }
+    }
+}
+
+impl FromStr for Estimate {
+    type Err = InvalidEstimateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || InvalidEstimateError(s.to_string());
+        let (hours, rest) = match s.split_once('h') {
+            Some((hours, rest)) => (hours.parse::<u32>().map_err(|_| err())?, rest),
+            None => (0, s),
+        };
+        let minutes = match rest {
+            "" if s.ends_with('h') => 0,
+            _ => rest
+                .strip_suffix('m')
+                .and_then(|m| m.parse::<u32>().ok())
+                .ok_or_else(err)?,
+        };
+        hours
+            .checked_mul(60)
+            .and_then(|h| h.checked_add(minutes))
+            .map(Self::from_minutes)
+            .ok_or_else(err)
+    }
+}
+
+impl TryFrom<String> for Estimate {
+    type Error = InvalidEstimateError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidEstimateError(String);
+
+impl fmt::Display for InvalidEstimateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" is not a valid estimate, like \"45m\", \"2h\", or \"1h30m\"",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidEstimateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn units_are_parsed() {
+        assert_eq!("45m".parse(), Ok(Estimate::from_minutes(45)));
+        assert_eq!("2h".parse(), Ok(Estimate::from_minutes(120)));
+        assert_eq!("1h30m".parse(), Ok(Estimate::from_minutes(90)));
+    }
+
+    #[test]
+    fn invalid_estimates_are_rejected() {
+        assert!("".parse::<Estimate>().is_err());
+        assert!("h".parse::<Estimate>().is_err());
+        assert!("30".parse::<Estimate>().is_err());
+        assert!("1h30".parse::<Estimate>().is_err());
+    }
+
+    #[test]
+    fn estimate_is_displayed_compactly() {
+        assert_eq!(Estimate::from_minutes(90).to_string(), "1h30m");
+        assert_eq!(Estimate::from_minutes(120).to_string(), "2h");
+        assert_eq!(Estimate::from_minutes(45).to_string(), "45m");
+    }
+}
@@ -0,0 +1,254 @@
+//! Exporting projects and contexts as JSON, for other tools to read.
+//!
+//! The format is described by `schema::export`, and only changes in backwards-compatible ways
+//! unless `schema::VERSION` changes.
+
+use crate::{
+    context::{Action as ContextAction, Context, Group},
+    filter::Filter,
+    gtd::Documents,
+    markdown::{Alignment, Fragment},
+    project::{Action, ActionStatus, Priority, Project, Status},
+    schema,
+};
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use std::{error::Error, fmt, path::PathBuf};
+
+/// The project statuses, as they're written in exports.
+pub const STATUSES: [Status; 3] = [Status::Someday, Status::InProgress, Status::Complete];
+
+/// The action statuses, in the order of the sections they're listed in.
+pub const ACTION_STATUSES: [ActionStatus; 4] = [
+    ActionStatus::Active,
+    ActionStatus::Waiting,
+    ActionStatus::Upcoming,
+    ActionStatus::Complete,
+];
+
+/// The priorities, from lowest to highest.
+pub const PRIORITIES: [Priority; 6] = [
+    Priority::Lowest,
+    Priority::Low,
+    Priority::Normal,
+    Priority::Medium,
+    Priority::High,
+    Priority::Highest,
+];
+
+/// Checks that every document in `docs` could be read, since an export without the ones that
+/// couldn't would look like they'd been deleted.
+pub fn check_readable(docs: &Documents) -> Result<(), UnreadableError> {
+    let root_dir = docs.loader().root_dir();
+    let relative = |path: &PathBuf| path.strip_prefix(root_dir).unwrap_or(path).to_owned();
+    let conflicted = docs.conflicted().iter().map(|file| {
+        let why = format!("has unresolved conflicts on {}", file.lines());
+        (relative(&file.path), why)
+    });
+    let unreadable = docs
+        .unreadable()
+        .iter()
+        .map(|file| (relative(&file.path), file.error.clone()));
+    let files = conflicted.chain(unreadable).collect::<Vec<_>>();
+    match files.is_empty() {
+        true => Ok(()),
+        false => Err(UnreadableError { files }),
+    }
+}
+
+/// The projects and contexts in `docs` that match `filter`, sorted by name.
+///
+/// Projects are filtered as a whole, so a matching project is exported with all of its actions.
+pub fn export(docs: &Documents, filter: &Filter) -> Value {
+    let projects = filter.select(docs);
+    let contexts = docs
+        .contexts()
+        .filter(|c| filter.matches_context(docs, c))
+        .collect::<Vec<_>>();
+
+    json!({
+        "version": schema::VERSION,
+        "projects": projects.into_iter().map(project).collect::<Vec<_>>(),
+        "contexts": contexts.into_iter().map(context).collect::<Vec<_>>(),
+    })
+}
+
+/// The documents that couldn't be read, relative to the GTD folder, with why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnreadableError {
+    pub files: Vec<(PathBuf, String)>,
+}
+
+impl fmt::Display for UnreadableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "these documents can't be read, so they'd be missing from the export:"
+        )?;
+        for (path, why) in self.files.iter() {
+            write!(f, "\n  {}: {}", path.display(), why)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for UnreadableError {}
+
+fn project(project: &Project) -> Value {
+    json!({
+        "name": project.name.as_str(),
+        "id": project.id(),
+        "title": project.title(),
+        "status": project.status.tag(),
+        "tags": project.tags.iter().map(|t| t.as_str()).collect::<Vec<_>>(),
+        "goal": project.goal.as_ref().map(|g| g.without_tables().to_string()),
+        "info": project.info.as_ref().map(|i| i.without_tables().to_string()),
+        "tables": tables(project),
+        "footnotes": project
+            .footnotes
+            .iter()
+            .map(|f| json!({ "label": f.label, "text": f.body.to_string() }))
+            .collect::<Vec<_>>(),
+        "actions": project
+            .actions
+            .actions()
+            .map(|(a, s)| action(a, s))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// The tables in the goal and info of `project`, which are left out of their text.
+fn tables(project: &Project) -> Vec<Value> {
+    let sections = [("goal", &project.goal), ("info", &project.info)];
+    let mut tables = Vec::new();
+    for (section, fragment) in sections.iter() {
+        for table in fragment.iter().flat_map(|f| f.tables()) {
+            let cells = |row: &[Fragment]| row.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            tables.push(json!({
+                "section": section,
+                "alignments": table.alignments.iter().map(|a| alignment_name(*a)).collect::<Vec<_>>(),
+                "header": cells(&table.header),
+                "rows": table.rows.iter().map(|r| cells(r)).collect::<Vec<_>>(),
+            }));
+        }
+    }
+    tables
+}
+
+fn action(action: &Action, status: ActionStatus) -> Value {
+    json!({
+        "id": action.id.as_ref().map(|id| id.as_str()),
+        "text": action.text.to_string(),
+        "status": action_status_name(status),
+        "tags": action.tags.iter().map(|t| t.as_str()).collect::<Vec<_>>(),
+        "priority": priority_name(action.priority),
+        "due": date(action.due),
+        "deferred": date(action.deferred),
+        "completed": date(action.completed),
+        "created": date(action.created),
+        "who": action.who,
+        "issue": action.issue,
+        "moved": action.moved,
+        "estimate_minutes": action.estimate.map(|e| e.minutes()),
+    })
+}
+
+fn context(context: &Context) -> Value {
+    let actions = context
+        .grouped_actions()
+        .map(|(group, action)| {
+            let group = group.map(Group::title);
+            match action {
+                ContextAction::Literal(text) => json!({
+                    "text": text.to_string(),
+                    "group": group,
+                }),
+                ContextAction::UnresolvedReference(block_ref) => json!({
+                    "text": block_ref.to_string(),
+                    "group": group,
+                }),
+                ContextAction::Reference(action_ref) => json!({
+                    "project": action_ref.project_name.as_str(),
+                    "action_id": action_ref.action_id.as_str(),
+                    "group": group,
+                }),
+            }
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "name": context.name.as_str(),
+        "actions": actions,
+    })
+}
+
+fn date(date: Option<NaiveDate>) -> Option<String> {
+    date.map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+/// The name of an action status, like `active`.
+pub fn action_status_name(status: ActionStatus) -> &'static str {
+    match status {
+        ActionStatus::Active => "active",
+        ActionStatus::Waiting => "waiting",
+        ActionStatus::Upcoming => "upcoming",
+        ActionStatus::Complete => "complete",
+    }
+}
+
+/// The name of a priority, like `high`.
+pub fn alignment_name(alignment: Alignment) -> Option<&'static str> {
+    match alignment {
+        Alignment::None => None,
+        Alignment::Left => Some("left"),
+        Alignment::Center => Some("center"),
+        Alignment::Right => Some("right"),
+    }
+}
+
+pub fn priority_name(priority: Priority) -> &'static str {
+    match priority {
+        Priority::Lowest => "lowest",
+        Priority::Low => "low",
+        Priority::Normal => "normal",
+        Priority::Medium => "medium",
+        Priority::High => "high",
+        Priority::Highest => "highest",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+    use std::{fs, path::Path};
+
+    #[test]
+    fn unreadable_documents_are_reported() {
+        let dir = TempDir::new("export");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        assert_eq!(check_readable(&docs), Ok(()));
+
+        fs::write(
+            dir.join("Projects/202401020000 Fix bike.md"),
+            "# Fix bike\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let err = check_readable(&docs).unwrap_err();
+        assert_eq!(
+            err.files,
+            [(
+                Path::new("Projects/202401020000 Fix bike.md").to_owned(),
+                String::from("Project is missing status")
+            )]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Making filenames and paths that work on every platform, and in Obsidian links.
+//!
+//! Titles become filenames when projects are created, so characters that Windows doesn't allow in
+//! filenames, or that would end a wiki link early, are taken out of them first. The title in the
+//! project's heading is left as it was written.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Characters that can't be in a filename on Windows, or in the name of a linked note in Obsidian.
+const INVALID: &[char] = &['<', '>', ':', '"', '|', '?', '*', '#', '^', '[', ']'];
+
+/// Names that Windows reserves for devices, even with an extension.
+const RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turns `title` into something that can be used in a filename anywhere. Slashes become dashes,
+/// other characters that aren't allowed are taken out, and trailing dots and spaces, which Windows
+/// drops, are trimmed.
+pub fn sanitize(title: &str) -> String {
+    let cleaned = title
+        .chars()
+        .filter(|c| !INVALID.contains(c) && !c.is_control())
+        .map(|c| if c == '/' || c == '\\' { '-' } else { c })
+        .collect::<String>();
+    let mut cleaned = cleaned
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(&['.', ' '][..])
+        .to_string();
+    if cleaned.is_empty() {
+        cleaned = String::from("Untitled");
+    }
+    if RESERVED.iter().any(|r| r.eq_ignore_ascii_case(&cleaned)) {
+        cleaned.push('_');
+    }
+    cleaned
+}
+
+/// `path` with `/` between its parts on every platform, for output that other programs read, like
+/// JSON and URIs.
+pub fn to_slash(path: &Path) -> String {
+    let mut out = String::new();
+    for component in path.components() {
+        if !out.is_empty() && !out.ends_with('/') {
+            out.push('/');
+        }
+        match component {
+            Component::RootDir if out.is_empty() => out.push('/'),
+            Component::RootDir => {}
+            c => out.push_str(&c.as_os_str().to_string_lossy()),
+        }
+    }
+    out
+}
+
+/// `path` in a form that can be longer than 260 characters on Windows, where longer paths otherwise
+/// can't be opened. Other platforms don't have the limit, so the path is left as it is.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> PathBuf {
+    const MAX_PATH: usize = 260;
+    let text = path.as_os_str().to_string_lossy();
+    // UNC paths and ones that are already verbatim are left alone, as are relative paths, which
+    // can't be made verbatim.
+    if text.len() < MAX_PATH || text.starts_with(r"\\") || !path.is_absolute() {
+        return path.to_owned();
+    }
+    PathBuf::from(format!(r"\\?\{}", text.replace('/', r"\")))
+}
+
+/// `path` in a form that can be longer than 260 characters on Windows, where longer paths otherwise
+/// can't be opened. Other platforms don't have the limit, so the path is left as it is.
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> PathBuf {
+    path.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_characters_are_taken_out_of_titles() {
+        assert_eq!(sanitize("Q3: Plan budget?"), "Q3 Plan budget");
+        assert_eq!(
+            sanitize("A/B test the \\ landing page"),
+            "A-B test the - landing page"
+        );
+        assert_eq!(sanitize("Fix #12 [urgent]"), "Fix 12 urgent");
+        assert_eq!(sanitize("Say \"hi\" | wave *"), "Say hi wave");
+        assert_eq!(sanitize("Write report..."), "Write report");
+        assert_eq!(sanitize("Café ☕"), "Café ☕");
+    }
+
+    #[test]
+    fn titles_that_windows_reserves_are_changed() {
+        assert_eq!(sanitize("con"), "con_");
+        assert_eq!(sanitize("LPT1"), "LPT1_");
+        assert_eq!(sanitize("Console"), "Console");
+        assert_eq!(sanitize("???"), "Untitled");
+    }
+
+    #[test]
+    fn paths_are_joined_with_slashes() {
+        assert_eq!(
+            to_slash(Path::new("Projects/Plan trip.md")),
+            "Projects/Plan trip.md"
+        );
+        assert_eq!(to_slash(Path::new("/notes/Projects")), "/notes/Projects");
+        assert_eq!(
+            to_slash(&Path::new("Projects").join("a.md")),
+            "Projects/a.md"
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_paths_are_joined_with_slashes() {
+        assert_eq!(
+            to_slash(Path::new(r"C:\notes\Projects")),
+            "C:/notes/Projects"
+        );
+        assert_eq!(to_slash(Path::new(r"Projects\a.md")), "Projects/a.md");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn long_paths_are_verbatim() {
+        let short = Path::new(r"C:\notes\Projects\a.md");
+        assert_eq!(long_path(short), short);
+        let long = PathBuf::from(format!(r"C:\notes/{}.md", "a".repeat(300)));
+        let verbatim = long_path(&long);
+        assert!(verbatim.to_string_lossy().starts_with(r"\\?\C:\notes\aaa"));
+    }
+}
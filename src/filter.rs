@@ -0,0 +1,154 @@
+//! Narrowing down what listings show, shared by `next`, `list`, and `export`.
+//!
+//! Each part of a filter can be given more than once. A thing matches a part if it matches any of
+//! the values given for it, and matches the filter if it matches every part that's given. Excluded
+//! tags are the exception: anything with any of them is left out.
+
+use crate::{
+    context::{Action as ContextAction, Context, Name as ContextName},
+    gtd::Documents,
+    project::{Action, Project},
+    selector::Glob,
+    tag::Tag,
+};
+
+/// What to show in a listing. An empty filter matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Contexts to show actions from. Aliases match the contexts they stand for.
+    pub contexts: Vec<ContextName>,
+    /// Matched against each project's title and its full name.
+    pub projects: Vec<Glob>,
+    /// Tags that actions or their projects need one of, including tags nested under them.
+    pub tags: Vec<Tag>,
+    /// Tags that actions and their projects can't have, including tags nested under them.
+    pub exclude_tags: Vec<Tag>,
+}
+
+impl Filter {
+    /// Whether nothing has been set, which matches everything.
+    pub fn is_empty(&self) -> bool {
+        self.contexts.is_empty()
+            && self.projects.is_empty()
+            && self.tags.is_empty()
+            && self.exclude_tags.is_empty()
+    }
+
+    /// Whether `context` is one of the contexts given, going by canonical names, which takes
+    /// `docs` to resolve aliases.
+    pub fn matches_context(&self, docs: &Documents, context: &Context) -> bool {
+        let canonical = docs.canonical_context_name(&context.name);
+        self.contexts.is_empty()
+            || self
+                .contexts
+                .iter()
+                .any(|c| docs.canonical_context_name(c) == canonical)
+    }
+
+    /// Whether `project` matches, going by its own name and tags.
+    ///
+    /// If contexts are given, the project also needs an action in one of them, which takes `docs`
+    /// to check.
+    pub fn matches_project(&self, docs: &Documents, project: &Project) -> bool {
+        let is_in_context = || {
+            docs.contexts()
+                .filter(|c| self.matches_context(docs, c))
+                .flat_map(|c| c.actions())
+                .filter_map(ContextAction::to_action_ref)
+                .any(|r| r.project_name == project.name)
+        };
+        self.matches_project_name(project)
+            && (self.tags.is_empty() || self.tags.iter().any(|t| project.has_tag(t)))
+            && !self.exclude_tags.iter().any(|t| project.has_tag(t))
+            && (self.contexts.is_empty() || is_in_context())
+    }
+
+    /// Whether `action` in `project` matches, where tags on either count.
+    pub fn matches_action(&self, project: &Project, action: &Action) -> bool {
+        let has_tag = |t: &Tag| action.has_tag(t) || project.has_tag(t);
+        self.matches_project_name(project)
+            && (self.tags.is_empty() || self.tags.iter().any(has_tag))
+            && !self.exclude_tags.iter().any(has_tag)
+    }
+
+    /// Whether an action written directly in a context matches. Since it has no project or tags,
+    /// it only matches if neither are asked for.
+    pub fn matches_literal(&self) -> bool {
+        self.projects.is_empty() && self.tags.is_empty()
+    }
+
+    /// The projects in `docs` that match, sorted by name.
+    pub fn select<'a>(&self, docs: &'a Documents) -> Vec<&'a Project> {
+        docs.projects()
+            .filter(|p| self.matches_project(docs, p))
+            .collect()
+    }
+
+    fn matches_project_name(&self, project: &Project) -> bool {
+        self.projects.is_empty()
+            || self
+                .projects
+                .iter()
+                .any(|g| g.matches(project.title()) || g.matches(project.name.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(tags: &str) -> Project {
+        Project::parse(
+            "197001010000 Do taxes",
+            &format!(
+                "# Do taxes\n#in-progress {}\n\n## Actions\n\n### Active\n\n- Find receipts #phone\n",
+                tags
+            ),
+        )
+        .unwrap()
+    }
+
+    fn tag(s: &str) -> Tag {
+        Tag::new(s).unwrap()
+    }
+
+    #[test]
+    fn values_of_a_part_are_alternatives() {
+        let project = project("#area/home");
+        let (action, _) = project.actions.actions().next().unwrap();
+        let filter = Filter {
+            projects: vec![Glob::new("Plan*"), Glob::new("Tax*"), Glob::new("Do tax*")],
+            tags: vec![tag("#work"), tag("#phone")],
+            ..Filter::default()
+        };
+        assert!(filter.matches_action(&project, action));
+        assert!(!filter.matches_literal());
+    }
+
+    #[test]
+    fn parts_must_all_match() {
+        let project = project("#area/home");
+        let (action, _) = project.actions.actions().next().unwrap();
+        let filter = Filter {
+            projects: vec![Glob::new("Do*")],
+            tags: vec![tag("#work")],
+            ..Filter::default()
+        };
+        assert!(!filter.matches_action(&project, action));
+    }
+
+    #[test]
+    fn excluded_tags_on_project_or_action_exclude() {
+        let project = project("#area/home #low-energy");
+        let (action, _) = project.actions.actions().next().unwrap();
+        let exclude = |t: &str| Filter {
+            exclude_tags: vec![tag(t)],
+            ..Filter::default()
+        };
+        assert!(!exclude("#low-energy").matches_action(&project, action));
+        assert!(!exclude("#phone").matches_action(&project, action));
+        assert!(!exclude("#area").matches_action(&project, action));
+        assert!(exclude("#work").matches_action(&project, action));
+        assert!(exclude("#work").matches_literal());
+    }
+}
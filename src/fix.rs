@@ -0,0 +1,123 @@
+//! Fixing the problems that validation rules find.
+//!
+//! A rule that can fix its problems has a [`Fix`], which looks at each problem the rule found and
+//! says how to change the GTD folder to fix it, as an [`Edit`]. Nothing is changed until the edits
+//! are [`apply`]'d, which makes all of them at once, so `gtd undo` can take them back together.
+
+use crate::{
+    gtd::{Documents, Loader},
+    transaction::{Transaction, TransactionError},
+    validate::Diagnostic,
+};
+use std::path::PathBuf;
+
+/// A change to the GTD folder that fixes a problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    /// What the edit does, like `Archived 202401010000 Plan trip.`
+    pub description: String,
+    pub change: FileChange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    /// Replaces the text of the file at `path`, which was `old` when the problem was found, with
+    /// `new`.
+    Write {
+        path: PathBuf,
+        old: String,
+        new: String,
+    },
+    /// Moves the file at `from` to `to`.
+    Move { from: PathBuf, to: PathBuf },
+}
+
+/// Fixes the problems that a rule finds.
+pub trait Fix {
+    /// The edit that fixes `diagnostic`, which was found in `docs`, or `None` if it can't be fixed
+    /// automatically.
+    fn fix(&self, docs: &Documents, diagnostic: &Diagnostic) -> Option<Edit>;
+}
+
+impl<F> Fix for F
+where
+    F: Fn(&Documents, &Diagnostic) -> Option<Edit>,
+{
+    fn fix(&self, docs: &Documents, diagnostic: &Diagnostic) -> Option<Edit> {
+        self(docs, diagnostic)
+    }
+}
+
+/// Makes `edits` to the GTD folder of `loader` together, returning the descriptions of the ones
+/// that were made.
+///
+/// Each edit was worked out from the folder as it was before any of them, so one that writes a
+/// file an earlier edit already changed is skipped, and left for the next time problems are fixed.
+pub fn apply<'e, I>(loader: &Loader, edits: I) -> Result<Vec<String>, TransactionError>
+where
+    I: IntoIterator<Item = &'e Edit>,
+{
+    loader.ensure_files()?;
+    let mut transaction = Transaction::new(loader, "validate --fix");
+    let mut made = Vec::new();
+    for edit in edits {
+        match &edit.change {
+            FileChange::Write { path, old, new } => {
+                if transaction.read(path)?.as_ref() != Some(old) {
+                    continue;
+                }
+                transaction.write(path, new.as_str())?;
+            }
+            FileChange::Move { from, to } => transaction.rename(from, to)?,
+        }
+        made.push(edit.description.clone());
+    }
+    transaction.commit()?;
+    Ok(made)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+    use std::fs;
+
+    #[test]
+    fn stale_writes_are_skipped() {
+        let dir = TempDir::new("fix");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let path = dir.join("Projects/202401010000 Plan trip.md");
+        let old = "# Plan trip\n#in-progress\n";
+        fs::write(&path, old).unwrap();
+        let loader = Loader::from_config(dir.to_path_buf(), &Config::default());
+
+        let write = |tag: &str| Edit {
+            description: format!("Tagged {}.", tag),
+            change: FileChange::Write {
+                path: path.clone(),
+                old: old.to_string(),
+                new: format!("# Plan trip\n#in-progress #{}\n", tag),
+            },
+        };
+        let moved = dir.join("Archive/202401010000 Plan trip.md");
+        let archive = Edit {
+            description: String::from("Archived."),
+            change: FileChange::Move {
+                from: path.clone(),
+                to: moved.clone(),
+            },
+        };
+        let edits = [write("home"), write("work"), archive];
+        assert_eq!(
+            apply(&loader, edits.iter()).unwrap(),
+            vec!["Tagged home.", "Archived."]
+        );
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(moved).unwrap(),
+            "# Plan trip\n#in-progress #home\n"
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! YAML frontmatter at the start of documents.
+//!
+//! Only flat `key: value` entries are understood. Other lines are kept as they are so that
+//! rewriting the frontmatter doesn't lose anything.
+
+use std::fmt;
+
+const DELIMITER: &str = "---";
+
+/// The frontmatter of a document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frontmatter {
+    lines: Vec<String>,
+}
+
+impl Frontmatter {
+    /// Splits the frontmatter from the start of `text`, returning it along with the byte offset
+    /// where the rest of the document starts.
+    ///
+    /// If `text` doesn't start with frontmatter, it's empty and the offset is 0.
+    pub fn parse(text: &str) -> (Self, usize) {
+        let mut lines = text.split_inclusive('\n');
+        let mut offset = match lines.next() {
+            Some(first) if first.trim_end() == DELIMITER => first.len(),
+            _ => return (Self::default(), 0),
+        };
+
+        let mut contents = Vec::new();
+        for line in lines {
+            offset += line.len();
+            if line.trim_end() == DELIMITER {
+                let lines = contents.into_iter().map(String::from).collect();
+                return (Self { lines }, offset);
+            }
+            contents.push(line.trim_end_matches(&['\r', '\n'][..]));
+        }
+
+        // An unclosed block isn't frontmatter.
+        (Self::default(), 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The value of `key`, with surrounding quotes removed.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines
+            .iter()
+            .find_map(|l| entry(l).filter(|(k, _)| *k == key))
+            .map(|(_, v)| unquote(v))
+    }
+
+    /// Sets `key` to `value`, replacing its existing entry or adding a new one at the end.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let line = format!("{}: {}", key, value);
+        match self
+            .lines
+            .iter_mut()
+            .find(|l| entry(l).is_some_and(|(k, _)| k == key))
+        {
+            Some(existing) => *existing = line,
+            None => self.lines.push(line),
+        }
+    }
+
+    /// Removes the entry for `key`, if there is one.
+    pub fn remove(&mut self, key: &str) {
+        self.lines
+            .retain(|l| entry(l).is_none_or(|(k, _)| k != key));
+    }
+
+    /// Replaces the frontmatter of `text` with this one, leaving the rest of the text untouched.
+    pub fn replace_in(&self, text: &str) -> String {
+        let (_, offset) = Self::parse(text);
+        let body = &text[offset..];
+        if self.is_empty() {
+            return body.to_string();
+        }
+        format!("{}{}", self, body)
+    }
+}
+
+impl fmt::Display for Frontmatter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", DELIMITER)?;
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+        writeln!(f, "{}", DELIMITER)
+    }
+}
+
+/// Splits a top-level `key: value` line.
+fn entry(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with(char::is_whitespace) || line.starts_with('#') {
+        return None;
+    }
+    let (key, value) = line.split_once(':')?;
+    Some((key.trim(), value.trim()))
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(v) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return v;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "---\nprogress: 50\ntitle: \"Trip\"\n---\n# Trip\n";
+
+    mod parse {
+        use super::*;
+
+        #[test]
+        fn text_without_frontmatter_is_empty() {
+            let (frontmatter, offset) = Frontmatter::parse("# Trip\n");
+            assert!(frontmatter.is_empty());
+            assert_eq!(offset, 0);
+        }
+
+        #[test]
+        fn offset_is_after_closing_delimiter() {
+            let (_, offset) = Frontmatter::parse(TEXT);
+            assert_eq!(&TEXT[offset..], "# Trip\n");
+        }
+
+        #[test]
+        fn values_are_unquoted() {
+            let (frontmatter, _) = Frontmatter::parse(TEXT);
+            assert_eq!(frontmatter.get("progress"), Some("50"));
+            assert_eq!(frontmatter.get("title"), Some("Trip"));
+        }
+
+        #[test]
+        fn unclosed_frontmatter_is_ignored() {
+            let (frontmatter, offset) = Frontmatter::parse("---\nprogress: 50\n# Trip\n");
+            assert!(frontmatter.is_empty());
+            assert_eq!(offset, 0);
+        }
+
+        #[test]
+        fn nested_values_arent_entries() {
+            let (frontmatter, _) = Frontmatter::parse("---\naliases:\n  - a: b\n---\n");
+            assert_eq!(frontmatter.get("a"), None);
+            assert_eq!(frontmatter.get("aliases"), Some(""));
+        }
+    }
+
+    mod replace_in {
+        use super::*;
+
+        #[test]
+        fn existing_entry_is_updated() {
+            let (mut frontmatter, _) = Frontmatter::parse(TEXT);
+            frontmatter.set("progress", "75");
+            assert_eq!(
+                frontmatter.replace_in(TEXT),
+                "---\nprogress: 75\ntitle: \"Trip\"\n---\n# Trip\n"
+            );
+        }
+
+        #[test]
+        fn frontmatter_is_added_to_text_without_it() {
+            let mut frontmatter = Frontmatter::default();
+            frontmatter.set("progress", "0");
+            assert_eq!(
+                frontmatter.replace_in("# Trip\n"),
+                "---\nprogress: 0\n---\n# Trip\n"
+            );
+        }
+
+        #[test]
+        fn empty_frontmatter_is_removed() {
+            let (mut frontmatter, _) = Frontmatter::parse("---\nprogress: 50\n---\n# Trip\n");
+            frontmatter.remove("progress");
+            assert_eq!(
+                frontmatter.replace_in("---\nprogress: 50\n---\n# Trip\n"),
+                "# Trip\n"
+            );
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! A thin wrapper around the `git` command, for committing the changes that commands make when the
+//! GTD folder is in a repository.
+//!
+//! Only the files a command touched are committed. Since gtd doesn't keep track of what it writes,
+//! the changed files in the folder are compared before and after it runs, so edits that were
+//! already there are left alone unless the command changed those files too.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    error::Error,
+    fmt, fs,
+    hash::{Hash, Hasher},
+    io::{Error as IoError, ErrorKind},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// What the messages of the commits gtd makes start with.
+pub const COMMIT_PREFIX: &str = "gtd: ";
+
+/// The folder gtd keeps its own state in, which isn't committed.
+const STATE_DIRNAME: &str = ".gtd";
+
+/// Whether `dir` is in a git repository.
+pub fn is_repo(dir: &Path) -> bool {
+    git(dir, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|o| o.status.success())
+}
+
+/// The files in a folder that differed from the last commit at some point, and what they held.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    root_dir: PathBuf,
+    /// A hash of each changed file, or `None` if it was deleted.
+    changed: HashMap<PathBuf, Option<u64>>,
+}
+
+impl Snapshot {
+    /// Notes which files in `root_dir` differ from the last commit, and what's in them.
+    pub fn take(root_dir: &Path) -> Result<Self, GitError> {
+        let changed = changed_files(root_dir)?
+            .into_iter()
+            .map(|path| {
+                let hash = hash_file(&path);
+                (path, hash)
+            })
+            .collect();
+        Ok(Self {
+            root_dir: root_dir.to_owned(),
+            changed,
+        })
+    }
+
+    /// The files that have changed since the snapshot was taken, sorted.
+    pub fn touched(&self) -> Result<Vec<PathBuf>, GitError> {
+        let now = changed_files(&self.root_dir)?;
+        let mut touched = now
+            .iter()
+            .filter(|path| self.changed.get(*path) != Some(&hash_file(path)))
+            .cloned()
+            .collect::<Vec<_>>();
+        // Files that changed back to how they were in the last commit, like after an undo.
+        touched.extend(self.changed.keys().filter(|p| !now.contains(p)).cloned());
+        touched.sort();
+        Ok(touched)
+    }
+
+    /// Stages and commits the files that have changed since the snapshot was taken, with the
+    /// message `message` after `COMMIT_PREFIX`. Returns whether anything was committed.
+    pub fn commit(&self, message: &str) -> Result<bool, GitError> {
+        let touched = self.touched()?;
+        if touched.is_empty() {
+            return Ok(false);
+        }
+        run(&self.root_dir, &["add", "--all", "--"], &touched)?;
+        // Other changes that were already staged are left out, since only the paths given are
+        // committed.
+        let message = format!("{}{}", COMMIT_PREFIX, message);
+        run(
+            &self.root_dir,
+            &["commit", "--quiet", "-m", &message, "--"],
+            &touched,
+        )?;
+        Ok(true)
+    }
+}
+
+/// A commit made by gtd.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub hash: String,
+    /// When it was made, like `2024-01-05 14:00`.
+    pub date: String,
+    /// Its message, without `COMMIT_PREFIX`.
+    pub message: String,
+}
+
+/// The last `count` commits gtd made in the repository of `root_dir` that touched the folder,
+/// newest first.
+pub fn log(root_dir: &Path, count: usize) -> Result<Vec<Commit>, GitError> {
+    let output = run(
+        root_dir,
+        &[
+            "log",
+            &format!("--max-count={}", count),
+            "--fixed-strings",
+            &format!("--grep={}", COMMIT_PREFIX),
+            "--date=format:%Y-%m-%d %H:%M",
+            "--format=%h%x09%ad%x09%s",
+            "--",
+            ".",
+        ],
+        &[],
+    )?;
+    let commits = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (hash, date, subject) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(Commit {
+                hash: hash.to_string(),
+                date: date.to_string(),
+                message: subject.strip_prefix(COMMIT_PREFIX)?.to_string(),
+            })
+        })
+        .collect();
+    Ok(commits)
+}
+
+/// The files in `root_dir` that differ from the last commit, including untracked ones, except
+/// gtd's own state.
+fn changed_files(root_dir: &Path) -> Result<Vec<PathBuf>, GitError> {
+    let top = run(root_dir, &["rev-parse", "--show-toplevel"], &[])?;
+    let top = PathBuf::from(String::from_utf8_lossy(&top.stdout).trim_end());
+    let output = run(
+        root_dir,
+        &[
+            "status",
+            "--porcelain",
+            "-z",
+            "--untracked-files=all",
+            "--no-renames",
+            "--",
+            ".",
+        ],
+        &[],
+    )?;
+    let state_dir = root_dir.canonicalize()?.join(STATE_DIRNAME);
+    let files = output
+        .stdout
+        .split(|b| *b == 0)
+        // Each entry is two status letters and a space, then the path from the top of the repo.
+        .filter_map(|entry| entry.get(3..))
+        .filter(|path| !path.is_empty())
+        .map(|path| top.join(String::from_utf8_lossy(path).as_ref()))
+        .filter(|path| !path.starts_with(&state_dir))
+        .collect();
+    Ok(files)
+}
+
+fn hash_file(path: &Path) -> Option<u64> {
+    let bytes = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<Output, IoError> {
+    Command::new("git").args(args).current_dir(dir).output()
+}
+
+/// Runs git with `args` and then `paths` in `dir`, failing if it does.
+fn run(dir: &Path, args: &[&str], paths: &[PathBuf]) -> Result<Output, GitError> {
+    let output = Command::new("git")
+        .args(args)
+        .args(paths)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => GitError::NotInstalled,
+            _ => GitError::IoError(e),
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Failed(stderr.trim().to_string()));
+    }
+    Ok(output)
+}
+
+#[derive(Debug)]
+pub enum GitError {
+    IoError(IoError),
+    NotInstalled,
+    /// Git failed, with this error message.
+    Failed(String),
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::NotInstalled => write!(f, "git isn't installed"),
+            Self::Failed(message) => write!(f, "git failed: {}", message),
+        }
+    }
+}
+
+impl Error for GitError {}
+
+impl From<IoError> for GitError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    #[test]
+    fn only_touched_files_are_committed() {
+        let dir = TempDir::new("git");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        let init = |args: &[&str]| run(&dir, args, &[]).unwrap();
+        init(&["init", "--quiet"]);
+        init(&["config", "user.name", "gtd"]);
+        init(&["config", "user.email", "gtd@example.com"]);
+        fs::write(dir.join("Inbox.md"), "- Call mom\n").unwrap();
+        fs::write(dir.join("Projects/Trip.md"), "# Trip\n").unwrap();
+        init(&["add", "."]);
+        init(&["commit", "--quiet", "-m", "Start"]);
+
+        // An edit from before the command, which shouldn't be committed.
+        fs::write(dir.join("Projects/Trip.md"), "# Trip\n#someday\n").unwrap();
+        let snapshot = Snapshot::take(&dir).unwrap();
+        fs::write(dir.join("Inbox.md"), "- Call mom\n- Buy milk\n").unwrap();
+        fs::create_dir_all(dir.join(".gtd")).unwrap();
+        fs::write(dir.join(".gtd/undo.json"), "{}").unwrap();
+        assert_eq!(
+            snapshot.touched().unwrap(),
+            [dir.canonicalize().unwrap().join("Inbox.md")]
+        );
+        assert!(snapshot.commit("capture 'Buy milk'").unwrap());
+        assert!(!Snapshot::take(&dir).unwrap().commit("nothing").unwrap());
+
+        let log = log(&dir, 10).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].message, "capture 'Buy milk'");
+        let status = run(&dir, &["status", "--porcelain"], &[]).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&status.stdout),
+            " M Projects/Trip.md\n?? .gtd/\n"
+        );
+    }
+}
@@ -1,52 +1,143 @@
 use crate::{
-    context::{Context, Name as ContextName, ParseError as ContextParseError},
-    project::{Name as ProjectName, ParseError as ProjectParseError, Project},
+    action_list::{self, ActionList},
+    config::{Config, ContextConfig, ContextLayout, ContextOrder, ProgressConfig},
+    conflict::ConflictedFile,
+    context::{
+        Action as ContextAction, Context, Name as ContextName, ParseError as ContextParseError,
+    },
+    diff::{self, WriteMode},
+    filename,
+    frontmatter::Frontmatter,
+    parser::HeadingDepths,
+    progress::Progress,
+    project::{
+        Action, ActionRef, ActionStatus, Name as ProjectName, NameScheme,
+        ParseError as ProjectParseError, Project, Status as ProjectStatus,
+    },
+    references::{Reference, ReferenceIndex},
+    resolve::{self, ResolveError},
+    single_file::{self, Kind},
+    walk::Ignore,
 };
+use chrono::Local;
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
     convert::AsRef,
     error::Error,
     fmt, fs,
-    io::Error as IoError,
+    io::{Error as IoError, ErrorKind},
     path::{Path, PathBuf},
 };
 #[derive(Debug)]
 pub struct Documents {
     loader: Loader,
-    projects: HashMap<ProjectName, Project>,
-    contexts: HashMap<ContextName, Context>,
+    projects: BTreeMap<ProjectName, Project>,
+    misnamed_projects: Vec<String>,
+    contexts: BTreeMap<ContextName, Context>,
+    context_config: ContextConfig,
+    progress_config: ProgressConfig,
+    references: ReferenceIndex,
+    /// The documents that weren't read because they have unresolved conflicts.
+    conflicted: Vec<ConflictedFile>,
+    /// The documents that weren't read because they aren't valid projects or contexts.
+    unreadable: Vec<UnreadableFile>,
 }
 
 impl Documents {
-    pub fn load<P: AsRef<Path>>(cur_dir: P) -> Option<Self> {
+    pub fn load<P: AsRef<Path>>(cur_dir: P, config: &Config) -> Option<Self> {
         let cur_dir = cur_dir.as_ref();
-        let loader = Loader::new(cur_dir.to_owned());
-
-        let projects = loader
-            .all_project_names()
-            .ok()?
-            .map(|name| {
-                let project = loader.load_project(&name).unwrap();
-                (name, project)
-            })
-            .collect();
-
-        let contexts = loader
-            .all_context_names()
-            .ok()?
-            .map(|name| {
-                let context = loader.load_context(&name).unwrap();
-                (name, context)
-            })
-            .collect();
-
-        Some(Self {
+        let loader = Loader::from_config(cur_dir.to_owned(), config);
+        let mut docs = Self {
             loader,
-            projects,
-            contexts,
-        })
+            projects: BTreeMap::new(),
+            misnamed_projects: Vec::new(),
+            contexts: BTreeMap::new(),
+            context_config: config.contexts.clone(),
+            progress_config: config.progress.clone(),
+            references: ReferenceIndex::default(),
+            conflicted: Vec::new(),
+            unreadable: Vec::new(),
+        };
+        docs.reload()?;
+        Some(docs)
     }
 
+    /// Reads the projects and contexts again, with the contexts from either their files or the
+    /// action list, depending on the layout in `[contexts]`. Links are only re-indexed for files
+    /// that changed since they were last read.
+    pub fn reload(&mut self) -> Option<()> {
+        let loader = &self.loader;
+        let mut progress = Progress::new(&self.progress_config);
+        let mut projects = BTreeMap::new();
+        let mut misnamed_projects = Vec::new();
+        let mut conflicted = Vec::new();
+        let mut unreadable = Vec::new();
+        let names = loader.all_project_names().ok()?.collect::<Vec<_>>();
+        progress.start("Reading projects", Some(names.len()));
+        for name in names {
+            progress.advance();
+            match loader.load_project_file(name) {
+                Ok(project) => {
+                    projects.insert(project.name.clone(), project);
+                }
+                Err(SkippedFile::Misnamed(filename)) => misnamed_projects.push(filename),
+                Err(SkippedFile::Conflicted(file)) => conflicted.push(file),
+                Err(SkippedFile::Unreadable(file)) => unreadable.push(file),
+            }
+        }
+
+        let mut contexts = BTreeMap::new();
+        let loaded = match self.context_config.layout {
+            ContextLayout::Files => {
+                let names = loader.all_context_names().ok()?.collect::<Vec<_>>();
+                progress.start("Reading contexts", Some(names.len()));
+                names
+                    .into_iter()
+                    .map(|name| {
+                        progress.advance();
+                        (loader.context_path(&name), loader.load_context(&name))
+                    })
+                    .collect()
+            }
+            ContextLayout::List => {
+                let path = action_list::path(loader.root_dir());
+                ActionList::load(loader).map_or_else(
+                    |e| vec![(path.clone(), Err(e))],
+                    |list| {
+                        let contexts = list.contexts.into_iter();
+                        contexts.map(|c| (path.clone(), Ok(c))).collect()
+                    },
+                )
+            }
+        };
+        progress.finish();
+        for (path, context) in loaded {
+            match context {
+                Ok(context) => {
+                    contexts.insert(context.name.clone(), context);
+                }
+                Err(LoadContextError::Conflicted(file)) => conflicted.push(file),
+                Err(e) => unreadable.push(UnreadableFile {
+                    path: loader.document_path(path),
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        self.references.refresh(loader.root_dir()).ok()?;
+        self.projects = projects;
+        self.misnamed_projects = misnamed_projects;
+        self.contexts = contexts;
+        self.conflicted = conflicted;
+        self.unreadable = unreadable;
+        Some(())
+    }
+
+    pub fn loader(&self) -> &Loader {
+        &self.loader
+    }
+
+    /// The projects, sorted by name.
     pub fn projects(&self) -> impl Iterator<Item = &Project> {
         self.projects.values()
     }
@@ -55,76 +146,610 @@ impl Documents {
         self.projects.get(name)
     }
 
+    /// The projects with `status`.
+    pub fn projects_with_status(&self, status: ProjectStatus) -> impl Iterator<Item = &Project> {
+        self.projects().filter(move |p| p.status == status)
+    }
+
+    /// Every action in every project.
+    pub fn actions(&self) -> impl Iterator<Item = ProjectAction<'_>> {
+        self.projects()
+            .flat_map(move |project| self.project_actions(project))
+    }
+
+    /// The actions with `status` in every project.
+    pub fn actions_with_status(
+        &self,
+        status: ActionStatus,
+    ) -> impl Iterator<Item = ProjectAction<'_>> {
+        self.actions().filter(move |a| a.status == status)
+    }
+
+    /// The active actions in projects that are in progress, which are the ones that can be done
+    /// next.
+    pub fn active_actions(&self) -> impl Iterator<Item = ProjectAction<'_>> {
+        self.projects_with_status(ProjectStatus::InProgress)
+            .flat_map(move |project| self.project_actions(project))
+            .filter(|a| a.status == ActionStatus::Active)
+    }
+
+    /// The actions that the context `name` links to, in the order they're listed. Links to
+    /// projects or actions that don't exist are skipped.
+    pub fn actions_in_context(
+        &self,
+        name: &ContextName,
+    ) -> impl Iterator<Item = ProjectAction<'_>> {
+        self.context(name)
+            .into_iter()
+            .flat_map(|context| context.actions())
+            .filter_map(ContextAction::to_action_ref)
+            .filter_map(move |action_ref| self.linked_action(action_ref))
+    }
+
+    /// The action that `action_ref` links to, if it exists.
+    pub fn linked_action(&self, action_ref: &ActionRef) -> Option<ProjectAction<'_>> {
+        let project = self.project(&action_ref.project_name)?;
+        let (action, status) = project.actions.get_action(&action_ref.action_id)?;
+        Some(ProjectAction {
+            docs: self,
+            project,
+            action,
+            status,
+        })
+    }
+
+    fn project_actions<'a>(
+        &'a self,
+        project: &'a Project,
+    ) -> impl Iterator<Item = ProjectAction<'a>> {
+        project
+            .actions
+            .actions()
+            .map(move |(action, status)| ProjectAction {
+                docs: self,
+                project,
+                action,
+                status,
+            })
+    }
+
+    /// Finds the project whose title or name best matches `query`.
+    pub fn find_project(&self, query: &str) -> Result<&Project, ResolveError> {
+        resolve::resolve(
+            query,
+            self.projects(),
+            |p| vec![p.title(), p.name.as_str()],
+            |p| p.name.to_string(),
+        )
+    }
+
+    /// Finds the incomplete action whose text or ID best matches `query`, along with its project.
+    pub fn find_action(&self, query: &str) -> Result<(&Project, &Action), ResolveError> {
+        let candidates = self
+            .projects()
+            .flat_map(|p| {
+                p.actions
+                    .actions()
+                    .filter(|(_, s)| *s != ActionStatus::Complete)
+                    .map(move |(a, _)| (p, a, a.text.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        resolve::resolve(
+            query,
+            candidates,
+            |(_, a, text)| {
+                let mut names = vec![text.as_str()];
+                names.extend(a.id.as_ref().map(|id| id.as_str()));
+                names
+            },
+            |(p, _, text)| format!("{} ({})", text, p.title()),
+        )
+        .map(|(p, a, _)| (p, a))
+    }
+
+    /// Filenames in the projects folder that don't follow the naming scheme, sorted.
+    pub fn misnamed_projects(&self) -> &[String] {
+        &self.misnamed_projects[..]
+    }
+
+    /// The contexts, sorted by name.
     pub fn contexts(&self) -> impl Iterator<Item = &Context> {
         self.contexts.values()
     }
+
+    /// The context `name`, or the one it's an alias of. If the alias has a file of its own, that's
+    /// used when there's no file for the context it's an alias of.
+    pub fn context(&self, name: &ContextName) -> Option<&Context> {
+        let canonical = self.canonical_context_name(name);
+        self.contexts.get(&canonical).or_else(|| {
+            self.contexts
+                .values()
+                .filter(|c| self.canonical_context_name(&c.name) == canonical)
+                .min_by(|a, b| a.name.cmp(&b.name))
+        })
+    }
+
+    /// The name that the context `name` is an alias of, or `name` itself if it isn't an alias.
+    pub fn canonical_context_name(&self, name: &ContextName) -> ContextName {
+        self.context_config.canonical(name)
+    }
+
+    /// The documents that were left out because they have unresolved conflicts.
+    pub fn conflicted(&self) -> &[ConflictedFile] {
+        &self.conflicted
+    }
+
+    /// The documents that were left out because they aren't valid projects or contexts.
+    pub fn unreadable(&self) -> &[UnreadableFile] {
+        &self.unreadable
+    }
+
+    /// How many actions the context `name` has that aren't complete, along with its limit from
+    /// `[contexts.capacity]`, or `None` if it doesn't have one. Actions in the files of its aliases
+    /// are counted too.
+    pub fn context_capacity(&self, name: &ContextName) -> Option<Capacity> {
+        let max = self.context_config.capacity(name)?;
+        let canonical = self.canonical_context_name(name);
+        let actions = self
+            .contexts
+            .values()
+            .filter(|c| self.canonical_context_name(&c.name) == canonical)
+            .flat_map(|c| c.actions())
+            .filter(|action| match action {
+                ContextAction::Literal(_) => true,
+                ContextAction::UnresolvedReference(_) => false,
+                ContextAction::Reference(action_ref) => self
+                    .linked_action(action_ref)
+                    .is_some_and(|a| a.status != ActionStatus::Complete),
+            })
+            .count();
+        Some(Capacity { actions, max })
+    }
+
+    /// Whether the contexts were read from a file each or from the action list.
+    pub fn context_layout(&self) -> ContextLayout {
+        self.context_config.layout
+    }
+
+    /// The order that the actions in each context are kept in.
+    pub fn context_order(&self) -> ContextOrder {
+        self.context_config.order
+    }
+
+    /// The links to the document `name`, like a project or context name, from other documents.
+    pub fn backlinks(&self, name: &str) -> &[Reference] {
+        self.references.links_to(name)
+    }
+}
+
+/// How full a context is, compared to its limit in `[contexts.capacity]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capacity {
+    /// The actions in the context that aren't complete.
+    pub actions: usize,
+    pub max: usize,
+}
+
+impl Capacity {
+    pub fn is_exceeded(&self) -> bool {
+        self.actions > self.max
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// The folder in the root of the GTD folder that archived projects are moved to.
+pub const ARCHIVE_DIRNAME: &str = "Archive";
+
+/// An action, along with the project it's in.
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectAction<'a> {
+    docs: &'a Documents,
+    pub project: &'a Project,
+    pub action: &'a Action,
+    pub status: ActionStatus,
+}
+
+impl<'a> ProjectAction<'a> {
+    /// The contexts that link to the action.
+    pub fn contexts(&self) -> impl Iterator<Item = &'a Context> {
+        let (project, id) = (self.project, self.action.id.as_ref());
+        self.docs.contexts().filter(move |context| {
+            context
+                .actions()
+                .iter()
+                .filter_map(ContextAction::to_action_ref)
+                .any(|r| r.project_name == project.name && Some(&r.action_id) == id)
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Loader {
     root_dir: PathBuf,
     project_dir: PathBuf,
     context_dir: PathBuf,
+    scheme: NameScheme,
+    /// The document that everything is read from in single-file mode.
+    single_file: Option<PathBuf>,
+    depths: HeadingDepths,
 }
 
 // TODO: Document.
 impl Loader {
-    pub fn new(root_dir: PathBuf) -> Self {
+    pub fn new(root_dir: PathBuf, scheme: NameScheme) -> Self {
         let project_dir = root_dir.join("Projects");
         let context_dir = root_dir.join("Contexts");
         Self {
             root_dir,
             project_dir,
             context_dir,
+            scheme,
+            single_file: None,
+            depths: HeadingDepths::default(),
+        }
+    }
+
+    /// Creates a loader for `root_dir` using the naming scheme and layout in `config`.
+    pub fn from_config(root_dir: PathBuf, config: &Config) -> Self {
+        let single_file = config.single_file.path.as_ref().map(|p| root_dir.join(p));
+        let loader = Self::new(root_dir, config.project_names.clone()).with_depths(config.headings);
+        match single_file {
+            Some(path) => loader.with_single_file(path),
+            None => loader,
         }
     }
 
-    pub fn all_project_names(&self) -> Result<impl Iterator<Item = ProjectName>, IoError> {
-        Self::read_dir(&self.project_dir).map(|i| i.map(|n| ProjectName::new(n).unwrap()))
+    /// Reads projects and contexts from sections of the document at `path` instead of from their
+    /// own files. Documents can't be edited in this mode.
+    pub fn with_single_file(mut self, path: PathBuf) -> Self {
+        self.single_file = Some(path);
+        self
+    }
+
+    /// Reads documents whose headings are at `depths`. Documents can only be edited at the default
+    /// depths.
+    pub fn with_depths(mut self, depths: HeadingDepths) -> Self {
+        self.depths = depths;
+        self
+    }
+
+    /// How deep the headings of documents are.
+    pub fn depths(&self) -> HeadingDepths {
+        self.depths
+    }
+
+    /// The file that a document at `path` is actually read from, which is the single file in
+    /// single-file mode.
+    fn document_path(&self, path: PathBuf) -> PathBuf {
+        self.single_file.clone().unwrap_or(path)
+    }
+
+    /// The document that everything is read from, if the loader is in single-file mode.
+    pub fn single_file(&self) -> Option<&Path> {
+        self.single_file.as_deref()
+    }
+
+    pub fn root_dir(&self) -> &Path {
+        &self.root_dir
+    }
+
+    pub fn scheme(&self) -> &NameScheme {
+        &self.scheme
+    }
+
+    /// Lists the names of all projects, or the filename if it doesn't follow the naming scheme.
+    pub fn all_project_names(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<ProjectName, String>> + '_, IoError> {
+        self.names(&self.project_dir, Kind::Project).map(move |i| {
+            i.map(move |n| ProjectName::with_scheme(n.clone(), &self.scheme).ok_or(n))
+        })
+    }
+
+    /// Loads the projects one at a time as they're iterated over, so that only one has to be in
+    /// memory at once. Each file that isn't loaded comes out as why it was skipped.
+    pub fn projects_iter(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<Project, SkippedFile>> + '_, IoError> {
+        let names = self.all_project_names()?;
+        Ok(names.map(move |name| self.load_project_file(name)))
+    }
+
+    /// Loads the project named `name`, as listed by `all_project_names`.
+    fn load_project_file(&self, name: Result<ProjectName, String>) -> Result<Project, SkippedFile> {
+        let name = name.map_err(SkippedFile::Misnamed)?;
+        self.load_project(&name).map_err(|e| match e {
+            LoadProjectError::Conflicted(file) => SkippedFile::Conflicted(file),
+            e => SkippedFile::Unreadable(UnreadableFile {
+                path: self.document_path(self.project_path(&name)),
+                error: e.to_string(),
+            }),
+        })
+    }
+
+    /// Loads the contexts one at a time as they're iterated over, from their files or the action
+    /// list depending on `layout`. Each file that isn't loaded comes out as why it was skipped.
+    pub fn contexts_iter(
+        &self,
+        layout: ContextLayout,
+    ) -> Result<Box<dyn Iterator<Item = Result<Context, SkippedFile>> + '_>, IoError> {
+        let contexts: Box<dyn Iterator<Item = _>> = match layout {
+            ContextLayout::Files => Box::new(self.all_context_names()?.map(move |name| {
+                let path = self.context_path(&name);
+                self.load_context(&name)
+                    .map_err(|e| self.skipped_context(path, e))
+            })),
+            ContextLayout::List => match ActionList::load(self) {
+                Ok(list) => Box::new(list.contexts.into_iter().map(Ok)),
+                Err(e) => {
+                    let path = action_list::path(&self.root_dir);
+                    Box::new(std::iter::once(Err(self.skipped_context(path, e))))
+                }
+            },
+        };
+        Ok(contexts)
+    }
+
+    /// Why the context at `path` was skipped, given the error it couldn't be loaded with.
+    fn skipped_context(&self, path: PathBuf, error: LoadContextError) -> SkippedFile {
+        match error {
+            LoadContextError::Conflicted(file) => SkippedFile::Conflicted(file),
+            e => SkippedFile::Unreadable(UnreadableFile {
+                path: self.document_path(path),
+                error: e.to_string(),
+            }),
+        }
     }
 
     pub fn all_context_names(&self) -> Result<impl Iterator<Item = ContextName>, IoError> {
-        Self::read_dir(&self.context_dir).map(|i| i.map(ContextName::new))
+        self.names(&self.context_dir, Kind::Context)
+            .map(|i| i.map(ContextName::new))
+    }
+
+    /// The names of the documents of `kind`, from the files in `dir` or the single file.
+    fn names(&self, dir: &Path, kind: Kind) -> Result<impl Iterator<Item = String>, IoError> {
+        let names = match &self.single_file {
+            Some(path) => single_file::split(&fs::read_to_string(path)?)
+                .into_iter()
+                .filter(|s| s.kind == kind)
+                .map(|s| s.name)
+                .collect(),
+            None => self.read_dir(dir, &Ignore::load(&self.root_dir)?)?,
+        };
+        Ok(names.into_iter())
     }
 
-    fn read_dir(dir: &Path) -> Result<impl Iterator<Item = String>, IoError> {
-        let iter = fs::read_dir(dir)?.flat_map(|e| {
+    /// The names of the files in `dir` that aren't ignored by `ignore`, sorted.
+    fn read_dir(&self, dir: &Path, ignore: &Ignore) -> Result<Vec<String>, IoError> {
+        let names = fs::read_dir(dir)?.flat_map(|e| {
             let path = e.ok()?.path();
-            if path.is_dir() {
+            let relative = path.strip_prefix(&self.root_dir).unwrap_or(&path);
+            if path.is_dir() || ignore.is_ignored(relative, false) {
                 return None;
             }
 
             let name = path.file_stem()?.to_str()?.to_string();
             Some(name)
         });
-        Ok(iter)
+        // The order of the entries depends on the file system, so it's fixed here so that
+        // everything read from the folder comes out the same way every time.
+        let mut names = names.collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
     }
 
     pub fn load_project(&self, name: &ProjectName) -> Result<Project, LoadProjectError> {
         let name = name.as_str().to_string();
-        let text = Self::load_markdown_file(&self.project_dir, &name)?;
-        let project = Project::parse(name, &text)?;
+        let text = match &self.single_file {
+            Some(path) => Self::load_section(path, Kind::Project, &name, |n| {
+                ProjectName::with_scheme(n.to_string(), &self.scheme)
+                    .map_or_else(|| n.to_string(), |n| n.title().to_string())
+            })?,
+            None => {
+                let path = self.project_dir.join(format!("{}.md", name));
+                Self::load_checked::<LoadProjectError>(&path)?
+            }
+        };
+        let project = Project::parse_with_depths(name, &text, &self.scheme, self.depths)?;
         Ok(project)
     }
 
+    /// The text of the section of `kind` named `name` in the single file at `path`, titled by
+    /// `title` given the name.
+    fn load_section<F>(path: &Path, kind: Kind, name: &str, title: F) -> Result<String, IoError>
+    where
+        F: FnOnce(&str) -> String,
+    {
+        single_file::split(&fs::read_to_string(path)?)
+            .into_iter()
+            .find(|s| s.kind == kind && s.name == name)
+            .map(|s| s.text(&title(name)))
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, format!("no section {}", name)))
+    }
+
+    /// Fails in single-file mode or with custom heading depths, where documents can't be edited.
+    pub fn ensure_files(&self) -> Result<(), IoError> {
+        if self.single_file.is_some() {
+            return Err(IoError::new(
+                ErrorKind::Unsupported,
+                "documents can't be edited in single-file mode",
+            ));
+        }
+        if self.depths != HeadingDepths::default() {
+            return Err(IoError::new(
+                ErrorKind::Unsupported,
+                "documents can't be edited with custom heading depths",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Creates a new, empty project file titled `title`, returning its name. Characters that can't
+    /// be in a filename are left out of the name, but kept in the title.
+    pub fn create_project(&self, title: &str) -> Result<ProjectName, CreateProjectError> {
+        self.ensure_files()?;
+        let id = self.scheme.generate_id(&Local::now());
+        let name = self
+            .scheme
+            .format(&id, &filename::sanitize(title))
+            .ok_or(CreateProjectError::InvalidName)?;
+
+        let path = self.project_path(&name);
+        if path.exists() {
+            return Err(CreateProjectError::AlreadyExists(name));
+        }
+
+        fs::create_dir_all(&self.project_dir)?;
+        fs::write(path, Project::template(title))?;
+        Ok(name)
+    }
+
+    /// Rewrites the file of the project `name` with `edit`, which is given the file's text and the
+    /// project parsed from it, and returns the new text or `None` to leave the file unchanged.
+    ///
+    /// Returns whether the file was written.
+    pub fn update_project<F>(&self, name: &ProjectName, edit: F) -> Result<bool, LoadProjectError>
+    where
+        F: FnOnce(&str, &Project) -> Option<String>,
+    {
+        self.ensure_files()?;
+        let path = self.project_path(name);
+        let text = Self::load_checked::<LoadProjectError>(&path)?;
+        let project = Project::parse_with_depths(name.as_str(), &text, &self.scheme, self.depths)?;
+        match edit(&text, &project) {
+            Some(new_text) if new_text != text => {
+                fs::write(path, new_text)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Edits the frontmatter of the project `name` with `edit`, leaving the rest of the file as it
+    /// is.
+    ///
+    /// If the frontmatter changed, the change is shown as a word diff and written according to
+    /// `mode`. Returns whether the file was written.
+    pub fn update_project_frontmatter<F>(
+        &self,
+        name: &ProjectName,
+        mode: WriteMode,
+        edit: F,
+    ) -> Result<bool, IoError>
+    where
+        F: FnOnce(&mut Frontmatter),
+    {
+        self.ensure_files()?;
+        let path = self.project_path(name);
+        let text = fs::read_to_string(&path)?;
+        let (mut frontmatter, _) = Frontmatter::parse(&text);
+        let original = frontmatter.clone();
+        edit(&mut frontmatter);
+        if frontmatter == original {
+            return Ok(false);
+        }
+
+        let new_text = frontmatter.replace_in(&text);
+        if !diff::review(name.as_str(), &text, &new_text, mode)? {
+            return Ok(false);
+        }
+        fs::write(path, new_text)?;
+        Ok(true)
+    }
+
     pub fn load_context(&self, name: &ContextName) -> Result<Context, LoadContextError> {
         let name = name.as_str().to_string();
-        let text = Self::load_markdown_file(&self.context_dir, &name)?;
-        let context = Context::parse(name, &text)?;
+        let text = match &self.single_file {
+            Some(path) => Self::load_section(path, Kind::Context, &name, str::to_string)?,
+            None => {
+                let path = self.context_path(&ContextName::new(name.clone()));
+                Self::load_checked::<LoadContextError>(&path)?
+            }
+        };
+        let context = Context::parse_with_scheme(name, &text, &self.scheme)?;
         Ok(context)
     }
 
-    fn load_markdown_file(dir: &Path, name: &str) -> Result<String, std::io::Error> {
-        let name = format!("{}.md", name);
-        let path = dir.join(name);
-        fs::read_to_string(path)
+    /// The directory that projects are stored in.
+    pub fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+
+    /// The directory that contexts are stored in.
+    pub fn context_dir(&self) -> &Path {
+        &self.context_dir
+    }
+
+    /// The path of the file for the project `name`.
+    pub fn project_path(&self, name: &ProjectName) -> PathBuf {
+        filename::long_path(&self.project_dir.join(format!("{}.md", name)))
+    }
+
+    /// The directory that archived projects are moved to.
+    pub fn archive_dir(&self) -> PathBuf {
+        self.root_dir.join(ARCHIVE_DIRNAME)
+    }
+
+    /// The directory that gtd keeps its own state in, like what was last synced.
+    pub fn state_dir(&self) -> PathBuf {
+        self.root_dir.join(".gtd")
+    }
+
+    /// Moves the file of the project `name` into the archive directory, returning its new path.
+    ///
+    /// Fails without moving anything if the archive already has a file with the same name.
+    pub fn archive_project(&self, name: &ProjectName) -> Result<PathBuf, IoError> {
+        self.ensure_files()?;
+        let archive_dir = self.archive_dir();
+        fs::create_dir_all(&archive_dir)?;
+        let to = filename::long_path(&archive_dir.join(format!("{}.md", name)));
+        if to.exists() {
+            return Err(IoError::new(
+                ErrorKind::AlreadyExists,
+                format!("{} is already archived", name),
+            ));
+        }
+        fs::rename(self.project_path(name), &to)?;
+        Ok(to)
+    }
+
+    /// The path of the file for the context `name`.
+    pub fn context_path(&self, name: &ContextName) -> PathBuf {
+        filename::long_path(&self.context_dir.join(format!("{}.md", name)))
+    }
+
+    /// Reads the file at `path`, failing if it has unresolved conflicts.
+    pub fn load_checked<E: From<IoError> + From<ConflictedFile>>(path: &Path) -> Result<String, E> {
+        let text = fs::read_to_string(path)?;
+        match ConflictedFile::check(path, &text) {
+            Some(file) => Err(file.into()),
+            None => Ok(text),
+        }
     }
 }
 
+/// A document that couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnreadableFile {
+    pub path: PathBuf,
+    /// Why it couldn't be read.
+    pub error: String,
+}
+
+/// A document that wasn't loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkippedFile {
+    /// A project file whose name, given here, doesn't follow the naming scheme.
+    Misnamed(String),
+    Conflicted(ConflictedFile),
+    Unreadable(UnreadableFile),
+}
+
 #[derive(Debug)]
 pub enum LoadProjectError {
     IoError(IoError),
     ProjectParseError(ProjectParseError<'static>),
+    Conflicted(ConflictedFile),
 }
 
 impl fmt::Display for LoadProjectError {
@@ -132,6 +757,7 @@ impl fmt::Display for LoadProjectError {
         match self {
             Self::IoError(e) => write!(f, "{}", e),
             Self::ProjectParseError(e) => write!(f, "{}", e),
+            Self::Conflicted(file) => write!(f, "{}", file),
         }
     }
 }
@@ -144,6 +770,12 @@ impl From<IoError> for LoadProjectError {
     }
 }
 
+impl From<ConflictedFile> for LoadProjectError {
+    fn from(file: ConflictedFile) -> Self {
+        Self::Conflicted(file)
+    }
+}
+
 impl<'a> From<ProjectParseError<'a>> for LoadProjectError {
     fn from(error: ProjectParseError<'a>) -> Self {
         Self::ProjectParseError(error.into_static())
@@ -154,6 +786,7 @@ impl<'a> From<ProjectParseError<'a>> for LoadProjectError {
 pub enum LoadContextError {
     IoError(IoError),
     ContextParseError(ContextParseError<'static>),
+    Conflicted(ConflictedFile),
 }
 
 impl fmt::Display for LoadContextError {
@@ -161,6 +794,7 @@ impl fmt::Display for LoadContextError {
         match self {
             Self::IoError(e) => write!(f, "{}", e),
             Self::ContextParseError(e) => write!(f, "{}", e),
+            Self::Conflicted(file) => write!(f, "{}", file),
         }
     }
 }
@@ -173,8 +807,152 @@ impl From<IoError> for LoadContextError {
     }
 }
 
+impl From<ConflictedFile> for LoadContextError {
+    fn from(file: ConflictedFile) -> Self {
+        Self::Conflicted(file)
+    }
+}
+
 impl<'a> From<ContextParseError<'a>> for LoadContextError {
     fn from(error: ContextParseError<'a>) -> Self {
         Self::ContextParseError(error.into_static())
     }
 }
+
+#[derive(Debug)]
+pub enum CreateProjectError {
+    IoError(IoError),
+    InvalidName,
+    AlreadyExists(ProjectName),
+}
+
+impl fmt::Display for CreateProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::InvalidName => write!(f, "title doesn't produce a valid project name"),
+            Self::AlreadyExists(name) => write!(f, "project \"{}\" already exists", name),
+        }
+    }
+}
+
+impl Error for CreateProjectError {}
+
+impl From<IoError> for CreateProjectError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    #[test]
+    fn actions_are_found_with_their_projects_and_contexts() {
+        let dir = TempDir::new("gtd");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights ^aaaaaa\n- Pack bags ^bbbbbb\n\n### Waiting\n\n- Passport ^cccccc\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Projects/202401010001 Paint shed.md"),
+            "# Paint shed\n#someday\n\n## Actions\n\n### Active\n\n- Buy paint ^aaaaaa\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Contexts/@home.md"),
+            "# @home\n\n- ![[202401010000 Plan trip#^bbbbbb]]\n- Water plants\n- ![[202401010000 Plan trip#^zzzzzz]]\n- ![[202401010001 Paint shed#^aaaaaa]]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Contexts/@computer.md"),
+            "# @computer\n\n- ![[202401010000 Plan trip#^bbbbbb]]\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let text = |a: ProjectAction| a.action.text.to_string();
+
+        assert_eq!(docs.actions().count(), 4);
+        assert_eq!(
+            docs.actions_with_status(ActionStatus::Waiting)
+                .map(text)
+                .collect::<Vec<_>>(),
+            ["Passport"]
+        );
+        let active = docs.active_actions().collect::<Vec<_>>();
+        assert_eq!(
+            active.iter().copied().map(text).collect::<Vec<_>>(),
+            ["Book flights", "Pack bags"]
+        );
+        assert_eq!(active[0].contexts().count(), 0);
+        let mut contexts = active[1]
+            .contexts()
+            .map(|c| c.name.to_string())
+            .collect::<Vec<_>>();
+        contexts.sort();
+        assert_eq!(contexts, ["@computer", "@home"]);
+
+        let home = ContextName::new(String::from("@home"));
+        assert_eq!(
+            docs.actions_in_context(&home)
+                .map(|a| (a.project.title().to_string(), text(a)))
+                .collect::<Vec<_>>(),
+            [
+                (String::from("Plan trip"), String::from("Pack bags")),
+                (String::from("Paint shed"), String::from("Buy paint")),
+            ]
+        );
+        assert_eq!(
+            docs.projects_with_status(ProjectStatus::Someday)
+                .map(Project::title)
+                .collect::<Vec<_>>(),
+            ["Paint shed"]
+        );
+    }
+
+    #[test]
+    fn documents_are_in_name_order() {
+        let dir = TempDir::new("gtd-order");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        for name in ["202401010300 C", "202401010100 A", "202401010200 B"].iter() {
+            let text = format!("# {}\n#in-progress\n", &name[13..]);
+            fs::write(dir.join(format!("Projects/{}.md", name)), text).unwrap();
+        }
+        for name in ["Zebra", "Apple", "Mango"].iter() {
+            fs::write(dir.join(format!("Projects/{}.md", name)), "# Fruit\n").unwrap();
+        }
+        for name in ["@phone", "@computer", "@home"].iter() {
+            let text = format!("# {}\n", name);
+            fs::write(dir.join(format!("Contexts/{}.md", name)), text).unwrap();
+        }
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+
+        assert_eq!(
+            docs.projects().map(Project::title).collect::<Vec<_>>(),
+            ["A", "B", "C"]
+        );
+        assert_eq!(docs.misnamed_projects(), ["Apple", "Mango", "Zebra"]);
+        assert_eq!(
+            docs.contexts()
+                .map(|c| c.name.to_string())
+                .collect::<Vec<_>>(),
+            ["@computer", "@home", "@phone"]
+        );
+    }
+
+    #[test]
+    fn new_project_names_leave_out_invalid_characters() {
+        let dir = TempDir::new("gtd-new");
+        let loader = Loader::new(dir.to_path_buf(), NameScheme::default());
+        let name = loader.create_project("Q3: Plan A/B test?").unwrap();
+        assert_eq!(name.title(), "Q3 Plan A-B test");
+        let project = loader.load_project(&name).unwrap();
+        assert_eq!(project.title.text(), "Q3: Plan A/B test?");
+    }
+}
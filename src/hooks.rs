@@ -0,0 +1,155 @@
+//! Running the shell commands set in `[hooks]` when things happen, so things like committing to
+//! git or sending a notification can be set up without gtd knowing about them.
+//!
+//! Each hook is run with `sh -c` in the root of the GTD folder, and gets a JSON object describing
+//! what happened on stdin, like `{"event":"complete-project","project":"202401011200 Plan trip",
+//! ...}`.
+
+use crate::config::HooksConfig;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::{
+    error::Error,
+    fmt,
+    io::{Error as IoError, Write},
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
+};
+
+/// Something that happened, which is what a hook is told about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    /// Something was captured to the inbox.
+    Capture {
+        date: NaiveDate,
+        /// The number of items that were captured.
+        count: usize,
+        /// The text that was captured, if it was given on the command line.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    /// A project was completed.
+    CompleteProject {
+        date: NaiveDate,
+        project: String,
+        title: String,
+        outcome: String,
+        archived: bool,
+    },
+    /// `gtd validate --fix` fixed problems.
+    Fix {
+        /// A description of each fix, like `Archived 202401011200 Plan trip.`.
+        fixes: Vec<String>,
+    },
+}
+
+impl Event {
+    /// The name of the hook that's run for the event.
+    pub fn hook_name(&self) -> &'static str {
+        match self {
+            Self::Capture { .. } => "on-capture",
+            Self::CompleteProject { .. } => "on-complete-project",
+            Self::Fix { .. } => "post-fix",
+        }
+    }
+
+    fn command<'a>(&self, config: &'a HooksConfig) -> Option<&'a str> {
+        match self {
+            Self::Capture { .. } => config.on_capture.as_deref(),
+            Self::CompleteProject { .. } => config.on_complete_project.as_deref(),
+            Self::Fix { .. } => config.post_fix.as_deref(),
+        }
+    }
+}
+
+/// Runs the hook for `event` in `root_dir` and waits for it to finish, if one is set. Returns
+/// whether there was one.
+pub fn run(config: &HooksConfig, root_dir: &Path, event: &Event) -> Result<bool, HookError> {
+    let command = match event.command(config) {
+        Some(command) => command,
+        None => return Ok(false),
+    };
+    let payload = serde_json::to_string(event).map_err(IoError::from)?;
+
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .current_dir(root_dir)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that doesn't read its payload closes stdin early, which isn't a failure.
+        let _ = writeln!(stdin, "{}", payload);
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(HookError::Failed(event.hook_name(), status));
+    }
+    Ok(true)
+}
+
+#[derive(Debug)]
+pub enum HookError {
+    IoError(IoError),
+    /// The hook with this name exited with a failure.
+    Failed(&'static str, ExitStatus),
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::Failed(name, status) => write!(f, "the {} hook failed ({})", name, status),
+        }
+    }
+}
+
+impl Error for HookError {}
+
+impl From<IoError> for HookError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+    use std::fs;
+
+    #[test]
+    fn hook_gets_event_on_stdin() {
+        let dir = TempDir::new("hooks");
+        fs::create_dir_all(&dir).unwrap();
+        let config = HooksConfig {
+            on_capture: Some(String::from("cat > event.json")),
+            post_fix: Some(String::from("exit 3")),
+            ..HooksConfig::default()
+        };
+        let event = Event::Capture {
+            date: NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+            count: 1,
+            text: Some(String::from("Call mom")),
+        };
+
+        assert!(run(&config, &dir, &event).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join("event.json")).unwrap(),
+            "{\"event\":\"capture\",\"date\":\"2024-01-10\",\"count\":1,\"text\":\"Call mom\"}\n"
+        );
+        let fix = Event::Fix { fixes: Vec::new() };
+        assert!(matches!(
+            run(&config, &dir, &fix),
+            Err(HookError::Failed("post-fix", _))
+        ));
+        let completed = Event::CompleteProject {
+            date: NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+            project: String::from("202401011200 Plan trip"),
+            title: String::from("Plan trip"),
+            outcome: String::new(),
+            archived: false,
+        };
+        assert!(!run(&config, &dir, &completed).unwrap());
+    }
+}
@@ -0,0 +1,341 @@
+//! Reading events from iCalendar (`.ics`) data.
+//!
+//! Only what's needed to know when someone is busy is read: each event's summary, start, end, and
+//! whether it blocks time. Recurrence rules aren't expanded, so recurring events only appear on
+//! their first occurrence. Tasks can also be read and written, for syncing actions.
+
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+/// When an event starts or ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventTime {
+    /// A whole day, for all-day events.
+    Date(NaiveDate),
+    /// A time in the local time zone.
+    DateTime(NaiveDateTime),
+}
+
+impl EventTime {
+    /// The local time this is at, where whole days start at midnight.
+    pub fn to_local(self) -> NaiveDateTime {
+        match self {
+            Self::Date(date) => date.and_time(NaiveTime::MIN),
+            Self::DateTime(time) => time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub summary: String,
+    pub start: EventTime,
+    pub end: EventTime,
+    /// Whether the event doesn't block time, like a reminder or a birthday.
+    pub is_transparent: bool,
+}
+
+impl Event {
+    pub fn is_all_day(&self) -> bool {
+        matches!(self.start, EventTime::Date(_))
+    }
+
+    /// The part of `date` that the event takes up, if any.
+    pub fn on(&self, date: NaiveDate) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        let day_start = date.and_time(NaiveTime::MIN);
+        let day_end = day_start + Duration::days(1);
+        let (start, end) = (self.start.to_local(), self.end.to_local());
+        if start >= day_end || (start < day_start && end <= day_start) {
+            return None;
+        }
+        Some((start.max(day_start), end.min(day_end)))
+    }
+}
+
+/// Reads the events in `text`, skipping any that don't have a valid start.
+pub fn parse(text: &str) -> Vec<Event> {
+    components(text, "VEVENT")
+        .into_iter()
+        .filter_map(|properties| {
+            let mut event = RawEvent::default();
+            for (name, value) in properties {
+                match name.as_str() {
+                    "SUMMARY" => event.summary = Some(unescape(&value)),
+                    "DTSTART" => event.start = parse_time(&value),
+                    "DTEND" => event.end = parse_time(&value),
+                    "TRANSP" => event.is_transparent = value == "TRANSPARENT",
+                    _ => {}
+                }
+            }
+            event.build()
+        })
+        .collect()
+}
+
+/// A task, as stored in a `VTODO` component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Todo {
+    pub uid: String,
+    pub summary: String,
+    pub due: Option<NaiveDate>,
+    /// The date the task was completed, or today's date if it's complete without one.
+    pub completed: Option<NaiveDate>,
+}
+
+impl Todo {
+    /// Renders the task as a calendar object holding just this task.
+    pub fn to_ics(&self) -> String {
+        let mut lines = vec![
+            String::from("BEGIN:VCALENDAR"),
+            String::from("VERSION:2.0"),
+            String::from("PRODID:-//gtd//gtd//EN"),
+            String::from("BEGIN:VTODO"),
+            format!("UID:{}", escape(&self.uid)),
+            format!("SUMMARY:{}", escape(&self.summary)),
+        ];
+        if let Some(due) = self.due {
+            lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+        }
+        match self.completed {
+            Some(date) => {
+                lines.push(String::from("STATUS:COMPLETED"));
+                lines.push(format!("COMPLETED:{}T000000Z", date.format("%Y%m%d")));
+            }
+            None => lines.push(String::from("STATUS:NEEDS-ACTION")),
+        }
+        lines.push(String::from("END:VTODO"));
+        lines.push(String::from("END:VCALENDAR"));
+
+        let mut ics = lines.join("\r\n");
+        ics.push_str("\r\n");
+        ics
+    }
+}
+
+/// Reads the tasks in `text`, skipping any without a UID. Tasks that are complete without a
+/// completion date are treated as completed on `today`.
+pub fn parse_todos(text: &str, today: NaiveDate) -> Vec<Todo> {
+    components(text, "VTODO")
+        .into_iter()
+        .filter_map(|properties| {
+            let mut uid = None;
+            let mut summary = String::new();
+            let mut due = None;
+            let mut completed = None;
+            let mut is_complete = false;
+            for (name, value) in properties {
+                match name.as_str() {
+                    "UID" => uid = Some(unescape(&value)),
+                    "SUMMARY" => summary = unescape(&value),
+                    "DUE" => due = parse_time(&value).map(|t| t.to_local().date()),
+                    "COMPLETED" => completed = parse_time(&value).map(|t| t.to_local().date()),
+                    "STATUS" => is_complete = value == "COMPLETED",
+                    _ => {}
+                }
+            }
+            if is_complete && completed.is_none() {
+                completed = Some(today);
+            }
+            Some(Todo {
+                uid: uid?,
+                summary,
+                due,
+                completed,
+            })
+        })
+        .collect()
+}
+
+/// The properties of each `kind` component in `text`, like `VEVENT`, as upper-cased names and
+/// values.
+fn components(text: &str, kind: &str) -> Vec<Vec<(String, String)>> {
+    let mut components = Vec::new();
+    let mut current = None::<Vec<(String, String)>>;
+
+    for line in unfold(text) {
+        let (name, value) = match split_property(&line) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value == kind => current = Some(Vec::new()),
+            ("END", Some(_)) if value == kind => components.extend(current.take()),
+            (_, Some(properties)) => properties.push((name, value.to_string())),
+            _ => {}
+        }
+    }
+
+    components
+}
+
+#[derive(Debug, Default)]
+struct RawEvent {
+    summary: Option<String>,
+    start: Option<EventTime>,
+    end: Option<EventTime>,
+    is_transparent: bool,
+}
+
+impl RawEvent {
+    fn build(self) -> Option<Event> {
+        let start = self.start?;
+        // Events without an end last a day if they're all-day, and no time at all otherwise.
+        let end = self.end.unwrap_or(match start {
+            EventTime::Date(date) => EventTime::Date(date + Duration::days(1)),
+            time => time,
+        });
+        Some(Event {
+            summary: self.summary.unwrap_or_default(),
+            start,
+            end,
+            is_transparent: self.is_transparent,
+        })
+    }
+}
+
+/// Joins folded lines, which continue the previous line when they start with whitespace.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines = Vec::<String>::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Splits a content line like `DTSTART;TZID=Europe/Paris:20240105T090000` into its upper-cased
+/// name and its value, dropping any parameters.
+fn split_property(line: &str) -> Option<(String, &str)> {
+    let (head, value) = line.split_once(':')?;
+    let name = head.split(';').next().unwrap_or(head);
+    Some((name.to_ascii_uppercase(), value.trim_end()))
+}
+
+/// Parses a date or date-time value. UTC times are converted to local time, and times in other
+/// time zones are assumed to be local.
+fn parse_time(value: &str) -> Option<EventTime> {
+    // Dates are always just `YYYYMMDD`, even without a `VALUE=DATE` parameter.
+    if value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .map(EventTime::Date);
+    }
+
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            let local = Utc.from_utc_datetime(&time).with_timezone(&Local);
+            Some(EventTime::DateTime(local.naive_local()))
+        }
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(EventTime::DateTime),
+    }
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => {}
+        }
+    }
+    out
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(d: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, d)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn events_are_parsed() {
+        let text = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:Team sync\\, weekly\r\nDTSTART;TZID=Europe/Paris:20240105T090000\r\nDTEND;TZID=Europe/Paris:20240105T093000\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nSUMMARY:Holi\r\n day\r\nDTSTART;VALUE=DATE:20240106\r\nTRANSP:TRANSPARENT\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(
+            parse(text),
+            vec![
+                Event {
+                    summary: String::from("Team sync, weekly"),
+                    start: EventTime::DateTime(time(5, 9, 0)),
+                    end: EventTime::DateTime(time(5, 9, 30)),
+                    is_transparent: false,
+                },
+                Event {
+                    summary: String::from("Holiday"),
+                    start: EventTime::Date(NaiveDate::from_ymd_opt(2024, 1, 6).unwrap()),
+                    end: EventTime::Date(NaiveDate::from_ymd_opt(2024, 1, 7).unwrap()),
+                    is_transparent: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn todos_are_parsed() {
+        let text = "BEGIN:VCALENDAR\nBEGIN:VTODO\nUID:abc\nSUMMARY:Pack\nDUE;VALUE=DATE:20240105\nSTATUS:COMPLETED\nEND:VTODO\nBEGIN:VTODO\nSUMMARY:No UID\nEND:VTODO\nEND:VCALENDAR\n";
+        let today = NaiveDate::from_ymd_opt(2024, 1, 7).unwrap();
+        assert_eq!(
+            parse_todos(text, today),
+            vec![Todo {
+                uid: String::from("abc"),
+                summary: String::from("Pack"),
+                due: NaiveDate::from_ymd_opt(2024, 1, 5),
+                completed: Some(today),
+            }]
+        );
+    }
+
+    #[test]
+    fn todo_round_trips() {
+        let todo = Todo {
+            uid: String::from("abc"),
+            summary: String::from("Pack bags, shoes"),
+            due: NaiveDate::from_ymd_opt(2024, 1, 5),
+            completed: None,
+        };
+        let today = NaiveDate::from_ymd_opt(2024, 1, 7).unwrap();
+        assert_eq!(parse_todos(&todo.to_ics(), today), vec![todo]);
+    }
+
+    #[test]
+    fn event_without_start_is_skipped() {
+        let text = "BEGIN:VEVENT\nSUMMARY:Nothing\nEND:VEVENT\n";
+        assert_eq!(parse(text), vec![]);
+    }
+
+    #[test]
+    fn event_is_clipped_to_day() {
+        let event = Event {
+            summary: String::new(),
+            start: EventTime::DateTime(time(5, 22, 0)),
+            end: EventTime::DateTime(time(6, 2, 0)),
+            is_transparent: false,
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 1, 6).unwrap();
+        assert_eq!(event.on(date), Some((time(6, 0, 0), time(6, 2, 0))));
+        assert_eq!(event.on(date + Duration::days(1)), None);
+    }
+}
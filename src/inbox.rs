@@ -0,0 +1,132 @@
+//! Capturing items into the inbox, which is a list kept in `Inbox.md`.
+
+use crate::{email::Email, links, project::CREATED_MARKER};
+use chrono::NaiveDate;
+use std::{
+    fs::{self, OpenOptions},
+    io::{Error as IoError, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+pub const INBOX_FILE: &str = "Inbox.md";
+
+/// The path of the inbox in the GTD folder `root_dir`.
+pub fn path(root_dir: &Path) -> PathBuf {
+    root_dir.join(INBOX_FILE)
+}
+
+/// The number of items in the inbox, which is 0 if there's no inbox.
+pub fn count(root_dir: &Path) -> usize {
+    fs::read_to_string(path(root_dir))
+        .map(|text| text.lines().filter(|l| l.starts_with("- ")).count())
+        .unwrap_or(0)
+}
+
+/// Adds `text` to the inbox as captured on `today`.
+pub fn capture(root_dir: &Path, text: &str, today: NaiveDate) -> Result<(), IoError> {
+    append(root_dir, &[item(text, today)])?;
+    println!("Captured \"{}\".", text);
+    Ok(())
+}
+
+/// Adds the email at `eml` to the inbox, with a link back to the message.
+///
+/// The item is dated when the email was sent, or `today` if the email has no date.
+pub fn capture_email(root_dir: &Path, eml: &Path, today: NaiveDate) -> Result<(), IoError> {
+    let (text, line) = email_item(eml, today)?;
+    append(root_dir, &[line])?;
+    println!("Captured \"{}\".", text);
+    Ok(())
+}
+
+/// Adds every new email in the maildir `dir` to the inbox, skipping ones that are already in it.
+///
+/// Only messages in the maildir's `new` folder are captured, since mail clients move messages out
+/// of it once they've been seen. Returns the number of emails captured.
+pub fn capture_maildir(root_dir: &Path, dir: &Path, today: NaiveDate) -> Result<usize, IoError> {
+    let inbox = fs::read_to_string(path(root_dir)).unwrap_or_default();
+
+    let mut messages = fs::read_dir(dir.join("new"))?
+        .map(|e| Ok(e?.path()))
+        .collect::<Result<Vec<_>, IoError>>()?;
+    messages.sort();
+
+    let mut lines = Vec::new();
+    for message in messages {
+        if !message.is_file() || inbox.contains(&message_uri(&message)?) {
+            continue;
+        }
+
+        match email_item(&message, today) {
+            Ok((text, line)) => {
+                println!("Captured \"{}\".", text);
+                lines.push(line);
+            }
+            Err(e) if e.kind() == ErrorKind::InvalidData => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    append(root_dir, &lines)?;
+    Ok(lines.len())
+}
+
+/// The text describing the email at `eml`, and the inbox line for it.
+fn email_item(eml: &Path, today: NaiveDate) -> Result<(String, String), IoError> {
+    let email = Email::load(eml)?;
+    if email.is_empty() {
+        return Err(IoError::new(
+            ErrorKind::InvalidData,
+            format!("{} isn't an email", eml.display()),
+        ));
+    }
+
+    let subject = email.subject.as_deref().unwrap_or("(no subject)");
+    let text = match email.sender() {
+        Some(sender) => format!("{} (from {})", subject, sender),
+        None => subject.to_string(),
+    };
+    let date = email.date.map_or(today, |d| d.date_naive());
+    let line = format!("{} [email]({})", item(&text, date), message_uri(eml)?);
+    Ok((text, line))
+}
+
+fn item(text: &str, date: NaiveDate) -> String {
+    format!("- {} {} {}", text, CREATED_MARKER, date.format("%Y-%m-%d"))
+}
+
+fn message_uri(eml: &Path) -> Result<String, IoError> {
+    Ok(links::file_uri(&fs::canonicalize(eml)?))
+}
+
+fn append(root_dir: &Path, lines: &[String]) -> Result<(), IoError> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let path = path(root_dir);
+    let needs_newline = match fs::read_to_string(&path) {
+        Ok(text) => !text.is_empty() && !text.ends_with('\n'),
+        Err(_) => false,
+    };
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if needs_newline {
+        writeln!(file)?;
+    }
+    for line in lines {
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn item_is_dated() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        assert_eq!(item("Call Bob", date), "- Call Bob ➕ 2024-01-05");
+    }
+}
@@ -0,0 +1,235 @@
+//! Setting up a new GTD folder, or filling in what's missing from an existing one.
+//!
+//! Nothing that's already in the folder is changed, so it's safe to run in a folder that's in use,
+//! like an Obsidian vault with notes in it.
+
+use crate::{
+    builder::ProjectBuilder, config, gtd::Loader, inbox, obsidian::Vault, project::Status,
+    sample::Sample,
+};
+use chrono::{DateTime, Duration, Local};
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{Error as IoError, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+/// What setting up a GTD folder did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub root_dir: PathBuf,
+    /// The folders and files that were created, relative to the root of the GTD folder.
+    pub created: Vec<PathBuf>,
+    /// The folders and files that were already there, and were left as they were.
+    pub kept: Vec<PathBuf>,
+    /// The Obsidian vault that the folder is in, if the config was set up for it.
+    pub vault: Option<Vault>,
+}
+
+/// Sets up the GTD folder of `loader`, creating the folders and files it's missing: `Projects`,
+/// `Contexts`, and `Archive`, the inbox, and a starter config. If the folder is in an Obsidian
+/// vault, the config opens and links documents in Obsidian.
+///
+/// With an `example`, the projects and contexts it generates are added too, along with a project
+/// tagged `#someday`, with project IDs from `now`.
+pub fn init(
+    loader: &Loader,
+    example: Option<&Sample>,
+    now: &DateTime<Local>,
+) -> Result<Report, IoError> {
+    let root_dir = loader.root_dir();
+    let vault = Vault::detect(root_dir);
+    let mut report = Report {
+        root_dir: root_dir.to_owned(),
+        created: Vec::new(),
+        kept: Vec::new(),
+        vault: None,
+    };
+    for dir in [
+        loader.project_dir().to_owned(),
+        loader.context_dir().to_owned(),
+        loader.archive_dir(),
+    ] {
+        report.add_dir(&dir)?;
+    }
+
+    let inbox = match example.is_some() {
+        true => "# Inbox\n\n- Look into travel insurance\n",
+        false => "# Inbox\n",
+    };
+    report.add_file(&inbox::path(root_dir), inbox)?;
+    if report.add_file(&config::path(root_dir), &starter_config(vault.as_ref()))? {
+        report.vault = vault;
+    }
+
+    if let Some(sample) = example {
+        loader.ensure_files()?;
+        for (path, text) in sample.documents(loader, now) {
+            report.add_file(&path, &text)?;
+        }
+        // The generated projects might not include one that's someday.
+        if let Some((path, text)) = someday_example(loader, sample, now) {
+            report.add_file(&path, &text)?;
+        }
+    }
+    Ok(report)
+}
+
+/// A project that might be started someday, with an ID from before the projects `sample` generates.
+fn someday_example(
+    loader: &Loader,
+    sample: &Sample,
+    now: &DateTime<Local>,
+) -> Option<(PathBuf, String)> {
+    let scheme = loader.scheme();
+    let id = scheme.generate_id(&(*now - Duration::minutes(sample.projects as i64)));
+    let name = scheme.format(&id, "Learn to sail")?;
+    let project = ProjectBuilder::new(name.as_str())
+        .scheme(scheme)
+        .status(Status::Someday)
+        .goal("Sail across the bay without help.");
+    Some((loader.project_path(&name), project.to_markdown()))
+}
+
+impl Report {
+    /// Creates the folder at `path` if it doesn't exist.
+    fn add_dir(&mut self, path: &Path) -> Result<(), IoError> {
+        match path.is_dir() {
+            true => self.kept.push(self.relative(path)),
+            false => {
+                fs::create_dir_all(path)?;
+                self.created.push(self.relative(path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the file at `path` with `text` if it doesn't exist, returning whether it was
+    /// created.
+    fn add_file(&mut self, path: &Path, text: &str) -> Result<bool, IoError> {
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut file) => {
+                file.write_all(text.as_bytes())?;
+                self.created.push(self.relative(path));
+                Ok(true)
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                self.kept.push(self.relative(path));
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root_dir).unwrap_or(path).to_owned()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for path in &self.created {
+            writeln!(f, "Created {}", path.display())?;
+        }
+        for path in &self.kept {
+            writeln!(f, "Kept {}, which was already there", path.display())?;
+        }
+        if let Some(vault) = &self.vault {
+            writeln!(
+                f,
+                "Set up to open and link documents in the Obsidian vault \"{}\"",
+                vault.name()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The config written to a new GTD folder, which is in `vault` if it's given.
+fn starter_config(vault: Option<&Vault>) -> String {
+    let mut text = String::from(
+        "# Settings for gtd. Every setting is optional, and the README lists all of them.\n\
+         \n\
+         [next]\n\
+         # Flag actions that have been active for longer than these.\n\
+         old-after = \"2w\"\n\
+         stuck-after = \"1m\"\n",
+    );
+    if vault.is_some() {
+        text.push_str(
+            "\n\
+             [open]\n\
+             # Open documents in Obsidian instead of $EDITOR.\n\
+             method = \"obsidian\"\n\
+             \n\
+             [links]\n\
+             # Link to documents in Obsidian from command output.\n\
+             style = \"hyperlink\"\n",
+        );
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, gtd::Documents, links::Linker, test_dir::TempDir, validate};
+
+    #[test]
+    fn folder_is_set_up_without_changing_what_is_there() {
+        let dir = TempDir::new("init");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::write(dir.join("Inbox.md"), "- Call mom\n").unwrap();
+        let loader = Loader::from_config(dir.to_path_buf(), &Config::default());
+
+        let report = init(&loader, None, &Local::now()).unwrap();
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(report.created, paths(&["Contexts", "Archive", "gtd.toml"]));
+        assert_eq!(report.kept, paths(&["Projects", "Inbox.md"]));
+        assert_eq!(report.vault, None);
+        assert_eq!(
+            fs::read_to_string(dir.join("Inbox.md")).unwrap(),
+            "- Call mom\n"
+        );
+        Config::load(&dir).unwrap();
+
+        let report = init(&loader, None, &Local::now()).unwrap();
+        assert!(report.created.is_empty());
+        assert_eq!(report.kept.len(), 5);
+    }
+
+    #[test]
+    fn config_is_set_up_for_an_obsidian_vault() {
+        let dir = TempDir::new("init");
+        fs::create_dir_all(dir.join(".obsidian")).unwrap();
+        let loader = Loader::from_config(dir.join("GTD"), &Config::default());
+        fs::create_dir_all(loader.root_dir()).unwrap();
+
+        let report = init(&loader, None, &Local::now()).unwrap();
+        assert_eq!(report.vault, Vault::detect(&dir));
+        let config = Config::load(loader.root_dir()).unwrap();
+        assert_eq!(config.open.method, config::OpenMethod::Obsidian);
+        assert_eq!(config.links.style, config::LinkStyle::Hyperlink);
+    }
+
+    #[test]
+    fn example_documents_are_valid() {
+        let dir = TempDir::new("init");
+        fs::create_dir_all(&dir).unwrap();
+        let config = Config::default();
+        let loader = Loader::from_config(dir.to_path_buf(), &config);
+
+        let sample = Sample::default();
+        let report = init(&loader, Some(&sample), &Local::now()).unwrap();
+        assert_eq!(report.created.len(), 6 + sample.projects + sample.contexts);
+        assert!(!dir.join("Someday.md").exists());
+        let docs = Documents::load(&dir, &config).unwrap();
+        assert_eq!(docs.projects().count(), sample.projects + 1);
+        assert!(docs.projects().any(|p| p.status == Status::Someday));
+        assert_eq!(docs.contexts().count(), sample.contexts);
+        let today = Local::now().date_naive();
+        let summary = validate::check(&docs, &config, today, &Linker::default());
+        assert_eq!(summary.diagnostics, Vec::new());
+    }
+}
@@ -0,0 +1,465 @@
+//! Actions linked to the issues they're tracked in, like `@issue(owner/repo#123)` for a GitHub
+//! issue or `@issue(OPS-123)` for a Jira one.
+//!
+//! Work tracked in an issue tracker is often finished there, so `gtd issues sync` looks up the
+//! issue of each incomplete action and flags the ones whose issue is closed, and can complete them.
+//! Going the other way, `gtd import` adds the open issues in a repository as actions.
+
+use crate::{
+    builder::ActionBuilder,
+    complete, edit,
+    gtd::{CreateProjectError, Documents, LoadProjectError},
+    project::{Action, ActionStatus, Name as ProjectName, Project},
+    prompt,
+};
+use chrono::NaiveDate;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    io::Error as IoError,
+    str::FromStr,
+};
+
+/// An issue in an issue tracker.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Issue {
+    /// An issue in a GitHub repository, like `owner/repo#123`.
+    GitHub { repo: String, number: u64 },
+    /// A Jira issue, like `OPS-123`.
+    Jira { key: String },
+}
+
+impl FromStr for Issue {
+    type Err = IssueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || IssueError::InvalidIssue(s.to_string());
+        if let Some((repo, number)) = s.split_once('#') {
+            let number = number.parse().map_err(|_| invalid())?;
+            if !is_repo(repo) {
+                return Err(invalid());
+            }
+            return Ok(Self::GitHub {
+                repo: repo.to_string(),
+                number,
+            });
+        }
+        let is_key = matches!(
+            s.rsplit_once('-'),
+            Some((project, number))
+                if project.starts_with(|c: char| c.is_ascii_uppercase())
+                    && project.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+                    && !number.is_empty()
+                    && number.chars().all(|c| c.is_ascii_digit())
+        );
+        if !is_key {
+            return Err(invalid());
+        }
+        Ok(Self::Jira { key: s.to_string() })
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::GitHub { repo, number } => write!(f, "{}#{}", repo, number),
+            Self::Jira { key } => write!(f, "{}", key),
+        }
+    }
+}
+
+/// Checks if `repo` is the name of a GitHub repository, like `owner/repo`.
+pub fn is_repo(repo: &str) -> bool {
+    let is_repo = matches!(
+        repo.split_once('/'),
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/')
+    );
+    is_repo && !repo.contains(char::is_whitespace)
+}
+
+/// Whether an issue is still being worked on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueState {
+    Open,
+    Closed,
+}
+
+/// Somewhere that the state of issues can be looked up.
+pub trait IssueTracker {
+    fn state(&mut self, issue: &Issue) -> Result<IssueState, IoError>;
+}
+
+/// An action linked to an issue.
+#[derive(Debug, Clone)]
+pub struct LinkedAction<'a> {
+    pub project: &'a Project,
+    pub action: &'a Action,
+    pub issue: Issue,
+}
+
+/// The incomplete actions in `docs` that are linked to issues, sorted by project. Actions whose
+/// annotation isn't an issue are returned as errors alongside them.
+pub fn linked_actions(docs: &Documents) -> (Vec<LinkedAction<'_>>, Vec<IssueError>) {
+    let mut linked = Vec::new();
+    let mut errors = Vec::new();
+    let actions = docs
+        .actions()
+        .filter(|a| a.status != ActionStatus::Complete);
+    for action in actions {
+        let issue = match &action.action.issue {
+            Some(issue) => issue,
+            None => continue,
+        };
+        match issue.parse() {
+            Ok(issue) => linked.push(LinkedAction {
+                project: action.project,
+                action: action.action,
+                issue,
+            }),
+            Err(e) => errors.push(e),
+        }
+    }
+    (linked, errors)
+}
+
+/// The actions in `linked` whose issue is closed, looking each issue up in `tracker` once.
+pub fn closed<'a, T: IssueTracker>(
+    linked: &[LinkedAction<'a>],
+    tracker: &mut T,
+) -> Result<Vec<LinkedAction<'a>>, IssueError> {
+    let mut states = HashMap::new();
+    let mut closed = Vec::new();
+    for action in linked {
+        let state = match states.get(&action.issue) {
+            Some(state) => *state,
+            None => {
+                let state = tracker
+                    .state(&action.issue)
+                    .map_err(|e| IssueError::LookupFailed(action.issue.clone(), e))?;
+                states.insert(action.issue.clone(), state);
+                state
+            }
+        };
+        if state == IssueState::Closed {
+            closed.push(action.clone());
+        }
+    }
+    Ok(closed)
+}
+
+/// What a sync found.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IssueSummary {
+    /// Actions linked to issues that were looked up.
+    pub checked: usize,
+    /// Actions whose issue is closed.
+    pub closed: usize,
+    /// Actions that were completed.
+    pub completed: usize,
+}
+
+/// Looks up the issues of the incomplete actions in `docs`, and lists the actions whose issue is
+/// closed. If `complete` is set, they're completed on `today`, after asking unless `yes` is set.
+pub fn sync<T: IssueTracker>(
+    docs: &Documents,
+    tracker: &mut T,
+    complete: bool,
+    yes: bool,
+    today: NaiveDate,
+) -> Result<IssueSummary, IssueError> {
+    let (linked, errors) = linked_actions(docs);
+    for e in errors {
+        eprintln!("Warning: {}", e);
+    }
+    let closed = closed(&linked, tracker)?;
+    for action in closed.iter() {
+        println!(
+            "- {} ({}) is closed: {}",
+            action.issue,
+            action.project.title(),
+            action.action.text
+        );
+    }
+
+    let mut summary = IssueSummary {
+        checked: linked.len(),
+        closed: closed.len(),
+        completed: 0,
+    };
+    let question = format!("Complete {} actions?", closed.len());
+    if !complete || closed.is_empty() || !(yes || prompt::confirm(&question)?) {
+        return Ok(summary);
+    }
+    for action in closed.iter() {
+        if complete::complete(docs, action.project, action.action, today)? {
+            summary.completed += 1;
+        }
+    }
+    Ok(summary)
+}
+
+/// An open issue to import as an action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenIssue {
+    pub issue: Issue,
+    pub title: String,
+}
+
+/// What an import changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// The project the issues were imported into.
+    pub project: ProjectName,
+    /// Whether the project was created for the import.
+    pub created: bool,
+    /// Actions that were added.
+    pub added: usize,
+}
+
+/// Imports `issues` as active actions in the project titled `repo`, creating it if there isn't one.
+/// Each action is linked to its issue, and issues that an action in the project is already linked
+/// to are skipped, so importing again only adds the new ones. Existing actions are left as they
+/// are, since they've often been reworded into next actions.
+pub fn import(
+    docs: &Documents,
+    repo: &str,
+    issues: &[OpenIssue],
+    today: NaiveDate,
+) -> Result<ImportSummary, IssueError> {
+    let existing = docs.projects().find(|p| p.title == repo);
+    let (project, created) = match existing {
+        Some(project) => (project.name.clone(), false),
+        None => (docs.loader().create_project(repo)?, true),
+    };
+
+    let mut added = 0;
+    docs.loader().update_project(&project, |text, project| {
+        let linked = project
+            .actions
+            .actions()
+            .filter_map(|(a, _)| a.issue.as_ref()?.parse::<Issue>().ok())
+            .collect::<HashSet<_>>();
+        let items = issues
+            .iter()
+            .filter(|i| !linked.contains(&i.issue))
+            .map(|i| {
+                let action = ActionBuilder::new(escape_markdown(&i.title))
+                    .issue(i.issue.to_string())
+                    .created(today);
+                format!("- {}\n", action.to_markdown())
+            })
+            .collect::<Vec<_>>();
+        added = items.len();
+        if items.is_empty() {
+            return None;
+        }
+        Some(edit::add_actions(
+            text,
+            project,
+            ActionStatus::Active,
+            &items.concat(),
+        ))
+    })?;
+    Ok(ImportSummary {
+        project,
+        created,
+        added,
+    })
+}
+
+/// `text` with the characters that would make it into Markdown escaped, so issue titles are kept as
+/// they were written. `#` and `@` are written as character references, since tags and annotations
+/// are found after backslash escapes are taken out.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+    {
+        match c {
+            '#' => escaped.push_str("&#35;"),
+            '@' => escaped.push_str("&#64;"),
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '^' | '~' | '|' | '!' | '&' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Debug)]
+pub enum IssueError {
+    IoError(IoError),
+    LoadProjectError(LoadProjectError),
+    CreateProjectError(CreateProjectError),
+    /// An `@issue(…)` annotation that isn't a GitHub or Jira issue.
+    InvalidIssue(String),
+    LookupFailed(Issue, IoError),
+}
+
+impl fmt::Display for IssueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::LoadProjectError(e) => write!(f, "{}", e),
+            Self::CreateProjectError(e) => write!(f, "{}", e),
+            Self::InvalidIssue(issue) => write!(
+                f,
+                "\"{}\" is not an issue, like \"owner/repo#123\" or \"OPS-123\"",
+                issue
+            ),
+            Self::LookupFailed(issue, e) => write!(f, "couldn't look up {}: {}", issue, e),
+        }
+    }
+}
+
+impl Error for IssueError {}
+
+impl From<IoError> for IssueError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<LoadProjectError> for IssueError {
+    fn from(error: LoadProjectError) -> Self {
+        Self::LoadProjectError(error)
+    }
+}
+
+impl From<CreateProjectError> for IssueError {
+    fn from(error: CreateProjectError) -> Self {
+        Self::CreateProjectError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+    use std::fs;
+
+    struct FakeTracker {
+        closed: Vec<Issue>,
+        lookups: usize,
+    }
+
+    impl IssueTracker for FakeTracker {
+        fn state(&mut self, issue: &Issue) -> Result<IssueState, IoError> {
+            self.lookups += 1;
+            Ok(if self.closed.contains(issue) {
+                IssueState::Closed
+            } else {
+                IssueState::Open
+            })
+        }
+    }
+
+    #[test]
+    fn issues_are_parsed() {
+        assert_eq!(
+            "kstrohbeck/gtd#12".parse::<Issue>().unwrap(),
+            Issue::GitHub {
+                repo: String::from("kstrohbeck/gtd"),
+                number: 12
+            }
+        );
+        assert_eq!(
+            "OPS-7".parse::<Issue>().unwrap(),
+            Issue::Jira {
+                key: String::from("OPS-7")
+            }
+        );
+        for invalid in ["gtd#12", "a/b#x", "a/b/c#1", "ops-7", "OPS-", "OPS"].iter() {
+            assert!(invalid.parse::<Issue>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn actions_with_closed_issues_are_completed() {
+        let dir = TempDir::new("issues");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let path = dir.join("Projects/202401011200 Release.md");
+        fs::write(
+            &path,
+            "# Release\n#in-progress\n\n## Actions\n\n### Active\n\n- Fix login @issue(acme/app#12)\n- Fix logout @issue(acme/app#13)\n- Update docs @issue(acme/app#12)\n- Call Bob @issue(nonsense)\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let mut tracker = FakeTracker {
+            closed: vec!["acme/app#12".parse().unwrap()],
+            lookups: 0,
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+
+        let summary = sync(&docs, &mut tracker, true, true, date).unwrap();
+        assert_eq!(
+            summary,
+            IssueSummary {
+                checked: 3,
+                closed: 2,
+                completed: 2
+            }
+        );
+        assert_eq!(tracker.lookups, 2);
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("- Fix logout @issue(acme/app#13)\n"));
+        assert!(text.contains("- Fix login @issue(acme/app#12) ✅ 2024-01-05\n"));
+        assert!(text.contains("- Update docs @issue(acme/app#12) ✅ 2024-01-05\n"));
+    }
+
+    #[test]
+    fn open_issues_are_imported_once() {
+        let dir = TempDir::new("import");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let issue = |number, title: &str| OpenIssue {
+            issue: Issue::GitHub {
+                repo: String::from("acme/app"),
+                number,
+            },
+            title: title.to_string(),
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let issues = [
+            issue(12, "Fix login"),
+            issue(14, "Support #dark-mode [beta] for @who(Bob)"),
+        ];
+        let summary = import(&docs, "acme/app", &issues, date).unwrap();
+        assert!(summary.created);
+        assert_eq!(summary.added, 2);
+
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let project = docs.project(&summary.project).unwrap();
+        assert_eq!(project.title.text(), "acme/app");
+        let actions = project.actions.actions().collect::<Vec<_>>();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(
+            actions[1].0.text.to_string(),
+            "Support #dark-mode [beta] for @who(Bob)"
+        );
+        assert!(actions[1].0.who.is_none());
+        assert!(actions[1].0.tags.is_empty());
+        assert_eq!(actions[1].0.issue.as_deref(), Some("acme/app#14"));
+
+        let issues = [issue(12, "Fix the login page"), issue(15, "Add logout")];
+        let again = import(&docs, "acme/app", &issues, date).unwrap();
+        assert_eq!(again.project, summary.project);
+        assert!(!again.created);
+        assert_eq!(again.added, 1);
+        let text = fs::read_to_string(docs.loader().project_path(&summary.project)).unwrap();
+        assert!(text.contains("- Fix login @issue(acme/app#12) ➕ 2024-01-05\n"));
+        assert!(text.contains("- Add logout @issue(acme/app#15) ➕ 2024-01-05\n"));
+        assert!(!text.contains("Fix the login page"));
+    }
+}
@@ -0,0 +1,403 @@
+//! Config put together from every place it can be set.
+//!
+//! Settings are read from these places, with later ones taking precedence over earlier ones:
+//!
+//! 1. The user's config, `$XDG_CONFIG_HOME/gtd/config.toml`, or `~/.config/gtd/config.toml` if
+//!    `XDG_CONFIG_HOME` isn't set.
+//! 2. `gtd.toml` in the root of the GTD folder.
+//! 3. Environment variables like `GTD_NEXT__OLD_AFTER=1w`, where `__` separates the parts of the
+//!    key and `_` stands for `-`.
+//! 4. Flags like `-c next.old-after=1w`.
+//!
+//! Tables are merged key by key, so the GTD folder can change one setting in a section and keep
+//! the rest of the section from the user's config.
+
+use crate::config::{self, Config, ConfigError};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use toml::{value::Table, Value};
+
+/// The prefix of environment variables that set config.
+const ENV_PREFIX: &str = "GTD_";
+
+/// Where a setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// The user's config file.
+    User(PathBuf),
+    /// `gtd.toml` in the GTD folder.
+    Folder(PathBuf),
+    /// An environment variable, by name.
+    Environment(String),
+    /// A `-c key=value` flag, as it was given.
+    Flag(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::User(path) | Self::Folder(path) => write!(f, "{}", path.display()),
+            Self::Environment(name) => write!(f, "${}", name),
+            Self::Flag(flag) => write!(f, "-c {}", flag),
+        }
+    }
+}
+
+/// The settings from every place config is read from, merged, along with where each came from.
+#[derive(Debug, Clone, Default)]
+pub struct Layers {
+    settings: Table,
+    origins: BTreeMap<Vec<String>, Origin>,
+}
+
+impl Layers {
+    /// Reads the settings for the GTD folder `root_dir`, from the user's config, the folder's
+    /// config, the environment variables `vars`, and the `-c` flags `flags`.
+    pub fn load<I>(root_dir: &Path, vars: I, flags: &[String]) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let vars = vars.into_iter().collect::<BTreeMap<_, _>>();
+        let mut layers = Self::default();
+        if let Some(path) = user_path(&vars) {
+            if let Some(table) = read(&path)? {
+                layers.add(table, &Origin::User(path));
+            }
+        }
+        let path = config::path(root_dir);
+        if let Some(table) = read(&path)? {
+            layers.add(table, &Origin::Folder(path));
+        }
+        for (name, value) in &vars {
+            if let Some(key) = env_key(name) {
+                let table = nested(&key, parse_value(value));
+                layers.add(table, &Origin::Environment(name.clone()));
+            }
+        }
+        for flag in flags {
+            let (key, value) = parse_flag(flag)?;
+            layers.add(nested(&key, value), &Origin::Flag(flag.clone()));
+        }
+        Ok(layers)
+    }
+
+    /// Merges the settings in `table` over the ones already read, noting that they came from
+    /// `origin`.
+    pub fn add(&mut self, table: Table, origin: &Origin) {
+        merge(
+            &mut self.settings,
+            table,
+            &mut Vec::new(),
+            &mut self.origins,
+            origin,
+        );
+    }
+
+    /// The config that the settings make up, with defaults for everything that isn't set.
+    pub fn config(&self) -> Result<Config, ConfigError> {
+        Value::Table(self.settings.clone())
+            .try_into()
+            .map(Config::move_deprecated)
+            .map_err(ConfigError::from)
+    }
+
+    /// The value of the setting at `key`, or the table of settings under it.
+    pub fn get(&self, key: &[String]) -> Option<&Value> {
+        lookup(&self.settings, key)
+    }
+
+    /// Every setting, as its dotted key like `next.old-after`, its value, and where it came from,
+    /// sorted by key.
+    pub fn settings(&self) -> Vec<(String, &Value, &Origin)> {
+        self.origins
+            .iter()
+            .filter_map(|(key, origin)| Some((dotted(key), lookup(&self.settings, key)?, origin)))
+            .collect()
+    }
+}
+
+/// The path of the user's config file, from the environment variables `vars`.
+pub fn user_path(vars: &BTreeMap<String, String>) -> Option<PathBuf> {
+    let dir = match vars.get("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(vars.get("HOME")?).join(".config"),
+    };
+    Some(dir.join("gtd").join("config.toml"))
+}
+
+/// The settings in the config file at `path`, or `None` if there isn't one.
+fn read(path: &Path) -> Result<Option<Table>, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(toml::from_str(&text)?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The key that the environment variable `name` sets, like `["next", "old-after"]` for
+/// `GTD_NEXT__OLD_AFTER`, if it sets one.
+fn env_key(name: &str) -> Option<Vec<String>> {
+    let key = name.strip_prefix(ENV_PREFIX)?;
+    if key.is_empty() {
+        return None;
+    }
+    Some(
+        key.split("__")
+            .map(|part| part.to_lowercase().replace('_', "-"))
+            .collect(),
+    )
+}
+
+/// Splits a flag like `next.old-after=1w` into its key and value.
+fn parse_flag(flag: &str) -> Result<(Vec<String>, Value), ConfigError> {
+    let (key, value) = flag
+        .split_once('=')
+        .ok_or_else(|| ConfigError::InvalidSetting(flag.to_string()))?;
+    let key = parse_key(key.trim()).ok_or_else(|| ConfigError::InvalidSetting(flag.to_string()))?;
+    Ok((key, parse_value(value.trim())))
+}
+
+/// Splits a dotted key like `contexts.capacity."@calls"` into its parts.
+pub fn parse_key(key: &str) -> Option<Vec<String>> {
+    let mut parts = Vec::new();
+    let mut rest = key;
+    loop {
+        let (part, after) = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => {
+                let end = rest.find('.').unwrap_or(rest.len());
+                let part = &rest[..end];
+                if part.is_empty() || !part.chars().all(is_bare_key_char) {
+                    return None;
+                }
+                (part, &rest[end..])
+            }
+        };
+        parts.push(part.to_string());
+        match after.strip_prefix('.') {
+            Some(after) => rest = after,
+            None if after.is_empty() => return Some(parts),
+            None => return None,
+        }
+    }
+}
+
+/// Reads a value given on its own, like `10`, `true`, or `["area"]`, falling back to a string for
+/// text that isn't a TOML value, so that `1w` doesn't need quotes.
+pub fn parse_value(text: &str) -> Value {
+    toml::from_str::<Table>(&format!("value = {}", text))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(text.to_string()))
+}
+
+/// Writes `key` as a dotted key, quoting the parts that need it.
+pub fn dotted(key: &[String]) -> String {
+    key.iter()
+        .map(|part| {
+            if !part.is_empty() && part.chars().all(is_bare_key_char) {
+                part.clone()
+            } else {
+                Value::String(part.clone()).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn is_bare_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+/// A table with `value` at `key`.
+fn nested(key: &[String], value: Value) -> Table {
+    let mut value = value;
+    for part in key[1..].iter().rev() {
+        let mut table = Table::new();
+        table.insert(part.clone(), value);
+        value = Value::Table(table);
+    }
+    let mut table = Table::new();
+    table.insert(key[0].clone(), value);
+    table
+}
+
+/// The value at `key` in `table`.
+pub fn lookup<'a>(table: &'a Table, key: &[String]) -> Option<&'a Value> {
+    let (last, parents) = key.split_last()?;
+    let mut table = table;
+    for part in parents {
+        table = table.get(part)?.as_table()?;
+    }
+    table.get(last)
+}
+
+/// Merges `from` into `into`, which is at `key`, noting that every value in `from` came from
+/// `origin`.
+fn merge(
+    into: &mut Table,
+    from: Table,
+    key: &mut Vec<String>,
+    origins: &mut BTreeMap<Vec<String>, Origin>,
+    origin: &Origin,
+) {
+    for (name, value) in from {
+        key.push(name.clone());
+        match (into.get_mut(&name), value) {
+            (Some(Value::Table(existing)), Value::Table(table)) => {
+                merge(existing, table, key, origins, origin)
+            }
+            (_, value) => {
+                origins.retain(|k, _| !k.starts_with(key));
+                note_origins(&value, key, origins, origin);
+                into.insert(name, value);
+            }
+        }
+        key.pop();
+    }
+}
+
+/// Notes that every setting in `value`, which is at `key`, came from `origin`.
+fn note_origins(
+    value: &Value,
+    key: &mut Vec<String>,
+    origins: &mut BTreeMap<Vec<String>, Origin>,
+    origin: &Origin,
+) {
+    match value {
+        Value::Table(table) => {
+            for (name, value) in table {
+                key.push(name.clone());
+                note_origins(value, key, origins, origin);
+                key.pop();
+            }
+        }
+        _ => {
+            origins.insert(key.clone(), origin.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    #[test]
+    fn later_layers_take_precedence_key_by_key() {
+        let dir = TempDir::new("layers");
+        let home = dir.join("home");
+        let root = dir.join("gtd");
+        fs::create_dir_all(home.join(".config/gtd")).unwrap();
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            home.join(".config/gtd/config.toml"),
+            "[next]\nold-after = \"2w\"\nstuck-after = \"8w\"\n\n[contexts.aliases]\n\"@pc\" = \"@computer\"\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("gtd.toml"),
+            "[next]\nold-after = \"1w\"\n\n[contexts.capacity]\n\"@calls\" = 10\n",
+        )
+        .unwrap();
+        let vars = vec![
+            (String::from("HOME"), home.display().to_string()),
+            (String::from("GTD_NEXT__STUCK_AFTER"), String::from("6w")),
+            (String::from("GTD_CONTEXTS__LAYOUT"), String::from("files")),
+            (String::from("PATH"), String::from("/bin")),
+        ];
+        let flags = [String::from("contexts.capacity.\"@calls\"=3")];
+
+        let layers = Layers::load(&root, vars, &flags).unwrap();
+        let settings = layers
+            .settings()
+            .into_iter()
+            .map(|(key, value, origin)| format!("{} = {} # {}", key, value, origin))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            settings,
+            vec![
+                format!(
+                    "contexts.aliases.\"@pc\" = \"@computer\" # {}",
+                    home.join(".config/gtd/config.toml").display()
+                ),
+                String::from(
+                    "contexts.capacity.\"@calls\" = 3 # -c contexts.capacity.\"@calls\"=3"
+                ),
+                String::from("contexts.layout = \"files\" # $GTD_CONTEXTS__LAYOUT"),
+                format!(
+                    "next.old-after = \"1w\" # {}",
+                    root.join("gtd.toml").display()
+                ),
+                String::from("next.stuck-after = \"6w\" # $GTD_NEXT__STUCK_AFTER"),
+            ]
+        );
+        let config = layers.config().unwrap();
+        assert_eq!(
+            config
+                .contexts
+                .capacity(&crate::context::Name::new(String::from("@calls"))),
+            Some(3)
+        );
+        assert_eq!(config.contexts.aliases.len(), 1);
+    }
+
+    #[test]
+    fn user_config_is_found_in_xdg_config_home() {
+        let vars = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        assert_eq!(
+            user_path(&vars(&[("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/home/me")])),
+            Some(PathBuf::from("/xdg/gtd/config.toml"))
+        );
+        assert_eq!(
+            user_path(&vars(&[("XDG_CONFIG_HOME", ""), ("HOME", "/home/me")])),
+            Some(PathBuf::from("/home/me/.config/gtd/config.toml"))
+        );
+        assert_eq!(user_path(&vars(&[])), None);
+    }
+
+    #[test]
+    fn keys_and_values_are_parsed() {
+        let key = |k: &str| parse_key(k).map(|parts| parts.join("|"));
+        assert_eq!(key("next.old-after"), Some(String::from("next|old-after")));
+        assert_eq!(
+            key("contexts.aliases.\"@pc\""),
+            Some(String::from("contexts|aliases|@pc"))
+        );
+        assert_eq!(key("next..old-after"), None);
+        assert_eq!(key("contexts.@pc"), None);
+        assert_eq!(key("\"@pc\"x"), None);
+
+        assert_eq!(parse_value("10"), Value::Integer(10));
+        assert_eq!(parse_value("true"), Value::Boolean(true));
+        assert_eq!(parse_value("1w"), Value::String(String::from("1w")));
+        assert_eq!(parse_value("\"1w\""), Value::String(String::from("1w")));
+        assert_eq!(
+            parse_value("[\"area\"]"),
+            Value::Array(vec![Value::String(String::from("area"))])
+        );
+
+        assert_eq!(
+            env_key("GTD_PROJECT_NAMES__ID_FORMAT"),
+            Some(vec![
+                String::from("project-names"),
+                String::from("id-format")
+            ])
+        );
+        assert_eq!(env_key("GTD_"), None);
+        assert_eq!(env_key("HOME"), None);
+        assert!(parse_flag("next.old-after").is_err());
+    }
+}
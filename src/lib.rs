@@ -0,0 +1,89 @@
+//! Automates handling of common GTD tasks.
+
+// Parse errors carry the offending `pulldown_cmark` events, which makes them large by design.
+#![allow(clippy::result_large_err)]
+
+pub mod action_list;
+pub mod archive;
+pub mod attachments;
+pub mod builder;
+#[cfg(feature = "caldav")]
+pub mod caldav;
+pub mod calendar;
+pub mod complete;
+pub mod config;
+pub mod conflict;
+pub mod context;
+pub mod dedupe;
+pub mod default_context;
+pub mod diff;
+pub mod doctor;
+pub mod edit;
+pub mod email;
+pub mod estimate;
+pub mod export;
+pub mod filename;
+pub mod filter;
+pub mod fix;
+pub mod frontmatter;
+pub mod git;
+pub mod gtd;
+pub mod hooks;
+pub mod ics;
+pub mod inbox;
+pub mod init;
+pub mod issues;
+pub mod layers;
+pub mod links;
+pub mod list;
+pub mod locale;
+pub mod markdown;
+pub mod merge;
+pub mod next;
+pub mod obsidian;
+pub mod open;
+pub mod order;
+pub mod parser;
+pub mod period;
+pub mod plan;
+pub mod pomodoro;
+pub mod progress;
+pub mod project;
+pub mod project_index;
+pub mod promote;
+pub mod prompt;
+pub mod publish;
+pub mod pulldown;
+pub mod references;
+pub mod relocate;
+pub mod remind;
+pub mod resolve;
+pub mod revalidate;
+pub mod review;
+pub mod rng;
+pub mod roundtrip;
+pub mod sample;
+pub mod schema;
+pub mod selector;
+pub mod show;
+pub mod single_file;
+pub mod snapshot;
+pub mod snooze;
+pub mod someday;
+pub mod split;
+pub mod stats;
+pub mod sync;
+pub mod tag;
+#[cfg(test)]
+mod test_dir;
+pub mod throughput;
+pub mod timelog;
+pub mod today;
+#[cfg(feature = "issues")]
+pub mod trackers;
+pub mod transaction;
+pub mod trash;
+pub mod undo;
+pub mod validate;
+pub mod waiting;
+pub mod walk;
@@ -0,0 +1,119 @@
+//! Links from command output to the documents it mentions.
+
+use crate::{
+    config::{Config, LinkStyle},
+    filename,
+    obsidian::{self, Vault},
+};
+use std::{fmt::Display, path::Path};
+
+/// Formats the names of documents as links, according to the configured link style.
+#[derive(Debug, Clone, Default)]
+pub struct Linker {
+    style: LinkStyle,
+    vault: Option<Vault>,
+}
+
+impl Linker {
+    pub fn new(style: LinkStyle, vault: Option<Vault>) -> Self {
+        Self { style, vault }
+    }
+
+    /// Creates a linker for the documents in `root_dir`, detecting the vault if links need one.
+    pub fn from_config(config: &Config, root_dir: &Path) -> Self {
+        let vault = match config.links.style {
+            LinkStyle::Plain => None,
+            _ => Vault::find(root_dir, config.obsidian.vault.as_deref()),
+        };
+        Self::new(config.links.style, vault)
+    }
+
+    /// Renders `text` as a link to the file at `path`.
+    pub fn link<T: Display>(&self, path: &Path, text: T) -> String {
+        match self.style {
+            LinkStyle::Plain => text.to_string(),
+            LinkStyle::Uri => format!("{} <{}>", text, self.target(path)),
+            LinkStyle::Hyperlink => hyperlink(&self.target(path), text),
+        }
+    }
+
+    /// The URI that a link to `path` points to.
+    ///
+    /// This is an `obsidian://` URI if the file is in a vault, and a `file://` URI otherwise.
+    fn target(&self, path: &Path) -> String {
+        self.vault
+            .as_ref()
+            .and_then(|v| v.uri(path))
+            .unwrap_or_else(|| file_uri(path))
+    }
+}
+
+/// Wraps `text` in the OSC 8 escape sequence that terminals use for hyperlinks.
+fn hyperlink<T: Display>(uri: &str, text: T) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", uri, text)
+}
+
+/// The `file://` URI of `path`, which should be absolute.
+pub fn file_uri(path: &Path) -> String {
+    let path = filename::to_slash(path);
+    let encoded = path
+        .split('/')
+        .map(|part| {
+            // Windows drive letters, like `C:`, are kept as they are.
+            let is_drive = part.len() == 2 && part.ends_with(':');
+            match is_drive {
+                true => part.to_string(),
+                false => obsidian::percent_encode(part),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    match encoded.starts_with('/') {
+        true => format!("file://{}", encoded),
+        false => format!("file:///{}", encoded),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn vault() -> Vault {
+        Vault::new(String::from("Notes"), PathBuf::from("/notes"))
+    }
+
+    #[test]
+    fn plain_link_is_text() {
+        let linker = Linker::new(LinkStyle::Plain, Some(vault()));
+        let link = linker.link(Path::new("/notes/Projects/Plan trip.md"), "Plan trip");
+        assert_eq!(link, "Plan trip");
+    }
+
+    #[test]
+    fn uri_link_follows_text() {
+        let linker = Linker::new(LinkStyle::Uri, Some(vault()));
+        let link = linker.link(Path::new("/notes/Projects/Plan trip.md"), "Plan trip");
+        assert_eq!(
+            link,
+            "Plan trip <obsidian://open?vault=Notes&file=Projects%2FPlan%20trip>"
+        );
+    }
+
+    #[test]
+    fn hyperlink_wraps_text() {
+        let linker = Linker::new(LinkStyle::Hyperlink, Some(vault()));
+        let link = linker.link(Path::new("/notes/Projects/Trip.md"), "Trip");
+        assert_eq!(
+            link,
+            "\x1b]8;;obsidian://open?vault=Notes&file=Projects%2FTrip\x1b\\Trip\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn file_outside_vault_uses_file_uri() {
+        let linker = Linker::new(LinkStyle::Uri, None);
+        let link = linker.link(Path::new("/gtd/Projects/Plan trip.md"), "Plan trip");
+        assert_eq!(link, "Plan trip <file:///gtd/Projects/Plan%20trip.md>");
+    }
+}
@@ -0,0 +1,75 @@
+//! Listing of projects.
+
+use crate::{
+    diff::WriteMode,
+    filter::Filter,
+    gtd::Documents,
+    project::{Project, Status},
+};
+use std::{fmt, io::Error as IoError};
+
+/// The frontmatter key that project progress is written to.
+pub const PROGRESS_KEY: &str = "progress";
+
+/// Lists the projects that match `filter`, grouped by status.
+pub fn list(docs: &Documents, filter: &Filter) {
+    let projects = filter.select(docs);
+
+    let groups = [Status::InProgress, Status::Someday, Status::Complete];
+
+    let mut is_first = true;
+    for status in groups.iter() {
+        let mut group = projects.iter().filter(|p| p.status == *status).peekable();
+        if group.peek().is_none() {
+            continue;
+        }
+
+        if !is_first {
+            println!();
+        }
+        is_first = false;
+
+        println!("{}:", status);
+        for project in group {
+            println!("- {}", ProjectLine(project));
+        }
+    }
+}
+
+struct ProjectLine<'a>(&'a Project);
+
+impl<'a> fmt::Display for ProjectLine<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.title())?;
+        for tag in self.0.tags.iter() {
+            write!(f, " {}", tag)?;
+        }
+        if let Some(percent) = self.0.progress().percent() {
+            write!(f, " ({}%)", percent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the completion percentage of each project into its frontmatter according to `mode`,
+/// returning the number of files that changed.
+///
+/// Projects without any actions have their progress removed.
+pub fn write_progress(docs: &Documents, mode: WriteMode) -> Result<usize, IoError> {
+    let projects = docs.projects().collect::<Vec<_>>();
+
+    let mut written = 0;
+    for project in projects {
+        let percent = project.progress().percent();
+        let changed = docs
+            .loader()
+            .update_project_frontmatter(&project.name, mode, |fm| match percent {
+                Some(percent) => fm.set(PROGRESS_KEY, &percent.to_string()),
+                None => fm.remove(PROGRESS_KEY),
+            })?;
+        if changed {
+            written += 1;
+        }
+    }
+    Ok(written)
+}
@@ -0,0 +1,281 @@
+//! Showing dates and messages in the language and formats set in `[locale]`.
+//!
+//! Messages are kept in a Fluent file for each language, which is built into gtd, like
+//! `locales/en.ftl`. Only the parts of Fluent that gtd's messages need are read: messages, with
+//! variables like `{ $count }`, that can go on over indented lines, and comments. A message that a
+//! language doesn't have is shown in English.
+
+use crate::config::{DateFormat, LocaleConfig};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::{collections::BTreeMap, env, error::Error, fmt};
+
+/// The languages that gtd has messages in, and their Fluent files.
+const BUNDLES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+/// The language that messages fall back to.
+const FALLBACK: &str = "en";
+
+/// The messages of a language, by ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bundle {
+    messages: BTreeMap<String, String>,
+}
+
+impl Bundle {
+    /// Reads the messages in the Fluent file `text`.
+    pub fn parse(text: &str) -> Self {
+        let mut messages = BTreeMap::new();
+        let mut current: Option<(String, String)> = None;
+        for line in text.lines() {
+            let is_continuation = line.starts_with(' ') && !line.trim().is_empty();
+            if is_continuation {
+                if let Some((_, value)) = current.as_mut() {
+                    if !value.is_empty() {
+                        value.push('\n');
+                    }
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            messages.extend(current.take());
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some((id, value)) = line.split_once('=') {
+                current = Some((id.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        messages.extend(current);
+        Self { messages }
+    }
+
+    /// The message `id`, with its variables filled in from `args`. Variables that aren't given
+    /// are left as they are.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> Option<String> {
+        let mut message = self.messages.get(id)?.clone();
+        for (name, value) in args {
+            message = message.replace(&format!("{{ ${} }}", name), &value.to_string());
+        }
+        Some(message)
+    }
+}
+
+/// The language and formats that things are shown in.
+#[derive(Debug, Clone)]
+pub struct Locale {
+    language: String,
+    messages: Bundle,
+    fallback: Bundle,
+    date_format: DateFormat,
+    first_day_of_week: Weekday,
+}
+
+impl Locale {
+    /// The locale set in `config`, with the language from the environment if it isn't set there.
+    /// Languages from the environment that gtd doesn't have messages in fall back to English.
+    pub fn from_config(config: &LocaleConfig) -> Result<Self, LocaleError> {
+        let language = match &config.language {
+            Some(language) => {
+                if bundle(language).is_none() {
+                    return Err(LocaleError::UnknownLanguage(language.clone()));
+                }
+                language.clone()
+            }
+            None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|var| env::var(var).ok())
+                .find(|value| !value.is_empty())
+                .map(|value| language_of(&value))
+                .filter(|language| bundle(language).is_some())
+                .unwrap_or_else(|| FALLBACK.to_string()),
+        };
+        Ok(Self::new(
+            &language,
+            config.date_format,
+            config.first_day_of_week,
+        ))
+    }
+
+    fn new(language: &str, date_format: DateFormat, first_day_of_week: Weekday) -> Self {
+        let messages = Bundle::parse(bundle(language).unwrap_or_default());
+        let fallback = Bundle::parse(bundle(FALLBACK).unwrap_or_default());
+        Self {
+            language: language.to_string(),
+            messages,
+            fallback,
+            date_format,
+            first_day_of_week,
+        }
+    }
+
+    /// The code of the language messages are shown in, like `en`.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The message `id`, with its variables filled in from `args`. If there's no such message,
+    /// which is a bug, the ID is shown instead.
+    pub fn message(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        self.messages
+            .format(id, args)
+            .or_else(|| self.fallback.format(id, args))
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// `date`, formatted to be shown in a report.
+    pub fn date(&self, date: NaiveDate) -> String {
+        match self.date_format {
+            DateFormat::Iso => date.format("%Y-%m-%d").to_string(),
+            DateFormat::Local => date.format(&self.message("date-format", &[])).to_string(),
+        }
+    }
+
+    /// The day that weeks start on.
+    pub fn first_day_of_week(&self) -> Weekday {
+        self.first_day_of_week
+    }
+
+    /// The first day of the week that `date` is in.
+    pub fn week_start(&self, date: NaiveDate) -> NaiveDate {
+        week_start(date, self.first_day_of_week)
+    }
+}
+
+impl Default for Locale {
+    /// English, with ISO dates and weeks that start on Monday.
+    fn default() -> Self {
+        Self::new(FALLBACK, DateFormat::Iso, Weekday::Mon)
+    }
+}
+
+/// The first day of the week that `date` is in, where weeks start on `first_day`.
+pub fn week_start(date: NaiveDate, first_day: Weekday) -> NaiveDate {
+    date - Duration::days(date.weekday().days_since(first_day) as i64)
+}
+
+fn bundle(language: &str) -> Option<&'static str> {
+    BUNDLES
+        .iter()
+        .find(|(code, _)| *code == language)
+        .map(|(_, text)| *text)
+}
+
+/// The language in a locale from the environment, like `de` in `de_DE.UTF-8`.
+fn language_of(locale: &str) -> String {
+    locale
+        .split(&['_', '.', '@', '-'][..])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocaleError {
+    /// There are no messages in this language.
+    UnknownLanguage(String),
+}
+
+impl fmt::Display for LocaleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownLanguage(language) => {
+                let known = BUNDLES.iter().map(|(code, _)| *code).collect::<Vec<_>>();
+                write!(
+                    f,
+                    "there are no messages in \"{}\" (try one of {})",
+                    language,
+                    known.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl Error for LocaleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::format::{Item, StrftimeItems};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn messages_are_read_from_fluent() {
+        let bundle = Bundle::parse(
+            "# A comment.\n\n## A section\n\ngreeting = Hello, { $name }!\nlong =\n    First line\n    second line\nempty-line-after = Yes\n\n",
+        );
+        assert_eq!(
+            bundle.format("greeting", &[("name", &"Kyle")]),
+            Some(String::from("Hello, Kyle!"))
+        );
+        assert_eq!(
+            bundle.format("long", &[]),
+            Some(String::from("First line\nsecond line"))
+        );
+        assert_eq!(
+            bundle.format("empty-line-after", &[]),
+            Some(String::from("Yes"))
+        );
+        assert_eq!(bundle.format("missing", &[]), None);
+    }
+
+    #[test]
+    fn every_language_has_the_english_messages() {
+        let english = Bundle::parse(bundle(FALLBACK).unwrap());
+        for (code, text) in BUNDLES {
+            let messages = Bundle::parse(text);
+            let missing = english
+                .messages
+                .keys()
+                .filter(|id| !messages.messages.contains_key(*id))
+                .collect::<Vec<_>>();
+            assert!(missing.is_empty(), "{} is missing {:?}", code, missing);
+            let date_format = messages.format("date-format", &[]).unwrap();
+            let is_valid = !StrftimeItems::new(&date_format).any(|i| i == Item::Error);
+            assert!(is_valid, "{} has an invalid date format", code);
+        }
+    }
+
+    #[test]
+    fn messages_and_dates_follow_the_language() {
+        let config = LocaleConfig {
+            language: Some(String::from("de")),
+            date_format: DateFormat::Local,
+            first_day_of_week: Weekday::Sun,
+        };
+        let locale = Locale::from_config(&config).unwrap();
+        assert_eq!(
+            locale.message("restored-files", &[("count", &3)]),
+            "3 Dateien wiederhergestellt."
+        );
+        assert_eq!(locale.date(date(2024, 1, 5)), "05.01.2024");
+        assert_eq!(locale.week_start(date(2024, 1, 10)), date(2024, 1, 7));
+
+        let locale = Locale::default();
+        assert_eq!(locale.date(date(2024, 1, 5)), "2024-01-05");
+        assert_eq!(locale.week_start(date(2024, 1, 7)), date(2024, 1, 1));
+        assert_eq!(locale.message("no-such-message", &[]), "no-such-message");
+
+        let config = LocaleConfig {
+            language: Some(String::from("xx")),
+            ..LocaleConfig::default()
+        };
+        assert_eq!(
+            Locale::from_config(&config).unwrap_err(),
+            LocaleError::UnknownLanguage(String::from("xx"))
+        );
+    }
+
+    #[test]
+    fn language_is_taken_from_locale_names() {
+        assert_eq!(language_of("de_DE.UTF-8"), "de");
+        assert_eq!(language_of("en"), "en");
+        assert_eq!(language_of("C.UTF-8"), "c");
+    }
+}
@@ -1,18 +1,72 @@
-use self::gtd::Documents;
 use argh::FromArgs;
-use std::env;
-
-mod context;
-mod gtd;
-mod markdown;
-mod parser;
-mod project;
-mod pulldown;
-mod validate;
+use chrono::Local;
+use gtd::{
+    action_list, archive, attachments,
+    calendar::{self, Calendar},
+    complete,
+    config::{self, Config, ContextLayout, Setting},
+    conflict,
+    context::Name as ContextName,
+    dedupe,
+    diff::WriteMode,
+    doctor,
+    estimate::Estimate,
+    export,
+    filter::Filter,
+    git::{self, Snapshot},
+    gtd::{Documents, Loader},
+    hooks::{self, Event},
+    inbox, init,
+    layers::{self, Layers},
+    links::Linker,
+    list,
+    locale::Locale,
+    merge, next, open,
+    period::Period,
+    plan, pomodoro,
+    project::{ActionId, Status},
+    project_index::{self, GroupBy},
+    promote, prompt, publish, relocate, remind,
+    revalidate::{self, Baseline},
+    review, roundtrip,
+    sample::Sample,
+    schema,
+    selector::{Glob, Selector},
+    show,
+    snapshot::{self, SnapshotDiff},
+    snooze::{self, Until},
+    someday,
+    split::{self, SplitBy},
+    stats::{self, Stats},
+    sync::{Resolution, SyncOptions},
+    tag::Tag,
+    throughput, timelog, today, trash,
+    undo::Undo as UndoRecord,
+    validate::{self, Severity},
+    waiting,
+};
+#[cfg(feature = "notifications")]
+use std::time::Duration;
+use std::{
+    env,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use toml::Value;
 
 /// Task management application.
 #[derive(Debug, FromArgs)]
 struct Gtd {
+    /// set a config key for this run, like next.old-after=1w, over the config files and
+    /// environment.
+    #[argh(option, short = 'c')]
+    config: Vec<String>,
+    /// don't show progress while reading and checking documents, the same as -c
+    /// progress.show=false.
+    #[argh(switch, short = 'q')]
+    quiet: bool,
     #[argh(subcommand)]
     subcommand: Subcommand,
 }
@@ -21,21 +75,1970 @@ struct Gtd {
 #[argh(subcommand)]
 enum Subcommand {
     Validate(Validate),
+    New(New),
+    List(List),
+    Next(Next),
+    Open(Open),
+    Show(Show),
+    Report(Report),
+    Start(Start),
+    Stop(Stop),
+    Pomodoro(Pomodoro),
+    Remind(Remind),
+    Snooze(Snooze),
+    Promote(Promote),
+    CompleteAction(CompleteAction),
+    CompleteProject(CompleteProject),
+    MoveAction(MoveAction),
+    Archive(Archive),
+    Waiting(Waiting),
+    Nag(Nag),
+    Capture(Capture),
+    Review(Review),
+    Sync(Sync),
+    Issues(Issues),
+    Import(Import),
+    LintLinks(LintLinks),
+    Explain(Explain),
+    Doctor(Doctor),
+    Today(Today),
+    Plan(Plan),
+    Export(Export),
+    Diff(Diff),
+    Publish(Publish),
+    Schema(Schema),
+    Dashboard(Dashboard),
+    Status(StatusBar),
+    MergeContext(MergeContext),
+    Dedupe(Dedupe),
+    SplitContext(SplitContext),
+    Reconcile(Reconcile),
+    ProjectsFile(ProjectsFile),
+    Undo(Undo),
+    Trash(Trash),
+    Resolve(Resolve),
+    Log(Log),
+    Roundtrip(Roundtrip),
+    Config(Configure),
+    Init(Init),
 }
 
 /// Validates all projects and lists.
 #[derive(Debug, FromArgs)]
 #[argh(subcommand, name = "validate")]
-struct Validate {}
+struct Validate {
+    /// also print how many problems each rule found.
+    #[argh(switch)]
+    statistics: bool,
+    /// how to print the problems: "text" (the default) or "json".
+    #[argh(option, default = "validate::Format::Text")]
+    format: validate::Format,
+    /// fix the problems that can be fixed automatically before checking.
+    #[argh(switch)]
+    fix: bool,
+    /// read projects one at a time instead of all at once, running only the rules that check
+    /// each project on its own.
+    #[argh(switch)]
+    streaming: bool,
+    /// with --fix, the IDs of the rules to fix the problems of, like GTD016. Every rule's are fixed
+    /// if none are given.
+    #[argh(positional)]
+    rules: Vec<String>,
+}
+
+/// Creates a new project.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "new")]
+struct New {
+    /// title of the project.
+    #[argh(positional)]
+    title: String,
+}
+
+/// Lists all projects.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "list")]
+struct List {
+    /// only list projects with an action in this context. Can be given more than once.
+    #[argh(option)]
+    context: Vec<ContextName>,
+
+    /// only list projects whose title matches this pattern, like "Tax*". Can be given more than
+    /// once.
+    #[argh(option)]
+    project: Vec<Glob>,
+
+    /// only list projects with this tag, or a tag nested under it. Can be given more than once.
+    #[argh(option)]
+    tag: Vec<Tag>,
+
+    /// leave out projects with this tag, or a tag nested under it. Can be given more than once.
+    #[argh(option)]
+    exclude_tag: Vec<Tag>,
+
+    /// write each project's completion percentage into its frontmatter.
+    #[argh(switch)]
+    write_progress: bool,
+
+    /// with --write-progress, show the changes without writing them.
+    #[argh(switch)]
+    dry_run: bool,
+
+    /// with --write-progress, ask before writing each change.
+    #[argh(switch)]
+    interactive: bool,
+}
+
+/// Lists next actions by context.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "next")]
+struct Next {
+    /// only list actions in this context. Can be given more than once.
+    #[argh(option)]
+    context: Vec<ContextName>,
+
+    /// only list actions in projects whose title matches this pattern, like "Tax*". Can be given
+    /// more than once.
+    #[argh(option)]
+    project: Vec<Glob>,
+
+    /// only list actions (or actions in projects) with this tag, or a tag nested under it. Can be
+    /// given more than once.
+    #[argh(option)]
+    tag: Vec<Tag>,
+
+    /// leave out actions (or actions in projects) with this tag, or a tag nested under it. Can be
+    /// given more than once.
+    #[argh(option)]
+    exclude_tag: Vec<Tag>,
+
+    /// list today's appointments from the configured calendar first.
+    #[argh(switch)]
+    with_calendar: bool,
+
+    /// print one action picked at random instead, favoring high priority and older actions.
+    #[argh(switch)]
+    pick: bool,
+
+    /// with --pick, the seed to pick with, so the same action is picked each time.
+    #[argh(option)]
+    seed: Option<u64>,
+}
+
+/// Lists overdue and due actions and the most important next actions in each context.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "today")]
+struct Today {
+    /// also write the list to Today.md in the GTD folder.
+    #[argh(switch)]
+    write: bool,
+}
+
+/// Picks actions whose estimates fit in the time available today.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "plan")]
+struct Plan {
+    /// the time available, like "4h" or "2h30m".
+    #[argh(option)]
+    capacity: Estimate,
+}
+
+/// Prints all projects and contexts as JSON.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "export")]
+struct Export {
+    /// only export contexts with this name, and projects with an action in one of them. Can be
+    /// given more than once.
+    #[argh(option)]
+    context: Vec<ContextName>,
+
+    /// only export projects whose title matches this pattern, like "Tax*". Can be given more than
+    /// once.
+    #[argh(option)]
+    project: Vec<Glob>,
+
+    /// only export projects with this tag, or a tag nested under it. Can be given more than once.
+    #[argh(option)]
+    tag: Vec<Tag>,
+
+    /// leave out projects with this tag, or a tag nested under it. Can be given more than once.
+    #[argh(option)]
+    exclude_tag: Vec<Tag>,
+}
+
+/// Compares the projects to an earlier `gtd export`, listing the actions that were completed,
+/// added, moved, or removed since, and the projects that stalled.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "diff")]
+struct Diff {
+    /// the JSON file that `gtd export` wrote earlier.
+    #[argh(positional)]
+    export: PathBuf,
+
+    /// how to print the changes: "text" (the default) or "json".
+    #[argh(option, default = "validate::Format::Text")]
+    format: validate::Format,
+}
+
+/// Writes projects, contexts, and reports as a static website.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "publish")]
+struct Publish {
+    /// the folder to write the site into, which is made if it doesn't exist.
+    #[argh(option)]
+    out: PathBuf,
+}
+
+/// Prints the counts of projects and actions from the last command, without reading every file.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "dashboard")]
+struct Dashboard {}
+
+/// Prints the cached counts of projects and actions, quickly enough for a shell prompt.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "status")]
+struct StatusBar {
+    /// print the counts on one line, as set by `segments` in `[status]`.
+    #[argh(switch)]
+    oneline: bool,
+}
+
+/// Prints the JSON Schema of the output of `export`, `validate --format json`, or
+/// `diff --format json`.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "schema")]
+struct Schema {
+    /// which output to describe: "export", "diagnostics", or "diff".
+    #[argh(positional)]
+    format: schema::Format,
+}
+
+/// Opens a project or context in an editor.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "open")]
+struct Open {
+    /// name of the project or context to open.
+    #[argh(positional)]
+    name: String,
+
+    /// ID of an action in the project to jump to.
+    #[argh(option)]
+    action: Option<String>,
+}
+
+/// Shows the details of a project.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "show")]
+struct Show {
+    /// name of the project to show.
+    #[argh(positional)]
+    name: String,
+}
+
+/// Prints reports about the GTD folder.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "report")]
+struct Report {
+    #[argh(subcommand)]
+    report: ReportKind,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum ReportKind {
+    Throughput(Throughput),
+    Time(Time),
+}
+
+/// Shows actions completed per week, and when in-progress projects should be finished.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "throughput")]
+struct Throughput {
+    /// number of weeks to report on.
+    #[argh(option, default = "8")]
+    weeks: usize,
+
+    /// print the report as CSV.
+    #[argh(switch)]
+    csv: bool,
+}
+
+/// Shows the time spent on each project and context.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "time")]
+struct Time {}
+
+/// Starts a timer on an action, stopping the running timer.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "start")]
+struct Start {
+    /// text or ID of the action.
+    #[argh(positional)]
+    action: String,
+}
+
+/// Stops the running timer.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "stop")]
+struct Stop {}
+
+/// Runs a pomodoro focus timer on an action.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "pomodoro")]
+struct Pomodoro {
+    /// text or ID of the action.
+    #[argh(positional)]
+    action: String,
+
+    /// length of the pomodoro in minutes, instead of the configured length.
+    #[argh(option)]
+    minutes: Option<u64>,
+}
+
+/// Lists actions that are due, overdue, or no longer deferred.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "remind")]
+struct Remind {
+    /// keep running, showing desktop notifications for new reminders.
+    #[argh(switch)]
+    daemon: bool,
+
+    /// minutes between checks for reminders when running as a daemon.
+    #[argh(option, default = "15")]
+    interval: u64,
+}
+
+/// Defers an action, hiding it from `next` until then.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "snooze")]
+struct Snooze {
+    /// text or ID of the action.
+    #[argh(positional)]
+    action: String,
+
+    /// date to defer the action until, or a period from today like "3d", "2w", or "1m".
+    #[argh(positional)]
+    until: Until,
+}
+
+/// Moves a waiting or upcoming action to its project's active actions.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "promote")]
+struct Promote {
+    /// text or ID of the action.
+    #[argh(positional)]
+    action: String,
+}
+
+/// Moves the actions in one context into another, and makes the first an alias of the second.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "merge-context")]
+struct MergeContext {
+    /// the context to move actions out of, which is deleted.
+    #[argh(positional)]
+    source: ContextName,
+    /// the context to move actions into.
+    #[argh(positional)]
+    target: ContextName,
+    /// print the changes without making them.
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+/// Finds projects that are copies of each other, and merges or deletes the copies.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "dedupe")]
+struct Dedupe {
+    /// print the changes without making them.
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+/// Groups the actions in a long context under headings, or into separate contexts.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "split-context")]
+struct SplitContext {
+    /// the context to split.
+    #[argh(positional)]
+    name: ContextName,
+    /// what to group actions by: "project" (the default) or "tag".
+    #[argh(option, default = "SplitBy::Project")]
+    by: SplitBy,
+    /// move each group into its own context instead of under a heading.
+    #[argh(switch)]
+    files: bool,
+    /// print the changes without making them.
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+/// Checks that the context files and the action list agree, or regenerates one from the other.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "reconcile")]
+struct Reconcile {
+    /// regenerate the other layout from this one: "files" or "list".
+    #[argh(option)]
+    from: Option<ContextLayout>,
+    /// print the changes without making them.
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+/// Manages the index of projects in Projects.md.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "projects-file")]
+struct ProjectsFile {
+    #[argh(subcommand)]
+    command: ProjectsFileCommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum ProjectsFileCommand {
+    Regenerate(ProjectsFileRegenerate),
+}
+
+/// Rewrites the index of projects from the project files.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "regenerate")]
+struct ProjectsFileRegenerate {
+    /// what to group projects by: "status" (the default) or "area".
+    #[argh(option, default = "GroupBy::Status")]
+    by: GroupBy,
+    /// print the changes without making them.
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+/// Undoes the last `merge-context`, `split-context`, or `reconcile`.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "undo")]
+struct Undo {}
+
+/// Lists or restores the files that commands have deleted.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "trash")]
+struct Trash {
+    #[argh(subcommand)]
+    command: TrashCommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum TrashCommand {
+    List(TrashList),
+    Restore(TrashRestore),
+}
+
+/// Lists the files in the trash, by when they were deleted.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "list")]
+struct TrashList {}
+
+/// Moves files in the trash back to where they were.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "restore")]
+struct TrashRestore {
+    /// when the files were deleted, as shown by `gtd trash list`, like 20240105093000.
+    #[argh(positional)]
+    stamp: String,
+}
+
+/// Moves an action to another project, pointing the links to it at the new project.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "move-action")]
+struct MoveAction {
+    /// text or ID of the action.
+    #[argh(positional)]
+    action: String,
+
+    /// title or name of the project to move it to.
+    #[argh(option)]
+    to: String,
+
+    /// print the changes without making them.
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+/// Completes an action, or every active action in the selected projects.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "complete-action")]
+struct CompleteAction {
+    /// text or ID of the action.
+    #[argh(positional)]
+    action: Option<String>,
+
+    /// complete every active action in the selected projects.
+    #[argh(switch)]
+    all_active: bool,
+
+    /// only select projects whose title or name matches this pattern, like "Trip*".
+    #[argh(option)]
+    project: Option<Glob>,
+
+    /// only select projects with this tag, or a tag nested under it.
+    #[argh(option)]
+    tag: Option<Tag>,
+
+    /// only select projects with this status: someday, in-progress, or complete.
+    #[argh(option)]
+    status: Option<Status>,
+
+    /// only select projects with no activity in this period, like "90d".
+    #[argh(option)]
+    older_than: Option<Period>,
+
+    /// don't ask before completing the actions.
+    #[argh(switch)]
+    yes: bool,
+
+    /// promote the first upcoming action of a project whose last active action is completed.
+    #[argh(switch)]
+    chain: bool,
+}
+
+/// Marks a project as complete, recording what came of it and removing its actions from contexts.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "complete-project")]
+struct CompleteProject {
+    /// title or ID of the project.
+    #[argh(positional)]
+    project: String,
+
+    /// a short note on what came of the project, instead of asking for one.
+    #[argh(option)]
+    outcome: Option<String>,
+
+    /// complete any remaining actions without asking first.
+    #[argh(switch)]
+    yes: bool,
+
+    /// move the project into the archive folder once it's complete.
+    #[argh(switch)]
+    archive: bool,
+}
+
+/// Moves the selected projects into the archive folder.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "archive")]
+struct Archive {
+    /// only select projects whose title or name matches this pattern, like "Trip*".
+    #[argh(option)]
+    project: Option<Glob>,
+
+    /// only select projects with this tag, or a tag nested under it.
+    #[argh(option)]
+    tag: Option<Tag>,
+
+    /// only select projects with this status: someday, in-progress, or complete.
+    #[argh(option)]
+    status: Option<Status>,
+
+    /// only select projects with no activity in this period, like "90d".
+    #[argh(option)]
+    older_than: Option<Period>,
+
+    /// also archive the attachments that only the selected projects link to.
+    #[argh(switch)]
+    attachments: bool,
+
+    /// don't ask before archiving the projects.
+    #[argh(switch)]
+    yes: bool,
+}
+
+/// Lists waiting actions by who they're waiting on.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "waiting")]
+struct Waiting {}
+
+/// Prints a follow-up list of the actions waiting on someone.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "nag")]
+struct Nag {
+    /// name of the person.
+    #[argh(positional)]
+    person: String,
+}
+
+/// Captures an item into the inbox.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "capture")]
+struct Capture {
+    /// text of the item.
+    #[argh(positional)]
+    text: Option<String>,
+
+    /// capture the email in this file instead.
+    #[argh(option)]
+    from_eml: Option<String>,
+
+    /// capture the new emails in this maildir instead.
+    #[argh(option)]
+    maildir: Option<String>,
+}
+
+/// Lists problems to look at during a weekly review.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "review")]
+struct Review {
+    /// go through old someday projects one at a time instead, deciding what to do with each.
+    #[argh(switch)]
+    someday: bool,
+
+    /// go through the projects due for review by their own `review-every` instead, recording when
+    /// each one was reviewed.
+    #[argh(switch)]
+    due: bool,
+
+    /// go through the actions that have been waiting too long instead, offering to add an action to
+    /// follow up on each one.
+    #[argh(switch)]
+    waiting: bool,
+
+    /// with --someday, review projects that have been someday for this long, like "90d" (3m by
+    /// default).
+    #[argh(option, default = "Period::Months(3)")]
+    older_than: Period,
+}
+
+/// Syncs actions with the configured CalDAV calendar.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "sync")]
+struct Sync {
+    /// how to settle due dates changed on both sides: "local", "remote", or "ask" (the default).
+    #[argh(option, default = "Resolution::Ask")]
+    conflicts: Resolution,
+
+    /// print the changes without making them.
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+/// Works with the issues that actions are linked to with `@issue(…)`.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "issues")]
+struct Issues {
+    #[argh(subcommand)]
+    command: IssuesCommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum IssuesCommand {
+    Sync(IssuesSync),
+}
+
+/// Lists the incomplete actions whose issue is closed.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "sync")]
+struct IssuesSync {
+    /// also complete the actions.
+    #[argh(switch)]
+    complete: bool,
+
+    /// don't ask before completing the actions.
+    #[argh(switch)]
+    yes: bool,
+}
+
+/// Adds the open issues in a GitHub repository as actions in a project named after it.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "import")]
+struct Import {
+    /// the repository to import issues from, like `owner/repo`.
+    #[argh(option)]
+    github: String,
+
+    /// only import issues assigned to this user, or to you if it's `me`.
+    #[argh(option, default = "String::from(\"me\")")]
+    assignee: String,
+}
+
+/// Checks that linked attachments exist, and lists attachments that nothing links to.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "lint-links")]
+struct LintLinks {}
+
+/// Explains a validation rule, or lists all of them.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "explain")]
+struct Explain {
+    /// ID of the rule, like GTD012.
+    #[argh(positional)]
+    rule: Option<String>,
+}
+
+/// Resolves the conflicts that git or a sync tool left in files, one list item at a time.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "resolve")]
+struct Resolve {}
+
+/// Lists the recent commits that gtd made with `auto-commit` in `[git]`.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "log")]
+struct Log {
+    /// how many commits to list.
+    #[argh(option, default = "20")]
+    count: usize,
+}
+
+/// Shows where a project or context file would change if gtd read it and wrote it back, and exits
+/// with an error if it would.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "roundtrip")]
+struct Roundtrip {
+    /// path of the file.
+    #[argh(positional)]
+    file: PathBuf,
+}
+
+/// Shows or changes the config, as put together from the config files, environment, and flags.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "config")]
+struct Configure {
+    #[argh(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum ConfigCommand {
+    Show(ConfigShow),
+    Get(ConfigGet),
+    Set(ConfigSet),
+}
+
+/// Lists every setting that's set, as dotted keys.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "show")]
+struct ConfigShow {
+    /// also show where each setting came from.
+    #[argh(switch)]
+    origin: bool,
+}
+
+/// Prints the value of a setting, or of every setting under a table like contexts.aliases.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "get")]
+struct ConfigGet {
+    /// the dotted key of the setting, like next.old-after.
+    #[argh(positional)]
+    key: String,
+}
+
+/// Sets a setting in the GTD folder's gtd.toml, or in the user's config.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "set")]
+struct ConfigSet {
+    /// the dotted key of the setting, like next.old-after.
+    #[argh(positional)]
+    key: String,
+    /// the value, like 1w, 10, true, or ["GTD012"].
+    #[argh(positional)]
+    value: String,
+    /// set it in the user's config, for every GTD folder, instead.
+    #[argh(switch)]
+    user: bool,
+}
+
+/// Sets up a GTD folder, creating the folders, files, and config it's missing without changing
+/// anything that's already there.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "init")]
+struct Init {
+    /// also add example projects and contexts, generated at random.
+    #[argh(switch)]
+    example: bool,
+    /// with --example, how many projects to generate.
+    #[argh(option, default = "5")]
+    projects: usize,
+    /// with --example, how many contexts to generate.
+    #[argh(option, default = "3")]
+    contexts: usize,
+    /// with --example, how many actions in contexts link to actions or projects that don't exist.
+    #[argh(option, default = "0")]
+    broken_links: usize,
+    /// with --example, the seed to generate with, so the same folder is generated each time.
+    #[argh(option)]
+    seed: Option<u64>,
+}
+
+/// Shows how the GTD folder is set up, and how many files in it are read or ignored.
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "doctor")]
+struct Doctor {}
 
 fn main() {
-    let gtd: Gtd = argh::from_env();
+    let mut gtd: Gtd = argh::from_env();
     let cur_dir = env::current_dir().unwrap();
+    if gtd.quiet {
+        gtd.config.push(String::from("progress.show=false"));
+    }
+    let layers = Layers::load(&cur_dir, env::vars(), &gtd.config).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    // The config is shown before it's checked, so that it can be used to find what's wrong.
+    if let Subcommand::Config(opts) = &gtd.subcommand {
+        match &opts.command {
+            ConfigCommand::Show(opts) => show_config(&layers, opts.origin),
+            ConfigCommand::Get(opts) => get_config(&layers, &opts.key),
+            ConfigCommand::Set(opts) => set_config(&cur_dir, opts),
+        }
+        return;
+    }
+    let config = layers.config().unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    let locale = Locale::from_config(&config.locale).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+
+    // Note what's changed in the folder first, so only what the command changes is committed.
+    let commit = match commit_message(&gtd.subcommand) {
+        Some(message) if config.git.auto_commit && git::is_repo(&cur_dir) => {
+            match Snapshot::take(&cur_dir) {
+                Ok(snapshot) => Some((snapshot, message)),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+    // Check the documents before a command changes them too, so the problems it introduces can be
+    // told apart from ones that were already there.
+    let baseline = match commit_message(&gtd.subcommand) {
+        Some(_)
+            if config.validate.check_changes
+                && !matches!(gtd.subcommand, Subcommand::Validate(_)) =>
+        {
+            let docs = Documents::load(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            docs.map(|docs| (Baseline::take(&docs, &config, today), cur_dir.clone()))
+        }
+        _ => None,
+    };
 
     match gtd.subcommand {
-        Subcommand::Validate(_opts) => {
-            let docs = Documents::load(cur_dir);
-            validate::validate(docs.unwrap());
+        Subcommand::Validate(opts) if opts.streaming => {
+            if opts.fix {
+                eprintln!("Error: --fix can't be used with --streaming");
+                process::exit(1);
+            }
+            let loader = Loader::from_config(cur_dir, &config);
+            let today = Local::now().date_naive();
+            let result =
+                validate::validate_streaming(&loader, &config, today, opts.statistics, opts.format);
+            match result {
+                Ok(summary) if summary.count(Severity::Error) > 0 => process::exit(1),
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Validate(opts) => {
+            if !opts.fix && !opts.rules.is_empty() {
+                eprintln!("Error: rules can only be given with --fix");
+                process::exit(1);
+            }
+            for id in opts.rules.iter() {
+                match validate::rule(id) {
+                    Some(rule) if rule.is_fixable => {}
+                    Some(rule) => {
+                        eprintln!("Error: {} can't fix problems automatically", rule.id);
+                        process::exit(1);
+                    }
+                    None => {
+                        eprintln!("Error: there's no rule \"{}\"", id);
+                        process::exit(1);
+                    }
+                }
+            }
+            let mut docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            if opts.fix {
+                match validate::fix(&docs, &config, today, &opts.rules) {
+                    Ok(fixes) if !fixes.is_empty() => {
+                        if opts.format == validate::Format::Text {
+                            for fix in fixes.iter() {
+                                println!("{}", fix);
+                            }
+                            println!();
+                        }
+                        docs.reload().unwrap();
+                        run_hook(&cur_dir, &config, Event::Fix { fixes });
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        process::exit(1);
+                    }
+                }
+            }
+            let summary = validate::validate(docs, &config, today, opts.statistics, opts.format);
+            if summary.count(Severity::Error) > 0 {
+                process::exit(1);
+            }
+        }
+        Subcommand::New(opts) => {
+            let loader = Loader::from_config(cur_dir, &config);
+            match loader.create_project(&opts.title) {
+                Ok(name) => println!("{}", locale.message("created-project", &[("name", &name)])),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::List(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let filter = Filter {
+                contexts: opts.context,
+                projects: opts.project,
+                tags: opts.tag,
+                exclude_tags: opts.exclude_tag,
+            };
+            list::list(&docs, &filter);
+            if opts.write_progress {
+                let mode = WriteMode::from_flags(opts.dry_run, opts.interactive);
+                if let Err(e) = list::write_progress(&docs, mode) {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Next(opts) => {
+            let links = Linker::from_config(&config, &cur_dir);
+            if opts.with_calendar {
+                match load_calendar(&cur_dir, &config) {
+                    Some(calendar) => calendar::print_agenda(&calendar, Local::now().date_naive()),
+                    None => {
+                        eprintln!("Error: no calendar is configured");
+                        process::exit(1);
+                    }
+                }
+            }
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            let filter = Filter {
+                contexts: opts.context,
+                projects: opts.project,
+                tags: opts.tag,
+                exclude_tags: opts.exclude_tag,
+            };
+            if opts.pick {
+                let seed = opts.seed.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_nanos() as u64)
+                });
+                next::pick(&docs, &links, today, &filter, seed);
+            } else {
+                next::next(&docs, &links, today, &filter, &config.next);
+            }
+        }
+        Subcommand::Today(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let date = Local::now().date_naive();
+            let text = today::render(&today::today(&docs, date, config.today.per_context));
+            print!("{}", text);
+            if opts.write {
+                match today::write(&docs, &text) {
+                    Ok((path, true)) => println!(
+                        "\n{}",
+                        locale.message("wrote-file", &[("path", &path.display())])
+                    ),
+                    Ok((path, false)) => println!(
+                        "\n{}",
+                        locale.message("file-is-up-to-date", &[("path", &path.display())])
+                    ),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        process::exit(1);
+                    }
+                }
+            }
+        }
+        Subcommand::Plan(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let date = Local::now().date_naive();
+            print!("{}", plan::render(&plan::plan(&docs, opts.capacity, date)));
+        }
+        Subcommand::Export(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let filter = Filter {
+                contexts: opts.context,
+                projects: opts.project,
+                tags: opts.tag,
+                exclude_tags: opts.exclude_tag,
+            };
+            if let Err(e) = export::check_readable(&docs) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+            println!("{:#}", export::export(&docs, &filter));
+        }
+        Subcommand::Diff(opts) => {
+            let old = match snapshot::Snapshot::load(&opts.export) {
+                Ok(old) => old,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            };
+            let docs = load_documents(&cur_dir, &config);
+            if let Err(e) = export::check_readable(&docs) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+            let new = snapshot::Snapshot::of(&docs);
+            let diff = SnapshotDiff::new(&old, &new);
+            match opts.format {
+                validate::Format::Json => println!("{:#}", diff.to_json()),
+                validate::Format::Text if diff.is_empty() && diff.stalled.is_empty() => {
+                    println!("{}", locale.message("snapshot-unchanged", &[]))
+                }
+                validate::Format::Text => print!("{}", diff),
+            }
+        }
+        Subcommand::Publish(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            match publish::publish(&docs, &config.publish, &locale, today, &opts.out) {
+                Ok(count) => println!(
+                    "{}",
+                    locale.message(
+                        "published-site",
+                        &[("count", &count), ("path", &opts.out.display())]
+                    )
+                ),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Schema(opts) => {
+            println!("{:#}", opts.format.schema());
+        }
+        Subcommand::Dashboard(_) => {
+            let stats = cached_stats(&cur_dir, &config);
+            print!("{}", stats::render(&stats, Local::now().date_naive()));
+        }
+        Subcommand::Status(opts) => {
+            let stats = cached_stats(&cur_dir, &config);
+            if opts.oneline {
+                println!("{}", stats::oneline(&stats, &config.status.segments));
+            } else {
+                print!("{}", stats::render(&stats, Local::now().date_naive()));
+            }
+        }
+        Subcommand::MergeContext(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let mode = WriteMode::from_flags(opts.dry_run, false);
+            match merge::merge_context(&docs, &opts.source, &opts.target, mode) {
+                Ok(Some(merged)) => println!(
+                    "{}",
+                    locale.message(
+                        "merged-context",
+                        &[
+                            ("moved", &merged.moved),
+                            ("source", &opts.source),
+                            ("target", &opts.target),
+                            ("duplicates", &merged.duplicates),
+                        ]
+                    )
+                ),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Dedupe(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let mode = WriteMode::from_flags(opts.dry_run, false);
+            match dedupe::assist(&docs, mode) {
+                Ok(0) => {}
+                Ok(count) => println!(
+                    "{}",
+                    locale.message("deduped-projects", &[("count", &count)])
+                ),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::SplitContext(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let mode = WriteMode::from_flags(opts.dry_run, false);
+            match split::split_context(&docs, &opts.name, opts.by, opts.files, mode) {
+                Ok(Some(groups)) => println!(
+                    "{}",
+                    locale.message("split-context", &[("name", &opts.name), ("count", &groups)])
+                ),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::ProjectsFile(opts) => match opts.command {
+            ProjectsFileCommand::Regenerate(opts) => {
+                let docs = load_documents(&cur_dir, &config);
+                let mode = WriteMode::from_flags(opts.dry_run, false);
+                match project_index::regenerate(&docs, opts.by, mode) {
+                    Ok(false) if mode == WriteMode::Write => {
+                        println!("{}", locale.message("nothing-to-change", &[]))
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        process::exit(1);
+                    }
+                }
+            }
+        },
+        Subcommand::Reconcile(opts) => {
+            let loader = Loader::from_config(cur_dir, &config);
+            match opts.from {
+                Some(from) => {
+                    let mode = WriteMode::from_flags(opts.dry_run, false);
+                    match action_list::regenerate(&loader, from, mode) {
+                        Ok(false) if mode == WriteMode::Write => {
+                            println!("{}", locale.message("nothing-to-change", &[]))
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            process::exit(1);
+                        }
+                    }
+                }
+                None => match action_list::check(&loader) {
+                    Ok(differences) if differences.is_empty() => {
+                        println!("{}", locale.message("action-list-matches", &[]))
+                    }
+                    Ok(differences) => {
+                        for difference in differences {
+                            println!("{}", difference);
+                        }
+                        process::exit(1);
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        process::exit(1);
+                    }
+                },
+            }
+        }
+        Subcommand::Undo(_) => {
+            let loader = Loader::from_config(cur_dir, &config);
+            let result = UndoRecord::load(&loader).and_then(|undo| match undo {
+                Some(undo) => {
+                    let description = undo.description.clone();
+                    undo.restore(&loader).map(|_| Some(description))
+                }
+                None => Ok(None),
+            });
+            match result {
+                Ok(Some(description)) => println!(
+                    "{}",
+                    locale.message("undid", &[("description", &description)])
+                ),
+                Ok(None) => println!("{}", locale.message("nothing-to-undo", &[])),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Trash(opts) => {
+            let loader = Loader::from_config(cur_dir, &config);
+            match opts.command {
+                TrashCommand::List(_) => match trash::list(&loader) {
+                    Ok(entries) if entries.is_empty() => {
+                        println!("{}", locale.message("trash-is-empty", &[]))
+                    }
+                    Ok(entries) => {
+                        for entry in entries {
+                            println!("{}:", entry.stamp);
+                            for file in entry.files {
+                                println!("- {}", file.display());
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        process::exit(1);
+                    }
+                },
+                TrashCommand::Restore(opts) => match trash::restore(&loader, &opts.stamp) {
+                    Ok(files) => println!(
+                        "{}",
+                        locale.message("restored-files", &[("count", &files.len())])
+                    ),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        process::exit(1);
+                    }
+                },
+            }
+        }
+        Subcommand::Open(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let action = opts.action.map(ActionId::new);
+            if let Err(e) = open::open(&docs, &config, &opts.name, action.as_ref()) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Show(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            if let Err(e) = show::show(&docs, &opts.name) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Report(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            match opts.report {
+                ReportKind::Throughput(opts) => {
+                    throughput::throughput(&docs, today, opts.weeks.max(1), opts.csv, &locale)
+                }
+                ReportKind::Time(_opts) => {
+                    if let Err(e) = timelog::report(&docs, Local::now().naive_local()) {
+                        eprintln!("Error: {}", e);
+                        process::exit(1);
+                    }
+                }
+            }
+        }
+        Subcommand::Start(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            if let Err(e) = timelog::start(&docs, &opts.action, Local::now().naive_local()) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Stop(_opts) => {
+            if let Err(e) = timelog::stop(&cur_dir, Local::now().naive_local()) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Pomodoro(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let res = pomodoro::pomodoro(&docs, &config.pomodoro, &opts.action, opts.minutes);
+            if let Err(e) = res {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Remind(opts) => {
+            if opts.daemon {
+                run_remind_daemon(&cur_dir, &config, opts.interval);
+            } else {
+                let docs = load_documents(&cur_dir, &config);
+                remind::remind(&docs, Local::now().date_naive());
+            }
+        }
+        Subcommand::Snooze(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            if let Err(e) = snooze::snooze(&docs, &opts.action, opts.until, today) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Promote(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            if let Err(e) = promote::promote(&docs, &opts.action, today) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::CompleteProject(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            let outcome = opts.outcome.as_deref();
+            let res = complete::complete_project(
+                &docs,
+                &opts.project,
+                outcome,
+                opts.yes,
+                opts.archive,
+                today,
+            );
+            match res {
+                Ok(completed) => run_hook(
+                    &cur_dir,
+                    &config,
+                    Event::CompleteProject {
+                        date: today,
+                        project: completed.name.to_string(),
+                        title: completed.title,
+                        outcome: completed.outcome,
+                        archived: opts.archive,
+                    },
+                ),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::CompleteAction(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            let selector = Selector {
+                project: opts.project,
+                tag: opts.tag,
+                status: opts.status,
+                older_than: opts.older_than,
+            };
+            let chain = opts.chain || config.complete.chain;
+            let res = match (opts.action, opts.all_active) {
+                (Some(query), false) if selector.is_empty() => {
+                    complete::complete_action(&docs, &query, today, chain)
+                }
+                (None, true) => {
+                    complete::complete_active(&docs, &selector, today, opts.yes, chain).map(|_| ())
+                }
+                _ => {
+                    eprintln!("Error: give either an action, or --all-active with a selection");
+                    process::exit(1);
+                }
+            };
+            if let Err(e) = res {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::MoveAction(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let mode = WriteMode::from_flags(opts.dry_run, false);
+            let today = Local::now().date_naive();
+            match relocate::move_action(&docs, &opts.action, &opts.to, mode, today) {
+                Ok(Some(moved)) => println!(
+                    "{}",
+                    locale.message(
+                        "moved-action",
+                        &[
+                            ("from", &moved.from),
+                            ("to", &moved.to),
+                            ("links", &moved.links)
+                        ]
+                    )
+                ),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Archive(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            let selector = Selector {
+                project: opts.project,
+                tag: opts.tag,
+                status: opts.status,
+                older_than: opts.older_than,
+            };
+            let today = Local::now().date_naive();
+            let attachments = opts
+                .attachments
+                .then_some(config.attachments.folder.as_path());
+            if let Err(e) = archive::archive(&docs, &selector, today, opts.yes, attachments) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Waiting(_opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            waiting::waiting(&docs, Local::now().date_naive());
+        }
+        Subcommand::Nag(opts) => {
+            let docs = load_documents(&cur_dir, &config);
+            if let Err(e) = waiting::nag(&docs, &opts.person, Local::now().date_naive()) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Capture(opts) => {
+            let today = Local::now().date_naive();
+            let res = match (opts.text, opts.from_eml, opts.maildir) {
+                (Some(text), None, None) => {
+                    inbox::capture(&cur_dir, &text, today).map(|_| (1, Some(text)))
+                }
+                (None, Some(eml), None) => {
+                    inbox::capture_email(&cur_dir, Path::new(&eml), today).map(|_| (1, None))
+                }
+                (None, None, Some(dir)) => inbox::capture_maildir(&cur_dir, Path::new(&dir), today)
+                    .map(|n| {
+                        println!("{}", locale.message("captured-emails", &[("count", &n)]));
+                        (n, None)
+                    }),
+                _ => {
+                    eprintln!("Error: give one of some text, --from-eml, or --maildir");
+                    process::exit(1);
+                }
+            };
+            let (count, text) = res.unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            });
+            let loader = Loader::from_config(cur_dir.clone(), &config);
+            let _ = stats::refresh_inbox(&loader);
+            if count > 0 {
+                let event = Event::Capture {
+                    date: today,
+                    count,
+                    text,
+                };
+                run_hook(&cur_dir, &config, event);
+            }
+        }
+        Subcommand::Review(opts) if opts.someday => {
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            if let Err(e) = someday::review(&docs, opts.older_than, today) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Review(opts) if opts.due => {
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            if let Err(e) = review::review_due(&docs, today) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Review(opts) if opts.waiting => {
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            if let Err(e) = waiting::follow_up(&docs, &config, today) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Review(_opts) => {
+            let calendar = load_calendar(&cur_dir, &config);
+            let docs = load_documents(&cur_dir, &config);
+            let today = Local::now().date_naive();
+            review::review(&docs, calendar.as_ref(), config.calendar.busy_hours, today);
+        }
+        Subcommand::Sync(opts) => {
+            let options = SyncOptions {
+                resolution: opts.conflicts,
+                dry_run: opts.dry_run,
+            };
+            run_sync(&cur_dir, &config, &locale, options);
+        }
+        Subcommand::Issues(opts) => match opts.command {
+            IssuesCommand::Sync(opts) => {
+                run_issues_sync(&cur_dir, &config, &locale, opts.complete, opts.yes)
+            }
+        },
+        Subcommand::Import(opts) => {
+            run_import(&cur_dir, &config, &locale, &opts.github, &opts.assignee)
+        }
+        Subcommand::Explain(opts) => match opts.rule {
+            Some(id) => match validate::rule(&id) {
+                Some(rule) => print!("{}", validate::explain(rule)),
+                None => {
+                    eprintln!("Error: there's no rule \"{}\"", id);
+                    process::exit(1);
+                }
+            },
+            None => {
+                for rule in validate::RULES {
+                    let opt_in = if rule.is_opt_in { " (opt-in)" } else { "" };
+                    println!("{} {}{}", rule.id, rule.name, opt_in);
+                }
+            }
+        },
+        Subcommand::LintLinks(_opts) => {
+            match attachments::audit(&cur_dir, &config.attachments.folder) {
+                Ok(audit) => {
+                    let links = Linker::from_config(&config, &cur_dir);
+                    attachments::print_audit(&audit, &cur_dir, &links);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Doctor(_opts) => {
+            let loader = Loader::from_config(cur_dir, &config);
+            match doctor::check(&loader, &config) {
+                Ok(report) => print!("{}", report),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Resolve(_) => {
+            let loader = Loader::from_config(cur_dir, &config);
+            if let Err(e) = conflict::resolve_all(&loader) {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        Subcommand::Roundtrip(opts) => {
+            let loader = Loader::from_config(cur_dir, &config);
+            match roundtrip::check(&loader, &opts.file) {
+                Ok(roundtrip) if roundtrip.is_unchanged() => {
+                    let path = opts.file.display();
+                    println!(
+                        "{}",
+                        locale.message("roundtrip-unchanged", &[("path", &path)])
+                    )
+                }
+                Ok(roundtrip) => {
+                    let path = opts.file.display();
+                    println!(
+                        "{}",
+                        locale.message("roundtrip-changed", &[("path", &path)])
+                    );
+                    print!("{}", roundtrip.render(io::stdout().is_terminal()));
+                    process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Log(opts) => match git::log(&cur_dir, opts.count) {
+            Ok(commits) if commits.is_empty() => println!("{}", locale.message("no-commits", &[])),
+            Ok(commits) => {
+                for commit in commits {
+                    println!("{} {} {}", commit.hash, commit.date, commit.message);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        },
+        Subcommand::Init(opts) => {
+            let loader = Loader::from_config(cur_dir, &config);
+            let sample = Sample {
+                projects: opts.projects,
+                contexts: opts.contexts,
+                broken_links: opts.broken_links,
+                seed: opts.seed.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_nanos() as u64)
+                }),
+            };
+            let example = Some(&sample).filter(|_| opts.example);
+            match init::init(&loader, example, &Local::now()) {
+                Ok(report) => print!("{}", report),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+        Subcommand::Config(_) => unreachable!("config commands run before the config is checked"),
+    }
+
+    if let Some((baseline, root_dir)) = baseline {
+        check_changes(&root_dir, &config, &locale, &baseline);
+    }
+
+    if let Some((snapshot, message)) = commit {
+        if let Err(e) = snapshot.commit(&message) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Prints every setting in `layers` as `key = value`, followed by where it came from if `origin`
+/// is set.
+fn show_config(layers: &Layers, origin: bool) {
+    let settings = layers.settings();
+    if settings.is_empty() {
+        println!("Nothing is set, so every setting has its default.");
+    }
+    for (key, value, from) in settings {
+        match origin {
+            true => println!("{} = {}  # {}", key, value, from),
+            false => println!("{} = {}", key, value),
+        }
+    }
+}
+
+/// Prints the value of the setting at `key`, with strings unquoted, or the settings under it if
+/// it's a table.
+fn get_config(layers: &Layers, key: &str) {
+    let key = parse_config_key(key);
+    if Setting::find(&key).is_none() && !Setting::is_table(&key) {
+        eprintln!("Error: there's no setting \"{}\"", layers::dotted(&key));
+        process::exit(1);
+    }
+    match layers.get(&key) {
+        Some(Value::String(text)) => println!("{}", text),
+        Some(Value::Table(_)) => {
+            let prefix = format!("{}.", layers::dotted(&key));
+            for (name, value, _) in layers.settings() {
+                if let Some(name) = name.strip_prefix(&prefix) {
+                    println!("{} = {}", name, value);
+                }
+            }
+        }
+        Some(value) => println!("{}", value),
+        None => {
+            eprintln!(
+                "Error: \"{}\" isn't set, so it has its default",
+                layers::dotted(&key)
+            );
+            process::exit(1);
+        }
+    }
+}
+
+/// Sets a setting in the config file of the GTD folder `root_dir`, or the user's config file.
+fn set_config(root_dir: &Path, opts: &ConfigSet) {
+    let path = match opts.user {
+        true => {
+            match layers::user_path(&env::vars().collect()) {
+                Some(path) => path,
+                None => {
+                    eprintln!("Error: there's no user config, since neither XDG_CONFIG_HOME nor HOME is set");
+                    process::exit(1);
+                }
+            }
+        }
+        false => config::path(root_dir),
+    };
+    let key = parse_config_key(&opts.key);
+    match config::set_in_file(&path, &key, &opts.value) {
+        Ok(value) => println!(
+            "Set {} = {} in {}.",
+            layers::dotted(&key),
+            value,
+            path.display()
+        ),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Splits a dotted key given as an argument into its parts.
+fn parse_config_key(key: &str) -> Vec<String> {
+    layers::parse_key(key).unwrap_or_else(|| {
+        eprintln!("Error: \"{}\" isn't a key like next.old-after", key);
+        process::exit(1);
+    })
+}
+
+/// What a commit of the changes `subcommand` makes is described as, or `None` if it doesn't
+/// change anything.
+fn commit_message(subcommand: &Subcommand) -> Option<String> {
+    let message = match subcommand {
+        Subcommand::Validate(opts) if opts.fix => String::from("fix problems"),
+        Subcommand::New(opts) => format!("new project '{}'", opts.title),
+        Subcommand::List(opts) if opts.write_progress => String::from("write progress"),
+        Subcommand::Today(opts) if opts.write => String::from("write today's actions"),
+        Subcommand::Start(opts) => format!("start timer on '{}'", opts.action),
+        Subcommand::Stop(_) => String::from("stop timer"),
+        Subcommand::Pomodoro(opts) => format!("pomodoro on '{}'", opts.action),
+        Subcommand::Snooze(opts) => format!("snooze '{}'", opts.action),
+        Subcommand::Promote(opts) => format!("promote '{}'", opts.action),
+        Subcommand::CompleteAction(opts) => match &opts.action {
+            Some(action) => format!("complete action '{}'", action),
+            None => String::from("complete active actions"),
+        },
+        Subcommand::CompleteProject(opts) => format!("complete project '{}'", opts.project),
+        Subcommand::MoveAction(opts) => format!("move '{}' to '{}'", opts.action, opts.to),
+        Subcommand::Archive(_) => String::from("archive projects"),
+        Subcommand::Capture(opts) => match &opts.text {
+            Some(text) => format!("capture '{}'", text),
+            None => String::from("capture email"),
+        },
+        Subcommand::Review(_) => String::from("review"),
+        Subcommand::Sync(_) => String::from("sync"),
+        Subcommand::Issues(opts) => match &opts.command {
+            IssuesCommand::Sync(opts) if opts.complete => {
+                String::from("complete actions with closed issues")
+            }
+            IssuesCommand::Sync(_) => return None,
+        },
+        Subcommand::Import(opts) => format!("import issues from {}", opts.github),
+        Subcommand::MergeContext(opts) => {
+            format!("merge context {} into {}", opts.source, opts.target)
+        }
+        Subcommand::Dedupe(_) => String::from("dedupe projects"),
+        Subcommand::SplitContext(opts) => format!("split context {}", opts.name),
+        Subcommand::Reconcile(_) => String::from("reconcile contexts"),
+        Subcommand::ProjectsFile(opts) => match &opts.command {
+            ProjectsFileCommand::Regenerate(_) => String::from("regenerate the project index"),
+        },
+        Subcommand::Undo(_) => String::from("undo"),
+        Subcommand::Resolve(_) => String::from("resolve conflicts"),
+        Subcommand::Trash(opts) => match &opts.command {
+            TrashCommand::Restore(opts) => format!("restore {} from the trash", opts.stamp),
+            TrashCommand::List(_) => return None,
+        },
+        _ => return None,
+    };
+    Some(message)
+}
+
+/// Warns about the problems that the command introduced since `baseline`, and asks for next
+/// actions for the projects it left without one if `[validate]` says to.
+fn check_changes(root_dir: &Path, config: &Config, locale: &Locale, baseline: &Baseline) {
+    let docs = match Documents::load(root_dir, config) {
+        Some(docs) => docs,
+        None => return,
+    };
+    let today = Local::now().date_naive();
+    let introduced = baseline.introduced(&docs, config, today);
+    if introduced.is_empty() {
+        return;
+    }
+    println!();
+    let count = introduced.len();
+    println!(
+        "{}",
+        locale.message("changes-introduced-problems", &[("count", &count)])
+    );
+    validate::print_diagnostics(&introduced, &Linker::from_config(config, root_dir));
+
+    if !config.validate.ask_for_next_action || !io::stdin().is_terminal() {
+        return;
+    }
+    for name in revalidate::without_next_actions(&introduced) {
+        let question = locale.message("ask-next-action", &[("project", &name.title())]);
+        let result = prompt::ask(&question, Some("")).map_err(|e| e.to_string());
+        let added = match result {
+            Ok(Some(text)) if !text.trim().is_empty() => {
+                revalidate::add_next_action(&docs, &name, text.trim(), today)
+                    .map_err(|e| e.to_string())
+            }
+            result => result.map(|_| false),
+        };
+        if let Err(e) = added {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Loads the documents in `root_dir`, and caches their stats for `gtd dashboard`.
+fn load_documents(root_dir: &Path, config: &Config) -> Documents {
+    let docs = Documents::load(root_dir, config).unwrap();
+    // The cache is only a convenience, so failing to write it shouldn't stop the command.
+    let _ = Stats::compute(&docs, Local::now().naive_local()).save(docs.loader());
+    docs
+}
+
+/// Loads the stats cached by the last command, or reads the documents if there aren't any.
+fn cached_stats(root_dir: &Path, config: &Config) -> Stats {
+    let loader = Loader::from_config(root_dir.to_path_buf(), config);
+    match Stats::load(&loader) {
+        Ok(Some(stats)) => stats,
+        Ok(None) => {
+            let docs = load_documents(root_dir, config);
+            Stats::compute(&docs, Local::now().naive_local())
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
         }
     }
 }
+
+/// Loads the configured calendar, exiting if it can't be read.
+fn load_calendar(root_dir: &Path, config: &Config) -> Option<Calendar> {
+    Calendar::load(&config.calendar, root_dir).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    })
+}
+
+#[cfg(feature = "caldav")]
+fn run_sync(root_dir: &Path, config: &Config, locale: &Locale, options: SyncOptions) {
+    use gtd::{caldav::CalDav, sync};
+
+    let mut backend = match CalDav::from_config(&config.caldav) {
+        Ok(backend) => backend,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let docs = load_documents(root_dir, config);
+    match sync::sync(&docs, &mut backend, options, Local::now().naive_local()) {
+        Ok(summary) => {
+            let message = locale.message(
+                "synced",
+                &[
+                    ("added", &summary.added),
+                    ("pushed", &summary.pushed),
+                    ("pulled", &summary.pulled),
+                    ("conflicts", &summary.conflicts),
+                ],
+            );
+            println!("{}", message);
+            if options.dry_run {
+                println!("{}", locale.message("dry-run", &[]));
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(feature = "issues")]
+fn run_issues_sync(root_dir: &Path, config: &Config, locale: &Locale, complete: bool, yes: bool) {
+    use gtd::{issues, trackers::Trackers};
+
+    let mut trackers = match Trackers::from_config(&config.issues) {
+        Ok(trackers) => trackers,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let docs = load_documents(root_dir, config);
+    let today = Local::now().date_naive();
+    match issues::sync(&docs, &mut trackers, complete, yes, today) {
+        Ok(summary) => {
+            let message = locale.message(
+                "checked-issues",
+                &[
+                    ("checked", &summary.checked),
+                    ("closed", &summary.closed),
+                    ("completed", &summary.completed),
+                ],
+            );
+            println!("{}", message);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "issues"))]
+fn run_issues_sync(
+    _root_dir: &Path,
+    _config: &Config,
+    _locale: &Locale,
+    _complete: bool,
+    _yes: bool,
+) {
+    eprintln!("Error: gtd was built without the `issues` feature");
+    process::exit(1);
+}
+
+#[cfg(feature = "issues")]
+fn run_import(root_dir: &Path, config: &Config, locale: &Locale, repo: &str, assignee: &str) {
+    use gtd::{issues, trackers::Trackers};
+
+    if !issues::is_repo(repo) {
+        eprintln!(
+            "Error: \"{}\" is not a repository, like \"owner/repo\"",
+            repo
+        );
+        process::exit(1);
+    }
+    let found =
+        Trackers::from_config(&config.issues).and_then(|t| t.assigned_issues(repo, assignee));
+    let found = match found {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let docs = load_documents(root_dir, config);
+    let today = Local::now().date_naive();
+    match issues::import(&docs, repo, &found, today) {
+        Ok(summary) => {
+            if summary.created {
+                let name = &summary.project;
+                println!("{}", locale.message("created-project", &[("name", &name)]));
+            }
+            let message = locale.message(
+                "imported-issues",
+                &[
+                    ("added", &summary.added),
+                    ("found", &found.len()),
+                    ("project", &summary.project.title()),
+                ],
+            );
+            println!("{}", message);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "issues"))]
+fn run_import(_root_dir: &Path, _config: &Config, _locale: &Locale, _repo: &str, _assignee: &str) {
+    eprintln!("Error: gtd was built without the `issues` feature");
+    process::exit(1);
+}
+
+/// Runs the hook for `event`, if there is one, exiting if it fails.
+fn run_hook(root_dir: &Path, config: &Config, event: Event) {
+    if let Err(e) = hooks::run(&config.hooks, root_dir, &event) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "caldav"))]
+fn run_sync(_root_dir: &Path, _config: &Config, _locale: &Locale, _options: SyncOptions) {
+    eprintln!("Error: gtd was built without the `caldav` feature");
+    process::exit(1);
+}
+
+#[cfg(feature = "notifications")]
+fn run_remind_daemon(root_dir: &Path, config: &Config, interval: u64) {
+    let interval = Duration::from_secs(interval.max(1) * 60);
+    if let Err(e) = remind::daemon::run(root_dir, config, interval) {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "notifications"))]
+fn run_remind_daemon(_root_dir: &Path, _config: &Config, _interval: u64) {
+    eprintln!("Error: gtd was built without the `notifications` feature");
+    process::exit(1);
+}
@@ -1,9 +1,16 @@
-use crate::pulldown::{event_static, DisplayableEvent, DisplayableTag};
-use pulldown_cmark::{CowStr, Event, LinkType, Tag};
+pub use crate::pulldown::{
+    cow_str_static, event_static, write_inline_event, DisplayableEvent, DisplayableTag,
+    SpannedEvent,
+};
+pub use pulldown_cmark::Alignment;
+use pulldown_cmark::{escape, html, CowStr, Event, LinkType, Tag};
+use regex::Regex;
 use std::{
     convert::{TryFrom, TryInto},
     error::Error,
     fmt,
+    ops::Range,
+    sync::OnceLock,
 };
 
 /// A fragment of arbitrary Markdown text.
@@ -25,10 +32,547 @@ impl Fragment {
     pub fn into_events(self) -> Vec<Event<'static>> {
         self.0
     }
+
+    /// The text of the fragment without formatting, with runs of whitespace collapsed to single
+    /// spaces.
+    pub fn text(&self) -> String {
+        let text = self
+            .0
+            .iter()
+            .filter_map(|ev| match ev {
+                Event::Text(t) | Event::Code(t) => Some(&**t),
+                Event::SoftBreak | Event::HardBreak => Some(" "),
+                _ => None,
+            })
+            .collect::<String>();
+        collapse_whitespace(&text)
+    }
+
+    /// Renders the fragment as HTML. Wiki links are linked to the URL that `url` gives for them, or
+    /// shown as their text if it gives `None`. HTML written in the fragment is shown as text, so
+    /// that nothing in a note can run scripts where it's shown.
+    pub fn to_html<F>(&self, url: F) -> String
+    where
+        F: Fn(&WikiLink) -> Option<String>,
+    {
+        let mut events = Vec::new();
+        // Wiki links aren't Markdown, so they can be split over several text events.
+        let mut text = String::new();
+        let mut in_code_block = false;
+        for ev in self.0.iter() {
+            match ev {
+                Event::Text(t) | Event::Html(t) if !in_code_block => text.push_str(t),
+                ev => {
+                    push_wiki_links(&mut events, &text, &url);
+                    text.clear();
+                    match ev {
+                        Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+                        Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+                        _ => {}
+                    }
+                    // The definitions are rendered separately by `Footnote::to_html`, so
+                    // references are linked to them here instead of numbered by `push_html`.
+                    match ev {
+                        Event::FootnoteReference(label) => events.push(Event::Html(
+                            format!(
+                                "<sup class=\"footnote-reference\"><a href=\"#{}\">{}</a></sup>",
+                                footnote_id(label),
+                                escape_text(label)
+                            )
+                            .into(),
+                        )),
+                        ev => events.push(ev.clone()),
+                    }
+                }
+            }
+        }
+        push_wiki_links(&mut events, &text, &url);
+        let mut out = String::new();
+        html::push_html(&mut out, events.into_iter());
+        out
+    }
+
+    /// Renders the fragment back to Markdown. Paragraphs are separated by blank lines and block
+    /// quotes, including callouts, are quoted with `>`, but other block structure is ignored.
+    pub fn to_markdown(&self) -> String {
+        let mut out = MarkdownWriter::default();
+        for ev in self.0.iter() {
+            match ev {
+                Event::Start(Tag::Paragraph) => out.start_block(),
+                Event::Start(Tag::BlockQuote) => {
+                    out.start_block();
+                    out.quote += 1;
+                }
+                Event::End(Tag::Paragraph) => out.is_after_block = true,
+                Event::End(Tag::BlockQuote) => {
+                    out.quote -= 1;
+                    out.is_after_block = true;
+                }
+                ev => {
+                    let mut text = String::new();
+                    // Writing to a `String` can't fail.
+                    let _ = write_inline_event(&mut text, ev);
+                    out.push_str(&text);
+                }
+            }
+        }
+        out.text
+    }
+
+    /// The Obsidian callouts in the fragment, which are block quotes that start with their kind,
+    /// like `> [!warning] Visa`. Quotes inside other blocks aren't included.
+    pub fn callouts(&self) -> Vec<Callout> {
+        self.blocks()
+            .into_iter()
+            .filter_map(|range| Callout::parse(&self.0[range]))
+            .collect()
+    }
+
+    /// The fragment without its callouts.
+    pub fn without_callouts(&self) -> Self {
+        self.without(|events| Callout::parse(events).is_some())
+    }
+
+    /// The labels of the footnotes referenced in the fragment, like `1` for `[^1]`, in the order
+    /// they're first referenced.
+    pub fn footnote_references(&self) -> Vec<&str> {
+        let mut labels = Vec::new();
+        for ev in self.0.iter() {
+            if let Event::FootnoteReference(label) = ev {
+                if !labels.contains(&&**label) {
+                    labels.push(&**label);
+                }
+            }
+        }
+        labels
+    }
+
+    /// The footnote definitions in the fragment that aren't inside other blocks.
+    pub fn footnotes(&self) -> Vec<Footnote> {
+        self.blocks()
+            .into_iter()
+            .filter_map(|range| Footnote::parse(&self.0[range]))
+            .collect()
+    }
+
+    /// The fragment without its footnote definitions.
+    pub fn without_footnotes(&self) -> Self {
+        self.without(|events| Footnote::parse(events).is_some())
+    }
+
+    /// The tables in the fragment that aren't inside other blocks.
+    pub fn tables(&self) -> Vec<Table> {
+        self.blocks()
+            .into_iter()
+            .filter_map(|range| Table::parse(&self.0[range]))
+            .collect()
+    }
+
+    /// The fragment without its tables.
+    pub fn without_tables(&self) -> Self {
+        self.without(|events| Table::parse(events).is_some())
+    }
+
+    /// The fragment without the blocks that `is_removed` picks, given their events.
+    fn without<F: Fn(&[Event<'static>]) -> bool>(&self, is_removed: F) -> Self {
+        let mut events = self.0.clone();
+        for range in self.blocks().into_iter().rev() {
+            if is_removed(&self.0[range.clone()]) {
+                events.drain(range);
+            }
+        }
+        Self(events)
+    }
+
+    /// The ranges of the events of each block that isn't inside another block.
+    fn blocks(&self) -> Vec<Range<usize>> {
+        let mut blocks = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, ev) in self.0.iter().enumerate() {
+            match ev {
+                Event::Start(_) => {
+                    if depth == 0 {
+                        start = i;
+                    }
+                    depth += 1;
+                }
+                Event::End(_) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        blocks.push(start..i + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+        blocks
+    }
 }
 
-/// The text of a Markdown heading.
+/// Writes Markdown, quoting each line that's inside a block quote.
+#[derive(Default)]
+struct MarkdownWriter {
+    text: String,
+    /// How many block quotes the writer is in.
+    quote: usize,
+    is_mid_line: bool,
+    /// Whether a block just ended, so the next one needs a blank line before it.
+    is_after_block: bool,
+}
+
+impl MarkdownWriter {
+    fn start_block(&mut self) {
+        if self.is_after_block {
+            self.push_str("\n\n");
+            self.is_after_block = false;
+        }
+    }
+
+    fn push_str(&mut self, s: &str) {
+        for c in s.chars() {
+            if !self.is_mid_line {
+                let prefix = "> ".repeat(self.quote);
+                match c {
+                    '\n' => self.text.push_str(prefix.trim_end()),
+                    _ => self.text.push_str(&prefix),
+                }
+            }
+            self.text.push(c);
+            self.is_mid_line = c != '\n';
+        }
+    }
+}
+
+/// A Markdown table.
 #[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub alignments: Vec<Alignment>,
+    pub header: Vec<Fragment>,
+    pub rows: Vec<Vec<Fragment>>,
+}
+
+impl Table {
+    /// Reads the events of a block as a table, if it's one.
+    fn parse(events: &[Event<'static>]) -> Option<Self> {
+        let alignments = match events.first()? {
+            Event::Start(Tag::Table(alignments)) => alignments.clone(),
+            _ => return None,
+        };
+        let mut header = Vec::new();
+        let mut rows = Vec::<Vec<Fragment>>::new();
+        let mut cell = None::<Vec<Event<'static>>>;
+        let mut is_header = false;
+        for ev in events[1..events.len() - 1].iter() {
+            match ev {
+                Event::Start(Tag::TableHead) => is_header = true,
+                Event::End(Tag::TableHead) => is_header = false,
+                Event::Start(Tag::TableRow) => rows.push(Vec::new()),
+                Event::Start(Tag::TableCell) => cell = Some(Vec::new()),
+                Event::End(Tag::TableCell) => {
+                    let cell = Fragment(cell.take().unwrap_or_default());
+                    match (is_header, rows.last_mut()) {
+                        (false, Some(row)) => row.push(cell),
+                        _ => header.push(cell),
+                    }
+                }
+                ev => {
+                    if let Some(cell) = cell.as_mut() {
+                        cell.push(ev.clone());
+                    }
+                }
+            }
+        }
+        Some(Self {
+            alignments,
+            header,
+            rows,
+        })
+    }
+
+    /// Renders the table as text for a terminal, with its columns aligned and boxed in.
+    pub fn to_text(&self) -> String {
+        let rows = std::iter::once(&self.header)
+            .chain(self.rows.iter())
+            .map(|row| row.iter().map(Fragment::to_string).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths = (0..columns)
+            .map(|i| {
+                rows.iter()
+                    .filter_map(|r| r.get(i))
+                    .map(|c| c.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        let rule = |left: &str, middle: &str, right: &str| {
+            let lines = widths.iter().map(|w| "─".repeat(w + 2)).collect::<Vec<_>>();
+            format!("{}{}{}\n", left, lines.join(middle), right)
+        };
+        let mut out = rule("┌", "┬", "┐");
+        for (i, row) in rows.iter().enumerate() {
+            if i == 1 {
+                out.push_str(&rule("├", "┼", "┤"));
+            }
+            out.push('│');
+            for (j, width) in widths.iter().enumerate() {
+                let cell = row.get(j).map_or("", String::as_str);
+                let padding = width - cell.chars().count();
+                let (before, after) = match self.alignments.get(j) {
+                    Some(Alignment::Right) => (padding, 0),
+                    Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                out.push_str(&format!(
+                    " {}{}{} │",
+                    " ".repeat(before),
+                    cell,
+                    " ".repeat(after)
+                ));
+            }
+            out.push('\n');
+        }
+        out.push_str(&rule("└", "┴", "┘"));
+        out
+    }
+}
+
+/// A footnote definition, like `[^1]: Prices from January.`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Footnote {
+    pub label: String,
+    pub body: Fragment,
+}
+
+impl Footnote {
+    /// Reads the events of a block as a footnote, if it's a footnote definition.
+    fn parse(events: &[Event<'static>]) -> Option<Self> {
+        match events.first()? {
+            Event::Start(Tag::FootnoteDefinition(label)) => Some(Self {
+                label: label.to_string(),
+                body: Fragment(events[1..events.len() - 1].to_vec()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Renders the definition as an HTML list item that the references to it link to, with wiki
+    /// links in it handled like in [`Fragment::to_html`].
+    pub fn to_html<F>(&self, url: F) -> String
+    where
+        F: Fn(&WikiLink) -> Option<String>,
+    {
+        format!(
+            "<li id=\"{}\">{}</li>\n",
+            footnote_id(&self.label),
+            self.body.to_html(url)
+        )
+    }
+}
+
+/// The HTML id of the footnote labelled `label`.
+fn footnote_id(label: &str) -> String {
+    let mut id = String::from("fn-");
+    // Escaping into a `String` can't fail.
+    let _ = escape::escape_href(&mut id, label);
+    id
+}
+
+/// Escapes `text` for HTML.
+fn escape_text(text: &str) -> String {
+    let mut out = String::new();
+    // Escaping into a `String` can't fail.
+    let _ = escape::escape_html(&mut out, text);
+    out
+}
+
+/// An Obsidian callout, like:
+///
+/// ```markdown
+/// > [!warning] Visa
+/// > Apply at least a month ahead.
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Callout {
+    /// The kind of callout, in lowercase, like `warning`.
+    pub kind: String,
+    /// The title after the kind, if there is one.
+    pub title: Option<String>,
+    /// What's in the callout after its first line.
+    pub body: Fragment,
+}
+
+impl Callout {
+    /// Reads the events of a block as a callout, if it's a block quote that starts with a
+    /// callout's kind.
+    fn parse(events: &[Event<'static>]) -> Option<Self> {
+        if events.first() != Some(&Event::Start(Tag::BlockQuote)) {
+            return None;
+        }
+        static KIND: OnceLock<Regex> = OnceLock::new();
+        let kind = KIND.get_or_init(|| Regex::new(r"^\[!([\w-]+)\][+-]?(?:\s+(.*))?$").unwrap());
+
+        // The quote's first paragraph starts with the kind, and maybe a title, on its own line.
+        let inner = &events[1..events.len() - 1];
+        if inner.first() != Some(&Event::Start(Tag::Paragraph)) {
+            return None;
+        }
+        let line_end = inner.iter().position(|ev| {
+            matches!(
+                ev,
+                Event::SoftBreak | Event::HardBreak | Event::End(Tag::Paragraph)
+            )
+        })?;
+        let line = Fragment(inner[1..line_end].to_vec()).to_markdown();
+        let captures = kind.captures(line.trim())?;
+
+        let body = match inner[line_end] {
+            Event::End(Tag::Paragraph) => inner[line_end + 1..].to_vec(),
+            _ => [&[Event::Start(Tag::Paragraph)][..], &inner[line_end + 1..]].concat(),
+        };
+        Some(Self {
+            kind: captures[1].to_lowercase(),
+            title: captures
+                .get(2)
+                .map(|t| t.as_str().trim().to_string())
+                .filter(|t| !t.is_empty()),
+            body: Fragment(body),
+        })
+    }
+
+    /// Whether the callout is a warning, which Obsidian also calls `caution` and `attention`.
+    pub fn is_warning(&self) -> bool {
+        matches!(self.kind.as_str(), "warning" | "caution" | "attention")
+    }
+}
+
+impl fmt::Display for Fragment {
+    /// Displays the inline contents of the fragment as text, ignoring any block structure.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for ev in self.0.iter() {
+            match ev {
+                Event::Text(t) => write!(f, "{}", t)?,
+                Event::Code(c) => write!(f, "`{}`", c)?,
+                Event::FootnoteReference(label) => write!(f, "[^{}]", label)?,
+                Event::SoftBreak | Event::HardBreak => write!(f, " ")?,
+                Event::Start(Tag::Emphasis) | Event::End(Tag::Emphasis) => write!(f, "_")?,
+                Event::Start(Tag::Strong) | Event::End(Tag::Strong) => write!(f, "**")?,
+                Event::Start(Tag::Strikethrough) | Event::End(Tag::Strikethrough) => {
+                    write!(f, "~~")?
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A link to a note in Obsidian's wiki link syntax, like `[[Note#Heading|shown text]]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WikiLink<'a> {
+    /// The name of the note linked to, which is empty for links within the same note.
+    pub note: &'a str,
+    /// The heading or block in the note, after the `#`.
+    pub section: Option<&'a str>,
+    /// The text the link is shown as, after the `|`.
+    pub alias: Option<&'a str>,
+    /// Whether the note is embedded, like `![[Note]]`, instead of linked to.
+    pub is_embed: bool,
+}
+
+impl<'a> WikiLink<'a> {
+    /// The text that Obsidian shows for the link.
+    pub fn text(&self) -> &'a str {
+        match (self.alias, self.section) {
+            (Some(alias), _) => alias,
+            (None, Some(section)) if self.note.is_empty() => section,
+            _ => self.note,
+        }
+    }
+}
+
+/// Pushes `text` onto `events`, with each wiki link in it made into a link to its URL.
+fn push_wiki_links<F>(events: &mut Vec<Event<'static>>, text: &str, url: &F)
+where
+    F: Fn(&WikiLink) -> Option<String>,
+{
+    static WIKI_LINK: OnceLock<Regex> = OnceLock::new();
+    let wiki_link = WIKI_LINK.get_or_init(|| {
+        Regex::new(r"(!?)\[\[([^\[\]|#]*)(?:#([^\[\]|]*))?(?:\|([^\[\]]*))?\]\]").unwrap()
+    });
+    let mut last = 0;
+    for captures in wiki_link.captures_iter(text) {
+        let whole = captures.get(0).unwrap();
+        let link = WikiLink {
+            note: captures.get(2).map_or("", |m| m.as_str()).trim(),
+            section: captures.get(3).map(|m| m.as_str().trim()),
+            alias: captures.get(4).map(|m| m.as_str().trim()),
+            is_embed: !captures[1].is_empty(),
+        };
+        if whole.start() > last {
+            events.push(Event::Text(text[last..whole.start()].to_string().into()));
+        }
+        match url(&link) {
+            Some(url) => {
+                let mut href = String::new();
+                // Writing to a `String` can't fail.
+                let _ = escape::escape_href(&mut href, &url);
+                events.push(Event::Html(format!("<a href=\"{}\">", href).into()));
+                events.push(Event::Text(link.text().to_string().into()));
+                events.push(Event::Html("</a>".into()));
+            }
+            None => events.push(Event::Text(link.text().to_string().into())),
+        }
+        last = whole.end();
+    }
+    if last < text.len() {
+        events.push(Event::Text(text[last..].to_string().into()));
+    }
+}
+
+/// A range of bytes in the source text of a document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The 1-based line number in `text` that the span starts on.
+    pub fn start_line(&self, text: &str) -> usize {
+        let start = self.start.min(text.len());
+        text.as_bytes()[..start]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count()
+            + 1
+    }
+
+    /// The byte range of the whole lines in `text` that the span covers, including the final
+    /// newline but not any trailing blank lines.
+    pub fn line_range(&self, text: &str) -> Range<usize> {
+        let start = self.start.min(text.len());
+        let end = self.end.clamp(start, text.len());
+        let end = start + text[start..end].trim_end().len();
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let last = if end > start { end - 1 } else { start };
+        let line_end = text[last..].find('\n').map_or(text.len(), |i| last + i + 1);
+        line_start..line_end
+    }
+}
+
+/// The text of a Markdown heading.
+///
+/// Headings are equal if they have the same formatting and the same text once whitespace is
+/// collapsed, however the text was split up by the parser. A heading is equal to a string if its
+/// text without formatting is.
+#[derive(Debug, Clone)]
 pub struct Heading(Vec<HeadingEvent<'static>>);
 
 impl Heading {
@@ -41,7 +585,7 @@ impl Heading {
             return None;
         }
 
-        self.0.get(0)?.try_to_text().map(|s| &**s)
+        self.0.first()?.try_to_text().map(|s| &**s)
     }
 
     pub fn try_to_title_string(&self) -> Option<String> {
@@ -51,6 +595,102 @@ impl Heading {
             Some(s)
         })
     }
+
+    /// The text of the heading without formatting, with runs of whitespace collapsed to single
+    /// spaces.
+    pub fn text(&self) -> String {
+        let text = self
+            .0
+            .iter()
+            .filter_map(|ev| ev.try_to_text().or_else(|| ev.try_to_code()))
+            .map(|t| &**t)
+            .collect::<String>();
+        collapse_whitespace(&text)
+    }
+
+    /// The anchor that links to the heading, like `plan-a-trip` for `Plan a _trip_!`.
+    pub fn slug(&self) -> String {
+        slug(&self.text())
+    }
+
+    /// The events of the heading with adjacent text merged and whitespace collapsed.
+    fn normalized(&self) -> Vec<HeadingEvent<'_>> {
+        let mut events = Vec::<HeadingEvent>::with_capacity(self.0.len());
+        for ev in self.0.iter() {
+            match (events.last_mut(), ev) {
+                (Some(HeadingEvent::Text(last)), HeadingEvent::Text(t)) => {
+                    *last = format!("{}{}", last, t).into();
+                }
+                (_, ev) => events.push(ev.clone()),
+            }
+        }
+
+        let last = events.len().saturating_sub(1);
+        for (i, ev) in events.iter_mut().enumerate() {
+            if let HeadingEvent::Text(t) = ev {
+                let mut text = collapse_whitespace_inner(t);
+                if i == 0 {
+                    text = text.trim_start().to_string();
+                }
+                if i == last {
+                    text = text.trim_end().to_string();
+                }
+                *t = text.into();
+            }
+        }
+        events.retain(|ev| !matches!(ev, HeadingEvent::Text(t) if t.is_empty()));
+        events
+    }
+}
+
+impl PartialEq for Heading {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl PartialEq<str> for Heading {
+    fn eq(&self, other: &str) -> bool {
+        self.text() == collapse_whitespace(other)
+    }
+}
+
+impl PartialEq<&str> for Heading {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+/// Trims `text` and collapses its runs of whitespace to single spaces.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Collapses the runs of whitespace in `text` to single spaces, keeping any at the ends.
+fn collapse_whitespace_inner(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if !c.is_whitespace() {
+            out.push(c);
+        } else if !out.ends_with(' ') {
+            out.push(' ');
+        }
+    }
+    out
+}
+
+/// Turns `text` into a heading anchor the way GitHub does: lowercased, with spaces replaced by
+/// dashes and punctuation other than dashes and underscores removed.
+pub fn slug(text: &str) -> String {
+    collapse_whitespace(text)
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
 }
 
 impl fmt::Display for Heading {
@@ -101,58 +741,20 @@ impl<'a> HeadingEvent<'a> {
 
 impl<'a> fmt::Display for HeadingEvent<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        struct LinkParts<'a> {
-            url: &'a CowStr<'a>,
-            title: &'a CowStr<'a>,
-        }
-
-        impl<'a> fmt::Display for LinkParts<'a> {
-            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                write!(f, "{}", self.url)?;
-                if self.title.is_empty() {
-                    write!(f, " {}", self.title)?;
-                }
-                Ok(())
-            }
-        }
+        write_inline_event(f, &Event::from(self.clone()))
+    }
+}
 
-        match self {
-            HeadingEvent::Start(tag) => match tag {
-                HeadingTag::Emphasis => write!(f, "_")?,
-                HeadingTag::Strong => write!(f, "**")?,
-                HeadingTag::Strikethrough => write!(f, "~~")?,
-                HeadingTag::Link(ty, _, _) => match ty {
-                    LinkType::Autolink | LinkType::Email => write!(f, "<")?,
-                    _ => write!(f, "[")?,
-                },
-                HeadingTag::Image(ty, _, _) => match ty {
-                    LinkType::Autolink | LinkType::Email => write!(f, "!<")?,
-                    _ => write!(f, "![")?,
-                },
-            },
-            HeadingEvent::End(tag) => match tag {
-                HeadingTag::Emphasis => write!(f, "_")?,
-                HeadingTag::Strong => write!(f, "**")?,
-                HeadingTag::Strikethrough => write!(f, "~~")?,
-                HeadingTag::Link(ty, url, title) | HeadingTag::Image(ty, url, title) => {
-                    let parts = LinkParts { url, title };
-                    match ty {
-                        LinkType::Inline => write!(f, "]({})", parts)?,
-                        LinkType::Reference | LinkType::ReferenceUnknown => {
-                            write!(f, "][{}]", parts)?
-                        }
-                        LinkType::Collapsed | LinkType::CollapsedUnknown => write!(f, "][]")?,
-                        LinkType::Shortcut | LinkType::ShortcutUnknown => write!(f, "]")?,
-                        LinkType::Autolink | LinkType::Email => write!(f, ">")?,
-                    }
-                }
-            },
-            HeadingEvent::Text(t) => write!(f, "{}", t)?,
-            HeadingEvent::Code(c) => write!(f, "`{}`", c)?,
-            HeadingEvent::Html(h) => write!(f, "<{}>", h)?,
-            HeadingEvent::FootnoteReference(s) => write!(f, "^{}", s)?,
+impl<'a> From<HeadingEvent<'a>> for Event<'a> {
+    fn from(event: HeadingEvent<'a>) -> Self {
+        match event {
+            HeadingEvent::Start(tag) => Event::Start(tag.into()),
+            HeadingEvent::End(tag) => Event::End(tag.into()),
+            HeadingEvent::Text(s) => Event::Text(s),
+            HeadingEvent::Code(s) => Event::Code(s),
+            HeadingEvent::Html(s) => Event::Html(s),
+            HeadingEvent::FootnoteReference(s) => Event::FootnoteReference(s),
         }
-        Ok(())
     }
 }
 
@@ -225,6 +827,18 @@ impl<'a> TryFrom<Tag<'a>> for HeadingTag<'a> {
     }
 }
 
+impl<'a> From<HeadingTag<'a>> for Tag<'a> {
+    fn from(tag: HeadingTag<'a>) -> Self {
+        match tag {
+            HeadingTag::Emphasis => Tag::Emphasis,
+            HeadingTag::Strong => Tag::Strong,
+            HeadingTag::Strikethrough => Tag::Strikethrough,
+            HeadingTag::Link(ty, url, title) => Tag::Link(ty, url, title),
+            HeadingTag::Image(ty, url, title) => Tag::Image(ty, url, title),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct HeadingTagError<'a>(Tag<'a>);
 
@@ -285,10 +899,54 @@ impl BlockRef {
     }
 }
 
+impl fmt::Display for BlockRef {
+    /// Displays the block ref as the Markdown link it was read from, like `![[Note#^abc123]]`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let embed = if self.is_embedded { "!" } else { "" };
+        write!(f, "{}[[{}#^{}]]", embed, self.link, self.id)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    mod to_html {
+        use super::*;
+        use crate::parser::Parser;
+
+        fn fragment(text: &str) -> Fragment {
+            Parser::new(text).parse_list().unwrap().remove(0)
+        }
+
+        fn url(link: &WikiLink) -> Option<String> {
+            (link.note == "Plan trip").then(|| match link.section {
+                Some(section) => format!("trip.html#{}", section),
+                None => String::from("trip.html"),
+            })
+        }
+
+        #[test]
+        fn wiki_links_are_linked_to_their_urls() {
+            assert_eq!(
+                fragment("- See [[Plan trip]] and [[Plan trip#Goal|the goal]]\n").to_html(url),
+                "See <a href=\"trip.html\">Plan trip</a> and <a href=\"trip.html#Goal\">the goal</a>"
+            );
+            assert_eq!(
+                fragment("- Ask [[Alice Smith|Alice]] about ![[map.png]]\n").to_html(url),
+                "Ask Alice about map.png"
+            );
+        }
+
+        #[test]
+        fn markdown_is_rendered_and_html_is_escaped() {
+            assert_eq!(
+                fragment("- Book **flights** & `[[hotel]]` <b>now</b>\n").to_html(url),
+                "Book <strong>flights</strong> &amp; <code>[[hotel]]</code> &lt;b&gt;now&lt;/b&gt;"
+            );
+        }
+    }
+
     mod heading {
         use super::*;
 
@@ -307,6 +965,278 @@ mod tests {
                 assert_eq!(title, Some(String::from("Foo bar baz")));
             }
         }
+
+        mod eq {
+            use super::*;
+
+            #[test]
+            fn split_text_and_whitespace_are_ignored() {
+                let split = Heading(vec![
+                    HeadingEvent::Text("Foo".into()),
+                    HeadingEvent::Text("  bar ".into()),
+                ]);
+                let joined = Heading(vec![HeadingEvent::Text("Foo bar".into())]);
+                assert_eq!(split, joined);
+            }
+
+            #[test]
+            fn formatting_is_compared() {
+                let plain = Heading(vec![HeadingEvent::Text("Foo".into())]);
+                let emphasized = Heading(vec![
+                    HeadingEvent::Start(HeadingTag::Emphasis),
+                    HeadingEvent::Text("Foo".into()),
+                    HeadingEvent::End(HeadingTag::Emphasis),
+                ]);
+                assert_ne!(plain, emphasized);
+                assert_eq!(emphasized, "Foo");
+            }
+
+            #[test]
+            fn code_is_compared_as_text() {
+                let heading = Heading(vec![
+                    HeadingEvent::Text("Use ".into()),
+                    HeadingEvent::Code("gtd".into()),
+                ]);
+                assert_eq!(heading, "Use  gtd ");
+                assert_ne!(heading, "Use it");
+            }
+        }
+
+        mod display {
+            use crate::parser::Parser;
+
+            fn round_trip(title: &str) -> String {
+                let text = format!("# {}\n", title);
+                let heading = Parser::new(&text).parse_heading(1).unwrap();
+                heading.to_string()
+            }
+
+            #[test]
+            fn plain_text_round_trips() {
+                assert_eq!(round_trip("Plan a trip"), "Plan a trip");
+            }
+
+            #[test]
+            fn formatting_round_trips() {
+                assert_eq!(round_trip("_Plan_ a **trip**"), "_Plan_ a **trip**");
+            }
+
+            #[test]
+            fn code_round_trips() {
+                assert_eq!(round_trip("Use `gtd next`"), "Use `gtd next`");
+                assert_eq!(round_trip("Type ``a ` b``"), "Type ``a ` b``");
+                assert_eq!(round_trip("Type `` `a` ``"), "Type `` `a` ``");
+            }
+
+            #[test]
+            fn links_round_trip() {
+                assert_eq!(
+                    round_trip("Read [GTD](https://example.com \"Getting Things Done\")"),
+                    "Read [GTD](https://example.com \"Getting Things Done\")"
+                );
+                assert_eq!(round_trip("Read [GTD](gtd.md)"), "Read [GTD](gtd.md)");
+                assert_eq!(
+                    round_trip("See <https://example.com>"),
+                    "See <https://example.com>"
+                );
+                assert_eq!(round_trip("See [[Other project]]"), "See [[Other project]]");
+            }
+
+            #[test]
+            fn images_round_trip() {
+                assert_eq!(
+                    round_trip("![Logo](logo.png \"The logo\") Plans"),
+                    "![Logo](logo.png \"The logo\") Plans"
+                );
+            }
+
+            #[test]
+            fn html_and_footnotes_round_trip() {
+                assert_eq!(round_trip("<b>Plan</b> trip"), "<b>Plan</b> trip");
+                assert_eq!(round_trip("Plan trip[^1]"), "Plan trip[^1]");
+            }
+        }
+
+        #[test]
+        fn slug_is_lowercase_and_dashed() {
+            let heading = Heading(vec![
+                HeadingEvent::Text("Plan a ".into()),
+                HeadingEvent::Start(HeadingTag::Emphasis),
+                HeadingEvent::Text("trip".into()),
+                HeadingEvent::End(HeadingTag::Emphasis),
+                HeadingEvent::Text("!".into()),
+            ]);
+            assert_eq!(heading.slug(), "plan-a-trip");
+        }
+    }
+
+    mod fragment {
+        use super::*;
+
+        #[test]
+        fn inline_events_are_displayed() {
+            let frag = Fragment::from_events(vec![
+                Event::Text("Foo ".into()),
+                Event::Start(Tag::Emphasis),
+                Event::Text("bar".into()),
+                Event::End(Tag::Emphasis),
+                Event::SoftBreak,
+                Event::Code("baz".into()),
+            ]);
+            assert_eq!(frag.to_string(), "Foo _bar_ `baz`");
+        }
+
+        #[test]
+        fn block_events_are_ignored() {
+            let frag = Fragment::from_events(vec![
+                Event::Start(Tag::Paragraph),
+                Event::Text("Foo".into()),
+                Event::End(Tag::Paragraph),
+            ]);
+            assert_eq!(frag.to_string(), "Foo");
+        }
+
+        #[test]
+        fn text_has_no_formatting() {
+            let frag = Fragment::from_events(vec![
+                Event::Text("Foo  ".into()),
+                Event::Start(Tag::Emphasis),
+                Event::Text("bar".into()),
+                Event::End(Tag::Emphasis),
+                Event::SoftBreak,
+                Event::Code("baz".into()),
+            ]);
+            assert_eq!(frag.text(), "Foo bar baz");
+        }
+
+        #[test]
+        fn inline_events_are_rendered_as_markdown() {
+            let frag = Fragment::from_events(vec![
+                Event::Text("Read ".into()),
+                Event::Start(Tag::Link(LinkType::Inline, "gtd.md".into(), "".into())),
+                Event::Code("gtd".into()),
+                Event::End(Tag::Link(LinkType::Inline, "gtd.md".into(), "".into())),
+            ]);
+            assert_eq!(frag.to_markdown(), "Read [`gtd`](gtd.md)");
+        }
+
+        fn parse(text: &str) -> Fragment {
+            Fragment::from_events(pulldown_cmark::Parser::new(text).collect())
+        }
+
+        #[test]
+        fn quotes_are_rendered_as_markdown() {
+            let text = "Relax.\n\n> [!warning] Visa\n> Apply early.\n>\n> > Nested\n\nAfter.";
+            assert_eq!(parse(text).to_markdown(), text);
+        }
+
+        #[test]
+        fn callouts_are_found() {
+            let frag = parse("> [!Warning]+ Visa\n> Apply early.\n\n> [!note]\n\n> Just a quote.\n\n- > [!tip] In a list\n");
+            let callouts = frag.callouts();
+            assert_eq!(callouts.len(), 2);
+            assert_eq!(callouts[0].kind, "warning");
+            assert_eq!(callouts[0].title.as_deref(), Some("Visa"));
+            assert_eq!(callouts[0].body.to_markdown(), "Apply early.");
+            assert!(callouts[0].is_warning());
+            assert_eq!(callouts[1].kind, "note");
+            assert_eq!(callouts[1].title, None);
+            assert!(callouts[1].body.as_events().is_empty());
+            assert_eq!(
+                frag.without_callouts().to_markdown(),
+                "> Just a quote.\n\n> [!tip] In a list"
+            );
+        }
+
+        #[test]
+        fn footnotes_are_found() {
+            let options = pulldown_cmark::Options::ENABLE_FOOTNOTES;
+            let text = "Relax.[^1] Really[^b][^1].\n\n[^1]: In *Lisbon*.\n\nAfter.\n";
+            let frag =
+                Fragment::from_events(pulldown_cmark::Parser::new_ext(text, options).collect());
+            assert_eq!(frag.footnote_references(), vec!["1", "b"]);
+            let footnotes = frag.footnotes();
+            assert_eq!(footnotes.len(), 1);
+            assert_eq!(footnotes[0].label, "1");
+            assert_eq!(footnotes[0].body.to_markdown(), "In _Lisbon_.");
+            assert_eq!(
+                frag.without_footnotes().to_markdown(),
+                "Relax.[^1] Really[^b][^1].\n\nAfter."
+            );
+        }
+
+        fn parse_tables(text: &str) -> Fragment {
+            let options = pulldown_cmark::Options::ENABLE_TABLES;
+            Fragment::from_events(pulldown_cmark::Parser::new_ext(text, options).collect())
+        }
+
+        #[test]
+        fn tables_are_found() {
+            let frag = parse_tables(
+                "Costs:\n\n| Item | Price |\n|:--|--:|\n| Flights | 400 |\n| _Hotel_ | 90 |\n",
+            );
+            let tables = frag.tables();
+            assert_eq!(tables.len(), 1);
+            let table = &tables[0];
+            assert_eq!(table.alignments, vec![Alignment::Left, Alignment::Right]);
+            let header = table
+                .header
+                .iter()
+                .map(Fragment::to_string)
+                .collect::<Vec<_>>();
+            assert_eq!(header, vec!["Item", "Price"]);
+            let rows = table
+                .rows
+                .iter()
+                .map(|r| r.iter().map(Fragment::to_markdown).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            assert_eq!(rows, vec![vec!["Flights", "400"], vec!["_Hotel_", "90"]]);
+            assert_eq!(frag.without_tables().to_markdown(), "Costs:");
+        }
+
+        #[test]
+        fn tables_are_aligned_and_boxed() {
+            let frag = parse_tables("| Item | Price | Note |\n|--|--:|:-:|\n| Flights | 400 | |\n| Hotel | 90 | 3 nights |\n");
+            assert_eq!(
+                frag.tables()[0].to_text(),
+                "┌─────────┬───────┬──────────┐\n\
+                 │ Item    │ Price │   Note   │\n\
+                 ├─────────┼───────┼──────────┤\n\
+                 │ Flights │   400 │          │\n\
+                 │ Hotel   │    90 │ 3 nights │\n\
+                 └─────────┴───────┴──────────┘\n"
+            );
+        }
+    }
+
+    mod span {
+        use super::*;
+
+        #[test]
+        fn first_line_is_1() {
+            assert_eq!(Span::new(0, 3).start_line("foo\nbar"), 1);
+        }
+
+        #[test]
+        fn later_line_is_found() {
+            assert_eq!(Span::new(8, 11).start_line("foo\nbar\nbaz"), 3);
+        }
+
+        #[test]
+        fn line_range_covers_whole_lines() {
+            let text = "foo\n- bar\n  baz\nqux";
+            assert_eq!(Span::new(6, 15).line_range(text), 4..16);
+        }
+
+        #[test]
+        fn line_range_includes_last_line_without_newline() {
+            assert_eq!(Span::new(4, 7).line_range("foo\nbar"), 4..7);
+        }
+
+        #[test]
+        fn line_range_excludes_trailing_blank_lines() {
+            assert_eq!(Span::new(0, 6).line_range("- foo\n\nbar"), 0..6);
+        }
     }
 
     mod block_ref {
@@ -0,0 +1,275 @@
+//! Merging one context into another, for when two contexts turn out to be the same place.
+
+use crate::{
+    config,
+    context::{Action as ContextAction, Name as ContextName},
+    diff::WriteMode,
+    edit,
+    gtd::Documents,
+    order,
+    project::NameScheme,
+    transaction::{Transaction, TransactionError},
+};
+use std::{collections::HashSet, error::Error, fmt, fs, io::Error as IoError};
+
+/// How many actions a merge moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Merged {
+    pub moved: usize,
+    /// Actions that were left out because the target already had them.
+    pub duplicates: usize,
+}
+
+/// Moves the actions in the context `source` into `target`, leaving out ones `target` already has
+/// and keeping `target` in the order set in `[contexts]`, then moves `source` to the trash and
+/// makes it an alias of `target` in the config, along with any aliases of `source`.
+///
+/// Each change is shown as a word diff and written according to `mode`, all together or not at all,
+/// and can be put back with `gtd undo`. Returns `None` if nothing was written.
+pub fn merge_context(
+    docs: &Documents,
+    source: &ContextName,
+    target: &ContextName,
+    mode: WriteMode,
+) -> Result<Option<Merged>, MergeError> {
+    if source == target {
+        return Err(MergeError::SameContext);
+    }
+    if !docs.contexts().any(|c| c.name == *source) {
+        return Err(MergeError::NoSuchContext(source.clone()));
+    }
+
+    let loader = docs.loader();
+    let source_path = loader.context_path(source);
+    let target_path = loader.context_path(target);
+    let config_path = config::path(loader.root_dir());
+    let mut transaction = Transaction::new(loader, format!("merge-context {} {}", source, target));
+    let source_text = fs::read_to_string(&source_path)?;
+    let target_text = transaction
+        .read(&target_path)?
+        .unwrap_or_else(|| format!("# {}\n", target));
+    let config_text = transaction.read(&config_path)?.unwrap_or_default();
+
+    let (new_target_text, merged) = merge_items(&target_text, &source_text, loader.scheme());
+    let new_target_text = order::reorder(docs, &new_target_text, docs.context_order());
+    let new_config_text = config::set_context_alias(&config_text, source.as_str(), target.as_str());
+
+    transaction.write(&target_path, new_target_text)?;
+    transaction.write(&config_path, new_config_text)?;
+    transaction.delete(&source_path)?;
+    if !transaction.review(mode)? {
+        return Ok(None);
+    }
+    transaction.commit()?;
+    Ok(Some(merged))
+}
+
+/// Adds the list items in the context text `source` to the ungrouped items in the context text
+/// `target`, leaving out ones that link to the same action as, or have the same text as, an item
+/// already in `target`.
+pub fn merge_items(target: &str, source: &str, scheme: &NameScheme) -> (String, Merged) {
+    let mut seen = edit::list_items(target)
+        .into_iter()
+        .filter_map(|item| key(item, scheme))
+        .collect::<HashSet<_>>();
+    let mut merged = Merged {
+        moved: 0,
+        duplicates: 0,
+    };
+    let mut added = Vec::new();
+    for item in edit::list_items(source) {
+        let is_duplicate = key(item, scheme).is_some_and(|k| !seen.insert(k));
+        if is_duplicate {
+            merged.duplicates += 1;
+        } else {
+            merged.moved += 1;
+            added.push(item);
+        }
+    }
+    if added.is_empty() {
+        return (target.to_string(), merged);
+    }
+
+    // In a grouped context, the items go with the ungrouped ones before the first group.
+    let offset = edit::first_group(target).unwrap_or(target.len());
+    let (before, after) = target.split_at(offset);
+
+    let mut text = before.trim_end().to_string();
+    let ends_in_list = text
+        .lines()
+        .last()
+        .is_some_and(|l| edit::is_item_start(l) || l.starts_with(char::is_whitespace));
+    text.push_str(if ends_in_list { "\n" } else { "\n\n" });
+    text.push_str(&added.join("\n"));
+    text.push('\n');
+    if !after.is_empty() {
+        text.push('\n');
+        text.push_str(after);
+    }
+    (text, merged)
+}
+
+/// What makes two items the same action: the action they link to, or their text.
+fn key(item: &str, scheme: &NameScheme) -> Option<String> {
+    match ContextAction::parse_item(item, scheme)? {
+        ContextAction::Literal(fragment) => Some(fragment.text().trim().to_lowercase()),
+        ContextAction::Reference(action_ref) => Some(format!(
+            "{}#^{}",
+            action_ref.project_name, action_ref.action_id
+        )),
+        ContextAction::UnresolvedReference(block_ref) => {
+            Some(format!("{}#^{}", block_ref.link, block_ref.id))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MergeError {
+    NoSuchContext(ContextName),
+    SameContext,
+    IoError(IoError),
+    TransactionError(TransactionError),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoSuchContext(name) => write!(f, "there is no context {}", name),
+            Self::SameContext => write!(f, "can't merge a context into itself"),
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::TransactionError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for MergeError {}
+
+impl From<IoError> for MergeError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<TransactionError> for MergeError {
+    fn from(error: TransactionError) -> Self {
+        Self::TransactionError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, test_dir::TempDir, trash, undo::Undo};
+
+    #[test]
+    fn duplicates_are_left_out() {
+        let target = "# @computer\n\n- ![[202401010000 Plan trip#^abcdef]]\n- Back up laptop\n";
+        let source = "# @online\n\n- ![[202401010000 Plan trip#^abcdef]]\n- back up laptop\n- Renew domain\n  before March\n- ![[202401010000 Plan trip#^ffffff]]\n";
+        let (text, merged) = merge_items(target, source, NameScheme::default_ref());
+        assert_eq!(
+            text,
+            "# @computer\n\n- ![[202401010000 Plan trip#^abcdef]]\n- Back up laptop\n- Renew domain\n  before March\n- ![[202401010000 Plan trip#^ffffff]]\n"
+        );
+        assert_eq!(
+            merged,
+            Merged {
+                moved: 2,
+                duplicates: 2
+            }
+        );
+    }
+
+    #[test]
+    fn items_start_a_list_in_an_empty_context() {
+        let (text, _) = merge_items(
+            "# @computer\n",
+            "# @online\n\n- Renew domain\n",
+            NameScheme::default_ref(),
+        );
+        assert_eq!(text, "# @computer\n\n- Renew domain\n");
+    }
+
+    #[test]
+    fn items_go_before_groups() {
+        let (text, _) = merge_items(
+            "# @computer\n\n- Back up laptop\n\n## Plan trip\n\n- ![[202401010000 Plan trip#^abcdef]]\n",
+            "# @online\n\n- Renew domain\n",
+            NameScheme::default_ref(),
+        );
+        assert_eq!(
+            text,
+            "# @computer\n\n- Back up laptop\n- Renew domain\n\n## Plan trip\n\n- ![[202401010000 Plan trip#^abcdef]]\n"
+        );
+    }
+
+    #[test]
+    fn hand_ordered_items_keep_their_order() {
+        let target = "# @computer\n\n- Reply to Sam\n- Back up laptop\n- ![[202401010000 Plan trip#^abcdef]]\n\n## Later\n\n- Tidy desktop\n";
+        let source = "# @online\n\n- Renew domain\n- back up laptop\n- Cancel trial\n";
+        let (text, _) = merge_items(target, source, NameScheme::default_ref());
+        assert!(edit::keeps_order(target, &text));
+        assert!(edit::keeps_order(source, &text));
+        assert_eq!(
+            edit::list_items(&text),
+            vec![
+                "- Reply to Sam",
+                "- Back up laptop",
+                "- ![[202401010000 Plan trip#^abcdef]]",
+                "- Renew domain",
+                "- Cancel trial",
+                "- Tidy desktop"
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_can_be_undone() {
+        let dir = TempDir::new("merge");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let computer = "# @computer\n\n- Back up laptop\n";
+        let online = "# @online\n\n- Renew domain\n";
+        fs::write(dir.join("Contexts/@computer.md"), computer).unwrap();
+        fs::write(dir.join("Contexts/@online.md"), online).unwrap();
+        let name = |s: &str| ContextName::new(s.to_string());
+
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let merged = merge_context(
+            &docs,
+            &name("@online"),
+            &name("@computer"),
+            WriteMode::Write,
+        );
+        assert_eq!(
+            merged.unwrap(),
+            Some(Merged {
+                moved: 1,
+                duplicates: 0
+            })
+        );
+        assert!(!dir.join("Contexts/@online.md").exists());
+        assert_eq!(trash::list(docs.loader()).unwrap().len(), 1);
+        let config = Config::load(&dir).unwrap();
+        assert_eq!(
+            config.contexts.canonical(&name("@online")),
+            name("@computer")
+        );
+
+        Undo::load(docs.loader())
+            .unwrap()
+            .unwrap()
+            .restore(docs.loader())
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("Contexts/@computer.md")).unwrap(),
+            computer
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("Contexts/@online.md")).unwrap(),
+            online
+        );
+        assert!(!config::path(&dir).exists());
+        assert!(trash::list(docs.loader()).unwrap().is_empty());
+        assert!(Undo::load(docs.loader()).unwrap().is_none());
+    }
+}
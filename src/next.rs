@@ -0,0 +1,320 @@
+//! Listing of next actions, grouped by context, and picking one of them to do.
+
+use crate::{
+    config::NextConfig,
+    context::{Action as ContextAction, Context, Group},
+    filter::Filter,
+    gtd::{Capacity, Documents},
+    links::Linker,
+    project::Priority,
+    rng::Rng,
+};
+use chrono::NaiveDate;
+use std::io::{self, IsTerminal};
+
+/// An action that can be done next, from a context.
+#[derive(Debug, Clone)]
+pub struct Candidate<'a> {
+    pub context: &'a Context,
+    /// The group the action is under in its context, if it's in one.
+    pub group: Option<&'a Group>,
+    /// The action as it's listed, along with a link to its project if it has one.
+    pub line: String,
+    pub priority: Priority,
+    /// The date the action was created, or else the date its project was created.
+    pub created: Option<NaiveDate>,
+    /// The date the action was promoted to active, or else the date it was created.
+    pub active_since: Option<NaiveDate>,
+}
+
+/// How long a next action has been active, compared to the thresholds in `[next]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Age {
+    Fresh,
+    /// Active for longer than `old-after`.
+    Old,
+    /// Active for longer than `stuck-after`.
+    Stuck,
+}
+
+impl Candidate<'_> {
+    /// How likely the candidate is to be picked compared to others, which goes up with its
+    /// priority and with each week since it was created, up to a year.
+    pub fn weight(&self, today: NaiveDate) -> u64 {
+        let priority = match self.priority {
+            Priority::Lowest => 1,
+            Priority::Low => 2,
+            Priority::Normal => 4,
+            Priority::Medium => 6,
+            Priority::High => 8,
+            Priority::Highest => 12,
+        };
+        let weeks = self
+            .created
+            .map_or(0, |d| (today - d).num_weeks().clamp(0, 52) as u64);
+        priority * (weeks + 1)
+    }
+
+    /// How long the candidate has been active as of `today`. Literal actions are always fresh,
+    /// since there's no telling when they were added.
+    pub fn age(&self, today: NaiveDate, config: &NextConfig) -> Age {
+        match self.active_since {
+            Some(since) if since < config.stuck_after.before(today) => Age::Stuck,
+            Some(since) if since < config.old_after.before(today) => Age::Old,
+            _ => Age::Fresh,
+        }
+    }
+
+    /// A note on how long the candidate has been active if it's old or stuck, in yellow or red if
+    /// `color` is set.
+    fn age_note(&self, today: NaiveDate, config: &NextConfig, color: bool) -> String {
+        let (label, code) = match self.age(today, config) {
+            Age::Fresh => return String::new(),
+            Age::Old => ("active", 33),
+            Age::Stuck => ("stuck", 31),
+        };
+        let days = self.active_since.map_or(0, |d| (today - d).num_days());
+        let note = format!("[{} for {} days]", label, days);
+        if color {
+            format!(" \x1b[{}m{}\x1b[0m", code, note)
+        } else {
+            format!(" {}", note)
+        }
+    }
+}
+
+/// A note on how many open actions a context has if it's over its capacity, in red if `color` is
+/// set.
+fn capacity_note(capacity: Option<Capacity>, color: bool) -> String {
+    let capacity = match capacity {
+        Some(capacity) if capacity.is_exceeded() => capacity,
+        _ => return String::new(),
+    };
+    let note = format!("[over capacity: {}/{}]", capacity.actions, capacity.max);
+    if color {
+        format!(" \x1b[31m{}\x1b[0m", note)
+    } else {
+        format!(" {}", note)
+    }
+}
+
+/// Lists the actions in each context that match `filter`, leaving out referenced actions deferred
+/// until after `today`. Actions under a `##` heading in their context are nested under it.
+///
+/// Actions that have been active for longer than the thresholds in `config` are flagged, so that
+/// stuck actions stand out, as are contexts with more open actions than their capacity.
+pub fn next(
+    docs: &Documents,
+    links: &Linker,
+    today: NaiveDate,
+    filter: &Filter,
+    config: &NextConfig,
+) {
+    let color = io::stdout().is_terminal();
+    let mut is_first = true;
+    for context in contexts(docs, filter) {
+        let lines = candidates(docs, links, today, context, filter);
+        if lines.is_empty() {
+            continue;
+        }
+
+        if !is_first {
+            println!();
+        }
+        is_first = false;
+
+        let path = docs.loader().context_path(&context.name);
+        let name = docs.canonical_context_name(&context.name);
+        let note = capacity_note(docs.context_capacity(&name), color);
+        println!("{}:{}", links.link(&path, &name), note);
+        let mut group = None;
+        for candidate in lines {
+            if candidate.group != group {
+                group = candidate.group;
+                if let Some(group) = group {
+                    println!("- {}:", group.title());
+                }
+            }
+            let indent = if group.is_some() { "  " } else { "" };
+            let note = candidate.age_note(today, config, color);
+            println!("{}- {}{}", indent, candidate.line, note);
+        }
+    }
+}
+
+/// Prints one action picked at random from the ones `next` would list, with higher priority and
+/// older actions more likely to be picked. The same `seed` always picks the same action.
+pub fn pick(docs: &Documents, links: &Linker, today: NaiveDate, filter: &Filter, seed: u64) {
+    let candidates = contexts(docs, filter)
+        .into_iter()
+        .flat_map(|c| candidates(docs, links, today, c, filter))
+        .collect::<Vec<_>>();
+    let weights = candidates
+        .iter()
+        .map(|c| c.weight(today))
+        .collect::<Vec<_>>();
+    match pick_weighted(&weights, &mut Rng::new(seed)) {
+        Some(i) => {
+            let context = &candidates[i].context.name;
+            let path = docs.loader().context_path(context);
+            let name = docs.canonical_context_name(context);
+            println!("{}: {}", links.link(&path, &name), candidates[i].line);
+        }
+        None => println!("There are no next actions."),
+    }
+}
+
+/// The contexts in `docs` that match `filter`, sorted by name.
+fn contexts<'a>(docs: &'a Documents, filter: &Filter) -> Vec<&'a Context> {
+    docs.contexts()
+        .filter(|c| filter.matches_context(docs, c))
+        .collect()
+}
+
+/// The actions in `context` that match `filter`, leaving out referenced actions deferred until
+/// after `today`. The filter's contexts aren't checked, since `context` is given.
+pub fn candidates<'a>(
+    docs: &Documents,
+    links: &Linker,
+    today: NaiveDate,
+    context: &'a Context,
+    filter: &Filter,
+) -> Vec<Candidate<'a>> {
+    context
+        .grouped_actions()
+        .filter_map(|(group, action)| match action {
+            ContextAction::Literal(text) => match filter.matches_literal() {
+                false => None,
+                true => Some(Candidate {
+                    context,
+                    group,
+                    line: text.to_string(),
+                    priority: Priority::default(),
+                    created: None,
+                    active_since: None,
+                }),
+            },
+            // Like a link to a project that doesn't exist, it's reported by `validate` instead.
+            ContextAction::UnresolvedReference(_) => None,
+            ContextAction::Reference(action_ref) => {
+                let project = docs.project(&action_ref.project_name)?;
+                let (action, _) = project.actions.get_action(&action_ref.action_id)?;
+                if action.is_deferred(today) || !filter.matches_action(project, action) {
+                    return None;
+                }
+
+                let path = docs.loader().project_path(&project.name);
+                let title = links.link(&path, project.title());
+                let created = action
+                    .created
+                    .or_else(|| docs.loader().scheme().id_date(project.id()?));
+                Some(Candidate {
+                    context,
+                    group,
+                    line: format!("{} ({})", action.text, title),
+                    priority: action.priority,
+                    created,
+                    active_since: action.activated.or(created),
+                })
+            }
+        })
+        .collect()
+}
+
+/// Picks an index at random, with each one as likely as its share of the total of `weights`.
+fn pick_weighted(weights: &[u64], rng: &mut Rng) -> Option<usize> {
+    let total = weights.iter().sum::<u64>();
+    if total == 0 {
+        return None;
+    }
+    let mut target = rng.next_u64() % total;
+    weights.iter().position(|w| {
+        if target < *w {
+            return true;
+        }
+        target -= w;
+        false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_picks_same_index() {
+        let weights = [4, 8, 1, 12];
+        let first = pick_weighted(&weights, &mut Rng::new(7));
+        assert_eq!(pick_weighted(&weights, &mut Rng::new(7)), first);
+    }
+
+    #[test]
+    fn zero_weights_are_never_picked() {
+        for seed in 0..100 {
+            assert_eq!(pick_weighted(&[0, 3, 0], &mut Rng::new(seed)), Some(1));
+        }
+        assert_eq!(pick_weighted(&[], &mut Rng::new(0)), None);
+    }
+
+    #[test]
+    fn weight_grows_with_priority_and_age() {
+        let context = Context::parse("@home", "# @home\n").unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let candidate = |priority, created| Candidate {
+            context: &context,
+            group: None,
+            line: String::from("Pack bags"),
+            priority,
+            created,
+            active_since: created,
+        };
+        assert_eq!(candidate(Priority::Normal, None).weight(today), 4);
+        assert_eq!(candidate(Priority::High, None).weight(today), 8);
+        assert_eq!(
+            candidate(Priority::Normal, NaiveDate::from_ymd_opt(2024, 1, 1)).weight(today),
+            12
+        );
+    }
+
+    #[test]
+    fn age_is_flagged_past_thresholds() {
+        let context = Context::parse("@home", "# @home\n").unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let candidate = |active_since| Candidate {
+            context: &context,
+            group: None,
+            line: String::from("Pack bags"),
+            priority: Priority::Normal,
+            created: None,
+            active_since,
+        };
+        let config = NextConfig::default();
+        let age = |d| candidate(NaiveDate::from_ymd_opt(2024, 2, d)).age(today, &config);
+        assert_eq!(age(20), Age::Fresh);
+        assert_eq!(age(10), Age::Old);
+        assert_eq!(
+            candidate(NaiveDate::from_ymd_opt(2024, 1, 20)).age(today, &config),
+            Age::Stuck
+        );
+        assert_eq!(candidate(None).age(today, &config), Age::Fresh);
+
+        let old = candidate(NaiveDate::from_ymd_opt(2024, 2, 10));
+        assert_eq!(old.age_note(today, &config, false), " [active for 20 days]");
+        assert_eq!(
+            old.age_note(today, &config, true),
+            " \x1b[33m[active for 20 days]\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn capacity_is_flagged_when_exceeded() {
+        let capacity = |actions| Some(Capacity { actions, max: 3 });
+        assert_eq!(capacity_note(None, false), "");
+        assert_eq!(capacity_note(capacity(3), false), "");
+        assert_eq!(capacity_note(capacity(4), false), " [over capacity: 4/3]");
+        assert_eq!(
+            capacity_note(capacity(4), true),
+            " \x1b[31m[over capacity: 4/3]\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Helpers for working with Obsidian vaults.
+
+use std::path::{Path, PathBuf};
+
+/// The folder Obsidian uses to mark the root of a vault.
+const VAULT_MARKER: &str = ".obsidian";
+
+/// An Obsidian vault.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vault {
+    name: String,
+    root: PathBuf,
+}
+
+impl Vault {
+    pub fn new(name: String, root: PathBuf) -> Self {
+        Self { name, root }
+    }
+
+    /// Finds the vault that `dir` is in by searching it and its ancestors for a `.obsidian` folder.
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let root = dir.ancestors().find(|d| d.join(VAULT_MARKER).is_dir())?;
+        let name = root.file_name()?.to_str()?.to_string();
+        Some(Self::new(name, root.to_owned()))
+    }
+
+    /// Finds the vault that `dir` is in, using `name` as its name if given.
+    ///
+    /// If there's a name but no vault can be detected, `dir` is assumed to be the root of the vault.
+    pub fn find(dir: &Path, name: Option<&str>) -> Option<Self> {
+        let detected = Self::detect(dir);
+        match name {
+            Some(name) => {
+                let root = detected.map_or_else(|| dir.to_owned(), |v| v.root);
+                Some(Self::new(name.to_string(), root))
+            }
+            None => detected,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Builds an `obsidian://open` URI for the file at `path`, which must be inside the vault.
+    pub fn uri(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let relative = relative.with_extension("");
+        let file = relative
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?
+            .join("/");
+
+        Some(format!(
+            "obsidian://open?vault={}&file={}",
+            percent_encode(&self.name),
+            percent_encode(&file)
+        ))
+    }
+}
+
+/// Percent-encodes `s` for use in a URI query component.
+pub fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+/// Decodes the percent-encoded bytes in `s`, leaving invalid escapes as they are.
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(b) => {
+                decoded.push(b);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserved_characters_are_encoded() {
+        assert_eq!(percent_encode("a b/c&d"), "a%20b%2Fc%26d");
+    }
+
+    #[test]
+    fn encoded_text_is_decoded() {
+        assert_eq!(percent_decode("a%20b%C3%A9%2"), "a bé%2");
+    }
+
+    #[test]
+    fn non_ascii_is_encoded_as_utf8() {
+        assert_eq!(percent_encode("é"), "%C3%A9");
+    }
+
+    #[test]
+    fn uri_is_relative_to_vault() {
+        let vault = Vault::new(String::from("My vault"), PathBuf::from("/notes"));
+        let uri = vault.uri(Path::new("/notes/GTD/Projects/Plan trip.md"));
+        assert_eq!(
+            uri,
+            Some(String::from(
+                "obsidian://open?vault=My%20vault&file=GTD%2FProjects%2FPlan%20trip"
+            ))
+        );
+    }
+
+    #[test]
+    fn named_vault_defaults_to_dir() {
+        let vault = Vault::find(Path::new("/nonexistent/gtd"), Some("Notes")).unwrap();
+        assert_eq!(vault.name(), "Notes");
+        assert_eq!(vault.root(), Path::new("/nonexistent/gtd"));
+    }
+
+    #[test]
+    fn missing_vault_is_none() {
+        assert_eq!(Vault::find(Path::new("/nonexistent/gtd"), None), None);
+    }
+
+    #[test]
+    fn path_outside_vault_has_no_uri() {
+        let vault = Vault::new(String::from("notes"), PathBuf::from("/notes"));
+        assert_eq!(vault.uri(Path::new("/other/file.md")), None);
+    }
+}
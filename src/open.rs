@@ -0,0 +1,143 @@
+//! Opening documents in an external editor.
+
+use crate::{
+    config::{Config, OpenMethod},
+    context::Context,
+    gtd::Documents,
+    obsidian::Vault,
+    project::{ActionId, Project},
+    resolve::{self, ResolveError},
+};
+use std::{env, error::Error, fmt, fs, io::Error as IoError, path::Path, process::Command};
+
+/// A document that can be opened.
+enum Target<'a> {
+    Project(&'a Project),
+    Context(&'a Context),
+}
+
+/// Opens the project or context best matching `query`, optionally at the action with ID `action`.
+pub fn open(
+    docs: &Documents,
+    config: &Config,
+    query: &str,
+    action: Option<&ActionId>,
+) -> Result<(), OpenError> {
+    let targets = docs
+        .projects()
+        .map(Target::Project)
+        .chain(docs.contexts().map(Target::Context));
+
+    let target = resolve::resolve(
+        query,
+        targets,
+        |t| match t {
+            Target::Project(p) => vec![p.title(), p.name.as_str()],
+            Target::Context(c) => vec![c.name.as_str()],
+        },
+        |t| match t {
+            Target::Project(p) => p.name.to_string(),
+            Target::Context(c) => c.name.to_string(),
+        },
+    )?;
+
+    let (path, line) = match (target, action) {
+        (Target::Project(project), action) => {
+            let path = docs.loader().project_path(&project.name);
+            let line = match action {
+                Some(id) => {
+                    let (action, _) = project
+                        .actions
+                        .get_action(id)
+                        .ok_or(OpenError::NoSuchAction)?;
+                    let text = fs::read_to_string(&path)?;
+                    Some(action.span.start_line(&text))
+                }
+                None => None,
+            };
+            (path, line)
+        }
+        (Target::Context(_), Some(_)) => return Err(OpenError::NoSuchAction),
+        (Target::Context(context), None) => (docs.loader().context_path(&context.name), None),
+    };
+
+    match config.open.method {
+        OpenMethod::Editor => open_in_editor(&path, line),
+        OpenMethod::Obsidian => {
+            let vault = Vault::find(docs.loader().root_dir(), config.obsidian.vault.as_deref())
+                .ok_or(OpenError::NoVault)?;
+            let uri = vault.uri(&path).ok_or(OpenError::NoVault)?;
+            open_uri(&uri)
+        }
+    }
+}
+
+fn open_in_editor(path: &Path, line: Option<usize>) -> Result<(), OpenError> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+
+    // The editor may be configured with arguments, like `code --wait`.
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().ok_or(OpenError::NoEditor)?;
+    let mut command = Command::new(program);
+    command.args(parts);
+
+    // Most terminal editors accept `+<line>` to jump to a line.
+    if let Some(line) = line {
+        command.arg(format!("+{}", line));
+    }
+
+    command.arg(path).status()?;
+    Ok(())
+}
+
+fn open_uri(uri: &str) -> Result<(), OpenError> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command.arg(uri).status()?;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum OpenError {
+    IoError(IoError),
+    ResolveError(ResolveError),
+    NoSuchAction,
+    NoEditor,
+    NoVault,
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::ResolveError(e) => write!(f, "{}", e),
+            Self::NoSuchAction => write!(f, "document doesn't have that action"),
+            Self::NoEditor => write!(f, "no editor is configured"),
+            Self::NoVault => write!(f, "couldn't find the Obsidian vault"),
+        }
+    }
+}
+
+impl Error for OpenError {}
+
+impl From<IoError> for OpenError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<ResolveError> for OpenError {
+    fn from(error: ResolveError) -> Self {
+        Self::ResolveError(error)
+    }
+}
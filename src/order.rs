@@ -0,0 +1,175 @@
+//! Keeping the actions in contexts in the order set by `order` in `[contexts]`.
+//!
+//! Actions are only moved within the list they're in, so ones under a group heading stay in that
+//! group, and actions that are tied keep the order they were written in.
+
+use crate::{
+    config::ContextOrder,
+    context::{Action as ContextAction, Context},
+    edit,
+    gtd::Documents,
+    project::Priority,
+};
+use chrono::NaiveDate;
+use std::cmp::Reverse;
+
+/// What an action is sorted by. Actions without a project, or without what they're sorted by,
+/// have `true` first, so they go last.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Manual,
+    Priority(Reverse<Priority>),
+    Project(bool, String),
+    Age(bool, Option<NaiveDate>),
+}
+
+/// Whether the actions in `context`, from `docs`, are in `order`.
+pub fn is_in_order(docs: &Documents, context: &Context, order: ContextOrder) -> bool {
+    let is_sorted = |actions: &[ContextAction]| {
+        let keys = actions
+            .iter()
+            .map(|a| key(docs, Some(a), order))
+            .collect::<Vec<_>>();
+        keys.windows(2).all(|pair| pair[0] <= pair[1])
+    };
+    is_sorted(context.ungrouped_actions())
+        && context
+            .groups()
+            .iter()
+            .all(|group| is_sorted(context.group_actions(group)))
+}
+
+/// Sorts each list of actions in the context text `text` into `order`, looking up the actions they
+/// link to in `docs`. Each action keeps the lines indented under it, and everything that isn't in a
+/// list is left as it is.
+pub fn reorder(docs: &Documents, text: &str, order: ContextOrder) -> String {
+    if order == ContextOrder::Manual {
+        return text.to_string();
+    }
+
+    let start = edit::context_body_start(text);
+    let mut out = String::from(&text[..start]);
+    let lines = text[start..].split_inclusive('\n').collect::<Vec<_>>();
+    let mut items = Vec::<String>::new();
+    for (i, line) in lines.iter().enumerate() {
+        let is_indented = line.starts_with(char::is_whitespace) && !line.trim().is_empty();
+        // A blank line only ends the list if what comes after it isn't more of the list.
+        let continues_list = line.trim().is_empty()
+            && lines[i + 1..]
+                .iter()
+                .find(|l| !l.trim().is_empty())
+                .is_some_and(|l| edit::is_item_start(l) || l.starts_with(char::is_whitespace));
+        match items.last_mut() {
+            _ if edit::is_item_start(line) => items.push(line.to_string()),
+            Some(item) if is_indented || continues_list => item.push_str(line),
+            _ => {
+                out.push_str(&sorted(docs, &mut items, order));
+                out.push_str(line);
+            }
+        }
+    }
+    out.push_str(&sorted(docs, &mut items, order));
+    if !text.ends_with('\n') && out.ends_with('\n') {
+        out.pop();
+    }
+    out
+}
+
+/// The list items in `items` sorted into `order`, which leaves `items` empty.
+fn sorted(docs: &Documents, items: &mut Vec<String>, order: ContextOrder) -> String {
+    let scheme = docs.loader().scheme();
+    let mut keyed = items
+        .drain(..)
+        .map(|mut item| {
+            if !item.ends_with('\n') {
+                item.push('\n');
+            }
+            let action = ContextAction::parse_item(item.trim_end(), scheme);
+            (key(docs, action.as_ref(), order), item)
+        })
+        .collect::<Vec<_>>();
+    keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+    keyed.into_iter().map(|(_, item)| item).collect()
+}
+
+fn key(docs: &Documents, action: Option<&ContextAction>, order: ContextOrder) -> Key {
+    let action_ref = action.and_then(ContextAction::to_action_ref);
+    let linked = action_ref.and_then(|r| docs.linked_action(r));
+    match order {
+        ContextOrder::Manual => Key::Manual,
+        ContextOrder::Priority => Key::Priority(Reverse(
+            linked.map_or(Priority::Normal, |a| a.action.priority),
+        )),
+        ContextOrder::Project => {
+            let project = action_ref.and_then(|r| docs.project(&r.project_name));
+            let title = project.map(|p| p.title().to_lowercase());
+            Key::Project(title.is_none(), title.unwrap_or_default())
+        }
+        ContextOrder::Age => {
+            let since = linked.and_then(|a| a.action.active_since());
+            Key::Age(since.is_none(), since)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+    use std::fs;
+
+    fn folder(context: &str) -> (TempDir, Documents) {
+        let dir = TempDir::new("order");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book hotel ^aaaaaa\n- Book flights ⏫ ^bbbbbb\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Projects/202401020000 Fix bike.md"),
+            "# Fix bike\n#in-progress\n\n## Actions\n\n### Active\n\n- Buy tube @active(2024-01-03) ^cccccc\n",
+        )
+        .unwrap();
+        fs::write(dir.join("Contexts/@errands.md"), context).unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        (dir, docs)
+    }
+
+    const CONTEXT: &str = "# @errands\n\n- Buy milk\n- ![[202401010000 Plan trip#^aaaaaa]]\n- ![[202401020000 Fix bike#^cccccc]]\n- ![[202401010000 Plan trip#^bbbbbb]]\n\n## Later\n\n- ![[202401010000 Plan trip#^bbbbbb]]\n- Return books\n";
+
+    #[test]
+    fn actions_are_sorted_within_their_lists() {
+        let (_dir, docs) = folder(CONTEXT);
+        assert_eq!(
+            reorder(&docs, CONTEXT, ContextOrder::Priority),
+            "# @errands\n\n- ![[202401010000 Plan trip#^bbbbbb]]\n- Buy milk\n- ![[202401010000 Plan trip#^aaaaaa]]\n- ![[202401020000 Fix bike#^cccccc]]\n\n## Later\n\n- ![[202401010000 Plan trip#^bbbbbb]]\n- Return books\n"
+        );
+        assert_eq!(
+            reorder(&docs, CONTEXT, ContextOrder::Project),
+            "# @errands\n\n- ![[202401020000 Fix bike#^cccccc]]\n- ![[202401010000 Plan trip#^aaaaaa]]\n- ![[202401010000 Plan trip#^bbbbbb]]\n- Buy milk\n\n## Later\n\n- ![[202401010000 Plan trip#^bbbbbb]]\n- Return books\n"
+        );
+        assert_eq!(reorder(&docs, CONTEXT, ContextOrder::Manual), CONTEXT);
+    }
+
+    #[test]
+    fn reordered_contexts_are_in_order() {
+        let (_dir, docs) = folder(CONTEXT);
+        let context = docs.contexts().next().unwrap();
+        for order in [
+            ContextOrder::Priority,
+            ContextOrder::Project,
+            ContextOrder::Age,
+        ] {
+            assert!(!is_in_order(&docs, context, order), "{}", order);
+            let text = reorder(&docs, CONTEXT, order);
+            let (_dir, docs) = folder(&text);
+            let context = docs.contexts().next().unwrap();
+            assert!(is_in_order(&docs, context, order), "{}", order);
+            assert_eq!(reorder(&docs, &text, order), text);
+        }
+        assert!(is_in_order(&docs, context, ContextOrder::Manual));
+    }
+}
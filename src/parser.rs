@@ -1,31 +1,42 @@
 //! Markdown parser and helpers.
 
 use crate::{
-    markdown::{Fragment, Heading},
-    pulldown::{event_static, DisplayableEvent},
+    frontmatter::Frontmatter,
+    markdown::{Footnote, Fragment, Heading, Span},
+    pulldown::{event_static, DisplayableEvent, SpannedEvent},
+    tag,
 };
-use pulldown_cmark::{CowStr, Event, Options, Parser as MarkdownParser, Tag};
+use pulldown_cmark::{Event, OffsetIter, Options, Parser as MarkdownParser, Tag};
 use std::{
     convert::{TryFrom, TryInto},
     error::Error,
     fmt,
     iter::Peekable,
+    ops::Range,
 };
 
 pub struct Doc<'a> {
+    pub frontmatter: Frontmatter,
     pub title: Heading,
-    pub tags: Vec<String>,
+    /// The tags in the paragraphs between the title and whatever comes after them, which can be
+    /// on lines of their own or mixed in with other text.
+    pub tags: Vec<tag::Tag>,
     pub parser: Parser<'a>,
 }
 
 impl<'a> Doc<'a> {
     pub fn parse(text: &'a str) -> Result<Self, ParseError<'a>> {
-        let mut parser = Parser::new(text);
+        let (frontmatter, start) = Frontmatter::parse(text);
+        let mut parser = Parser::new_at(text, start);
 
         let title = parser.parse_heading(1)?;
-        let tags = parser.parse_tags().unwrap_or_else(|_| Vec::new());
+        let mut tags = Vec::new();
+        while parser.peek() == Some(&Event::Start(Tag::Paragraph)) {
+            tags.extend(parser.parse_tags()?);
+        }
 
         Ok(Self {
+            frontmatter,
             title,
             tags,
             parser,
@@ -33,27 +44,235 @@ impl<'a> Doc<'a> {
     }
 }
 
+/// How deep the headings that give documents their structure are, for vaults that don't use the
+/// usual `##` sections and `###` action statuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadingDepths {
+    /// The sections of a project, like `## Goal` and `## Actions`.
+    pub project_sections: u32,
+    /// The action statuses of a project, like `### Active`. If they're as deep as the sections,
+    /// they're sections themselves, without an `Actions` section around them.
+    pub action_statuses: u32,
+    /// The contexts in the action list, like `## @computer`, with their groups one level deeper.
+    /// At depth 1 the list has no title.
+    pub action_list_contexts: u32,
+}
+
+impl HeadingDepths {
+    pub fn new(
+        project_sections: u32,
+        action_statuses: u32,
+        action_list_contexts: u32,
+    ) -> Result<Self, HeadingDepthsError> {
+        let check = |name, depth, min| match depth {
+            d if (min..=6).contains(&d) => Ok(d),
+            _ => Err(HeadingDepthsError { name, depth, min }),
+        };
+        Ok(Self {
+            project_sections: check("project-sections", project_sections, 2)?,
+            action_statuses: check("action-statuses", action_statuses, project_sections)?,
+            action_list_contexts: check("action-list-contexts", action_list_contexts, 1)?,
+        })
+    }
+
+    /// Whether action statuses are sections of a project instead of being in an `Actions` section.
+    pub fn has_status_sections(&self) -> bool {
+        self.action_statuses == self.project_sections
+    }
+}
+
+impl Default for HeadingDepths {
+    fn default() -> Self {
+        Self {
+            project_sections: 2,
+            action_statuses: 3,
+            action_list_contexts: 2,
+        }
+    }
+}
+
+/// An error when a heading depth is out of range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadingDepthsError {
+    pub name: &'static str,
+    pub depth: u32,
+    pub min: u32,
+}
+
+impl fmt::Display for HeadingDepthsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} must be a heading depth from {} to 6, not {}",
+            self.name, self.min, self.depth
+        )
+    }
+}
+
+impl Error for HeadingDepthsError {}
+
+/// A heading as it's written in the text of a document, which may be `## Goal`, `## Goal ##`, or
+/// `Goal` underlined with `---`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceHeading {
+    pub level: u32,
+    /// The heading's text as written, without its `#`s or underline.
+    pub text: String,
+    /// The lines the heading is on, including its underline and the newline at the end.
+    pub lines: Range<usize>,
+}
+
+impl SourceHeading {
+    /// The heading written the usual way, like `## Goal`.
+    pub fn to_atx(&self) -> String {
+        format!("{} {}", "#".repeat(self.level as usize), self.text)
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// The headings in `text` that aren't inside a list, quote, or code block, in order. The
+/// frontmatter is skipped, so its closing `---` isn't taken as an underline.
+pub fn source_headings(text: &str) -> Vec<SourceHeading> {
+    let (_, start) = Frontmatter::parse(text);
+    let mut headings = Vec::new();
+    let mut depth = 0;
+    for (event, range) in MarkdownParser::new_ext(&text[start..], options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading(level)) if depth == 0 => {
+                let range = start + range.start..start + range.end;
+                let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+                let line_end = match text[..range.end].ends_with('\n') {
+                    true => range.end,
+                    false => text[range.end..]
+                        .find('\n')
+                        .map_or(text.len(), |i| range.end + i + 1),
+                };
+                let source = &text[line_start..line_end];
+                headings.push(SourceHeading {
+                    level,
+                    text: heading_text(source),
+                    lines: line_start..line_end,
+                });
+                depth += 1;
+            }
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+    headings
+}
+
+/// Rewrites the headings in `text` that are underlined or closed with `#`s the usual way, like
+/// `## Goal`, leaving everything else as it is.
+pub fn normalize_headings(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut end = 0;
+    for heading in source_headings(text) {
+        out.push_str(&text[end..heading.lines.start]);
+        let source = &text[heading.lines.clone()];
+        let atx = heading.to_atx();
+        if source.trim_end() == atx {
+            out.push_str(source);
+        } else {
+            out.push_str(&atx);
+            out.push('\n');
+        }
+        end = heading.lines.end;
+    }
+    out.push_str(&text[end..]);
+    out
+}
+
+/// The text of the heading on `lines`, without its `#`s, closing `#`s, or underline.
+fn heading_text(lines: &str) -> String {
+    let lines = lines.trim_end().lines().collect::<Vec<_>>();
+    match lines.split_last() {
+        Some((underline, text)) if !text.is_empty() && is_underline(underline) => {
+            text.iter().map(|l| l.trim()).collect::<Vec<_>>().join(" ")
+        }
+        _ => {
+            let text = lines
+                .first()
+                .map_or("", |l| l.trim().trim_start_matches('#'));
+            let text = text.trim();
+            match text.trim_end_matches('#') {
+                "" => String::new(),
+                open if open.ends_with([' ', '\t']) => open.trim_end().to_string(),
+                _ => text.to_string(),
+            }
+        }
+    }
+}
+
+/// Whether `line` underlines a setext heading, like `===` or `---`.
+fn is_underline(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && (line.chars().all(|c| c == '=') || line.chars().all(|c| c == '-'))
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES | Options::ENABLE_TASKLISTS
+}
+
 /// A Markdown parser.
 ///
 /// `Parser` has single event lookahead, meaning that as long as you only need one event to
 /// determine what to parse (which its internal parsing methods do,) you don't need to care about
 /// backtracking.
 pub struct Parser<'a> {
-    parser: Peekable<MarkdownParser<'a>>,
+    parser: Peekable<OffsetIter<'a>>,
+    len: usize,
+    start: usize,
+    last_end: usize,
 }
 
 impl<'a> Parser<'a> {
     /// Creates a new parser from `text`.
     pub fn new(text: &'a str) -> Self {
-        let options =
-            Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES | Options::ENABLE_TASKLISTS;
-        let parser = MarkdownParser::new_ext(text, options).peekable();
-        Self { parser }
+        Self::new_at(text, 0)
+    }
+
+    /// Creates a new parser for the part of `text` starting at byte offset `start`.
+    ///
+    /// Offsets and spans are still relative to the start of `text`.
+    pub fn new_at(text: &'a str, start: usize) -> Self {
+        let parser = MarkdownParser::new_ext(&text[start..], options())
+            .into_offset_iter()
+            .peekable();
+        Self {
+            parser,
+            len: text.len(),
+            start,
+            last_end: start,
+        }
     }
 
     /// Peeks at the next event in the parser without consuming it.
     pub fn peek(&mut self) -> Option<&Event<'a>> {
-        self.parser.peek()
+        self.parser.peek().map(|(ev, _)| ev)
+    }
+
+    /// The byte offset in the text where the next event starts, or the end of the text if there
+    /// are no more events.
+    pub fn offset(&mut self) -> usize {
+        let (len, start) = (self.len, self.start);
+        self.parser
+            .peek()
+            .map_or(len, |(_, range)| start + range.start)
+    }
+
+    /// Runs the parsing function `func`, returning its output along with the span of text that it
+    /// consumed.
+    pub fn spanned<F, T>(&mut self, func: F) -> Result<(T, Span), ParseError<'a>>
+    where
+        F: Fn(&mut Self) -> Result<T, ParseError<'a>>,
+    {
+        let start = self.offset();
+        let output = func(self)?;
+        let end = self.last_end.max(start);
+        Ok((output, Span::new(start, end)))
     }
 
     /// Parses an arbitrary event.
@@ -94,18 +313,6 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// Parses an unbroken chunk of text.
-    fn parse_text(&mut self) -> Result<CowStr<'a>, ParseError<'a>> {
-        self.parse_general(
-            |ev| matches!(ev, &Event::Text(_)),
-            |ev| match ev {
-                Event::Text(t) => Some(t),
-                _ => None,
-            },
-            || Event::Text(CowStr::Inlined(' '.into())),
-        )
-    }
-
     /// Parses a start `tag`.
     fn parse_start(&mut self, tag: &Tag<'a>) -> Result<Event<'a>, ParseError<'a>> {
         self.parse_general(
@@ -220,14 +427,61 @@ impl<'a> Parser<'a> {
         self.parse_general_list_opt(None, Self::parse_item)
     }
 
-    /// Parses a list of hashtags.
-    pub fn parse_tags(&mut self) -> Result<Vec<String>, ParseError<'a>> {
-        self.parse_element_res(&Tag::Paragraph, |p| {
-            Ok(p.parse_text()?
-                .split(' ')
-                .flat_map(|s| s.strip_prefix('#').map(|s| s.to_string()))
-                .collect())
-        })
+    /// Parses an unordered list that may be empty (nonexistent,) along with the span of each item.
+    pub fn parse_spanned_list_opt(&mut self) -> Result<Vec<(Fragment, Span)>, ParseError<'a>> {
+        self.parse_general_list_opt(None, |p| p.spanned(Self::parse_item))
+    }
+
+    /// Parses the footnote definitions at the current position, which can be anywhere between
+    /// blocks.
+    pub fn parse_footnotes(&mut self) -> Vec<Footnote> {
+        let mut footnotes = Vec::new();
+        while let Some(Event::Start(Tag::FootnoteDefinition(label))) = self.peek() {
+            let end = Event::End(Tag::FootnoteDefinition(label.clone()));
+            let label = label.to_string();
+            self.next();
+            let body = self.parse_until(end);
+            self.next();
+            footnotes.push(Footnote { label, body });
+        }
+        footnotes
+    }
+
+    /// Parses a paragraph, returning the hashtags in it. Only its plain text is looked at, so tags
+    /// in code, links, or emphasis aren't counted.
+    pub fn parse_tags(&mut self) -> Result<Vec<tag::Tag>, ParseError<'a>> {
+        let paragraph = self.parse_element(&Tag::Paragraph, |p| {
+            p.parse_until(Event::End(Tag::Paragraph))
+        })?;
+        let mut text = String::new();
+        let mut depth = 0;
+        for event in paragraph.into_events() {
+            match event {
+                Event::Text(t) if depth == 0 => {
+                    text.push_str(&t);
+                    continue;
+                }
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                _ => {}
+            }
+            // Anything but plain text, like a line break, separates words.
+            text.push(' ');
+        }
+        Ok(text
+            .split_whitespace()
+            .flat_map(tag::Tag::from_word)
+            .collect())
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Consumes the next event, along with its span in the text.
+    pub fn next_spanned(&mut self) -> Option<SpannedEvent<'a>> {
+        let (ev, range) = self.parser.next()?;
+        let span = Span::new(self.start + range.start, self.start + range.end);
+        self.last_end = span.end;
+        Some(SpannedEvent::new(ev, span))
     }
 }
 
@@ -235,7 +489,7 @@ impl<'a> Iterator for Parser<'a> {
     type Item = Event<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.parser.next()
+        self.next_spanned().map(|ev| ev.event)
     }
 }
 
@@ -312,6 +566,17 @@ mod tests {
     use super::*;
     use crate::markdown::Fragment;
 
+    #[test]
+    fn spanned_events_point_into_text() {
+        let text = "---\nkey: value\n---\n# Foo\n";
+        let (_, start) = Frontmatter::parse(text);
+        let mut parser = Parser::new_at(text, start);
+        let heading = parser.next_spanned().unwrap();
+        assert_eq!(heading.event, Event::Start(Tag::Heading(1)));
+        assert_eq!(&text[heading.span.start..heading.span.end], "# Foo\n");
+        assert_eq!(heading.line(text), 4);
+    }
+
     mod parse_until {
         use super::*;
 
@@ -362,6 +627,105 @@ mod tests {
         }
     }
 
+    mod doc {
+        use super::*;
+
+        fn tags(text: &str) -> Vec<String> {
+            let doc = Doc::parse(text).unwrap();
+            doc.tags.iter().map(|t| t.as_str().to_string()).collect()
+        }
+
+        #[test]
+        fn tags_are_read_from_every_paragraph_after_the_title() {
+            let text = "# Plan trip\n\nA week in Rome, #someday.\n\n#area/home\n#travel\n\n## Goal\n\n#ignored\n";
+            assert_eq!(tags(text), ["someday", "area/home", "travel"]);
+        }
+
+        #[test]
+        fn tags_in_code_and_links_are_not_read() {
+            let text = "# Plan trip\n#in-progress `#code` [#link](x) *#emphasis* #123\n";
+            assert_eq!(tags(text), ["in-progress"]);
+        }
+
+        #[test]
+        fn parser_is_left_after_the_paragraphs() {
+            let mut doc = Doc::parse("# Plan trip\n\nIntro.\n\n#someday\n\n## Goal\n").unwrap();
+            assert_eq!(doc.parser.parse_heading(2).unwrap(), "Goal");
+        }
+    }
+
+    /// Markdown that other tools write, which should be read the same as the usual way of writing
+    /// it.
+    mod conformance {
+        use super::*;
+        use crate::project::Project;
+
+        fn headings(text: &str) -> Vec<(u32, String)> {
+            source_headings(text)
+                .into_iter()
+                .map(|h| (h.level, h.text))
+                .collect()
+        }
+
+        #[test]
+        fn heading_variants_are_found() {
+            let cases: &[(&str, &[(u32, &str)])] = &[
+                ("# Title", &[(1, "Title")]),
+                ("Title\n=====\n", &[(1, "Title")]),
+                ("Goal\n---\n", &[(2, "Goal")]),
+                ("Long\ntitle\n===\n", &[(1, "Long title")]),
+                ("## Goal ##\n", &[(2, "Goal")]),
+                ("## Goal #####   \n", &[(2, "Goal")]),
+                ("   ## Goal\n", &[(2, "Goal")]),
+                ("## C#\n", &[(2, "C#")]),
+                ("## Goal \\#\n", &[(2, "Goal \\#")]),
+                ("## #\n", &[(2, "")]),
+                ("##\n", &[(2, "")]),
+                ("#in-progress\n", &[]),
+                ("    ## Indented code\n", &[]),
+                ("```\n## Fenced code\n```\n", &[]),
+                ("- ## In a list\n", &[]),
+                ("> ## In a quote\n", &[]),
+                ("- Item\n---\n", &[]),
+                ("---\nkey: value\n---\n# Title\n", &[(1, "Title")]),
+            ];
+            for (text, expected) in cases {
+                let expected = expected
+                    .iter()
+                    .map(|(l, t)| (*l, t.to_string()))
+                    .collect::<Vec<_>>();
+                assert_eq!(headings(text), expected, "{:?}", text);
+            }
+        }
+
+        #[test]
+        fn headings_are_normalized() {
+            let text = "---\nkey: value\n---\nPlan trip\n=========\n#in-progress\n\n## Goal ##\n\n```\nNot\n---\n```\n\nActions\n-------\n\n### Active\n";
+            assert_eq!(
+                normalize_headings(text),
+                "---\nkey: value\n---\n# Plan trip\n#in-progress\n\n## Goal\n\n```\nNot\n---\n```\n\n## Actions\n\n### Active\n"
+            );
+        }
+
+        #[test]
+        fn projects_are_read_the_same_however_headings_are_written() {
+            let usual = "# Plan trip\n#in-progress\n\n## Goal\n\nGo.\n\n## Actions\n\n### Active\n\n- Book flights ^abcdef\n";
+            let variants = [
+                "Plan trip\n=========\n#in-progress\n\nGoal\n----\n\nGo.\n\nActions\n-------\n\n### Active\n\n- Book flights ^abcdef\n",
+                "# Plan trip #\n#in-progress\n\n## Goal ##\n\nGo.\n\n## Actions ##\n\n### Active ###\n\n- Book flights ^abcdef\n",
+            ];
+            let name = "197001010000 Plan trip";
+            let expected = Project::parse(name, usual).unwrap();
+            for text in variants {
+                let project = Project::parse(name, text).unwrap();
+                assert_eq!(project.goal, expected.goal, "{:?}", text);
+                assert_eq!(project.title, expected.title, "{:?}", text);
+                let actions = project.actions.actions().map(|(a, _)| a.text.to_string());
+                assert_eq!(actions.collect::<Vec<_>>(), ["Book flights"], "{:?}", text);
+            }
+        }
+    }
+
     mod parse_heading {
         use super::*;
 
@@ -455,6 +819,27 @@ mod tests {
         }
     }
 
+    mod spanned {
+        use super::*;
+
+        #[test]
+        fn list_item_spans_are_found() {
+            let text = "- one\n- two\n";
+            let mut parser = Parser::new(text);
+            let items = parser.parse_spanned_list_opt().unwrap();
+            let spans = items.into_iter().map(|(_, s)| s).collect::<Vec<_>>();
+            assert_eq!(spans, vec![Span::new(0, 6), Span::new(6, 12)]);
+        }
+
+        #[test]
+        fn offset_at_end_is_text_length() {
+            let text = "foo";
+            let mut parser = Parser::new(text);
+            let _ = parser.by_ref().count();
+            assert_eq!(parser.offset(), 3);
+        }
+    }
+
     mod parse_tags {
         use super::*;
 
@@ -463,7 +848,21 @@ mod tests {
             let text = "#foo #bar";
             let mut parser = Parser::new(text);
             let tags = parser.parse_tags();
-            assert_eq!(tags, Ok(vec!["foo".into(), "bar".into()]),);
+            assert_eq!(
+                tags,
+                Ok(vec![
+                    tag::Tag::new("foo").unwrap(),
+                    tag::Tag::new("bar").unwrap()
+                ]),
+            );
+        }
+
+        #[test]
+        fn nested_tags_are_parsed() {
+            let text = "#area/work";
+            let mut parser = Parser::new(text);
+            let tags = parser.parse_tags();
+            assert_eq!(tags, Ok(vec![tag::Tag::new("area/work").unwrap()]));
         }
     }
 }
@@ -0,0 +1,182 @@
+//! Lengths of time written like `3d`, `2w`, or `1m`.
+
+use chrono::{Duration, Months, NaiveDate};
+use serde::Deserialize;
+use std::{convert::TryFrom, error::Error, fmt, str::FromStr};
+
+/// A number of days, weeks, or months.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Period {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+}
+
+/// The longest period that can be written, in years.
+const MAX_YEARS: u32 = 100;
+
+impl Period {
+    /// The date this period after `date`, or the last date there is if that's too far away.
+    pub fn after(&self, date: NaiveDate) -> NaiveDate {
+        self.checked_after(date).unwrap_or(NaiveDate::MAX)
+    }
+
+    /// The date this period before `date`, or the first date there is if that's too far away.
+    pub fn before(&self, date: NaiveDate) -> NaiveDate {
+        self.checked_before(date).unwrap_or(NaiveDate::MIN)
+    }
+
+    /// The date this period after `date`, if there is one.
+    pub fn checked_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            Self::Days(n) => date.checked_add_signed(Duration::days(n.into())),
+            Self::Weeks(n) => date.checked_add_signed(Duration::weeks(n.into())),
+            Self::Months(n) => date.checked_add_months(Months::new(n)),
+        }
+    }
+
+    /// The date this period before `date`, if there is one.
+    pub fn checked_before(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match *self {
+            Self::Days(n) => date.checked_sub_signed(Duration::days(n.into())),
+            Self::Weeks(n) => date.checked_sub_signed(Duration::weeks(n.into())),
+            Self::Months(n) => date.checked_sub_months(Months::new(n)),
+        }
+    }
+
+    fn is_in_range(&self) -> bool {
+        match *self {
+            Self::Days(n) => n <= MAX_YEARS * 366,
+            Self::Weeks(n) => n <= MAX_YEARS * 53,
+            Self::Months(n) => n <= MAX_YEARS * 12,
+        }
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Days(n) => write!(f, "{}d", n),
+            Self::Weeks(n) => write!(f, "{}w", n),
+            Self::Months(n) => write!(f, "{}m", n),
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = InvalidPeriodError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || InvalidPeriodError::Malformed(s.to_string());
+        let unit = s.chars().last().ok_or_else(err)?;
+        let n = s[..s.len() - unit.len_utf8()]
+            .parse::<u32>()
+            .map_err(|_| err())?;
+        let period = match unit {
+            'd' => Self::Days(n),
+            'w' => Self::Weeks(n),
+            'm' => Self::Months(n),
+            _ => return Err(err()),
+        };
+        match period.is_in_range() {
+            true => Ok(period),
+            false => Err(InvalidPeriodError::TooLong(period)),
+        }
+    }
+}
+
+impl TryFrom<String> for Period {
+    type Error = InvalidPeriodError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvalidPeriodError {
+    Malformed(String),
+    TooLong(Period),
+}
+
+impl fmt::Display for InvalidPeriodError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Malformed(s) => write!(
+                f,
+                "\"{}\" is not a valid period, like \"3d\", \"2w\", or \"1m\"",
+                s
+            ),
+            Self::TooLong(period) => write!(
+                f,
+                "\"{}\" is longer than {} years, the longest period there can be",
+                period, MAX_YEARS
+            ),
+        }
+    }
+}
+
+impl Error for InvalidPeriodError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn units_are_parsed() {
+        assert_eq!("3d".parse(), Ok(Period::Days(3)));
+        assert_eq!("2w".parse(), Ok(Period::Weeks(2)));
+        assert_eq!("1m".parse(), Ok(Period::Months(1)));
+    }
+
+    #[test]
+    fn missing_number_is_invalid() {
+        assert!("d".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn unknown_unit_is_invalid() {
+        assert!("3y".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn periods_that_are_too_long_are_invalid() {
+        assert_eq!("36600d".parse(), Ok(Period::Days(36600)));
+        assert_eq!(
+            "999999999d".parse::<Period>(),
+            Err(InvalidPeriodError::TooLong(Period::Days(999999999)))
+        );
+        assert!("9999999w".parse::<Period>().is_err());
+        assert!("1201m".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn dates_out_of_range_are_none() {
+        let period = Period::Days(u32::MAX);
+        assert_eq!(period.checked_after(date(2024, 1, 1)), None);
+        assert_eq!(period.checked_before(date(2024, 1, 1)), None);
+        assert_eq!(period.after(date(2024, 1, 1)), NaiveDate::MAX);
+        assert_eq!(
+            Period::Weeks(u32::MAX).before(date(2024, 1, 1)),
+            NaiveDate::MIN
+        );
+    }
+
+    #[test]
+    fn months_are_added_by_calendar() {
+        assert_eq!(
+            Period::Months(1).after(date(2024, 1, 31)),
+            date(2024, 2, 29)
+        );
+    }
+
+    #[test]
+    fn weeks_are_subtracted() {
+        assert_eq!(Period::Weeks(2).before(date(2024, 1, 15)), date(2024, 1, 1));
+    }
+}
@@ -0,0 +1,178 @@
+//! Planning a day's work from the time estimates on actions.
+//!
+//! Actions are packed into the time available a round at a time, taking the next action that fits
+//! from each in-progress project in turn, so that one big project doesn't crowd out the rest.
+
+use crate::{
+    estimate::Estimate,
+    gtd::Documents,
+    project::{Action, ActionStatus, Priority, Project, Status},
+};
+use chrono::NaiveDate;
+use std::cmp::Reverse;
+
+/// An action picked for a plan, along with its project.
+#[derive(Debug, Clone, Copy)]
+pub struct Planned<'a> {
+    pub project: &'a Project,
+    pub action: &'a Action,
+    pub estimate: Estimate,
+}
+
+/// The actions picked to fit in a day.
+#[derive(Debug)]
+pub struct Plan<'a> {
+    pub date: NaiveDate,
+    pub capacity: Estimate,
+    /// The picked actions, in the order they were picked.
+    pub actions: Vec<Planned<'a>>,
+    /// The number of actions that could have been picked, but don't have estimates.
+    pub unestimated: usize,
+}
+
+impl Plan<'_> {
+    /// The total of the estimates of the picked actions.
+    pub fn total(&self) -> Estimate {
+        Estimate::from_minutes(self.actions.iter().map(|p| p.estimate.minutes()).sum())
+    }
+}
+
+/// Picks active actions from in-progress projects whose estimates fit in `capacity` on `date`.
+///
+/// Each project's actions are considered soonest due first, then highest priority first, then in
+/// the order they're listed. Projects take turns, in the same order by their first action.
+pub fn plan(docs: &Documents, capacity: Estimate, date: NaiveDate) -> Plan<'_> {
+    let mut unestimated = 0;
+    let mut queues = docs
+        .projects()
+        .filter(|p| p.status == Status::InProgress)
+        .map(|project| {
+            let mut queue = project
+                .actions
+                .actions()
+                .filter(|(a, s)| *s == ActionStatus::Active && !a.is_deferred(date))
+                .filter_map(|(action, _)| match action.estimate {
+                    Some(estimate) => Some(Planned {
+                        project,
+                        action,
+                        estimate,
+                    }),
+                    None => {
+                        unestimated += 1;
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            queue.sort_by_key(|p| urgency(p.action));
+            queue
+        })
+        .filter(|q| !q.is_empty())
+        .collect::<Vec<_>>();
+    queues.sort_by(|a, b| {
+        (urgency(a[0].action), &a[0].project.name).cmp(&(urgency(b[0].action), &b[0].project.name))
+    });
+
+    let mut remaining = capacity.minutes();
+    let mut actions = Vec::new();
+    loop {
+        let mut is_picked = false;
+        for queue in queues.iter_mut() {
+            if let Some(i) = queue.iter().position(|p| p.estimate.minutes() <= remaining) {
+                let planned = queue.remove(i);
+                remaining -= planned.estimate.minutes();
+                actions.push(planned);
+                is_picked = true;
+            }
+        }
+        if !is_picked {
+            break;
+        }
+    }
+
+    Plan {
+        date,
+        capacity,
+        actions,
+        unestimated,
+    }
+}
+
+/// The key that orders actions from most to least urgent: soonest due first, with actions that
+/// aren't due last, and then highest priority first.
+fn urgency(action: &Action) -> (bool, Option<NaiveDate>, Reverse<Priority>) {
+    (action.due.is_none(), action.due, Reverse(action.priority))
+}
+
+/// Renders `plan` as a Markdown document, with the picked actions grouped by project.
+pub fn render(plan: &Plan) -> String {
+    let mut out = format!(
+        "# Plan, {}\n\nPlanned {} of {}.\n",
+        plan.date.format("%Y-%m-%d"),
+        plan.total(),
+        plan.capacity
+    );
+
+    let mut projects = Vec::<&Project>::new();
+    for planned in plan.actions.iter() {
+        if !projects.iter().any(|p| p.name == planned.project.name) {
+            projects.push(planned.project);
+        }
+    }
+    for project in projects {
+        out.push_str(&format!(
+            "\n## [[{}|{}]]\n\n",
+            project.name,
+            project.title()
+        ));
+        for planned in plan.actions.iter() {
+            if planned.project.name == project.name {
+                out.push_str(&format!(
+                    "- {} ({})\n",
+                    planned.action.text, planned.estimate
+                ));
+            }
+        }
+    }
+
+    if plan.unestimated > 0 {
+        out.push_str(&format!(
+            "\nLeft out {} active actions without an `@estimate`.\n",
+            plan.unestimated
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+    use std::fs;
+
+    #[test]
+    fn projects_take_turns_within_capacity() {
+        let dir = TempDir::new("plan");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack bags @estimate(1h)\n- Book flights @estimate(30m) 📅 2024-01-06\n- Renew passport @estimate(2h)\n- Buy guidebook\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Projects/202401020000 Do taxes.md"),
+            "# Do taxes\n#in-progress\n\n## Actions\n\n### Active\n\n- Find receipts @estimate(1h) ⏫\n",
+        )
+        .unwrap();
+
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let plan = plan(&docs, Estimate::from_minutes(150), date);
+
+        assert_eq!(
+            render(&plan),
+            "# Plan, 2024-01-05\n\nPlanned 2h30m of 2h30m.\n\n## [[202401010000 Plan trip|Plan trip]]\n\n- Book flights (30m)\n- Pack bags (1h)\n\n## [[202401020000 Do taxes|Do taxes]]\n\n- Find receipts (1h)\n\nLeft out 1 active actions without an `@estimate`.\n"
+        );
+    }
+}
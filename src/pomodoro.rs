@@ -0,0 +1,149 @@
+//! Pomodoro focus timers on actions, logged to the time log.
+
+use crate::{
+    config::PomodoroConfig,
+    edit,
+    gtd::{Documents, LoadProjectError},
+    prompt::confirm,
+    resolve::ResolveError,
+    timelog::{self, Session, Target, Timelog},
+};
+use chrono::{Duration, Local, NaiveDateTime};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Error as IoError, Write},
+    thread,
+};
+
+/// Runs a pomodoro on the action best matching `query`.
+///
+/// The countdown is shown in the terminal. When it finishes, the pomodoro is logged against the
+/// action, and once enough pomodoros have been spent on it the user is asked if the action is
+/// complete.
+pub fn pomodoro(
+    docs: &Documents,
+    config: &PomodoroConfig,
+    query: &str,
+    minutes: Option<u64>,
+) -> Result<(), PomodoroError> {
+    let (project, action) = docs.find_action(query)?;
+    let action_id = action.id.clone().ok_or(PomodoroError::NoActionId)?;
+    let target = Target {
+        project_name: project.name.to_string(),
+        action_id: Some(action_id.clone()),
+    };
+
+    let length = Duration::minutes(minutes.unwrap_or(config.minutes) as i64);
+    println!(
+        "Focusing on \"{}\" ({}) for {} minutes.",
+        action.text,
+        project.title(),
+        length.num_minutes()
+    );
+
+    let start = Local::now().naive_local();
+    countdown(start, length)?;
+    let end = Local::now().naive_local();
+
+    let root_dir = docs.loader().root_dir();
+    timelog::log_session(
+        root_dir,
+        &Session {
+            target: target.clone(),
+            start,
+            end: Some(end),
+            is_pomodoro: true,
+        },
+    )?;
+
+    let done = Timelog::load(root_dir)?.pomodoros(&target);
+    println!("\nPomodoro finished ({} on this action).", done);
+
+    if done >= config.sessions && confirm("Mark the action as complete?")? {
+        let today = end.date();
+        let completed = docs
+            .loader()
+            .update_project(&project.name, |text, project| {
+                let (action, _) = project.actions.get_action(&action_id)?;
+                edit::complete_action(text, project, action, today)
+            })?;
+        if completed {
+            println!("Completed \"{}\".", action.text);
+        }
+    }
+
+    Ok(())
+}
+
+/// Shows the time left until `length` after `start`, returning once it's over.
+fn countdown(start: NaiveDateTime, length: Duration) -> Result<(), IoError> {
+    let end = start + length;
+    let mut stdout = io::stdout();
+    loop {
+        let left = end - Local::now().naive_local();
+        if left <= Duration::zero() {
+            break;
+        }
+
+        write!(stdout, "\r{} left ", format_remaining(left))?;
+        stdout.flush()?;
+        thread::sleep(std::time::Duration::from_secs(1));
+    }
+    Ok(())
+}
+
+/// Formats `duration` as minutes and seconds, like `24:59`.
+fn format_remaining(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[derive(Debug)]
+pub enum PomodoroError {
+    IoError(IoError),
+    ResolveError(ResolveError),
+    LoadProjectError(LoadProjectError),
+    NoActionId,
+}
+
+impl fmt::Display for PomodoroError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::ResolveError(e) => write!(f, "{}", e),
+            Self::LoadProjectError(e) => write!(f, "{}", e),
+            Self::NoActionId => write!(f, "action needs an ID to be timed"),
+        }
+    }
+}
+
+impl Error for PomodoroError {}
+
+impl From<IoError> for PomodoroError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<ResolveError> for PomodoroError {
+    fn from(error: ResolveError) -> Self {
+        Self::ResolveError(error)
+    }
+}
+
+impl From<LoadProjectError> for PomodoroError {
+    fn from(error: LoadProjectError) -> Self {
+        Self::LoadProjectError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaining_time_is_minutes_and_seconds() {
+        assert_eq!(format_remaining(Duration::seconds(1499)), "24:59");
+    }
+}
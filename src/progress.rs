@@ -0,0 +1,154 @@
+//! Progress shown while loading and checking large GTD folders.
+//!
+//! Work is split into phases, like reading projects, and the phase that's underway is shown on
+//! one line of stderr with how much of it is done. Progress is only shown in a terminal, and only
+//! for phases that take long enough to notice, so it never ends up in piped output or flickers by
+//! on small folders.
+
+use crate::config::ProgressConfig;
+use std::{
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+/// How long a phase runs before its progress is shown.
+const DELAY: Duration = Duration::from_millis(200);
+
+/// How often the progress of a phase is redrawn.
+const INTERVAL: Duration = Duration::from_millis(50);
+
+/// How many characters wide the bar is.
+const WIDTH: usize = 24;
+
+/// The progress of some work. The default is never shown.
+#[derive(Debug, Default)]
+pub struct Progress {
+    is_shown: bool,
+    phase: Option<Phase>,
+}
+
+#[derive(Debug)]
+struct Phase {
+    name: &'static str,
+    done: usize,
+    total: Option<usize>,
+    started: Instant,
+    drawn: Option<Instant>,
+}
+
+impl Progress {
+    /// Progress that's shown if `config` allows it and stderr is a terminal.
+    pub fn new(config: &ProgressConfig) -> Self {
+        Self {
+            is_shown: config.show && io::stderr().is_terminal(),
+            phase: None,
+        }
+    }
+
+    /// Starts the phase `name`, like `Reading projects`, finishing the one before it. `total` is
+    /// how many things it'll do, if that's known up front.
+    pub fn start(&mut self, name: &'static str, total: Option<usize>) {
+        self.finish();
+        self.phase = Some(Phase {
+            name,
+            done: 0,
+            total,
+            started: Instant::now(),
+            drawn: None,
+        });
+    }
+
+    /// Counts one more thing done in the current phase.
+    pub fn advance(&mut self) {
+        let phase = match &mut self.phase {
+            Some(phase) => phase,
+            None => return,
+        };
+        phase.done += 1;
+        if !self.is_shown {
+            return;
+        }
+        let now = Instant::now();
+        let is_due = match phase.drawn {
+            Some(drawn) => now - drawn >= INTERVAL,
+            None => now - phase.started >= DELAY,
+        };
+        if is_due {
+            phase.drawn = Some(now);
+            let mut stderr = io::stderr().lock();
+            let _ = write!(
+                stderr,
+                "\r\x1b[2K{}",
+                render(phase.name, phase.done, phase.total)
+            );
+            let _ = stderr.flush();
+        }
+    }
+
+    /// Finishes the current phase, clearing its progress.
+    pub fn finish(&mut self) {
+        if let Some(Phase { drawn: Some(_), .. }) = self.phase.take() {
+            let mut stderr = io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// The progress of the phase `name`, with `done` of `total` things done, like
+/// `Reading projects [######                  ] 250/1000`.
+pub fn render(name: &str, done: usize, total: Option<usize>) -> String {
+    match total {
+        Some(total) if total > 0 => {
+            let filled = WIDTH * done.min(total) / total;
+            format!(
+                "{} [{}{}] {}/{}",
+                name,
+                "#".repeat(filled),
+                " ".repeat(WIDTH - filled),
+                done,
+                total
+            )
+        }
+        _ => format!("{} {}", name, done),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_rendered_with_counts() {
+        assert_eq!(
+            render("Reading projects", 250, Some(1000)),
+            "Reading projects [######                  ] 250/1000"
+        );
+        assert_eq!(
+            render("Reading projects", 1000, Some(1000)),
+            format!("Reading projects [{}] 1000/1000", "#".repeat(WIDTH))
+        );
+        assert_eq!(
+            render("Checking projects", 12, None),
+            "Checking projects 12"
+        );
+    }
+
+    #[test]
+    fn hidden_progress_still_counts() {
+        let mut progress = Progress::default();
+        progress.advance();
+        progress.start("Reading projects", Some(2));
+        progress.advance();
+        progress.advance();
+        assert_eq!(progress.phase.as_ref().map(|p| p.done), Some(2));
+        progress.finish();
+        assert!(progress.phase.is_none());
+    }
+}
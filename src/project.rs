@@ -1,31 +1,168 @@
 use crate::{
-    markdown::{BlockRef, Fragment, Heading},
-    parser::{self, Doc, Parser},
+    estimate::Estimate,
+    frontmatter::Frontmatter,
+    markdown::{BlockRef, Footnote, Fragment, Heading, Span},
+    parser::{self, Doc, HeadingDepths, Parser},
+    tag,
+};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, NaiveDate, NaiveDateTime, TimeZone,
 };
 use pulldown_cmark::{CowStr, Event, Tag};
-use std::{convert::TryFrom, error::Error, fmt};
+use regex::Regex;
+use serde::Deserialize;
+use std::{convert::TryFrom, error::Error, fmt, str::FromStr, sync::OnceLock};
 
 const SOMEDAY_TAG: &str = "someday";
 const IN_PROGRESS_TAG: &str = "in-progress";
 const COMPLETE_TAG: &str = "complete";
 
+pub const DEFAULT_NAME_PATTERN: &str = r"^(?P<id>\d{12}) (?P<title>.+)$";
+pub const DEFAULT_ID_FORMAT: &str = "%Y%m%d%H%M";
+pub const DEFAULT_NAME_TEMPLATE: &str = "{id} {title}";
+
+/// The marker before the date an action was completed, as used by the Obsidian Tasks plugin.
+pub const DONE_MARKER: &str = "✅";
+
+/// The marker before the date an action is due, as used by the Obsidian Tasks plugin.
+pub const DUE_MARKER: &str = "📅";
+
+/// The marker before the date an action is deferred until, as used by the Obsidian Tasks plugin for
+/// start dates.
+pub const DEFER_MARKER: &str = "🛫";
+
+/// The marker before the date an action was created, as used by the Obsidian Tasks plugin.
+pub const CREATED_MARKER: &str = "➕";
+
+/// How important an action is, from the priority markers used by the Obsidian Tasks plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    Lowest,
+    Low,
+    #[default]
+    Normal,
+    Medium,
+    High,
+    Highest,
+}
+
+impl Priority {
+    const MARKERS: [(&'static str, Self); 5] = [
+        ("🔺", Self::Highest),
+        ("⏫", Self::High),
+        ("🔼", Self::Medium),
+        ("🔽", Self::Low),
+        ("⏬", Self::Lowest),
+    ];
+
+    /// The marker for this priority, or `None` for normal priority, which doesn't have one.
+    pub fn marker(&self) -> Option<&'static str> {
+        Self::MARKERS
+            .iter()
+            .find(|(_, p)| p == self)
+            .map(|(marker, _)| *marker)
+    }
+}
+
+/// The annotation naming who a waiting action is waiting on, like `@who(Alice)`.
+const WHO_ANNOTATION: &str = "who";
+
+/// The annotation holding the ID of the copy of an action in a sync backend, like `@uid(abc123)`.
+pub const UID_ANNOTATION: &str = "uid";
+
+/// The annotation linking an action to the issue it's tracked in, like `@issue(owner/repo#123)`.
+pub const ISSUE_ANNOTATION: &str = "issue";
+
+/// The annotation recording where an action was moved from and when, like
+/// `@moved(2024-01-05 from Plan trip)`.
+pub const MOVED_ANNOTATION: &str = "moved";
+
+/// The annotation holding how long an action will take, like `@estimate(1h30m)`.
+const ESTIMATE_ANNOTATION: &str = "estimate";
+
+/// The annotation holding the date an action was promoted to active, like `@active(2024-01-05)`.
+pub const ACTIVE_ANNOTATION: &str = "active";
+
+/// A section of a project that can be left out, which `[validate]` can require.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Section {
+    Goal,
+    Info,
+}
+
+impl Section {
+    /// The text of the section's heading.
+    pub fn heading(&self) -> &'static str {
+        match self {
+            Self::Goal => "Goal",
+            Self::Info => "Info",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Project {
     pub name: Name,
+    pub frontmatter: Frontmatter,
     // TODO: Rename title.
     pub title: Heading,
-    pub tags: Vec<String>,
+    pub tags: Vec<tag::Tag>,
     pub status: Status,
     pub goal: Option<Fragment>,
     pub info: Option<Fragment>,
+    pub outcome: Option<Outcome>,
     pub actions: Actions,
+    /// The footnote definitions anywhere in the project, in the order they're written. They're
+    /// taken out of the sections they're written in.
+    pub footnotes: Vec<Footnote>,
+    /// Whether the actions are under the deprecated "Action Items" heading instead of "Actions".
+    pub has_action_items_section: bool,
+}
+
+/// What came of a finished project, from its `## Outcome` section, like `Saw Rome. ✅ 2024-01-10`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub note: Fragment,
+    /// The date the project was completed, from a `✅ YYYY-MM-DD` marker.
+    pub completed: Option<NaiveDate>,
+}
+
+impl Outcome {
+    fn from_fragment(fragment: Fragment) -> Self {
+        let (evs, completed) = split_date(fragment.into_events(), DONE_MARKER);
+        Self {
+            note: Fragment::from_events(evs),
+            completed,
+        }
+    }
 }
 
 impl Project {
-    pub fn parse<S: Into<String>>(filename: S, text: &str) -> Result<Self, ParseError> {
-        let name = Name::new(filename.into()).ok_or(ParseError::InvalidProjectName)?;
+    pub fn parse<S: Into<String>>(filename: S, text: &str) -> Result<Self, ParseError<'_>> {
+        Self::parse_with_scheme(filename, text, NameScheme::default_ref())
+    }
+
+    pub fn parse_with_scheme<'a, S: Into<String>>(
+        filename: S,
+        text: &'a str,
+        scheme: &NameScheme,
+    ) -> Result<Self, ParseError<'a>> {
+        Self::parse_with_depths(filename, text, scheme, HeadingDepths::default())
+    }
+
+    /// Parses a project whose sections and action statuses are at `depths`.
+    pub fn parse_with_depths<'a, S: Into<String>>(
+        filename: S,
+        text: &'a str,
+        scheme: &NameScheme,
+        depths: HeadingDepths,
+    ) -> Result<Self, ParseError<'a>> {
+        let name =
+            Name::with_scheme(filename.into(), scheme).ok_or(ParseError::InvalidProjectName)?;
 
         let Doc {
+            frontmatter,
             title,
             mut tags,
             mut parser,
@@ -41,47 +178,127 @@ impl Project {
 
         let mut goal = None;
         let mut info = None;
+        let mut outcome = None;
         let mut actions = None;
-
-        while parser.peek().is_some() {
-            let section_heading = parser.parse_heading(2).map_err(ParseError::ParseError)?;
+        let mut footnotes = Vec::new();
+        let mut has_action_items_section = false;
+        let level = depths.project_sections;
+
+        loop {
+            footnotes.extend(parser.parse_footnotes());
+            if parser.peek().is_none() {
+                break;
+            }
+            let section_heading = parser
+                .parse_heading(level)
+                .map_err(ParseError::ParseError)?;
             let section_title = section_heading
                 .try_to_text()
                 .ok_or_else(|| ParseError::HasSectionWithNonStringTitle(section_heading.clone()))?;
 
-            match &*section_title {
-                "Goal" => goal = Some(parser.parse_until(Event::Start(Tag::Heading(2)))),
-                "Info" => info = Some(parser.parse_until(Event::Start(Tag::Heading(2)))),
-                "Actions" => actions = Actions::parse(&mut parser).ok(),
-                "Action Items" => {
-                    let title_string = title.try_to_title_string().unwrap();
-                    println!("Warning: Project \"{}\" uses deprecated \"Action Items\" section; rename to \"Actions\".", title_string);
-                    actions = Actions::parse(&mut parser).ok();
+            let status = ActionStatus::from_heading(section_title);
+            let mut parse_section = |parser: &mut Parser<'a>| {
+                let fragment = parser.parse_until(Event::Start(Tag::Heading(level)));
+                footnotes.extend(fragment.footnotes());
+                fragment.without_footnotes()
+            };
+            match section_title {
+                "Goal" => goal = Some(parse_section(&mut parser)),
+                "Info" => info = Some(parse_section(&mut parser)),
+                "Outcome" => outcome = Some(Outcome::from_fragment(parse_section(&mut parser))),
+                "Actions" if !depths.has_status_sections() => {
+                    actions =
+                        Actions::parse(&mut parser, depths.action_statuses, &mut footnotes).ok()
                 }
-                _ => {
-                    return Err(ParseError::HasUnexpectedSection(section_heading));
+                "Action Items" if !depths.has_status_sections() => {
+                    has_action_items_section = true;
+                    actions =
+                        Actions::parse(&mut parser, depths.action_statuses, &mut footnotes).ok();
                 }
+                _ => match status.filter(|_| depths.has_status_sections()) {
+                    Some(status) => {
+                        let list = Actions::parse_list(&mut parser)?;
+                        actions
+                            .get_or_insert_with(Actions::default)
+                            .set(status, list);
+                    }
+                    None => return Err(ParseError::HasUnexpectedSection(section_heading)),
+                },
             }
         }
 
         Ok(Self {
             name,
+            frontmatter,
             title,
             tags,
             status,
             goal,
             info,
+            outcome,
             actions: actions.unwrap_or_else(Actions::default),
+            footnotes,
+            has_action_items_section,
         })
     }
 
-    pub fn id(&self) -> &str {
+    /// Generates the text of a new project titled `title`.
+    pub fn template(title: &str) -> String {
+        format!(
+            "# {}\n#{}\n\n## Goal\n\n## Actions\n\n### Active\n\n",
+            title, IN_PROGRESS_TAG
+        )
+    }
+
+    pub fn id(&self) -> Option<&str> {
         self.name.id()
     }
 
+    /// Checks if the project has `tag`, or a tag nested under it.
+    pub fn has_tag(&self, tag: &tag::Tag) -> bool {
+        self.tags.iter().any(|t| t.is_under(tag))
+    }
+
+    /// Checks if the project has `section`, even if it's empty.
+    pub fn has_section(&self, section: Section) -> bool {
+        match section {
+            Section::Goal => self.goal.is_some(),
+            Section::Info => self.info.is_some(),
+        }
+    }
+
     pub fn title(&self) -> &str {
         self.name.title()
     }
+
+    /// A hash of what the project says: its heading, goal, info, and the content hashes of its
+    /// actions, regardless of their order. It doesn't change with the project's name, tags, or
+    /// status, so a copy of a project file has the same hash as the original.
+    ///
+    /// The hash is the same from run to run and version to version, so it can be stored.
+    pub fn content_hash(&self) -> u64 {
+        let mut actions = self
+            .actions
+            .actions()
+            .map(|(a, _)| a.content_hash())
+            .collect::<Vec<_>>();
+        actions.sort_unstable();
+        let mut text = self.title.to_string().to_lowercase();
+        for section in [&self.goal, &self.info] {
+            text.push('\0');
+            text.push_str(&section.as_ref().map(|f| f.text()).unwrap_or_default());
+        }
+        for hash in actions {
+            text.push('\0');
+            text.push_str(&hash.to_string());
+        }
+        fnv1a(&text)
+    }
+
+    /// How many of the project's actions are complete.
+    pub fn progress(&self) -> Progress {
+        self.actions.progress()
+    }
 }
 
 impl fmt::Display for Project {
@@ -93,34 +310,34 @@ impl fmt::Display for Project {
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Name {
     name: String,
-    split_idx: usize,
+    id: Option<(usize, usize)>,
+    title: (usize, usize),
 }
 
 impl Name {
+    /// Creates a `Name` using the default naming scheme.
     pub fn new(name: String) -> Option<Self> {
-        let split_idx = name
-            .char_indices()
-            .find_map(|(i, c)| if c == ' ' { Some(i) } else { None })?;
-
-        // Validate the ID.
-        let id = &name[..split_idx];
-        if id.len() != 12 || id.chars().any(|c| !c.is_digit(10)) {
-            return None;
-        }
+        Self::with_scheme(name, NameScheme::default_ref())
+    }
 
-        Some(Self { name, split_idx })
+    /// Creates a `Name` using the given naming `scheme`.
+    pub fn with_scheme(name: String, scheme: &NameScheme) -> Option<Self> {
+        let captures = scheme.pattern.captures(&name)?;
+        let id = captures.name("id").map(|m| (m.start(), m.end()));
+        let title = captures.name("title").map(|m| (m.start(), m.end()))?;
+        Some(Self { name, id, title })
     }
 
     pub fn as_str(&self) -> &str {
         &self.name
     }
 
-    pub fn id(&self) -> &str {
-        &self.name[..self.split_idx]
+    pub fn id(&self) -> Option<&str> {
+        self.id.map(|(start, end)| &self.name[start..end])
     }
 
     pub fn title(&self) -> &str {
-        &self.name[self.split_idx + 1..]
+        &self.name[self.title.0..self.title.1]
     }
 }
 
@@ -130,6 +347,101 @@ impl fmt::Display for Name {
     }
 }
 
+/// The format project filenames follow.
+///
+/// A scheme is a regex with a required `title` capture and an optional `id` capture, along with
+/// the information needed to build new names: a `chrono` format string for generating IDs and a
+/// template where `{id}` and `{title}` are substituted.
+#[derive(Debug, Clone)]
+pub struct NameScheme {
+    pattern: Regex,
+    id_format: String,
+    template: String,
+}
+
+impl NameScheme {
+    pub fn new(pattern: &str, id_format: &str, template: &str) -> Result<Self, NameSchemeError> {
+        let pattern = Regex::new(pattern).map_err(NameSchemeError::InvalidPattern)?;
+        if !pattern.capture_names().any(|n| n == Some("title")) {
+            return Err(NameSchemeError::MissingTitleCapture);
+        }
+        // Formatting a time with an invalid format panics, so it's checked up front.
+        if StrftimeItems::new(id_format).any(|item| item == Item::Error) {
+            return Err(NameSchemeError::InvalidIdFormat(id_format.to_string()));
+        }
+
+        Ok(Self {
+            pattern,
+            id_format: id_format.to_string(),
+            template: template.to_string(),
+        })
+    }
+
+    /// Returns a shared instance of the default scheme.
+    pub fn default_ref() -> &'static Self {
+        static DEFAULT: OnceLock<NameScheme> = OnceLock::new();
+        DEFAULT.get_or_init(Self::default)
+    }
+
+    /// Generates an ID for a project created at `time`.
+    pub fn generate_id<Tz>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz: TimeZone,
+        Tz::Offset: fmt::Display,
+    {
+        time.format(&self.id_format).to_string()
+    }
+
+    /// The date a project with `id` was created, if the ID format includes one.
+    pub fn id_date(&self, id: &str) -> Option<NaiveDate> {
+        NaiveDateTime::parse_from_str(id, &self.id_format)
+            .map(|t| t.date())
+            .or_else(|_| NaiveDate::parse_from_str(id, &self.id_format))
+            .ok()
+    }
+
+    /// Builds a name out of an `id` and `title`, returning `None` if the result doesn't follow the
+    /// scheme.
+    pub fn format(&self, id: &str, title: &str) -> Option<Name> {
+        let name = self.template.replace("{id}", id).replace("{title}", title);
+        let name = Name::with_scheme(name, self)?;
+        if name.title() != title {
+            return None;
+        }
+        Some(name)
+    }
+}
+
+impl Default for NameScheme {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_NAME_PATTERN,
+            DEFAULT_ID_FORMAT,
+            DEFAULT_NAME_TEMPLATE,
+        )
+        .expect("default name scheme is invalid")
+    }
+}
+
+#[derive(Debug)]
+pub enum NameSchemeError {
+    InvalidPattern(regex::Error),
+    MissingTitleCapture,
+    InvalidIdFormat(String),
+}
+
+impl fmt::Display for NameSchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidPattern(e) => write!(f, "invalid name pattern: {}", e),
+            Self::MissingTitleCapture => write!(f, "name pattern has no `title` capture"),
+            Self::InvalidIdFormat(format) => write!(f, "invalid ID format: \"{}\"", format),
+        }
+    }
+}
+
+impl Error for NameSchemeError {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Status {
     Someday,
@@ -137,6 +449,27 @@ pub enum Status {
     Complete,
 }
 
+impl Status {
+    /// The tag that marks a project as having this status.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Self::Someday => SOMEDAY_TAG,
+            Self::InProgress => IN_PROGRESS_TAG,
+            Self::Complete => COMPLETE_TAG,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Someday => write!(f, "Someday"),
+            Self::InProgress => write!(f, "In progress"),
+            Self::Complete => write!(f, "Complete"),
+        }
+    }
+}
+
 impl TryFrom<&str> for Status {
     type Error = ();
 
@@ -150,59 +483,90 @@ impl TryFrom<&str> for Status {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s).map_err(|_| {
+            format!(
+                "\"{}\" is not a status, like \"{}\", \"{}\", or \"{}\"",
+                s, SOMEDAY_TAG, IN_PROGRESS_TAG, COMPLETE_TAG
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Actions {
     active: Vec<Action>,
+    waiting: Vec<Action>,
     upcoming: Vec<Action>,
     complete: Vec<Action>,
 }
 
 impl Actions {
-    fn parse<'a>(parser: &mut Parser<'a>) -> Result<Self, ParseError<'a>> {
-        let mut active = Vec::new();
-        let mut upcoming = Vec::new();
-        let mut complete = Vec::new();
-
-        while let Some(Event::Start(Tag::Heading(3))) = parser.peek() {
-            let section_heading = parser.parse_heading(3)?;
+    /// Parses the action statuses at heading `level`, up to the next heading that isn't one.
+    /// Footnote definitions between them are added to `footnotes`.
+    fn parse<'a>(
+        parser: &mut Parser<'a>,
+        level: u32,
+        footnotes: &mut Vec<Footnote>,
+    ) -> Result<Self, ParseError<'a>> {
+        let mut actions = Self::default();
+
+        footnotes.extend(parser.parse_footnotes());
+        while parser.peek() == Some(&Event::Start(Tag::Heading(level))) {
+            let section_heading = parser.parse_heading(level)?;
             let section_title = section_heading
                 .try_to_text()
                 .ok_or_else(|| ParseError::HasSectionWithNonStringTitle(section_heading.clone()))?;
 
-            let actions_type = match &*section_title {
-                "Active" => ActionStatus::Active,
-                "Upcoming" => ActionStatus::Upcoming,
-                "Complete" => ActionStatus::Complete,
-                _ => {
-                    return Err(ParseError::HasUnexpectedSection(section_heading));
-                }
-            };
+            let status = ActionStatus::from_heading(section_title)
+                .ok_or_else(|| ParseError::HasUnexpectedSection(section_heading.clone()))?;
+            actions.set(status, Self::parse_list(parser)?);
+            footnotes.extend(parser.parse_footnotes());
+        }
 
-            let actions = parser
-                .parse_list_opt()?
-                .into_iter()
-                .map(Action::from_fragment)
-                .collect();
+        Ok(actions)
+    }
 
-            match actions_type {
-                ActionStatus::Active => active = actions,
-                ActionStatus::Upcoming => upcoming = actions,
-                ActionStatus::Complete => complete = actions,
-            }
-        }
+    /// Parses the list of actions under a status heading.
+    fn parse_list<'a>(parser: &mut Parser<'a>) -> Result<Vec<Action>, ParseError<'a>> {
+        Ok(parser
+            .parse_spanned_list_opt()?
+            .into_iter()
+            .map(|(frag, span)| Action {
+                span,
+                ..Action::from_fragment(frag)
+            })
+            .collect())
+    }
 
-        Ok(Self {
-            active,
-            upcoming,
-            complete,
-        })
+    fn set(&mut self, status: ActionStatus, actions: Vec<Action>) {
+        match status {
+            ActionStatus::Active => self.active = actions,
+            ActionStatus::Waiting => self.waiting = actions,
+            ActionStatus::Upcoming => self.upcoming = actions,
+            ActionStatus::Complete => self.complete = actions,
+        }
     }
 
     pub fn actions(&self) -> impl Iterator<Item = (&Action, ActionStatus)> {
         let active = self.active.iter().map(|a| (a, ActionStatus::Active));
+        let waiting = self.waiting.iter().map(|a| (a, ActionStatus::Waiting));
         let upcoming = self.upcoming.iter().map(|a| (a, ActionStatus::Upcoming));
         let complete = self.complete.iter().map(|a| (a, ActionStatus::Complete));
-        active.chain(upcoming).chain(complete)
+        active.chain(waiting).chain(upcoming).chain(complete)
+    }
+
+    /// How many of the actions are complete.
+    pub fn progress(&self) -> Progress {
+        let total =
+            self.active.len() + self.waiting.len() + self.upcoming.len() + self.complete.len();
+        Progress {
+            complete: self.complete.len(),
+            total,
+        }
     }
 
     pub fn get_action(&self, id: &ActionId) -> Option<(&Action, ActionStatus)> {
@@ -211,26 +575,116 @@ impl Actions {
     }
 }
 
-impl Default for Actions {
-    fn default() -> Self {
-        Self {
-            active: Vec::new(),
-            upcoming: Vec::new(),
-            complete: Vec::new(),
+/// The number of complete actions out of all actions in a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub complete: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    /// The percentage of actions that are complete, rounded down, or `None` if there are no
+    /// actions.
+    pub fn percent(&self) -> Option<usize> {
+        (self.complete * 100).checked_div(self.total)
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.complete, self.total)?;
+        if let Some(percent) = self.percent() {
+            write!(f, " ({}%)", percent)?;
         }
+        Ok(())
     }
 }
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ActionStatus {
     Active,
+    /// Delegated, and waiting on someone else.
+    Waiting,
     Upcoming,
     Complete,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl ActionStatus {
+    /// The status that the heading `title` is for, like `Active`.
+    fn from_heading(title: &str) -> Option<Self> {
+        match title {
+            "Active" => Some(Self::Active),
+            "Waiting" => Some(Self::Waiting),
+            "Upcoming" => Some(Self::Upcoming),
+            "Complete" => Some(Self::Complete),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Action {
     pub text: Fragment,
     pub id: Option<ActionId>,
+    pub tags: Vec<tag::Tag>,
+
+    /// The date the action was completed, from a `✅ YYYY-MM-DD` marker.
+    pub completed: Option<NaiveDate>,
+
+    /// The date the action is due, from a `📅 YYYY-MM-DD` marker.
+    pub due: Option<NaiveDate>,
+
+    /// The date the action is deferred until, from a `🛫 YYYY-MM-DD` marker.
+    pub deferred: Option<NaiveDate>,
+
+    /// The date the action was created, from a `➕ YYYY-MM-DD` marker. For waiting actions, this is
+    /// when the waiting started.
+    pub created: Option<NaiveDate>,
+
+    /// Who the action is waiting on, from a `@who(Name)` annotation.
+    pub who: Option<String>,
+
+    /// The ID of the action in a sync backend, from a `@uid(ID)` annotation.
+    pub uid: Option<String>,
+
+    /// The issue the action is tracked in, from an `@issue(owner/repo#123)` annotation.
+    pub issue: Option<String>,
+
+    /// Where the action was last moved from and when, from a `@moved(2024-01-05 from Plan trip)`
+    /// annotation.
+    pub moved: Option<String>,
+
+    /// How important the action is, from a priority marker like `⏫`.
+    pub priority: Priority,
+
+    /// How long the action will take, from an `@estimate(1h30m)` annotation.
+    pub estimate: Option<Estimate>,
+
+    /// The date the action was promoted to active, from an `@active(YYYY-MM-DD)` annotation.
+    pub activated: Option<NaiveDate>,
+
+    /// Where the action is in the project's source text.
+    pub span: Span,
+}
+
+impl PartialEq for Action {
+    /// Compares the contents of two actions, ignoring where they are in their documents.
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+            && self.id == other.id
+            && self.tags == other.tags
+            && self.completed == other.completed
+            && self.due == other.due
+            && self.deferred == other.deferred
+            && self.created == other.created
+            && self.who == other.who
+            && self.uid == other.uid
+            && self.issue == other.issue
+            && self.moved == other.moved
+            && self.priority == other.priority
+            && self.estimate == other.estimate
+            && self.activated == other.activated
+    }
 }
 
 impl Action {
@@ -268,6 +722,19 @@ impl Action {
                 return Action {
                     text: Fragment::from_events(evs),
                     id: None,
+                    tags: Vec::new(),
+                    completed: None,
+                    due: None,
+                    deferred: None,
+                    created: None,
+                    who: None,
+                    uid: None,
+                    issue: None,
+                    moved: None,
+                    priority: Priority::Normal,
+                    estimate: None,
+                    activated: None,
+                    span: Span::default(),
                 }
             }
         };
@@ -285,11 +752,250 @@ impl Action {
             }
         };
 
+        let (evs, tags) = split_tags(evs);
+        let (evs, completed) = split_date(evs, DONE_MARKER);
+        let (evs, due) = split_date(evs, DUE_MARKER);
+        let (evs, deferred) = split_date(evs, DEFER_MARKER);
+        let (evs, created) = split_date(evs, CREATED_MARKER);
+        let (evs, who) = split_annotation(evs, WHO_ANNOTATION);
+        let (evs, uid) = split_annotation(evs, UID_ANNOTATION);
+        let (evs, issue) = split_annotation(evs, ISSUE_ANNOTATION);
+        let (evs, moved) = split_annotation(evs, MOVED_ANNOTATION);
+        let (evs, priority) = split_priority(evs);
+        let (evs, estimate) = split_annotation(evs, ESTIMATE_ANNOTATION);
+        let estimate = estimate.and_then(|e| e.parse().ok());
+        let (evs, activated) = split_annotation(evs, ACTIVE_ANNOTATION);
+        let activated = activated.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok());
+
         Action {
             text: Fragment::from_events(evs),
             id,
+            tags,
+            completed,
+            due,
+            deferred,
+            created,
+            who,
+            uid,
+            issue,
+            moved,
+            priority,
+            estimate,
+            activated,
+            span: Span::default(),
+        }
+    }
+
+    /// Checks if the action has `tag`, or a tag nested under it.
+    pub fn has_tag(&self, tag: &tag::Tag) -> bool {
+        self.tags.iter().any(|t| t.is_under(tag))
+    }
+
+    /// Checks if the action is deferred until after `today`.
+    pub fn is_deferred(&self, today: NaiveDate) -> bool {
+        self.deferred.is_some_and(|d| d > today)
+    }
+
+    /// Checks if the action is due on or before `today`.
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        self.due.is_some_and(|d| d <= today)
+    }
+
+    /// The date the action became active: when it was promoted, or else when it was created.
+    pub fn active_since(&self) -> Option<NaiveDate> {
+        self.activated.or(self.created)
+    }
+
+    /// What two actions need to have in common to be the same action: the text without
+    /// formatting, lowercased, with runs of whitespace collapsed.
+    pub fn normalized_text(&self) -> String {
+        self.text.text().to_lowercase()
+    }
+
+    /// A hash of the action's text that doesn't change with formatting, whitespace, or case, or
+    /// with the action's ID, tags, dates, and annotations.
+    ///
+    /// The hash is the same from run to run and version to version, so it can be stored.
+    pub fn content_hash(&self) -> u64 {
+        fnv1a(&self.normalized_text())
+    }
+
+    /// Checks if `other` is the same action, even if its text is formatted differently or it has
+    /// different metadata.
+    pub fn matches(&self, other: &Action) -> bool {
+        self.normalized_text() == other.normalized_text()
+    }
+}
+
+/// The 64-bit FNV-1a hash of `text`.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Removes all hashtags from the text events in `evs`, returning the remaining events and the tags.
+fn split_tags(evs: Vec<Event<'static>>) -> (Vec<Event<'static>>, Vec<tag::Tag>) {
+    let mut tags = Vec::new();
+    let mut rest = Vec::with_capacity(evs.len());
+
+    for ev in evs {
+        let text = match ev {
+            Event::Text(t) => t,
+            ev => {
+                rest.push(ev);
+                continue;
+            }
+        };
+
+        let num_tags = tags.len();
+        let mut words = Vec::<String>::new();
+        for word in text.split(' ') {
+            match tag::Tag::from_word(word) {
+                Some(tag) => {
+                    tags.push(tag);
+                    // Punctuation after a tag isn't part of it, so it stays with the word before.
+                    let end = word.trim_end_matches(tag::TRAILING_PUNCTUATION).len();
+                    match words.last_mut() {
+                        Some(last) => last.push_str(&word[end..]),
+                        None if end < word.len() => words.push(word[end..].to_string()),
+                        None => {}
+                    }
+                }
+                None => words.push(word.to_string()),
+            }
+        }
+
+        if tags.len() == num_tags {
+            rest.push(Event::Text(text));
+            continue;
+        }
+
+        let text = words.join(" ");
+        if !text.is_empty() {
+            rest.push(Event::Text(CowStr::Boxed(text.into_boxed_str())));
+        }
+    }
+
+    (rest, tags)
+}
+
+/// Removes a date marker like `✅ YYYY-MM-DD` from the text events in `evs`, returning the
+/// remaining events and the date.
+fn split_date(evs: Vec<Event<'static>>, marker: &str) -> (Vec<Event<'static>>, Option<NaiveDate>) {
+    let mut completed = None;
+    let mut rest = Vec::with_capacity(evs.len());
+
+    for ev in evs {
+        let text = match ev {
+            Event::Text(t) if completed.is_none() => t,
+            ev => {
+                rest.push(ev);
+                continue;
+            }
+        };
+
+        let found = text.find(marker).and_then(|idx| {
+            let after = &text[idx + marker.len()..];
+            let date_str = after.trim_start().get(..10)?;
+            let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok()?;
+            let end = text.len() - after.trim_start().len() + 10;
+            Some((idx, end, date))
+        });
+
+        let (start, end, date) = match found {
+            Some(m) => m,
+            None => {
+                rest.push(Event::Text(text));
+                continue;
+            }
+        };
+
+        completed = Some(date);
+        let text = format!("{}{}", text[..start].trim_end(), &text[end..]);
+        if !text.is_empty() {
+            rest.push(Event::Text(CowStr::Boxed(text.into_boxed_str())));
+        }
+    }
+
+    (rest, completed)
+}
+
+/// Removes a priority marker like `⏫` from the text events in `evs`, returning the remaining events
+/// and the priority.
+fn split_priority(evs: Vec<Event<'static>>) -> (Vec<Event<'static>>, Priority) {
+    let mut priority = None;
+    let mut rest = Vec::with_capacity(evs.len());
+
+    for ev in evs {
+        let text = match ev {
+            Event::Text(t) if priority.is_none() => t,
+            ev => {
+                rest.push(ev);
+                continue;
+            }
+        };
+
+        let found = Priority::MARKERS
+            .iter()
+            .find_map(|(marker, p)| Some((text.find(marker)?, marker.len(), *p)));
+        let (start, len, found_priority) = match found {
+            Some(m) => m,
+            None => {
+                rest.push(Event::Text(text));
+                continue;
+            }
+        };
+
+        priority = Some(found_priority);
+        let text = format!("{}{}", text[..start].trim_end(), &text[start + len..]);
+        if !text.is_empty() {
+            rest.push(Event::Text(CowStr::Boxed(text.into_boxed_str())));
         }
     }
+
+    (rest, priority.unwrap_or_default())
+}
+
+/// Removes an annotation like `@who(Name)` from the text events in `evs`, where `name` is the part
+/// before the parentheses, returning the remaining events and the annotation's value.
+fn split_annotation(evs: Vec<Event<'static>>, name: &str) -> (Vec<Event<'static>>, Option<String>) {
+    let annotation_start = format!("@{}(", name);
+    let mut value = None;
+    let mut rest = Vec::with_capacity(evs.len());
+
+    for ev in evs {
+        let text = match ev {
+            Event::Text(t) if value.is_none() => t,
+            ev => {
+                rest.push(ev);
+                continue;
+            }
+        };
+
+        let found = text.find(&annotation_start).and_then(|start| {
+            let value_start = start + annotation_start.len();
+            let value_end = value_start + text[value_start..].find(')')?;
+            let value = text[value_start..value_end].trim();
+            (!value.is_empty()).then(|| (start, value_end + 1, value.to_string()))
+        });
+
+        let (start, end, found_value) = match found {
+            Some(m) => m,
+            None => {
+                rest.push(Event::Text(text));
+                continue;
+            }
+        };
+
+        value = Some(found_value);
+        let text = format!("{}{}", text[..start].trim_end(), &text[end..]);
+        if !text.is_empty() {
+            rest.push(Event::Text(CowStr::Boxed(text.into_boxed_str())));
+        }
+    }
+
+    (rest, value)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -299,6 +1005,38 @@ impl ActionId {
     pub fn new(id: String) -> Self {
         Self(id)
     }
+
+    /// Makes a six-character block ID for an action with the text `text`, trying others until
+    /// `is_taken` says one is free. The same text and taken IDs always make the same ID.
+    pub fn generate<F: Fn(&ActionId) -> bool>(text: &str, is_taken: F) -> Self {
+        const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+        let mut seed = text.to_string();
+        loop {
+            let mut hash = fnv1a(&seed);
+            let id = (0..6)
+                .map(|_| {
+                    let digit = DIGITS[(hash % 36) as usize] as char;
+                    hash /= 36;
+                    digit
+                })
+                .collect::<String>();
+            let id = Self(id);
+            if !is_taken(&id) {
+                return id;
+            }
+            seed.push('\0');
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ActionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "^{}", self.0)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -308,8 +1046,8 @@ pub struct ActionRef {
 }
 
 impl ActionRef {
-    pub fn from_block_ref(block_ref: BlockRef) -> Option<Self> {
-        let project_name = Name::new(block_ref.link)?;
+    pub fn from_block_ref(block_ref: BlockRef, scheme: &NameScheme) -> Option<Self> {
+        let project_name = Name::with_scheme(block_ref.link, scheme)?;
         let action_id = ActionId(block_ref.id);
         Some(Self {
             project_name,
@@ -364,11 +1102,54 @@ impl<'a> From<parser::ParseError<'a>> for ParseError<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::convert::TryInto;
 
     mod action {
         use super::*;
 
+        fn action(text: &str) -> Action {
+            let project = Project::parse(
+                "197001010000 Project",
+                &format!(
+                    "# Project\n#in-progress\n\n## Actions\n\n### Active\n\n- {}\n",
+                    text
+                ),
+            )
+            .unwrap();
+            let action = project.actions.actions().next().unwrap().0.clone();
+            action
+        }
+
+        #[test]
+        fn formatting_and_metadata_dont_change_identity() {
+            let plain = action("Book flights");
+            let edited = action("Book  **Flights** #phone 📅 2024-01-05 ^abcdef");
+            assert!(plain.matches(&edited));
+            assert_eq!(plain.content_hash(), edited.content_hash());
+        }
+
+        #[test]
+        fn different_text_changes_identity() {
+            let flights = action("Book flights");
+            let hotel = action("Book hotel");
+            assert!(!flights.matches(&hotel));
+            assert_ne!(flights.content_hash(), hotel.content_hash());
+        }
+
+        #[test]
+        fn content_hash_is_stable() {
+            assert_eq!(action("a").content_hash(), 0xaf63_dc4c_8601_ec8c);
+            assert_eq!(action("_A_").content_hash(), 0xaf63_dc4c_8601_ec8c);
+        }
+
+        #[test]
+        fn generated_id_skips_taken_ids() {
+            let first = ActionId::generate("Pack bags", |_| false);
+            assert_eq!(first.as_str().len(), 6);
+            assert!(first.as_str().chars().all(|c| c.is_ascii_alphanumeric()));
+            assert_eq!(ActionId::generate("Pack bags", |_| false), first);
+            assert_ne!(ActionId::generate("Pack bags", |id| *id == first), first);
+        }
+
         #[test]
         fn text_action_with_id_has_correct_id() {
             let frag = Fragment::from_events(vec![Event::Text("action text ^abcdef".into())]);
@@ -403,6 +1184,209 @@ mod tests {
             );
         }
 
+        #[test]
+        fn tags_are_parsed() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "action #phone text #area/work ^abcdef".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(
+                action.tags,
+                vec![
+                    tag::Tag::new("phone").unwrap(),
+                    tag::Tag::new("area/work").unwrap()
+                ]
+            );
+        }
+
+        #[test]
+        fn tags_are_removed_from_text() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "#phone action #low-energy text ^abcdef".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("action text".into())])
+            );
+        }
+
+        #[test]
+        fn punctuation_after_tags_is_left_in_text() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "Call #phone, then email #work. ^abcdef".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(
+                action.tags,
+                vec![
+                    tag::Tag::new("phone").unwrap(),
+                    tag::Tag::new("work").unwrap()
+                ]
+            );
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("Call, then email.".into())])
+            );
+        }
+
+        #[test]
+        fn completion_date_is_parsed() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "action text ✅ 2024-01-05 ^abcdef".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.completed, NaiveDate::from_ymd_opt(2024, 1, 5));
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("action text".into())])
+            );
+        }
+
+        #[test]
+        fn due_and_defer_dates_are_parsed() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "action text 🛫 2024-01-01 📅 2024-01-05 ^abcdef".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.due, NaiveDate::from_ymd_opt(2024, 1, 5));
+            assert_eq!(action.deferred, NaiveDate::from_ymd_opt(2024, 1, 1));
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("action text".into())])
+            );
+        }
+
+        #[test]
+        fn priority_is_parsed() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "Book flights ⏫ 📅 2024-01-05 ^abcdef".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.priority, Priority::High);
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("Book flights".into())])
+            );
+        }
+
+        #[test]
+        fn estimate_is_parsed() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "Book flights @estimate(1h30m) ^abcdef".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.estimate, Some(Estimate::from_minutes(90)));
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("Book flights".into())])
+            );
+        }
+
+        #[test]
+        fn activation_date_is_parsed() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "Book flights @active(2024-01-05) ➕ 2024-01-02".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.activated, NaiveDate::from_ymd_opt(2024, 1, 5));
+            assert_eq!(action.active_since(), action.activated);
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("Book flights".into())])
+            );
+        }
+
+        #[test]
+        fn who_and_created_date_are_parsed() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "Send contract @who(Alice Smith) ➕ 2024-01-02 ^abcdef".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.who.as_deref(), Some("Alice Smith"));
+            assert_eq!(action.created, NaiveDate::from_ymd_opt(2024, 1, 2));
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("Send contract".into())])
+            );
+        }
+
+        #[test]
+        fn issue_is_parsed() {
+            let frag = Fragment::from_events(vec![Event::Text(
+                "Fix login @issue(kstrohbeck/gtd#12) #deep-work ^abcdef".into(),
+            )]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.issue.as_deref(), Some("kstrohbeck/gtd#12"));
+            assert_eq!(action.tags, [tag::Tag::new("#deep-work").unwrap()]);
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("Fix login".into())])
+            );
+        }
+
+        #[test]
+        fn empty_who_is_kept_in_text() {
+            let frag = Fragment::from_events(vec![Event::Text("Send contract @who()".into())]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.who, None);
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("Send contract @who()".into())])
+            );
+        }
+
+        #[test]
+        fn invalid_completion_date_is_kept_in_text() {
+            let frag = Fragment::from_events(vec![Event::Text("action text ✅ soon".into())]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.completed, None);
+            assert_eq!(
+                action.text,
+                Fragment::from_events(vec![Event::Text("action text ✅ soon".into())])
+            );
+        }
+
+        #[test]
+        fn tags_are_parsed_after_formatting() {
+            let frag = Fragment::from_events(vec![
+                Event::Text("Something with ".into()),
+                Event::Code("code".into()),
+                Event::Text(" #phone".into()),
+            ]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(
+                action,
+                Action {
+                    text: Fragment::from_events(vec![
+                        Event::Text("Something with ".into()),
+                        Event::Code("code".into()),
+                    ]),
+                    id: None,
+                    tags: vec![tag::Tag::new("phone").unwrap()],
+                    completed: None,
+                    due: None,
+                    deferred: None,
+                    created: None,
+                    who: None,
+                    uid: None,
+                    issue: None,
+                    moved: None,
+                    priority: Priority::Normal,
+                    estimate: None,
+                    activated: None,
+                    span: Span::default(),
+                }
+            );
+        }
+
+        #[test]
+        fn numeric_hash_is_not_tag() {
+            let frag = Fragment::from_events(vec![Event::Text("Fix issue #123".into())]);
+            let action = Action::from_fragment(frag);
+            assert_eq!(action.tags, vec![]);
+        }
+
         #[test]
         fn complex_action_with_id_has_correct_id() {
             let frag = Fragment::from_events(vec![
@@ -473,6 +1457,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn template_parses() {
+        let project_str = Project::template("Project title");
+        let project = Project::parse("197001010000 Project title", &project_str).unwrap();
+        assert_eq!(project.status, Status::InProgress);
+    }
+
+    #[test]
+    fn copied_project_has_the_same_content_hash() {
+        let text = "# Plan trip\n#in-progress #area/home\n\n## Goal\n\nSee Rome.\n\n## Actions\n\n### Active\n\n- Book flights ^abcdef\n- Pack bags\n";
+        let copy = "# Plan trip\n#someday\n\n## Goal\n\nSee Rome.\n\n## Actions\n\n### Upcoming\n\n- Pack bags\n- Book **flights**\n";
+        let original = Project::parse("202401010000 Plan trip", text).unwrap();
+        let copied = Project::parse("202401020000 Plan trip 1", copy).unwrap();
+        assert_eq!(original.content_hash(), copied.content_hash());
+
+        let other =
+            Project::parse("202401030000 Plan trip", &text.replace("Rome", "Paris")).unwrap();
+        assert_ne!(original.content_hash(), other.content_hash());
+    }
+
     #[test]
     fn basic_project_parses() {
         let project_str = "# Project title\n#in-progress\n";
@@ -486,9 +1490,10 @@ mod tests {
         let project = Project::parse("197001010000 Project title", project_str).unwrap();
         assert_eq!(
             project.title,
-            Fragment::from_events(vec![Event::Text("Project title".into())])
-                .try_into()
-                .unwrap()
+            Heading::try_from(Fragment::from_events(vec![Event::Text(
+                "Project title".into()
+            )]))
+            .unwrap()
         );
     }
 
@@ -498,25 +1503,43 @@ mod tests {
         let project = Project::parse("197001010000 Title with code", project_str).unwrap();
         assert_eq!(
             project.title,
-            Fragment::from_events(vec![
+            Heading::try_from(Fragment::from_events(vec![
                 Event::Text("Title with ".into()),
                 Event::Code("code".into()),
-            ])
-            .try_into()
+            ]))
             .unwrap()
         );
     }
 
+    #[test]
+    fn formatted_title_with_deprecated_section_parses() {
+        let project_str =
+            "# Plan _trip_\n#in-progress\n\n## Action Items\n\n### Active\n\n- Book flights\n";
+        let project = Project::parse("197001010000 Plan trip", project_str).unwrap();
+        assert_eq!(project.actions.actions().count(), 1);
+        assert!(project.has_action_items_section);
+    }
+
     #[test]
     fn tags_are_parsed() {
         let project_str = "# Project title\n#in-progress #other #tags\n";
         let project = Project::parse("197001010000 Project title", project_str).unwrap();
         assert_eq!(
             project.tags,
-            vec![String::from("other"), String::from("tags")]
+            vec![
+                tag::Tag::new("other").unwrap(),
+                tag::Tag::new("tags").unwrap()
+            ]
         );
     }
 
+    #[test]
+    fn nested_tag_matches_ancestor() {
+        let project_str = "# Project title\n#in-progress #area/work/clients\n";
+        let project = Project::parse("197001010000 Project title", project_str).unwrap();
+        assert!(project.has_tag(&tag::Tag::new("area/work").unwrap()));
+    }
+
     #[test]
     fn someday_status_is_parsed() {
         let project_str = "# Project title\n#someday\n";
@@ -542,7 +1565,9 @@ mod tests {
     fn status_is_not_in_tags() {
         let project_str = "# Project title\n#in-progress #other #tags\n";
         let project = Project::parse("197001010000 Project title", project_str).unwrap();
-        assert!(!project.tags.contains(&String::from("in-progress")));
+        assert!(!project
+            .tags
+            .contains(&tag::Tag::new("in-progress").unwrap()));
     }
 
     #[test]
@@ -594,6 +1619,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn footnotes_are_parsed_wherever_they_are() {
+        let project_str = "# Project title\n#in-progress\n\n## Goal\n\nFoo.[^1]\n\n[^1]: In the goal.\n\n## Actions\n\n### Active\n\n- First action[^2]\n\n[^2]: Between statuses.\n\n### Complete\n\n- Second action\n\n[^3]: At the end.\n";
+        let project = Project::parse("197001010000 Project title", project_str).unwrap();
+        let footnotes = project
+            .footnotes
+            .iter()
+            .map(|f| (f.label.as_str(), f.body.to_markdown()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            footnotes,
+            vec![
+                ("1", String::from("In the goal.")),
+                ("2", String::from("Between statuses.")),
+                ("3", String::from("At the end.")),
+            ]
+        );
+        assert_eq!(project.goal.unwrap().to_markdown(), "Foo.[^1]");
+        assert_eq!(project.actions.progress().total, 2);
+    }
+
     #[test]
     fn actions_are_parsed() {
         let project_str =
@@ -604,12 +1650,38 @@ mod tests {
             Actions {
                 active: vec![Action {
                     text: Fragment::from_events(vec![Event::Text("First action".into())]),
-                    id: None
+                    id: None,
+                    tags: vec![],
+                    completed: None,
+                    due: None,
+                    deferred: None,
+                    created: None,
+                    who: None,
+                    uid: None,
+                    issue: None,
+                    moved: None,
+                    priority: Priority::Normal,
+                    estimate: None,
+                    activated: None,
+                    span: Span::default(),
                 }],
                 upcoming: vec![
                     Action {
                         text: Fragment::from_events(vec![Event::Text("Second action".into())]),
                         id: Some(ActionId(String::from("abcdef"))),
+                        tags: vec![],
+                        completed: None,
+                        due: None,
+                        deferred: None,
+                        created: None,
+                        who: None,
+                        uid: None,
+                        issue: None,
+                        moved: None,
+                        priority: Priority::Normal,
+                        estimate: None,
+                        activated: None,
+                        span: Span::default(),
                     },
                     Action {
                         text: Fragment::from_events(vec![
@@ -617,8 +1689,22 @@ mod tests {
                             Event::Code("with code".into())
                         ]),
                         id: Some(ActionId(String::from("fedcba"))),
+                        tags: vec![],
+                        completed: None,
+                        due: None,
+                        deferred: None,
+                        created: None,
+                        who: None,
+                        uid: None,
+                        issue: None,
+                        moved: None,
+                        priority: Priority::Normal,
+                        estimate: None,
+                        activated: None,
+                        span: Span::default(),
                     }
                 ],
+                waiting: vec![],
                 complete: vec![],
             }
         );
@@ -653,12 +1739,38 @@ mod tests {
             Actions {
                 active: vec![Action {
                     text: Fragment::from_events(vec![Event::Text("First action".into())]),
-                    id: None
+                    id: None,
+                    tags: vec![],
+                    completed: None,
+                    due: None,
+                    deferred: None,
+                    created: None,
+                    who: None,
+                    uid: None,
+                    issue: None,
+                    moved: None,
+                    priority: Priority::Normal,
+                    estimate: None,
+                    activated: None,
+                    span: Span::default(),
                 }],
                 upcoming: vec![
                     Action {
                         text: Fragment::from_events(vec![Event::Text("Second action".into())]),
                         id: Some(ActionId(String::from("abcdef"))),
+                        tags: vec![],
+                        completed: None,
+                        due: None,
+                        deferred: None,
+                        created: None,
+                        who: None,
+                        uid: None,
+                        issue: None,
+                        moved: None,
+                        priority: Priority::Normal,
+                        estimate: None,
+                        activated: None,
+                        span: Span::default(),
                     },
                     Action {
                         text: Fragment::from_events(vec![
@@ -666,13 +1778,98 @@ mod tests {
                             Event::Code("with code".into())
                         ]),
                         id: Some(ActionId(String::from("fedcba"))),
+                        tags: vec![],
+                        completed: None,
+                        due: None,
+                        deferred: None,
+                        created: None,
+                        who: None,
+                        uid: None,
+                        issue: None,
+                        moved: None,
+                        priority: Priority::Normal,
+                        estimate: None,
+                        activated: None,
+                        span: Span::default(),
                     }
                 ],
+                waiting: vec![],
                 complete: vec![],
             }
         );
     }
 
+    #[test]
+    fn action_spans_are_parsed() {
+        let project_str = "# Project title\n#in-progress\n\n## Actions\n\n### Active\n\n- First action\n- Second action\n";
+        let project = Project::parse("197001010000 Project title", project_str).unwrap();
+        let lines = project
+            .actions
+            .actions()
+            .map(|(a, _)| a.span.start_line(project_str))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![8, 9]);
+    }
+
+    #[test]
+    fn frontmatter_is_parsed() {
+        let project_str = "---\nprogress: 50\n---\n# Project title\n#in-progress\n";
+        let project = Project::parse("197001010000 Project title", project_str).unwrap();
+        assert_eq!(project.frontmatter.get("progress"), Some("50"));
+        assert_eq!(project.status, Status::InProgress);
+    }
+
+    #[test]
+    fn action_spans_include_frontmatter() {
+        let project_str = "---\nprogress: 0\n---\n# Project title\n#in-progress\n\n## Actions\n\n### Active\n\n- First action\n";
+        let project = Project::parse("197001010000 Project title", project_str).unwrap();
+        let (action, _) = project.actions.actions().next().unwrap();
+        assert_eq!(action.span.start_line(project_str), 11);
+    }
+
+    #[test]
+    fn waiting_section_is_parsed() {
+        let project_str = "# Project title\n#in-progress\n\n## Actions\n\n### Active\n\n- First\n\n### Waiting\n\n- Second @who(Alice)\n";
+        let project = Project::parse("197001010000 Project title", project_str).unwrap();
+        let statuses = project
+            .actions
+            .actions()
+            .map(|(a, s)| (a.text.to_string(), s))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                (String::from("First"), ActionStatus::Active),
+                (String::from("Second"), ActionStatus::Waiting),
+            ]
+        );
+    }
+
+    #[test]
+    fn progress_counts_complete_actions() {
+        let project_str = "# Project title\n#in-progress\n\n## Actions\n\n### Active\n\n- First\n\n### Upcoming\n\n- Second\n\n### Complete\n\n- Third\n- Fourth\n";
+        let project = Project::parse("197001010000 Project title", project_str).unwrap();
+        let progress = project.progress();
+        assert_eq!(
+            progress,
+            Progress {
+                complete: 2,
+                total: 4
+            }
+        );
+        assert_eq!(progress.to_string(), "2/4 (50%)");
+    }
+
+    #[test]
+    fn progress_without_actions_has_no_percent() {
+        let progress = Progress {
+            complete: 0,
+            total: 0,
+        };
+        assert_eq!(progress.percent(), None);
+        assert_eq!(progress.to_string(), "0/0");
+    }
+
     #[test]
     fn empty_action_section_is_allowed() {
         let project_str = "# Project title\n#in-progress\n## Actions\n\n";
@@ -699,12 +1896,87 @@ mod tests {
                 upcoming: vec![Action {
                     text: Fragment::from_events(vec![Event::Text("foo".into())]),
                     id: None,
+                    tags: vec![],
+                    completed: None,
+                    due: None,
+                    deferred: None,
+                    created: None,
+                    who: None,
+                    uid: None,
+                    issue: None,
+                    moved: None,
+                    priority: Priority::Normal,
+                    estimate: None,
+                    activated: None,
+                    span: Span::default(),
                 }],
+                waiting: vec![],
                 complete: vec![],
             }
         );
     }
 
+    mod depths {
+        use super::*;
+
+        fn texts(project: &Project) -> Vec<(String, ActionStatus)> {
+            project
+                .actions
+                .actions()
+                .map(|(a, s)| (a.text.to_string(), s))
+                .collect()
+        }
+
+        #[test]
+        fn statuses_can_be_sections() {
+            let depths = HeadingDepths::new(2, 2, 2).unwrap();
+            let project_str = "# Project title\n#in-progress\n\n## Goal\n\nGo.\n\n## Active\n\n- One\n\n## Complete\n\n- Two\n";
+            let project = Project::parse_with_depths(
+                "197001010000 Project title",
+                project_str,
+                NameScheme::default_ref(),
+                depths,
+            )
+            .unwrap();
+
+            assert!(project.goal.is_some());
+            assert_eq!(
+                texts(&project),
+                [
+                    (String::from("One"), ActionStatus::Active),
+                    (String::from("Two"), ActionStatus::Complete),
+                ]
+            );
+        }
+
+        #[test]
+        fn sections_can_be_deeper() {
+            let depths = HeadingDepths::new(3, 4, 2).unwrap();
+            let project_str =
+                "# Project title\n#in-progress\n\n### Actions\n\n#### Waiting\n\n- One\n";
+            let project = Project::parse_with_depths(
+                "197001010000 Project title",
+                project_str,
+                NameScheme::default_ref(),
+                depths,
+            )
+            .unwrap();
+
+            assert_eq!(
+                texts(&project),
+                [(String::from("One"), ActionStatus::Waiting)]
+            );
+            assert!(Project::parse("197001010000 Project title", project_str).is_err());
+        }
+
+        #[test]
+        fn statuses_above_sections_are_err() {
+            assert!(HeadingDepths::new(3, 2, 2).is_err());
+            assert!(HeadingDepths::new(1, 2, 2).is_err());
+            assert!(HeadingDepths::new(2, 3, 7).is_err());
+        }
+    }
+
     mod id {
         use super::*;
 
@@ -713,7 +1985,41 @@ mod tests {
             let project_str = "# Project title\n#in-progress\n";
             let project = Project::parse("197001010000 Project title", project_str).unwrap();
 
-            assert_eq!(project.id(), "197001010000");
+            assert_eq!(project.id(), Some("197001010000"));
+        }
+
+        #[test]
+        fn id_is_parsed_with_custom_scheme() {
+            let scheme = NameScheme::new(
+                r"^(?P<id>\d{14}) (?P<title>.+)$",
+                "%Y%m%d%H%M%S",
+                "{id} {title}",
+            )
+            .unwrap();
+            let project_str = "# Project title\n#in-progress\n";
+            let project =
+                Project::parse_with_scheme("19700101000000 Project title", project_str, &scheme)
+                    .unwrap();
+
+            assert_eq!(project.id(), Some("19700101000000"));
+        }
+
+        #[test]
+        fn id_is_missing_without_id_capture() {
+            let scheme = NameScheme::new(r"^(?P<title>.+)$", "", "{title}").unwrap();
+            let project_str = "# Project title\n#in-progress\n";
+            let project =
+                Project::parse_with_scheme("Project title", project_str, &scheme).unwrap();
+
+            assert_eq!(project.id(), None);
+        }
+
+        #[test]
+        fn name_not_matching_scheme_is_err() {
+            let project_str = "# Project title\n#in-progress\n";
+            let project = Project::parse("Project title", project_str);
+
+            assert_eq!(project, Err(ParseError::InvalidProjectName));
         }
     }
 
@@ -728,4 +2034,61 @@ mod tests {
             assert_eq!(project.title(), "Project title");
         }
     }
+
+    mod name_scheme {
+        use super::*;
+        use chrono::Utc;
+
+        #[test]
+        fn default_scheme_generates_12_digit_id() {
+            let time = Utc.with_ymd_and_hms(1970, 1, 1, 12, 30, 0).unwrap();
+            assert_eq!(NameScheme::default().generate_id(&time), "197001011230");
+        }
+
+        #[test]
+        fn id_date_is_parsed_from_default_id() {
+            assert_eq!(
+                NameScheme::default().id_date("202401051230"),
+                NaiveDate::from_ymd_opt(2024, 1, 5)
+            );
+        }
+
+        #[test]
+        fn id_date_is_parsed_from_date_only_id() {
+            let scheme =
+                NameScheme::new(r"^(?P<id>\d{8}) (?P<title>.+)$", "%Y%m%d", "{id} {title}")
+                    .unwrap();
+            assert_eq!(
+                scheme.id_date("20240105"),
+                NaiveDate::from_ymd_opt(2024, 1, 5)
+            );
+        }
+
+        #[test]
+        fn format_builds_valid_name() {
+            let name = NameScheme::default()
+                .format("197001010000", "Project title")
+                .unwrap();
+            assert_eq!(name.as_str(), "197001010000 Project title");
+        }
+
+        #[test]
+        fn format_fails_if_id_doesnt_match() {
+            let name = NameScheme::default().format("1970", "Project title");
+            assert_eq!(name, None);
+        }
+
+        #[test]
+        fn pattern_without_title_is_err() {
+            let scheme = NameScheme::new(r"^(?P<id>\d+)$", "", "{id}");
+            assert!(matches!(scheme, Err(NameSchemeError::MissingTitleCapture)));
+        }
+
+        #[test]
+        fn invalid_id_format_is_err() {
+            let scheme = NameScheme::new(r"^(?P<id>\d+) (?P<title>.+)$", "%Y%Q", "{id} {title}");
+            assert!(matches!(scheme, Err(NameSchemeError::InvalidIdFormat(f)) if f == "%Y%Q"));
+            assert!(NameScheme::new(r"^(?P<title>.+)$", "%Y%m%d%H%M", "{title}").is_ok());
+        }
+    }
 }
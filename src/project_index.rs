@@ -0,0 +1,190 @@
+//! A "Projects" file in the root of the GTD folder that links to every project, grouped by status
+//! or by area.
+//!
+//! The index is only ever written by `gtd projects-file regenerate`, from the project files
+//! themselves, so it can't drift from them: anything written in it by hand is replaced.
+
+use crate::{
+    diff::{self, WriteMode},
+    gtd::Documents,
+    project::{Project, Status},
+    tag::Tag,
+    transaction::{Transaction, TransactionError},
+};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// The name of the project index, in the root of the GTD folder.
+pub const PROJECT_INDEX_FILENAME: &str = "Projects.md";
+
+/// The tag that projects' areas are nested under, like `#area/work`.
+pub const AREA_TAG: &str = "area";
+
+const TITLE: &str = "# Projects";
+
+/// The order the statuses are listed in.
+const STATUSES: [Status; 3] = [Status::InProgress, Status::Someday, Status::Complete];
+
+/// What to group the projects in the index by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Status,
+    /// The first tag of each project that's nested under `#area`.
+    Area,
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Status => write!(f, "status"),
+            Self::Area => write!(f, "area"),
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "status" => Ok(Self::Status),
+            "area" => Ok(Self::Area),
+            _ => Err(format!(
+                "\"{}\" is not something to group by, like \"status\" or \"area\"",
+                s
+            )),
+        }
+    }
+}
+
+/// The path of the project index in `root_dir`.
+pub fn path(root_dir: &Path) -> PathBuf {
+    root_dir.join(PROJECT_INDEX_FILENAME)
+}
+
+/// The area of `project`, like `work` for `#area/work`, if it has one.
+pub fn area(project: &Project) -> Option<String> {
+    let area = Tag::new(AREA_TAG)?;
+    let tag = project.tags.iter().find(|t| t.is_descendant_of(&area))?;
+    Some(tag.segments().skip(1).collect::<Vec<_>>().join("/"))
+}
+
+/// Renders the index of `projects`, grouped by `by`.
+///
+/// Groups are in a fixed order: statuses from in progress to complete, or areas alphabetically
+/// with projects that have no area last. Within a group, projects are sorted by title and then by
+/// name, so the same projects always give the same index.
+pub fn render(projects: &[&Project], by: GroupBy) -> String {
+    let mut projects = projects.to_vec();
+    projects.sort_by(|a, b| {
+        (a.title().to_lowercase(), &a.name).cmp(&(b.title().to_lowercase(), &b.name))
+    });
+
+    let groups = match by {
+        GroupBy::Status => STATUSES
+            .iter()
+            .map(|status| {
+                let group = projects.iter().filter(|p| p.status == *status);
+                (status.to_string(), group.copied().collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>(),
+        GroupBy::Area => {
+            let mut areas = projects.iter().filter_map(|p| area(p)).collect::<Vec<_>>();
+            areas.sort();
+            areas.dedup();
+            let mut groups = areas
+                .into_iter()
+                .map(|a| {
+                    let group = projects.iter().filter(|p| area(p).as_ref() == Some(&a));
+                    let group = group.copied().collect::<Vec<_>>();
+                    (a, group)
+                })
+                .collect::<Vec<_>>();
+            let group = projects.iter().filter(|p| area(p).is_none());
+            groups.push((String::from("No area"), group.copied().collect()));
+            groups
+        }
+    };
+
+    let mut out = format!("{}\n", TITLE);
+    for (heading, group) in groups.into_iter().filter(|(_, g)| !g.is_empty()) {
+        out.push_str(&format!("\n## {}\n\n", heading));
+        for project in group {
+            out.push_str(&format!("- [[{}|{}]]", project.name, project.title()));
+            if by == GroupBy::Area && project.status != Status::InProgress {
+                out.push_str(&format!(" ({})", project.status.to_string().to_lowercase()));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Rewrites the project index in the GTD folder of `docs` from its projects, grouped by `by`.
+///
+/// The change is shown as a word diff and written according to `mode`, and can be put back with
+/// `gtd undo`. Returns whether the index was written.
+pub fn regenerate(
+    docs: &Documents,
+    by: GroupBy,
+    mode: WriteMode,
+) -> Result<bool, TransactionError> {
+    let loader = docs.loader();
+    let path = path(loader.root_dir());
+    let projects = docs.projects().collect::<Vec<_>>();
+    let mut transaction = Transaction::new(loader, format!("projects-file regenerate --by {}", by));
+    let old = transaction.read(&path)?.unwrap_or_default();
+    let new = render(&projects, by);
+    if old == new {
+        return Ok(false);
+    }
+    if !diff::review(PROJECT_INDEX_FILENAME, &old, &new, mode)? {
+        return Ok(false);
+    }
+    transaction.write(&path, new)?;
+    transaction.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, text: &str) -> Project {
+        Project::parse(name, text).unwrap()
+    }
+
+    #[test]
+    fn projects_are_grouped_in_a_fixed_order() {
+        let projects = [
+            project("202401010002 Learn piano", "# Learn piano\n#someday"),
+            project(
+                "202401010001 Plan trip",
+                "# Plan trip\n#in-progress #area/home",
+            ),
+            project(
+                "202401010000 Ship beta",
+                "# Ship beta\n#in-progress #area/work/app",
+            ),
+            project("202401010003 Fix roof", "# Fix roof\n#complete #area/home"),
+        ];
+        let projects = projects.iter().collect::<Vec<_>>();
+
+        assert_eq!(
+            render(&projects, GroupBy::Status),
+            "# Projects\n\n## In progress\n\n- [[202401010001 Plan trip|Plan trip]]\n- [[202401010000 Ship beta|Ship beta]]\n\n## Someday\n\n- [[202401010002 Learn piano|Learn piano]]\n\n## Complete\n\n- [[202401010003 Fix roof|Fix roof]]\n"
+        );
+        assert_eq!(
+            render(&projects, GroupBy::Area),
+            "# Projects\n\n## home\n\n- [[202401010003 Fix roof|Fix roof]] (complete)\n- [[202401010001 Plan trip|Plan trip]]\n\n## work/app\n\n- [[202401010000 Ship beta|Ship beta]]\n\n## No area\n\n- [[202401010002 Learn piano|Learn piano]] (someday)\n"
+        );
+        let mut reversed = projects.clone();
+        reversed.reverse();
+        assert_eq!(
+            render(&reversed, GroupBy::Area),
+            render(&projects, GroupBy::Area)
+        );
+    }
+}
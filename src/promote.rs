@@ -0,0 +1,199 @@
+//! Promoting waiting or upcoming actions to active.
+
+use crate::{
+    context::Name as ContextName,
+    default_context, edit,
+    gtd::{Documents, LoadProjectError},
+    markdown::Span,
+    project::{ActionId, ActionStatus, Project, Status},
+    resolve::ResolveError,
+};
+use chrono::NaiveDate;
+use std::{error::Error, fmt, io::Error as IoError};
+
+/// An action that was promoted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Promoted {
+    pub text: String,
+    /// The project's default context, if the action was linked from it.
+    pub linked_from: Option<ContextName>,
+}
+
+/// Moves the waiting or upcoming action best matching `query` to its project's active actions,
+/// recording `today` as the day it became active.
+///
+/// If the project has a `default-context`, the action is linked from it, and given a block ID first
+/// if it doesn't have one.
+pub fn promote(docs: &Documents, query: &str, today: NaiveDate) -> Result<(), PromoteError> {
+    let (project, action) = docs.find_action(query)?;
+    let promoted = promote_in(docs, project, today, |reparsed| {
+        // Find the action again in case the file changed since it was loaded.
+        reparsed
+            .actions
+            .actions()
+            .find(|(a, _)| a.span == action.span && a == &action)
+            .map(|(a, _)| a.span)
+    })?
+    .ok_or(PromoteError::NotPromotable)?;
+
+    println!("Promoted \"{}\" ({}).", promoted.text, project.title());
+    if let Some(context) = promoted.linked_from {
+        println!("Linked it from {}.", context);
+    }
+    Ok(())
+}
+
+/// Promotes the first upcoming action of `project` on `today` if the project is in progress and
+/// has no active actions left, like after its last one is completed, so it isn't left stalled.
+///
+/// Like `promote`, the action is linked from the project's `default-context` if it has one. Returns
+/// the action that was promoted, if one was.
+pub fn promote_next(
+    docs: &Documents,
+    project: &Project,
+    today: NaiveDate,
+) -> Result<Option<Promoted>, PromoteError> {
+    promote_in(docs, project, today, |reparsed| {
+        let is_stalled = reparsed.status == Status::InProgress
+            && !reparsed
+                .actions
+                .actions()
+                .any(|(_, s)| s == ActionStatus::Active);
+        if !is_stalled {
+            return None;
+        }
+        reparsed
+            .actions
+            .actions()
+            .find(|(_, s)| *s == ActionStatus::Upcoming)
+            .map(|(a, _)| a.span)
+    })
+}
+
+/// Promotes the action that `find` picks out of `project` as it is in its file, by its span, and
+/// links it from the project's default context. Returns `None` if nothing was picked or the action
+/// can't be promoted.
+fn promote_in<F>(
+    docs: &Documents,
+    project: &Project,
+    today: NaiveDate,
+    find: F,
+) -> Result<Option<Promoted>, PromoteError>
+where
+    F: FnOnce(&Project) -> Option<Span>,
+{
+    let context = default_context::default_context(docs, project);
+    let loader = docs.loader();
+
+    let mut promoted = None;
+    let written = loader.update_project(&project.name, |text, reparsed| {
+        let span = find(reparsed)?;
+        let (action, _) = reparsed.actions.actions().find(|(a, _)| a.span == span)?;
+        promoted = Some((action.text.to_string(), action.id.clone()));
+        if context.is_none() || action.id.is_some() {
+            return edit::promote_action(text, reparsed, action, today);
+        }
+
+        // The action needs an ID to be linked to, which moves the lines after it.
+        let id = ActionId::generate(&action.text.to_string(), |id| {
+            reparsed.actions.get_action(id).is_some()
+        });
+        let text = edit::set_action_id(text, action, &id);
+        let reparsed = Project::parse_with_depths(
+            project.name.as_str(),
+            &text,
+            loader.scheme(),
+            loader.depths(),
+        )
+        .ok()?;
+        let (action, _) = reparsed.actions.get_action(&id)?;
+        promoted = Some((action.text.to_string(), Some(id)));
+        edit::promote_action(&text, &reparsed, action, today)
+    })?;
+
+    let (text, id) = match promoted {
+        Some(promoted) if written => promoted,
+        _ => return Ok(None),
+    };
+    let linked_from = match (context, id) {
+        (Some(context), Some(id)) => {
+            default_context::link_action(docs, &context, &project.name, &id)?.then_some(context)
+        }
+        _ => None,
+    };
+    Ok(Some(Promoted { text, linked_from }))
+}
+
+#[derive(Debug)]
+pub enum PromoteError {
+    ResolveError(ResolveError),
+    LoadProjectError(LoadProjectError),
+    IoError(IoError),
+    /// The action is already active or complete, or the project changed while it was being edited.
+    NotPromotable,
+}
+
+impl fmt::Display for PromoteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ResolveError(e) => write!(f, "{}", e),
+            Self::LoadProjectError(e) => write!(f, "{}", e),
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::NotPromotable => write!(f, "action is already active or complete"),
+        }
+    }
+}
+
+impl Error for PromoteError {}
+
+impl From<ResolveError> for PromoteError {
+    fn from(error: ResolveError) -> Self {
+        Self::ResolveError(error)
+    }
+}
+
+impl From<LoadProjectError> for PromoteError {
+    fn from(error: LoadProjectError) -> Self {
+        Self::LoadProjectError(error)
+    }
+}
+
+impl From<IoError> for PromoteError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+    use std::fs;
+
+    #[test]
+    fn promoted_actions_are_linked_from_the_default_context() {
+        let dir = TempDir::new("promote");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let path = dir.join("Projects/202401010000 Paint shed.md");
+        fs::write(
+            &path,
+            "---\ndefault-context: \"@shed\"\n---\n# Paint shed\n#in-progress\n\n## Actions\n\n### Active\n\n- Sand walls ^abcdef\n\n### Upcoming\n\n- Prime walls\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+
+        promote(&docs, "Prime walls", today).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let project = Project::parse("202401010000 Paint shed", &text).unwrap();
+        let (action, _) = project.actions.actions().nth(1).unwrap();
+        let id = action.id.clone().unwrap();
+        assert!(text.contains(&format!("- Prime walls @active(2024-01-05) {}\n", id)));
+        assert_eq!(
+            fs::read_to_string(dir.join("Contexts/@shed.md")).unwrap(),
+            format!("# @shed\n\n- ![[202401010000 Paint shed#{}]]\n", id)
+        );
+    }
+}
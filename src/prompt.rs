@@ -0,0 +1,67 @@
+//! Questions asked of the user in the terminal.
+
+use std::io::{self, BufRead, Error as IoError, Write};
+
+/// Asks the user a yes or no question, defaulting to no.
+pub fn confirm(question: &str) -> Result<bool, IoError> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Asks the user to pick one of `choices`, which can be answered with a whole choice or its first
+/// letter. Asks again until the answer is one of the choices, and returns `None` if the input ends
+/// first.
+pub fn choose(question: &str, choices: &[&str]) -> Result<Option<usize>, IoError> {
+    let options = choices
+        .iter()
+        .map(|c| {
+            let mut chars = c.chars();
+            let first = chars.next().unwrap_or_default();
+            format!("[{}]{}", first, chars.as_str())
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    loop {
+        print!("{} {} ", question, options);
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        let answer = answer.trim().to_lowercase();
+        let choice = choices
+            .iter()
+            .position(|c| answer == *c || (answer.len() == 1 && c.starts_with(answer.as_str())));
+        if choice.is_some() {
+            return Ok(choice);
+        }
+    }
+}
+
+/// Asks the user for a line of text. An empty answer gives `default`, or asks again if there isn't
+/// one. Returns `None` if the input ends first.
+pub fn ask(question: &str, default: Option<&str>) -> Result<Option<String>, IoError> {
+    loop {
+        match default {
+            Some(default) if !default.is_empty() => print!("{} [{}] ", question, default),
+            _ => print!("{} ", question),
+        }
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        match (answer.trim(), default) {
+            ("", Some(default)) => return Ok(Some(default.to_string())),
+            ("", None) => {}
+            (answer, _) => return Ok(Some(answer.to_string())),
+        }
+    }
+}
@@ -0,0 +1,530 @@
+//! Publishing the GTD system as a static website, for reading it on devices without Obsidian.
+//!
+//! The site is plain HTML with relative links, so it can be opened straight from disk or put on
+//! any web server. It has an index of projects, a page for each project and context, the `today`
+//! and `waiting` reports, and a search box. The search index is built into `search.js` instead of
+//! being fetched, since browsers don't let pages opened from disk fetch files.
+
+use crate::{
+    config::PublishConfig,
+    context::{Action as ContextAction, Context},
+    filename,
+    gtd::Documents,
+    locale::Locale,
+    markdown::{Fragment, WikiLink},
+    project::{Action, ActionStatus, Project, Status},
+    today::{self, Entry},
+    waiting,
+};
+use chrono::NaiveDate;
+use pulldown_cmark::escape;
+use serde_json::json;
+use std::{
+    fs,
+    io::Error as IoError,
+    path::{Path, PathBuf},
+};
+
+/// The project statuses, in the order projects are listed on the index.
+const STATUSES: [Status; 3] = [Status::InProgress, Status::Someday, Status::Complete];
+
+/// The action sections of a project, in the order they're shown.
+const SECTIONS: [(ActionStatus, &str); 4] = [
+    (ActionStatus::Active, "Active"),
+    (ActionStatus::Waiting, "Waiting"),
+    (ActionStatus::Upcoming, "Upcoming"),
+    (ActionStatus::Complete, "Complete"),
+];
+
+const STYLE: &str =
+    "body { font-family: sans-serif; max-width: 48em; margin: 0 auto; padding: 1em; \
+line-height: 1.5; }
+nav a { margin-right: 1em; }
+.meta { color: #666; }
+.complete { color: #666; text-decoration: line-through; }
+#results:empty { display: none; }
+";
+
+/// Filters the pages in `PAGES`, which is written before this, by the words in the search box.
+const SEARCH: &str = "
+function search(query) {
+  const words = query.toLowerCase().split(/\\s+/).filter(w => w);
+  const results = document.getElementById('results');
+  results.replaceChildren();
+  if (words.length === 0) return;
+  for (const page of PAGES) {
+    const text = (page.title + ' ' + page.text).toLowerCase();
+    if (!words.every(w => text.includes(w))) continue;
+    const link = document.createElement('a');
+    link.href = page.url;
+    link.textContent = page.title;
+    const item = document.createElement('li');
+    item.appendChild(link);
+    results.appendChild(item);
+  }
+}
+";
+
+/// A page of the site, with its path relative to the site's folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub path: PathBuf,
+    pub text: String,
+}
+
+/// Renders the site for `docs` as of `today`, with dates shown as `locale` shows them.
+pub fn site(
+    docs: &Documents,
+    config: &PublishConfig,
+    locale: &Locale,
+    today: NaiveDate,
+) -> Vec<Page> {
+    let links = Links {
+        docs,
+        note_url: config.note_url.as_deref(),
+    };
+    let mut pages = vec![
+        Page {
+            path: PathBuf::from("index.html"),
+            text: index(docs),
+        },
+        Page {
+            path: PathBuf::from("today.html"),
+            text: today_report(&links, locale, today),
+        },
+        Page {
+            path: PathBuf::from("waiting.html"),
+            text: waiting_report(&links, locale),
+        },
+    ];
+    pages.extend(docs.projects().map(|p| Page {
+        path: PathBuf::from(project_url(p)),
+        text: project_page(&links, p, locale),
+    }));
+    pages.extend(docs.contexts().map(|c| Page {
+        path: PathBuf::from(context_url(c)),
+        text: context_page(&links, c),
+    }));
+    pages.push(Page {
+        path: PathBuf::from("style.css"),
+        text: STYLE.to_string(),
+    });
+    pages.push(Page {
+        path: PathBuf::from("search.js"),
+        text: search_script(docs),
+    });
+    pages
+}
+
+/// Writes the site for `docs` into `out`, returning how many files were written. Files from an
+/// earlier publish that are no longer part of the site are left where they are.
+pub fn publish(
+    docs: &Documents,
+    config: &PublishConfig,
+    locale: &Locale,
+    today: NaiveDate,
+    out: &Path,
+) -> Result<usize, IoError> {
+    let pages = site(docs, config, locale, today);
+    for page in pages.iter() {
+        let path = filename::long_path(&out.join(&page.path));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &page.text)?;
+    }
+    Ok(pages.len())
+}
+
+fn index(docs: &Documents) -> String {
+    let mut body = String::from(
+        "<input id=\"search\" type=\"search\" placeholder=\"Search\" \
+         oninput=\"search(this.value)\">\n<ul id=\"results\"></ul>\n",
+    );
+    for status in STATUSES.iter() {
+        let projects = docs.projects_with_status(*status).collect::<Vec<_>>();
+        if projects.is_empty() {
+            continue;
+        }
+        body.push_str(&format!("<h2>{}</h2>\n<ul>\n", status));
+        for project in projects {
+            body.push_str(&format!(
+                "<li>{} <span class=\"meta\">{}</span></li>\n",
+                project_link(project, ""),
+                project.actions.progress()
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+    body.push_str("<h2>Contexts</h2>\n<ul>\n");
+    for context in docs.contexts() {
+        body.push_str(&format!("<li>{}</li>\n", context_link(context, "")));
+    }
+    body.push_str("</ul>\n<script src=\"search.js\"></script>\n");
+    layout("Projects", "", &body)
+}
+
+fn project_page(links: &Links, project: &Project, locale: &Locale) -> String {
+    let mut body = format!("<p class=\"meta\">{}", project.status);
+    for tag in project.tags.iter() {
+        body.push_str(&format!(" #{}", escape_text(tag.as_str())));
+    }
+    body.push_str("</p>\n");
+    let sections = [("Goal", &project.goal), ("Info", &project.info)];
+    for (heading, fragment) in sections.iter() {
+        if let Some(fragment) = fragment {
+            body.push_str(&format!(
+                "<h2>{}</h2>\n{}",
+                heading,
+                links.block(fragment, "../")
+            ));
+        }
+    }
+    if let Some(outcome) = &project.outcome {
+        body.push_str(&format!(
+            "<h2>Outcome</h2>\n{}",
+            links.block(&outcome.note, "../")
+        ));
+        if let Some(completed) = outcome.completed {
+            body.push_str(&format!(
+                "<p class=\"meta\">Completed {}</p>\n",
+                locale.date(completed)
+            ));
+        }
+    }
+    for (status, heading) in SECTIONS.iter() {
+        let actions = links
+            .docs
+            .actions_with_status(*status)
+            .filter(|a| a.project.name == project.name)
+            .collect::<Vec<_>>();
+        if actions.is_empty() {
+            continue;
+        }
+        body.push_str(&format!("<h2>{}</h2>\n<ul>\n", heading));
+        for action in actions {
+            let contexts = action
+                .contexts()
+                .map(|c| context_link(c, "../"))
+                .collect::<Vec<_>>();
+            body.push_str(&format!(
+                "<li{}>{}{}",
+                status_class(*status),
+                links.inline(&action.action.text, "../"),
+                action_meta(action.action, locale)
+            ));
+            if !contexts.is_empty() {
+                body.push_str(&format!(
+                    " <span class=\"meta\">{}</span>",
+                    contexts.join(" ")
+                ));
+            }
+            body.push_str("</li>\n");
+        }
+        body.push_str("</ul>\n");
+    }
+    if !project.footnotes.is_empty() {
+        body.push_str("<h2>Footnotes</h2>\n<ol class=\"footnotes\">\n");
+        for footnote in project.footnotes.iter() {
+            body.push_str(&footnote.to_html(|link| links.url(link, "../")));
+        }
+        body.push_str("</ol>\n");
+    }
+    layout(project.title(), "../", &body)
+}
+
+fn context_page(links: &Links, context: &Context) -> String {
+    let mut body = context_actions(links, context.ungrouped_actions());
+    for group in context.groups() {
+        body.push_str(&format!(
+            "<h2>{}</h2>\n{}",
+            escape_text(&group.title()),
+            context_actions(links, context.group_actions(group))
+        ));
+    }
+    layout(&context.title.to_string(), "../", &body)
+}
+
+fn context_actions(links: &Links, actions: &[ContextAction]) -> String {
+    if actions.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("<ul>\n");
+    for action in actions {
+        let item = match action {
+            ContextAction::Literal(fragment) => links.inline(fragment, "../"),
+            ContextAction::Reference(action_ref) => match links.docs.linked_action(action_ref) {
+                Some(linked) => format!(
+                    "{} <span class=\"meta\">{}</span>",
+                    links.inline(&linked.action.text, "../"),
+                    project_link(linked.project, "../")
+                ),
+                None => escape_text(&format!(
+                    "{}#^{}",
+                    action_ref.project_name.as_str(),
+                    action_ref.action_id.as_str()
+                )),
+            },
+            ContextAction::UnresolvedReference(block_ref) => escape_text(&block_ref.to_string()),
+        };
+        out.push_str(&format!("<li>{}</li>\n", item));
+    }
+    out.push_str("</ul>\n");
+    out
+}
+
+fn today_report(links: &Links, locale: &Locale, date: NaiveDate) -> String {
+    let report = today::today(links.docs, date, usize::MAX);
+    let mut body = format!("<p class=\"meta\">{}</p>\n", locale.date(date));
+    let sections = [("Overdue", &report.overdue), ("Due today", &report.due)];
+    for (heading, entries) in sections.iter() {
+        if !entries.is_empty() {
+            body.push_str(&format!(
+                "<h2>{}</h2>\n{}",
+                heading,
+                entry_list(links, entries, locale)
+            ));
+        }
+    }
+    for (context, entries) in report.next.iter() {
+        body.push_str(&format!(
+            "<h2>{}</h2>\n{}",
+            context_link(context, ""),
+            entry_list(links, entries, locale)
+        ));
+    }
+    layout("Today", "", &body)
+}
+
+fn waiting_report(links: &Links, locale: &Locale) -> String {
+    let mut body = String::new();
+    for (who, actions) in waiting::delegations(links.docs.projects()) {
+        let entries = actions
+            .into_iter()
+            .map(|(project, action)| Entry { project, action })
+            .collect::<Vec<_>>();
+        body.push_str(&format!(
+            "<h2>{}</h2>\n{}",
+            escape_text(who.unwrap_or("Unassigned")),
+            entry_list(links, &entries, locale)
+        ));
+    }
+    layout("Waiting", "", &body)
+}
+
+fn entry_list(links: &Links, entries: &[Entry], locale: &Locale) -> String {
+    let mut out = String::from("<ul>\n");
+    for entry in entries {
+        out.push_str(&format!(
+            "<li>{}{} <span class=\"meta\">{}</span></li>\n",
+            links.inline(&entry.action.text, ""),
+            action_meta(entry.action, locale),
+            project_link(entry.project, "")
+        ));
+    }
+    out.push_str("</ul>\n");
+    out
+}
+
+/// The dates and people of `action` that are worth seeing at a glance.
+fn action_meta(action: &Action, locale: &Locale) -> String {
+    let mut meta = Vec::new();
+    if let Some(who) = &action.who {
+        meta.push(format!("@{}", escape_text(who)));
+    }
+    if let Some(due) = action.due {
+        meta.push(format!("due {}", locale.date(due)));
+    }
+    if let Some(deferred) = action.deferred {
+        meta.push(format!("deferred to {}", locale.date(deferred)));
+    }
+    if let Some(completed) = action.completed {
+        meta.push(format!("completed {}", locale.date(completed)));
+    }
+    if meta.is_empty() {
+        return String::new();
+    }
+    format!(" <span class=\"meta\">{}</span>", meta.join(", "))
+}
+
+fn status_class(status: ActionStatus) -> &'static str {
+    match status {
+        ActionStatus::Complete => " class=\"complete\"",
+        _ => "",
+    }
+}
+
+fn search_script(docs: &Documents) -> String {
+    let projects = docs.projects().map(|p| {
+        let text = p
+            .actions
+            .actions()
+            .map(|(a, _)| a.text.text())
+            .chain(p.goal.iter().map(Fragment::text))
+            .collect::<Vec<_>>()
+            .join(" ");
+        json!({ "title": p.title(), "url": href(&project_url(p)), "text": text })
+    });
+    let contexts = docs
+        .contexts()
+        .map(|c| json!({ "title": c.title.to_string(), "url": href(&context_url(c)), "text": "" }));
+    let pages = projects.chain(contexts).collect::<Vec<_>>();
+    format!("const PAGES = {};\n{}", json!(pages), SEARCH)
+}
+
+fn layout(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n\
+         <body>\n<nav><a href=\"{root}index.html\">Projects</a><a href=\"{root}today.html\">Today</a>\
+         <a href=\"{root}waiting.html\">Waiting</a></nav>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        title = escape_text(title),
+        root = root,
+        body = body
+    )
+}
+
+fn project_url(project: &Project) -> String {
+    format!(
+        "projects/{}.html",
+        filename::sanitize(project.name.as_str())
+    )
+}
+
+fn context_url(context: &Context) -> String {
+    format!(
+        "contexts/{}.html",
+        filename::sanitize(context.name.as_str())
+    )
+}
+
+/// A link to `project`'s page from a page `root` away from the root of the site.
+fn project_link(project: &Project, root: &str) -> String {
+    link(
+        &format!("{}{}", root, project_url(project)),
+        project.title(),
+    )
+}
+
+/// A link to `context`'s page from a page `root` away from the root of the site.
+fn context_link(context: &Context, root: &str) -> String {
+    link(
+        &format!("{}{}", root, context_url(context)),
+        &context.title.to_string(),
+    )
+}
+
+fn link(url: &str, text: &str) -> String {
+    format!("<a href=\"{}\">{}</a>", href(url), escape_text(text))
+}
+
+fn href(url: &str) -> String {
+    let mut out = String::new();
+    // Writing to a `String` can't fail.
+    let _ = escape::escape_href(&mut out, url);
+    out
+}
+
+fn escape_text(text: &str) -> String {
+    let mut out = String::new();
+    let _ = escape::escape_html(&mut out, text);
+    out
+}
+
+/// Where the wiki links in notes point on the site.
+struct Links<'a> {
+    docs: &'a Documents,
+    note_url: Option<&'a str>,
+}
+
+impl<'a> Links<'a> {
+    /// The URL of the note `link` links to, from a page `root` away from the root of the site.
+    /// Projects and contexts link to their pages, and other notes to the configured URL.
+    fn url(&self, link: &WikiLink, root: &str) -> Option<String> {
+        if let Some(project) = self.docs.projects().find(|p| p.name.as_str() == link.note) {
+            return Some(format!("{}{}", root, project_url(project)));
+        }
+        if let Some(context) = self.docs.contexts().find(|c| c.name.as_str() == link.note) {
+            return Some(format!("{}{}", root, context_url(context)));
+        }
+        let note_url = self.note_url.filter(|_| !link.note.is_empty())?;
+        Some(note_url.replace("{note}", link.note))
+    }
+
+    /// `fragment` as HTML on a page `root` away from the root of the site.
+    fn block(&self, fragment: &Fragment, root: &str) -> String {
+        fragment.to_html(|link| self.url(link, root))
+    }
+
+    /// `fragment` as HTML, without the paragraph it's in.
+    fn inline(&self, fragment: &Fragment, root: &str) -> String {
+        let text = self.block(fragment, root);
+        let text = text.trim_end();
+        let text = text.strip_prefix("<p>").unwrap_or(text);
+        text.strip_suffix("</p>").unwrap_or(text).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+
+    #[test]
+    fn site_links_projects_and_contexts() {
+        let dir = TempDir::new("publish");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401011200 Plan trip.md"),
+            "# Plan trip\n#in-progress\n\n## Goal\n\nSee [[Packing list]].[^1]\n\n[^1]: Ask <Ann> about [[Packing list]].\n\n## Info\n\n| Item | Price |\n|--|--:|\n| Flights | 400 |\n\n## Actions\n\n### Active\n\n- Book **flights** & hotel ^abc123\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Contexts/@home.md"),
+            "# @home\n\n- ![[202401011200 Plan trip#^abc123]]\n- Water <plants> before [[202401011200 Plan trip|the trip]]\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+
+        let config = PublishConfig {
+            note_url: Some(String::from("obsidian://open?file={note}")),
+        };
+
+        let out = dir.join("site");
+        assert_eq!(
+            publish(&docs, &config, &Locale::default(), date, &out).unwrap(),
+            7
+        );
+        let read = |path: &str| fs::read_to_string(out.join(path)).unwrap();
+
+        let project = read("projects/202401011200 Plan trip.html");
+        assert!(project.contains("<title>Plan trip</title>"));
+        assert!(project.contains(
+            "<li>Book <strong>flights</strong> &amp; hotel <span class=\"meta\">\
+             <a href=\"../contexts/@home.html\">@home</a></span></li>"
+        ));
+        let context = read("contexts/@home.html");
+        assert!(context
+            .contains("<a href=\"../projects/202401011200%20Plan%20trip.html\">Plan trip</a>"));
+        assert!(context.contains(
+            "<li>Water &lt;plants&gt; before \
+             <a href=\"../projects/202401011200%20Plan%20trip.html\">the trip</a></li>"
+        ));
+        assert!(project.contains("<th>Item</th><th align=\"right\">Price</th>"));
+        assert!(project.contains("<td>Flights</td><td align=\"right\">400</td>"));
+        assert!(project.contains(
+            "<p>See <a href=\"obsidian://open?file=Packing%20list\">Packing list</a>.\
+             <sup class=\"footnote-reference\"><a href=\"#fn-1\">1</a></sup></p>"
+        ));
+        assert!(project.contains(
+            "<li id=\"fn-1\"><p>Ask &lt;Ann&gt; about \
+             <a href=\"obsidian://open?file=Packing%20list\">Packing list</a>.</p>\n</li>"
+        ));
+        assert!(read("index.html").contains("<h2>In progress</h2>"));
+        assert!(read("search.js").contains("\"url\":\"projects/202401011200%20Plan%20trip.html\""));
+    }
+}
@@ -1,6 +1,7 @@
 //! Helpers to wrap types from `pulldown_cmark`.
 
-use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag};
+use crate::markdown::Span;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, LinkType, Tag};
 use std::fmt;
 
 /// Extends the lifetime of a `pulldown_cmark::CowStr` to `'static`.
@@ -58,6 +59,33 @@ fn code_block_kind_static<'a>(kind: CodeBlockKind<'a>) -> CodeBlockKind<'static>
     }
 }
 
+/// A `pulldown_cmark::Event` along with where it is in the source text, for pointing diagnostics at
+/// it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedEvent<'a> {
+    pub event: Event<'a>,
+    pub span: Span,
+}
+
+impl<'a> SpannedEvent<'a> {
+    pub fn new(event: Event<'a>, span: Span) -> Self {
+        Self { event, span }
+    }
+
+    /// Extends the lifetime of the event to `'static`.
+    pub fn into_static(self) -> SpannedEvent<'static> {
+        SpannedEvent {
+            event: event_static(self.event),
+            span: self.span,
+        }
+    }
+
+    /// The 1-based line number in `text` that the event starts on.
+    pub fn line(&self, text: &str) -> usize {
+        self.span.start_line(text)
+    }
+}
+
 /// Wrapper for `pulldown_cmark::Event`s that allows them to be displayed.
 pub struct DisplayableEvent<'a>(pub &'a Event<'a>);
 
@@ -104,3 +132,72 @@ impl<'a> fmt::Display for DisplayableTag<'a> {
         }
     }
 }
+
+/// Writes an inline event as the Markdown it was most likely parsed from, so that rendering the
+/// events of a line gives back the line. Block events are ignored.
+///
+/// Emphasis is written with `_`, and links to definitions elsewhere in the document are written
+/// inline, since the events don't say how they were written. Text is written as it is, without
+/// escaping.
+pub fn write_inline_event<W: fmt::Write>(w: &mut W, event: &Event) -> fmt::Result {
+    match event {
+        Event::Start(tag) => match tag {
+            Tag::Emphasis => w.write_str("_"),
+            Tag::Strong => w.write_str("**"),
+            Tag::Strikethrough => w.write_str("~~"),
+            Tag::Link(LinkType::Autolink, _, _) | Tag::Link(LinkType::Email, _, _) => {
+                w.write_str("<")
+            }
+            Tag::Link(..) => w.write_str("["),
+            Tag::Image(..) => w.write_str("!["),
+            _ => Ok(()),
+        },
+        Event::End(tag) => match tag {
+            Tag::Emphasis => w.write_str("_"),
+            Tag::Strong => w.write_str("**"),
+            Tag::Strikethrough => w.write_str("~~"),
+            Tag::Link(ty, url, title) | Tag::Image(ty, url, title) => match ty {
+                LinkType::Autolink | LinkType::Email => w.write_str(">"),
+                LinkType::ReferenceUnknown
+                | LinkType::CollapsedUnknown
+                | LinkType::ShortcutUnknown => w.write_str("]"),
+                _ => write_destination(w, url, title),
+            },
+            _ => Ok(()),
+        },
+        Event::Text(t) => w.write_str(t),
+        Event::Code(c) => write_code(w, c),
+        Event::Html(h) => w.write_str(h),
+        Event::FootnoteReference(s) => write!(w, "[^{}]", s),
+        Event::SoftBreak => w.write_str("\n"),
+        Event::HardBreak => w.write_str("\\\n"),
+        Event::Rule | Event::TaskListMarker(_) => Ok(()),
+    }
+}
+
+/// Writes the `](url "title")` that ends an inline link or image.
+fn write_destination<W: fmt::Write>(w: &mut W, url: &str, title: &str) -> fmt::Result {
+    w.write_str("](")?;
+    if url.contains(' ') {
+        write!(w, "<{}>", url)?;
+    } else {
+        w.write_str(url)?;
+    }
+    if !title.is_empty() {
+        write!(w, " \"{}\"", title.replace('"', "\\\""))?;
+    }
+    w.write_str(")")
+}
+
+/// Writes inline code, with enough backticks around it that none inside it end it early.
+fn write_code<W: fmt::Write>(w: &mut W, code: &str) -> fmt::Result {
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run + 1);
+    // The parser strips one space from each end when both have one, and backticks at the ends
+    // would join the fence, so both need a space of padding.
+    let is_padded = code.starts_with('`')
+        || code.ends_with('`')
+        || (code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty());
+    let pad = if is_padded { " " } else { "" };
+    write!(w, "{}{}{}{}{}", fence, pad, code, pad, fence)
+}
@@ -0,0 +1,285 @@
+//! Links between the documents in a GTD folder, and an index of which documents link to each one.
+//!
+//! Links are Markdown links, like `[trip](Projects/Plan%20trip.md)`, and Obsidian wikilinks and
+//! embeds, like `[[Plan trip]]` or `![[Plan trip#^abcdef]]`.
+
+use crate::{
+    frontmatter::Frontmatter,
+    markdown::Span,
+    obsidian::percent_decode,
+    walk::{self, Ignore},
+};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Error as IoError,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// A link in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Where the link points, like `Plan trip#^abcdef` or `../Attachments/map.png`.
+    pub target: String,
+    /// The 1-based line the link is on.
+    pub line: usize,
+}
+
+impl Link {
+    /// The part of the target before any `#` heading or block reference.
+    pub fn path(&self) -> &str {
+        self.target.split('#').next().unwrap_or_default().trim()
+    }
+
+    /// The block ID the link points to, from a `#^abcdef` suffix.
+    pub fn block(&self) -> Option<&str> {
+        self.target.split_once("#^").map(|(_, block)| block.trim())
+    }
+
+    /// The name of the document the link points to, which is its filename without `.md`.
+    pub fn document(&self) -> Option<&str> {
+        let path = self.path();
+        let name = path.rsplit('/').next()?;
+        let name = name.strip_suffix(".md").unwrap_or(name);
+        match Path::new(name).extension() {
+            Some(_) if !path.ends_with(".md") => None,
+            _ if name.is_empty() || path.contains("://") => None,
+            _ => Some(name),
+        }
+    }
+}
+
+/// The links in the Markdown in `text`, in the order they appear.
+pub fn links(text: &str) -> Vec<Link> {
+    let (_, start) = Frontmatter::parse(text);
+    let wikilink = Regex::new(r"\[\[([^\]|]+)[^\]]*\]\]").unwrap();
+    let options = Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES | Options::ENABLE_TASKLISTS;
+
+    let mut targets = Vec::new();
+    // Wikilinks aren't Markdown, so they're found in runs of text, which the parser splits up at
+    // brackets.
+    let mut run = String::new();
+    let mut run_start = start;
+    for (event, range) in Parser::new_ext(&text[start..], options).into_offset_iter() {
+        let offset = start + range.start;
+        if let Event::Text(t) = &event {
+            if run.is_empty() {
+                run_start = offset;
+            }
+            run.push_str(t);
+            continue;
+        }
+
+        targets.extend(
+            wikilink
+                .captures_iter(&run)
+                .map(|c| (run_start, c[1].to_string())),
+        );
+        run.clear();
+        if let Event::Start(Tag::Link(_, dest, _)) | Event::Start(Tag::Image(_, dest, _)) = event {
+            targets.push((offset, percent_decode(&dest)));
+        }
+    }
+    targets.extend(
+        wikilink
+            .captures_iter(&run)
+            .map(|c| (run_start, c[1].to_string())),
+    );
+
+    targets.sort_by_key(|(offset, _)| *offset);
+    targets
+        .into_iter()
+        .map(|(offset, target)| Link {
+            target,
+            line: Span::new(offset, offset).start_line(text),
+        })
+        .collect()
+}
+
+/// A link to a document from another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// The document the link is in, relative to the GTD folder.
+    pub source: PathBuf,
+    pub link: Link,
+}
+
+impl Reference {
+    /// The name of the document the link is in.
+    pub fn source_name(&self) -> String {
+        self.source
+            .file_stem()
+            .map_or_else(String::new, |s| s.to_string_lossy().into_owned())
+    }
+}
+
+/// Which documents link to each document in a GTD folder.
+///
+/// The index remembers when it last read each file, so refreshing it only reads files that have
+/// changed since.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReferenceIndex {
+    references: HashMap<String, Vec<Reference>>,
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl ReferenceIndex {
+    /// Indexes the links in every Markdown file in `root_dir`.
+    pub fn build(root_dir: &Path) -> Result<Self, IoError> {
+        let mut index = Self::default();
+        index.refresh(root_dir)?;
+        Ok(index)
+    }
+
+    /// Updates the index with the Markdown files in `root_dir` that were added, changed, or
+    /// removed since it was last built or refreshed.
+    pub fn refresh(&mut self, root_dir: &Path) -> Result<(), IoError> {
+        let mut seen = HashSet::new();
+        let docs = files(root_dir)?
+            .into_iter()
+            .filter(|p| p.extension().is_some_and(|e| e == "md"));
+        for doc in docs {
+            let source = doc.strip_prefix(root_dir).unwrap_or(&doc).to_owned();
+            let modified = fs::metadata(&doc)?.modified()?;
+            seen.insert(source.clone());
+            if self.modified.get(&source) == Some(&modified) {
+                continue;
+            }
+
+            let text = fs::read_to_string(&doc)?;
+            self.remove(&source);
+            self.add(&source, &text);
+            self.modified.insert(source, modified);
+        }
+
+        let removed = self
+            .modified
+            .keys()
+            .filter(|source| !seen.contains(*source))
+            .cloned()
+            .collect::<Vec<_>>();
+        for source in removed {
+            self.remove(&source);
+            self.modified.remove(&source);
+        }
+        Ok(())
+    }
+
+    /// Adds the links in `text`, which is the document at `source`. Links from a document to
+    /// itself are skipped.
+    pub fn add(&mut self, source: &Path, text: &str) {
+        let source_name = source.file_stem().map(|s| s.to_string_lossy());
+        for link in links(text) {
+            let document = match link.document() {
+                Some(document) if Some(document) != source_name.as_deref() => document.to_string(),
+                _ => continue,
+            };
+            self.references
+                .entry(document)
+                .or_default()
+                .push(Reference {
+                    source: source.to_owned(),
+                    link,
+                });
+        }
+    }
+
+    /// Removes the links in the document at `source`.
+    pub fn remove(&mut self, source: &Path) {
+        for references in self.references.values_mut() {
+            references.retain(|r| r.source != source);
+        }
+        self.references
+            .retain(|_, references| !references.is_empty());
+    }
+
+    /// The links to the document `name` from other documents.
+    pub fn links_to(&self, name: &str) -> &[Reference] {
+        self.references.get(name).map_or(&[], |r| &r[..])
+    }
+}
+
+/// All the files in `dir` and its subdirectories, sorted by path, skipping hidden ones like
+/// `.obsidian` and ones ignored by the ignore files in `dir`.
+pub fn files(dir: &Path) -> Result<Vec<PathBuf>, IoError> {
+    Ok(walk::walk(dir, &Ignore::load(dir)?)?.included)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    fn link(target: &str) -> Link {
+        Link {
+            target: target.to_string(),
+            line: 1,
+        }
+    }
+
+    #[test]
+    fn wikilink_is_split_up() {
+        let links = links("# Review\n\n![[202401010000 Plan trip#^abcdef|trip]]\n");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].line, 3);
+        assert_eq!(links[0].document(), Some("202401010000 Plan trip"));
+        assert_eq!(links[0].block(), Some("abcdef"));
+    }
+
+    #[test]
+    fn markdown_link_names_document() {
+        assert_eq!(
+            link("../Projects/202401010000 Plan trip.md#Goal").document(),
+            Some("202401010000 Plan trip")
+        );
+        assert_eq!(link("map.png").document(), None);
+        assert_eq!(link("https://example.com").document(), None);
+    }
+
+    #[test]
+    fn links_to_self_are_skipped() {
+        let mut index = ReferenceIndex::default();
+        index.add(
+            Path::new("Projects/Plan trip.md"),
+            "# Plan trip\n\n[[Plan trip#Goal]]\n",
+        );
+        index.add(Path::new("Notes/Review.md"), "# Review\n\n[[Plan trip]]\n");
+        let sources = index
+            .links_to("Plan trip")
+            .iter()
+            .map(|r| r.source_name())
+            .collect::<Vec<_>>();
+        assert_eq!(sources, vec!["Review"]);
+    }
+
+    #[test]
+    fn removed_document_links_are_dropped() {
+        let mut index = ReferenceIndex::default();
+        index.add(Path::new("Notes/Review.md"), "# Review\n\n[[Plan trip]]\n");
+        index.remove(Path::new("Notes/Review.md"));
+        assert!(index.links_to("Plan trip").is_empty());
+    }
+
+    #[test]
+    fn refresh_reads_changed_documents() {
+        let dir = TempDir::new("references");
+        fs::create_dir_all(dir.join("Notes")).unwrap();
+        fs::write(dir.join("Notes/Review.md"), "# Review\n\n[[Plan trip]]\n").unwrap();
+        let mut index = ReferenceIndex::build(&dir).unwrap();
+        assert_eq!(index.links_to("Plan trip").len(), 1);
+
+        fs::write(dir.join("Notes/Ideas.md"), "# Ideas\n\n[[Plan trip]]\n").unwrap();
+        fs::remove_file(dir.join("Notes/Review.md")).unwrap();
+        index.refresh(&dir).unwrap();
+
+        let sources = index
+            .links_to("Plan trip")
+            .iter()
+            .map(|r| r.source_name())
+            .collect::<Vec<_>>();
+        assert_eq!(sources, vec!["Ideas"]);
+    }
+}
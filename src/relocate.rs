@@ -0,0 +1,285 @@
+//! Moving an action from one project to another, for when it turns out to belong somewhere else.
+//!
+//! Contexts and other notes link to actions by their project and block ID, so the links to a moved
+//! action are pointed at its new project. Its ID is kept unless the new project already has an
+//! action with it, and the move is recorded on the action with `@moved(…)`.
+
+use crate::{
+    diff::WriteMode,
+    edit,
+    gtd::{Documents, LoadProjectError, Loader},
+    obsidian::percent_encode,
+    project::{ActionId, Name as ProjectName, Project, MOVED_ANNOTATION},
+    resolve::ResolveError,
+    transaction::{Transaction, TransactionError},
+};
+use chrono::NaiveDate;
+use std::{error::Error, fmt, io::Error as IoError};
+
+/// Where an action was moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Moved {
+    pub from: ProjectName,
+    pub to: ProjectName,
+    /// The action's ID in its new project, if it has one.
+    pub id: Option<ActionId>,
+    /// Links to the action that were pointed at its new project.
+    pub links: usize,
+}
+
+/// Moves the incomplete action that best matches `query` into the same section of the project that
+/// best matches `to`, and points the links to it at the new project.
+///
+/// Each change is shown as a word diff and written according to `mode`, all together or not at all,
+/// and can be put back with `gtd undo`. Returns `None` if nothing was written.
+pub fn move_action(
+    docs: &Documents,
+    query: &str,
+    to: &str,
+    mode: WriteMode,
+    today: NaiveDate,
+) -> Result<Option<Moved>, MoveError> {
+    let loader = docs.loader();
+    loader.ensure_files()?;
+    let (source, action) = docs.find_action(query)?;
+    let target = docs.find_project(to)?;
+    if source.name == target.name {
+        return Err(MoveError::SameProject);
+    }
+    let index = source
+        .actions
+        .actions()
+        .position(|(a, _)| a.span == action.span)
+        .expect("the action is in its project");
+    let (_, status) = source.actions.actions().nth(index).unwrap();
+
+    let id = match &action.id {
+        Some(id) if target.actions.get_action(id).is_some() => {
+            Some(ActionId::generate(&action.text.to_string(), |id| {
+                target.actions.get_action(id).is_some()
+            }))
+        }
+        id => id.clone(),
+    };
+
+    // The action is annotated and given its new ID where it is, finding it again by its position
+    // after each change, and then taken out.
+    let (source_text, project) = load(loader, &source.name)?;
+    let moved =
+        format!("{} from {}", today.format("%Y-%m-%d"), source.title()).replace(['(', ')'], "");
+    let (found, _) = project.actions.actions().nth(index).unwrap();
+    let mut text = edit::annotate_action(&source_text, found, MOVED_ANNOTATION, &moved);
+    if let Some(id) = &id {
+        let project = parse(loader, &source.name, &text)?;
+        let (found, _) = project.actions.actions().nth(index).unwrap();
+        text = edit::set_action_id(&text, found, id);
+    }
+    let project = parse(loader, &source.name, &text)?;
+    let (found, _) = project.actions.actions().nth(index).unwrap();
+    let (new_source_text, item) = edit::remove_action(&text, found);
+
+    let (target_text, target_project) = load(loader, &target.name)?;
+    let new_target_text = edit::add_actions(&target_text, &target_project, status, &item);
+
+    let mut transaction = Transaction::new(loader, format!("move-action {} --to {}", query, to));
+    transaction.write(&loader.project_path(&source.name), new_source_text)?;
+    transaction.write(&loader.project_path(&target.name), new_target_text)?;
+
+    let mut links = 0;
+    if let (Some(old_id), Some(new_id)) = (&action.id, &id) {
+        let old_link = (source.name.as_str(), old_id.as_str());
+        let new_link = (target.name.as_str(), new_id.as_str());
+        let references = docs
+            .backlinks(source.name.as_str())
+            .iter()
+            .filter(|r| r.link.block() == Some(old_id.as_str()));
+        for reference in references {
+            let path = loader.root_dir().join(&reference.source);
+            let text = transaction.read(&path)?.unwrap_or_default();
+            let retargeted = retarget_line(&text, reference.link.line, old_link, new_link);
+            if retargeted != text {
+                transaction.write(&path, retargeted)?;
+                links += 1;
+            }
+        }
+    }
+
+    if !transaction.review(mode)? {
+        return Ok(None);
+    }
+    transaction.commit()?;
+    Ok(Some(Moved {
+        from: source.name.clone(),
+        to: target.name.clone(),
+        id,
+        links,
+    }))
+}
+
+fn load(loader: &Loader, name: &ProjectName) -> Result<(String, Project), LoadProjectError> {
+    let text = Loader::load_checked::<LoadProjectError>(&loader.project_path(name))?;
+    let project = parse(loader, name, &text)?;
+    Ok((text, project))
+}
+
+fn parse(loader: &Loader, name: &ProjectName, text: &str) -> Result<Project, LoadProjectError> {
+    Ok(Project::parse_with_depths(
+        name.as_str(),
+        text,
+        loader.scheme(),
+        loader.depths(),
+    )?)
+}
+
+/// Points the links to the block `from` on the 1-based `line` of `text` at `to`, where both are a
+/// document name and a block ID. Wiki links use the name as it is, and Markdown links use it
+/// percent-encoded, with or without `.md`.
+fn retarget_line(text: &str, line: usize, from: (&str, &str), to: (&str, &str)) -> String {
+    let names = [
+        (from.0.to_string(), to.0.to_string()),
+        (percent_encode(from.0), percent_encode(to.0)),
+    ];
+    text.split_inclusive('\n')
+        .enumerate()
+        .map(|(i, text)| {
+            let mut text = text.to_string();
+            if i + 1 != line {
+                return text;
+            }
+            for (old, new) in names.iter() {
+                for separator in ["#^", ".md#^"] {
+                    text = text.replace(
+                        &format!("{}{}{}", old, separator, from.1),
+                        &format!("{}{}{}", new, separator, to.1),
+                    );
+                }
+            }
+            text
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum MoveError {
+    IoError(IoError),
+    ResolveError(ResolveError),
+    LoadProjectError(LoadProjectError),
+    TransactionError(TransactionError),
+    SameProject,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::ResolveError(e) => write!(f, "{}", e),
+            Self::LoadProjectError(e) => write!(f, "{}", e),
+            Self::TransactionError(e) => write!(f, "{}", e),
+            Self::SameProject => write!(f, "the action is already in that project"),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+impl From<IoError> for MoveError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<ResolveError> for MoveError {
+    fn from(error: ResolveError) -> Self {
+        Self::ResolveError(error)
+    }
+}
+
+impl From<LoadProjectError> for MoveError {
+    fn from(error: LoadProjectError) -> Self {
+        Self::LoadProjectError(error)
+    }
+}
+
+impl From<TransactionError> for MoveError {
+    fn from(error: TransactionError) -> Self {
+        Self::TransactionError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, test_dir::TempDir, undo::Undo};
+    use std::fs;
+
+    #[test]
+    fn links_are_pointed_at_the_new_project() {
+        let text = "- ![[Plan trip#^abcdef]]\n- [[Plan trip#^abcdef|flights]] and [b](Projects/Plan%20trip.md#^abcdef)\n- ![[Plan trip#^bcdefa]]\n";
+        assert_eq!(
+            retarget_line(text, 2, ("Plan trip", "abcdef"), ("Travel", "cdefab")),
+            "- ![[Plan trip#^abcdef]]\n- [[Travel#^cdefab|flights]] and [b](Projects/Travel.md#^cdefab)\n- ![[Plan trip#^bcdefa]]\n"
+        );
+    }
+
+    #[test]
+    fn action_is_moved_with_its_links() {
+        let dir = TempDir::new("relocate");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let trip = dir.join("Projects/202401010000 Plan trip.md");
+        let chores = dir.join("Projects/202401010001 Chores.md");
+        let home = dir.join("Contexts/@home.md");
+        fs::write(
+            &trip,
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights ^aaaaaa\n\n### Waiting\n\n- Passport @who(Office) ^bbbbbb\n",
+        )
+        .unwrap();
+        fs::write(
+            &chores,
+            "# Chores\n#in-progress\n\n## Actions\n\n### Active\n\n- Mow lawn ^bbbbbb\n",
+        )
+        .unwrap();
+        fs::write(
+            &home,
+            "# @home\n\n- ![[202401010000 Plan trip#^bbbbbb]]\n- ![[202401010001 Chores#^bbbbbb]]\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+
+        let moved = move_action(&docs, "bbbbbb", "Chores", WriteMode::Write, date);
+        assert!(matches!(moved, Err(MoveError::ResolveError(_))));
+        let moved = move_action(&docs, "Passport", "Chores", WriteMode::Write, date)
+            .unwrap()
+            .unwrap();
+        let id = moved.id.unwrap();
+        assert_ne!(id.as_str(), "bbbbbb");
+        assert_eq!(moved.links, 1);
+
+        assert_eq!(
+            fs::read_to_string(&trip).unwrap(),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights ^aaaaaa\n\n### Waiting\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&chores).unwrap(),
+            format!("# Chores\n#in-progress\n\n## Actions\n\n### Active\n\n- Mow lawn ^bbbbbb\n\n### Waiting\n\n- Passport @who(Office) @moved(2024-01-05 from Plan trip) {}\n", id)
+        );
+        assert_eq!(
+            fs::read_to_string(&home).unwrap(),
+            format!(
+                "# @home\n\n- ![[202401010001 Chores#{}]]\n- ![[202401010001 Chores#^bbbbbb]]\n",
+                id
+            )
+        );
+
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let moved = move_action(&docs, "Passport", "Chores", WriteMode::Write, date);
+        assert!(matches!(moved, Err(MoveError::SameProject)));
+        Undo::load(docs.loader())
+            .unwrap()
+            .unwrap()
+            .restore(docs.loader())
+            .unwrap();
+        assert!(fs::read_to_string(&trip).unwrap().contains("Passport"));
+    }
+}
@@ -0,0 +1,199 @@
+//! Reminders for actions that are due or whose deferral has ended.
+
+use crate::{
+    gtd::Documents,
+    project::{Action, ActionStatus, Project, Status},
+};
+use chrono::NaiveDate;
+use std::fmt;
+
+/// Why an action needs attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReminderKind {
+    Overdue,
+    Due,
+    /// The action was deferred until today.
+    Activated,
+}
+
+/// A reminder about an action.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Reminder {
+    pub kind: ReminderKind,
+    pub date: NaiveDate,
+    pub action: String,
+    pub project: String,
+}
+
+impl fmt::Display for Reminder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            ReminderKind::Overdue => "Overdue",
+            ReminderKind::Due => "Due today",
+            ReminderKind::Activated => "Starts today",
+        };
+        write!(f, "{}: {} ({})", kind, self.action, self.project)
+    }
+}
+
+/// Finds the reminders for incomplete actions in in-progress projects as of `today`, most urgent
+/// first.
+pub fn reminders(docs: &Documents, today: NaiveDate) -> Vec<Reminder> {
+    let mut reminders = docs
+        .projects()
+        .filter(|p| p.status == Status::InProgress)
+        .flat_map(|p| {
+            p.actions
+                .actions()
+                .filter(|(_, s)| *s != ActionStatus::Complete)
+                .filter_map(move |(a, _)| reminder(p, a, today))
+        })
+        .collect::<Vec<_>>();
+    reminders.sort();
+    reminders
+}
+
+fn reminder(project: &Project, action: &Action, today: NaiveDate) -> Option<Reminder> {
+    let (kind, date) = match (action.due, action.deferred) {
+        (Some(due), _) if due < today => (ReminderKind::Overdue, due),
+        (Some(due), _) if due == today => (ReminderKind::Due, due),
+        (_, Some(deferred)) if deferred == today => (ReminderKind::Activated, deferred),
+        _ => return None,
+    };
+
+    Some(Reminder {
+        kind,
+        date,
+        action: action.text.to_string(),
+        project: project.title().to_string(),
+    })
+}
+
+/// Prints the reminders as of `today`.
+pub fn remind(docs: &Documents, today: NaiveDate) {
+    for reminder in reminders(docs, today) {
+        println!("- {}", reminder);
+    }
+}
+
+/// Watching the GTD folder and showing desktop notifications for new reminders.
+#[cfg(feature = "notifications")]
+pub mod daemon {
+    use super::{reminders, Reminder};
+    use crate::{config::Config, gtd::Documents};
+    use chrono::Local;
+    use std::{
+        collections::HashSet,
+        io::Error as IoError,
+        path::Path,
+        process::Command,
+        thread,
+        time::{Duration, SystemTime},
+    };
+
+    /// Checks for reminders every `interval`, and whenever a document changes, notifying about
+    /// each reminder once. Never returns unless a notification can't be shown.
+    pub fn run(root_dir: &Path, config: &Config, interval: Duration) -> Result<(), IoError> {
+        let mut notified = HashSet::<Reminder>::new();
+        let mut last_check = None;
+        let mut docs = None::<Documents>;
+        let poll = interval.min(Duration::from_secs(5));
+
+        loop {
+            let changed = last_modified(root_dir);
+            let due_for_check = last_check.is_none_or(|t: SystemTime| {
+                t.elapsed().map_or(true, |e| e >= interval) || changed.is_some_and(|c| c > t)
+            });
+
+            if due_for_check {
+                last_check = Some(SystemTime::now());
+                if docs.as_mut().is_none_or(|d| d.reload().is_none()) {
+                    docs = Documents::load(root_dir, config);
+                }
+                if let Some(docs) = &docs {
+                    let today = Local::now().date_naive();
+                    for reminder in reminders(docs, today) {
+                        if notified.insert(reminder.clone()) {
+                            notify(&reminder)?;
+                        }
+                    }
+                }
+            }
+
+            thread::sleep(poll);
+        }
+    }
+
+    /// The latest modification time of any document in the GTD folder.
+    fn last_modified(root_dir: &Path) -> Option<SystemTime> {
+        ["Projects", "Contexts"]
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(root_dir.join(dir)).ok())
+            .flatten()
+            .filter_map(|e| e.ok()?.metadata().ok()?.modified().ok())
+            .max()
+    }
+
+    /// Shows a desktop notification using the platform's notification tool.
+    fn notify(reminder: &Reminder) -> Result<(), IoError> {
+        let body = reminder.to_string();
+        let mut command = if cfg!(target_os = "macos") {
+            let mut command = Command::new("osascript");
+            let script = format!(
+                "display notification {:?} with title \"gtd\"",
+                body.replace('\\', "\\\\")
+            );
+            command.args(["-e", &script]);
+            command
+        } else {
+            let mut command = Command::new("notify-send");
+            command.args(["gtd", &body]);
+            command
+        };
+        command.status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, d).unwrap()
+    }
+
+    fn project() -> Project {
+        let project_str = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack 📅 2024-01-05\n- Book hotel 📅 2024-01-03\n- Relax\n\n### Upcoming\n\n- Go 🛫 2024-01-05\n\n### Complete\n\n- Book flights 📅 2024-01-01\n";
+        Project::parse("197001010000 Plan trip", project_str).unwrap()
+    }
+
+    fn kinds(today: NaiveDate) -> Vec<(ReminderKind, String)> {
+        let project = project();
+        let mut reminders = project
+            .actions
+            .actions()
+            .filter(|(_, s)| *s != ActionStatus::Complete)
+            .filter_map(|(a, _)| reminder(&project, a, today))
+            .collect::<Vec<_>>();
+        reminders.sort();
+        reminders.into_iter().map(|r| (r.kind, r.action)).collect()
+    }
+
+    #[test]
+    fn due_overdue_and_activated_actions_are_found() {
+        assert_eq!(
+            kinds(date(5)),
+            vec![
+                (ReminderKind::Overdue, String::from("Book hotel")),
+                (ReminderKind::Due, String::from("Pack")),
+                (ReminderKind::Activated, String::from("Go")),
+            ]
+        );
+    }
+
+    #[test]
+    fn future_dates_arent_reminders() {
+        assert_eq!(kinds(date(2)), vec![]);
+    }
+}
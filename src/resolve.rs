@@ -0,0 +1,183 @@
+//! Fuzzy lookup of documents by name.
+
+use std::{error::Error, fmt};
+
+/// How well a query matches a name, from worst to best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Match {
+    /// All the characters of the query appear in order in the name.
+    Subsequence,
+    /// The query appears somewhere in the name.
+    Substring,
+    /// The name starts with the query.
+    Prefix,
+    /// The name is the query.
+    Exact,
+}
+
+/// Matches `query` against `name`, ignoring case.
+pub fn match_name(query: &str, name: &str) -> Option<Match> {
+    let query = query.to_lowercase();
+    let name = name.to_lowercase();
+
+    if name == query {
+        Some(Match::Exact)
+    } else if name.starts_with(&query) {
+        Some(Match::Prefix)
+    } else if name.contains(&query) {
+        Some(Match::Substring)
+    } else if is_subsequence(&query, &name) {
+        Some(Match::Subsequence)
+    } else {
+        None
+    }
+}
+
+fn is_subsequence(query: &str, name: &str) -> bool {
+    let mut chars = name.chars();
+    query.chars().all(|q| chars.any(|c| c == q))
+}
+
+/// Finds the single candidate whose names best match `query`.
+///
+/// `names` lists all the names a candidate can be referred to by, and `display` is used to list the
+/// candidates if more than one matches equally well.
+pub fn resolve<T, I, F, G>(
+    query: &str,
+    candidates: I,
+    names: F,
+    display: G,
+) -> Result<T, ResolveError>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(&T) -> Vec<&str>,
+    G: Fn(&T) -> String,
+{
+    let mut best = None;
+    let mut matches = Vec::new();
+
+    for candidate in candidates {
+        let quality = names(&candidate)
+            .into_iter()
+            .filter_map(|n| match_name(query, n))
+            .max();
+
+        let quality = match quality {
+            Some(q) => q,
+            None => continue,
+        };
+
+        if best.is_none_or(|b| quality > b) {
+            best = Some(quality);
+            matches.clear();
+        }
+
+        if best == Some(quality) {
+            matches.push(candidate);
+        }
+    }
+
+    match matches.len() {
+        0 => Err(ResolveError::NotFound(query.to_string())),
+        1 => Ok(matches.pop().unwrap()),
+        _ => {
+            let mut names = matches.iter().map(display).collect::<Vec<_>>();
+            names.sort();
+            Err(ResolveError::Ambiguous(query.to_string(), names))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    NotFound(String),
+    Ambiguous(String, Vec<String>),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound(query) => write!(f, "nothing matches \"{}\"", query),
+            Self::Ambiguous(query, names) => {
+                write!(f, "\"{}\" matches more than one document:", query)?;
+                for name in names {
+                    write!(f, "\n- {}", name)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ResolveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod match_name {
+        use super::*;
+
+        #[test]
+        fn exact_match_ignores_case() {
+            assert_eq!(match_name("plan trip", "Plan Trip"), Some(Match::Exact));
+        }
+
+        #[test]
+        fn prefix_is_matched() {
+            assert_eq!(match_name("plan", "Plan trip"), Some(Match::Prefix));
+        }
+
+        #[test]
+        fn substring_is_matched() {
+            assert_eq!(match_name("trip", "Plan trip"), Some(Match::Substring));
+        }
+
+        #[test]
+        fn subsequence_is_matched() {
+            assert_eq!(match_name("pltr", "Plan trip"), Some(Match::Subsequence));
+        }
+
+        #[test]
+        fn unrelated_name_doesnt_match() {
+            assert_eq!(match_name("taxes", "Plan trip"), None);
+        }
+    }
+
+    mod resolve {
+        use super::*;
+
+        fn run(query: &str, candidates: &[&'static str]) -> Result<&'static str, ResolveError> {
+            resolve(
+                query,
+                candidates.iter().copied(),
+                |c| vec![*c],
+                |c| c.to_string(),
+            )
+        }
+
+        #[test]
+        fn better_match_wins() {
+            let res = run("trip", &["Plan trip", "Trip report"]);
+            assert_eq!(res, Ok("Trip report"));
+        }
+
+        #[test]
+        fn equal_matches_are_ambiguous() {
+            let res = run("trip", &["Plan trip", "Book trip"]);
+            assert_eq!(
+                res,
+                Err(ResolveError::Ambiguous(
+                    String::from("trip"),
+                    vec![String::from("Book trip"), String::from("Plan trip")]
+                ))
+            );
+        }
+
+        #[test]
+        fn no_match_is_not_found() {
+            let res = run("taxes", &["Plan trip"]);
+            assert_eq!(res, Err(ResolveError::NotFound(String::from("taxes"))));
+        }
+    }
+}
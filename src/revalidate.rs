@@ -0,0 +1,214 @@
+//! Checking the documents a command changed, so a problem it introduces is pointed out straight
+//! away instead of at the next `gtd validate`.
+//!
+//! The documents are checked before the command runs and again after it, and only the problems
+//! that are new, in documents the command changed, are reported. Rules that look at the folder as
+//! a whole are reported whenever they find something new.
+
+use crate::{
+    action_list,
+    builder::ActionBuilder,
+    config::{Config, ContextLayout},
+    edit,
+    gtd::{Documents, LoadProjectError},
+    links::Linker,
+    project::{ActionStatus, Name as ProjectName},
+    validate::{self, Diagnostic, DocumentKind, Summary},
+};
+use chrono::NaiveDate;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The rule whose problems can be followed up by asking for a next action.
+const NO_ACTIVE_ACTIONS: &str = "GTD006";
+
+/// The problems in a GTD folder before a command changed it, and what its documents' files looked
+/// like.
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    summary: Summary,
+    files: BTreeMap<PathBuf, FileState>,
+}
+
+/// How big a file was and when it was last changed, which tells whether a command wrote it.
+type FileState = Option<(u64, SystemTime)>;
+
+impl Baseline {
+    /// Checks `docs` as of `today`, before a command changes them.
+    pub fn take(docs: &Documents, config: &Config, today: NaiveDate) -> Self {
+        Self {
+            summary: validate::check(docs, config, today, &Linker::default()),
+            files: files(docs),
+        }
+    }
+
+    /// The problems in `docs`, as they are after the command, that weren't there before it, in
+    /// documents whose files it changed or in the folder as a whole.
+    pub fn introduced(
+        &self,
+        docs: &Documents,
+        config: &Config,
+        today: NaiveDate,
+    ) -> Vec<Diagnostic> {
+        let summary = validate::check(docs, config, today, &Linker::default());
+        let changed = files(docs)
+            .into_iter()
+            .filter(|(path, state)| self.files.get(path) != Some(state))
+            .map(|(path, _)| path)
+            .collect::<HashSet<_>>();
+        let before = self
+            .summary
+            .diagnostics
+            .iter()
+            .map(key)
+            .collect::<HashSet<_>>();
+        summary
+            .diagnostics
+            .into_iter()
+            .filter(|d| !before.contains(&key(d)))
+            .filter(|d| match &d.document {
+                Some(document) => changed.contains(&file(docs, &document.kind, &document.path)),
+                None => true,
+            })
+            .collect()
+    }
+}
+
+/// What tells a problem apart from others.
+fn key(diagnostic: &Diagnostic) -> (&str, Option<&Path>, &str) {
+    (
+        diagnostic.rule.id,
+        diagnostic.document.as_ref().map(|d| d.path.as_path()),
+        &diagnostic.message,
+    )
+}
+
+/// The in-progress projects that `diagnostics` say have nothing left to do next.
+pub fn without_next_actions(diagnostics: &[Diagnostic]) -> Vec<ProjectName> {
+    let mut names = diagnostics
+        .iter()
+        .filter(|d| d.rule.id == NO_ACTIVE_ACTIONS)
+        .filter_map(|d| d.document.as_ref())
+        .filter(|d| d.kind == DocumentKind::Project)
+        .filter_map(|d| ProjectName::new(d.name.clone()))
+        .collect::<Vec<_>>();
+    names.dedup();
+    names
+}
+
+/// Adds `text` as an active action of the project `name`, created on `today`.
+pub fn add_next_action(
+    docs: &Documents,
+    name: &ProjectName,
+    text: &str,
+    today: NaiveDate,
+) -> Result<bool, LoadProjectError> {
+    let item = format!(
+        "- {}\n",
+        ActionBuilder::new(text).created(today).to_markdown()
+    );
+    docs.loader().update_project(name, |text, project| {
+        Some(edit::add_actions(
+            text,
+            project,
+            ActionStatus::Active,
+            &item,
+        ))
+    })
+}
+
+/// The state of the file of each document in `docs`.
+fn files(docs: &Documents) -> BTreeMap<PathBuf, FileState> {
+    let loader = docs.loader();
+    let projects = docs
+        .projects()
+        .map(|p| loader.project_path(&p.name))
+        .collect::<Vec<_>>();
+    let contexts = docs
+        .contexts()
+        .map(|c| file(docs, &DocumentKind::Context, &loader.context_path(&c.name)))
+        .collect::<Vec<_>>();
+    projects
+        .into_iter()
+        .chain(contexts)
+        .map(|path| {
+            let state = fs::metadata(&path)
+                .and_then(|m| Ok((m.len(), m.modified()?)))
+                .ok();
+            (path, state)
+        })
+        .collect()
+}
+
+/// The file that a document of `kind` at `path` is written in, which for contexts is the action
+/// list if they're all kept in one.
+fn file(docs: &Documents, kind: &DocumentKind, path: &Path) -> PathBuf {
+    match (kind, docs.context_layout()) {
+        (DocumentKind::Context, ContextLayout::List) => action_list::path(docs.loader().root_dir()),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    #[test]
+    fn only_new_problems_in_changed_documents_are_reported() {
+        let dir = TempDir::new("revalidate");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let trip = dir.join("Projects/202401010000 Plan trip.md");
+        fs::write(
+            &trip,
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights ^aaaaaa\n",
+        )
+        .unwrap();
+        // A problem that was already there isn't reported again.
+        fs::write(
+            dir.join("Projects/202401010001 Chores.md"),
+            "# Chores\n#in-progress\n\n## Actions\n\n### Upcoming\n\n- Mow lawn\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Contexts/@computer.md"),
+            "# @computer\n\n- ![[202401010000 Plan trip#^aaaaaa]]\n",
+        )
+        .unwrap();
+        let config = Config::default();
+        let today = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let docs = Documents::load(&dir, &config).unwrap();
+        let baseline = Baseline::take(&docs, &config, today);
+        assert!(baseline.introduced(&docs, &config, today).is_empty());
+
+        fs::write(
+            &trip,
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Complete\n\n- Book flights ^aaaaaa\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &config).unwrap();
+        let introduced = baseline.introduced(&docs, &config, today);
+        let rules = introduced.iter().map(|d| d.rule.id).collect::<Vec<_>>();
+        assert_eq!(rules, ["GTD006"]);
+        let names = without_next_actions(&introduced);
+        assert_eq!(
+            names,
+            [ProjectName::new("202401010000 Plan trip".into()).unwrap()]
+        );
+
+        assert!(add_next_action(&docs, &names[0], "Book hotel", today).unwrap());
+        let docs = Documents::load(&dir, &config).unwrap();
+        let rules = baseline
+            .introduced(&docs, &config, today)
+            .iter()
+            .map(|d| d.rule.id)
+            .collect::<Vec<_>>();
+        // The new action isn't in a context yet.
+        assert_eq!(rules, ["GTD012"]);
+    }
+}
@@ -0,0 +1,267 @@
+//! Checks to go through during a weekly review, and reviewing projects on a schedule of their
+//! own.
+//!
+//! A project with `review-every: 2w` in its frontmatter is due for review once that long has passed
+//! since `last-reviewed`, which is set whenever the project is visited by `gtd review --due`, or
+//! else since the project's ID.
+
+use crate::{
+    calendar::Calendar,
+    gtd::{Documents, LoadProjectError},
+    period::Period,
+    project::{Action, ActionStatus, NameScheme, Project, Status},
+    prompt,
+    timelog::format_duration,
+};
+use chrono::{Duration, NaiveDate};
+use std::{error::Error, fmt, io::Error as IoError};
+
+/// The frontmatter key holding how often a project should be reviewed.
+pub const REVIEW_EVERY_KEY: &str = "review-every";
+
+/// The frontmatter key holding the date a project was last reviewed.
+pub const LAST_REVIEWED_KEY: &str = "last-reviewed";
+
+/// An action due on a day that's fully booked with appointments.
+#[derive(Debug)]
+pub struct Collision<'a> {
+    pub project: &'a Project,
+    pub action: &'a Action,
+    pub due: NaiveDate,
+    pub busy: Duration,
+}
+
+/// Finds the incomplete actions due from `today` on whose due dates have at least `busy_hours` of
+/// events, soonest first.
+pub fn collisions<'a>(
+    docs: &'a Documents,
+    calendar: &Calendar,
+    today: NaiveDate,
+    busy_hours: u32,
+) -> Vec<Collision<'a>> {
+    let mut collisions = docs
+        .projects()
+        .filter(|p| p.status != Status::Complete)
+        .flat_map(|p| {
+            p.actions
+                .actions()
+                .filter(|(_, s)| *s != ActionStatus::Complete)
+                .map(move |(a, _)| (p, a))
+        })
+        .filter_map(|(project, action)| {
+            let due = action.due.filter(|d| *d >= today)?;
+            calendar
+                .is_fully_booked(due, busy_hours)
+                .then(|| Collision {
+                    project,
+                    action,
+                    due,
+                    busy: calendar.busy_time(due),
+                })
+        })
+        .collect::<Vec<_>>();
+    collisions.sort_by_key(|c| (c.due, c.action.text.to_string()));
+    collisions
+}
+
+/// Prints the problems to look at in a review as of `today`.
+pub fn review(docs: &Documents, calendar: Option<&Calendar>, busy_hours: u32, today: NaiveDate) {
+    let collisions = calendar
+        .map(|c| collisions(docs, c, today, busy_hours))
+        .unwrap_or_default();
+    if collisions.is_empty() {
+        println!("Nothing to review.");
+        return;
+    }
+
+    println!("Due on fully booked days:");
+    for collision in collisions {
+        println!(
+            "- {}: {} ({}), {} booked",
+            collision.due,
+            collision.action.text,
+            collision.project.title(),
+            format_duration(collision.busy)
+        );
+    }
+}
+
+/// A project that's due for review.
+#[derive(Debug)]
+pub struct Due<'a> {
+    pub project: &'a Project,
+    /// How often the project should be reviewed.
+    pub every: Period,
+    /// The date the project was last reviewed.
+    pub last_reviewed: NaiveDate,
+}
+
+/// The date `project` was last reviewed, if it can be found. Projects that have never been reviewed
+/// count from when they were created, according to `scheme`.
+pub fn last_reviewed(project: &Project, scheme: &NameScheme) -> Option<NaiveDate> {
+    project
+        .frontmatter
+        .get(LAST_REVIEWED_KEY)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .or_else(|| scheme.id_date(project.id()?))
+}
+
+/// Whether `project`, reviewed every `every`, is due for review as of `today`, returning when it
+/// was last reviewed if so.
+pub fn is_due(
+    project: &Project,
+    scheme: &NameScheme,
+    every: Period,
+    today: NaiveDate,
+) -> Option<NaiveDate> {
+    if project.status == Status::Complete {
+        return None;
+    }
+    let last_reviewed = last_reviewed(project, scheme)?;
+    (last_reviewed <= every.before(today)).then_some(last_reviewed)
+}
+
+/// The projects in `docs` that are due for review as of `today`, longest overdue first. Projects
+/// with an invalid `review-every` are left out.
+pub fn due(docs: &Documents, today: NaiveDate) -> Vec<Due<'_>> {
+    let scheme = docs.loader().scheme();
+    let mut due = docs
+        .projects()
+        .filter_map(|project| {
+            let every = project.frontmatter.get(REVIEW_EVERY_KEY)?.parse().ok()?;
+            let last_reviewed = is_due(project, scheme, every, today)?;
+            Some(Due {
+                project,
+                every,
+                last_reviewed,
+            })
+        })
+        .collect::<Vec<_>>();
+    due.sort_by(|a, b| {
+        (a.every.after(a.last_reviewed), &a.project.name)
+            .cmp(&(b.every.after(b.last_reviewed), &b.project.name))
+    });
+    due
+}
+
+/// Goes through the projects that are due for review as of `today` one at a time, showing each
+/// one's goal and open actions. Moving on from a project sets its `last-reviewed` to `today`.
+/// Returns the number of projects reviewed.
+pub fn review_due(docs: &Documents, today: NaiveDate) -> Result<usize, ReviewError> {
+    let due = due(docs, today);
+    if due.is_empty() {
+        println!("No projects are due for review.");
+        return Ok(0);
+    }
+
+    let mut reviewed = 0;
+    for (i, item) in due.iter().enumerate() {
+        let project = item.project;
+        println!(
+            "\n({}/{}) {}, last reviewed {}, reviewed every {}",
+            i + 1,
+            due.len(),
+            project.title(),
+            item.last_reviewed,
+            item.every
+        );
+        if let Some(goal) = &project.goal {
+            println!("Goal: {}", goal);
+        }
+        for (action, status) in project.actions.actions() {
+            match status {
+                ActionStatus::Active => println!("- {}", action.text),
+                ActionStatus::Waiting => println!("- {} (waiting)", action.text),
+                _ => {}
+            }
+        }
+
+        if prompt::choose("Done?", &["next", "quit"])? != Some(0) {
+            break;
+        }
+        docs.loader()
+            .update_project(&project.name, |text, project| {
+                let mut frontmatter = project.frontmatter.clone();
+                frontmatter.set(LAST_REVIEWED_KEY, &today.format("%Y-%m-%d").to_string());
+                Some(frontmatter.replace_in(text))
+            })?;
+        reviewed += 1;
+    }
+
+    println!("\nReviewed {} projects.", reviewed);
+    Ok(reviewed)
+}
+
+#[derive(Debug)]
+pub enum ReviewError {
+    IoError(IoError),
+    LoadProjectError(LoadProjectError),
+}
+
+impl fmt::Display for ReviewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReviewError::IoError(e) => write!(f, "{}", e),
+            ReviewError::LoadProjectError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ReviewError {}
+
+impl From<IoError> for ReviewError {
+    fn from(e: IoError) -> Self {
+        ReviewError::IoError(e)
+    }
+}
+
+impl From<LoadProjectError> for ReviewError {
+    fn from(e: LoadProjectError) -> Self {
+        ReviewError::LoadProjectError(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+    use std::fs;
+
+    #[test]
+    fn projects_past_their_cadence_are_due() {
+        let dir = TempDir::new("review");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        for (name, text) in [
+            (
+                "202401010000 Plan trip",
+                "---\nreview-every: 2w\nlast-reviewed: 2024-05-25\n---\n# Plan trip\n#in-progress\n",
+            ),
+            (
+                "202401010000 Do taxes",
+                "---\nreview-every: 1m\n---\n# Do taxes\n#in-progress\n",
+            ),
+            (
+                "202401010000 Paint shed",
+                "---\nreview-every: 1w\nlast-reviewed: 2024-06-05\n---\n# Paint shed\n#in-progress\n",
+            ),
+            (
+                "202401010000 Fix bike",
+                "---\nreview-every: 1w\n---\n# Fix bike\n#complete\n",
+            ),
+            ("202401010000 Learn piano", "# Learn piano\n#in-progress\n"),
+        ] {
+            fs::write(dir.join(format!("Projects/{}.md", name)), text).unwrap();
+        }
+
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        let titles = due(&docs, today)
+            .iter()
+            .map(|d| d.project.title())
+            .collect::<Vec<_>>();
+
+        assert_eq!(titles, vec!["Do taxes", "Plan trip"]);
+    }
+}
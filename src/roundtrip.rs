@@ -0,0 +1,356 @@
+//! Checking that documents survive being read and written back.
+//!
+//! A document is written back by rendering what was parsed from it with the [`builder`]s, the same
+//! way gtd writes documents it makes. Anything in a file that isn't part of what gtd reads, like
+//! the bullet style of a list or a code block in a goal, would be changed by that, so
+//! [`check`] shows exactly where, before commands that rewrite files are let loose on a vault.
+//!
+//! [`builder`]: crate::builder
+
+use crate::{
+    builder::{ContextBuilder, ProjectBuilder},
+    context::Context,
+    diff::{self, Hunk},
+    gtd::{LoadContextError, LoadProjectError, Loader},
+    project::Project,
+};
+use std::{
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// A file, and what it would be if it were parsed and written back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Roundtrip {
+    pub path: PathBuf,
+    pub original: String,
+    pub rewritten: String,
+}
+
+impl Roundtrip {
+    pub fn is_unchanged(&self) -> bool {
+        self.original == self.rewritten
+    }
+
+    /// The changes that writing the file back would make.
+    pub fn hunks(&self) -> Vec<Hunk> {
+        diff::hunks(&self.original, &self.rewritten)
+    }
+
+    /// Renders the changes like [`diff::render`], but with the lines they're on in the original
+    /// file.
+    pub fn render(&self, color: bool) -> String {
+        let mut out = String::new();
+        for hunk in self.hunks() {
+            out.push_str(&format!("@@ line {} @@\n", hunk.old_line));
+            out.push_str(&diff::render_lines(&hunk, color));
+        }
+        out
+    }
+}
+
+/// The Markdown that `project` would be written back as.
+pub fn project_markdown(project: &Project) -> String {
+    ProjectBuilder::from_project(project).to_markdown()
+}
+
+/// The Markdown that `context` would be written back as.
+pub fn context_markdown(context: &Context) -> String {
+    ContextBuilder::from_context(context).to_markdown()
+}
+
+/// Reads the file at `path` and works out what it would be if it were written back. It's read as a
+/// context if it's in the contexts folder, and as a project otherwise.
+pub fn check(loader: &Loader, path: &Path) -> Result<Roundtrip, RoundtripError> {
+    let name = path
+        .file_stem()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (original, rewritten) = if is_in(path, loader.context_dir()) {
+        let text = Loader::load_checked(path).map_err(RoundtripError::Context)?;
+        let context = Context::parse_with_scheme(name, &text, loader.scheme())
+            .map_err(|e| RoundtripError::Context(e.into()))?;
+        let rewritten = context_markdown(&context);
+        (text, rewritten)
+    } else {
+        let text = Loader::load_checked(path).map_err(RoundtripError::Project)?;
+        let project = Project::parse_with_scheme(name, &text, loader.scheme())
+            .map_err(|e| RoundtripError::Project(e.into()))?;
+        let rewritten = project_markdown(&project);
+        (text, rewritten)
+    };
+    Ok(Roundtrip {
+        path: path.to_owned(),
+        original,
+        rewritten,
+    })
+}
+
+/// Whether the file at `path` is directly in `dir`.
+fn is_in(path: &Path, dir: &Path) -> bool {
+    let parent = path.parent().and_then(|p| p.canonicalize().ok());
+    parent.is_some() && parent == dir.canonicalize().ok()
+}
+
+#[derive(Debug)]
+pub enum RoundtripError {
+    Project(LoadProjectError),
+    Context(LoadContextError),
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Project(e) => write!(f, "{}", e),
+            Self::Context(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for RoundtripError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::ActionBuilder,
+        estimate::Estimate,
+        project::{ActionStatus, Priority, Status},
+        test_dir::TempDir,
+    };
+    use chrono::NaiveDate;
+    use std::fs;
+
+    /// A small pseudo-random number generator, so that failures can be reproduced from the seed.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            // xorshift64*
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn chance(&mut self, percent: usize) -> bool {
+            self.below(100) < percent
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[self.below(items.len())]
+        }
+
+        fn date(&mut self) -> NaiveDate {
+            NaiveDate::from_ymd_opt(2024, 1 + self.below(12) as u32, 1 + self.below(28) as u32)
+                .unwrap()
+        }
+
+        fn id(&mut self) -> String {
+            let chars = b"abcdefghijklmnopqrstuvwxyz0123456789";
+            (0..6)
+                .map(|_| chars[self.below(chars.len())] as char)
+                .collect()
+        }
+    }
+
+    const WORDS: &[&str] = &[
+        "Book",
+        "flights",
+        "to",
+        "Rome",
+        "**soon**",
+        "_maybe_",
+        "`code`",
+        "~~old~~",
+        "[site](https://example.com)",
+        "[[Packing list]]",
+        "café",
+        "50%",
+        "a+b",
+        "(draft)",
+        "&",
+        "x#1",
+        "`#notatag`",
+    ];
+
+    const TAGS: &[&str] = &["phone", "errand", "area/home", "#deep-work", "a1"];
+
+    fn text(rng: &mut Rng) -> String {
+        let count = 1 + rng.below(5);
+        (0..count)
+            .map(|_| rng.pick(WORDS))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn arbitrary_action(rng: &mut Rng) -> ActionBuilder {
+        let mut action = ActionBuilder::new(text(rng));
+        for _ in 0..rng.below(3) {
+            action = action.tag(rng.pick(TAGS));
+        }
+        let priorities = [
+            Priority::Normal,
+            Priority::Low,
+            Priority::High,
+            Priority::Highest,
+        ];
+        action = action.priority(priorities[rng.below(priorities.len())]);
+        if rng.chance(30) {
+            action = action.id(rng.id());
+        }
+        if rng.chance(20) {
+            action = action.who(rng.pick(&["Alice", "Bob Smith", "the bank"]));
+        }
+        if rng.chance(10) {
+            action = action.uid(rng.id());
+        }
+        if rng.chance(10) {
+            action = action.issue(rng.pick(&["kstrohbeck/gtd#12", "OPS-7", "a/b#1x"]));
+        }
+        if rng.chance(5) {
+            action =
+                action.moved(rng.pick(&["2024-01-05 from Plan trip", "2023-12-31 from Taxes"]));
+        }
+        if rng.chance(20) {
+            action = action.estimate(Estimate::from_minutes(15 * (1 + rng.below(8) as u32)));
+        }
+        let dates: [fn(ActionBuilder, NaiveDate) -> ActionBuilder; 5] = [
+            ActionBuilder::created,
+            ActionBuilder::deferred,
+            ActionBuilder::due,
+            ActionBuilder::completed,
+            ActionBuilder::activated,
+        ];
+        for set in dates.iter() {
+            if rng.chance(15) {
+                action = set(action, rng.date());
+            }
+        }
+        action
+    }
+
+    fn arbitrary_project(rng: &mut Rng) -> ProjectBuilder {
+        let name = format!("2024010112{:02} {}", rng.below(60), text(rng));
+        let statuses = [Status::InProgress, Status::Someday, Status::Complete];
+        let mut project = ProjectBuilder::new(name).status(statuses[rng.below(statuses.len())]);
+        if rng.chance(20) {
+            project = project.frontmatter("progress", &rng.below(100).to_string());
+        }
+        for _ in 0..rng.below(3) {
+            project = project.tag(rng.pick(TAGS));
+        }
+        if rng.chance(60) {
+            project = project.goal(text(rng));
+        }
+        if rng.chance(30) {
+            project = project.info(text(rng));
+        }
+        if rng.chance(20) {
+            let completed = if rng.chance(50) {
+                Some(rng.date())
+            } else {
+                None
+            };
+            project = project.outcome(text(rng), completed);
+        }
+        let statuses = [
+            ActionStatus::Active,
+            ActionStatus::Waiting,
+            ActionStatus::Upcoming,
+            ActionStatus::Complete,
+        ];
+        for _ in 0..rng.below(6) {
+            let status = statuses[rng.below(statuses.len())];
+            project = project.action(status, arbitrary_action(rng));
+        }
+        project
+    }
+
+    fn arbitrary_context(rng: &mut Rng) -> ContextBuilder {
+        let mut context = ContextBuilder::new(rng.pick(&["@computer", "@home", "@errands"]));
+        for _ in 0..rng.below(6) {
+            if rng.chance(15) {
+                context = context.group(text(rng));
+            }
+            context = if rng.chance(50) {
+                context.literal(text(rng))
+            } else {
+                let project = format!("2024010112{:02} {}", rng.below(60), rng.pick(WORDS));
+                context.reference(project, rng.id())
+            };
+        }
+        context
+    }
+
+    #[test]
+    fn projects_are_written_back_as_they_were_read() {
+        let mut built = 0;
+        for seed in 1..500 {
+            let builder = arbitrary_project(&mut Rng(seed));
+            // Models that can't be written so they're read back the same are rejected by
+            // `build`, which is what's tested in the builder itself.
+            let project = match builder.build() {
+                Ok(project) => project,
+                Err(_) => continue,
+            };
+            built += 1;
+            let text = builder.to_markdown();
+            assert_eq!(project_markdown(&project), text, "seed {}", seed);
+            let reread = Project::parse(project.name.as_str(), &text).unwrap();
+            assert_eq!(reread, project, "seed {}", seed);
+        }
+        assert!(built > 250, "only {} projects were built", built);
+    }
+
+    #[test]
+    fn contexts_are_written_back_as_they_were_read() {
+        let mut built = 0;
+        for seed in 1..500 {
+            let builder = arbitrary_context(&mut Rng(seed));
+            let context = match builder.build() {
+                Ok(context) => context,
+                Err(_) => continue,
+            };
+            built += 1;
+            assert_eq!(
+                context_markdown(&context),
+                builder.to_markdown(),
+                "seed {}",
+                seed
+            );
+        }
+        assert!(built > 250, "only {} contexts were built", built);
+    }
+
+    #[test]
+    fn check_shows_where_a_file_would_change() {
+        let dir = TempDir::new("roundtrip");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let loader = Loader::new(dir.to_path_buf(), Default::default());
+
+        let project = dir.join("Projects/202401011200 Plan trip.md");
+        fs::write(
+            &project,
+            "# Plan trip\n#in-progress\n\n## Goal\n\nSee Rome.\n\n> [!warning] Visa\n> Apply early.\n>\n> Really.\n\nAnd Florence.\n\n## Actions\n\n### Active\n\n* Book flights ^abc123\n",
+        )
+        .unwrap();
+        let roundtrip = check(&loader, &project).unwrap();
+        assert!(!roundtrip.is_unchanged());
+        assert_eq!(
+            roundtrip.render(false),
+            "@@ line 19 @@\n[-*-]{+-+} Book flights ^abc123\n"
+        );
+
+        let context = dir.join("Contexts/@home.md");
+        let text = "# @home\n\n- Water plants\n- ![[202401011200 Plan trip#^abc123]]\n";
+        fs::write(&context, text).unwrap();
+        assert!(check(&loader, &context).unwrap().is_unchanged());
+    }
+}
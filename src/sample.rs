@@ -0,0 +1,349 @@
+//! Randomly generated GTD folders, for demos, benchmarks, and tests.
+//!
+//! A [`Sample`] describes how big a folder to generate, and its seed, so the same sample always
+//! generates the same documents. Unless it's asked for broken links, the documents it generates
+//! pass `validate`.
+
+use crate::{
+    builder::{ActionBuilder, ContextBuilder, ProjectBuilder},
+    context::Name as ContextName,
+    gtd::Loader,
+    project::{ActionStatus, Name as ProjectName, Priority, Status},
+    rng::Rng,
+};
+use chrono::{DateTime, Duration, Local};
+use std::{collections::HashSet, fs, io::Error as IoError, path::PathBuf};
+
+const VERBS: &[&str] = &[
+    "Plan",
+    "Organize",
+    "Renovate",
+    "Fix",
+    "Research",
+    "Set up",
+    "Clean out",
+    "Sort out",
+];
+
+const SUBJECTS: &[&str] = &[
+    "summer vacation",
+    "kitchen",
+    "garage",
+    "tax return",
+    "website",
+    "birthday party",
+    "vegetable garden",
+    "team offsite",
+    "household budget",
+    "spare bedroom",
+    "car insurance",
+    "photo library",
+];
+
+/// What sets apart projects about the same subject, once every subject has been used. No two
+/// subjects, with or without one of these, are close enough for their actions to be taken for
+/// duplicates.
+const PLACES: &[&str] = &[
+    "at the cabin",
+    "for the school fundraiser",
+    "at mom's house",
+    "for the intern",
+    "in the basement",
+    "for the book club",
+    "downtown",
+    "for the annual conference",
+    "at the rental property",
+    "for the neighbours",
+    "by the beach",
+    "for the wedding",
+];
+
+const GOALS: &[&str] = &[
+    "Get it done before the end of the month.",
+    "Have it sorted out, so it stops coming up.",
+    "Make it something everyone's happy with.",
+    "Keep it simple, and don't spend too much.",
+];
+
+/// The actions of a project, with `{}` for its subject.
+const ACTIONS: &[&str] = &[
+    "Research options for the {}",
+    "Get quotes for the {}",
+    "Email Sam about the {}",
+    "Draft a plan for the {}",
+    "Call about the {}",
+    "Order supplies for the {}",
+    "Review notes on the {}",
+    "Block out time for the {}",
+];
+
+const AREAS: &[&str] = &["area/home", "area/work", "area/health", "area/money"];
+
+const PEOPLE: &[&str] = &["Sam", "Alex", "Jordan", "the landlord"];
+
+const CONTEXTS: &[&str] = &[
+    "@computer",
+    "@errands",
+    "@home",
+    "@calls",
+    "@office",
+    "@online",
+    "@agenda",
+    "@anywhere",
+];
+
+const LITERALS: &[&str] = &[
+    "Buy milk",
+    "Water the plants",
+    "Back up the laptop",
+    "Return library books",
+    "Renew passport",
+    "Pick up dry cleaning",
+];
+
+/// How big a GTD folder to generate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub projects: usize,
+    pub contexts: usize,
+    /// How many actions in contexts link to actions or projects that don't exist. They're only
+    /// added if there are contexts to add them to.
+    pub broken_links: usize,
+    pub seed: u64,
+}
+
+impl Default for Sample {
+    fn default() -> Self {
+        Self {
+            projects: 5,
+            contexts: 3,
+            broken_links: 0,
+            seed: 0,
+        }
+    }
+}
+
+impl Sample {
+    /// The documents of the sample for the GTD folder of `loader`, as the path and text of each
+    /// file. Project IDs count back from `now`, a minute apart.
+    pub fn documents(&self, loader: &Loader, now: &DateTime<Local>) -> Vec<(PathBuf, String)> {
+        let mut rng = Rng::new(self.seed);
+        let scheme = loader.scheme();
+        let today = now.date_naive();
+        let mut ids = HashSet::new();
+
+        let context_names = (0..self.contexts)
+            .map(|i| match CONTEXTS.get(i) {
+                Some(name) => name.to_string(),
+                None => format!("@context-{}", i + 1),
+            })
+            .collect::<Vec<_>>();
+        let mut contexts = context_names
+            .iter()
+            .map(|name| {
+                let mut context = ContextBuilder::new(name.as_str()).scheme(scheme);
+                for _ in 0..rng.below(3) {
+                    context = context.literal(*rng.pick(LITERALS));
+                }
+                context
+            })
+            .collect::<Vec<_>>();
+
+        let mut documents = Vec::new();
+        let mut in_progress = Vec::new();
+        for i in 0..self.projects {
+            let subject = subject(i);
+            let title = format!("{} {}", rng.pick(VERBS), subject);
+            let id = scheme.generate_id(&(*now - Duration::minutes(i as i64)));
+            let name = match scheme.format(&id, &title) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let status = match rng.below(100) {
+                0..=69 => Status::InProgress,
+                70..=84 => Status::Someday,
+                _ => Status::Complete,
+            };
+            let mut project = ProjectBuilder::new(name.as_str())
+                .scheme(scheme)
+                .status(status)
+                .goal(*rng.pick(GOALS));
+            if rng.chance(80) {
+                project = project.tag(*rng.pick(AREAS));
+            }
+
+            let mut templates = ACTIONS.to_vec();
+            for n in 0..1 + rng.below(5) {
+                let template = templates.remove(rng.below(templates.len()));
+                let action_id = unique_id(&mut rng, &mut ids);
+                let mut action =
+                    ActionBuilder::new(template.replace("{}", &subject)).id(action_id.as_str());
+                let action_status = match status {
+                    Status::Complete => {
+                        action = action.completed(today - Duration::days(rng.below(10) as i64));
+                        ActionStatus::Complete
+                    }
+                    Status::InProgress if n == 0 || rng.chance(30) => ActionStatus::Active,
+                    Status::InProgress if rng.chance(20) => {
+                        action = action.who(*rng.pick(PEOPLE));
+                        ActionStatus::Waiting
+                    }
+                    _ => ActionStatus::Upcoming,
+                };
+                if action_status != ActionStatus::Complete && rng.chance(20) {
+                    action = action.due(today + Duration::days(1 + rng.below(30) as i64));
+                }
+                if rng.chance(10) {
+                    action = action.priority(Priority::High);
+                }
+                if action_status == ActionStatus::Active && !contexts.is_empty() {
+                    let i = rng.below(contexts.len());
+                    contexts[i] = contexts[i]
+                        .clone()
+                        .reference(name.as_str(), action_id.as_str());
+                }
+                project = project.action(action_status, action);
+            }
+            documents.push((loader.project_path(&name), project.to_markdown()));
+            if status == Status::InProgress {
+                in_progress.push(name);
+            }
+        }
+
+        for _ in 0..self.broken_links {
+            if contexts.is_empty() {
+                break;
+            }
+            let action_id = unique_id(&mut rng, &mut ids);
+            let project = match in_progress.is_empty() || rng.chance(50) {
+                true => missing_project(loader, now),
+                false => Some(rng.pick(&in_progress).clone()),
+            };
+            if let Some(project) = project {
+                let i = rng.below(contexts.len());
+                contexts[i] = contexts[i].clone().reference(project.as_str(), action_id);
+            }
+        }
+
+        for (name, context) in context_names.iter().zip(contexts) {
+            let path = loader.context_path(&ContextName::new(name.clone()));
+            documents.push((path, context.to_markdown()));
+        }
+        documents
+    }
+
+    /// Writes the documents of the sample to the GTD folder of `loader`, replacing any files that
+    /// are already at their paths, and returns how many were written.
+    pub fn write(&self, loader: &Loader, now: &DateTime<Local>) -> Result<usize, IoError> {
+        loader.ensure_files()?;
+        fs::create_dir_all(loader.project_dir())?;
+        fs::create_dir_all(loader.context_dir())?;
+        let documents = self.documents(loader, now);
+        for (path, text) in &documents {
+            fs::write(path, text)?;
+        }
+        Ok(documents.len())
+    }
+}
+
+/// The subject of the `i`th project. Past the first 156 projects, subjects are only numbered, so
+/// some of their actions are taken for duplicates.
+fn subject(i: usize) -> String {
+    let subject = SUBJECTS[i % SUBJECTS.len()];
+    let round = i / SUBJECTS.len();
+    match round {
+        0 => subject.to_string(),
+        _ if round <= PLACES.len() => format!("{} {}", subject, PLACES[round - 1]),
+        _ => format!("{} {}", subject, round - PLACES.len() + 1),
+    }
+}
+
+/// An action ID that isn't in `ids`, which it's added to.
+fn unique_id(rng: &mut Rng, ids: &mut HashSet<String>) -> String {
+    let chars = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    loop {
+        let id = (0..6).map(|_| *rng.pick(chars) as char).collect::<String>();
+        if ids.insert(id.clone()) {
+            return id;
+        }
+    }
+}
+
+/// The name of a project that isn't in the sample, since its ID is from after all of theirs.
+fn missing_project(loader: &Loader, now: &DateTime<Local>) -> Option<ProjectName> {
+    let scheme = loader.scheme();
+    let id = scheme.generate_id(&(*now + Duration::days(1)));
+    scheme.format(&id, "Deleted project")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        gtd::Documents,
+        links::Linker,
+        test_dir::TempDir,
+        validate::{self, Severity},
+    };
+
+    #[test]
+    fn samples_pass_validation() {
+        let config = Config::default();
+        let now = Local::now();
+        for seed in 0..10 {
+            let dir = TempDir::new("sample");
+            let loader = Loader::from_config(dir.to_path_buf(), &config);
+            let sample = Sample {
+                projects: 30,
+                contexts: 10,
+                broken_links: 0,
+                seed,
+            };
+            assert_eq!(sample.write(&loader, &now).unwrap(), 40);
+
+            let docs = Documents::load(&dir, &config).unwrap();
+            assert_eq!(docs.projects().count(), 30);
+            assert_eq!(docs.contexts().count(), 10);
+            let summary = validate::check(&docs, &config, now.date_naive(), &Linker::default());
+            assert_eq!(summary.diagnostics, Vec::new(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn broken_links_are_reported() {
+        let config = Config::default();
+        let now = Local::now();
+        let dir = TempDir::new("sample");
+        let loader = Loader::from_config(dir.to_path_buf(), &config);
+        let sample = Sample {
+            broken_links: 4,
+            ..Sample::default()
+        };
+        sample.write(&loader, &now).unwrap();
+
+        let docs = Documents::load(&dir, &config).unwrap();
+        let summary = validate::check(&docs, &config, now.date_naive(), &Linker::default());
+        assert_eq!(summary.count(Severity::Error), 4);
+    }
+
+    #[test]
+    fn same_seed_generates_same_documents() {
+        let loader = Loader::from_config(PathBuf::from("gtd"), &Config::default());
+        let now = Local::now();
+        let sample = Sample {
+            seed: 7,
+            ..Sample::default()
+        };
+        assert_eq!(
+            sample.documents(&loader, &now),
+            sample.documents(&loader, &now)
+        );
+        let other = Sample { seed: 8, ..sample };
+        assert_ne!(
+            sample.documents(&loader, &now),
+            other.documents(&loader, &now)
+        );
+    }
+}
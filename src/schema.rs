@@ -0,0 +1,383 @@
+//! JSON Schemas for the JSON that gtd prints, so other tools can rely on its shape.
+//!
+//! The schemas are built from the same lists of statuses, priorities, and rules that the output
+//! uses, so they can't fall out of date with it.
+
+use crate::{
+    export::{action_status_name, priority_name, ACTION_STATUSES, PRIORITIES, STATUSES},
+    validate::{DocumentKind, Severity, RULES},
+};
+use serde_json::{json, Value};
+use std::{fmt, str::FromStr};
+
+/// The version of the JSON formats, which changes when they change in a way that could break
+/// tools reading them.
+pub const VERSION: u32 = 1;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// The JSON formats that have schemas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The output of `gtd export`.
+    Export,
+    /// The output of `gtd validate --format json`.
+    Diagnostics,
+    /// The output of `gtd diff --format json`.
+    Diff,
+}
+
+impl Format {
+    pub fn schema(&self) -> Value {
+        match self {
+            Self::Export => export(),
+            Self::Diagnostics => diagnostics(),
+            Self::Diff => diff(),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Export => write!(f, "export"),
+            Self::Diagnostics => write!(f, "diagnostics"),
+            Self::Diff => write!(f, "diff"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "export" => Ok(Self::Export),
+            "diagnostics" => Ok(Self::Diagnostics),
+            "diff" => Ok(Self::Diff),
+            _ => Err(format!(
+                "\"{}\" is not a schema, like \"export\", \"diagnostics\", or \"diff\"",
+                s
+            )),
+        }
+    }
+}
+
+/// The schema of the output of `gtd export`.
+pub fn export() -> Value {
+    let statuses = STATUSES.iter().map(|s| s.tag()).collect::<Vec<_>>();
+    let action_statuses = ACTION_STATUSES
+        .iter()
+        .map(|s| action_status_name(*s))
+        .collect::<Vec<_>>();
+    let priorities = PRIORITIES
+        .iter()
+        .map(|p| priority_name(*p))
+        .collect::<Vec<_>>();
+
+    let action = object(json!({
+        "id": nullable("string"),
+        "text": { "type": "string" },
+        "status": { "enum": action_statuses },
+        "tags": strings(),
+        "priority": { "enum": priorities },
+        "due": date(),
+        "deferred": date(),
+        "completed": date(),
+        "created": date(),
+        "who": nullable("string"),
+        "issue": nullable("string"),
+        "moved": nullable("string"),
+        "estimate_minutes": nullable("integer"),
+    }));
+    let table = object(json!({
+        "section": { "enum": ["goal", "info"] },
+        "alignments": {
+            "type": "array",
+            "items": { "enum": ["left", "center", "right", null] },
+        },
+        "header": strings(),
+        "rows": { "type": "array", "items": strings() },
+    }));
+    let footnote = object(json!({
+        "label": { "type": "string" },
+        "text": { "type": "string" },
+    }));
+    let project = object(json!({
+        "name": { "type": "string" },
+        "id": nullable("string"),
+        "title": { "type": "string" },
+        "status": { "enum": statuses },
+        "tags": strings(),
+        "goal": nullable("string"),
+        "info": nullable("string"),
+        "tables": { "type": "array", "items": table },
+        "footnotes": { "type": "array", "items": footnote },
+        "actions": { "type": "array", "items": action },
+    }));
+    let context_action = json!({
+        "oneOf": [
+            object(json!({
+                "text": { "type": "string" },
+                "group": nullable("string"),
+            })),
+            object(json!({
+                "project": { "type": "string" },
+                "action_id": { "type": "string" },
+                "group": nullable("string"),
+            })),
+        ],
+    });
+    let context = object(json!({
+        "name": { "type": "string" },
+        "actions": { "type": "array", "items": context_action },
+    }));
+
+    document(
+        "export",
+        "The projects and contexts in a GTD folder, as printed by `gtd export`.",
+        json!({
+            "version": { "const": VERSION },
+            "projects": { "type": "array", "items": project },
+            "contexts": { "type": "array", "items": context },
+        }),
+    )
+}
+
+/// The schema of the output of `gtd validate --format json`.
+pub fn diagnostics() -> Value {
+    let rules = RULES.iter().map(|r| r.id).collect::<Vec<_>>();
+    let names = RULES.iter().map(|r| r.name).collect::<Vec<_>>();
+    let severities = [Severity::Error, Severity::Warning]
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>();
+    let kinds = [DocumentKind::Project, DocumentKind::Context]
+        .iter()
+        .map(|k| k.to_string())
+        .collect::<Vec<_>>();
+
+    let document_fields = object(json!({
+        "kind": { "enum": kinds },
+        "name": { "type": "string" },
+        "path": { "type": "string" },
+    }));
+    let document_schema = json!({
+        "oneOf": [{ "type": "null" }, document_fields],
+    });
+    let diagnostic = object(json!({
+        "rule": { "enum": rules },
+        "name": { "enum": names },
+        "severity": { "enum": severities },
+        "fixable": { "type": "boolean" },
+        "fix": nullable("string"),
+        "document": document_schema,
+        "related": { "type": "array", "items": document_fields },
+        "message": { "type": "string" },
+    }));
+
+    document(
+        "diagnostics",
+        "The problems found by `gtd validate --format json`.",
+        json!({
+            "version": { "const": VERSION },
+            "files": { "type": "integer" },
+            "errors": { "type": "integer" },
+            "warnings": { "type": "integer" },
+            "fixable": { "type": "integer" },
+            "diagnostics": { "type": "array", "items": diagnostic },
+        }),
+    )
+}
+
+/// The schema of the output of `gtd diff --format json`.
+pub fn diff() -> Value {
+    let action_statuses = ACTION_STATUSES
+        .iter()
+        .map(|s| action_status_name(*s))
+        .collect::<Vec<_>>();
+
+    let action = object(json!({
+        "project": { "type": "string" },
+        "id": nullable("string"),
+        "text": { "type": "string" },
+        "status": { "enum": action_statuses },
+    }));
+    let actions = json!({ "type": "array", "items": action });
+    let moved = object(json!({ "from": action, "to": action }));
+    let project = object(json!({
+        "name": { "type": "string" },
+        "title": { "type": "string" },
+    }));
+
+    document(
+        "diff",
+        "What changed since an earlier export, as printed by `gtd diff --format json`.",
+        json!({
+            "version": { "const": VERSION },
+            "completed": actions,
+            "added": actions,
+            "moved": { "type": "array", "items": moved },
+            "removed": actions,
+            "stalled": { "type": "array", "items": project },
+        }),
+    )
+}
+
+/// A top-level schema named `name` for an object with `properties`.
+fn document(name: &str, description: &str, properties: Value) -> Value {
+    let mut schema = object(properties);
+    schema["$schema"] = json!(DRAFT);
+    schema["$id"] = json!(format!("gtd-{}-v{}.json", name, VERSION));
+    schema["title"] = json!(format!("gtd {}", name));
+    schema["description"] = json!(description);
+    schema
+}
+
+/// A schema for an object that has exactly `properties`.
+fn object(properties: Value) -> Value {
+    let required = properties
+        .as_object()
+        .map(|p| p.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn nullable(kind: &str) -> Value {
+    json!({ "type": [kind, "null"] })
+}
+
+fn strings() -> Value {
+    json!({ "type": "array", "items": { "type": "string" } })
+}
+
+fn date() -> Value {
+    json!({ "type": ["string", "null"], "format": "date" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        gtd::Documents,
+        snapshot::{Snapshot, SnapshotDiff},
+        test_dir::TempDir,
+        validate,
+    };
+    use std::fs;
+
+    /// Checks `value` against the parts of JSON Schema that the schemas here use.
+    fn conforms(value: &Value, schema: &Value) -> bool {
+        if let Some(options) = schema.get("oneOf").and_then(Value::as_array) {
+            return options.iter().filter(|s| conforms(value, s)).count() == 1;
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            return options.contains(value);
+        }
+        if let Some(constant) = schema.get("const") {
+            return value == constant;
+        }
+
+        let types = match &schema["type"] {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => return true,
+        };
+        let is_type = |t: &str| match t {
+            "null" => value.is_null(),
+            "string" => value.is_string(),
+            "integer" => value.is_u64() || value.is_i64(),
+            "boolean" => value.is_boolean(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => false,
+        };
+        if !types.into_iter().any(is_type) {
+            return false;
+        }
+
+        match value {
+            Value::Array(items) => items.iter().all(|i| conforms(i, &schema["items"])),
+            Value::Object(fields) => {
+                let properties = schema["properties"].as_object().unwrap();
+                let has_required = schema["required"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .all(|k| fields.contains_key(k.as_str().unwrap()));
+                has_required
+                    && fields
+                        .iter()
+                        .all(|(k, v)| properties.get(k).is_some_and(|s| conforms(v, s)))
+            }
+            _ => true,
+        }
+    }
+
+    #[test]
+    fn output_conforms_to_schemas() {
+        let dir = TempDir::new("schema");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress #area/home\n\n## Goal\n\nRelax.[^1]\n\n[^1]: Really.\n\n## Info\n\n| Item | Price |\n|--|--:|\n| Flights | 400 |\n\n## Actions\n\n### Active\n\n- Book flights ⏫ @estimate(1h) @issue(acme/trips#4) 📅 2024-01-05 ^abcdef\n\n### Complete\n\n- Pick dates ✅ 2024-01-02\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Contexts/@home.md"),
+            "# @home\n\n- ![[202401010000 Plan trip#^abcdef]]\n- ![[202401010000 Plan trip#^ffffff]]\n\n## Chores\n\n- Water plants\n",
+        )
+        .unwrap();
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+
+        let exported = crate::export::export(&docs, &crate::filter::Filter::default());
+        assert!(conforms(&exported, &export()), "{:#}", exported);
+        assert_eq!(
+            exported["projects"][0]["tables"],
+            json!([{
+                "section": "info",
+                "alignments": [null, "right"],
+                "header": ["Item", "Price"],
+                "rows": [["Flights", "400"]],
+            }])
+        );
+        assert_eq!(exported["projects"][0]["goal"], json!("Relax.[^1]"));
+        assert_eq!(
+            exported["projects"][0]["footnotes"],
+            json!([{ "label": "1", "text": "Really." }])
+        );
+
+        let summary = validate::ValidatorRunner::new()
+            .for_all_context_actions(validate::rule("GTD009").unwrap(), |_: &_, _: Option<&_>| {
+                Err("the action isn't in the project".into())
+            })
+            .with_ad_hoc(validate::rule("GTD013").unwrap(), |_: &_, _: &_| {
+                vec![String::from("a file doesn't follow the naming scheme")]
+            })
+            .run(&docs);
+        let diagnostics_json = validate::to_json(&summary, &dir);
+        assert!(
+            conforms(&diagnostics_json, &diagnostics()),
+            "{:#}",
+            diagnostics_json
+        );
+
+        let old = Snapshot::from_value(json!({ "version": VERSION, "projects": [] })).unwrap();
+        let new = Snapshot::of(&docs);
+        let diff_json = SnapshotDiff::new(&old, &new).to_json();
+        assert!(conforms(&diff_json, &diff()), "{:#}", diff_json);
+    }
+
+    #[test]
+    fn unknown_fields_dont_conform() {
+        let value = json!({ "version": VERSION, "projects": [], "contexts": [], "extra": 1 });
+        assert!(!conforms(&value, &export()));
+    }
+}
@@ -0,0 +1,230 @@
+//! Selecting groups of projects for commands that change many of them at once.
+
+use crate::{
+    gtd::Documents,
+    period::Period,
+    project::{NameScheme, Project, Status},
+    prompt::confirm,
+    tag::Tag,
+};
+use chrono::NaiveDate;
+use std::{convert::Infallible, error::Error, fmt, io::Error as IoError, str::FromStr};
+
+/// A pattern like `Trip*`, where `*` matches any run of characters and `?` matches any single
+/// character. Case is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob(String);
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        Self(pattern.to_lowercase())
+    }
+
+    /// Whether the whole of `name` matches the pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        let pattern = self.0.chars().collect::<Vec<_>>();
+        let name = name.to_lowercase().chars().collect::<Vec<_>>();
+
+        // Match greedily, going back to the last `*` and letting it match one more character
+        // whenever the rest of the pattern fails.
+        let (mut p, mut n) = (0, 0);
+        let mut star = None;
+        while n < name.len() {
+            match pattern.get(p) {
+                Some('*') => {
+                    star = Some((p, n));
+                    p += 1;
+                }
+                Some(&c) if c == '?' || c == name[n] => {
+                    p += 1;
+                    n += 1;
+                }
+                _ => match star {
+                    Some((star_p, star_n)) => {
+                        star = Some((star_p, star_n + 1));
+                        p = star_p + 1;
+                        n = star_n + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+        pattern[p..].iter().all(|&c| c == '*')
+    }
+}
+
+impl FromStr for Glob {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
+/// Which projects a batch operation applies to. A project is selected if it matches every part of
+/// the selector that's set.
+#[derive(Debug, Clone, Default)]
+pub struct Selector {
+    /// Matched against the project's title and its full name.
+    pub project: Option<Glob>,
+    pub tag: Option<Tag>,
+    pub status: Option<Status>,
+    /// How long it's been since anything happened in the project.
+    pub older_than: Option<Period>,
+}
+
+impl Selector {
+    /// Whether nothing has been set, which would select every project.
+    pub fn is_empty(&self) -> bool {
+        self.project.is_none()
+            && self.tag.is_none()
+            && self.status.is_none()
+            && self.older_than.is_none()
+    }
+
+    pub fn matches(&self, project: &Project, scheme: &NameScheme, today: NaiveDate) -> bool {
+        self.project
+            .as_ref()
+            .is_none_or(|g| g.matches(project.title()) || g.matches(project.name.as_str()))
+            && self.tag.as_ref().is_none_or(|t| project.has_tag(t))
+            && self.status.is_none_or(|s| project.status == s)
+            && self.older_than.is_none_or(|period| {
+                last_activity(project, scheme).is_some_and(|d| d <= period.before(today))
+            })
+    }
+
+    /// The projects in `docs` that are selected, sorted by name.
+    pub fn select<'a>(&self, docs: &'a Documents, today: NaiveDate) -> Vec<&'a Project> {
+        let scheme = docs.loader().scheme();
+        docs.projects()
+            .filter(|p| self.matches(p, scheme, today))
+            .collect()
+    }
+}
+
+/// The date an action in `project` was last completed, or the date the project was created if none
+/// have been.
+///
+/// Projects without either date have no known activity.
+pub fn last_activity(project: &Project, scheme: &NameScheme) -> Option<NaiveDate> {
+    project
+        .actions
+        .actions()
+        .filter_map(|(a, _)| a.completed)
+        .max()
+        .or_else(|| scheme.id_date(project.id()?))
+}
+
+/// Lists the items a batch operation will change and asks whether to go ahead, unless `yes` is
+/// set.
+pub fn confirm_batch(question: &str, items: &[String], yes: bool) -> Result<bool, IoError> {
+    for item in items {
+        println!("- {}", item);
+    }
+    if yes {
+        return Ok(true);
+    }
+    confirm(question)
+}
+
+/// A batch operation was run without anything to select projects by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoSelectorError;
+
+impl fmt::Display for NoSelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "select projects with --project, --tag, --status, or --older-than"
+        )
+    }
+}
+
+impl Error for NoSelectorError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    mod glob {
+        use super::*;
+
+        #[test]
+        fn star_matches_any_suffix() {
+            assert!(Glob::new("Trip*").matches("Trip to Paris"));
+            assert!(Glob::new("Trip*").matches("Trip"));
+            assert!(!Glob::new("Trip*").matches("Plan trip"));
+        }
+
+        #[test]
+        fn star_matches_in_the_middle() {
+            assert!(Glob::new("Plan*trip").matches("Plan a long trip"));
+            assert!(!Glob::new("Plan*trip").matches("Plan a long trip home"));
+        }
+
+        #[test]
+        fn question_mark_matches_one_character() {
+            assert!(Glob::new("Q? taxes").matches("Q1 taxes"));
+            assert!(!Glob::new("Q? taxes").matches("Q10 taxes"));
+        }
+
+        #[test]
+        fn case_is_ignored() {
+            assert!(Glob::new("trip*").matches("TRIP to Paris"));
+        }
+    }
+
+    mod matches {
+        use super::*;
+
+        fn project(text: &str) -> Project {
+            Project::parse("202401010000 Plan trip", text).unwrap()
+        }
+
+        #[test]
+        fn empty_selector_matches_everything() {
+            let project = project("# Plan trip\n#someday\n");
+            assert!(Selector::default().matches(
+                &project,
+                NameScheme::default_ref(),
+                date(2024, 6, 1)
+            ));
+        }
+
+        #[test]
+        fn status_must_match() {
+            let project = project("# Plan trip\n#someday\n");
+            let selector = Selector {
+                status: Some(Status::Complete),
+                ..Selector::default()
+            };
+            assert!(!selector.matches(&project, NameScheme::default_ref(), date(2024, 6, 1)));
+        }
+
+        #[test]
+        fn age_is_from_last_completed_action() {
+            let project = project("# Plan trip\n#complete\n\n## Actions\n\n### Complete\n\n- Pack ✅ 2024-03-01\n- Go ✅ 2024-05-01\n");
+            let selector = Selector {
+                older_than: Some(Period::Days(30)),
+                ..Selector::default()
+            };
+            let scheme = NameScheme::default_ref();
+            assert!(!selector.matches(&project, scheme, date(2024, 5, 20)));
+            assert!(selector.matches(&project, scheme, date(2024, 6, 1)));
+        }
+
+        #[test]
+        fn age_falls_back_to_creation_date() {
+            let project = project("# Plan trip\n#complete\n");
+            let selector = Selector {
+                older_than: Some(Period::Days(90)),
+                ..Selector::default()
+            };
+            assert!(selector.matches(&project, NameScheme::default_ref(), date(2024, 6, 1)));
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Showing the details of a single project.
+
+use crate::{
+    gtd::Documents,
+    markdown::{Callout, Fragment},
+    project::{ActionStatus, Project},
+    references::Reference,
+    resolve::ResolveError,
+};
+
+/// Shows the project best matching `query`, along with the documents that link to it and its
+/// actions.
+pub fn show(docs: &Documents, query: &str) -> Result<(), ResolveError> {
+    let project = docs.find_project(query)?;
+    print!("{}", render(project, docs.backlinks(project.name.as_str())));
+    Ok(())
+}
+
+/// Renders `project`, along with `references`, which are the links to it.
+fn render(project: &Project, references: &[Reference]) -> String {
+    let mut out = String::new();
+
+    out.push_str(project.title());
+    for tag in project.tags.iter() {
+        out.push_str(&format!(" {}", tag));
+    }
+    out.push('\n');
+    out.push_str(&format!("Status: {}\n", project.status));
+    out.push_str(&format!("Progress: {}\n", project.progress()));
+
+    if let Some(goal) = &project.goal {
+        let text = goal.without_callouts().without_tables();
+        out.push_str(&format!("\nGoal: {}\n", text));
+        for table in goal.tables() {
+            out.push_str(&table.to_text());
+        }
+    }
+    let warnings = [project.goal.as_ref(), project.info.as_ref()]
+        .iter()
+        .flatten()
+        .flat_map(|f| f.callouts())
+        .filter(Callout::is_warning)
+        .collect::<Vec<_>>();
+    if !warnings.is_empty() && project.goal.is_none() {
+        out.push('\n');
+    }
+    for warning in warnings {
+        let body = one_line(&warning.body);
+        match (&warning.title, body.as_str()) {
+            (Some(title), "") => out.push_str(&format!("Warning: {}\n", title)),
+            (Some(title), body) => out.push_str(&format!("Warning: {}: {}\n", title, body)),
+            (None, body) => out.push_str(&format!("Warning: {}\n", body)),
+        }
+    }
+    if let Some(outcome) = &project.outcome {
+        if project.goal.is_none() {
+            out.push('\n');
+        }
+        match outcome.completed {
+            Some(date) => out.push_str(&format!("Outcome: {} ({})\n", outcome.note, date)),
+            None => out.push_str(&format!("Outcome: {}\n", outcome.note)),
+        }
+    }
+
+    // The rest of the info is free-form, but tables are hard to read unaligned, so they're shown
+    // on their own.
+    let tables = project
+        .info
+        .as_ref()
+        .map(|i| i.tables())
+        .unwrap_or_default();
+    if !tables.is_empty() {
+        out.push_str("\nInfo:\n");
+        for table in tables {
+            out.push_str(&table.to_text());
+        }
+    }
+
+    let sections = [
+        (ActionStatus::Active, "Active"),
+        (ActionStatus::Waiting, "Waiting"),
+        (ActionStatus::Upcoming, "Upcoming"),
+        (ActionStatus::Complete, "Complete"),
+    ];
+    for (status, heading) in sections.iter() {
+        let mut actions = project
+            .actions
+            .actions()
+            .filter(|(_, s)| s == status)
+            .peekable();
+        if actions.peek().is_none() {
+            continue;
+        }
+
+        out.push_str(&format!("\n{}:\n", heading));
+        for (action, _) in actions {
+            out.push_str(&format!("- {}", action.text));
+            let id = action.id.as_ref().map(|id| id.as_str());
+            let sources = sources(
+                references
+                    .iter()
+                    .filter(|r| id.is_some() && r.link.block() == id),
+            );
+            if !sources.is_empty() {
+                out.push_str(&format!(" ({})", sources.join(", ")));
+            }
+            out.push('\n');
+        }
+    }
+
+    // Links to actions are shown with the actions, so only links to the project as a whole are
+    // left.
+    let backlinks = sources(references.iter().filter(|r| r.link.block().is_none()));
+    if !backlinks.is_empty() {
+        out.push_str("\nLinked from:\n");
+        for source in backlinks {
+            out.push_str(&format!("- {}\n", source));
+        }
+    }
+
+    if !project.footnotes.is_empty() {
+        out.push_str("\nFootnotes:\n");
+        for footnote in project.footnotes.iter() {
+            out.push_str(&format!(
+                "[^{}]: {}\n",
+                footnote.label,
+                one_line(&footnote.body)
+            ));
+        }
+    }
+
+    out
+}
+
+/// The Markdown of `fragment` on a single line.
+fn one_line(fragment: &Fragment) -> String {
+    let text = fragment.to_markdown();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The names of the documents that `references` are in, without duplicates.
+fn sources<'a>(references: impl Iterator<Item = &'a Reference>) -> Vec<String> {
+    let mut sources = Vec::new();
+    for reference in references {
+        let source = reference.source_name();
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::references::ReferenceIndex;
+    use std::path::Path;
+
+    #[test]
+    fn project_is_rendered() {
+        let project_str = "# Plan trip\n#in-progress #area/home\n\n## Goal\n\nRelax.\n\n## Actions\n\n### Active\n\n- Book flights\n\n### Complete\n\n- Pick dates\n";
+        let project = Project::parse("197001010000 Plan trip", project_str).unwrap();
+        assert_eq!(
+            render(&project, &[]),
+            "Plan trip #area/home\nStatus: In progress\nProgress: 1/2 (50%)\n\nGoal: Relax.\n\nActive:\n- Book flights\n\nComplete:\n- Pick dates\n"
+        );
+    }
+
+    #[test]
+    fn warning_callouts_are_rendered() {
+        let project_str = "# Plan trip\n#in-progress\n\n## Goal\n\nRelax.\n\n> [!warning] Visa\n> Apply a month ahead.\n\n> [!note] Pack light.\n\n## Info\n\n> [!CAUTION]-\n> Hotels fill up.\n";
+        let project = Project::parse("197001010000 Plan trip", project_str).unwrap();
+        assert_eq!(
+            render(&project, &[]),
+            "Plan trip\nStatus: In progress\nProgress: 0/0\n\nGoal: Relax.\nWarning: Visa: Apply a month ahead.\nWarning: Hotels fill up.\n"
+        );
+    }
+
+    #[test]
+    fn tables_are_rendered() {
+        let project_str = "# Plan trip\n#in-progress\n\n## Goal\n\nRelax.\n\n| Day | Place |\n|--|--|\n| 1 | Rome |\n\n## Info\n\nBudget:\n\n| Item | Price |\n|--|--:|\n| Flights | 400 |\n| Hotel | 90 |\n";
+        let project = Project::parse("197001010000 Plan trip", project_str).unwrap();
+        assert_eq!(
+            render(&project, &[]),
+            "Plan trip\nStatus: In progress\nProgress: 0/0\n\nGoal: Relax.\n\
+             ┌─────┬───────┐\n\
+             │ Day │ Place │\n\
+             ├─────┼───────┤\n\
+             │ 1   │ Rome  │\n\
+             └─────┴───────┘\n\
+             \nInfo:\n\
+             ┌─────────┬───────┐\n\
+             │ Item    │ Price │\n\
+             ├─────────┼───────┤\n\
+             │ Flights │   400 │\n\
+             │ Hotel   │    90 │\n\
+             └─────────┴───────┘\n"
+        );
+    }
+
+    #[test]
+    fn footnotes_are_rendered() {
+        let project_str = "# Plan trip\n#in-progress\n\n## Goal\n\nRelax.[^1]\n\n## Actions\n\n### Active\n\n- Book flights[^b]\n\n[^1]: Really.\n\n[^b]: The *cheap*\n    ones.\n";
+        let project = Project::parse("197001010000 Plan trip", project_str).unwrap();
+        assert_eq!(
+            render(&project, &[]),
+            "Plan trip\nStatus: In progress\nProgress: 0/1 (0%)\n\nGoal: Relax.[^1]\n\nActive:\n- Book flights[^b]\n\nFootnotes:\n[^1]: Really.\n[^b]: The _cheap_ ones.\n"
+        );
+    }
+
+    #[test]
+    fn references_are_rendered() {
+        let project_str = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights ^abcdef\n- Pack bags\n";
+        let project = Project::parse("197001010000 Plan trip", project_str).unwrap();
+        let mut index = ReferenceIndex::default();
+        index.add(
+            Path::new("Contexts/@computer.md"),
+            "# @computer\n\n- ![[197001010000 Plan trip#^abcdef]]\n",
+        );
+        index.add(
+            Path::new("Reviews/Weekly.md"),
+            "# Weekly\n\n[[197001010000 Plan trip]] and [[197001010000 Plan trip#Goal]]\n",
+        );
+        assert_eq!(
+            render(&project, index.links_to("197001010000 Plan trip")),
+            "Plan trip\nStatus: In progress\nProgress: 0/2 (0%)\n\nActive:\n- Book flights (@computer)\n- Pack bags\n\nLinked from:\n- Weekly\n"
+        );
+    }
+}
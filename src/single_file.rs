@@ -0,0 +1,173 @@
+//! Single-file mode, where a whole GTD system is one Markdown document instead of a folder of them.
+//!
+//! The document has `## Projects`, `## Someday`, and `## Contexts` sections, with a `###` heading
+//! for each project or context, named the way its file would be. Everything under a `###` heading
+//! is read as if it were that document's own file, with its headings two levels higher, so the same
+//! parsers and rules apply to both layouts:
+//!
+//! ```markdown
+//! ## Projects
+//!
+//! ### 202401010000 Plan trip
+//! #in-progress
+//!
+//! #### Actions
+//!
+//! ##### Active
+//!
+//! - Book flights ^abcdef
+//!
+//! ## Contexts
+//!
+//! ### @computer
+//!
+//! - ![[202401010000 Plan trip#^abcdef]]
+//! ```
+
+use crate::parser;
+
+/// The kind of document a section holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A project, from either `## Projects` or `## Someday`.
+    Project,
+    Context,
+}
+
+/// A project or context in a single-file document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub kind: Kind,
+    /// The name of the document, like `202401010000 Plan trip` or `@computer`.
+    pub name: String,
+    /// The lines under the section's heading, with their headings moved up two levels.
+    pub body: String,
+}
+
+impl Section {
+    /// The text the document would have as its own file, titled `title`.
+    pub fn text(&self, title: &str) -> String {
+        format!("# {}\n{}", title, self.body)
+    }
+}
+
+/// Splits a single-file document into its projects and contexts, in the order they're written.
+/// Anything outside of a `###` heading in a known section is ignored. Underlined headings and ones
+/// closed with `#`s are read as if they were written the usual way.
+pub fn split(text: &str) -> Vec<Section> {
+    let text = parser::normalize_headings(text);
+    let mut sections = Vec::<Section>::new();
+    let mut kind = None;
+    let mut in_section = false;
+    for line in text.lines() {
+        match heading_level(line) {
+            Some(2) => {
+                kind = match line[2..].trim() {
+                    "Projects" | "Someday" => Some(Kind::Project),
+                    "Contexts" => Some(Kind::Context),
+                    _ => None,
+                };
+                in_section = false;
+            }
+            Some(3) => {
+                in_section = kind.is_some();
+                if let Some(kind) = kind {
+                    sections.push(Section {
+                        kind,
+                        name: line[3..].trim().to_string(),
+                        body: String::new(),
+                    });
+                }
+            }
+            Some(level) if level < 2 => in_section = false,
+            level => {
+                if let (true, Some(section)) = (in_section, sections.last_mut()) {
+                    let line = if level.is_some() { &line[2..] } else { line };
+                    section.body.push_str(line);
+                    section.body.push('\n');
+                }
+            }
+        }
+    }
+    sections
+}
+
+/// The level of the ATX heading on `line`, if it is one.
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    let is_heading = (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' '));
+    is_heading.then_some(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config, context::Name as ContextName, gtd::Documents, project::Status,
+        test_dir::TempDir,
+    };
+    use std::fs;
+
+    const TEXT: &str = "# GTD\n\n## Projects\n\n### 202401010000 Plan trip\n#in-progress\n\n#### Actions\n\n##### Active\n\n- Book flights ^abcdef\n\n## Someday\n\n### 202401020000 Learn piano\n#someday\n\n## Contexts\n\n### @computer\n\n- ![[202401010000 Plan trip#^abcdef]]\n\n#### Plan trip\n\n- Back up laptop\n\n## Notes\n\n### Ignored\n\n- Not a context\n";
+
+    #[test]
+    fn sections_are_split_with_headings_moved_up() {
+        let sections = split(TEXT);
+        let names = sections
+            .iter()
+            .map(|s| (s.kind, s.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                (Kind::Project, "202401010000 Plan trip"),
+                (Kind::Project, "202401020000 Learn piano"),
+                (Kind::Context, "@computer"),
+            ]
+        );
+        assert_eq!(
+            sections[0].text("Plan trip"),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights ^abcdef\n\n"
+        );
+        assert_eq!(
+            sections[2].body,
+            "\n- ![[202401010000 Plan trip#^abcdef]]\n\n## Plan trip\n\n- Back up laptop\n\n"
+        );
+    }
+
+    #[test]
+    fn tags_are_not_headings() {
+        assert_eq!(heading_level("#in-progress"), None);
+        assert_eq!(heading_level("### @home"), Some(3));
+        assert_eq!(heading_level("#######"), None);
+    }
+
+    #[test]
+    fn documents_are_loaded_from_the_single_file() {
+        let dir = TempDir::new("single-file");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("GTD.md"), TEXT).unwrap();
+        let config = Config::parse("[single-file]\npath = \"GTD.md\"\n").unwrap();
+
+        let docs = Documents::load(&dir, &config).unwrap();
+        let mut projects = docs
+            .projects()
+            .map(|p| (p.title(), p.status))
+            .collect::<Vec<_>>();
+        projects.sort_by_key(|(title, _)| *title);
+        assert_eq!(
+            projects,
+            [
+                ("Learn piano", Status::Someday),
+                ("Plan trip", Status::InProgress)
+            ]
+        );
+        let context = docs
+            .context(&ContextName::new(String::from("@computer")))
+            .unwrap();
+        assert_eq!(context.actions().len(), 2);
+        assert_eq!(context.groups().len(), 1);
+        assert!(docs.loader().create_project("Do taxes").is_err());
+    }
+}
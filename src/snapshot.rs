@@ -0,0 +1,441 @@
+//! Comparing a GTD folder to an earlier `gtd export`, for `gtd diff`.
+//!
+//! An export is a snapshot of every project and action, so keeping one from each run is enough to
+//! see what happened between them, without keeping the folder in git. Actions are matched up by
+//! their block ID when they have one, and by their text when they don't.
+
+use crate::{
+    export,
+    filter::Filter,
+    gtd::Documents,
+    project::{ActionStatus, Status},
+    schema::VERSION,
+};
+use serde::Deserialize;
+use serde_json::{json, Error as JsonError, Value};
+use std::{collections::HashSet, error::Error, fmt, fs, io::Error as IoError, path::Path};
+
+/// The projects in an export, with just what's needed to compare them.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Snapshot {
+    projects: Vec<SnapshotProject>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct SnapshotProject {
+    name: String,
+    title: String,
+    status: String,
+    actions: Vec<SnapshotAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct SnapshotAction {
+    id: Option<String>,
+    text: String,
+    status: String,
+}
+
+impl Snapshot {
+    /// Reads the export at `path`.
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        let text = fs::read_to_string(path)?;
+        Self::from_value(serde_json::from_str(&text)?)
+    }
+
+    /// The snapshot of everything in `docs`.
+    pub fn of(docs: &Documents) -> Self {
+        Self::from_value(export::export(docs, &Filter::default()))
+            .expect("exports can be read back")
+    }
+
+    /// Reads an export that's already been parsed.
+    pub fn from_value(value: Value) -> Result<Self, SnapshotError> {
+        match value["version"].as_u64() {
+            Some(version) if version == u64::from(VERSION) => {}
+            version => return Err(SnapshotError::UnsupportedVersion(version)),
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Every action, with the project it's in.
+    fn actions(&self) -> Vec<ActionState<'_>> {
+        self.projects
+            .iter()
+            .flat_map(|p| p.actions.iter().map(move |a| ActionState::new(p, a)))
+            .collect()
+    }
+}
+
+/// An action and where it is in a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionState<'a> {
+    /// The name of the project the action is in.
+    pub project: &'a str,
+    /// The title of the project the action is in.
+    pub title: &'a str,
+    pub id: Option<&'a str>,
+    pub text: &'a str,
+    /// The action's status, like `active`.
+    pub status: &'a str,
+}
+
+impl<'a> ActionState<'a> {
+    fn new(project: &'a SnapshotProject, action: &'a SnapshotAction) -> Self {
+        Self {
+            project: &project.name,
+            title: &project.title,
+            id: action.id.as_deref(),
+            text: &action.text,
+            status: &action.status,
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.status == export::action_status_name(ActionStatus::Complete)
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "project": self.project,
+            "id": self.id,
+            "text": self.text,
+            "status": self.status,
+        })
+    }
+}
+
+/// An action that's in a different project or section than it was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move<'a> {
+    pub from: ActionState<'a>,
+    pub to: ActionState<'a>,
+}
+
+/// What changed between two snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDiff<'a> {
+    /// Actions that were completed, including ones that were added and completed in between.
+    pub completed: Vec<ActionState<'a>>,
+    /// Incomplete actions that weren't there before.
+    pub added: Vec<ActionState<'a>>,
+    pub moved: Vec<Move<'a>>,
+    /// Actions that aren't there anymore, as they were before.
+    pub removed: Vec<ActionState<'a>>,
+    /// The names and titles of the projects that were in progress in both snapshots, and whose
+    /// actions didn't change at all.
+    pub stalled: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> SnapshotDiff<'a> {
+    /// Compares the snapshot `new` to the earlier snapshot `old`.
+    pub fn new(old: &'a Snapshot, new: &'a Snapshot) -> Self {
+        let old_actions = old.actions();
+        let new_actions = new.actions();
+        let pairs = match_actions(&old_actions, &new_actions);
+
+        let mut diff = Self::default();
+        let mut old_matched = vec![false; old_actions.len()];
+        let mut new_matched = vec![false; new_actions.len()];
+        for (i, j) in pairs {
+            old_matched[i] = true;
+            new_matched[j] = true;
+            let (from, to) = (old_actions[i], new_actions[j]);
+            if to.is_complete() && !from.is_complete() {
+                diff.completed.push(to);
+            } else if from.project != to.project || from.status != to.status {
+                diff.moved.push(Move { from, to });
+            }
+        }
+        for (action, _) in new_actions.iter().zip(new_matched).filter(|(_, m)| !m) {
+            if action.is_complete() {
+                diff.completed.push(*action);
+            } else {
+                diff.added.push(*action);
+            }
+        }
+        diff.removed = old_actions
+            .iter()
+            .zip(old_matched)
+            .filter(|(_, m)| !m)
+            .map(|(a, _)| *a)
+            .collect();
+
+        let changed = diff
+            .completed
+            .iter()
+            .chain(diff.added.iter())
+            .chain(diff.removed.iter())
+            .map(|a| a.project)
+            .chain(
+                diff.moved
+                    .iter()
+                    .flat_map(|m| [m.from.project, m.to.project]),
+            )
+            .collect::<HashSet<_>>();
+        let in_progress = Status::InProgress.tag();
+        let was_in_progress = old
+            .projects
+            .iter()
+            .filter(|p| p.status == in_progress)
+            .map(|p| p.name.as_str())
+            .collect::<HashSet<_>>();
+        diff.stalled = new
+            .projects
+            .iter()
+            .filter(|p| p.status == in_progress && was_in_progress.contains(p.name.as_str()))
+            .filter(|p| !changed.contains(p.name.as_str()))
+            .map(|p| (p.name.as_str(), p.title.as_str()))
+            .collect();
+        diff.completed.sort_by_key(|a| a.project);
+        diff.added.sort_by_key(|a| a.project);
+        diff
+    }
+
+    /// Checks if nothing changed, so every in-progress project stalled.
+    pub fn is_empty(&self) -> bool {
+        self.completed.is_empty()
+            && self.added.is_empty()
+            && self.moved.is_empty()
+            && self.removed.is_empty()
+    }
+
+    /// The changes as JSON, in the format described by `schema::snapshot_diff`.
+    pub fn to_json(&self) -> Value {
+        let actions =
+            |actions: &[ActionState]| actions.iter().map(|a| a.to_json()).collect::<Vec<_>>();
+        json!({
+            "version": VERSION,
+            "completed": actions(&self.completed),
+            "added": actions(&self.added),
+            "moved": self
+                .moved
+                .iter()
+                .map(|m| json!({ "from": m.from.to_json(), "to": m.to.to_json() }))
+                .collect::<Vec<_>>(),
+            "removed": actions(&self.removed),
+            "stalled": self
+                .stalled
+                .iter()
+                .map(|(name, title)| json!({ "name": name, "title": title }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl<'a> fmt::Display for SnapshotDiff<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sections = [
+            ("Completed", &self.completed),
+            ("Added", &self.added),
+            ("Removed", &self.removed),
+        ];
+        for (heading, actions) in sections.iter() {
+            if actions.is_empty() {
+                continue;
+            }
+            writeln!(f, "{}:", heading)?;
+            for action in actions.iter() {
+                writeln!(f, "- {} ({})", action.text, action.title)?;
+            }
+        }
+        if !self.moved.is_empty() {
+            writeln!(f, "Moved:")?;
+            for Move { from, to } in self.moved.iter() {
+                writeln!(
+                    f,
+                    "- {}: {} ({}) → {} ({})",
+                    to.text, from.title, from.status, to.title, to.status
+                )?;
+            }
+        }
+        if !self.stalled.is_empty() {
+            writeln!(f, "Stalled:")?;
+            for (_, title) in self.stalled.iter() {
+                writeln!(f, "- {}", title)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Pairs up the indices of the actions in `old` and `new` that are the same action. Block IDs are
+/// only unique within a project, so actions are matched by ID and project first, then by ID alone
+/// for ones that moved, and then the same way by text.
+fn match_actions(old: &[ActionState], new: &[ActionState]) -> Vec<(usize, usize)> {
+    type Same = fn(&ActionState, &ActionState) -> bool;
+    let passes: [Same; 4] = [
+        |a, b| a.id.is_some() && a.id == b.id && a.project == b.project,
+        |a, b| a.id.is_some() && a.id == b.id,
+        |a, b| a.text == b.text && a.project == b.project,
+        |a, b| a.text == b.text,
+    ];
+
+    let mut pairs = Vec::new();
+    let mut old_matched = vec![false; old.len()];
+    let mut new_matched = vec![false; new.len()];
+    for same in passes.iter() {
+        for (j, b) in new.iter().enumerate() {
+            if new_matched[j] {
+                continue;
+            }
+            let found = old
+                .iter()
+                .enumerate()
+                .find(|(i, a)| !old_matched[*i] && same(a, b));
+            if let Some((i, _)) = found {
+                old_matched[i] = true;
+                new_matched[j] = true;
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    IoError(IoError),
+    JsonError(JsonError),
+    /// An export from a version of gtd whose format is different, or something else entirely.
+    UnsupportedVersion(Option<u64>),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::JsonError(e) => write!(f, "the export isn't valid: {}", e),
+            Self::UnsupportedVersion(Some(version)) => write!(
+                f,
+                "the export is version {}, but only version {} can be compared",
+                version, VERSION
+            ),
+            Self::UnsupportedVersion(None) => {
+                write!(f, "the file isn't the output of `gtd export`")
+            }
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl From<IoError> for SnapshotError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<JsonError> for SnapshotError {
+    fn from(error: JsonError) -> Self {
+        Self::JsonError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(projects: Value) -> Snapshot {
+        Snapshot::from_value(json!({ "version": VERSION, "projects": projects, "contexts": [] }))
+            .unwrap()
+    }
+
+    fn project(name: &str, status: &str, actions: Value) -> Value {
+        json!({ "name": name, "title": name, "status": status, "actions": actions })
+    }
+
+    fn action(id: Option<&str>, text: &str, status: &str) -> Value {
+        json!({ "id": id, "text": text, "status": status })
+    }
+
+    #[test]
+    fn changes_between_snapshots_are_found() {
+        let old = snapshot(json!([
+            project(
+                "Trip",
+                "in-progress",
+                json!([
+                    action(Some("aaaaaa"), "Book flights", "active"),
+                    action(None, "Pack", "upcoming"),
+                    action(None, "Call Bob", "waiting"),
+                ])
+            ),
+            project(
+                "Taxes",
+                "in-progress",
+                json!([action(None, "File", "active")])
+            ),
+            project(
+                "Garden",
+                "in-progress",
+                json!([action(None, "Dig", "active")])
+            ),
+        ]));
+        let new = snapshot(json!([
+            project(
+                "Trip",
+                "in-progress",
+                json!([
+                    action(Some("aaaaaa"), "Book cheap flights", "complete"),
+                    action(None, "Pack", "active"),
+                    action(None, "Rent a car", "active"),
+                ])
+            ),
+            project(
+                "Taxes",
+                "in-progress",
+                json!([action(None, "File", "active")])
+            ),
+            project("Garden", "in-progress", json!([])),
+            project("Move", "someday", json!([action(None, "Dig", "active")])),
+        ]));
+
+        let diff = SnapshotDiff::new(&old, &new);
+        let texts = |actions: &Vec<ActionState<'_>>| {
+            actions
+                .iter()
+                .map(|a| a.text.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(&diff.completed), ["Book cheap flights"]);
+        assert_eq!(texts(&diff.added), ["Rent a car"]);
+        assert_eq!(texts(&diff.removed), ["Call Bob"]);
+        let moves = diff
+            .moved
+            .iter()
+            .map(|m| {
+                (
+                    m.to.text,
+                    m.from.project,
+                    m.from.status,
+                    m.to.project,
+                    m.to.status,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            moves,
+            [
+                ("Pack", "Trip", "upcoming", "Trip", "active"),
+                ("Dig", "Garden", "active", "Move", "active")
+            ]
+        );
+        assert_eq!(diff.stalled, [("Taxes", "Taxes")]);
+        assert!(SnapshotDiff::new(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let value = json!({ "version": VERSION + 1, "projects": [], "contexts": [] });
+        assert!(matches!(
+            Snapshot::from_value(value),
+            Err(SnapshotError::UnsupportedVersion(Some(_)))
+        ));
+        assert!(matches!(
+            Snapshot::from_value(json!([])),
+            Err(SnapshotError::UnsupportedVersion(None))
+        ));
+    }
+}
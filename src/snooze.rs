@@ -0,0 +1,148 @@
+//! Deferring actions until a later date.
+
+use crate::{
+    edit,
+    gtd::{Documents, LoadProjectError},
+    period::{InvalidPeriodError, Period},
+    project::DEFER_MARKER,
+    resolve::ResolveError,
+};
+use chrono::NaiveDate;
+use std::{error::Error, fmt, str::FromStr};
+
+/// When to defer an action until, either as a date or a period from today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    Date(NaiveDate),
+    Period(Period),
+}
+
+impl Until {
+    /// The date to defer until, if a period from `today` doesn't go past the last date there is.
+    pub fn date(&self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Self::Date(date) => Some(*date),
+            Self::Period(period) => period.checked_after(today),
+        }
+    }
+}
+
+impl FromStr for Until {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(Self::Date(date));
+        }
+        s.parse().map(Self::Period).map_err(|e| match e {
+            InvalidPeriodError::Malformed(_) => {
+                format!("\"{}\" is not a date or a period like \"3d\"", s)
+            }
+            e => e.to_string(),
+        })
+    }
+}
+
+/// Defers the incomplete action best matching `query` until `until`, writing the deferral date
+/// into its project.
+pub fn snooze(
+    docs: &Documents,
+    query: &str,
+    until: Until,
+    today: NaiveDate,
+) -> Result<(), SnoozeError> {
+    let (project, action) = docs.find_action(query)?;
+    let date = until.date(today).ok_or(SnoozeError::OutOfRange(until))?;
+
+    let written = docs
+        .loader()
+        .update_project(&project.name, |text, reparsed| {
+            // Find the action again in case the file changed since it was loaded.
+            let (action, _) = reparsed
+                .actions
+                .actions()
+                .find(|(a, _)| a.span == action.span && a == &action)?;
+            Some(edit::set_action_date(text, action, DEFER_MARKER, date))
+        })?;
+
+    if !written && action.deferred != Some(date) {
+        return Err(SnoozeError::Changed);
+    }
+
+    println!("Snoozed \"{}\" until {}.", action.text, date);
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum SnoozeError {
+    ResolveError(ResolveError),
+    LoadProjectError(LoadProjectError),
+    Changed,
+    OutOfRange(Until),
+}
+
+impl fmt::Display for SnoozeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ResolveError(e) => write!(f, "{}", e),
+            Self::LoadProjectError(e) => write!(f, "{}", e),
+            Self::Changed => write!(f, "project changed while it was being edited"),
+            Self::OutOfRange(Until::Period(period)) => {
+                write!(f, "{} from today is past the last date there is", period)
+            }
+            Self::OutOfRange(Until::Date(date)) => write!(f, "{} is out of range", date),
+        }
+    }
+}
+
+impl Error for SnoozeError {}
+
+impl From<ResolveError> for SnoozeError {
+    fn from(error: ResolveError) -> Self {
+        Self::ResolveError(error)
+    }
+}
+
+impl From<LoadProjectError> for SnoozeError {
+    fn from(error: LoadProjectError) -> Self {
+        Self::LoadProjectError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, d).unwrap()
+    }
+
+    #[test]
+    fn date_is_used_as_is() {
+        let until = "2024-01-20".parse::<Until>().unwrap();
+        assert_eq!(until.date(date(5)), Some(date(20)));
+    }
+
+    #[test]
+    fn period_is_from_today() {
+        let until = "3d".parse::<Until>().unwrap();
+        assert_eq!(until.date(date(5)), Some(date(8)));
+    }
+
+    #[test]
+    fn periods_out_of_range_are_rejected() {
+        let err = "999999999d".parse::<Until>().unwrap_err();
+        assert!(err.contains("longer than 100 years"), "{}", err);
+        assert!("9999999w".parse::<Until>().is_err());
+
+        let until = Until::Period(Period::Days(u32::MAX));
+        assert_eq!(until.date(date(5)), None);
+        let end = Until::Period(Period::Days(1));
+        assert_eq!(end.date(NaiveDate::MAX), None);
+    }
+
+    #[test]
+    fn other_text_is_invalid() {
+        assert!("tomorrow".parse::<Until>().is_err());
+    }
+}
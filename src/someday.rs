@@ -0,0 +1,210 @@
+//! Reviewing someday projects that have been waiting a long time, so the someday list doesn't turn
+//! into a graveyard.
+//!
+//! A project's someday date is when it was put on the someday list. It's read from `someday` in
+//! the project's frontmatter, which is set whenever a project is kept during a review, or else from
+//! when git first saw the project's file, or else from the project's ID.
+
+use crate::{
+    edit,
+    gtd::{Documents, LoadProjectError, Loader},
+    period::Period,
+    project::{Project, Status},
+    prompt,
+    trash::Trash,
+};
+use chrono::{Local, NaiveDate};
+use std::{error::Error, fmt, io::Error as IoError, process::Command};
+
+/// The frontmatter key holding the date a project was put on the someday list.
+pub const SOMEDAY_KEY: &str = "someday";
+
+const CHOICES: [&str; 4] = ["promote", "keep", "delete", "quit"];
+
+/// A someday project that's due for review.
+#[derive(Debug)]
+pub struct Item<'a> {
+    pub project: &'a Project,
+    /// The date the project was put on the someday list.
+    pub since: NaiveDate,
+}
+
+/// How many projects were changed in a review.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub promoted: usize,
+    pub kept: usize,
+    pub deleted: usize,
+}
+
+/// The date `project` was put on the someday list, if it can be found.
+pub fn someday_date(project: &Project, loader: &Loader) -> Option<NaiveDate> {
+    project
+        .frontmatter
+        .get(SOMEDAY_KEY)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .or_else(|| git_added_date(project, loader))
+        .or_else(|| loader.scheme().id_date(project.id()?))
+}
+
+/// The someday projects that have been on the someday list for at least `period` as of `today`,
+/// oldest first.
+pub fn queue<'a>(docs: &'a Documents, period: Period, today: NaiveDate) -> Vec<Item<'a>> {
+    let cutoff = period.before(today);
+    let mut items = docs
+        .projects()
+        .filter(|p| p.status == Status::Someday)
+        .filter_map(|project| {
+            let since = someday_date(project, docs.loader())?;
+            (since <= cutoff).then_some(Item { project, since })
+        })
+        .collect::<Vec<_>>();
+    items.sort_by(|a, b| (a.since, &a.project.name).cmp(&(b.since, &b.project.name)));
+    items
+}
+
+/// Goes through the someday projects older than `period` one at a time, asking whether to promote
+/// each one to in progress, keep it for another `period`, or delete it by moving it to the trash.
+pub fn review(docs: &Documents, period: Period, today: NaiveDate) -> Result<Summary, SomedayError> {
+    let items = queue(docs, period, today);
+    let mut summary = Summary::default();
+    if items.is_empty() {
+        println!("No someday projects are older than {}.", period);
+        return Ok(summary);
+    }
+
+    let loader = docs.loader();
+    let trash = Trash::new(loader, Local::now().naive_local());
+    for (i, item) in items.iter().enumerate() {
+        let project = item.project;
+        println!(
+            "\n({}/{}) {}, someday since {}",
+            i + 1,
+            items.len(),
+            project.title(),
+            item.since
+        );
+        if let Some(goal) = &project.goal {
+            println!("Goal: {}", goal);
+        }
+
+        match prompt::choose("What now?", &CHOICES)? {
+            Some(0) => {
+                loader.update_project(&project.name, |text, project| {
+                    let mut frontmatter = project.frontmatter.clone();
+                    frontmatter.remove(SOMEDAY_KEY);
+                    edit::set_status(&frontmatter.replace_in(text), Status::InProgress)
+                })?;
+                summary.promoted += 1;
+            }
+            Some(1) => {
+                loader.update_project(&project.name, |text, project| {
+                    let mut frontmatter = project.frontmatter.clone();
+                    frontmatter.set(SOMEDAY_KEY, &today.format("%Y-%m-%d").to_string());
+                    Some(frontmatter.replace_in(text))
+                })?;
+                summary.kept += 1;
+            }
+            Some(2) => {
+                trash.delete(loader, &loader.project_path(&project.name))?;
+                summary.deleted += 1;
+            }
+            _ => break,
+        }
+    }
+
+    println!(
+        "\nPromoted {}, kept {}, and deleted {} projects.",
+        summary.promoted, summary.kept, summary.deleted
+    );
+    Ok(summary)
+}
+
+/// The date of the commit that added the file of `project`, if the GTD folder is in a git
+/// repository.
+fn git_added_date(project: &Project, loader: &Loader) -> Option<NaiveDate> {
+    let output = Command::new("git")
+        .args(["log", "--follow", "--diff-filter=A", "--format=%as", "--"])
+        .arg(loader.project_path(&project.name))
+        .current_dir(loader.root_dir())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    NaiveDate::parse_from_str(stdout.lines().last()?.trim(), "%Y-%m-%d").ok()
+}
+
+#[derive(Debug)]
+pub enum SomedayError {
+    IoError(IoError),
+    LoadProjectError(LoadProjectError),
+}
+
+impl fmt::Display for SomedayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SomedayError::IoError(e) => write!(f, "{}", e),
+            SomedayError::LoadProjectError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SomedayError {}
+
+impl From<IoError> for SomedayError {
+    fn from(e: IoError) -> Self {
+        SomedayError::IoError(e)
+    }
+}
+
+impl From<LoadProjectError> for SomedayError {
+    fn from(e: LoadProjectError) -> Self {
+        SomedayError::LoadProjectError(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+    use std::fs;
+
+    #[test]
+    fn old_someday_projects_are_queued_oldest_first() {
+        let dir = TempDir::new("someday");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Learn piano.md"),
+            "---\nsomeday: 2024-03-01\n---\n# Learn piano\n#someday\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Projects/202402010000 Build boat.md"),
+            "# Build boat\n#someday\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Projects/202405010000 Paint shed.md"),
+            "# Paint shed\n#someday\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress\n",
+        )
+        .unwrap();
+
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let titles = queue(&docs, Period::Months(3), today)
+            .iter()
+            .map(|i| i.project.title())
+            .collect::<Vec<_>>();
+
+        assert_eq!(titles, vec!["Build boat", "Learn piano"]);
+    }
+}
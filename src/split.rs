@@ -0,0 +1,274 @@
+//! Splitting a long context into groups, since a flat list gets hard to read past a few dozen
+//! actions.
+
+use crate::{
+    context::{Action as ContextAction, Name as ContextName},
+    diff::WriteMode,
+    edit,
+    gtd::Documents,
+    transaction::{Transaction, TransactionError},
+};
+use std::{collections::BTreeMap, error::Error, fmt, fs, io::Error as IoError, str::FromStr};
+
+/// What to group a context's actions by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitBy {
+    /// The title of the project each action is from.
+    Project,
+    /// The first tag on each action, or else on its project.
+    Tag,
+}
+
+impl fmt::Display for SplitBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Project => write!(f, "project"),
+            Self::Tag => write!(f, "tag"),
+        }
+    }
+}
+
+impl FromStr for SplitBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "project" => Ok(Self::Project),
+            "tag" => Ok(Self::Tag),
+            _ => Err(format!(
+                "\"{}\" is not something to split by, like \"project\" or \"tag\"",
+                s
+            )),
+        }
+    }
+}
+
+/// A context's actions sorted into groups, as the Markdown of their list items.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Split<'a> {
+    /// Actions that don't belong in any group, like ones written directly in the context.
+    pub ungrouped: Vec<&'a str>,
+    /// The actions in each group, by the group's name.
+    pub groups: BTreeMap<String, Vec<&'a str>>,
+}
+
+/// Groups the actions in the context `name` by `by`, either under a `##` heading for each group or,
+/// with `into_files`, in a new context for each group named like `@computer-plan-trip`. Actions
+/// that aren't in any group stay where they are.
+///
+/// Each change is shown as a word diff and written according to `mode`, all together or not at all,
+/// and can be put back with `gtd undo`. Returns the number of groups, or `None` if nothing was
+/// written.
+pub fn split_context(
+    docs: &Documents,
+    name: &ContextName,
+    by: SplitBy,
+    into_files: bool,
+    mode: WriteMode,
+) -> Result<Option<usize>, SplitError> {
+    if !docs.contexts().any(|c| c.name == *name) {
+        return Err(SplitError::NoSuchContext(name.clone()));
+    }
+    let loader = docs.loader();
+    let path = loader.context_path(name);
+    let text = fs::read_to_string(&path)?;
+    let header = &text[..edit::context_body_start(&text)];
+    let split = split(docs, &text, by);
+
+    let mut files = Vec::new();
+    if into_files {
+        files.push((
+            name.clone(),
+            render(header, &split.ungrouped, &BTreeMap::new()),
+        ));
+        for (group, items) in split.groups.iter() {
+            let group_name = ContextName::new(format!("{}-{}", name, slug(group)));
+            if loader.context_path(&group_name).exists() {
+                return Err(SplitError::AlreadyExists(group_name));
+            }
+            let group_header = format!("# {}", group_name);
+            files.push((group_name, render(&group_header, items, &BTreeMap::new())));
+        }
+    } else {
+        files.push((
+            name.clone(),
+            render(header, &split.ungrouped, &split.groups),
+        ));
+    }
+
+    let mut transaction = Transaction::new(loader, format!("split-context {} --by {}", name, by));
+    for (file_name, new_text) in files {
+        transaction.write(&loader.context_path(&file_name), new_text)?;
+    }
+    if !transaction.review(mode)? {
+        return Ok(None);
+    }
+    transaction.commit()?;
+    Ok(Some(split.groups.len()))
+}
+
+/// Sorts the list items in the context text `text` into groups by `by`.
+pub fn split<'a>(docs: &Documents, text: &'a str, by: SplitBy) -> Split<'a> {
+    let mut split = Split::default();
+    for item in edit::list_items(text) {
+        let action_ref = ContextAction::parse_item(item, docs.loader().scheme())
+            .and_then(|a| a.to_action_ref().cloned());
+        let group = action_ref.and_then(|r| match by {
+            SplitBy::Project => Some(r.project_name.title().to_string()),
+            SplitBy::Tag => {
+                let project = docs.project(&r.project_name)?;
+                let (action, _) = project.actions.get_action(&r.action_id)?;
+                let tag = action.tags.first().or_else(|| project.tags.first())?;
+                Some(tag.to_string())
+            }
+        });
+        match group {
+            Some(group) => split.groups.entry(group).or_default().push(item),
+            None => split.ungrouped.push(item),
+        }
+    }
+    split
+}
+
+/// Renders a context with `header` at the top, then the `ungrouped` items, then each group of items
+/// under a `##` heading.
+pub fn render(header: &str, ungrouped: &[&str], groups: &BTreeMap<String, Vec<&str>>) -> String {
+    let mut out = format!("{}\n", header.trim_end());
+    if !ungrouped.is_empty() {
+        out.push_str(&format!("\n{}\n", ungrouped.join("\n")));
+    }
+    for (group, items) in groups {
+        out.push_str(&format!("\n## {}\n\n{}\n", group, items.join("\n")));
+    }
+    out
+}
+
+/// `group` in lowercase, with runs of anything but letters and digits replaced by `-`.
+fn slug(group: &str) -> String {
+    group
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[derive(Debug)]
+pub enum SplitError {
+    NoSuchContext(ContextName),
+    /// Splitting into files would overwrite an existing context.
+    AlreadyExists(ContextName),
+    IoError(IoError),
+    TransactionError(TransactionError),
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoSuchContext(name) => write!(f, "there is no context {}", name),
+            Self::AlreadyExists(name) => write!(f, "context {} already exists", name),
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::TransactionError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SplitError {}
+
+impl From<IoError> for SplitError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<TransactionError> for SplitError {
+    fn from(error: TransactionError) -> Self {
+        Self::TransactionError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+
+    fn with_docs<F: FnOnce(&Documents)>(contexts: &[(&str, &str)], f: F) {
+        let dir = TempDir::new("split");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress #area/home\n\n## Actions\n\n### Active\n\n- Book flights #online ^abcdef\n- Pack bags ^bcdefa\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Projects/202401020000 Do taxes.md"),
+            "# Do taxes\n#in-progress #area/work\n\n## Actions\n\n### Active\n\n- File return ^cdefab\n",
+        )
+        .unwrap();
+        for (name, text) in contexts {
+            fs::write(dir.join(format!("Contexts/{}.md", name)), text).unwrap();
+        }
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        f(&docs);
+    }
+
+    const COMPUTER: &str = "# @computer\n\n- ![[202401020000 Do taxes#^cdefab]]\n- Back up laptop\n- ![[202401010000 Plan trip#^abcdef]]\n- ![[202401010000 Plan trip#^bcdefa]]\n";
+
+    #[test]
+    fn actions_are_grouped_under_headings() {
+        with_docs(&[("@computer", COMPUTER)], |docs| {
+            let name = ContextName::new(String::from("@computer"));
+            let groups = split_context(docs, &name, SplitBy::Project, false, WriteMode::Write);
+            assert_eq!(groups.unwrap(), Some(2));
+
+            let text = fs::read_to_string(docs.loader().context_path(&name)).unwrap();
+            assert_eq!(
+                text,
+                "# @computer\n\n- Back up laptop\n\n## Do taxes\n\n- ![[202401020000 Do taxes#^cdefab]]\n\n## Plan trip\n\n- ![[202401010000 Plan trip#^abcdef]]\n- ![[202401010000 Plan trip#^bcdefa]]\n"
+            );
+            let context = docs.loader().load_context(&name).unwrap();
+            assert_eq!(context.actions().len(), 4);
+            assert_eq!(context.groups().len(), 2);
+        });
+    }
+
+    #[test]
+    fn actions_are_grouped_by_their_own_tag_first() {
+        with_docs(&[("@computer", COMPUTER)], |docs| {
+            let split = split(docs, COMPUTER, SplitBy::Tag);
+            let groups = split
+                .groups
+                .iter()
+                .map(|(g, items)| (g.as_str(), items.len()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                groups,
+                [("#area/home", 1), ("#area/work", 1), ("#online", 1)]
+            );
+            assert_eq!(split.ungrouped, ["- Back up laptop"]);
+        });
+    }
+
+    #[test]
+    fn groups_can_be_split_into_files() {
+        with_docs(&[("@computer", COMPUTER)], |docs| {
+            let name = ContextName::new(String::from("@computer"));
+            split_context(docs, &name, SplitBy::Project, true, WriteMode::Write).unwrap();
+
+            let read = |name: &str| {
+                fs::read_to_string(
+                    docs.loader()
+                        .context_path(&ContextName::new(name.to_string())),
+                )
+                .unwrap()
+            };
+            assert_eq!(read("@computer"), "# @computer\n\n- Back up laptop\n");
+            assert_eq!(
+                read("@computer-do-taxes"),
+                "# @computer-do-taxes\n\n- ![[202401020000 Do taxes#^cdefab]]\n"
+            );
+        });
+    }
+}
@@ -0,0 +1,265 @@
+//! A summary of the GTD folder that's cached after each command, so it can be printed without
+//! loading every document.
+//!
+//! The cache is only as fresh as the last command that loaded the documents, so it says when it
+//! was last updated.
+
+use crate::{
+    config::Segment,
+    filter::Filter,
+    gtd::{Documents, Loader},
+    inbox,
+    links::Linker,
+    next,
+    project::{ActionStatus, Status},
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error as IoError, ErrorKind},
+    path::PathBuf,
+};
+
+/// The file in the state directory that the summary is cached in.
+pub const STATS_FILENAME: &str = "stats.json";
+
+/// Counts of what's in a GTD folder.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    /// When the counts were taken.
+    pub updated: NaiveDateTime,
+    pub in_progress: usize,
+    pub someday: usize,
+    pub complete: usize,
+    /// The number of next actions in each context, by canonical context name.
+    pub next: BTreeMap<String, usize>,
+    /// Unfinished actions due before the day the counts were taken.
+    pub overdue: usize,
+    /// Actions waiting on someone else.
+    pub waiting: usize,
+    /// Items in the inbox.
+    pub inbox: usize,
+}
+
+impl Stats {
+    /// Counts what's in `docs` at `now`.
+    pub fn compute(docs: &Documents, now: NaiveDateTime) -> Self {
+        let today = now.date();
+        let mut stats = Self {
+            updated: now,
+            inbox: inbox::count(docs.loader().root_dir()),
+            ..Self::default()
+        };
+
+        for project in docs.projects() {
+            match project.status {
+                Status::InProgress => stats.in_progress += 1,
+                Status::Someday => stats.someday += 1,
+                Status::Complete => {
+                    stats.complete += 1;
+                    continue;
+                }
+            }
+            for (action, status) in project.actions.actions() {
+                match status {
+                    ActionStatus::Complete => continue,
+                    ActionStatus::Waiting => stats.waiting += 1,
+                    _ => {}
+                }
+                if action.due.is_some_and(|d| d < today) {
+                    stats.overdue += 1;
+                }
+            }
+        }
+
+        let links = Linker::default();
+        for context in docs.contexts() {
+            let count = next::candidates(docs, &links, today, context, &Filter::default()).len();
+            if count > 0 {
+                let name = docs.canonical_context_name(&context.name);
+                *stats.next.entry(name.to_string()).or_default() += count;
+            }
+        }
+        stats
+    }
+
+    /// The number of next actions in all contexts.
+    pub fn next_total(&self) -> usize {
+        self.next.values().sum()
+    }
+
+    /// Loads the cached stats for the GTD folder of `loader`, if there are any.
+    pub fn load(loader: &Loader) -> Result<Option<Self>, IoError> {
+        let text = match fs::read_to_string(path(loader)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        // A cache from an older version is the same as no cache.
+        Ok(serde_json::from_str(&text).ok())
+    }
+
+    /// Caches the stats for the GTD folder of `loader`.
+    pub fn save(&self, loader: &Loader) -> Result<(), IoError> {
+        let path = path(loader);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(IoError::from)?;
+        fs::write(path, text)
+    }
+}
+
+/// Updates just the inbox count in the cached stats, if there are any, since it can change without
+/// loading the documents.
+pub fn refresh_inbox(loader: &Loader) -> Result<(), IoError> {
+    match Stats::load(loader)? {
+        Some(mut stats) => {
+            stats.inbox = inbox::count(loader.root_dir());
+            stats.save(loader)
+        }
+        None => Ok(()),
+    }
+}
+
+fn path(loader: &Loader) -> PathBuf {
+    loader.state_dir().join(STATS_FILENAME)
+}
+
+/// Renders `stats` for `gtd dashboard`. Overdue actions are only counted as of `updated`, so
+/// `today` is used to point out when that's a different day.
+pub fn render(stats: &Stats, today: NaiveDate) -> String {
+    let mut out = format!(
+        "Projects: {} in progress, {} someday, {} complete\n",
+        stats.in_progress, stats.someday, stats.complete
+    );
+    out.push_str(&format!("Next actions: {}", stats.next_total()));
+    if !stats.next.is_empty() {
+        let contexts = stats
+            .next
+            .iter()
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect::<Vec<_>>();
+        out.push_str(&format!(" ({})", contexts.join(", ")));
+    }
+    out.push('\n');
+    out.push_str(&format!("Overdue: {}\n", stats.overdue));
+    out.push_str(&format!("Waiting: {}\n", stats.waiting));
+    out.push_str(&format!("Inbox: {}\n", stats.inbox));
+
+    out.push_str(&format!(
+        "\nUpdated {}",
+        stats.updated.format("%Y-%m-%d %H:%M")
+    ));
+    if stats.updated.date() != today {
+        out.push_str(", so overdue actions may be out of date");
+    }
+    out.push_str(".\n");
+    out
+}
+
+/// Renders the `segments` of `stats` on one line, like `7 next · 2 overdue · inbox 5`, for
+/// embedding in a shell prompt or status bar.
+pub fn oneline(stats: &Stats, segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Next => format!("{} next", stats.next_total()),
+            Segment::Overdue => format!("{} overdue", stats.overdue),
+            Segment::Waiting => format!("{} waiting", stats.waiting),
+            Segment::Inbox => format!("inbox {}", stats.inbox),
+            Segment::InProgress => format!("{} in progress", stats.in_progress),
+            Segment::Someday => format!("{} someday", stats.someday),
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 5)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn stats_are_counted_and_cached() {
+        let dir = TempDir::new("stats");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights 📅 2024-01-04 ^abcdef\n- Pack bags ^bcdefa\n\n### Waiting\n\n- Visa @(Embassy)\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Projects/202401020000 Learn piano.md"),
+            "# Learn piano\n#someday\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Contexts/@computer.md"),
+            "# @computer\n\n- ![[202401010000 Plan trip#^abcdef]]\n- Back up photos\n",
+        )
+        .unwrap();
+        fs::write(dir.join("Inbox.md"), "- Call Bob ➕ 2024-01-04\n").unwrap();
+
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let stats = Stats::compute(&docs, now());
+        assert_eq!(
+            render(&stats, now().date()),
+            "Projects: 1 in progress, 1 someday, 0 complete\nNext actions: 2 (@computer 2)\nOverdue: 1\nWaiting: 1\nInbox: 1\n\nUpdated 2024-01-05 09:30.\n"
+        );
+
+        let loader = docs.loader();
+        assert_eq!(Stats::load(loader).unwrap(), None);
+        stats.save(loader).unwrap();
+        assert_eq!(Stats::load(loader).unwrap(), Some(stats));
+
+        fs::write(
+            dir.join("Inbox.md"),
+            "- Call Bob ➕ 2024-01-04\n- Buy milk ➕ 2024-01-05\n",
+        )
+        .unwrap();
+        refresh_inbox(loader).unwrap();
+        assert_eq!(Stats::load(loader).unwrap().unwrap().inbox, 2);
+    }
+
+    #[test]
+    fn segments_are_rendered_in_order() {
+        let mut stats = Stats {
+            updated: now(),
+            overdue: 2,
+            waiting: 3,
+            inbox: 5,
+            ..Stats::default()
+        };
+        stats.next.insert(String::from("@computer"), 4);
+        stats.next.insert(String::from("@home"), 3);
+        assert_eq!(
+            oneline(&stats, &Config::default().status.segments),
+            "7 next · 2 overdue · 3 waiting · inbox 5"
+        );
+        assert_eq!(oneline(&stats, &[Segment::Inbox]), "inbox 5");
+        assert_eq!(oneline(&stats, &[]), "");
+    }
+
+    #[test]
+    fn stale_stats_are_pointed_out() {
+        let stats = Stats {
+            updated: now(),
+            ..Stats::default()
+        };
+        let tomorrow = NaiveDate::from_ymd_opt(2024, 1, 6).unwrap();
+        assert!(render(&stats, tomorrow)
+            .ends_with("Updated 2024-01-05 09:30, so overdue actions may be out of date.\n"));
+    }
+}
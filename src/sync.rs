@@ -0,0 +1,681 @@
+//! Syncing actions with external task lists, like the tasks in a CalDAV calendar.
+//!
+//! Active actions in in-progress projects are pushed to a backend as tasks, and the task's ID is
+//! written into the action as a `@uid(...)` annotation so the two can be matched up later. Each
+//! sync saves a copy of the tasks as it left them, so that a change on one side since then can be
+//! told apart from changes on both sides, which conflict.
+//!
+//! Completions and due dates are synced both ways, and completing either copy completes the other.
+//! A task's summary always follows its action's text.
+
+use crate::{
+    edit,
+    gtd::{Documents, LoadProjectError, Loader},
+    ics::{self, Todo},
+    project::{Action, ActionStatus, Project, Status, DUE_MARKER, UID_ANNOTATION},
+    prompt,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt, fs,
+    io::{Error as IoError, ErrorKind},
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// A task list that actions can be synced with.
+pub trait SyncBackend {
+    /// A short name for the backend, like `caldav`, which names the file its sync state is kept in.
+    fn name(&self) -> &str;
+
+    /// Gets all the tasks in the backend.
+    fn pull(&mut self) -> Result<Vec<Todo>, IoError>;
+
+    /// Creates `task` in the backend, replacing the task with the same UID if there is one.
+    fn push(&mut self, task: &Todo) -> Result<(), IoError>;
+}
+
+/// How to settle a conflict, where an action's due date and its task's due date were both changed
+/// since the last sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Keep the action's due date.
+    Local,
+    /// Keep the task's due date.
+    Remote,
+    /// Ask which one to keep.
+    Ask,
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Self::Local),
+            "remote" => Ok(Self::Remote),
+            "ask" => Ok(Self::Ask),
+            _ => Err(format!(
+                "\"{}\" is not a conflict resolution, like \"local\", \"remote\", or \"ask\"",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncOptions {
+    pub resolution: Resolution,
+    /// Whether to only print the changes, without making them. Conflicts that would be asked about
+    /// are skipped.
+    pub dry_run: bool,
+}
+
+/// What a sync changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    /// Actions that were added to the backend.
+    pub added: usize,
+    /// Tasks that were updated from their actions.
+    pub pushed: usize,
+    /// Actions that were updated from their tasks.
+    pub pulled: usize,
+    /// Actions that were left alone because they conflict with their tasks.
+    pub conflicts: usize,
+}
+
+/// A change that syncing makes to an action or its task.
+#[derive(Debug, Clone)]
+pub enum Change<'a> {
+    /// Adds `action` to the backend as `task`.
+    Add {
+        project: &'a Project,
+        action: &'a Action,
+        task: Todo,
+    },
+    /// Replaces the task of `action` in the backend with `task`.
+    Push {
+        project: &'a Project,
+        action: &'a Action,
+        task: Todo,
+    },
+    /// Updates `action` to match `task`.
+    Pull {
+        project: &'a Project,
+        action: &'a Action,
+        task: Todo,
+    },
+    /// Leaves `action` and its task alone, because they conflict.
+    Conflict {
+        project: &'a Project,
+        action: &'a Action,
+    },
+}
+
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Add {
+                project, action, ..
+            } => {
+                write!(f, "Add \"{}\" ({}).", action.text, project.title())
+            }
+            Self::Push {
+                project, action, ..
+            } => write!(
+                f,
+                "Update the task for \"{}\" ({}).",
+                action.text,
+                project.title()
+            ),
+            Self::Pull {
+                project, action, ..
+            } => write!(
+                f,
+                "Update \"{}\" ({}) from its task.",
+                action.text,
+                project.title()
+            ),
+            Self::Conflict { project, action } => write!(
+                f,
+                "Skip \"{}\" ({}), which conflicts with its task.",
+                action.text,
+                project.title()
+            ),
+        }
+    }
+}
+
+/// The tasks as the last sync with a backend left them.
+#[derive(Debug, Clone)]
+pub struct SyncState {
+    path: PathBuf,
+    tasks: HashMap<String, Todo>,
+}
+
+impl SyncState {
+    /// Loads the state of syncing with the backend `name`, which is empty if it hasn't been synced
+    /// with yet.
+    pub fn load(loader: &Loader, name: &str, today: NaiveDate) -> Result<Self, IoError> {
+        let path = loader
+            .state_dir()
+            .join("sync")
+            .join(format!("{}.ics", name));
+        let tasks = match fs::read_to_string(&path) {
+            Ok(text) => ics::parse_todos(&text, today)
+                .into_iter()
+                .map(|t| (t.uid.clone(), t))
+                .collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, tasks })
+    }
+
+    pub fn get(&self, uid: &str) -> Option<&Todo> {
+        self.tasks.get(uid)
+    }
+
+    pub fn insert(&mut self, task: Todo) {
+        self.tasks.insert(task.uid.clone(), task);
+    }
+
+    pub fn save(&self) -> Result<(), IoError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut tasks = self.tasks.values().collect::<Vec<_>>();
+        tasks.sort_by(|a, b| a.uid.cmp(&b.uid));
+        fs::write(
+            &self.path,
+            tasks.iter().map(|t| t.to_ics()).collect::<String>(),
+        )
+    }
+}
+
+/// Syncs the actions in `docs` with `backend` at `now`, printing each change.
+pub fn sync<B: SyncBackend>(
+    docs: &Documents,
+    backend: &mut B,
+    options: SyncOptions,
+    now: NaiveDateTime,
+) -> Result<SyncSummary, SyncError> {
+    let remote = backend.pull()?;
+    let mut state = SyncState::load(docs.loader(), backend.name(), now.date())?;
+    let plan = plan(docs, &remote, &state, options, now)?;
+
+    let mut summary = SyncSummary::default();
+    for change in &plan.changes {
+        println!("{}", change);
+        match change {
+            Change::Add { .. } => summary.added += 1,
+            Change::Push { .. } => summary.pushed += 1,
+            Change::Pull { .. } => summary.pulled += 1,
+            Change::Conflict { .. } => summary.conflicts += 1,
+        }
+        if !options.dry_run {
+            apply(docs, backend, change)?;
+        }
+    }
+
+    if !options.dry_run {
+        for task in plan.synced {
+            state.insert(task);
+        }
+        state.save()?;
+    }
+    Ok(summary)
+}
+
+#[derive(Debug, Default)]
+struct Plan<'a> {
+    changes: Vec<Change<'a>>,
+    /// The tasks as the changes leave them, for every action that doesn't conflict.
+    synced: Vec<Todo>,
+}
+
+/// Works out the changes that syncing `docs` with the tasks in `remote` makes.
+fn plan<'a>(
+    docs: &'a Documents,
+    remote: &[Todo],
+    state: &SyncState,
+    options: SyncOptions,
+    now: NaiveDateTime,
+) -> Result<Plan<'a>, IoError> {
+    let remote = remote
+        .iter()
+        .map(|t| (t.uid.as_str(), t))
+        .collect::<HashMap<_, _>>();
+
+    let projects = docs.projects().collect::<Vec<_>>();
+
+    let mut plan = Plan::default();
+    let mut added = 0;
+    for project in projects {
+        for (action, status) in project.actions.actions() {
+            match &action.uid {
+                Some(uid) => {
+                    let task = match remote.get(uid.as_str()) {
+                        Some(task) => task,
+                        None => continue,
+                    };
+                    let base = state.get(uid);
+                    let merged = match merge(project, action, status, task, base, options, now)? {
+                        Some(merged) => merged,
+                        None => {
+                            plan.changes.push(Change::Conflict { project, action });
+                            continue;
+                        }
+                    };
+
+                    let is_complete = status == ActionStatus::Complete;
+                    if merged.due != action.due || (merged.completed.is_some() && !is_complete) {
+                        plan.changes.push(Change::Pull {
+                            project,
+                            action,
+                            task: merged.clone(),
+                        });
+                    }
+                    if merged != **task {
+                        plan.changes.push(Change::Push {
+                            project,
+                            action,
+                            task: merged.clone(),
+                        });
+                    }
+                    plan.synced.push(merged);
+                }
+                None if status == ActionStatus::Active && project.status == Status::InProgress => {
+                    let task = Todo {
+                        uid: format!("{}-{}@gtd", now.format("%Y%m%dT%H%M%S"), added),
+                        summary: action.text.to_string(),
+                        due: action.due,
+                        completed: None,
+                    };
+                    added += 1;
+                    plan.synced.push(task.clone());
+                    plan.changes.push(Change::Add {
+                        project,
+                        action,
+                        task,
+                    });
+                }
+                None => {}
+            }
+        }
+    }
+
+    Ok(plan)
+}
+
+/// The task that `action` and its `task` should both end up as, given `base`, the task as the last
+/// sync left it. Returns `None` if their due dates conflict and the conflict wasn't settled.
+fn merge(
+    project: &Project,
+    action: &Action,
+    status: ActionStatus,
+    task: &Todo,
+    base: Option<&Todo>,
+    options: SyncOptions,
+    now: NaiveDateTime,
+) -> Result<Option<Todo>, IoError> {
+    let due = match base {
+        _ if action.due == task.due => action.due,
+        Some(base) if base.due == task.due => action.due,
+        Some(base) if base.due == action.due => task.due,
+        _ => match options.resolution {
+            Resolution::Local => action.due,
+            Resolution::Remote => task.due,
+            Resolution::Ask if options.dry_run => return Ok(None),
+            Resolution::Ask => {
+                let question = format!(
+                    "\"{}\" ({}) is due {} here but {} in the backend. Keep the date here?",
+                    action.text,
+                    project.title(),
+                    format_due(action.due),
+                    format_due(task.due)
+                );
+                if prompt::confirm(&question)? {
+                    action.due
+                } else {
+                    task.due
+                }
+            }
+        },
+    };
+
+    // Completing either copy completes both, so there's nothing to conflict.
+    let completed = match status {
+        ActionStatus::Complete => action
+            .completed
+            .or(task.completed)
+            .or_else(|| Some(now.date())),
+        _ => task.completed,
+    };
+
+    Ok(Some(Todo {
+        uid: task.uid.clone(),
+        summary: action.text.to_string(),
+        due,
+        completed,
+    }))
+}
+
+fn format_due(due: Option<NaiveDate>) -> String {
+    match due {
+        Some(date) => date.format("%Y-%m-%d").to_string(),
+        None => String::from("never"),
+    }
+}
+
+fn apply<B: SyncBackend>(
+    docs: &Documents,
+    backend: &mut B,
+    change: &Change,
+) -> Result<(), SyncError> {
+    match change {
+        Change::Add {
+            project,
+            action,
+            task,
+        } => {
+            backend.push(task)?;
+            edit_action(docs, project, action, |text, a, _| {
+                Some(edit::annotate_action(text, a, UID_ANNOTATION, &task.uid))
+            })?;
+        }
+        Change::Push { task, .. } => backend.push(task)?,
+        Change::Pull {
+            project,
+            action,
+            task,
+        } => {
+            if task.due != action.due {
+                edit_action(docs, project, action, |text, a, _| {
+                    Some(match task.due {
+                        Some(due) => edit::set_action_date(text, a, DUE_MARKER, due),
+                        None => edit::clear_action_date(text, a, DUE_MARKER),
+                    })
+                })?;
+            }
+            if let Some(date) = task.completed {
+                edit_action(docs, project, action, |text, a, p| {
+                    edit::complete_action(text, p, a, date)
+                })?;
+            }
+        }
+        Change::Conflict { .. } => {}
+    }
+    Ok(())
+}
+
+/// Edits `action` in the file of `project`, finding it again by its UID, or by its contents if it
+/// doesn't have one, in case earlier edits changed it.
+fn edit_action<F>(
+    docs: &Documents,
+    project: &Project,
+    action: &Action,
+    edit: F,
+) -> Result<bool, LoadProjectError>
+where
+    F: FnOnce(&str, &Action, &Project) -> Option<String>,
+{
+    docs.loader()
+        .update_project(&project.name, |text, reparsed| {
+            let (found, _) = reparsed
+                .actions
+                .actions()
+                .find(|(a, _)| match &action.uid {
+                    Some(uid) => a.uid.as_ref() == Some(uid),
+                    None => a == &action,
+                })?;
+            edit(text, found, reparsed)
+        })
+}
+
+#[derive(Debug)]
+pub enum SyncError {
+    IoError(IoError),
+    LoadProjectError(LoadProjectError),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::LoadProjectError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SyncError {}
+
+impl From<IoError> for SyncError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<LoadProjectError> for SyncError {
+    fn from(error: LoadProjectError) -> Self {
+        Self::LoadProjectError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+    use std::path::Path;
+
+    #[derive(Default)]
+    struct MemoryBackend {
+        tasks: Vec<Todo>,
+    }
+
+    impl SyncBackend for MemoryBackend {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        fn pull(&mut self) -> Result<Vec<Todo>, IoError> {
+            Ok(self.tasks.clone())
+        }
+
+        fn push(&mut self, task: &Todo) -> Result<(), IoError> {
+            self.tasks.retain(|t| t.uid != task.uid);
+            self.tasks.push(task.clone());
+            Ok(())
+        }
+    }
+
+    /// Creates a GTD folder with a single project holding `text`.
+    fn folder(text: &str) -> TempDir {
+        let dir = TempDir::new("sync");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(dir.join("Projects/202401010000 Plan trip.md"), text).unwrap();
+        dir
+    }
+
+    fn project_text(dir: &Path) -> String {
+        fs::read_to_string(dir.join("Projects/202401010000 Plan trip.md")).unwrap()
+    }
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, d).unwrap()
+    }
+
+    fn now() -> NaiveDateTime {
+        date(5).and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn options(resolution: Resolution) -> SyncOptions {
+        SyncOptions {
+            resolution,
+            dry_run: false,
+        }
+    }
+
+    fn task(uid: &str, due: Option<NaiveDate>, completed: Option<NaiveDate>) -> Todo {
+        Todo {
+            uid: uid.to_string(),
+            summary: String::from("Pack"),
+            due,
+            completed,
+        }
+    }
+
+    #[test]
+    fn new_active_actions_are_added() {
+        let dir =
+            folder("# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack ^aaaaaa\n");
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let mut backend = MemoryBackend::default();
+
+        let summary = sync(&docs, &mut backend, options(Resolution::Ask), now()).unwrap();
+
+        assert_eq!(summary.added, 1);
+        assert_eq!(backend.tasks[0].summary, "Pack");
+        assert_eq!(
+            project_text(&dir),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack @uid(20240105T120000-0@gtd) ^aaaaaa\n"
+        );
+        let state = SyncState::load(docs.loader(), "memory", date(5)).unwrap();
+        assert_eq!(state.get("20240105T120000-0@gtd"), Some(&backend.tasks[0]));
+    }
+
+    #[test]
+    fn remote_completions_are_pulled() {
+        let dir = folder("# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack @uid(abc)\n- Go @uid(def)\n");
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let mut backend = MemoryBackend {
+            tasks: vec![task("abc", None, Some(date(4)))],
+        };
+
+        let summary = sync(&docs, &mut backend, options(Resolution::Ask), now()).unwrap();
+
+        assert_eq!(summary.pulled, 1);
+        assert_eq!(
+            project_text(&dir),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Go @uid(def)\n\n### Complete\n\n- Pack @uid(abc) ✅ 2024-01-04\n"
+        );
+    }
+
+    #[test]
+    fn local_completions_are_pushed() {
+        let dir = folder("# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Go\n\n### Complete\n\n- Pack @uid(abc) ✅ 2024-01-03\n");
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let mut backend = MemoryBackend {
+            tasks: vec![task("abc", None, None)],
+        };
+
+        let summary = sync(&docs, &mut backend, options(Resolution::Ask), now()).unwrap();
+
+        assert_eq!(summary.pushed, 1);
+        let task = backend.tasks.iter().find(|t| t.uid == "abc").unwrap();
+        assert_eq!(task.completed, Some(date(3)));
+    }
+
+    #[test]
+    fn remote_due_date_change_is_pulled() {
+        let dir = folder("# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack 📅 2024-01-10 @uid(abc)\n");
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let mut state = SyncState::load(docs.loader(), "memory", date(5)).unwrap();
+        state.insert(task("abc", Some(date(10)), None));
+        state.save().unwrap();
+        let mut backend = MemoryBackend {
+            tasks: vec![task("abc", Some(date(12)), None)],
+        };
+
+        // The task changed since the last sync, so there's no conflict to resolve.
+        let summary = sync(&docs, &mut backend, options(Resolution::Local), now()).unwrap();
+
+        assert_eq!(summary.pulled, 1);
+        assert_eq!(summary.pushed, 0);
+        assert_eq!(
+            project_text(&dir),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack 📅 2024-01-12 @uid(abc)\n"
+        );
+    }
+
+    mod conflicts {
+        use super::*;
+
+        const TEXT: &str = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack 📅 2024-01-10 @uid(abc)\n";
+
+        #[test]
+        fn local_wins() {
+            let dir = folder(TEXT);
+            let docs = Documents::load(&dir, &Config::default()).unwrap();
+            let mut backend = MemoryBackend {
+                tasks: vec![task("abc", Some(date(12)), None)],
+            };
+
+            sync(&docs, &mut backend, options(Resolution::Local), now()).unwrap();
+
+            assert_eq!(backend.tasks[0].due, Some(date(10)));
+            assert_eq!(project_text(&dir), TEXT);
+        }
+
+        #[test]
+        fn remote_wins() {
+            let dir = folder(TEXT);
+            let docs = Documents::load(&dir, &Config::default()).unwrap();
+            let mut backend = MemoryBackend {
+                tasks: vec![task("abc", None, None)],
+            };
+
+            sync(&docs, &mut backend, options(Resolution::Remote), now()).unwrap();
+
+            assert_eq!(backend.tasks[0].due, None);
+            assert_eq!(
+                project_text(&dir),
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack @uid(abc)\n"
+            );
+        }
+
+        #[test]
+        fn dry_run_skips_questions() {
+            let dir = folder(TEXT);
+            let docs = Documents::load(&dir, &Config::default()).unwrap();
+            let mut backend = MemoryBackend {
+                tasks: vec![task("abc", Some(date(12)), None)],
+            };
+            let options = SyncOptions {
+                resolution: Resolution::Ask,
+                dry_run: true,
+            };
+
+            let summary = sync(&docs, &mut backend, options, now()).unwrap();
+
+            assert_eq!(summary.conflicts, 1);
+        }
+    }
+
+    #[test]
+    fn dry_run_changes_nothing() {
+        let text =
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack @uid(abc)\n- Go\n";
+        let dir = folder(text);
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let mut backend = MemoryBackend {
+            tasks: vec![task("abc", None, Some(date(4)))],
+        };
+        let options = SyncOptions {
+            resolution: Resolution::Ask,
+            dry_run: true,
+        };
+
+        let summary = sync(&docs, &mut backend, options, now()).unwrap();
+
+        assert_eq!(summary.added, 1);
+        assert_eq!(summary.pulled, 1);
+        assert_eq!(backend.tasks.len(), 1);
+        assert_eq!(project_text(&dir), text);
+        assert!(!docs.loader().state_dir().exists());
+    }
+}
@@ -0,0 +1,234 @@
+//! Hashtags, which may be nested like `#area/work/clients`.
+
+use serde::Deserialize;
+use std::{convert::TryFrom, error::Error, fmt, str::FromStr};
+
+const SEPARATOR: char = '/';
+
+/// Punctuation that can end a word in prose without being part of a tag in it.
+pub const TRAILING_PUNCTUATION: [char; 7] = ['.', ',', ';', ':', '!', '?', ')'];
+
+/// A possibly nested tag, stored without its leading `#`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Tag(String);
+
+impl Tag {
+    /// Creates a tag from its text, which may optionally start with a `#`.
+    ///
+    /// Returns `None` if the tag is empty, has empty segments, or is only made up of digits (like
+    /// `#123`, which is usually an issue number.)
+    pub fn new<S: Into<String>>(tag: S) -> Option<Self> {
+        let tag = tag.into();
+        let tag = match tag.strip_prefix('#') {
+            Some(t) => t.to_string(),
+            None => tag,
+        };
+
+        if tag.split(SEPARATOR).any(str::is_empty) {
+            return None;
+        }
+
+        if tag.chars().all(|c| c.is_ascii_digit() || c == SEPARATOR) {
+            return None;
+        }
+
+        Some(Self(tag))
+    }
+
+    /// Reads a word of prose as a tag, if it's one, like `#someday` in `Maybe #someday, later.`
+    ///
+    /// The word has to start with `#`, and punctuation at its end isn't part of the tag.
+    pub fn from_word(word: &str) -> Option<Self> {
+        let tag = word.strip_prefix('#')?;
+        Self::new(tag.trim_end_matches(TRAILING_PUNCTUATION))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Iterates through the segments of the tag, from outermost to innermost.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split(SEPARATOR)
+    }
+
+    /// The number of segments in the tag.
+    pub fn depth(&self) -> usize {
+        self.segments().count()
+    }
+
+    /// The innermost segment of the tag, e.g. `clients` for `#area/work/clients`.
+    pub fn leaf(&self) -> &str {
+        self.segments().last().unwrap_or(&self.0)
+    }
+
+    /// The tag this one is nested directly under, if any.
+    pub fn parent(&self) -> Option<Tag> {
+        let idx = self.0.rfind(SEPARATOR)?;
+        Some(Self(self.0[..idx].to_string()))
+    }
+
+    /// Iterates through all tags this one is nested under, from innermost to outermost.
+    pub fn ancestors(&self) -> impl Iterator<Item = Tag> {
+        std::iter::successors(self.parent(), Tag::parent)
+    }
+
+    /// Checks if this tag is `other` or is nested (at any depth) under it.
+    pub fn is_under(&self, other: &Tag) -> bool {
+        self == other || self.is_descendant_of(other)
+    }
+
+    /// Checks if this tag is nested (at any depth) under `other`.
+    pub fn is_descendant_of(&self, other: &Tag) -> bool {
+        match self.0.strip_prefix(&other.0) {
+            Some(rest) => rest.starts_with(SEPARATOR),
+            None => false,
+        }
+    }
+
+    /// Checks if this tag is nested directly under `other`.
+    pub fn is_child_of(&self, other: &Tag) -> bool {
+        self.parent().as_ref() == Some(other)
+    }
+
+    /// Checks if any segment of the tag is `segment`.
+    pub fn has_segment(&self, segment: &str) -> bool {
+        self.segments().any(|s| s == segment)
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+impl FromStr for Tag {
+    type Err = InvalidTagError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s).ok_or_else(|| InvalidTagError(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for Tag {
+    type Error = InvalidTagError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidTagError(String);
+
+impl fmt::Display for InvalidTagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\" is not a valid tag", self.0)
+    }
+}
+
+impl Error for InvalidTagError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(s: &str) -> Tag {
+        Tag::new(s).unwrap()
+    }
+
+    #[test]
+    fn words_are_read_as_tags() {
+        assert_eq!(Tag::from_word("#someday,"), Some(tag("someday")));
+        assert_eq!(Tag::from_word("#area/work."), Some(tag("area/work")));
+        assert_eq!(Tag::from_word("someday"), None);
+        assert_eq!(Tag::from_word("#"), None);
+    }
+
+    #[test]
+    fn leading_hash_is_stripped() {
+        assert_eq!(tag("#area/work").as_str(), "area/work");
+    }
+
+    #[test]
+    fn empty_segment_is_invalid() {
+        assert_eq!(Tag::new("area//work"), None);
+    }
+
+    #[test]
+    fn empty_tag_is_invalid() {
+        assert_eq!(Tag::new("#"), None);
+    }
+
+    #[test]
+    fn numeric_tag_is_invalid() {
+        assert_eq!(Tag::new("#123"), None);
+    }
+
+    #[test]
+    fn tag_with_digits_is_valid() {
+        assert!(Tag::new("#y1984").is_some());
+    }
+
+    #[test]
+    fn segments_are_split() {
+        let t = tag("area/work/clients");
+        assert_eq!(
+            t.segments().collect::<Vec<_>>(),
+            vec!["area", "work", "clients"]
+        );
+    }
+
+    #[test]
+    fn leaf_is_last_segment() {
+        assert_eq!(tag("area/work/clients").leaf(), "clients");
+    }
+
+    #[test]
+    fn parent_is_found() {
+        assert_eq!(tag("area/work/clients").parent(), Some(tag("area/work")));
+    }
+
+    #[test]
+    fn top_level_tag_has_no_parent() {
+        assert_eq!(tag("area").parent(), None);
+    }
+
+    #[test]
+    fn ancestors_are_innermost_first() {
+        let ancestors = tag("area/work/clients").ancestors().collect::<Vec<_>>();
+        assert_eq!(ancestors, vec![tag("area/work"), tag("area")]);
+    }
+
+    #[test]
+    fn descendant_is_under_ancestor() {
+        assert!(tag("area/work/clients").is_under(&tag("area")));
+    }
+
+    #[test]
+    fn tag_is_under_itself() {
+        assert!(tag("area").is_under(&tag("area")));
+    }
+
+    #[test]
+    fn tag_with_same_prefix_is_not_under() {
+        assert!(!tag("areas").is_under(&tag("area")));
+    }
+
+    #[test]
+    fn grandchild_is_not_child() {
+        assert!(!tag("area/work/clients").is_child_of(&tag("area")));
+    }
+
+    #[test]
+    fn segment_is_found_at_any_depth() {
+        assert!(tag("area/work/clients").has_segment("work"));
+    }
+
+    #[test]
+    fn display_includes_hash() {
+        assert_eq!(tag("area/work").to_string(), "#area/work");
+    }
+}
@@ -0,0 +1,47 @@
+//! Temporary folders for tests.
+
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A path under the system's temporary folder that's deleted when it's dropped, so that it's
+/// cleaned up even when a test fails. The folder itself isn't created.
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// A path with `name` in it, like `gtd-sync-test-…`, that's unique to this process and call.
+    pub fn new(name: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        Self(std::env::temp_dir().join(format!(
+            "gtd-{}-test-{}-{}",
+            name,
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::SeqCst)
+        )))
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // The test may never have created the folder, or removed it itself.
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
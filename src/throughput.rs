@@ -0,0 +1,412 @@
+//! Reports on how quickly actions are being completed, and what came of the projects finished
+//! along the way.
+
+use crate::{
+    gtd::Documents,
+    locale::{week_start, Locale},
+    project::{ActionStatus, Project, Status},
+};
+use chrono::{Duration, NaiveDate, Weekday};
+use std::fmt::Write;
+
+/// The number of actions completed in the week starting on `start`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Week {
+    pub start: NaiveDate,
+    pub completed: usize,
+}
+
+/// When an in-progress project is expected to be finished.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate<'a> {
+    pub project: &'a Project,
+    pub remaining: usize,
+    /// The estimated finish date, or `None` if no actions were completed recently.
+    pub finish: Option<NaiveDate>,
+}
+
+/// A project completed with a note on what came of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finished<'a> {
+    pub project: &'a Project,
+    pub completed: NaiveDate,
+    pub note: String,
+}
+
+/// Prints the throughput report for the `weeks` weeks up to `today`, with weeks starting and dates
+/// shown as `locale` says. CSV is always in English with ISO dates, so it can be read by other
+/// programs.
+pub fn throughput(docs: &Documents, today: NaiveDate, weeks: usize, csv: bool, locale: &Locale) {
+    let projects = docs.projects().collect::<Vec<_>>();
+
+    let first_day = locale.first_day_of_week();
+    let history = weekly_completions(&projects, today, weeks, first_day);
+    let estimates = estimates(&projects, today, weeks, first_day);
+    let outcomes = outcomes(&projects, today, weeks, first_day);
+    if csv {
+        print!("{}", render_csv(&history, &estimates, &outcomes));
+    } else {
+        print!("{}", render_table(&history, &estimates, &outcomes, locale));
+    }
+}
+
+/// Counts the actions completed in each of the `weeks` weeks up to and including the week of
+/// `today`, oldest first, where weeks start on `first_day`.
+pub fn weekly_completions(
+    projects: &[&Project],
+    today: NaiveDate,
+    weeks: usize,
+    first_day: Weekday,
+) -> Vec<Week> {
+    let this_week = week_start(today, first_day);
+    let mut history = (0..weeks)
+        .rev()
+        .map(|i| Week {
+            start: this_week - Duration::weeks(i as i64),
+            completed: 0,
+        })
+        .collect::<Vec<_>>();
+
+    for date in completion_dates(projects) {
+        let start = week_start(date, first_day);
+        if let Some(week) = history.iter_mut().find(|w| w.start == start) {
+            week.completed += 1;
+        }
+    }
+
+    history
+}
+
+/// Estimates when each in-progress project will be finished, based on how many of its actions were
+/// completed in the `weeks` weeks up to `today`, where weeks start on `first_day`.
+pub fn estimates<'a>(
+    projects: &[&'a Project],
+    today: NaiveDate,
+    weeks: usize,
+    first_day: Weekday,
+) -> Vec<Estimate<'a>> {
+    let window_start = week_start(today, first_day) - Duration::weeks(weeks as i64 - 1);
+
+    projects
+        .iter()
+        .filter(|p| p.status == Status::InProgress)
+        .map(|&project| {
+            let progress = project.progress();
+            let remaining = progress.total - progress.complete;
+            let recent = completion_dates(&[project])
+                .filter(|d| *d >= window_start && *d <= today)
+                .count();
+
+            let finish = if remaining == 0 {
+                Some(today)
+            } else if recent == 0 {
+                None
+            } else {
+                let days = (remaining * weeks * 7).div_ceil(recent);
+                Some(today + Duration::days(days as i64))
+            };
+
+            Estimate {
+                project,
+                remaining,
+                finish,
+            }
+        })
+        .collect()
+}
+
+/// The complete projects whose outcomes are dated in the `weeks` weeks up to `today`, in the order
+/// they were completed, where weeks start on `first_day`.
+pub fn outcomes<'a>(
+    projects: &[&'a Project],
+    today: NaiveDate,
+    weeks: usize,
+    first_day: Weekday,
+) -> Vec<Finished<'a>> {
+    let window_start = week_start(today, first_day) - Duration::weeks(weeks as i64 - 1);
+    let mut outcomes = projects
+        .iter()
+        .filter(|p| p.status == Status::Complete)
+        .filter_map(|&project| {
+            let outcome = project.outcome.as_ref()?;
+            let completed = outcome.completed?;
+            (completed >= window_start && completed <= today).then(|| Finished {
+                project,
+                completed,
+                note: outcome.note.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    outcomes.sort_by_key(|f| f.completed);
+    outcomes
+}
+
+fn completion_dates<'a>(projects: &'a [&'a Project]) -> impl Iterator<Item = NaiveDate> + 'a {
+    projects.iter().flat_map(|p| {
+        p.actions
+            .actions()
+            .filter(|(_, s)| *s == ActionStatus::Complete)
+            .filter_map(|(a, _)| a.completed)
+    })
+}
+
+fn render_table(
+    history: &[Week],
+    estimates: &[Estimate],
+    outcomes: &[Finished],
+    locale: &Locale,
+) -> String {
+    let mut out = String::new();
+    let dates = history
+        .iter()
+        .map(|w| locale.date(w.start))
+        .collect::<Vec<_>>();
+    let heading = locale.message("throughput-week", &[]);
+    let width = dates
+        .iter()
+        .chain(Some(&heading))
+        .map(|d| d.chars().count())
+        .max()
+        .unwrap_or(0);
+    writeln!(
+        out,
+        "{:<width$}  {}",
+        heading,
+        locale.message("throughput-completed", &[]),
+        width = width
+    )
+    .unwrap();
+    for (week, date) in history.iter().zip(dates) {
+        writeln!(out, "{:<width$}  {}", date, week.completed, width = width).unwrap();
+    }
+
+    if !outcomes.is_empty() {
+        writeln!(out).unwrap();
+        writeln!(out, "{}", locale.message("throughput-outcomes", &[])).unwrap();
+        for finished in outcomes {
+            let completed = locale.date(finished.completed);
+            match finished.note.is_empty() {
+                true => writeln!(out, "{}  {}", completed, finished.project.title()),
+                false => writeln!(
+                    out,
+                    "{}  {}: {}",
+                    completed,
+                    finished.project.title(),
+                    finished.note
+                ),
+            }
+            .unwrap();
+        }
+    }
+
+    if estimates.is_empty() {
+        return out;
+    }
+
+    let project = locale.message("throughput-project", &[]);
+    let remaining = locale.message("throughput-remaining", &[]);
+    let width = estimates
+        .iter()
+        .map(|e| e.project.title().chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(project.chars().count());
+    let remaining_width = remaining.chars().count();
+
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "{:<width$}  {}  {}",
+        project,
+        remaining,
+        locale.message("throughput-estimated-finish", &[]),
+        width = width
+    )
+    .unwrap();
+    for estimate in estimates {
+        writeln!(
+            out,
+            "{:<width$}  {:<remaining_width$}  {}",
+            estimate.project.title(),
+            estimate.remaining,
+            finish_str(estimate, locale),
+            width = width,
+            remaining_width = remaining_width
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn render_csv(history: &[Week], estimates: &[Estimate], outcomes: &[Finished]) -> String {
+    let mut out = String::new();
+    writeln!(out, "week,completed").unwrap();
+    for week in history {
+        writeln!(out, "{},{}", week.start, week.completed).unwrap();
+    }
+
+    writeln!(out).unwrap();
+    writeln!(out, "project,remaining,estimated_finish").unwrap();
+    for estimate in estimates {
+        writeln!(
+            out,
+            "{},{},{}",
+            csv_field(estimate.project.title()),
+            estimate.remaining,
+            estimate.finish.map(|d| d.to_string()).unwrap_or_default()
+        )
+        .unwrap();
+    }
+
+    writeln!(out).unwrap();
+    writeln!(out, "project,completed,outcome").unwrap();
+    for finished in outcomes {
+        writeln!(
+            out,
+            "{},{},{}",
+            csv_field(finished.project.title()),
+            finished.completed,
+            csv_field(&finished.note)
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn finish_str(estimate: &Estimate, locale: &Locale) -> String {
+    match estimate.finish {
+        Some(date) => locale.date(date),
+        None => locale.message("throughput-unknown", &[]),
+    }
+}
+
+/// Quotes `field` if it contains characters that are special in CSV.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DateFormat, LocaleConfig};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn project() -> Project {
+        let project_str = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack\n- Go\n\n### Complete\n\n- Pick dates ✅ 2024-01-02\n- Book flights ✅ 2024-01-10\n- Book hotel ✅ 2023-06-01\n";
+        Project::parse("197001010000 Plan trip", project_str).unwrap()
+    }
+
+    #[test]
+    fn week_starts_on_first_day() {
+        assert_eq!(
+            week_start(date(2024, 1, 14), Weekday::Mon),
+            date(2024, 1, 8)
+        );
+        assert_eq!(week_start(date(2024, 1, 8), Weekday::Mon), date(2024, 1, 8));
+        assert_eq!(
+            week_start(date(2024, 1, 14), Weekday::Sun),
+            date(2024, 1, 14)
+        );
+        assert_eq!(
+            week_start(date(2024, 1, 13), Weekday::Sun),
+            date(2024, 1, 7)
+        );
+    }
+
+    #[test]
+    fn completions_are_counted_per_week() {
+        let project = project();
+        let history = weekly_completions(&[&project], date(2024, 1, 10), 3, Weekday::Mon);
+        assert_eq!(
+            history,
+            vec![
+                Week {
+                    start: date(2023, 12, 25),
+                    completed: 0
+                },
+                Week {
+                    start: date(2024, 1, 1),
+                    completed: 1
+                },
+                Week {
+                    start: date(2024, 1, 8),
+                    completed: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finish_is_estimated_from_recent_pace() {
+        let project = project();
+        let estimates = estimates(&[&project], date(2024, 1, 10), 2, Weekday::Mon);
+        // Two actions in two weeks, with two remaining.
+        assert_eq!(estimates[0].remaining, 2);
+        assert_eq!(estimates[0].finish, Some(date(2024, 1, 24)));
+    }
+
+    #[test]
+    fn finish_is_unknown_without_recent_completions() {
+        let project = project();
+        let estimates = estimates(&[&project], date(2025, 1, 1), 4, Weekday::Mon);
+        assert_eq!(estimates[0].finish, None);
+    }
+
+    #[test]
+    fn outcomes_in_the_window_are_listed() {
+        let done = |name: &str, date: &str| {
+            let text = format!(
+                "# Trip\n#complete\n\n## Outcome\n\nSaw _Rome_. ✅ {}\n",
+                date
+            );
+            Project::parse(name, &text).unwrap()
+        };
+        let recent = done("197001010000 Trip", "2024-01-09");
+        let old = done("197001010001 Trip", "2023-06-01");
+        let in_progress = project();
+        let outcomes = outcomes(
+            &[&in_progress, &recent, &old],
+            date(2024, 1, 10),
+            2,
+            Weekday::Mon,
+        );
+        assert_eq!(
+            outcomes,
+            vec![Finished {
+                project: &recent,
+                completed: date(2024, 1, 9),
+                note: String::from("Saw _Rome_."),
+            }]
+        );
+    }
+
+    #[test]
+    fn table_is_shown_in_the_locale() {
+        let project = project();
+        let history = weekly_completions(&[&project], date(2024, 1, 10), 2, Weekday::Sun);
+        let estimates = estimates(&[&project], date(2024, 1, 10), 2, Weekday::Sun);
+        let config = LocaleConfig {
+            language: Some(String::from("de")),
+            date_format: DateFormat::Local,
+            first_day_of_week: Weekday::Sun,
+        };
+        let locale = Locale::from_config(&config).unwrap();
+        assert_eq!(
+            render_table(&history, &estimates, &[], &locale),
+            "Woche       Erledigt\n31.12.2023  1\n07.01.2024  1\n\nProjekt    Offen  Voraussichtliches Ende\nPlan trip  2      24.01.2024\n"
+        );
+    }
+
+    #[test]
+    fn csv_fields_with_commas_are_quoted() {
+        assert_eq!(csv_field("Taxes, 2024"), "\"Taxes, 2024\"");
+        assert_eq!(csv_field("Taxes"), "Taxes");
+    }
+}
@@ -0,0 +1,435 @@
+//! Time tracking, using a log of timer starts and stops kept in `Timelog.md`.
+//!
+//! Each entry is a list item like `- 2024-01-05 14:00 start [[202401011200 Plan trip#^abcdef]]` or
+//! `- 2024-01-05 14:30 stop`. Starting a timer implicitly stops the one that was running. Sessions
+//! that were pomodoros have `#pomodoro` after the link.
+
+use crate::{
+    gtd::Documents,
+    project::{ActionId, ActionRef, Name as ProjectName, Project},
+    resolve::ResolveError,
+};
+use chrono::{Duration, NaiveDateTime};
+use regex::Regex;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    fs::{self, OpenOptions},
+    io::{Error as IoError, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// The name of the time log file, in the root of the GTD folder.
+pub const TIMELOG_FILE: &str = "Timelog.md";
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+const POMODORO_TAG: &str = "#pomodoro";
+
+/// What time was spent on: an action in a project, or the project as a whole if the action has no
+/// ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub project_name: String,
+    pub action_id: Option<ActionId>,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[[{}", self.project_name)?;
+        if let Some(id) = &self.action_id {
+            write!(f, "#{}", id)?;
+        }
+        write!(f, "]]")
+    }
+}
+
+/// A period of time spent on a target. A session with no end is still running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub target: Target,
+    pub start: NaiveDateTime,
+    pub end: Option<NaiveDateTime>,
+    pub is_pomodoro: bool,
+}
+
+impl Session {
+    /// How long the session lasted, counting a running session up to `now`.
+    pub fn duration(&self, now: NaiveDateTime) -> Duration {
+        self.end.unwrap_or(now) - self.start
+    }
+}
+
+/// The sessions recorded in a time log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timelog {
+    sessions: Vec<Session>,
+}
+
+impl Timelog {
+    /// Parses the entries in `text`, ignoring any lines that aren't entries.
+    pub fn parse(text: &str) -> Self {
+        static ENTRY: OnceLock<Regex> = OnceLock::new();
+        let entry = ENTRY.get_or_init(|| {
+            Regex::new(
+                r"^[-*] (\d{4}-\d{2}-\d{2} \d{2}:\d{2}) (?:start \[\[([^\]#|]+)(?:#\^([^\]|]+))?\]\]( #pomodoro)?|stop)\s*$",
+            )
+            .unwrap()
+        });
+
+        let mut sessions: Vec<Session> = Vec::new();
+        for line in text.lines() {
+            let caps = match entry.captures(line.trim_end()) {
+                Some(c) => c,
+                None => continue,
+            };
+            let at = match NaiveDateTime::parse_from_str(&caps[1], TIME_FORMAT) {
+                Ok(at) => at,
+                Err(_) => continue,
+            };
+
+            if let Some(running) = sessions.last_mut().filter(|s| s.end.is_none()) {
+                running.end = Some(at);
+            }
+
+            if let Some(project_name) = caps.get(2) {
+                sessions.push(Session {
+                    target: Target {
+                        project_name: project_name.as_str().to_string(),
+                        action_id: caps.get(3).map(|id| ActionId::new(id.as_str().to_string())),
+                    },
+                    start: at,
+                    end: None,
+                    is_pomodoro: caps.get(4).is_some(),
+                });
+            }
+        }
+
+        Self { sessions }
+    }
+
+    /// Loads the time log in `root_dir`. A missing log is empty.
+    pub fn load(root_dir: &Path) -> Result<Self, IoError> {
+        match fs::read_to_string(path(root_dir)) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// The number of finished pomodoros spent on `target`.
+    pub fn pomodoros(&self, target: &Target) -> usize {
+        self.sessions
+            .iter()
+            .filter(|s| s.is_pomodoro && s.end.is_some() && &s.target == target)
+            .count()
+    }
+
+    /// The session that's currently running, if any.
+    pub fn running(&self) -> Option<&Session> {
+        self.sessions.last().filter(|s| s.end.is_none())
+    }
+}
+
+/// The path of the time log in `root_dir`.
+pub fn path(root_dir: &Path) -> PathBuf {
+    root_dir.join(TIMELOG_FILE)
+}
+
+/// Starts a timer on the action best matching `query`, stopping the running timer if there is one.
+pub fn start(docs: &Documents, query: &str, now: NaiveDateTime) -> Result<(), TimelogError> {
+    let (project, action) = docs.find_action(query)?;
+
+    let root_dir = docs.loader().root_dir();
+    let target = Target {
+        project_name: project.name.to_string(),
+        action_id: action.id.clone(),
+    };
+
+    let mut lines = Vec::new();
+    if Timelog::load(root_dir)?.running().is_some() {
+        lines.push(stop_entry(now));
+    }
+    lines.push(start_entry(now, &target, false));
+    append(root_dir, &lines)?;
+
+    println!("Started \"{}\" ({}).", action.text, project.title());
+    Ok(())
+}
+
+/// Stops the running timer.
+pub fn stop(root_dir: &Path, now: NaiveDateTime) -> Result<(), TimelogError> {
+    let timelog = Timelog::load(root_dir)?;
+    let running = timelog.running().ok_or(TimelogError::NotRunning)?;
+    append(root_dir, &[stop_entry(now)])?;
+    println!(
+        "Stopped {} after {}.",
+        running.target,
+        format_duration(running.duration(now))
+    );
+    Ok(())
+}
+
+/// Prints the total time spent on each project and context.
+pub fn report(docs: &Documents, now: NaiveDateTime) -> Result<(), TimelogError> {
+    let timelog = Timelog::load(docs.loader().root_dir())?;
+    let totals = totals(docs, &timelog, now);
+
+    let mut is_first = true;
+    for (heading, group) in [
+        ("Projects", &totals.projects),
+        ("Contexts", &totals.contexts),
+    ] {
+        if group.is_empty() {
+            continue;
+        }
+
+        if !is_first {
+            println!();
+        }
+        is_first = false;
+
+        println!("{}:", heading);
+        for (name, duration) in group.iter() {
+            println!("- {}: {}", name, format_duration(*duration));
+        }
+    }
+    Ok(())
+}
+
+/// Time spent per project title and per context name.
+#[derive(Debug, Default, PartialEq)]
+pub struct Totals {
+    pub projects: BTreeMap<String, Duration>,
+    pub contexts: BTreeMap<String, Duration>,
+}
+
+/// Sums the time in `timelog` per project and per context that the action is in.
+pub fn totals(docs: &Documents, timelog: &Timelog, now: NaiveDateTime) -> Totals {
+    let mut totals = Totals::default();
+
+    for session in timelog.sessions() {
+        let duration = session.duration(now);
+        let project = project_for(docs, &session.target.project_name);
+        let title = project.map_or(session.target.project_name.as_str(), Project::title);
+        *totals
+            .projects
+            .entry(title.to_string())
+            .or_insert_with(Duration::zero) += duration;
+
+        let action = match (project, &session.target.action_id) {
+            (Some(p), Some(id)) => docs.linked_action(&ActionRef {
+                project_name: p.name.clone(),
+                action_id: id.clone(),
+            }),
+            _ => None,
+        };
+        for context in action.iter().flat_map(|a| a.contexts()) {
+            *totals
+                .contexts
+                .entry(context.name.to_string())
+                .or_insert_with(Duration::zero) += duration;
+        }
+    }
+
+    totals
+}
+
+fn project_for<'a>(docs: &'a Documents, name: &str) -> Option<&'a Project> {
+    let name = ProjectName::with_scheme(name.to_string(), docs.loader().scheme())?;
+    docs.project(&name)
+}
+
+/// Appends a finished session to the time log in `root_dir`, stopping the running timer when the
+/// session started if there was one.
+pub fn log_session(root_dir: &Path, session: &Session) -> Result<(), IoError> {
+    let end = match session.end {
+        Some(end) => end,
+        None => return Ok(()),
+    };
+
+    let mut lines = Vec::new();
+    if Timelog::load(root_dir)?.running().is_some() {
+        lines.push(stop_entry(session.start));
+    }
+    lines.push(start_entry(
+        session.start,
+        &session.target,
+        session.is_pomodoro,
+    ));
+    lines.push(stop_entry(end));
+    append(root_dir, &lines)
+}
+
+fn start_entry(at: NaiveDateTime, target: &Target, is_pomodoro: bool) -> String {
+    let mut entry = format!("- {} start {}", at.format(TIME_FORMAT), target);
+    if is_pomodoro {
+        entry.push(' ');
+        entry.push_str(POMODORO_TAG);
+    }
+    entry
+}
+
+fn stop_entry(at: NaiveDateTime) -> String {
+    format!("- {} stop", at.format(TIME_FORMAT))
+}
+
+/// Appends `lines` to the time log, creating it if needed.
+fn append(root_dir: &Path, lines: &[String]) -> Result<(), IoError> {
+    let path = path(root_dir);
+    let needs_newline = match fs::read_to_string(&path) {
+        Ok(text) => !text.is_empty() && !text.ends_with('\n'),
+        Err(_) => false,
+    };
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if needs_newline {
+        writeln!(file)?;
+    }
+    for line in lines {
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Formats `duration` as hours and minutes, like `1h 05m`.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+#[derive(Debug)]
+pub enum TimelogError {
+    IoError(IoError),
+    ResolveError(ResolveError),
+    NotRunning,
+}
+
+impl fmt::Display for TimelogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::ResolveError(e) => write!(f, "{}", e),
+            Self::NotRunning => write!(f, "no timer is running"),
+        }
+    }
+}
+
+impl Error for TimelogError {}
+
+impl From<IoError> for TimelogError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl From<ResolveError> for TimelogError {
+    fn from(error: ResolveError) -> Self {
+        Self::ResolveError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn time(h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 5)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    fn target(id: Option<&str>) -> Target {
+        Target {
+            project_name: String::from("202401011200 Plan trip"),
+            action_id: id.map(|id| ActionId::new(id.to_string())),
+        }
+    }
+
+    mod parse {
+        use super::*;
+
+        #[test]
+        fn start_and_stop_make_session() {
+            let text = "# Timelog\n\n- 2024-01-05 14:00 start [[202401011200 Plan trip#^abcdef]]\n- 2024-01-05 14:30 stop\n";
+            let timelog = Timelog::parse(text);
+            assert_eq!(
+                timelog.sessions(),
+                &[Session {
+                    target: target(Some("abcdef")),
+                    start: time(14, 0),
+                    end: Some(time(14, 30)),
+                    is_pomodoro: false,
+                }]
+            );
+            assert_eq!(timelog.running(), None);
+        }
+
+        #[test]
+        fn start_stops_running_session() {
+            let text = "- 2024-01-05 14:00 start [[202401011200 Plan trip]]\n- 2024-01-05 14:30 start [[202401011200 Plan trip#^abcdef]]\n";
+            let timelog = Timelog::parse(text);
+            assert_eq!(timelog.sessions()[0].end, Some(time(14, 30)));
+            assert_eq!(timelog.running().unwrap().target, target(Some("abcdef")));
+        }
+
+        #[test]
+        fn pomodoros_are_counted() {
+            let text = "- 2024-01-05 14:00 start [[202401011200 Plan trip#^abcdef]] #pomodoro\n- 2024-01-05 14:25 stop\n- 2024-01-05 14:30 start [[202401011200 Plan trip#^abcdef]]\n- 2024-01-05 14:55 stop\n- 2024-01-05 15:00 start [[202401011200 Plan trip#^abcdef]] #pomodoro\n";
+            let timelog = Timelog::parse(text);
+            assert!(timelog.sessions()[0].is_pomodoro);
+            // The last pomodoro hasn't finished.
+            assert_eq!(timelog.pomodoros(&target(Some("abcdef"))), 1);
+        }
+
+        #[test]
+        fn stop_without_start_is_ignored() {
+            let timelog = Timelog::parse("- 2024-01-05 14:30 stop\n");
+            assert!(timelog.sessions().is_empty());
+        }
+
+        #[test]
+        fn invalid_time_is_ignored() {
+            let timelog = Timelog::parse("- 2024-13-05 14:30 start [[Plan trip]]\n");
+            assert!(timelog.sessions().is_empty());
+        }
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let text = format!(
+            "{}\n{}\n",
+            start_entry(time(9, 0), &target(Some("abcdef")), false),
+            stop_entry(time(9, 45))
+        );
+        let timelog = Timelog::parse(&text);
+        assert_eq!(
+            timelog.sessions()[0].duration(time(12, 0)),
+            Duration::minutes(45)
+        );
+    }
+
+    #[test]
+    fn running_session_counts_until_now() {
+        let session = Session {
+            target: target(None),
+            start: time(9, 0),
+            end: None,
+            is_pomodoro: false,
+        };
+        assert_eq!(session.duration(time(10, 5)), Duration::minutes(65));
+    }
+
+    #[test]
+    fn duration_is_formatted_as_hours_and_minutes() {
+        assert_eq!(format_duration(Duration::minutes(65)), "1h 05m");
+    }
+}
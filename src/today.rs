@@ -0,0 +1,182 @@
+//! A short list of what to focus on today: overdue actions, actions due today, and the most
+//! important next actions in each context.
+//!
+//! The list only depends on the documents and the date, so it can be regenerated every morning and
+//! only changes when they do.
+
+use crate::{
+    context::Context,
+    gtd::Documents,
+    project::{Action, ActionStatus, Project, Status, DUE_MARKER},
+};
+use chrono::NaiveDate;
+use std::{cmp::Reverse, fs, io::Error as IoError, path::PathBuf};
+
+/// The file in the root of the GTD folder that the list is written to.
+pub const TODAY_FILENAME: &str = "Today.md";
+
+/// An action on the list, along with its project.
+#[derive(Debug, Clone, Copy)]
+pub struct Entry<'a> {
+    pub project: &'a Project,
+    pub action: &'a Action,
+}
+
+/// The actions to focus on for a day.
+#[derive(Debug)]
+pub struct Today<'a> {
+    pub date: NaiveDate,
+    /// Actions due before the day, soonest first.
+    pub overdue: Vec<Entry<'a>>,
+    /// Actions due on the day.
+    pub due: Vec<Entry<'a>>,
+    /// The most important next actions in each context that aren't overdue or due, by context
+    /// name.
+    pub next: Vec<(&'a Context, Vec<Entry<'a>>)>,
+}
+
+/// Builds the list for `date`, with up to `per_context` next actions from each context.
+///
+/// Next actions are picked by priority, and then by the order they're listed in their context.
+pub fn today(docs: &Documents, date: NaiveDate, per_context: usize) -> Today<'_> {
+    let mut dated = docs
+        .actions()
+        .filter(|a| a.project.status != Status::Complete && a.status != ActionStatus::Complete)
+        .filter(|a| a.action.is_due(date))
+        .map(|a| Entry {
+            project: a.project,
+            action: a.action,
+        })
+        .collect::<Vec<_>>();
+    dated.sort_by(|a, b| {
+        (a.action.due, &a.project.name, a.action.text.to_string()).cmp(&(
+            b.action.due,
+            &b.project.name,
+            b.action.text.to_string(),
+        ))
+    });
+    let (due, overdue) = dated
+        .into_iter()
+        .partition::<Vec<_>, _>(|e| e.action.due == Some(date));
+
+    let next = docs
+        .contexts()
+        .filter_map(|context| {
+            let mut entries = docs
+                .actions_in_context(&context.name)
+                .filter(|a| {
+                    a.status != ActionStatus::Complete
+                        && !a.action.is_deferred(date)
+                        && !a.action.is_due(date)
+                })
+                .map(|a| Entry {
+                    project: a.project,
+                    action: a.action,
+                })
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|e| Reverse(e.action.priority));
+            entries.truncate(per_context);
+            (!entries.is_empty()).then_some((context, entries))
+        })
+        .collect();
+
+    Today {
+        date,
+        overdue,
+        due,
+        next,
+    }
+}
+
+/// Renders the list as a Markdown document, linking each action to its project.
+pub fn render(today: &Today) -> String {
+    let mut out = format!("# Today, {}\n", today.date.format("%Y-%m-%d"));
+    if today.overdue.is_empty() && today.due.is_empty() && today.next.is_empty() {
+        out.push_str("\nNothing is due, and there are no next actions.\n");
+        return out;
+    }
+
+    let sections = [("Overdue", &today.overdue), ("Due today", &today.due)];
+    for (heading, entries) in sections.iter() {
+        if entries.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n## {}\n\n", heading));
+        for entry in entries.iter() {
+            out.push_str(&render_entry(entry));
+        }
+    }
+
+    if !today.next.is_empty() {
+        out.push_str("\n## Next\n");
+        for (context, entries) in today.next.iter() {
+            out.push_str(&format!("\n### {}\n\n", context.name));
+            for entry in entries {
+                out.push_str(&render_entry(entry));
+            }
+        }
+    }
+
+    out
+}
+
+fn render_entry(entry: &Entry) -> String {
+    let mut line = format!("- {}", entry.action.text);
+    if let Some(marker) = entry.action.priority.marker() {
+        line.push_str(&format!(" {}", marker));
+    }
+    line.push_str(&format!(
+        " ([[{}|{}]])",
+        entry.project.name,
+        entry.project.title()
+    ));
+    if let Some(due) = entry.action.due {
+        line.push_str(&format!(" {} {}", DUE_MARKER, due.format("%Y-%m-%d")));
+    }
+    line.push('\n');
+    line
+}
+
+/// Writes `text` to the today file in the GTD folder of `docs`, unless it already has that text.
+///
+/// Returns the path of the file and whether it was written.
+pub fn write(docs: &Documents, text: &str) -> Result<(PathBuf, bool), IoError> {
+    let path = docs.loader().root_dir().join(TODAY_FILENAME);
+    if fs::read_to_string(&path).is_ok_and(|old| old == text) {
+        return Ok((path, false));
+    }
+    fs::write(&path, text)?;
+    Ok((path, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::test_dir::TempDir;
+
+    #[test]
+    fn list_is_built_from_due_dates_and_priorities() {
+        let dir = TempDir::new("today");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(
+            dir.join("Projects/202401010000 Plan trip.md"),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights 📅 2024-01-03 ^aaaaaa\n- Renew passport 📅 2024-01-05 ^bbbbbb\n- Pack bags ^cccccc\n- Buy guidebook ⏫ ^dddddd\n- Pick hotel 🛫 2024-01-09 ^eeeeee\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("Contexts/@home.md"),
+            "# @home\n\n- ![[202401010000 Plan trip#^aaaaaa]]\n- ![[202401010000 Plan trip#^eeeeee]]\n- ![[202401010000 Plan trip#^cccccc]]\n- ![[202401010000 Plan trip#^dddddd]]\n",
+        )
+        .unwrap();
+
+        let docs = Documents::load(&dir, &Config::default()).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+
+        assert_eq!(
+            render(&today(&docs, date, 1)),
+            "# Today, 2024-01-05\n\n## Overdue\n\n- Book flights ([[202401010000 Plan trip|Plan trip]]) 📅 2024-01-03\n\n## Due today\n\n- Renew passport ([[202401010000 Plan trip|Plan trip]]) 📅 2024-01-05\n\n## Next\n\n### @home\n\n- Buy guidebook ⏫ ([[202401010000 Plan trip|Plan trip]])\n"
+        );
+    }
+}
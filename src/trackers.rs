@@ -0,0 +1,255 @@
+//! Looking up issues in GitHub and Jira, for `gtd issues sync` and `gtd import`.
+//!
+//! Requests are made with `curl`, and tokens are passed to it on stdin so they don't show up in the
+//! process list.
+
+use crate::{
+    config::IssuesConfig,
+    issues::{Issue, IssueState, IssueTracker, OpenIssue},
+    obsidian::percent_encode,
+};
+use serde_json::Value;
+use std::{
+    io::{Error as IoError, ErrorKind, Write},
+    process::{Command, Stdio},
+};
+
+/// How many issues GitHub is asked for at a time, which is the most it allows.
+const PAGE_SIZE: usize = 100;
+
+/// The GitHub and Jira sites in the config.
+#[derive(Debug, Clone)]
+pub struct Trackers {
+    github_url: String,
+    github_token: Option<String>,
+    jira_url: Option<String>,
+    jira_credentials: Option<String>,
+}
+
+impl Trackers {
+    /// Sets up the trackers in `config`, running their token commands if they have them.
+    pub fn from_config(config: &IssuesConfig) -> Result<Self, IoError> {
+        let github_token = config
+            .github_token_command
+            .as_deref()
+            .map(|c| secret("GitHub token", c))
+            .transpose()?;
+        let jira_credentials = match (&config.jira_username, &config.jira_token_command) {
+            (Some(username), Some(command)) => {
+                Some(format!("{}:{}", username, secret("Jira token", command)?))
+            }
+            (Some(username), None) => Some(username.clone()),
+            (None, _) => None,
+        };
+        Ok(Self {
+            github_url: config.github_url.trim_end_matches('/').to_string(),
+            github_token,
+            jira_url: config
+                .jira_url
+                .as_ref()
+                .map(|u| u.trim_end_matches('/').to_string()),
+            jira_credentials,
+        })
+    }
+
+    /// Gets the JSON at `url` with `curl`, passing it the lines of `config`.
+    fn get(url: &str, headers: &[&str], config: &[String]) -> Result<Value, IoError> {
+        let mut command = Command::new("curl");
+        command.args(["-fsS", "-K", "-"]);
+        for header in headers {
+            command.args(["-H", header]);
+        }
+        command
+            .arg(url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = command.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            for line in config {
+                writeln!(stdin, "{}", line)?;
+            }
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr);
+            return Err(IoError::other(format!(
+                "request failed: {}",
+                message.trim()
+            )));
+        }
+        serde_json::from_slice(&output.stdout).map_err(|e| IoError::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Gets the JSON at `path` in the GitHub API, like `/repos/owner/repo/issues/1`.
+    pub fn github(&self, path: &str) -> Result<Value, IoError> {
+        let config = self
+            .github_token
+            .iter()
+            .map(|t| format!("header = \"Authorization: Bearer {}\"", curl_escape(t)))
+            .collect::<Vec<_>>();
+        Self::get(
+            &format!("{}{}", self.github_url, path),
+            &["Accept: application/vnd.github+json"],
+            &config,
+        )
+    }
+
+    /// The open issues in the GitHub repository `repo` that are assigned to `assignee`, or to the
+    /// owner of the token if it's `me`.
+    pub fn assigned_issues(&self, repo: &str, assignee: &str) -> Result<Vec<OpenIssue>, IoError> {
+        let assignee = match assignee {
+            "me" => self.github("/user")?["login"]
+                .as_str()
+                .ok_or_else(|| IoError::new(ErrorKind::InvalidData, "the response has no login"))?
+                .to_string(),
+            assignee => assignee.to_string(),
+        };
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let response = self.github(&format!(
+                "/repos/{}/issues?state=open&assignee={}&per_page={}&page={}",
+                repo,
+                percent_encode(&assignee),
+                PAGE_SIZE,
+                page
+            ))?;
+            let items = response
+                .as_array()
+                .ok_or_else(|| IoError::new(ErrorKind::InvalidData, "the response isn't a list"))?;
+            issues.extend(items.iter().filter_map(|i| open_issue(repo, i)));
+            if items.len() < PAGE_SIZE {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    fn jira(&self, path: &str) -> Result<Value, IoError> {
+        let url = self
+            .jira_url
+            .as_ref()
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "no Jira URL is configured"))?;
+        let config = self
+            .jira_credentials
+            .iter()
+            .map(|c| format!("user = \"{}\"", curl_escape(c)))
+            .collect::<Vec<_>>();
+        Self::get(
+            &format!("{}{}", url, path),
+            &["Accept: application/json"],
+            &config,
+        )
+    }
+}
+
+impl IssueTracker for Trackers {
+    fn state(&mut self, issue: &Issue) -> Result<IssueState, IoError> {
+        let response = match issue {
+            Issue::GitHub { repo, number } => {
+                self.github(&format!("/repos/{}/issues/{}", repo, number))?
+            }
+            Issue::Jira { key } => self.jira(&format!(
+                "/rest/api/2/issue/{}?fields=status",
+                percent_encode(key)
+            ))?,
+        };
+        state_in_response(issue, &response)
+            .ok_or_else(|| IoError::new(ErrorKind::InvalidData, "the response has no state"))
+    }
+}
+
+/// The state of `issue` in the API's response for it. Jira issues are closed once their status is
+/// in the done category, whatever the status is called.
+fn state_in_response(issue: &Issue, response: &Value) -> Option<IssueState> {
+    let closed = match issue {
+        Issue::GitHub { .. } => response["state"].as_str()? == "closed",
+        Issue::Jira { .. } => {
+            response["fields"]["status"]["statusCategory"]["key"].as_str()? == "done"
+        }
+    };
+    Some(if closed {
+        IssueState::Closed
+    } else {
+        IssueState::Open
+    })
+}
+
+/// The issue in an item of GitHub's list of issues in `repo`. Pull requests are listed as issues
+/// too, so they're left out.
+fn open_issue(repo: &str, item: &Value) -> Option<OpenIssue> {
+    if item.get("pull_request").is_some() {
+        return None;
+    }
+    Some(OpenIssue {
+        issue: Issue::GitHub {
+            repo: repo.to_string(),
+            number: item["number"].as_u64()?,
+        },
+        title: item["title"].as_str()?.to_string(),
+    })
+}
+
+/// Runs the shell `command`, returning what it prints.
+fn secret(name: &str, command: &str) -> Result<String, IoError> {
+    let output = Command::new("sh").args(["-c", command]).output()?;
+    if !output.status.success() {
+        return Err(IoError::other(format!("{} command failed", name)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches('\n')
+        .to_string())
+}
+
+fn curl_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn states_are_read_from_responses() {
+        let github = "acme/app#1".parse().unwrap();
+        assert_eq!(
+            state_in_response(&github, &json!({ "number": 1, "state": "closed" })),
+            Some(IssueState::Closed)
+        );
+        assert_eq!(
+            state_in_response(&github, &json!({ "state": "open" })),
+            Some(IssueState::Open)
+        );
+        assert_eq!(state_in_response(&github, &json!({})), None);
+
+        let jira = "OPS-7".parse().unwrap();
+        let response = |category: &str| {
+            let status = json!({ "name": "Shipped", "statusCategory": { "key": category } });
+            json!({ "fields": { "status": status } })
+        };
+        assert_eq!(
+            state_in_response(&jira, &response("done")),
+            Some(IssueState::Closed)
+        );
+        assert_eq!(
+            state_in_response(&jira, &response("indeterminate")),
+            Some(IssueState::Open)
+        );
+    }
+
+    #[test]
+    fn pull_requests_are_not_imported() {
+        let issue = json!({ "number": 3, "title": "Fix login", "state": "open" });
+        assert_eq!(
+            open_issue("acme/app", &issue),
+            Some(OpenIssue {
+                issue: "acme/app#3".parse().unwrap(),
+                title: String::from("Fix login"),
+            })
+        );
+        let pull_request = json!({ "number": 4, "title": "Fix login", "pull_request": {} });
+        assert_eq!(open_issue("acme/app", &pull_request), None);
+    }
+}
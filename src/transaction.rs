@@ -0,0 +1,436 @@
+//! Changing several files at once, so a command either changes all of them or none.
+//!
+//! A transaction stages the new text of each file it's going to change, along with the files it's
+//! going to delete or move, without touching any of them. When it's committed, the staged projects
+//! and contexts are parsed first, and nothing is written if any of them can't be read any more.
+//! Otherwise the files are recorded so `gtd undo` can put them back, and written one by one. If
+//! one of them can't be written, the ones already written are put back the way they were.
+
+use crate::{
+    conflict::ConflictedFile,
+    context::{Context, Name as ContextName},
+    diff::{self, WriteMode},
+    gtd::Loader,
+    project::{Name as ProjectName, Project},
+    trash::{self, Trash},
+    undo::{Undo, UNDO_FILENAME},
+};
+use chrono::Local;
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt, fs,
+    io::{Error as IoError, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// Changes to files that are written together when they're committed.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    loader: &'a Loader,
+    /// What's making the change, like `merge-context @online @computer`, for `gtd undo`.
+    description: String,
+    files: BTreeMap<PathBuf, File>,
+}
+
+/// A file read or changed by a transaction.
+#[derive(Debug)]
+struct File {
+    /// The text the file had when it was first read, or `None` if it didn't exist.
+    old: Option<String>,
+    change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    Keep,
+    Write(String),
+    /// The file is moved into the trash.
+    Trash,
+    /// The file is removed, because it was moved somewhere else in the same transaction.
+    Remove,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn new<S: Into<String>>(loader: &'a Loader, description: S) -> Self {
+        Self {
+            loader,
+            description: description.into(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// The text the file at `path` will have once the transaction is committed, or `None` if it
+    /// won't exist.
+    pub fn read(&mut self, path: &Path) -> Result<Option<String>, IoError> {
+        let file = self.file(path)?;
+        Ok(match &file.change {
+            Change::Keep => file.old.clone(),
+            Change::Write(text) => Some(text.clone()),
+            Change::Trash | Change::Remove => None,
+        })
+    }
+
+    /// Stages writing `text` to the file at `path`, creating it if needed.
+    pub fn write<S: Into<String>>(&mut self, path: &Path, text: S) -> Result<(), IoError> {
+        self.file(path)?.change = Change::Write(text.into());
+        Ok(())
+    }
+
+    /// Stages moving the file at `path` into the trash.
+    pub fn delete(&mut self, path: &Path) -> Result<(), IoError> {
+        let file = self.file(path)?;
+        if file.old.is_none() {
+            return Err(not_found(path));
+        }
+        file.change = Change::Trash;
+        Ok(())
+    }
+
+    /// Stages moving the file at `from` to `to`, with any changes already staged for it. Fails if
+    /// there's already a file at `to`.
+    pub fn rename(&mut self, from: &Path, to: &Path) -> Result<(), IoError> {
+        let text = self.read(from)?.ok_or_else(|| not_found(from))?;
+        if self.read(to)?.is_some() {
+            return Err(IoError::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", to.display()),
+            ));
+        }
+        self.write(to, text)?;
+        self.file(from)?.change = Change::Remove;
+        Ok(())
+    }
+
+    /// Shows each staged change as a word diff, according to `mode`, returning whether the changes
+    /// should be written.
+    pub fn review(&self, mode: WriteMode) -> Result<bool, IoError> {
+        let mut is_written = true;
+        for (path, file) in self.changes() {
+            let name = display_name(path);
+            match &file.change {
+                Change::Write(text) => {
+                    let old = file.old.as_deref().unwrap_or_default();
+                    is_written &= diff::review(&name, old, text, mode)?;
+                }
+                Change::Trash => println!("{}:\n(deleted)", name),
+                Change::Remove => println!("{}:\n(moved)", name),
+                Change::Keep => {}
+            }
+        }
+        Ok(is_written)
+    }
+
+    /// Fails if any staged project or context that could be read before the transaction can't be
+    /// read after it.
+    pub fn validate(&self) -> Result<(), TransactionError> {
+        for (path, file) in self.changes() {
+            let text = match &file.change {
+                Change::Write(text) => text,
+                _ => continue,
+            };
+            let was_readable = match &file.old {
+                Some(old) => self.parse(path, old).is_none_or(|r| r.is_ok()),
+                None => true,
+            };
+            if !was_readable {
+                continue;
+            }
+            if let Some(Err(error)) = self.parse(path, text) {
+                return Err(TransactionError::Invalid(path.clone(), error));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses `text` as the document at `path`, returning `None` if it's neither a project nor a
+    /// context.
+    fn parse(&self, path: &Path, text: &str) -> Option<Result<(), String>> {
+        let name = path.file_stem()?.to_str()?;
+        if let Some(file) = ConflictedFile::check(path, text) {
+            return Some(Err(file.to_string()));
+        }
+        let scheme = self.loader.scheme();
+        match ProjectName::with_scheme(name.to_string(), scheme) {
+            Some(project) if self.loader.project_path(&project) == path => Some(
+                Project::parse_with_depths(name, text, scheme, self.loader.depths())
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+            ),
+            _ if self
+                .loader
+                .context_path(&ContextName::new(name.to_string()))
+                == path =>
+            {
+                Some(
+                    Context::parse_with_scheme(name, text, scheme)
+                        .map(|_| ())
+                        .map_err(|e| e.to_string()),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Validates the staged changes and writes them, after recording the files so `gtd undo` can
+    /// put them back. Returns whether anything changed.
+    ///
+    /// If a file can't be written, the files already written are put back, along with the change
+    /// that `gtd undo` would have undone, and the error is returned.
+    pub fn commit(self) -> Result<bool, TransactionError> {
+        self.validate()?;
+        let changes = self.changes().collect::<Vec<_>>();
+        if changes.is_empty() {
+            return Ok(false);
+        }
+
+        let undo_path = self.loader.state_dir().join(UNDO_FILENAME);
+        let last_undo = read(&undo_path)?;
+        let mut undo = Undo::new(self.description.as_str());
+        for (path, _) in changes.iter() {
+            undo.record(self.loader, path)?;
+        }
+        let trash = changes
+            .iter()
+            .any(|(_, f)| f.change == Change::Trash)
+            .then(|| Trash::new(self.loader, Local::now().naive_local()));
+        if let Some(trash) = &trash {
+            undo.record_trash(trash);
+        }
+        undo.save(self.loader)?;
+
+        let mut applied = Vec::new();
+        for (path, file) in changes.iter() {
+            if let Err(error) = self.apply(path, &file.change, trash.as_ref()) {
+                // Putting the files back is as good as it can be; the error that stopped the
+                // transaction is the one that's reported.
+                let _ = self.roll_back(&applied, trash.as_ref(), &undo_path, last_undo);
+                return Err(error.into());
+            }
+            applied.push((*path, *file));
+        }
+        Ok(true)
+    }
+
+    fn apply(&self, path: &Path, change: &Change, trash: Option<&Trash>) -> Result<(), IoError> {
+        match (change, trash) {
+            (Change::Write(text), _) => {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(path, text)
+            }
+            (Change::Trash, Some(trash)) => trash.delete(self.loader, path).map(|_| ()),
+            (Change::Remove, _) | (Change::Trash, None) => fs::remove_file(path),
+            (Change::Keep, _) => Ok(()),
+        }
+    }
+
+    /// Puts the `applied` files back the way they were, and the last change to undo back to
+    /// `last_undo`.
+    fn roll_back(
+        &self,
+        applied: &[(&PathBuf, &File)],
+        trash: Option<&Trash>,
+        undo_path: &Path,
+        last_undo: Option<String>,
+    ) -> Result<(), IoError> {
+        for (path, file) in applied.iter().rev() {
+            match (&file.change, &file.old) {
+                (Change::Trash, _) => {}
+                (_, Some(old)) => fs::write(path, old)?,
+                (_, None) => fs::remove_file(path)?,
+            }
+        }
+        if let Some(trash) = trash {
+            if applied.iter().any(|(_, f)| f.change == Change::Trash) {
+                trash::restore(self.loader, &trash.stamp)
+                    .map_err(|e| IoError::other(e.to_string()))?;
+            }
+        }
+        match last_undo {
+            Some(text) => fs::write(undo_path, text),
+            None => fs::remove_file(undo_path),
+        }
+    }
+
+    /// The staged files that change.
+    fn changes(&self) -> impl Iterator<Item = (&PathBuf, &File)> {
+        self.files.iter().filter(|(_, f)| match &f.change {
+            Change::Keep => false,
+            Change::Write(text) => f.old.as_ref() != Some(text),
+            Change::Trash | Change::Remove => true,
+        })
+    }
+
+    fn file(&mut self, path: &Path) -> Result<&mut File, IoError> {
+        if !self.files.contains_key(path) {
+            let old = read(path)?;
+            self.files.insert(
+                path.to_path_buf(),
+                File {
+                    old,
+                    change: Change::Keep,
+                },
+            );
+        }
+        Ok(self.files.get_mut(path).unwrap())
+    }
+}
+
+/// The text of the file at `path`, or `None` if it doesn't exist.
+fn read(path: &Path) -> Result<Option<String>, IoError> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn not_found(path: &Path) -> IoError {
+    IoError::new(
+        ErrorKind::NotFound,
+        format!("{} doesn't exist", path.display()),
+    )
+}
+
+/// The name a file is shown with in diffs, which is a document's name without `.md`.
+fn display_name(path: &Path) -> String {
+    let name = match path.extension() {
+        Some(extension) if extension == "md" => path.file_stem(),
+        _ => path.file_name(),
+    };
+    name.unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[derive(Debug)]
+pub enum TransactionError {
+    IoError(IoError),
+    /// A staged document that could be read before can't be after, and why.
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "{}", e),
+            Self::Invalid(path, e) => write!(
+                f,
+                "nothing was written, since {} would no longer be readable: {}",
+                display_name(path),
+                e
+            ),
+        }
+    }
+}
+
+impl Error for TransactionError {}
+
+impl From<IoError> for TransactionError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    const PROJECT: &str =
+        "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights\n";
+
+    fn folder() -> (TempDir, Loader) {
+        let dir = TempDir::new("transaction");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        fs::write(dir.join("Projects/202401010000 Plan trip.md"), PROJECT).unwrap();
+        fs::write(dir.join("Contexts/@home.md"), "# @home\n\n- Water plants\n").unwrap();
+        let loader = Loader::new(dir.to_path_buf(), Default::default());
+        (dir, loader)
+    }
+
+    #[test]
+    fn changes_are_written_together() {
+        let (dir, loader) = folder();
+        let project = dir.join("Projects/202401010000 Plan trip.md");
+        let home = dir.join("Contexts/@home.md");
+        let mut transaction = Transaction::new(&loader, "test");
+        transaction.write(&home, "# @home\n").unwrap();
+        transaction
+            .rename(&project, &dir.join("Archive/202401010000 Plan trip.md"))
+            .unwrap();
+        transaction.delete(&dir.join("Contexts/@home.md")).unwrap();
+        assert_eq!(transaction.read(&home).unwrap(), None);
+        assert_eq!(transaction.read(&project).unwrap(), None);
+        assert_eq!(
+            fs::read_to_string(&home).unwrap(),
+            "# @home\n\n- Water plants\n"
+        );
+        assert!(transaction.commit().unwrap());
+
+        assert!(!home.exists() && !project.exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("Archive/202401010000 Plan trip.md")).unwrap(),
+            PROJECT
+        );
+        Undo::load(&loader)
+            .unwrap()
+            .unwrap()
+            .restore(&loader)
+            .unwrap();
+        assert_eq!(fs::read_to_string(&project).unwrap(), PROJECT);
+        assert!(home.exists());
+    }
+
+    #[test]
+    fn nothing_is_written_if_a_document_would_be_unreadable() {
+        let (dir, loader) = folder();
+        let project = dir.join("Projects/202401010000 Plan trip.md");
+        let home = dir.join("Contexts/@home.md");
+        let mut transaction = Transaction::new(&loader, "test");
+        transaction.write(&home, "# @home\n").unwrap();
+        transaction.write(&project, "# Plan trip\n").unwrap();
+        assert!(matches!(
+            transaction.commit(),
+            Err(TransactionError::Invalid(path, _)) if path == project
+        ));
+        assert_eq!(fs::read_to_string(&project).unwrap(), PROJECT);
+        assert_eq!(
+            fs::read_to_string(&home).unwrap(),
+            "# @home\n\n- Water plants\n"
+        );
+        assert!(Undo::load(&loader).unwrap().is_none());
+    }
+
+    #[test]
+    fn written_files_are_put_back_if_one_fails() {
+        let (dir, loader) = folder();
+        let home = dir.join("Contexts/@home.md");
+        let mut last = Undo::new("last");
+        last.record(&loader, &home).unwrap();
+        last.save(&loader).unwrap();
+
+        let mut transaction = Transaction::new(&loader, "test");
+        transaction.write(&home, "# @home\n").unwrap();
+        transaction
+            .write(&dir.join("Logbook.md"), "- done\n")
+            .unwrap();
+        transaction.write(&dir.join("Notes/a.md"), "a\n").unwrap();
+        // A file can't be written inside another file.
+        fs::write(dir.join("Notes"), "").unwrap();
+        assert!(matches!(
+            transaction.commit(),
+            Err(TransactionError::IoError(_))
+        ));
+
+        assert_eq!(
+            fs::read_to_string(&home).unwrap(),
+            "# @home\n\n- Water plants\n"
+        );
+        assert!(!dir.join("Logbook.md").exists());
+        assert_eq!(Undo::load(&loader).unwrap(), Some(last));
+    }
+}
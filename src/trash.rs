@@ -0,0 +1,207 @@
+//! A trash folder that commands move files into instead of deleting them, so they can be restored.
+//!
+//! Each command that deletes files puts them in a folder of their own in `.gtd/trash`, named for
+//! when they were deleted, like `20240105093000`, with the same paths they had in the GTD folder.
+
+use crate::gtd::Loader;
+use chrono::NaiveDateTime;
+use std::{
+    error::Error,
+    fmt, fs,
+    io::{Error as IoError, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// The folder in the state directory that deleted files are moved into.
+pub const TRASH_DIRNAME: &str = "trash";
+
+/// Files deleted together, by one command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trash {
+    /// The name of the batch's folder in the trash.
+    pub stamp: String,
+    dir: PathBuf,
+}
+
+impl Trash {
+    /// Starts a batch of files deleted at `now`, named for the time. A batch from earlier in the
+    /// same second gets a number added, like `20240105093000-2`.
+    pub fn new(loader: &Loader, now: NaiveDateTime) -> Self {
+        let base = now.format("%Y%m%d%H%M%S").to_string();
+        let mut stamp = base.clone();
+        let mut n = 1;
+        while dir(loader).join(&stamp).exists() {
+            n += 1;
+            stamp = format!("{}-{}", base, n);
+        }
+        Self {
+            dir: dir(loader).join(&stamp),
+            stamp,
+        }
+    }
+
+    /// Moves the file at `path` into the trash, returning where it went.
+    pub fn delete(&self, loader: &Loader, path: &Path) -> Result<PathBuf, IoError> {
+        let relative = path.strip_prefix(loader.root_dir()).unwrap_or(path);
+        let to = self.dir.join(relative);
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(path, &to)?;
+        Ok(to)
+    }
+}
+
+/// A batch of files in the trash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub stamp: String,
+    /// The paths of the files, relative to the GTD folder.
+    pub files: Vec<PathBuf>,
+}
+
+/// The batches of files in the trash of the GTD folder of `loader`, oldest first.
+pub fn list(loader: &Loader) -> Result<Vec<Entry>, IoError> {
+    let entries = match fs::read_dir(dir(loader)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut list = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let stamp = path.file_name().unwrap_or_default().to_string_lossy();
+        list.push(Entry {
+            stamp: stamp.into_owned(),
+            files: files(&path)?,
+        });
+    }
+    list.sort_by(|a, b| a.stamp.cmp(&b.stamp));
+    Ok(list)
+}
+
+/// Moves the files in the batch `stamp` back to where they were, returning their paths relative
+/// to the GTD folder. Nothing is moved if any of them would overwrite a file.
+pub fn restore(loader: &Loader, stamp: &str) -> Result<Vec<PathBuf>, TrashError> {
+    let batch = dir(loader).join(stamp);
+    if stamp.is_empty() || !batch.is_dir() {
+        return Err(TrashError::NoSuchEntry(stamp.to_string()));
+    }
+    let files = files(&batch)?;
+    if let Some(file) = files.iter().find(|f| loader.root_dir().join(f).exists()) {
+        return Err(TrashError::AlreadyExists(file.clone()));
+    }
+    for file in files.iter() {
+        let to = loader.root_dir().join(file);
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(batch.join(file), to)?;
+    }
+    fs::remove_dir_all(batch)?;
+    Ok(files)
+}
+
+/// Removes the batch `stamp` from the trash, if it's there.
+pub fn empty(loader: &Loader, stamp: &str) -> Result<(), IoError> {
+    match fs::remove_dir_all(dir(loader).join(stamp)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn dir(loader: &Loader) -> PathBuf {
+    loader.state_dir().join(TRASH_DIRNAME)
+}
+
+/// The files in the folder `dir` and its subfolders, relative to it and sorted.
+fn files(dir: &Path) -> Result<Vec<PathBuf>, IoError> {
+    let mut found = Vec::new();
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(next) = dirs.pop() {
+        for entry in fs::read_dir(next)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                found.push(path.strip_prefix(dir).unwrap_or(&path).to_owned());
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+#[derive(Debug)]
+pub enum TrashError {
+    NoSuchEntry(String),
+    /// Restoring would overwrite the file at this path.
+    AlreadyExists(PathBuf),
+    IoError(IoError),
+}
+
+impl fmt::Display for TrashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoSuchEntry(stamp) => write!(f, "there's nothing in the trash from {}", stamp),
+            Self::AlreadyExists(path) => {
+                write!(f, "{} already exists", path.to_string_lossy())
+            }
+            Self::IoError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for TrashError {}
+
+impl From<IoError> for TrashError {
+    fn from(error: IoError) -> Self {
+        Self::IoError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::NameScheme;
+    use crate::test_dir::TempDir;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn deleted_files_can_be_restored() {
+        let dir = TempDir::new("trash");
+        fs::create_dir_all(dir.join("Contexts")).unwrap();
+        let path = dir.join("Contexts/@online.md");
+        fs::write(&path, "# @online\n").unwrap();
+        let loader = Loader::new(dir.to_path_buf(), NameScheme::default());
+        let now = NaiveDate::from_ymd_opt(2024, 1, 5)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+
+        let trash = Trash::new(&loader, now);
+        trash.delete(&loader, &path).unwrap();
+        assert!(!path.exists());
+        assert_eq!(Trash::new(&loader, now).stamp, "20240105093000-2");
+        assert_eq!(
+            list(&loader).unwrap(),
+            [Entry {
+                stamp: String::from("20240105093000"),
+                files: vec![PathBuf::from("Contexts/@online.md")],
+            }]
+        );
+
+        fs::write(&path, "# @online\n\n- New\n").unwrap();
+        assert!(matches!(
+            restore(&loader, "20240105093000"),
+            Err(TrashError::AlreadyExists(_))
+        ));
+        fs::remove_file(&path).unwrap();
+        restore(&loader, "20240105093000").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# @online\n");
+        assert!(list(&loader).unwrap().is_empty());
+    }
+}
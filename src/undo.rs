@@ -0,0 +1,113 @@
+//! Undoing the last change made by a command that rewrites several files at once.
+//!
+//! Before such a command writes anything, it records what each file it's about to change looked
+//! like, so `gtd undo` can put them back. Files it deletes are moved into the trash, which undoing
+//! it empties again.
+
+use crate::{
+    gtd::Loader,
+    trash::{self, Trash},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error as IoError, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// The file in the state directory that the last change is recorded in.
+pub const UNDO_FILENAME: &str = "undo.json";
+
+/// The files a change is about to touch, as they were before it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Undo {
+    /// What made the change, like `merge-context @online @computer`.
+    pub description: String,
+    /// The text of each file, by its path relative to the GTD folder, or `None` if it didn't exist.
+    files: BTreeMap<PathBuf, Option<String>>,
+    /// The batch in the trash that the change deleted files into, which undoing it empties.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    trash: Option<String>,
+}
+
+impl Undo {
+    pub fn new<S: Into<String>>(description: S) -> Self {
+        Self {
+            description: description.into(),
+            files: BTreeMap::new(),
+            trash: None,
+        }
+    }
+
+    /// Remembers what the file at `path` looks like now, or that it doesn't exist. A file that's
+    /// already remembered is left as it was first seen.
+    pub fn record(&mut self, loader: &Loader, path: &Path) -> Result<(), IoError> {
+        let relative = path
+            .strip_prefix(loader.root_dir())
+            .unwrap_or(path)
+            .to_path_buf();
+        if self.files.contains_key(&relative) {
+            return Ok(());
+        }
+        let text = match fs::read_to_string(path) {
+            Ok(text) => Some(text),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        self.files.insert(relative, text);
+        Ok(())
+    }
+
+    /// Remembers that the change deletes files into `trash`, so that undoing it doesn't leave
+    /// copies of them there.
+    pub fn record_trash(&mut self, trash: &Trash) {
+        self.trash = Some(trash.stamp.clone());
+    }
+
+    /// Saves this as the change to undo, replacing any earlier one.
+    pub fn save(&self, loader: &Loader) -> Result<(), IoError> {
+        let path = path(loader);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(IoError::from)?;
+        fs::write(path, text)
+    }
+
+    /// Loads the last change, if there is one that hasn't been undone.
+    pub fn load(loader: &Loader) -> Result<Option<Self>, IoError> {
+        match fs::read_to_string(path(loader)) {
+            Ok(text) => Ok(Some(serde_json::from_str(&text).map_err(IoError::from)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Puts every file back the way it was, and forgets the change so it can't be undone twice.
+    pub fn restore(self, loader: &Loader) -> Result<(), IoError> {
+        for (relative, text) in self.files {
+            let path = loader.root_dir().join(relative);
+            match text {
+                Some(text) => {
+                    if let Some(dir) = path.parent() {
+                        fs::create_dir_all(dir)?;
+                    }
+                    fs::write(path, text)?;
+                }
+                None => match fs::remove_file(path) {
+                    Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                },
+            }
+        }
+        if let Some(stamp) = self.trash {
+            trash::empty(loader, &stamp)?;
+        }
+        fs::remove_file(self::path(loader))
+    }
+}
+
+fn path(loader: &Loader) -> PathBuf {
+    loader.state_dir().join(UNDO_FILENAME)
+}
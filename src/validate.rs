@@ -1,86 +1,585 @@
 use crate::{
-    context::{Action as ContextAction, Context},
-    gtd::Documents,
-    project::{ActionStatus, Project, Status as ProjectStatus},
+    config::{Config, ContextLayout, TagConfig, ValidateConfig},
+    conflict::ConflictedFile,
+    context::{Action as ContextAction, Context, Name as ContextName},
+    dedupe::{self, is_near_duplicate},
+    edit, filename,
+    fix::{self, Edit, FileChange, Fix},
+    gtd::{Documents, Loader, SkippedFile, UnreadableFile},
+    links::Linker,
+    order,
+    period::Period,
+    progress::Progress,
+    project::{ActionStatus, NameScheme, Project, Status as ProjectStatus},
+    review::{self, REVIEW_EVERY_KEY},
+    schema,
+    transaction::TransactionError,
 };
-use std::{borrow::Cow, collections::HashSet};
-
-pub fn validate(docs: Documents) {
-    ValidatorRunner::new()
-        .for_all_projects(project_id_is_unique())
-        .for_all_projects(project_title_matches_name)
-        .for_all_projects(complete_project_has_only_complete_actions)
-        .for_all_projects(in_progress_project_has_active_actions)
-        .for_all_context_actions(action_link_is_valid)
-        .for_all_context_actions(linked_project_is_in_progress)
-        .for_all_context_actions(linked_project_contains_action)
-        .for_all_context_actions(action_in_project_is_active)
-        .for_all_context_actions(linked_action_is_unique())
-        .with_ad_hoc(all_active_actions_are_in_a_context)
-        .run(&docs);
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt, fs,
+    io::Error as IoError,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Instant,
+};
+
+/// A validation rule, with the documentation that `gtd explain` prints for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    /// A stable ID like `GTD001`, which is shown with each problem the rule finds.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub severity: Severity,
+    /// Whether problems the rule finds can be fixed automatically.
+    pub is_fixable: bool,
+    /// Whether the rule is off unless it's turned on in `[validate]`.
+    pub is_opt_in: bool,
+    /// The rules whose problems cause this one's. These run first, and when one of them finds a
+    /// problem in a project or a context action, this rule isn't run on it, so only the cause is
+    /// reported.
+    pub depends_on: &'static [&'static Rule],
+    /// Why the rule exists.
+    pub rationale: &'static str,
+    /// Markdown that breaks the rule.
+    pub failing: &'static str,
+    /// Markdown that follows the rule.
+    pub passing: &'static str,
+    /// How to fix a problem the rule finds.
+    pub fix: &'static str,
 }
 
-fn project_id_is_unique() -> impl FnMut(&Project) -> Result<(), Cow<'static, str>> {
-    let mut project_ids = HashSet::new();
+impl Rule {
+    /// How many rules deep the chain of rules this one depends on goes.
+    fn depth(&self) -> usize {
+        self.depends_on
+            .iter()
+            .map(|r| r.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
 
-    move |project| {
-        if !project_ids.insert(project.id().to_string()) {
-            return Err("has a duplicate ID".into());
-        }
+/// How serious a problem is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Something is broken, like a link that doesn't point anywhere.
+    Error,
+    /// A convention isn't followed.
+    Warning,
+}
 
-        Ok(())
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
     }
 }
 
-fn project_title_matches_name(project: &Project) -> Result<(), Cow<'static, str>> {
-    let name_title = project.title();
+const PROJECT_ID_IS_UNIQUE: Rule = Rule {
+    id: "GTD001",
+    name: "project-id-is-unique",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "Project IDs are how actions in contexts link to their projects, so two projects \
+                with the same ID make those links ambiguous.",
+    failing: "Projects/202401010000 Plan trip.md\nProjects/202401010000 Buy car.md",
+    passing: "Projects/202401010000 Plan trip.md\nProjects/202401021200 Buy car.md",
+    fix: "Rename one of the projects to give it a new ID.",
+};
 
-    let body_title = project
-        .title
-        .try_to_title_string()
-        .ok_or("has an invalid title in its body")?;
+const PROJECT_TITLE_MATCHES_NAME: Rule = Rule {
+    id: "GTD002",
+    name: "project-title-matches-name",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "A project's title is shown everywhere its filename is used, so the two should \
+                say the same thing.",
+    failing: "<!-- Projects/202401010000 Plan trip.md -->\n# Plan vacation",
+    passing: "<!-- Projects/202401010000 Plan trip.md -->\n# Plan trip",
+    fix: "Change the project's heading or rename its file so that they match.",
+};
 
-    if name_title != body_title {
-        return Err(format!("has a name \"{}\" that doesn't match its title", body_title).into());
-    }
+const PROJECT_TAGS_ARE_ALLOWED: Rule = Rule {
+    id: "GTD003",
+    name: "project-tags-are-allowed",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "When `allowed` is set in `[tags]`, only those tags and the tags nested under \
+                them are expected, which catches typos.",
+    failing: "# Plan trip\n#in-progress #area/hoem",
+    passing: "# Plan trip\n#in-progress #area/home",
+    fix: "Fix the tag, or add it to `allowed` in `[tags]`.",
+};
 
-    Ok(())
-}
+const PROJECT_HAS_REQUIRED_TAGS: Rule = Rule {
+    id: "GTD004",
+    name: "project-has-required-tags",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "Each tag in `required` in `[tags]` sorts projects into groups, like areas of \
+                responsibility, and every project belongs to exactly one of each.",
+    failing: "# Plan trip\n#in-progress",
+    passing: "# Plan trip\n#in-progress #area/home",
+    fix: "Give the project exactly one tag nested under each required tag.",
+};
 
-fn complete_project_has_only_complete_actions(project: &Project) -> Result<(), Cow<'static, str>> {
-    if project.status != ProjectStatus::Complete {
-        return Ok(());
-    }
+const COMPLETE_PROJECT_HAS_ONLY_COMPLETE_ACTIONS: Rule = Rule {
+    id: "GTD005",
+    name: "complete-project-has-only-complete-actions",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "A project that's done shouldn't have anything left to do.",
+    failing: "# Plan trip\n#complete\n\n## Actions\n\n### Active\n\n- Pack bags",
+    passing: "# Plan trip\n#complete\n\n## Actions\n\n### Complete\n\n- Pack bags",
+    fix: "Complete the remaining actions, or move the project back to in progress.",
+};
 
-    let are_all_actions_complete = project
-        .actions
-        .actions()
-        .all(|(_, status)| status == ActionStatus::Complete);
+const IN_PROGRESS_PROJECT_HAS_ACTIVE_ACTIONS: Rule = Rule {
+    id: "GTD006",
+    name: "in-progress-project-has-active-actions",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "A project that's in progress needs a next action, or something it's waiting on, \
+                to keep moving.",
+    failing: "# Plan trip\n#in-progress\n\n## Actions\n\n### Upcoming\n\n- Pack bags",
+    passing: "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack bags",
+    fix: "Add an active or waiting action, or move the project to someday or complete.",
+};
 
-    if !are_all_actions_complete {
-        return Err("is complete but has at least one uncomplete action".into());
-    }
+const ACTION_LINK_IS_VALID: Rule = Rule {
+    id: "GTD007",
+    name: "action-link-is-valid",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "An action in a context that links to a project that doesn't exist can't be \
+                tracked.",
+    failing: "# @home\n\n- ![[202401010000 Plan trp#^abcdef]]",
+    passing: "# @home\n\n- ![[202401010000 Plan trip#^abcdef]]",
+    fix: "Fix the link so that it names an existing project.",
+};
 
-    Ok(())
-}
+const LINKED_PROJECT_IS_IN_PROGRESS: Rule = Rule {
+    id: "GTD008",
+    name: "linked-project-is-in-progress",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "Contexts list what can be done next, and only in-progress projects have next \
+                actions.",
+    failing: "# @home\n\n- ![[202401010000 Plan trip#^abcdef]]\n\n<!-- Plan trip is #someday -->",
+    passing:
+        "# @home\n\n- ![[202401010000 Plan trip#^abcdef]]\n\n<!-- Plan trip is #in-progress -->",
+    fix: "Remove the action from the context, or move its project to in progress.",
+};
 
-fn in_progress_project_has_active_actions(project: &Project) -> Result<(), Cow<'static, str>> {
-    if project.status != ProjectStatus::InProgress {
-        return Ok(());
-    }
+const LINKED_PROJECT_CONTAINS_ACTION: Rule = Rule {
+    id: "GTD009",
+    name: "linked-project-contains-action",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[&LINKED_PROJECT_IS_IN_PROGRESS],
+    rationale: "A link to an action that isn't in its project points at nothing.",
+    failing: "# @home\n\n- ![[202401010000 Plan trip#^zzzzzz]]",
+    passing: "# @home\n\n- ![[202401010000 Plan trip#^abcdef]]",
+    fix: "Fix the block ID in the link, or remove the action from the context.",
+};
 
-    let has_active_action = project
-        .actions
-        .actions()
-        .filter(|(_, s)| s == &ActionStatus::Active)
-        .count()
-        >= 1;
+const ACTION_IN_PROJECT_IS_ACTIVE: Rule = Rule {
+    id: "GTD010",
+    name: "action-in-project-is-active",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[&LINKED_PROJECT_IS_IN_PROGRESS],
+    rationale: "Contexts list what can be done next, so actions that are complete, upcoming, or \
+                waiting don't belong in them.",
+    failing: "# Plan trip\n#in-progress\n\n## Actions\n\n### Complete\n\n- Pack bags ^abcdef",
+    passing: "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack bags ^abcdef",
+    fix: "Remove the action from the context, or make it active in its project.",
+};
 
-    if !has_active_action {
-        return Err("is in progress but has no active actions".into());
-    }
+const LINKED_ACTION_IS_UNIQUE: Rule = Rule {
+    id: "GTD011",
+    name: "linked-action-is-unique",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "An action should be in exactly one context, so it's only listed once.",
+    failing:
+        "# @home\n\n- ![[202401010000 Plan trip#^abcdef]]\n- ![[202401010000 Plan trip#^abcdef]]",
+    passing: "# @home\n\n- ![[202401010000 Plan trip#^abcdef]]",
+    fix: "Remove all but one of the links to the action.",
+};
 
-    Ok(())
+const ALL_ACTIVE_ACTIONS_ARE_IN_A_CONTEXT: Rule = Rule {
+    id: "GTD012",
+    name: "all-active-actions-are-in-a-context",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "Next actions are found by looking through contexts, so an active action that \
+                isn't in one is easy to forget.",
+    failing: "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack bags",
+    passing: "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack bags ^abcdef\n\n\
+              <!-- and in Contexts/@home.md: -->\n- ![[202401010000 Plan trip#^abcdef]]",
+    fix: "Give the action a block ID and link to it from a context.",
+};
+
+const ALL_PROJECT_NAMES_FOLLOW_SCHEME: Rule = Rule {
+    id: "GTD013",
+    name: "all-project-names-follow-scheme",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "Project files are found and linked to by their names, which have to match \
+                `pattern` in `[project-names]`.",
+    failing: "Projects/Plan trip.md",
+    passing: "Projects/202401010000 Plan trip.md",
+    fix: "Rename the file to follow the naming scheme, e.g. by adding an ID.",
+};
+
+const ACTION_IS_NOT_DUPLICATED: Rule = Rule {
+    id: "GTD014",
+    name: "action-is-not-duplicated",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "The same action in more than one in-progress project usually means a capture was \
+                filed twice, and one copy will be left behind when the other is done.",
+    failing: "<!-- Projects/202401010000 Plan trip.md -->\n- Book flights\n\n\
+              <!-- Projects/202401020000 Visit family.md -->\n- Book **flights**",
+    passing: "<!-- Projects/202401010000 Plan trip.md -->\n- Book flights\n\n\
+              <!-- Projects/202401020000 Visit family.md -->\n- Buy presents",
+    fix: "Remove all but one copy, or reword them if they really are different actions.",
+};
+
+const IN_PROGRESS_PROJECT_IS_NOT_STALLED: Rule = Rule {
+    id: "GTD015",
+    name: "in-progress-project-is-not-stalled",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[&IN_PROGRESS_PROJECT_HAS_ACTIVE_ACTIONS],
+    rationale:
+        "A project with upcoming actions but no active ones has stopped moving, even though \
+                it knows what comes next. It's usually because an action was completed without \
+                promoting the one after it.",
+    failing: "# Plan trip\n#in-progress\n\n## Actions\n\n### Upcoming\n\n- Pack bags\n\n\
+              ### Complete\n\n- Book flights ✅ 2024-01-02",
+    passing: "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack bags \
+              @active(2024-01-03)\n\n### Complete\n\n- Book flights ✅ 2024-01-02",
+    fix: "Promote the first upcoming action with `gtd promote` during the weekly review. How \
+          long a project can go without active actions is set by `stalled-after` in \
+          `[validate]`.",
+};
+
+const PROJECT_HAS_REQUIRED_SECTIONS: Rule = Rule {
+    id: "GTD016",
+    name: "project-has-required-sections",
+    severity: Severity::Warning,
+    is_fixable: true,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "Some kinds of projects need more than a list of actions, like a goal to know \
+                when they're done. Which sections are needed is set by `required-sections` in \
+                `[validate]`.",
+    failing: "# Plan trip\n#in-progress #work\n\n## Actions\n\n### Active\n\n- Pack bags",
+    passing: "# Plan trip\n#in-progress #work\n\n## Goal\n\nHave a relaxing week away.\n\n\
+              ## Actions\n\n### Active\n\n- Pack bags",
+    fix: "Add the section and fill it in. `gtd validate --fix` adds empty sections to fill in.",
+};
+
+const GOAL_DESCRIBES_OUTCOME: Rule = Rule {
+    id: "GTD017",
+    name: "goal-describes-outcome",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: true,
+    depends_on: &[],
+    rationale: "A goal is for knowing when a project is done, so it should describe what success \
+                looks like. An empty goal, a single word, or the title again doesn't.",
+    failing: "# Plan trip\n#in-progress\n\n## Goal\n\nPlan trip.",
+    passing: "# Plan trip\n#in-progress\n\n## Goal\n\nFlights, hotel, and time off are booked \
+              for a week in Lisbon.",
+    fix: "Write a sentence about what will be true once the project is done.",
+};
+
+const ALIASED_CONTEXTS_ARE_ONE_FILE: Rule = Rule {
+    id: "GTD018",
+    name: "aliased-contexts-are-one-file",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "An alias in `[contexts]` names the same context as the one it stands for, so a \
+                file for each splits that context's actions in two.",
+    failing: "Contexts/@computer.md\nContexts/@pc.md",
+    passing: "Contexts/@computer.md",
+    fix: "Move the actions from the alias's file into the other one, and delete it.",
+};
+
+const PROJECT_IS_NOT_DUPLICATED: Rule = Rule {
+    id: "GTD019",
+    name: "project-is-not-duplicated",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "Sync tools sometimes copy a note when two devices change it at once, leaving \
+                two projects that drift apart as each one is edited.",
+    failing: "Projects/202401010000 Plan trip.md\nProjects/202401010001 Plan trip.md",
+    passing: "Projects/202401010000 Plan trip.md",
+    fix: "Run `gtd dedupe` to merge the copies into one project or delete them.",
+};
+
+const COMPLETE_PROJECT_IS_ARCHIVED: Rule = Rule {
+    id: "GTD020",
+    name: "complete-project-is-archived",
+    severity: Severity::Warning,
+    is_fixable: true,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "Finished projects left in the projects folder make the working set harder to \
+                look through. How long a project can stay there after it's finished is set by \
+                `archive-after` in `[validate]`.",
+    failing: "Projects/202401010000 Plan trip.md\n\n# Plan trip\n#complete\n\n## Actions\n\n\
+              ### Complete\n\n- Book flights ✅ 2024-01-02",
+    passing: "Archive/202401010000 Plan trip.md\n\n# Plan trip\n#complete\n\n## Actions\n\n\
+              ### Complete\n\n- Book flights ✅ 2024-01-02",
+    fix: "Run `gtd archive` on the project. `gtd validate --fix` archives every project that's \
+          been complete for too long.",
+};
+
+const PROJECT_REVIEW_IS_NOT_OVERDUE: Rule = Rule {
+    id: "GTD021",
+    name: "project-review-is-not-overdue",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "Some projects need looking at more or less often than the weekly review, which is \
+                set by `review-every` in their frontmatter. One that hasn't been reviewed in that \
+                long has probably drifted.",
+    failing: "---\nreview-every: 2w\nlast-reviewed: 2024-01-01\n---\n# Plan trip\n#in-progress \
+              (on 2024-01-20)",
+    passing: "---\nreview-every: 2w\nlast-reviewed: 2024-01-15\n---\n# Plan trip\n#in-progress \
+              (on 2024-01-20)",
+    fix: "Review the project with `gtd review --due`, which records when it was reviewed.",
+};
+
+const FILE_HAS_NO_CONFLICTS: Rule = Rule {
+    id: "GTD022",
+    name: "file-has-no-conflicts",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale:
+        "When git or a sync tool can't merge two copies of a file that were both changed, it \
+                leaves both versions in the file between conflict markers. The file can't be read \
+                until they're resolved, so it's left out of every command.",
+    failing: "# @home\n\n<<<<<<< HEAD\n- Call mom\n=======\n- Call mom today\n>>>>>>> laptop",
+    passing: "# @home\n\n- Call mom today",
+    fix:
+        "Run `gtd resolve`, which shows both versions of each conflicting list item and asks which \
+          to keep.",
+};
+
+const FILE_IS_READABLE: Rule = Rule {
+    id: "GTD023",
+    name: "file-is-readable",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "A project or context that can't be read, like a project without a status tag or \
+                with a section gtd doesn't know about, is left out of every command instead of \
+                stopping them all.",
+    failing: "# Plan trip\n#in-progress\n\n## Notes\n\nAsk about the hotel.",
+    passing: "# Plan trip\n#in-progress\n\n## Info\n\nAsk about the hotel.",
+    fix: "Fix the file so it reads as a project or context, which the error says how to do.",
+};
+
+const ACTION_LINK_IS_TO_A_PROJECT: Rule = Rule {
+    id: "GTD024",
+    name: "action-link-is-to-a-project",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale:
+        "An embed in a context stands for an action in a project. One that links to a block \
+                in a note that isn't a project, or whose name doesn't follow the project name \
+                scheme, isn't read as an action, so it never shows up in `next` or anywhere else.",
+    failing: "# @home\n\n- ![[Packing list#^abcdef]]",
+    passing: "# @home\n\n- ![[202401010000 Plan trip#^abcdef]]",
+    fix: "Link to the action in its project instead, or write the action out as text.",
+};
+
+const FOOTNOTE_IS_DEFINED: Rule = Rule {
+    id: "GTD025",
+    name: "footnote-is-defined",
+    severity: Severity::Error,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "A footnote reference without a definition anywhere in the project is shown as \
+                its label in brackets, with nothing for it to point to.",
+    failing: "# Plan trip\n#in-progress\n\n## Goal\n\nRelax.[^1]",
+    passing: "# Plan trip\n#in-progress\n\n## Goal\n\nRelax.[^1]\n\n[^1]: Really.",
+    fix: "Add a definition like `[^1]: ...` to the project, or remove the reference.",
+};
+
+const CONTEXT_IS_WITHIN_CAPACITY: Rule = Rule {
+    id: "GTD026",
+    name: "context-is-within-capacity",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "A limit in `[contexts.capacity]` caps the work that's open in a context at once, \
+                so that it gets finished before more is started.",
+    failing: "[contexts.capacity]\n\"@calls\" = 2\n\n# @calls\n\n- Call mom\n- Call the bank\n- \
+              Call the dentist",
+    passing: "[contexts.capacity]\n\"@calls\" = 2\n\n# @calls\n\n- Call mom\n- Call the bank",
+    fix: "Finish or move actions out of the context until it's back under its limit, or raise \
+          the limit.",
+};
+
+const CONTEXT_FOLLOWS_ORDER: Rule = Rule {
+    id: "GTD027",
+    name: "context-follows-order",
+    severity: Severity::Warning,
+    is_fixable: true,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "`order` in `[contexts]` keeps the actions in each context sorted, by priority, \
+                project, or how long they've been active, so what to do next is at the top. \
+                Actions added or moved by hand end up out of place.",
+    failing: "[contexts]\norder = \"project\"\n\n# @calls\n\n- Call mom\n- \
+              ![[202401010000 Plan trip#^abcdef]]",
+    passing: "[contexts]\norder = \"project\"\n\n# @calls\n\n- \
+              ![[202401010000 Plan trip#^abcdef]]\n- Call mom",
+    fix: "Run `gtd validate --fix GTD027` to sort the context, or set `order` to \"manual\" to \
+          keep actions in the order they're written.",
+};
+
+const ACTIONS_SECTION_IS_NOT_DEPRECATED: Rule = Rule {
+    id: "GTD028",
+    name: "actions-section-is-not-deprecated",
+    severity: Severity::Warning,
+    is_fixable: false,
+    is_opt_in: false,
+    depends_on: &[],
+    rationale: "The \"Action Items\" section was renamed to \"Actions\". It's still read, but \
+                support for it will be removed.",
+    failing: "# Plan trip\n#in-progress\n\n## Action Items\n\n### Active\n\n- Book flights",
+    passing: "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights",
+    fix: "Rename the section to \"Actions\".",
+};
+
+/// Every validation rule, in order of ID.
+pub const RULES: &[Rule] = &[
+    PROJECT_ID_IS_UNIQUE,
+    PROJECT_TITLE_MATCHES_NAME,
+    PROJECT_TAGS_ARE_ALLOWED,
+    PROJECT_HAS_REQUIRED_TAGS,
+    COMPLETE_PROJECT_HAS_ONLY_COMPLETE_ACTIONS,
+    IN_PROGRESS_PROJECT_HAS_ACTIVE_ACTIONS,
+    ACTION_LINK_IS_VALID,
+    LINKED_PROJECT_IS_IN_PROGRESS,
+    LINKED_PROJECT_CONTAINS_ACTION,
+    ACTION_IN_PROJECT_IS_ACTIVE,
+    LINKED_ACTION_IS_UNIQUE,
+    ALL_ACTIVE_ACTIONS_ARE_IN_A_CONTEXT,
+    ALL_PROJECT_NAMES_FOLLOW_SCHEME,
+    ACTION_IS_NOT_DUPLICATED,
+    IN_PROGRESS_PROJECT_IS_NOT_STALLED,
+    PROJECT_HAS_REQUIRED_SECTIONS,
+    GOAL_DESCRIBES_OUTCOME,
+    ALIASED_CONTEXTS_ARE_ONE_FILE,
+    PROJECT_IS_NOT_DUPLICATED,
+    COMPLETE_PROJECT_IS_ARCHIVED,
+    PROJECT_REVIEW_IS_NOT_OVERDUE,
+    FILE_HAS_NO_CONFLICTS,
+    FILE_IS_READABLE,
+    ACTION_LINK_IS_TO_A_PROJECT,
+    FOOTNOTE_IS_DEFINED,
+    CONTEXT_IS_WITHIN_CAPACITY,
+    CONTEXT_FOLLOWS_ORDER,
+    ACTIONS_SECTION_IS_NOT_DEPRECATED,
+];
+
+/// Finds the rule with the ID `id`, ignoring case.
+pub fn rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|r| r.id.eq_ignore_ascii_case(id))
+}
+
+/// Renders the documentation for `rule`.
+pub fn explain(rule: &Rule) -> String {
+    let indent = |text: &str| {
+        text.lines()
+            .map(|l| match l {
+                "" => String::from("\n"),
+                l => format!("    {}\n", l),
+            })
+            .collect::<String>()
+    };
+    let (toggle, list) = match rule.is_opt_in {
+        true => ("on", "enabled"),
+        false => ("off", "disabled"),
+    };
+    let causes = rule
+        .depends_on
+        .iter()
+        .map(|r| {
+            format!(
+                " It isn't checked where {} {} finds a problem.",
+                r.id, r.name
+            )
+        })
+        .collect::<String>();
+    format!(
+        "{}: {} ({})\n\n{}{}\n\nFails:\n\n{}\nPasses:\n\n{}\n{} To turn the rule {}, add \"{}\" \
+         to `{}` in `[validate]` in gtd.toml.\n",
+        rule.id,
+        rule.name,
+        rule.severity,
+        rule.rationale,
+        causes,
+        indent(rule.failing),
+        indent(rule.passing),
+        rule.fix,
+        toggle,
+        rule.id,
+        list
+    )
 }
 
 macro_rules! unwrap_or_ok {
@@ -92,354 +591,2163 @@ macro_rules! unwrap_or_ok {
     };
 }
 
-fn action_link_is_valid(
-    action: &ContextAction,
-    project: Option<&Project>,
-) -> Result<(), Cow<'static, str>> {
-    let _action_ref = unwrap_or_ok!(action.to_action_ref());
-    if project.is_none() {
-        return Err("not a valid link to project".into());
-    }
-
-    Ok(())
+/// Validates `docs` as of `today`, printing each problem and then a summary in `format`, and
+/// returns what was found. With `statistics`, the number of problems each rule found is printed too.
+pub fn validate(
+    docs: Documents,
+    config: &Config,
+    today: NaiveDate,
+    statistics: bool,
+    format: Format,
+) -> Summary {
+    let start = Instant::now();
+    let root_dir = docs.loader().root_dir();
+    let links = links(config, root_dir, format);
+    let summary = runner(&docs, config, today)
+        .with_links(links.clone())
+        .with_progress(Progress::new(&config.progress))
+        .run(&docs);
+    print_summary(&summary, start, &links, root_dir, statistics, format);
+    summary
 }
 
-fn linked_project_is_in_progress(
-    action: &ContextAction,
-    project: Option<&Project>,
-) -> Result<(), Cow<'static, str>> {
-    let _action_ref = unwrap_or_ok!(action.to_action_ref());
-    let project = unwrap_or_ok!(project);
-
-    if project.status != ProjectStatus::InProgress {
-        return Err(format!("linked project \"{}\" is not in progress", project.title()).into());
-    }
-
-    Ok(())
+/// Checks `docs` as of `today` with every rule that's turned on, with documents in messages linked
+/// to by `links`.
+pub fn check(docs: &Documents, config: &Config, today: NaiveDate, links: &Linker) -> Summary {
+    runner(docs, config, today)
+        .with_links(links.clone())
+        .run(docs)
 }
 
-fn linked_project_contains_action(
-    action: &ContextAction,
-    project: Option<&Project>,
-) -> Result<(), Cow<'static, str>> {
-    let action_ref = unwrap_or_ok!(action.to_action_ref());
-    let project = unwrap_or_ok!(project);
-
-    if project.actions.get_action(&action_ref.action_id).is_none() {
-        return Err(format!(
-            "linked project \"{}\" doesn't have the action",
-            project.title()
+/// A runner with every rule for `docs` as of `today`.
+fn runner<'a>(docs: &'a Documents, config: &'a Config, today: NaiveDate) -> ValidatorRunner<'a> {
+    let runner = project_rules(
+        ValidatorRunner::new(),
+        config,
+        docs.loader().scheme(),
+        today,
+    );
+    file_rules(runner)
+        .for_all_context_actions(&ACTION_LINK_IS_VALID, action_link_is_valid)
+        .for_all_context_actions(
+            &LINKED_PROJECT_IS_IN_PROGRESS,
+            linked_project_is_in_progress,
         )
-        .into());
-    }
+        .for_all_context_actions(
+            &LINKED_PROJECT_CONTAINS_ACTION,
+            linked_project_contains_action,
+        )
+        .for_all_context_actions(&ACTION_IN_PROJECT_IS_ACTIVE, action_in_project_is_active)
+        .for_all_context_actions(&LINKED_ACTION_IS_UNIQUE, linked_action_is_unique())
+        .for_all_context_actions(&ACTION_LINK_IS_TO_A_PROJECT, action_link_is_to_a_project)
+        .with_ad_hoc(
+            &ALL_ACTIVE_ACTIONS_ARE_IN_A_CONTEXT,
+            all_active_actions_are_in_a_context,
+        )
+        .with_ad_hoc(&ACTION_IS_NOT_DUPLICATED, action_is_not_duplicated)
+        .with_ad_hoc(
+            &ALIASED_CONTEXTS_ARE_ONE_FILE,
+            aliased_contexts_are_one_file,
+        )
+        .with_ad_hoc(&PROJECT_IS_NOT_DUPLICATED, project_is_not_duplicated)
+        .with_ad_hoc(&CONTEXT_IS_WITHIN_CAPACITY, context_is_within_capacity)
+        .for_all_contexts(&CONTEXT_FOLLOWS_ORDER, context_follows_order)
+        .with_fix(&CONTEXT_FOLLOWS_ORDER, sort_context)
+}
 
-    Ok(())
+/// Like `validate`, but reads the projects in the GTD folder of `loader` one at a time instead of
+/// loading every document up front, so it works on folders too big to fit in memory. Only the
+/// rules that check each project on its own, or that a file could be loaded at all, are run.
+pub fn validate_streaming(
+    loader: &Loader,
+    config: &Config,
+    today: NaiveDate,
+    statistics: bool,
+    format: Format,
+) -> Result<Summary, IoError> {
+    let start = Instant::now();
+    let links = links(config, loader.root_dir(), format);
+    let runner = project_rules(ValidatorRunner::new(), config, loader.scheme(), today);
+    let summary = file_rules(runner)
+        .with_links(links.clone())
+        .with_progress(Progress::new(&config.progress))
+        .run_streaming(loader, config.contexts.layout)?;
+    print_summary(
+        &summary,
+        start,
+        &links,
+        loader.root_dir(),
+        statistics,
+        format,
+    );
+    Ok(summary)
 }
 
-fn action_in_project_is_active(
-    action: &ContextAction,
-    project: Option<&Project>,
-) -> Result<(), Cow<'static, str>> {
-    let action_ref = unwrap_or_ok!(action.to_action_ref());
-    let project = unwrap_or_ok!(project);
-    let (_, status) = unwrap_or_ok!(project.actions.get_action(&action_ref.action_id));
+/// Adds the rules that check each project on its own to `runner`.
+fn project_rules<'a>(
+    runner: ValidatorRunner<'a>,
+    config: &'a Config,
+    scheme: &'a NameScheme,
+    today: NaiveDate,
+) -> ValidatorRunner<'a> {
+    runner
+        .with_disabled(&config.validate.disabled)
+        .with_enabled(&config.validate.enabled)
+        .for_all_projects(&PROJECT_ID_IS_UNIQUE, project_id_is_unique())
+        .for_all_projects(&PROJECT_TITLE_MATCHES_NAME, project_title_matches_name)
+        .for_all_projects(
+            &PROJECT_TAGS_ARE_ALLOWED,
+            project_tags_are_allowed(&config.tags),
+        )
+        .for_all_projects(
+            &PROJECT_HAS_REQUIRED_TAGS,
+            project_has_required_tags(&config.tags),
+        )
+        .for_all_projects(
+            &COMPLETE_PROJECT_HAS_ONLY_COMPLETE_ACTIONS,
+            complete_project_has_only_complete_actions,
+        )
+        .for_all_projects(
+            &IN_PROGRESS_PROJECT_HAS_ACTIVE_ACTIONS,
+            in_progress_project_has_active_actions,
+        )
+        .for_all_projects(
+            &IN_PROGRESS_PROJECT_IS_NOT_STALLED,
+            in_progress_project_is_not_stalled(scheme, config.validate.stalled_after, today),
+        )
+        .for_all_projects(
+            &PROJECT_HAS_REQUIRED_SECTIONS,
+            project_has_required_sections(&config.validate),
+        )
+        .with_fix(
+            &PROJECT_HAS_REQUIRED_SECTIONS,
+            add_required_sections(&config.validate),
+        )
+        .for_all_projects(&GOAL_DESCRIBES_OUTCOME, goal_describes_outcome)
+        .for_all_projects(&FOOTNOTE_IS_DEFINED, footnote_is_defined)
+        .for_all_projects(
+            &ACTIONS_SECTION_IS_NOT_DEPRECATED,
+            actions_section_is_not_deprecated,
+        )
+        .for_all_projects(
+            &COMPLETE_PROJECT_IS_ARCHIVED,
+            complete_project_is_archived(scheme, config.validate.archive_after, today),
+        )
+        .with_fix(&COMPLETE_PROJECT_IS_ARCHIVED, archive_project)
+        .for_all_projects(
+            &PROJECT_REVIEW_IS_NOT_OVERDUE,
+            project_review_is_not_overdue(scheme, today),
+        )
+}
 
-    if status != ActionStatus::Active {
-        return Err(format!(
-            "action is not active in linked project \"{}\"",
-            project.title()
+/// Adds the rules for files that couldn't be loaded to `runner`, which are checked in
+/// `run_streaming` as well as `run`.
+fn file_rules(runner: ValidatorRunner) -> ValidatorRunner {
+    runner
+        .with_ad_hoc(
+            &ALL_PROJECT_NAMES_FOLLOW_SCHEME,
+            all_project_names_follow_scheme,
         )
-        .into());
-    }
+        .with_ad_hoc(&FILE_HAS_NO_CONFLICTS, file_has_no_conflicts)
+        .with_ad_hoc(&FILE_IS_READABLE, file_is_readable)
+}
 
-    Ok(())
+fn links(config: &Config, root_dir: &Path, format: Format) -> Linker {
+    // Links are only useful in a terminal.
+    match format {
+        Format::Text => Linker::from_config(config, root_dir),
+        Format::Json => Linker::default(),
+    }
 }
 
-fn linked_action_is_unique(
-) -> impl FnMut(&ContextAction, Option<&Project>) -> Result<(), Cow<'static, str>> {
-    let mut actions = HashSet::new();
-    move |action, _project| {
-        if let Some(action_ref) = action.to_action_ref() {
-            if !actions.insert(action_ref.clone()) {
-                return Err("action is not unique in contexts".into());
+/// Prints the problems in `summary` and then how many there were, in `format`.
+fn print_summary(
+    summary: &Summary,
+    start: Instant,
+    links: &Linker,
+    root_dir: &Path,
+    statistics: bool,
+    format: Format,
+) {
+    if format == Format::Json {
+        println!("{:#}", to_json(summary, root_dir));
+        return;
+    }
+
+    print_diagnostics(&summary.diagnostics, links);
+    if !summary.hits.is_empty() {
+        println!();
+    }
+    println!(
+        "Checked {} in {:.2}s: {}, {} ({} fixable).",
+        plural(summary.files, "file"),
+        start.elapsed().as_secs_f64(),
+        plural(summary.count(Severity::Error), "error"),
+        plural(summary.count(Severity::Warning), "warning"),
+        summary.fixable()
+    );
+
+    if statistics {
+        let hits = summary.statistics();
+        if !hits.is_empty() {
+            println!();
+            println!("Problems by rule:");
+            for (rule, count) in hits {
+                println!("- {} {}: {}", rule.id, rule.name, count);
             }
         }
-
-        Ok(())
     }
 }
 
-fn all_active_actions_are_in_a_context(docs: &Documents) {
-    let active_projects = docs
-        .projects()
-        .filter(|p| p.status == ProjectStatus::InProgress);
-
-    for project in active_projects {
-        let active_actions = project.actions.actions().filter_map(|(a, s)| {
-            if s == ActionStatus::Active {
-                Some(a)
-            } else {
-                None
+/// Prints `diagnostics`, grouping them under the documents they're in.
+pub fn print_diagnostics(diagnostics: &[Diagnostic], links: &Linker) {
+    let mut last_document = None;
+    for diagnostic in diagnostics {
+        let document = match &diagnostic.document {
+            Some(document) => document,
+            None => {
+                println!("{}", diagnostic);
+                last_document = None;
+                continue;
             }
-        });
+        };
 
-        'outer: for action in active_actions {
-            let action_id = match &action.id {
-                Some(id) => id,
-                None => {
-                    println!(
-                        "Project \"{}\" action is active but isn't in any contexts",
-                        project.title()
-                    );
-                    continue;
-                }
-            };
+        if last_document != Some(document) {
+            let link = links.link(&document.path, &document.name);
+            match document.kind {
+                DocumentKind::Project => println!("{}:", link),
+                DocumentKind::Context => println!("action in {}:", link),
+            }
+            last_document = Some(document);
+        }
+        println!("- {}", diagnostic);
+        if !diagnostic.related.is_empty() {
+            let related = diagnostic
+                .related
+                .iter()
+                .map(|d| links.link(&d.path, &d.name))
+                .collect::<Vec<_>>();
+            println!("  also in {}", related.join(", "));
+        }
+    }
+}
 
-            for context in docs.contexts() {
-                let action_refs = context.actions().iter().filter_map(|a| a.to_action_ref());
+/// The problems in `summary` in the JSON format described by `schema::diagnostics`, with paths
+/// relative to `root_dir`.
+pub fn to_json(summary: &Summary, root_dir: &Path) -> Value {
+    let diagnostics = summary
+        .diagnostics
+        .iter()
+        .map(|d| {
+            let document = |doc: &Document| {
+                let path = doc.path.strip_prefix(root_dir).unwrap_or(&doc.path);
+                json!({
+                    "kind": doc.kind.to_string(),
+                    "name": doc.name,
+                    "path": filename::to_slash(path),
+                })
+            };
+            json!({
+                "rule": d.rule.id,
+                "name": d.rule.name,
+                "severity": d.rule.severity.to_string(),
+                "fixable": d.fix.is_some(),
+                "fix": d.fix.as_ref().map(|f| f.description.as_str()),
+                "document": d.document.as_ref().map(document),
+                "related": d.related.iter().map(document).collect::<Vec<_>>(),
+                "message": d.message,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "version": schema::VERSION,
+        "files": summary.files,
+        "errors": summary.count(Severity::Error),
+        "warnings": summary.count(Severity::Warning),
+        "fixable": summary.fixable(),
+        "diagnostics": diagnostics,
+    })
+}
 
-                for action_ref in action_refs {
-                    if &action_ref.action_id == action_id {
-                        continue 'outer;
-                    }
-                }
-            }
+/// How `gtd validate` prints what it finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
 
-            // TODO: Actually print the action.
-            println!(
-                "Project \"{}\" action is active but isn't in any contexts",
-                project.title()
-            );
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "\"{}\" is not a format, like \"text\" or \"json\"",
+                s
+            )),
         }
     }
 }
 
-trait ProjectValidator {
-    fn validate(&mut self, project: &Project) -> Result<(), Cow<'static, str>>;
+fn plural(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        n => format!("{} {}s", n, noun),
+    }
 }
 
-impl<F> ProjectValidator for F
-where
-    F: FnMut(&Project) -> Result<(), Cow<'static, str>>,
-{
-    fn validate(&mut self, project: &Project) -> Result<(), Cow<'static, str>> {
-        self(project)
-    }
+/// What a validation run found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// The number of documents that were checked.
+    pub files: usize,
+    /// The number of problems each rule found, by rule ID.
+    pub hits: BTreeMap<&'static str, usize>,
+    /// The problems, in the order they were found.
+    pub diagnostics: Vec<Diagnostic>,
 }
 
-trait ContextActionValidator {
-    fn validate(
-        &mut self,
-        action: &ContextAction,
-        project: Option<&Project>,
-    ) -> Result<(), Cow<'static, str>>;
+/// A problem found by a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub rule: &'static Rule,
+    /// The document the problem is in, or `None` for problems with the GTD folder as a whole.
+    pub document: Option<Document>,
+    /// The other documents with the same problem, like every context that links to a project
+    /// that can't be read.
+    pub related: Vec<Document>,
+    pub message: String,
+    /// How to fix the problem, if the rule has a [`Fix`] for it.
+    pub fix: Option<Edit>,
 }
 
-impl<F> ContextActionValidator for F
-where
-    F: FnMut(&ContextAction, Option<&Project>) -> Result<(), Cow<'static, str>>,
-{
-    fn validate(
-        &mut self,
-        action: &ContextAction,
-        project: Option<&Project>,
-    ) -> Result<(), Cow<'static, str>> {
-        self(action, project)
+impl Diagnostic {
+    /// Adds `document` to the documents with the same problem, unless it's already one of them.
+    fn relate(&mut self, document: Document) {
+        if self.document.as_ref() != Some(&document) && !self.related.contains(&document) {
+            self.related.push(document);
+        }
     }
 }
 
-trait AdHocValidator {
-    fn validate(&mut self, docs: &Documents);
+impl fmt::Display for Diagnostic {
+    /// Renders the problem like `error[GTD001]: has a duplicate ID`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.rule.severity, self.rule.id, self.message
+        )
+    }
 }
 
-impl<F> AdHocValidator for F
-where
-    F: FnMut(&Documents),
-{
-    fn validate(&mut self, docs: &Documents) {
-        self(docs)
+/// A document that a problem was found in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub kind: DocumentKind,
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Document {
+    fn new(kind: DocumentKind, name: impl fmt::Display, path: &Path) -> Self {
+        Self {
+            kind,
+            name: name.to_string(),
+            path: path.to_owned(),
+        }
     }
 }
 
-pub struct ValidatorRunner<'a> {
-    project_validators: Vec<Box<dyn ProjectValidator + 'a>>,
-    context_action_validators: Vec<Box<dyn ContextActionValidator + 'a>>,
-    ad_hoc_validators: Vec<Box<dyn AdHocValidator + 'a>>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Project,
+    /// An action in a context.
+    Context,
 }
 
-impl<'a> ValidatorRunner<'a> {
-    pub fn new() -> Self {
-        Self::default()
+impl fmt::Display for DocumentKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Project => write!(f, "project"),
+            Self::Context => write!(f, "context"),
+        }
     }
+}
 
-    pub fn for_all_projects<F>(mut self, validator: F) -> Self
-    where
-        F: FnMut(&Project) -> Result<(), Cow<'static, str>> + 'a,
-    {
-        self.project_validators.push(Box::new(validator));
-        self
+impl Summary {
+    /// The number of problems with `severity`.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.rules()
+            .filter(|(r, _)| r.severity == severity)
+            .map(|(_, n)| n)
+            .sum()
     }
 
-    pub fn for_all_context_actions<F>(mut self, validator: F) -> Self
-    where
-        F: FnMut(&ContextAction, Option<&Project>) -> Result<(), Cow<'static, str>> + 'a,
-    {
-        self.context_action_validators.push(Box::new(validator));
-        self
+    /// The number of problems that can be fixed automatically.
+    pub fn fixable(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.fix.is_some()).count()
     }
 
-    pub fn with_ad_hoc<F>(mut self, validator: F) -> Self
-    where
-        F: FnMut(&Documents) + 'a,
-    {
-        self.ad_hoc_validators.push(Box::new(validator));
-        self
+    /// The rules that found problems and how many they found, most first.
+    pub fn statistics(&self) -> Vec<(&'static Rule, usize)> {
+        let mut hits = self.rules().collect::<Vec<_>>();
+        hits.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.id.cmp(b.id)));
+        hits
     }
 
-    pub fn run(mut self, docs: &Documents) {
-        for project in docs.projects() {
-            self.run_project_validators(project);
-        }
-
-        for context in docs.contexts() {
-            for action in context.actions() {
-                let project = action
-                    .to_action_ref()
-                    .and_then(|a| docs.project(&a.project_name));
-                self.run_context_action_validators(context, action, project);
-            }
-        }
-
-        self.run_ad_hoc_validators(docs);
+    fn rules(&self) -> impl Iterator<Item = (&'static Rule, usize)> + '_ {
+        self.hits.iter().filter_map(|(id, n)| Some((rule(id)?, *n)))
     }
+}
 
-    fn run_project_validators(&mut self, project: &Project) {
-        let results = self
-            .project_validators
-            .iter_mut()
-            .flat_map(|v| v.validate(project).err())
-            .collect::<Vec<_>>();
+fn project_id_is_unique() -> impl FnMut(&Project) -> Result<(), Cow<'static, str>> {
+    let mut project_ids = HashSet::new();
+
+    move |project| {
+        let id = unwrap_or_ok!(project.id());
+        if !project_ids.insert(id.to_string()) {
+            return Err("has a duplicate ID".into());
+        }
+
+        Ok(())
+    }
+}
+
+fn project_title_matches_name(project: &Project) -> Result<(), Cow<'static, str>> {
+    // Characters that can't be in filenames are left out of the names of new projects.
+    let is_sanitized = filename::sanitize(&project.title.text()) == project.title();
+    if project.title != project.title() && !is_sanitized {
+        return Err(format!(
+            "has a name \"{}\" that doesn't match its title",
+            project.title.text()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn project_tags_are_allowed(
+    tags: &TagConfig,
+) -> impl FnMut(&Project) -> Result<(), Cow<'static, str>> + '_ {
+    move |project| {
+        let unknown = project
+            .tags
+            .iter()
+            .filter(|t| !tags.is_allowed(t))
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+
+        if !unknown.is_empty() {
+            return Err(format!("has unknown tags {}", unknown.join(", ")).into());
+        }
+
+        Ok(())
+    }
+}
+
+fn project_has_required_tags(
+    tags: &TagConfig,
+) -> impl FnMut(&Project) -> Result<(), Cow<'static, str>> + '_ {
+    move |project| {
+        for required in tags.required.iter() {
+            let count = project.tags.iter().filter(|t| t.is_under(required)).count();
+
+            match count {
+                0 => return Err(format!("is missing a \"{}\" tag", required).into()),
+                1 => {}
+                _ => return Err(format!("has more than one \"{}\" tag", required).into()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn complete_project_has_only_complete_actions(project: &Project) -> Result<(), Cow<'static, str>> {
+    if project.status != ProjectStatus::Complete {
+        return Ok(());
+    }
+
+    let are_all_actions_complete = project
+        .actions
+        .actions()
+        .all(|(_, status)| status == ActionStatus::Complete);
+
+    if !are_all_actions_complete {
+        return Err("is complete but has at least one uncomplete action".into());
+    }
+
+    Ok(())
+}
+
+fn in_progress_project_has_active_actions(project: &Project) -> Result<(), Cow<'static, str>> {
+    if project.status != ProjectStatus::InProgress {
+        return Ok(());
+    }
+
+    let has_active_action = project
+        .actions
+        .actions()
+        .filter(|(_, s)| matches!(s, ActionStatus::Active | ActionStatus::Waiting))
+        .count()
+        >= 1;
+
+    if !has_active_action {
+        return Err("is in progress but has no active or waiting actions".into());
+    }
+
+    Ok(())
+}
+
+/// Checks that in-progress projects with upcoming actions haven't gone without an active action
+/// for longer than `stalled_after`.
+///
+/// The last time a project had an active action is taken to be when its last action was completed,
+/// or else when it was created, according to `scheme`.
+fn in_progress_project_is_not_stalled(
+    scheme: &NameScheme,
+    stalled_after: Period,
+    today: NaiveDate,
+) -> impl FnMut(&Project) -> Result<(), Cow<'static, str>> + '_ {
+    move |project| {
+        if project.status != ProjectStatus::InProgress {
+            return Ok(());
+        }
+
+        let has_active_action = project
+            .actions
+            .actions()
+            .any(|(_, s)| s == ActionStatus::Active);
+        if has_active_action {
+            return Ok(());
+        }
+        let (upcoming, _) = unwrap_or_ok!(project
+            .actions
+            .actions()
+            .find(|(_, s)| *s == ActionStatus::Upcoming));
+
+        let since = project
+            .actions
+            .actions()
+            .filter_map(|(a, _)| a.completed)
+            .max()
+            .or_else(|| scheme.id_date(project.id()?));
+        let since = unwrap_or_ok!(since);
+        if since >= stalled_after.before(today) {
+            return Ok(());
+        }
+
+        Err(format!(
+            "has had no active actions since {}; promote \"{}\" to keep it moving",
+            since.format("%Y-%m-%d"),
+            upcoming.text
+        )
+        .into())
+    }
+}
+
+fn project_has_required_sections(
+    config: &ValidateConfig,
+) -> impl FnMut(&Project) -> Result<(), Cow<'static, str>> + '_ {
+    move |project| {
+        let missing = config
+            .missing_sections(project)
+            .iter()
+            .map(|s| format!("\"{}\"", s.heading()))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(format!("is missing required sections {}", missing.join(", ")).into());
+        }
+
+        Ok(())
+    }
+}
+
+fn goal_describes_outcome(project: &Project) -> Result<(), Cow<'static, str>> {
+    if project.status == ProjectStatus::Complete {
+        return Ok(());
+    }
+    let goal = unwrap_or_ok!(&project.goal).text();
+
+    // Compare words without case or punctuation, so "Plan trip." repeats "Plan trip".
+    let words = |text: &str| {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+            .collect::<Vec<_>>()
+    };
+    let goal_words = words(&goal);
+    match goal_words.len() {
+        0 => Err("has an empty goal".into()),
+        1 => Err(format!("has a one-word goal \"{}\"", goal).into()),
+        _ if goal_words == words(project.title()) => {
+            Err("has a goal that repeats its title".into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that a project's actions are under "Actions" rather than the old "Action Items".
+fn actions_section_is_not_deprecated(project: &Project) -> Result<(), Cow<'static, str>> {
+    match project.has_action_items_section {
+        true => Err("uses the deprecated \"Action Items\" section instead of \"Actions\"".into()),
+        false => Ok(()),
+    }
+}
+
+/// Checks that every footnote referenced anywhere in a project is defined somewhere in it.
+fn footnote_is_defined(project: &Project) -> Result<(), Cow<'static, str>> {
+    let fragments = [&project.goal, &project.info]
+        .iter()
+        .filter_map(|f| f.as_ref())
+        .chain(project.outcome.iter().map(|o| &o.note))
+        .chain(project.actions.actions().map(|(a, _)| &a.text))
+        .chain(project.footnotes.iter().map(|f| &f.body))
+        .collect::<Vec<_>>();
+    let mut undefined = Vec::new();
+    for label in fragments.iter().flat_map(|f| f.footnote_references()) {
+        if !project.footnotes.iter().any(|f| f.label == label) && !undefined.contains(&label) {
+            undefined.push(label);
+        }
+    }
+    match undefined.as_slice() {
+        [] => Ok(()),
+        [label] => Err(format!("has no definition for footnote [^{}]", label).into()),
+        labels => {
+            let labels = labels
+                .iter()
+                .map(|l| format!("[^{}]", l))
+                .collect::<Vec<_>>();
+            Err(format!("has no definitions for footnotes {}", labels.join(", ")).into())
+        }
+    }
+}
+
+/// Checks that complete projects were finished no longer than `archive_after` ago, or else have
+/// been moved to the archive.
+fn complete_project_is_archived(
+    scheme: &NameScheme,
+    archive_after: Period,
+    today: NaiveDate,
+) -> impl FnMut(&Project) -> Result<(), Cow<'static, str>> + '_ {
+    move |project| {
+        let finished = unwrap_or_ok!(due_for_archive(project, scheme, archive_after, today));
+        Err(format!(
+            "has been complete since {} and should be archived",
+            finished.format("%Y-%m-%d")
+        )
+        .into())
+    }
+}
+
+/// Checks that projects with a `review-every` in their frontmatter have been reviewed that
+/// recently.
+fn project_review_is_not_overdue(
+    scheme: &NameScheme,
+    today: NaiveDate,
+) -> impl FnMut(&Project) -> Result<(), Cow<'static, str>> + '_ {
+    move |project| {
+        let every = unwrap_or_ok!(project.frontmatter.get(REVIEW_EVERY_KEY));
+        let every = every
+            .parse::<Period>()
+            .map_err(|e| format!("has an invalid {}: {}", REVIEW_EVERY_KEY, e))?;
+        let last_reviewed = unwrap_or_ok!(review::is_due(project, scheme, every, today));
+        Err(format!(
+            "is reviewed every {} but was last reviewed on {}",
+            every,
+            last_reviewed.format("%Y-%m-%d")
+        )
+        .into())
+    }
+}
+
+/// When `project` was finished, if it's complete and that was longer than `archive_after` before
+/// `today`.
+///
+/// A project is taken to be finished when its last action was completed, or else when it was
+/// created, according to `scheme`.
+fn due_for_archive(
+    project: &Project,
+    scheme: &NameScheme,
+    archive_after: Period,
+    today: NaiveDate,
+) -> Option<NaiveDate> {
+    if project.status != ProjectStatus::Complete {
+        return None;
+    }
+    let finished = project
+        .actions
+        .actions()
+        .filter_map(|(a, _)| a.completed)
+        .max()
+        .or_else(|| scheme.id_date(project.id()?))?;
+    match finished < archive_after.before(today) {
+        true => Some(finished),
+        false => None,
+    }
+}
+
+/// Fixes the problems in `docs` that can be fixed automatically as of `today`, returning a
+/// description of each fix. Rules that are turned off in `config` are left alone, and with `rules`,
+/// only the problems found by the rules with those IDs are fixed.
+///
+/// Only the files are changed, so `docs` needs to be reloaded to see the fixes.
+pub fn fix(
+    docs: &Documents,
+    config: &Config,
+    today: NaiveDate,
+    rules: &[String],
+) -> Result<Vec<String>, TransactionError> {
+    let summary = check(docs, config, today, &Linker::default());
+    let is_selected =
+        |rule: &Rule| rules.is_empty() || rules.iter().any(|id| id.eq_ignore_ascii_case(rule.id));
+    let edits = summary
+        .diagnostics
+        .iter()
+        .filter(|d| is_selected(d.rule))
+        .filter_map(|d| d.fix.as_ref());
+    fix::apply(docs.loader(), edits)
+}
+
+/// The project that `diagnostic` was found in.
+fn diagnostic_project<'d>(docs: &'d Documents, diagnostic: &Diagnostic) -> Option<&'d Project> {
+    let document = diagnostic.document.as_ref()?;
+    if document.kind != DocumentKind::Project {
+        return None;
+    }
+    docs.projects().find(|p| p.name.as_str() == document.name)
+}
+
+/// Adds the sections that a project is required to have but doesn't as empty sections.
+fn add_required_sections(
+    config: &ValidateConfig,
+) -> impl Fn(&Documents, &Diagnostic) -> Option<Edit> + '_ {
+    move |docs, diagnostic| {
+        let project = diagnostic_project(docs, diagnostic)?;
+        let path = docs.loader().project_path(&project.name);
+        let old = fs::read_to_string(&path).ok()?;
+        let mut new = old.clone();
+        let mut added = Vec::new();
+        for section in config.missing_sections(project) {
+            new = edit::insert_section(&new, section);
+            added.push(format!("\"{}\"", section.heading()));
+        }
+        if added.is_empty() {
+            return None;
+        }
+        Some(Edit {
+            description: format!(
+                "Added empty sections {} to {}.",
+                added.join(", "),
+                project.name
+            ),
+            change: FileChange::Write { path, old, new },
+        })
+    }
+}
+
+/// Moves a project into the archive.
+fn archive_project(docs: &Documents, diagnostic: &Diagnostic) -> Option<Edit> {
+    let project = diagnostic_project(docs, diagnostic)?;
+    let loader = docs.loader();
+    let to = loader.archive_dir().join(format!("{}.md", project.name));
+    Some(Edit {
+        description: format!("Archived {}.", project.name),
+        change: FileChange::Move {
+            from: loader.project_path(&project.name),
+            to: filename::long_path(&to),
+        },
+    })
+}
+
+/// Sorts a context into the order set in `[contexts]`.
+fn sort_context(docs: &Documents, diagnostic: &Diagnostic) -> Option<Edit> {
+    let document = diagnostic.document.as_ref()?;
+    if document.kind != DocumentKind::Context {
+        return None;
+    }
+    let order = docs.context_order();
+    let old = fs::read_to_string(&document.path).ok()?;
+    let new = order::reorder(docs, &old, order);
+    if new == old {
+        return None;
+    }
+    Some(Edit {
+        description: format!("Sorted {} by {}.", document.name, order),
+        change: FileChange::Write {
+            path: document.path.clone(),
+            old,
+            new,
+        },
+    })
+}
+
+fn action_link_is_valid(
+    action: &ContextAction,
+    project: Option<&Project>,
+) -> Result<(), Cow<'static, str>> {
+    let _action_ref = unwrap_or_ok!(action.to_action_ref());
+    if project.is_none() {
+        return Err("not a valid link to project".into());
+    }
+
+    Ok(())
+}
+
+fn action_link_is_to_a_project(
+    action: &ContextAction,
+    _project: Option<&Project>,
+) -> Result<(), Cow<'static, str>> {
+    match action {
+        ContextAction::UnresolvedReference(block_ref) => Err(format!(
+            "links to a block in \"{}\", which isn't a project",
+            block_ref.link
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+fn linked_project_is_in_progress(
+    action: &ContextAction,
+    project: Option<&Project>,
+) -> Result<(), Cow<'static, str>> {
+    let _action_ref = unwrap_or_ok!(action.to_action_ref());
+    let project = unwrap_or_ok!(project);
+
+    if project.status != ProjectStatus::InProgress {
+        return Err(format!("linked project \"{}\" is not in progress", project.title()).into());
+    }
+
+    Ok(())
+}
+
+fn linked_project_contains_action(
+    action: &ContextAction,
+    project: Option<&Project>,
+) -> Result<(), Cow<'static, str>> {
+    let action_ref = unwrap_or_ok!(action.to_action_ref());
+    let project = unwrap_or_ok!(project);
+
+    if project.actions.get_action(&action_ref.action_id).is_none() {
+        return Err(format!(
+            "linked project \"{}\" doesn't have the action ^{}",
+            project.title(),
+            action_ref.action_id
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn action_in_project_is_active(
+    action: &ContextAction,
+    project: Option<&Project>,
+) -> Result<(), Cow<'static, str>> {
+    let action_ref = unwrap_or_ok!(action.to_action_ref());
+    let project = unwrap_or_ok!(project);
+    let (_, status) = unwrap_or_ok!(project.actions.get_action(&action_ref.action_id));
+
+    if status != ActionStatus::Active {
+        return Err(format!(
+            "action ^{} is not active in linked project \"{}\"",
+            action_ref.action_id,
+            project.title()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn linked_action_is_unique(
+) -> impl FnMut(&ContextAction, Option<&Project>) -> Result<(), Cow<'static, str>> {
+    let mut actions = HashSet::new();
+    move |action, _project| {
+        if let Some(action_ref) = action.to_action_ref() {
+            if !actions.insert(action_ref.clone()) {
+                return Err(
+                    format!("action ^{} is not unique in contexts", action_ref.action_id).into(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn all_active_actions_are_in_a_context(docs: &Documents, links: &Linker) -> Vec<String> {
+    docs.active_actions()
+        .filter(|a| a.contexts().next().is_none())
+        .map(|a| {
+            let title = links.link(
+                &docs.loader().project_path(&a.project.name),
+                a.project.title(),
+            );
+            // TODO: Actually print the action.
+            format!(
+                "Project \"{}\" action is active but isn't in any contexts",
+                title
+            )
+        })
+        .collect()
+}
+
+fn all_project_names_follow_scheme(docs: &Documents, links: &Linker) -> Vec<String> {
+    docs.misnamed_projects()
+        .iter()
+        .map(|filename| misnamed_project(docs.loader(), links, filename))
+        .collect()
+}
+
+fn misnamed_project(loader: &Loader, links: &Linker, filename: &str) -> String {
+    let path = loader.project_dir().join(format!("{}.md", filename));
+    format!(
+        "Project file \"{}\" doesn't follow the project naming scheme",
+        links.link(&path, filename)
+    )
+}
+
+fn action_is_not_duplicated(docs: &Documents, links: &Linker) -> Vec<String> {
+    let projects = docs
+        .projects()
+        .filter(|p| p.status == ProjectStatus::InProgress)
+        .collect::<Vec<_>>();
+    let actions = projects
+        .iter()
+        .flat_map(|project| {
+            project
+                .actions
+                .actions()
+                .filter(|(_, s)| *s != ActionStatus::Complete)
+                .map(move |(action, _)| (*project, action, action.normalized_text()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut is_grouped = vec![false; actions.len()];
+    let mut problems = Vec::new();
+    for (i, (project, action, text)) in actions.iter().enumerate() {
+        if is_grouped[i] {
+            continue;
+        }
+        let mut group = vec![*project];
+        for (j, (other_project, _, other_text)) in actions.iter().enumerate().skip(i + 1) {
+            let is_duplicate = !is_grouped[j]
+                && !group.iter().any(|p| p.name == other_project.name)
+                && is_near_duplicate(text, other_text);
+            if is_duplicate {
+                is_grouped[j] = true;
+                group.push(*other_project);
+            }
+        }
+        if group.len() > 1 {
+            let names = group
+                .iter()
+                .map(|p| {
+                    let path = docs.loader().project_path(&p.name);
+                    format!("\"{}\"", links.link(&path, p.title()))
+                })
+                .collect::<Vec<_>>();
+            problems.push(format!(
+                "Action \"{}\" is in more than one project: {}",
+                action.text,
+                names.join(", ")
+            ));
+        }
+    }
+    problems
+}
+
+fn aliased_contexts_are_one_file(docs: &Documents, links: &Linker) -> Vec<String> {
+    let mut groups = BTreeMap::<ContextName, Vec<&ContextName>>::new();
+    for context in docs.contexts() {
+        groups
+            .entry(docs.canonical_context_name(&context.name))
+            .or_default()
+            .push(&context.name);
+    }
+    groups
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .map(|(canonical, mut names)| {
+            names.sort();
+            let names = names
+                .into_iter()
+                .map(|n| format!("\"{}\"", links.link(&docs.loader().context_path(n), n)))
+                .collect::<Vec<_>>();
+            format!(
+                "Context {} has more than one file: {}",
+                canonical,
+                names.join(", ")
+            )
+        })
+        .collect()
+}
+
+fn context_is_within_capacity(docs: &Documents, links: &Linker) -> Vec<String> {
+    let names = docs
+        .contexts()
+        .map(|c| docs.canonical_context_name(&c.name))
+        .collect::<BTreeSet<_>>();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let capacity = docs.context_capacity(&name)?;
+            if !capacity.is_exceeded() {
+                return None;
+            }
+            let link = links.link(&docs.loader().context_path(&name), &name);
+            Some(format!(
+                "Context {} has {} open actions, over its capacity of {}",
+                link, capacity.actions, capacity.max
+            ))
+        })
+        .collect()
+}
+
+fn file_has_no_conflicts(docs: &Documents, links: &Linker) -> Vec<String> {
+    docs.conflicted()
+        .iter()
+        .map(|file| conflicted_file(docs.loader(), links, file))
+        .collect()
+}
+
+fn conflicted_file(loader: &Loader, links: &Linker, file: &ConflictedFile) -> String {
+    let relative = file
+        .path
+        .strip_prefix(loader.root_dir())
+        .unwrap_or(&file.path);
+    let link = links.link(&file.path, relative.display());
+    format!("\"{}\" has unresolved conflicts on {}", link, file.lines())
+}
+
+fn file_is_readable(docs: &Documents, links: &Linker) -> Vec<String> {
+    docs.unreadable()
+        .iter()
+        .map(|file| unreadable_file(docs.loader(), links, file))
+        .collect()
+}
+
+fn unreadable_file(loader: &Loader, links: &Linker, file: &UnreadableFile) -> String {
+    let relative = file
+        .path
+        .strip_prefix(loader.root_dir())
+        .unwrap_or(&file.path);
+    let link = links.link(&file.path, relative.display());
+    format!("\"{}\" can't be read: {}", link, file.error)
+}
+
+fn project_is_not_duplicated(docs: &Documents, links: &Linker) -> Vec<String> {
+    dedupe::find(docs)
+        .into_iter()
+        .map(|duplicates| {
+            let names = duplicates
+                .projects
+                .iter()
+                .map(|p| {
+                    let path = docs.loader().project_path(&p.name);
+                    format!("\"{}\"", links.link(&path, p.name.as_str()))
+                })
+                .collect::<Vec<_>>();
+            let how = match duplicates.is_identical {
+                true => "the same",
+                false => "nearly the same",
+            };
+            format!("Projects {} have {} contents", names.join(", "), how)
+        })
+        .collect()
+}
+
+fn context_follows_order(docs: &Documents, context: &Context) -> Result<(), Cow<'static, str>> {
+    let order = docs.context_order();
+    if docs.context_layout() != ContextLayout::Files || order::is_in_order(docs, context, order) {
+        return Ok(());
+    }
+    Err(format!(
+        "actions aren't sorted by {}, as set by `order` in `[contexts]`",
+        order
+    )
+    .into())
+}
+
+trait ProjectValidator {
+    fn validate(&mut self, project: &Project) -> Result<(), Cow<'static, str>>;
+}
+
+impl<F> ProjectValidator for F
+where
+    F: FnMut(&Project) -> Result<(), Cow<'static, str>>,
+{
+    fn validate(&mut self, project: &Project) -> Result<(), Cow<'static, str>> {
+        self(project)
+    }
+}
+
+trait ContextActionValidator {
+    fn validate(
+        &mut self,
+        action: &ContextAction,
+        project: Option<&Project>,
+    ) -> Result<(), Cow<'static, str>>;
+}
+
+impl<F> ContextActionValidator for F
+where
+    F: FnMut(&ContextAction, Option<&Project>) -> Result<(), Cow<'static, str>>,
+{
+    fn validate(
+        &mut self,
+        action: &ContextAction,
+        project: Option<&Project>,
+    ) -> Result<(), Cow<'static, str>> {
+        self(action, project)
+    }
+}
+
+trait ContextValidator {
+    fn validate(&mut self, docs: &Documents, context: &Context) -> Result<(), Cow<'static, str>>;
+}
+
+impl<F> ContextValidator for F
+where
+    F: FnMut(&Documents, &Context) -> Result<(), Cow<'static, str>>,
+{
+    fn validate(&mut self, docs: &Documents, context: &Context) -> Result<(), Cow<'static, str>> {
+        self(docs, context)
+    }
+}
+
+trait AdHocValidator {
+    fn validate(&mut self, docs: &Documents, links: &Linker) -> Vec<String>;
+}
+
+impl<F> AdHocValidator for F
+where
+    F: FnMut(&Documents, &Linker) -> Vec<String>,
+{
+    fn validate(&mut self, docs: &Documents, links: &Linker) -> Vec<String> {
+        self(docs, links)
+    }
+}
+
+#[derive(Default)]
+pub struct ValidatorRunner<'a> {
+    project_validators: Vec<(&'static Rule, Box<dyn ProjectValidator + 'a>)>,
+    context_action_validators: Vec<(&'static Rule, Box<dyn ContextActionValidator + 'a>)>,
+    context_validators: Vec<(&'static Rule, Box<dyn ContextValidator + 'a>)>,
+    ad_hoc_validators: Vec<(&'static Rule, Box<dyn AdHocValidator + 'a>)>,
+    fixes: Vec<(&'static Rule, Box<dyn Fix + 'a>)>,
+    links: Linker,
+    disabled: Vec<String>,
+    enabled: Vec<String>,
+    progress: Progress,
+    /// The diagnostic that each problem caused by a linked project was first reported as, keyed by
+    /// rule ID, project, and message.
+    causes: HashMap<(&'static str, String, String), usize>,
+    summary: Summary,
+}
+
+impl<'a> ValidatorRunner<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how documents are linked to in the output.
+    pub fn with_links(mut self, links: Linker) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// Turns off the rules with the IDs in `disabled`.
+    pub fn with_disabled(mut self, disabled: &[String]) -> Self {
+        self.disabled.extend(disabled.iter().cloned());
+        self
+    }
+
+    /// Turns on the opt-in rules with the IDs in `enabled`.
+    pub fn with_enabled(mut self, enabled: &[String]) -> Self {
+        self.enabled.extend(enabled.iter().cloned());
+        self
+    }
+
+    /// Shows the progress of the run with `progress`.
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn for_all_projects<F>(mut self, rule: &'static Rule, validator: F) -> Self
+    where
+        F: FnMut(&Project) -> Result<(), Cow<'static, str>> + 'a,
+    {
+        self.project_validators.push((rule, Box::new(validator)));
+        self
+    }
+
+    pub fn for_all_context_actions<F>(mut self, rule: &'static Rule, validator: F) -> Self
+    where
+        F: FnMut(&ContextAction, Option<&Project>) -> Result<(), Cow<'static, str>> + 'a,
+    {
+        self.context_action_validators
+            .push((rule, Box::new(validator)));
+        self
+    }
+
+    pub fn for_all_contexts<F>(mut self, rule: &'static Rule, validator: F) -> Self
+    where
+        F: FnMut(&Documents, &Context) -> Result<(), Cow<'static, str>> + 'a,
+    {
+        self.context_validators.push((rule, Box::new(validator)));
+        self
+    }
+
+    pub fn with_ad_hoc<F>(mut self, rule: &'static Rule, validator: F) -> Self
+    where
+        F: FnMut(&Documents, &Linker) -> Vec<String> + 'a,
+    {
+        self.ad_hoc_validators.push((rule, Box::new(validator)));
+        self
+    }
+
+    /// Uses `fix` to fix the problems that `rule` finds. Problems are only fixed when the runner
+    /// is `run` with every document at once.
+    pub fn with_fix<F>(mut self, rule: &'static Rule, fix: F) -> Self
+    where
+        F: Fix + 'a,
+    {
+        self.fixes.push((rule, Box::new(fix)));
+        self
+    }
+
+    /// Runs the validators over `docs`, collecting the problems they find and how to fix them.
+    pub fn run(mut self, docs: &Documents) -> Summary {
+        self.retain_enabled();
+        self.progress
+            .start("Checking projects", Some(docs.projects().count()));
+        for project in docs.projects() {
+            self.progress.advance();
+            self.run_project_validators(docs.loader(), project);
+        }
+
+        self.progress
+            .start("Checking contexts", Some(docs.contexts().count()));
+        for context in docs.contexts() {
+            self.progress.advance();
+            for action in context.actions() {
+                let project = action
+                    .to_action_ref()
+                    .and_then(|a| docs.project(&a.project_name));
+                self.run_context_action_validators(docs, context, action, project);
+            }
+            self.run_context_validators(docs, context);
+        }
+
+        self.run_ad_hoc_validators(docs);
+        self.progress.finish();
+
+        for diagnostic in self.summary.diagnostics.iter_mut() {
+            diagnostic.fix = self
+                .fixes
+                .iter()
+                .filter(|(r, _)| r.id == diagnostic.rule.id)
+                .find_map(|(_, f)| f.fix(docs, diagnostic));
+        }
+
+        self.summary.files = docs.projects().count()
+            + docs.misnamed_projects().len()
+            + docs.contexts().count()
+            + docs.conflicted().len()
+            + docs.unreadable().len();
+        self.summary
+    }
+
+    /// Runs the project validators over the projects in the GTD folder of `loader`, reading them
+    /// one at a time, and reads the contexts in `layout` the same way. Files that can't be loaded
+    /// are reported under the rules that `run` finds them with, and the other validators need
+    /// every document at once, so they're skipped.
+    pub fn run_streaming(
+        mut self,
+        loader: &Loader,
+        layout: ContextLayout,
+    ) -> Result<Summary, IoError> {
+        self.retain_enabled();
+        self.progress.start("Checking projects", None);
+        let mut skipped = Vec::new();
+        for project in loader.projects_iter()? {
+            self.progress.advance();
+            match project {
+                Ok(project) => self.run_project_validators(loader, &project),
+                Err(file) => skipped.push(file),
+            }
+            self.summary.files += 1;
+        }
+
+        self.progress.start("Checking contexts", None);
+        for context in loader.contexts_iter(layout)? {
+            self.progress.advance();
+            skipped.extend(context.err());
+            self.summary.files += 1;
+        }
+        self.report_skipped(loader, &skipped);
+        self.progress.finish();
+        Ok(self.summary)
+    }
+
+    /// Reports the files in `skipped` under the rules that find them when every document is
+    /// loaded, if they're turned on, in the order those rules run in.
+    fn report_skipped(&mut self, loader: &Loader, skipped: &[SkippedFile]) {
+        let rules = self.ad_hoc_validators.iter().map(|(r, _)| *r);
+        let mut problems = Vec::new();
+        for rule in rules {
+            for file in skipped {
+                let message = match file {
+                    SkippedFile::Misnamed(filename)
+                        if rule.id == ALL_PROJECT_NAMES_FOLLOW_SCHEME.id =>
+                    {
+                        misnamed_project(loader, &self.links, filename)
+                    }
+                    SkippedFile::Conflicted(file) if rule.id == FILE_HAS_NO_CONFLICTS.id => {
+                        conflicted_file(loader, &self.links, file)
+                    }
+                    SkippedFile::Unreadable(file) if rule.id == FILE_IS_READABLE.id => {
+                        unreadable_file(loader, &self.links, file)
+                    }
+                    _ => continue,
+                };
+                problems.push((rule, message));
+            }
+        }
+        for (rule, message) in problems {
+            self.report(rule, None, message);
+        }
+    }
+
+    /// Drops the validators for rules that are turned off.
+    fn retain_enabled(&mut self) {
+        let disabled = std::mem::take(&mut self.disabled);
+        let enabled = std::mem::take(&mut self.enabled);
+        let is_listed =
+            |ids: &[String], rule: &Rule| ids.iter().any(|id| id.eq_ignore_ascii_case(rule.id));
+        let is_enabled = |rule: &Rule| {
+            !is_listed(&disabled, rule) && (!rule.is_opt_in || is_listed(&enabled, rule))
+        };
+        self.project_validators.retain(|(r, _)| is_enabled(r));
+        self.context_action_validators
+            .retain(|(r, _)| is_enabled(r));
+        self.context_validators.retain(|(r, _)| is_enabled(r));
+        self.ad_hoc_validators.retain(|(r, _)| is_enabled(r));
+
+        // Rules run after the rules they depend on, and otherwise in the order they were added.
+        self.project_validators.sort_by_key(|(r, _)| r.depth());
+        self.context_action_validators
+            .sort_by_key(|(r, _)| r.depth());
+    }
 
-        if !results.is_empty() {
-            println!("{}:", project.name);
-            for result in results {
-                println!("- {}", result);
+    fn run_project_validators(&mut self, loader: &Loader, project: &Project) {
+        let mut results = Vec::new();
+        let mut blocked = Vec::new();
+        for (rule, v) in self.project_validators.iter_mut() {
+            if follows_from(rule, &blocked) {
+                blocked.push(*rule);
+            } else if let Err(e) = v.validate(project) {
+                blocked.push(*rule);
+                results.push((*rule, e));
             }
         }
+
+        let path = loader.project_path(&project.name);
+        for (rule, message) in results {
+            self.report(
+                rule,
+                Some(Document::new(DocumentKind::Project, &project.name, &path)),
+                message.into_owned(),
+            );
+        }
     }
 
     fn run_context_action_validators(
         &mut self,
+        docs: &Documents,
         context: &Context,
         action: &ContextAction,
         project: Option<&Project>,
     ) {
-        let results = self
-            .context_action_validators
-            .iter_mut()
-            .flat_map(|v| v.validate(action, project).err())
-            .collect::<Vec<_>>();
+        let mut results = Vec::new();
+        let mut blocked = Vec::new();
+        for (rule, v) in self.context_action_validators.iter_mut() {
+            if follows_from(rule, &blocked) {
+                blocked.push(*rule);
+            } else if let Err(e) = v.validate(action, project) {
+                blocked.push(*rule);
+                results.push((*rule, e));
+            }
+        }
+
+        // The same problem with a linked project, found from several actions, is reported once,
+        // with the other places it was found as related documents.
+        let path = docs.loader().context_path(&context.name);
+        let linked = action.to_action_ref().map(|a| a.project_name.to_string());
+        for (rule, message) in results {
+            let document = Document::new(DocumentKind::Context, &context.name, &path);
+            let message = message.into_owned();
+            let key = linked.clone().map(|name| (rule.id, name, message.clone()));
+            match key.as_ref().and_then(|key| self.causes.get(key)) {
+                Some(&i) => self.summary.diagnostics[i].relate(document),
+                None => {
+                    if let Some(key) = key {
+                        self.causes.insert(key, self.summary.diagnostics.len());
+                    }
+                    self.report(rule, Some(document), message);
+                }
+            }
+        }
+    }
 
-        if !results.is_empty() {
-            println!("action in {}:", context.name);
-            for result in results {
-                println!("- {}", result);
+    fn run_context_validators(&mut self, docs: &Documents, context: &Context) {
+        let mut results = Vec::new();
+        for (rule, v) in self.context_validators.iter_mut() {
+            if let Err(e) = v.validate(docs, context) {
+                results.push((*rule, e));
             }
         }
+
+        let path = docs.loader().context_path(&context.name);
+        for (rule, message) in results {
+            self.report(
+                rule,
+                Some(Document::new(DocumentKind::Context, &context.name, &path)),
+                message.into_owned(),
+            );
+        }
     }
 
     fn run_ad_hoc_validators(&mut self, docs: &Documents) {
-        for v in self.ad_hoc_validators.iter_mut() {
-            v.validate(docs);
+        let mut problems = Vec::new();
+        let total = self.ad_hoc_validators.len();
+        self.progress
+            .start("Checking across documents", Some(total));
+        for (rule, v) in self.ad_hoc_validators.iter_mut() {
+            self.progress.advance();
+            for problem in v.validate(docs, &self.links) {
+                problems.push((*rule, problem));
+            }
         }
+        for (rule, problem) in problems {
+            self.report(rule, None, problem);
+        }
+    }
+
+    /// Records a problem found by `rule`.
+    fn report(&mut self, rule: &'static Rule, document: Option<Document>, message: String) {
+        *self.summary.hits.entry(rule.id).or_default() += 1;
+        self.summary.diagnostics.push(Diagnostic {
+            rule,
+            document,
+            related: Vec::new(),
+            message,
+            fix: None,
+        });
     }
 }
 
-impl<'a> Default for ValidatorRunner<'a> {
-    fn default() -> Self {
-        Self {
-            project_validators: Vec::new(),
-            context_action_validators: Vec::new(),
-            ad_hoc_validators: Vec::new(),
+/// Whether the problems `rule` finds in a document would follow from ones that the `blocked` rules
+/// found there, or were kept from looking for.
+fn follows_from(rule: &Rule, blocked: &[&Rule]) -> bool {
+    rule.depends_on
+        .iter()
+        .any(|d| blocked.iter().any(|b| b.id == d.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        markdown::Fragment,
+        project::{ActionId, ActionRef, Name as ProjectName},
+        tag::Tag,
+        test_dir::TempDir,
+    };
+    use pulldown_cmark::Event;
+
+    mod rules {
+        use super::*;
+
+        #[test]
+        fn ids_are_unique_and_in_order() {
+            let ids = RULES.iter().map(|r| r.id).collect::<Vec<_>>();
+            let mut sorted = ids.clone();
+            sorted.sort_unstable();
+            sorted.dedup();
+            assert_eq!(ids, sorted);
+        }
+
+        #[test]
+        fn rule_is_found_ignoring_case() {
+            assert_eq!(rule("gtd003"), Some(&PROJECT_TAGS_ARE_ALLOWED));
+            assert_eq!(rule("GTD999"), None);
+        }
+
+        #[test]
+        fn summary_counts_by_severity() {
+            let summary = Summary {
+                files: 3,
+                hits: vec![("GTD001", 2), ("GTD012", 3), ("GTD013", 1)]
+                    .into_iter()
+                    .collect(),
+                diagnostics: Vec::new(),
+            };
+            assert_eq!(summary.count(Severity::Error), 2);
+            assert_eq!(summary.count(Severity::Warning), 4);
+            assert_eq!(
+                summary
+                    .statistics()
+                    .iter()
+                    .map(|(r, n)| (r.id, *n))
+                    .collect::<Vec<_>>(),
+                vec![("GTD012", 3), ("GTD001", 2), ("GTD013", 1)]
+            );
+        }
+
+        #[test]
+        fn explanation_has_examples() {
+            let explanation = explain(&PROJECT_HAS_REQUIRED_TAGS);
+            assert!(explanation.starts_with("GTD004: project-has-required-tags (warning)\n\n"));
+            assert!(explanation.contains("Fails:\n\n    # Plan trip\n    #in-progress\n"));
+            assert!(explanation.contains("add \"GTD004\" to `disabled`"));
+        }
+    }
+
+    mod streaming {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn project_rules_run_one_project_at_a_time() {
+            let dir = TempDir::new("streaming");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Projects/202401010000 Plan trip.md"),
+                "# Plan vacation\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights ^abcdef\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Projects/202401020000 Old trip.md"),
+                "# Old trip\n#complete\n",
+            )
+            .unwrap();
+            fs::write(dir.join("Projects/Misnamed.md"), "# Misnamed\n#someday\n").unwrap();
+            fs::write(dir.join("Contexts/@computer.md"), "# @computer\n").unwrap();
+            let loader = Loader::new(dir.to_path_buf(), NameScheme::default());
+
+            let summary = ValidatorRunner::new()
+                .for_all_projects(&PROJECT_TITLE_MATCHES_NAME, project_title_matches_name)
+                .with_ad_hoc(
+                    &ALL_ACTIVE_ACTIONS_ARE_IN_A_CONTEXT,
+                    all_active_actions_are_in_a_context,
+                )
+                .run_streaming(&loader, ContextLayout::Files)
+                .unwrap();
+            assert_eq!(summary.files, 4);
+            let rules = summary
+                .diagnostics
+                .iter()
+                .map(|d| d.rule.id)
+                .collect::<Vec<_>>();
+            assert_eq!(rules, ["GTD002"]);
+        }
+
+        #[test]
+        fn files_that_cant_be_loaded_are_reported_like_without_streaming() {
+            let dir = TempDir::new("streaming-skipped");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Projects/202401010000 Plan trip.md"),
+                "# Plan vacation\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights ^abcdef\n",
+            )
+            .unwrap();
+            fs::write(dir.join("Projects/202401020000 Broken.md"), "# Broken\n").unwrap();
+            fs::write(
+                dir.join("Projects/202401030000 Conflicted.md"),
+                "# Conflicted\n<<<<<<< HEAD\n#in-progress\n=======\n#someday\n>>>>>>> laptop\n",
+            )
+            .unwrap();
+            fs::write(dir.join("Projects/Misnamed.md"), "# Misnamed\n#someday\n").unwrap();
+            fs::write(
+                dir.join("Contexts/@computer.md"),
+                "# @computer\n\n- ![[202401010000 Plan trip#^abcdef]]\n",
+            )
+            .unwrap();
+            let config = Config::default();
+            let today = NaiveDate::from_ymd_opt(2024, 1, 4).unwrap();
+            let docs = Documents::load(&dir, &config).unwrap();
+            let loader = docs.loader();
+
+            let loaded = runner(&docs, &config, today).run(&docs);
+            let streamed = file_rules(project_rules(
+                ValidatorRunner::new(),
+                &config,
+                loader.scheme(),
+                today,
+            ))
+            .run_streaming(loader, ContextLayout::Files)
+            .unwrap();
+            let rules = streamed
+                .diagnostics
+                .iter()
+                .map(|d| d.rule.id)
+                .collect::<Vec<_>>();
+            assert_eq!(rules, ["GTD002", "GTD013", "GTD022", "GTD023"]);
+            assert_eq!(streamed.diagnostics, loaded.diagnostics);
+            assert_eq!(streamed.hits, loaded.hits);
+        }
+
+        #[test]
+        fn unreadable_contexts_are_reported() {
+            let dir = TempDir::new("streaming-context");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(dir.join("Contexts/@home.md"), "Call mom\n").unwrap();
+            fs::write(dir.join("Contexts/@computer.md"), "# @computer\n").unwrap();
+            let config = Config::default();
+            let today = NaiveDate::from_ymd_opt(2024, 1, 4).unwrap();
+            let docs = Documents::load(&dir, &config).unwrap();
+            let loader = docs.loader();
+
+            let loaded = runner(&docs, &config, today).run(&docs);
+            let streamed = file_rules(ValidatorRunner::new())
+                .run_streaming(loader, ContextLayout::Files)
+                .unwrap();
+            assert_eq!(streamed.hits.get("GTD023"), Some(&1));
+            assert_eq!(streamed, loaded);
+
+            fs::write(crate::action_list::path(&dir), "Call mom\n").unwrap();
+            let streamed = file_rules(ValidatorRunner::new())
+                .run_streaming(loader, ContextLayout::List)
+                .unwrap();
+            assert_eq!(streamed.hits.get("GTD023"), Some(&1));
+        }
+    }
+
+    mod action_is_not_duplicated {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn near_duplicates_are_found() {
+            assert!(is_near_duplicate("book flights", "book flights"));
+            assert!(is_near_duplicate("book flights", "bok flights"));
+            assert!(!is_near_duplicate("book flights", "book hotels"));
+            assert!(!is_near_duplicate("call", "cell"));
+        }
+
+        #[test]
+        fn duplicates_in_other_projects_are_reported() {
+            let dir = TempDir::new("duplicate");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Projects/202401010000 Plan trip.md"),
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights\n- Pack bags\n- Pack bags\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Projects/202401020000 Visit family.md"),
+                "# Visit family\n#in-progress\n\n## Actions\n\n### Active\n\n- Book **flights** 📅 2024-01-05\n- Buy presents\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Projects/202401030000 Old trip.md"),
+                "# Old trip\n#complete\n\n## Actions\n\n### Complete\n\n- Book flights\n",
+            )
+            .unwrap();
+
+            let docs = Documents::load(&dir, &Config::default()).unwrap();
+            assert_eq!(
+                action_is_not_duplicated(&docs, &Linker::default()),
+                vec![String::from(
+                    "Action \"Book flights\" is in more than one project: \"Plan trip\", \"Visit family\""
+                )]
+            );
+        }
+    }
+
+    mod aliased_contexts_are_one_file {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn files_for_an_alias_and_its_context_are_reported() {
+            let dir = TempDir::new("alias");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Contexts/@computer.md"),
+                "# @computer\n\n- Back up\n",
+            )
+            .unwrap();
+            fs::write(dir.join("Contexts/@pc.md"), "# @pc\n\n- Update drivers\n").unwrap();
+            fs::write(dir.join("Contexts/@home.md"), "# @home\n\n- Water plants\n").unwrap();
+            let config = Config::parse("[contexts.aliases]\n\"@pc\" = \"@computer\"\n").unwrap();
+
+            let docs = Documents::load(&dir, &config).unwrap();
+            assert_eq!(
+                aliased_contexts_are_one_file(&docs, &Linker::default()),
+                vec![String::from(
+                    "Context @computer has more than one file: \"@computer\", \"@pc\""
+                )]
+            );
+            let name = ContextName::new(String::from("@pc"));
+            assert_eq!(docs.context(&name).unwrap().name.as_str(), "@computer");
+
+            let docs = Documents::load(&dir, &Config::default()).unwrap();
+            assert!(aliased_contexts_are_one_file(&docs, &Linker::default()).is_empty());
+        }
+    }
+
+    mod context_is_within_capacity {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn contexts_over_their_capacity_are_reported() {
+            let dir = TempDir::new("capacity");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Projects/202401010000 Plan trip.md"),
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Call hotel ^abcdef\n\n### Complete\n\n- Call airline ^ghijkl\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Contexts/@calls.md"),
+                "# @calls\n\n- Call mom\n- ![[202401010000 Plan trip#^abcdef]]\n- ![[202401010000 Plan trip#^ghijkl]]\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Contexts/@phone.md"),
+                "# @phone\n\n- Call the bank\n",
+            )
+            .unwrap();
+            fs::write(dir.join("Contexts/@home.md"), "# @home\n\n- Water plants\n").unwrap();
+            let config = |max: usize| {
+                Config::parse(&format!(
+                    "[contexts.aliases]\n\"@phone\" = \"@calls\"\n\n[contexts.capacity]\n\"@calls\" = {}\n\"@home\" = 1\n",
+                    max
+                ))
+                .unwrap()
+            };
+
+            let docs = Documents::load(&dir, &config(2)).unwrap();
+            assert_eq!(
+                context_is_within_capacity(&docs, &Linker::default()),
+                vec![String::from(
+                    "Context @calls has 3 open actions, over its capacity of 2"
+                )]
+            );
+
+            let docs = Documents::load(&dir, &config(3)).unwrap();
+            assert!(context_is_within_capacity(&docs, &Linker::default()).is_empty());
+        }
+    }
+
+    mod context_follows_order {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn contexts_out_of_order_are_reported_and_sorted() {
+            let dir = TempDir::new("context-order");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Projects/202401010000 Plan trip.md"),
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Call hotel ^abcdef\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Contexts/@calls.md"),
+                "# @calls\n\n- Call mom\n- ![[202401010000 Plan trip#^abcdef]]\n",
+            )
+            .unwrap();
+            let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+            let rules = |config: &Config| {
+                let docs = Documents::load(&dir, config).unwrap();
+                check(&docs, config, today, &Linker::default())
+                    .diagnostics
+                    .iter()
+                    .map(|d| (d.rule.id, d.message.clone()))
+                    .collect::<Vec<_>>()
+            };
+
+            assert!(rules(&Config::default()).is_empty());
+            let config = Config::parse("[contexts]\norder = \"project\"\n").unwrap();
+            assert_eq!(
+                rules(&config),
+                vec![(
+                    "GTD027",
+                    String::from(
+                        "actions aren't sorted by project, as set by `order` in `[contexts]`"
+                    )
+                )]
+            );
+
+            let docs = Documents::load(&dir, &config).unwrap();
+            assert_eq!(
+                fix(&docs, &config, today, &[]).unwrap(),
+                vec!["Sorted @calls by project."]
+            );
+            assert_eq!(
+                fs::read_to_string(dir.join("Contexts/@calls.md")).unwrap(),
+                "# @calls\n\n- ![[202401010000 Plan trip#^abcdef]]\n- Call mom\n"
+            );
+            assert!(rules(&config).is_empty());
+        }
+    }
+
+    mod rule_dependencies {
+        use super::*;
+        use std::fs;
+
+        fn folder() -> TempDir {
+            let dir = TempDir::new("dependencies");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Projects/202401010000 Plan trip.md"),
+                "# Plan trip\n#someday\n\n## Actions\n\n### Upcoming\n\n- Call hotel ^abcdef\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Projects/202001010000 Paint fence.md"),
+                "# Paint fence\n#in-progress\n\n## Actions\n\n### Upcoming\n\n- Buy paint ^ghijkl\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Contexts/@calls.md"),
+                "# @calls\n\n- ![[202401010000 Plan trip#^abcdef]]\n",
+            )
+            .unwrap();
+            dir
+        }
+
+        #[test]
+        fn only_causes_are_reported() {
+            let dir = folder();
+            let config = Config::default();
+            let docs = Documents::load(&dir, &config).unwrap();
+            let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+            let summary = check(&docs, &config, today, &Linker::default());
+            let ids = summary
+                .diagnostics
+                .iter()
+                .map(|d| d.rule.id)
+                .collect::<Vec<_>>();
+            assert_eq!(ids, vec!["GTD006", "GTD008"]);
+        }
+
+        #[test]
+        fn rules_run_after_the_rules_they_depend_on() {
+            let dir = folder();
+            let config = Config::default();
+            let docs = Documents::load(&dir, &config).unwrap();
+            let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+            let scheme = docs.loader().scheme();
+            let run = |runner: ValidatorRunner| {
+                let summary = runner.run(&docs);
+                summary
+                    .statistics()
+                    .into_iter()
+                    .map(|(r, _)| r.id)
+                    .collect::<Vec<_>>()
+            };
+
+            let stalled = in_progress_project_is_not_stalled(scheme, Period::Weeks(1), today);
+            assert_eq!(
+                run(ValidatorRunner::new()
+                    .for_all_projects(&IN_PROGRESS_PROJECT_IS_NOT_STALLED, stalled)),
+                vec!["GTD015"]
+            );
+            let stalled = in_progress_project_is_not_stalled(scheme, Period::Weeks(1), today);
+            let runner = ValidatorRunner::new()
+                .for_all_projects(&IN_PROGRESS_PROJECT_IS_NOT_STALLED, stalled)
+                .for_all_projects(
+                    &IN_PROGRESS_PROJECT_HAS_ACTIVE_ACTIONS,
+                    in_progress_project_has_active_actions,
+                );
+            assert_eq!(run(runner), vec!["GTD006"]);
+        }
+    }
+
+    mod related_documents {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn problems_with_a_linked_project_are_reported_once() {
+            let dir = TempDir::new("related");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Contexts/@calls.md"),
+                "# @calls\n\n- ![[202401010000 Plan trip#^abcdef]]\n- ![[202401010000 Plan trip#^ghijkl]]\n- ![[202401020000 Paint fence#^mnopqr]]\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Contexts/@home.md"),
+                "# @home\n\n- ![[202401010000 Plan trip#^stuvwx]]\n",
+            )
+            .unwrap();
+            let config = Config::default();
+            let docs = Documents::load(&dir, &config).unwrap();
+
+            let summary = ValidatorRunner::new()
+                .for_all_context_actions(&ACTION_LINK_IS_VALID, action_link_is_valid)
+                .run(&docs);
+            let places = summary
+                .diagnostics
+                .iter()
+                .map(|d| {
+                    let document = d.document.as_ref().map(|d| d.name.as_str());
+                    let related = d.related.iter().map(|d| d.name.as_str()).collect();
+                    (document, related)
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                places,
+                vec![
+                    (Some("@calls"), vec!["@home"]),
+                    (Some("@calls"), Vec::new())
+                ]
+            );
+            assert_eq!(summary.count(Severity::Error), 2);
+        }
+    }
+
+    mod project_is_not_duplicated {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn copied_project_files_are_reported() {
+            let dir = TempDir::new("duplicate-project");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Book flights\n";
+            fs::write(dir.join("Projects/202401010000 Plan trip.md"), text).unwrap();
+            fs::write(dir.join("Projects/202401010001 Plan trip.md"), text).unwrap();
+            fs::write(
+                dir.join("Projects/202401020000 Do taxes.md"),
+                "# Do taxes\n#in-progress\n",
+            )
+            .unwrap();
+
+            let docs = Documents::load(&dir, &Config::default()).unwrap();
+            assert_eq!(
+                project_is_not_duplicated(&docs, &Linker::default()),
+                vec![String::from(
+                    "Projects \"202401010000 Plan trip\", \"202401010001 Plan trip\" have the same contents"
+                )]
+            );
+        }
+    }
+
+    mod project_id_is_unique {
+        use super::*;
+
+        #[test]
+        fn different_id_same_name_is_unique() {
+            let project_a = Project::parse(
+                "197001010000 Project title",
+                "# Project title\n#in-progress\n",
+            )
+            .unwrap();
+            let project_b = Project::parse(
+                "197001011200 Project title",
+                "# Project title\n#in-progress\n",
+            )
+            .unwrap();
+
+            let mut validator = project_id_is_unique();
+            let _ = validator(&project_a);
+            let res = validator(&project_b);
+            assert!(res.is_ok());
+        }
+
+        #[test]
+        fn same_id_different_name_is_not_unique() {
+            let project_a = Project::parse(
+                "197001010000 Project title",
+                "# Project title\n#in-progress\n",
+            )
+            .unwrap();
+            let project_b = Project::parse(
+                "197001010000 Other project",
+                "# Other project\n#in-progress\n",
+            )
+            .unwrap();
+
+            let mut validator = project_id_is_unique();
+            let _ = validator(&project_a);
+            let res = validator(&project_b);
+            assert!(res.is_err());
+        }
+    }
+
+    mod project_title_matches_name {
+        use super::*;
+
+        #[test]
+        fn same_title_matches() {
+            let project = Project::parse(
+                "197001010000 Project title",
+                "# Project title\n#in-progress\n",
+            )
+            .unwrap();
+
+            let res = project_title_matches_name(&project);
+            assert!(res.is_ok());
+        }
+
+        #[test]
+        fn different_title_doesnt_match() {
+            let project = Project::parse(
+                "197001010000 Project title",
+                "# Other project\n#in-progress\n",
+            )
+            .unwrap();
+
+            let res = project_title_matches_name(&project);
+            assert!(res.is_err());
+        }
+
+        #[test]
+        fn title_with_characters_left_out_of_the_name_matches() {
+            let project = Project::parse(
+                "197001010000 Q3 Plan A-B test",
+                "# Q3: Plan A/B test?\n#in-progress\n",
+            )
+            .unwrap();
+
+            let res = project_title_matches_name(&project);
+            assert!(res.is_ok());
+        }
+
+        #[test]
+        fn formatted_title_matches() {
+            let project = Project::parse(
+                "197001010000 Project title",
+                "# _Project_  title\n#in-progress\n",
+            )
+            .unwrap();
+
+            let res = project_title_matches_name(&project);
+            assert!(res.is_ok());
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        markdown::Fragment,
-        project::{ActionId, ActionRef, Name as ProjectName},
-    };
-    use pulldown_cmark::Event;
 
-    mod project_id_is_unique {
+    mod project_tags_are_allowed {
         use super::*;
 
         #[test]
-        fn different_id_same_name_is_unique() {
-            let project_a = Project::parse(
+        fn allowed_tag_is_ok() {
+            let tags = TagConfig {
+                allowed: vec![Tag::new("area").unwrap()],
+                required: vec![],
+            };
+            let project = Project::parse(
                 "197001010000 Project title",
-                "# Project title\n#in-progress\n",
-            )
-            .unwrap();
-            let project_b = Project::parse(
-                "197001011200 Project title",
-                "# Project title\n#in-progress\n",
+                "# Project title\n#in-progress #area/work\n",
             )
             .unwrap();
 
-            let mut validator = project_id_is_unique();
-            let _ = validator(&project_a);
-            let res = validator(&project_b);
+            let res = project_tags_are_allowed(&tags)(&project);
             assert!(res.is_ok());
         }
 
         #[test]
-        fn same_id_different_name_is_not_unique() {
-            let project_a = Project::parse(
+        fn unknown_tag_is_err() {
+            let tags = TagConfig {
+                allowed: vec![Tag::new("area").unwrap()],
+                required: vec![],
+            };
+            let project = Project::parse(
                 "197001010000 Project title",
-                "# Project title\n#in-progress\n",
+                "# Project title\n#in-progress #other\n",
             )
             .unwrap();
-            let project_b = Project::parse(
-                "197001010000 Other project",
-                "# Other project\n#in-progress\n",
+
+            let res = project_tags_are_allowed(&tags)(&project);
+            assert!(res.is_err());
+        }
+
+        #[test]
+        fn any_tag_is_ok_without_allowed_tags() {
+            let tags = TagConfig::default();
+            let project = Project::parse(
+                "197001010000 Project title",
+                "# Project title\n#in-progress #other\n",
             )
             .unwrap();
 
-            let mut validator = project_id_is_unique();
-            let _ = validator(&project_a);
-            let res = validator(&project_b);
-            assert!(res.is_err());
+            let res = project_tags_are_allowed(&tags)(&project);
+            assert!(res.is_ok());
         }
     }
 
-    mod project_title_matches_name {
+    mod project_has_required_tags {
         use super::*;
 
+        fn tags() -> TagConfig {
+            TagConfig {
+                allowed: vec![],
+                required: vec![Tag::new("area").unwrap()],
+            }
+        }
+
         #[test]
-        fn same_title_matches() {
+        fn single_required_tag_is_ok() {
+            let tags = tags();
             let project = Project::parse(
                 "197001010000 Project title",
-                "# Project title\n#in-progress\n",
+                "# Project title\n#in-progress #area/work\n",
             )
             .unwrap();
 
-            let res = project_title_matches_name(&project);
+            let res = project_has_required_tags(&tags)(&project);
             assert!(res.is_ok());
         }
 
         #[test]
-        fn different_title_doesnt_match() {
+        fn missing_required_tag_is_err() {
+            let tags = tags();
             let project = Project::parse(
                 "197001010000 Project title",
-                "# Other project\n#in-progress\n",
+                "# Project title\n#in-progress\n",
             )
             .unwrap();
 
-            let res = project_title_matches_name(&project);
+            let res = project_has_required_tags(&tags)(&project);
+            assert!(res.is_err());
+        }
+
+        #[test]
+        fn multiple_required_tags_is_err() {
+            let tags = tags();
+            let project = Project::parse(
+                "197001010000 Project title",
+                "# Project title\n#in-progress #area/work #area/home\n",
+            )
+            .unwrap();
+
+            let res = project_has_required_tags(&tags)(&project);
             assert!(res.is_err());
         }
     }
@@ -532,6 +2840,401 @@ mod tests {
         }
     }
 
+    mod in_progress_project_is_not_stalled {
+        use super::*;
+
+        fn check(text: &str) -> Result<(), Cow<'static, str>> {
+            let project = Project::parse("202401010000 Plan trip", text).unwrap();
+            let scheme = NameScheme::default();
+            let today = NaiveDate::from_ymd_opt(2024, 1, 20).unwrap();
+            let mut validator =
+                in_progress_project_is_not_stalled(&scheme, Period::Weeks(1), today);
+            validator(&project)
+        }
+
+        #[test]
+        fn project_with_active_actions_is_ok() {
+            let res = check("# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack bags\n\n### Upcoming\n\n- Go\n");
+            assert!(res.is_ok());
+        }
+
+        #[test]
+        fn recently_completed_project_is_ok() {
+            let res = check("# Plan trip\n#in-progress\n\n## Actions\n\n### Upcoming\n\n- Go\n\n### Complete\n\n- Pack bags ✅ 2024-01-15\n");
+            assert!(res.is_ok());
+        }
+
+        #[test]
+        fn stalled_project_is_err() {
+            let res = check("# Plan trip\n#in-progress\n\n## Actions\n\n### Upcoming\n\n- Go\n- Come back\n\n### Complete\n\n- Pack bags ✅ 2024-01-05\n");
+            assert_eq!(
+                res.unwrap_err(),
+                "has had no active actions since 2024-01-05; promote \"Go\" to keep it moving"
+            );
+        }
+
+        #[test]
+        fn stalled_project_falls_back_to_creation_date() {
+            let res = check("# Plan trip\n#in-progress\n\n## Actions\n\n### Upcoming\n\n- Go\n");
+            assert!(res.is_err());
+        }
+    }
+
+    mod fixes {
+        use super::*;
+        use std::fs;
+
+        /// A GTD folder with a project that's missing a required goal and was finished long ago.
+        fn folder() -> (TempDir, Config) {
+            let dir = TempDir::new("validate-fixes");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Projects/202401010000 Plan trip.md"),
+                "# Plan trip\n#complete\n\n## Actions\n\n### Complete\n\n- Pack bags ✅ 2024-01-05\n",
+            )
+            .unwrap();
+            let config =
+                Config::parse("[[validate.required-sections]]\nsections = [\"Goal\"]\n").unwrap();
+            (dir, config)
+        }
+
+        #[test]
+        fn findings_are_marked_fixable() {
+            let (dir, config) = folder();
+            let docs = Documents::load(&dir, &config).unwrap();
+            let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+            let summary = check(&docs, &config, today, &Linker::default());
+            assert_eq!(summary.fixable(), 2);
+
+            let json = to_json(&summary, &dir);
+            let fixes = json["diagnostics"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|d| (d["rule"].clone(), d["fix"].clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                fixes,
+                vec![
+                    (
+                        json!("GTD016"),
+                        json!("Added empty sections \"Goal\" to 202401010000 Plan trip.")
+                    ),
+                    (json!("GTD020"), json!("Archived 202401010000 Plan trip.")),
+                ]
+            );
+        }
+
+        #[test]
+        fn only_the_given_rules_are_fixed() {
+            let (dir, config) = folder();
+            let docs = Documents::load(&dir, &config).unwrap();
+            let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+            assert_eq!(
+                fix(&docs, &config, today, &[String::from("gtd020")]).unwrap(),
+                vec!["Archived 202401010000 Plan trip."]
+            );
+            let archived = fs::read_to_string(dir.join("Archive/202401010000 Plan trip.md"));
+            assert!(!archived.unwrap().contains("## Goal"));
+        }
+
+        #[test]
+        fn other_rules_can_supply_fixes() {
+            static TITLE_IS_SHOUTED: Rule = Rule {
+                id: "ACME001",
+                name: "title-is-shouted",
+                severity: Severity::Warning,
+                is_fixable: true,
+                is_opt_in: false,
+                depends_on: &[],
+                rationale: "",
+                failing: "",
+                passing: "",
+                fix: "",
+            };
+            let (dir, config) = folder();
+            let docs = Documents::load(&dir, &config).unwrap();
+            let summary = ValidatorRunner::new()
+                .for_all_projects(&TITLE_IS_SHOUTED, |p: &Project| match p.title() {
+                    t if t == t.to_uppercase() => Ok(()),
+                    _ => Err("isn't shouted".into()),
+                })
+                .with_fix(&TITLE_IS_SHOUTED, |docs: &Documents, d: &Diagnostic| {
+                    let project = diagnostic_project(docs, d)?;
+                    let path = docs.loader().project_path(&project.name);
+                    let old = fs::read_to_string(&path).ok()?;
+                    let new = old.replacen(project.title(), &project.title().to_uppercase(), 1);
+                    Some(Edit {
+                        description: String::from("Shouted."),
+                        change: FileChange::Write { path, old, new },
+                    })
+                })
+                .run(&docs);
+            assert_eq!(summary.fixable(), 1);
+
+            let edits = summary.diagnostics.iter().filter_map(|d| d.fix.as_ref());
+            assert_eq!(fix::apply(docs.loader(), edits).unwrap(), vec!["Shouted."]);
+            let text = fs::read_to_string(dir.join("Projects/202401010000 Plan trip.md"));
+            assert!(text.unwrap().starts_with("# PLAN TRIP\n"));
+        }
+    }
+
+    mod complete_project_is_archived {
+        use super::*;
+        use std::fs;
+
+        const FINISHED: &str =
+            "# Plan trip\n#complete\n\n## Actions\n\n### Complete\n\n- Pack bags ✅ 2024-01-05\n";
+
+        fn check(text: &str) -> Result<(), Cow<'static, str>> {
+            let project = Project::parse("202401010000 Plan trip", text).unwrap();
+            let scheme = NameScheme::default();
+            let today = NaiveDate::from_ymd_opt(2024, 1, 20).unwrap();
+            let mut validator = complete_project_is_archived(&scheme, Period::Weeks(2), today);
+            validator(&project)
+        }
+
+        #[test]
+        fn recently_finished_project_is_ok() {
+            let res = check(&FINISHED.replace("2024-01-05", "2024-01-10"));
+            assert!(res.is_ok());
+        }
+
+        #[test]
+        fn in_progress_project_is_ok() {
+            let res = check(&FINISHED.replace("#complete", "#in-progress"));
+            assert!(res.is_ok());
+        }
+
+        #[test]
+        fn long_finished_project_is_err() {
+            assert_eq!(
+                check(FINISHED).unwrap_err(),
+                "has been complete since 2024-01-05 and should be archived"
+            );
+        }
+
+        #[test]
+        fn fix_archives_projects() {
+            let dir = TempDir::new("validate-archive");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(dir.join("Projects/202401010000 Plan trip.md"), FINISHED).unwrap();
+            fs::write(
+                dir.join("Projects/202401020000 Do taxes.md"),
+                "# Do taxes\n#in-progress\n\n## Actions\n\n### Active\n\n- File return\n",
+            )
+            .unwrap();
+            let config = Config::default();
+            let docs = Documents::load(&dir, &config).unwrap();
+            let today = NaiveDate::from_ymd_opt(2024, 1, 20).unwrap();
+
+            assert_eq!(
+                fix(&docs, &config, today, &[]).unwrap(),
+                vec!["Archived 202401010000 Plan trip."]
+            );
+            assert!(dir.join("Archive/202401010000 Plan trip.md").exists());
+            assert!(dir.join("Projects/202401020000 Do taxes.md").exists());
+        }
+    }
+
+    mod project_review_is_not_overdue {
+        use super::*;
+
+        fn check(frontmatter: &str) -> Result<(), Cow<'static, str>> {
+            let text = format!("---\n{}---\n# Plan trip\n#in-progress\n", frontmatter);
+            let project = Project::parse("202401010000 Plan trip", &text).unwrap();
+            let scheme = NameScheme::default();
+            let today = NaiveDate::from_ymd_opt(2024, 1, 20).unwrap();
+            let mut validator = project_review_is_not_overdue(&scheme, today);
+            validator(&project)
+        }
+
+        #[test]
+        fn recently_reviewed_project_is_ok() {
+            assert!(check("review-every: 2w\nlast-reviewed: 2024-01-15\n").is_ok());
+        }
+
+        #[test]
+        fn overdue_project_is_err() {
+            assert_eq!(
+                check("review-every: 2w\nlast-reviewed: 2024-01-01\n").unwrap_err(),
+                "is reviewed every 2w but was last reviewed on 2024-01-01"
+            );
+        }
+
+        #[test]
+        fn unreviewed_project_counts_from_its_id() {
+            assert_eq!(
+                check("review-every: 1w\n").unwrap_err(),
+                "is reviewed every 1w but was last reviewed on 2024-01-01"
+            );
+        }
+
+        #[test]
+        fn invalid_cadence_is_err() {
+            assert_eq!(
+                check("review-every: often\n").unwrap_err(),
+                "has an invalid review-every: \"often\" is not a valid period, like \"3d\", \
+                 \"2w\", or \"1m\""
+            );
+        }
+    }
+
+    mod project_has_required_sections {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn missing_sections_are_err() {
+            let config = Config::parse(
+                "[[validate.required-sections]]\ntag = \"work\"\nsections = [\"Goal\", \"Info\"]\n",
+            )
+            .unwrap();
+            let project = Project::parse(
+                "197001010000 Plan trip",
+                "# Plan trip\n#in-progress #work\n\n## Goal\n",
+            )
+            .unwrap();
+            let mut validator = project_has_required_sections(&config.validate);
+            let res = validator(&project);
+            assert_eq!(res.unwrap_err(), "is missing required sections \"Info\"");
+        }
+
+        #[test]
+        fn fix_adds_sections() {
+            let dir = TempDir::new("validate-fix");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            let path = dir.join("Projects/202401010000 Plan trip.md");
+            fs::write(
+                &path,
+                "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack bags\n",
+            )
+            .unwrap();
+            let config =
+                Config::parse("[[validate.required-sections]]\nsections = [\"Goal\"]\n").unwrap();
+            let docs = Documents::load(&dir, &config).unwrap();
+
+            let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+            assert_eq!(
+                fix(&docs, &config, today, &[]).unwrap(),
+                vec!["Added empty sections \"Goal\" to 202401010000 Plan trip."]
+            );
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                "# Plan trip\n#in-progress\n\n## Goal\n\n## Actions\n\n### Active\n\n- Pack bags\n"
+            );
+        }
+    }
+
+    mod footnote_is_defined {
+        use super::*;
+
+        fn check(body: &str) -> Result<(), Cow<'static, str>> {
+            let text = format!("# Plan trip\n#in-progress\n\n{}", body);
+            footnote_is_defined(&Project::parse("197001010000 Plan trip", &text).unwrap())
+        }
+
+        #[test]
+        fn defined_footnotes_are_ok() {
+            assert!(check("## Goal\n\nRelax.[^1]\n\n## Actions\n\n### Active\n\n- Book flights[^b]\n\n[^1]: Really.\n\n[^b]: See [^1].\n").is_ok());
+        }
+
+        #[test]
+        fn undefined_footnotes_are_err() {
+            assert_eq!(
+                check("## Goal\n\nRelax.[^1]\n").unwrap_err(),
+                "has no definition for footnote [^1]"
+            );
+            assert_eq!(
+                check("## Info\n\nSee [^a] and [^a].\n\n## Actions\n\n### Active\n\n- Book flights[^b]\n").unwrap_err(),
+                "has no definitions for footnotes [^a], [^b]"
+            );
+        }
+    }
+
+    mod actions_section_is_not_deprecated {
+        use super::*;
+
+        #[test]
+        fn action_items_section_is_err() {
+            let text =
+                "# Plan trip\n#in-progress\n\n## Action Items\n\n### Active\n\n- Book flights\n";
+            let project = Project::parse("197001010000 Plan trip", text).unwrap();
+            assert_eq!(
+                actions_section_is_not_deprecated(&project).unwrap_err(),
+                "uses the deprecated \"Action Items\" section instead of \"Actions\""
+            );
+            let project = Project::parse(
+                "197001010000 Plan trip",
+                &text.replace("Action Items", "Actions"),
+            )
+            .unwrap();
+            assert!(actions_section_is_not_deprecated(&project).is_ok());
+        }
+    }
+
+    mod goal_describes_outcome {
+        use super::*;
+        use std::fs;
+
+        fn check(goal: &str) -> Result<(), Cow<'static, str>> {
+            let text = format!("# Plan trip\n#in-progress\n\n## Goal\n\n{}\n", goal);
+            goal_describes_outcome(&Project::parse("197001010000 Plan trip", &text).unwrap())
+        }
+
+        #[test]
+        fn outcome_is_ok() {
+            assert!(check("Flights and hotel are booked for a week in **Lisbon**.").is_ok());
+        }
+
+        #[test]
+        fn missing_goal_is_ok() {
+            let project =
+                Project::parse("197001010000 Plan trip", "# Plan trip\n#in-progress\n").unwrap();
+            assert!(goal_describes_outcome(&project).is_ok());
+        }
+
+        #[test]
+        fn weak_goals_are_err() {
+            assert_eq!(check("").unwrap_err(), "has an empty goal");
+            assert_eq!(
+                check("*Travel*").unwrap_err(),
+                "has a one-word goal \"Travel\""
+            );
+            assert_eq!(
+                check("Plan   *trip*!").unwrap_err(),
+                "has a goal that repeats its title"
+            );
+        }
+
+        #[test]
+        fn rule_is_opt_in() {
+            let dir = TempDir::new("validate-opt-in");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Projects/202401010000 Plan trip.md"),
+                "# Plan trip\n#in-progress\n\n## Goal\n\nTravel\n",
+            )
+            .unwrap();
+            let docs = Documents::load(&dir, &Config::default()).unwrap();
+
+            let count = |enabled: &[String]| {
+                ValidatorRunner::new()
+                    .with_enabled(enabled)
+                    .for_all_projects(&GOAL_DESCRIBES_OUTCOME, goal_describes_outcome)
+                    .run(&docs)
+                    .diagnostics
+                    .len()
+            };
+            assert_eq!(count(&[]), 0);
+            assert_eq!(count(&[String::from("gtd017")]), 1);
+        }
+    }
+
     mod action_link_is_valid {
         use super::*;
 
@@ -573,6 +3276,37 @@ mod tests {
         }
     }
 
+    mod action_link_is_to_a_project {
+        use super::*;
+        use crate::markdown::BlockRef;
+
+        #[test]
+        fn link_to_project_is_ok() {
+            let action = ContextAction::Reference(ActionRef {
+                project_name: ProjectName::new("197001010000 Project title".into()).unwrap(),
+                action_id: ActionId::new("abcdef".into()),
+            });
+
+            let res = action_link_is_to_a_project(&action, None);
+            assert!(res.is_ok());
+        }
+
+        #[test]
+        fn link_to_other_note_is_err() {
+            let action = ContextAction::UnresolvedReference(BlockRef {
+                link: String::from("Packing list"),
+                id: String::from("abcdef"),
+                is_embedded: true,
+            });
+
+            let res = action_link_is_to_a_project(&action, None);
+            assert_eq!(
+                res,
+                Err("links to a block in \"Packing list\", which isn't a project".into())
+            );
+        }
+    }
+
     mod linked_project_is_in_progress {
         use super::*;
 
@@ -766,4 +3500,69 @@ mod tests {
             assert!(res.is_err());
         }
     }
+
+    mod file_has_no_conflicts {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn conflicted_files_are_left_out_and_reported() {
+            let dir = TempDir::new("conflict");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Contexts/@home.md"),
+                "# @home\n\n<<<<<<< HEAD\n- Call mom\n=======\n- Call mom today\n>>>>>>> laptop\n",
+            )
+            .unwrap();
+            fs::write(dir.join("Contexts/@phone.md"), "# @phone\n\n- Call dad\n").unwrap();
+
+            let docs = Documents::load(&dir, &Config::default()).unwrap();
+            assert_eq!(docs.contexts().count(), 1);
+            assert_eq!(
+                file_has_no_conflicts(&docs, &Linker::default()),
+                vec![String::from(
+                    "\"Contexts/@home.md\" has unresolved conflicts on lines 3-7"
+                )]
+            );
+        }
+    }
+
+    mod file_is_readable {
+        use super::*;
+        use std::fs;
+
+        #[test]
+        fn unreadable_files_are_left_out_and_reported() {
+            let dir = TempDir::new("unreadable");
+            fs::create_dir_all(dir.join("Projects")).unwrap();
+            fs::create_dir_all(dir.join("Contexts")).unwrap();
+            fs::write(
+                dir.join("Projects/202401011200 Plan trip.md"),
+                "# Plan trip\n#in-progress\n\n## Notes\n\nAsk about the hotel.\n",
+            )
+            .unwrap();
+            fs::write(
+                dir.join("Projects/202401011300 Do taxes.md"),
+                "# Do taxes\n#in-progress\n",
+            )
+            .unwrap();
+            fs::write(dir.join("Contexts/@home.md"), "Water plants\n").unwrap();
+
+            let docs = Documents::load(&dir, &Config::default()).unwrap();
+            assert_eq!(docs.projects().count(), 1);
+            assert_eq!(docs.contexts().count(), 0);
+            assert_eq!(
+                file_is_readable(&docs, &Linker::default()),
+                vec![
+                    String::from(
+                        "\"Projects/202401011200 Plan trip.md\" can't be read: Project has unexpected section"
+                    ),
+                    String::from(
+                        "\"Contexts/@home.md\" can't be read: expected start of level 1 heading, got start of paragraph"
+                    ),
+                ]
+            );
+        }
+    }
 }
@@ -0,0 +1,397 @@
+//! Tracking actions delegated to other people, and following up on them when they've waited too
+//! long.
+
+use crate::{
+    config::{Config, ContextLayout},
+    context::Context,
+    default_context, edit,
+    gtd::Documents,
+    merge, order,
+    period::Period,
+    project::{
+        Action, ActionId, ActionStatus, Name as ProjectName, NameScheme, Project, Status,
+        ACTIVE_ANNOTATION, CREATED_MARKER,
+    },
+    prompt,
+    resolve::{self, ResolveError},
+    undo::Undo,
+};
+use chrono::NaiveDate;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error as IoError, ErrorKind},
+    path::PathBuf,
+};
+
+/// What to call actions that aren't waiting on anyone in particular.
+const NO_ONE: &str = "Unassigned";
+
+/// Waiting actions grouped by who they're waiting on, sorted by name. Actions without `@who` are
+/// grouped under `None`.
+pub type Delegations<'a> = BTreeMap<Option<&'a str>, Vec<(&'a Project, &'a Action)>>;
+
+/// Groups the waiting actions in `projects` that aren't complete by who they're waiting on, with
+/// the longest waiting first.
+pub fn delegations<'a, I>(projects: I) -> Delegations<'a>
+where
+    I: IntoIterator<Item = &'a Project>,
+{
+    let mut delegations = Delegations::new();
+    for project in projects {
+        if project.status == Status::Complete {
+            continue;
+        }
+
+        let waiting = project
+            .actions
+            .actions()
+            .filter(|(_, s)| *s == ActionStatus::Waiting);
+        for (action, _) in waiting {
+            delegations
+                .entry(action.who.as_deref())
+                .or_default()
+                .push((project, action));
+        }
+    }
+
+    for actions in delegations.values_mut() {
+        // Actions without a start date go last, since there's no telling how long they've waited.
+        actions.sort_by_key(|(_, a)| (a.created.is_none(), a.created, a.text.to_string()));
+    }
+    delegations
+}
+
+/// Prints the waiting actions grouped by who they're waiting on, with how long each has waited as
+/// of `today`.
+pub fn waiting(docs: &Documents, today: NaiveDate) {
+    print!("{}", render_report(&delegations(docs.projects()), today));
+}
+
+/// Prints a follow-up list of the actions waiting on the person best matching `query`, ready to
+/// paste into a message to them.
+pub fn nag(docs: &Documents, query: &str, today: NaiveDate) -> Result<(), ResolveError> {
+    let delegations = delegations(docs.projects());
+    let (_, actions) = resolve::resolve(
+        query,
+        delegations.iter().filter(|(who, _)| who.is_some()),
+        |(who, _)| who.iter().copied().collect(),
+        |(who, _)| who.unwrap_or(NO_ONE).to_string(),
+    )?;
+    print!("{}", render_nag(actions, today));
+    Ok(())
+}
+
+/// The waiting actions in `docs` that have waited for at least `period` as of `today`, longest
+/// waiting first. Actions without a start date are left out.
+pub fn overdue(docs: &Documents, period: Period, today: NaiveDate) -> Vec<(&Project, &Action)> {
+    let cutoff = period.before(today);
+    let mut overdue = delegations(docs.projects())
+        .into_values()
+        .flatten()
+        .filter(|(_, a)| a.created.is_some_and(|d| d <= cutoff))
+        .collect::<Vec<_>>();
+    overdue.sort_by(|(p, a), (q, b)| (a.created, &p.name).cmp(&(b.created, &q.name)));
+    overdue
+}
+
+/// Drafts the text of an action for following up on the waiting `action`, like `Follow up with
+/// Alice about "Book hotel"`.
+pub fn draft_follow_up(action: &Action) -> String {
+    match &action.who {
+        Some(who) => format!("Follow up with {} about \"{}\"", who, action.text),
+        None => format!("Follow up on \"{}\"", action.text),
+    }
+}
+
+/// Adds an active action with the text `follow_up` and the block ID `id` to the project text
+/// `text`, and restarts the wait for the waiting `action` from `today`.
+///
+/// `action` must have been parsed from `text`, which is the text of the project `name`. Returns
+/// `None` if the text can't be parsed after the wait is restarted.
+pub fn add_follow_up(
+    text: &str,
+    name: &ProjectName,
+    scheme: &NameScheme,
+    action: &Action,
+    follow_up: &str,
+    id: &ActionId,
+    today: NaiveDate,
+) -> Option<String> {
+    // Restart the wait first, since adding the follow-up can move the waiting action.
+    let text = edit::set_action_date(text, action, CREATED_MARKER, today);
+    let project = Project::parse_with_scheme(name.as_str(), &text, scheme).ok()?;
+    let item = format!(
+        "- {} @{}({}) {}\n",
+        follow_up,
+        ACTIVE_ANNOTATION,
+        today.format("%Y-%m-%d"),
+        id
+    );
+    Some(edit::add_actions(
+        &text,
+        &project,
+        ActionStatus::Active,
+        &item,
+    ))
+}
+
+/// Goes through the waiting actions that have waited for longer than `follow-up-after` in
+/// `[waiting]` as of `today` one at a time, asking whether to follow up on each one.
+///
+/// Following up adds an active action to the project, with text drafted by `draft_follow_up` that
+/// can be changed, links it from a context, which is the project's `default-context` if it has one,
+/// and restarts the wait from `today`. The files are written at the end, after being recorded so
+/// `gtd undo` can put them back. Returns the number of actions followed up on.
+pub fn follow_up(docs: &Documents, config: &Config, today: NaiveDate) -> Result<usize, IoError> {
+    let loader = docs.loader();
+    loader.ensure_files()?;
+    if config.contexts.layout == ContextLayout::List {
+        return Err(IoError::new(
+            ErrorKind::Unsupported,
+            "follow-ups can only be added when each context has its own file",
+        ));
+    }
+
+    let period = config.waiting.follow_up_after;
+    let overdue = overdue(docs, period, today);
+    if overdue.is_empty() {
+        println!("No actions have been waiting for longer than {}.", period);
+        return Ok(0);
+    }
+
+    // The new text of each file that's changed.
+    let mut changes = BTreeMap::<PathBuf, String>::new();
+    let current = |changes: &BTreeMap<PathBuf, String>, path: &PathBuf| match changes.get(path) {
+        Some(text) => Ok(text.clone()),
+        None => fs::read_to_string(path),
+    };
+    let mut followed_up = 0;
+    for (i, (project, action)) in overdue.iter().enumerate() {
+        println!(
+            "\n({}/{}) {} ({}), waiting on {} since {}",
+            i + 1,
+            overdue.len(),
+            action.text,
+            project.title(),
+            action.who.as_deref().unwrap_or("someone"),
+            action.created.unwrap_or(today)
+        );
+        match prompt::choose("Follow up?", &["yes", "skip", "quit"])? {
+            Some(0) => {}
+            Some(1) => continue,
+            _ => break,
+        }
+        let follow_up = match prompt::ask("Action:", Some(&draft_follow_up(action)))? {
+            Some(text) => text,
+            None => break,
+        };
+        // Projects whose actions all happen in one place link follow-ups from it without asking.
+        let default =
+            default_context::default_context(docs, project).and_then(|n| docs.context(&n));
+        let context = match default {
+            Some(context) => context,
+            None => match ask_context(docs, config)? {
+                Some(context) => context,
+                None => break,
+            },
+        };
+
+        let project_path = loader.project_path(&project.name);
+        let text = current(&changes, &project_path)?;
+        let reparsed = Project::parse_with_depths(
+            project.name.as_str(),
+            &text,
+            loader.scheme(),
+            loader.depths(),
+        )
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        // Find the action again, since an earlier follow-up in the project moved things around.
+        let (waiting, _) = match reparsed
+            .actions
+            .actions()
+            .find(|(a, s)| *s == ActionStatus::Waiting && a.matches(action))
+        {
+            Some(found) => found,
+            None => continue,
+        };
+        let id = ActionId::generate(&follow_up, |id| reparsed.actions.get_action(id).is_some());
+        let text = match add_follow_up(
+            &text,
+            &project.name,
+            loader.scheme(),
+            waiting,
+            &follow_up,
+            &id,
+            today,
+        ) {
+            Some(text) => text,
+            None => continue,
+        };
+
+        let context_path = loader.context_path(&context.name);
+        let context_text = current(&changes, &context_path)?;
+        let link = format!("- ![[{}#{}]]\n", project.name, id);
+        let (context_text, _) = merge::merge_items(&context_text, &link, loader.scheme());
+        let context_text = order::reorder(docs, &context_text, docs.context_order());
+
+        println!(
+            "Added a follow-up to {} and {}.",
+            project.title(),
+            context.name
+        );
+        changes.insert(project_path, text);
+        changes.insert(context_path, context_text);
+        followed_up += 1;
+    }
+    if changes.is_empty() {
+        return Ok(0);
+    }
+
+    let mut undo = Undo::new("review --waiting");
+    for path in changes.keys() {
+        undo.record(loader, path)?;
+    }
+    undo.save(loader)?;
+    for (path, text) in changes.iter() {
+        fs::write(path, text)?;
+    }
+    Ok(followed_up)
+}
+
+/// Asks which context a follow-up goes in, defaulting to `follow-up-context` in `[waiting]`, until
+/// the answer matches one. Returns `None` if the input ends first.
+fn ask_context<'a>(docs: &'a Documents, config: &Config) -> Result<Option<&'a Context>, IoError> {
+    loop {
+        let query = match prompt::ask("Context:", config.waiting.follow_up_context.as_deref())? {
+            Some(query) => query,
+            None => return Ok(None),
+        };
+        let found = resolve::resolve(
+            &query,
+            docs.contexts(),
+            |c| vec![c.name.as_str()],
+            |c| c.name.to_string(),
+        );
+        match found {
+            Ok(context) => return Ok(Some(context)),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+fn render_report(delegations: &Delegations, today: NaiveDate) -> String {
+    let mut out = String::new();
+    for (i, (who, actions)) in delegations.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+
+        out.push_str(&format!("{}:\n", who.unwrap_or(NO_ONE)));
+        for (project, action) in actions {
+            let waited = match action.created {
+                Some(date) => format!("for {}", format_days(today, date)),
+                None => String::from("since an unknown date"),
+            };
+            out.push_str(&format!(
+                "- {} ({}), waiting {}\n",
+                action.text,
+                project.title(),
+                waited
+            ));
+        }
+    }
+    out
+}
+
+fn render_nag(actions: &[(&Project, &Action)], today: NaiveDate) -> String {
+    let mut out = String::from("Following up on:\n\n");
+    for (_, action) in actions {
+        match action.created {
+            Some(date) => out.push_str(&format!(
+                "- {} (asked on {}, {} ago)\n",
+                action.text,
+                date.format("%Y-%m-%d"),
+                format_days(today, date)
+            )),
+            None => out.push_str(&format!("- {}\n", action.text)),
+        }
+    }
+    out
+}
+
+/// Formats the number of days from `since` to `today`, like `3 days`.
+fn format_days(today: NaiveDate, since: NaiveDate) -> String {
+    match (today - since).num_days().max(0) {
+        1 => String::from("1 day"),
+        n => format!("{} days", n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, d).unwrap()
+    }
+
+    fn projects() -> Vec<Project> {
+        let trip = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack\n\n### Waiting\n\n- Send passport copy @who(Bob)\n- Book hotel @who(Alice) ➕ 2024-01-03\n- Confirm dates @who(Alice) ➕ 2024-01-01\n";
+        let taxes = "# Do taxes\n#in-progress\n\n## Actions\n\n### Waiting\n\n- Send W-2 @who(Alice) ➕ 2024-01-02\n- Receive 1099\n";
+        let old =
+            "# Old project\n#complete\n\n## Actions\n\n### Waiting\n\n- Forgotten @who(Alice)\n";
+        vec![
+            Project::parse("202401010000 Plan trip", trip).unwrap(),
+            Project::parse("202401010001 Do taxes", taxes).unwrap(),
+            Project::parse("202401010002 Old project", old).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn report_groups_by_person_longest_waiting_first() {
+        let projects = projects();
+        assert_eq!(
+            render_report(&delegations(&projects), date(5)),
+            "Unassigned:\n- Receive 1099 (Do taxes), waiting since an unknown date\n\nAlice:\n- Confirm dates (Plan trip), waiting for 4 days\n- Send W-2 (Do taxes), waiting for 3 days\n- Book hotel (Plan trip), waiting for 2 days\n\nBob:\n- Send passport copy (Plan trip), waiting since an unknown date\n"
+        );
+    }
+
+    #[test]
+    fn follow_up_is_added_and_wait_restarted() {
+        let text = "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack\n\n### Waiting\n\n- Book hotel @who(Alice) ➕ 2024-01-03\n";
+        let project = Project::parse("202401010000 Plan trip", text).unwrap();
+        let (action, _) = project
+            .actions
+            .actions()
+            .find(|(_, s)| *s == ActionStatus::Waiting)
+            .unwrap();
+        let follow_up = draft_follow_up(action);
+        assert_eq!(follow_up, "Follow up with Alice about \"Book hotel\"");
+
+        let id = ActionId::new(String::from("abc123"));
+        let text = add_follow_up(
+            text,
+            &project.name,
+            &NameScheme::default(),
+            action,
+            &follow_up,
+            &id,
+            date(10),
+        );
+        assert_eq!(
+            text.unwrap(),
+            "# Plan trip\n#in-progress\n\n## Actions\n\n### Active\n\n- Pack\n- Follow up with Alice about \"Book hotel\" @active(2024-01-10) ^abc123\n\n### Waiting\n\n- Book hotel @who(Alice) ➕ 2024-01-10\n"
+        );
+    }
+
+    #[test]
+    fn nag_lists_dates_asked() {
+        let projects = projects();
+        let delegations = delegations(&projects);
+        assert_eq!(
+            render_nag(&delegations[&Some("Alice")][..2], date(2)),
+            "Following up on:\n\n- Confirm dates (asked on 2024-01-01, 1 day ago)\n- Send W-2 (asked on 2024-01-02, 0 days ago)\n"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Walking a GTD folder for its files, on several threads at once, skipping the ones that are
+//! ignored.
+//!
+//! Files are ignored by patterns in `.gitignore` and `.gtdignore` in the root of the folder, which
+//! use the same syntax, so things like templates, archives, and `.trash/` can be left out of the
+//! GTD system without being moved out of the folder. Hidden files and folders, like `.obsidian`,
+//! are always skipped.
+
+use regex::Regex;
+use std::{
+    fs,
+    io::{Error as IoError, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{Condvar, Mutex},
+    thread,
+};
+
+/// The files in the root of a GTD folder that ignore patterns are read from, in order, so patterns
+/// in `.gtdignore` take precedence.
+pub const IGNORE_FILENAMES: &[&str] = &[".gitignore", ".gtdignore"];
+
+/// The most threads that a walk uses.
+const MAX_THREADS: usize = 8;
+
+/// A set of `.gitignore`-style patterns.
+#[derive(Debug, Clone, Default)]
+pub struct Ignore {
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    regex: Regex,
+    /// Whether the pattern starts with `!`, un-ignoring what it matches.
+    is_negated: bool,
+    /// Whether the pattern ends with `/`, only matching folders.
+    is_dir_only: bool,
+}
+
+impl Ignore {
+    /// Parses the patterns in `text`, one per line. Blank lines and lines starting with `#` are
+    /// skipped.
+    pub fn parse(text: &str) -> Self {
+        let patterns = text.lines().filter_map(Pattern::parse).collect();
+        Self { patterns }
+    }
+
+    /// Reads the patterns in the ignore files in `root_dir`, if there are any.
+    pub fn load(root_dir: &Path) -> Result<Self, IoError> {
+        let mut ignore = Self::default();
+        for filename in IGNORE_FILENAMES {
+            match fs::read_to_string(root_dir.join(filename)) {
+                Ok(text) => ignore.patterns.extend(Self::parse(&text).patterns),
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(ignore)
+    }
+
+    /// Whether the file or folder at `path`, relative to the root of the folder, is ignored. The
+    /// last pattern that matches it decides.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.patterns
+            .iter()
+            .rev()
+            .find(|p| (is_dir || !p.is_dir_only) && p.regex.is_match(&path))
+            .is_some_and(|p| !p.is_negated)
+    }
+}
+
+impl Pattern {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (is_negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (is_dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        // Patterns with a `/` before the end are relative to the root, and others match at any
+        // depth.
+        let prefix = match line.contains('/') {
+            true => "^",
+            false => "^(?:.*/)?",
+        };
+        let glob = line.strip_prefix('/').unwrap_or(line);
+        let regex = Regex::new(&format!("{}{}$", prefix, glob_to_regex(glob))).ok()?;
+        Some(Self {
+            regex,
+            is_negated,
+            is_dir_only,
+        })
+    }
+}
+
+/// Translates the glob `glob` into a regex, where `*` and `?` don't match `/`, and `**` matches
+/// any number of folders.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex
+}
+
+/// The files found by a walk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Walk {
+    /// The files that aren't ignored, sorted by path.
+    pub included: Vec<PathBuf>,
+    /// The number of files and folders that were ignored. An ignored folder counts once, since
+    /// what's in it isn't read.
+    pub excluded: usize,
+}
+
+/// The folders waiting to be read, shared between the threads of a walk.
+#[derive(Default)]
+struct Queue {
+    dirs: Vec<PathBuf>,
+    /// The number of folders being read right now, which might add more.
+    busy: usize,
+    error: Option<IoError>,
+}
+
+/// Finds the files in `root_dir` and its subfolders that aren't ignored by `ignore`, reading
+/// folders on several threads at once.
+pub fn walk(root_dir: &Path, ignore: &Ignore) -> Result<Walk, IoError> {
+    let queue = Mutex::new(Queue {
+        dirs: vec![root_dir.to_owned()],
+        ..Queue::default()
+    });
+    let ready = Condvar::new();
+    let found = Mutex::new(Walk::default());
+    let threads = thread::available_parallelism().map_or(1, |n| n.get().min(MAX_THREADS));
+
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| loop {
+                let dir = {
+                    let mut queue = queue.lock().unwrap();
+                    loop {
+                        if queue.error.is_some() {
+                            return;
+                        }
+                        if let Some(dir) = queue.dirs.pop() {
+                            queue.busy += 1;
+                            break dir;
+                        }
+                        if queue.busy == 0 {
+                            return;
+                        }
+                        queue = ready.wait(queue).unwrap();
+                    }
+                };
+
+                let result = read_dir(root_dir, &dir, ignore);
+                let mut queue = queue.lock().unwrap();
+                queue.busy -= 1;
+                match result {
+                    Ok((dirs, walk)) => {
+                        queue.dirs.extend(dirs);
+                        let mut found = found.lock().unwrap();
+                        found.included.extend(walk.included);
+                        found.excluded += walk.excluded;
+                    }
+                    Err(e) => {
+                        queue.error.get_or_insert(e);
+                    }
+                }
+                ready.notify_all();
+            });
+        }
+    });
+
+    if let Some(e) = queue.into_inner().unwrap().error {
+        return Err(e);
+    }
+    let mut found = found.into_inner().unwrap();
+    found.included.sort();
+    Ok(found)
+}
+
+/// Reads the folder `dir`, returning the subfolders to read next and the files in it.
+fn read_dir(root_dir: &Path, dir: &Path, ignore: &Ignore) -> Result<(Vec<PathBuf>, Walk), IoError> {
+    let mut dirs = Vec::new();
+    let mut walk = Walk::default();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if is_hidden {
+            continue;
+        }
+        let is_dir = path.is_dir();
+        let relative = path.strip_prefix(root_dir).unwrap_or(&path);
+        if ignore.is_ignored(relative, is_dir) {
+            walk.excluded += 1;
+        } else if is_dir {
+            dirs.push(path);
+        } else {
+            walk.included.push(path);
+        }
+    }
+    Ok((dirs, walk))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TempDir;
+
+    #[test]
+    fn patterns_match_like_gitignore() {
+        let ignore = Ignore::parse(
+            "# Comment\n\nTemplates/\n*.tmp\n/Archive\nProjects/**/draft-*.md\n!keep.tmp\n",
+        );
+        let is_ignored = |path: &str, is_dir| ignore.is_ignored(Path::new(path), is_dir);
+        assert!(is_ignored("Templates", true));
+        assert!(is_ignored("Projects/Templates", true));
+        assert!(!is_ignored("Templates", false));
+        assert!(is_ignored("Contexts/notes.tmp", false));
+        assert!(!is_ignored("Contexts/keep.tmp", false));
+        assert!(is_ignored("Archive", true));
+        assert!(!is_ignored("Projects/Archive", true));
+        assert!(is_ignored("Projects/draft-trip.md", false));
+        assert!(is_ignored("Projects/Old/draft-trip.md", false));
+        assert!(!is_ignored("Projects/trip.md", false));
+    }
+
+    #[test]
+    fn ignored_files_are_counted_but_not_included() {
+        let dir = TempDir::new("walk");
+        for folder in ["Projects/Old", "Contexts", "Templates", ".trash"] {
+            fs::create_dir_all(dir.join(folder)).unwrap();
+        }
+        for file in [
+            "Projects/202401010000 Plan trip.md",
+            "Projects/Old/202301010000 Old trip.md",
+            "Projects/scratch.tmp",
+            "Contexts/@home.md",
+            "Templates/Project.md",
+            ".trash/Deleted.md",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        fs::write(dir.join(".gtdignore"), "Templates/\n*.tmp\n").unwrap();
+
+        let walk = walk(&dir, &Ignore::load(&dir).unwrap()).unwrap();
+        let included = walk
+            .included
+            .iter()
+            .map(|p| p.strip_prefix(&dir).unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            included,
+            [
+                "Contexts/@home.md",
+                "Projects/202401010000 Plan trip.md",
+                "Projects/Old/202301010000 Old trip.md",
+            ]
+        );
+        assert_eq!(walk.excluded, 2);
+    }
+}